@@ -0,0 +1,48 @@
+#![feature(test)]
+
+extern crate test;
+
+use orgize::{AgendaOptions, Org};
+use test::Bencher;
+
+/// 50k entries with planning and body timestamps
+fn large_agenda() -> String {
+    let mut text = String::new();
+    for i in 0..50_000 {
+        text += &format!(
+            "* TODO Entry {} <2020-01-{:02} Wed 10:00>\nSCHEDULED: <2020-02-{:02} Sat>\n\
+             Meeting on <2020-03-{:02} Sun> and [2020-04-01 Wed], see *notes*.\n",
+            i,
+            i % 28 + 1,
+            i % 28 + 1,
+            i % 28 + 1,
+        );
+    }
+    text
+}
+
+#[bench]
+fn parse(b: &mut Bencher) {
+    let text = large_agenda();
+    b.iter(|| {
+        Org::parse(&text);
+    })
+}
+
+#[bench]
+fn body_timestamps(b: &mut Bencher) {
+    let text = large_agenda();
+    let org = Org::parse(&text);
+    b.iter(|| org.agenda(&AgendaOptions::default()))
+}
+
+#[bench]
+fn planning_only(b: &mut Bencher) {
+    let text = large_agenda();
+    let org = Org::parse(&text);
+    b.iter(|| {
+        org.agenda(&AgendaOptions {
+            body_timestamps: false,
+        })
+    })
+}
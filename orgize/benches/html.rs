@@ -0,0 +1,45 @@
+#![feature(test)]
+
+extern crate test;
+
+use orgize::Org;
+use test::Bencher;
+
+/// About 5 MB of headlines, paragraphs, lists and source blocks
+fn large_document() -> String {
+    let mut text = String::new();
+    for i in 0..20_000 {
+        text += &format!(
+            "* Chapter {}\n** Section\nSome *bold* text with a [[https://example.com][link]] \
+             and =code=.\n\n- first item\n- second item\n\n#+BEGIN_SRC rust\nfn main() {{}}\n#+END_SRC\n\
+             {}\n",
+            i,
+            "Filler text to make the paragraph longer. ".repeat(2)
+        );
+    }
+    text
+}
+
+#[bench]
+fn html(b: &mut Bencher) {
+    let text = large_document();
+    let org = Org::parse(&text);
+    b.iter(|| {
+        let mut writer = Vec::new();
+        org.html(&mut writer).unwrap();
+    })
+}
+
+#[cfg(feature = "rayon")]
+#[bench]
+fn html_parallel(b: &mut Bencher) {
+    use orgize::export::HtmlExportOptions;
+
+    let text = large_document();
+    let org = Org::parse(&text);
+    let options = HtmlExportOptions::default();
+    b.iter(|| {
+        let mut writer = Vec::new();
+        org.html_parallel(&mut writer, &options).unwrap();
+    })
+}
@@ -2,9 +2,41 @@
 
 extern crate test;
 
-use orgize::Org;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering::Relaxed};
+
+use orgize::{elements::ElementKind, Org, ParseConfig};
 use test::Bencher;
 
+/// Counts the allocations and the bytes in use, for `large_document_memory`
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static BYTES: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Relaxed);
+        BYTES.fetch_add(layout.size(), Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        BYTES.fetch_sub(layout.size(), Relaxed);
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Relaxed);
+        BYTES.fetch_add(new_size, Relaxed);
+        BYTES.fetch_sub(layout.size(), Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
 #[bench]
 fn org_syntax(b: &mut Bencher) {
     // wget https://orgmode.org/worg/sources/dev/org-syntax.org
@@ -28,3 +60,112 @@ fn org_faq(b: &mut Bencher) {
         Org::parse(include_str!("org-faq.org"));
     })
 }
+
+fn large() -> String {
+    let mut text = String::new();
+    for i in 0..10_000 {
+        text += &format!(
+            "* TODO headline {} :work:project:\n:PROPERTIES:\n:CATEGORY: work\n:END:\n\
+             #+BEGIN_SRC rust\nfn main() {{}}\n#+END_SRC\n",
+            i
+        );
+    }
+    text
+}
+
+fn replace_every_other_section(org: &mut Org<'_>) {
+    let headlines: Vec<_> = org.headlines().collect();
+    for headline in headlines.into_iter().step_by(2) {
+        headline.set_section_content(String::from("replaced"), org);
+    }
+}
+
+#[bench]
+fn large_document(b: &mut Bencher) {
+    let text = large();
+    b.iter(|| {
+        let mut org = Org::parse(&text);
+        replace_every_other_section(&mut org);
+        org.shrink_to_fit();
+    })
+}
+
+/// Prints the nodes, the allocations and the bytes in use of `large` once
+/// parsed, mutated, shrunk and owned, then benches the parsing alone
+///
+/// Repeated tags, property keys and languages are borrowed from the text
+/// while parsed, and from the interner once owned, so the allocations don't
+/// grow with them.
+#[bench]
+fn large_document_memory(b: &mut Bencher) {
+    let text = large();
+
+    let allocations = ALLOCATIONS.load(Relaxed);
+    let bytes = BYTES.load(Relaxed);
+    let report = |stage: &str, org: &Org<'_>| {
+        eprintln!(
+            "{:>8}: {} nodes, {} allocations, {} KiB",
+            stage,
+            org.arena().len(),
+            ALLOCATIONS.load(Relaxed) - allocations,
+            BYTES.load(Relaxed).saturating_sub(bytes) / 1024
+        );
+    };
+
+    let mut org = Org::parse(&text);
+    report("parsed", &org);
+    replace_every_other_section(&mut org);
+    report("mutated", &org);
+    org.shrink_to_fit();
+    report("shrunk", &org);
+    let org = org.into_owned();
+    report("owned", &org);
+    drop(org);
+
+    b.iter(|| {
+        Org::parse(&text);
+    })
+}
+
+fn prose() -> String {
+    let mut text = String::new();
+    for i in 0..2_000 {
+        text += &format!(
+            "* Chapter {}\nIt was a *bright* cold day in April, and the clocks were striking \
+             /thirteen/. Winston Smith, his chin nuzzled into his breast in an effort to escape \
+             the vile wind, slipped quickly through the _glass doors_ of Victory Mansions, \
+             though not quickly enough to prevent a swirl of gritty dust from entering along \
+             with him. The hallway smelt of boiled cabbage and old rag mats. At one end of it a \
+             coloured poster, too large for indoor display, had been tacked to the wall.\n\n",
+            i
+        );
+    }
+    text
+}
+
+#[bench]
+fn prose_document(b: &mut Bencher) {
+    let text = prose();
+    b.iter(|| {
+        Org::parse(&text);
+    })
+}
+
+#[bench]
+fn prose_document_without_emphasis(b: &mut Bencher) {
+    let text = prose();
+    let config = ParseConfig {
+        disabled_elements: vec![
+            ElementKind::Bold,
+            ElementKind::Italic,
+            ElementKind::Underline,
+            ElementKind::Strike,
+            ElementKind::Verbatim,
+            ElementKind::Code,
+        ],
+        ..Default::default()
+    };
+    b.iter(|| {
+        Org::parse_with_config(&text, &config);
+    })
+}
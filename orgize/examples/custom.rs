@@ -70,7 +70,7 @@ fn main() -> Result<(), MyError> {
         let contents = String::from_utf8(fs::read(&args[1])?)?;
 
         let mut writer = Vec::new();
-        let mut handler = MyHtmlHandler(DefaultHtmlHandler);
+        let mut handler = MyHtmlHandler(DefaultHtmlHandler::default());
         Org::parse(&contents).html_with_handler(&mut writer, &mut handler)?;
 
         println!("{}", String::from_utf8(writer)?);
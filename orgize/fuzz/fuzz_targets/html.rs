@@ -0,0 +1,13 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+use orgize::Org;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(s) = std::str::from_utf8(data) {
+        let mut writer = Vec::new();
+        Org::parse(s).html(&mut writer).unwrap();
+        // exporters must always produce valid utf-8
+        String::from_utf8(writer).unwrap();
+    }
+});
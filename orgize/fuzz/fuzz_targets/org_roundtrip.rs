@@ -0,0 +1,19 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+use orgize::Org;
+
+fn write_org(text: &str) -> String {
+    let mut writer = Vec::new();
+    Org::parse(text).org(&mut writer).unwrap();
+    String::from_utf8(writer).unwrap()
+}
+
+// parse -> write -> parse -> write must be stable after the first write
+fuzz_target!(|data: &[u8]| {
+    if let Ok(s) = std::str::from_utf8(data) {
+        let first = write_org(s);
+        let second = write_org(&first);
+        assert_eq!(first, second);
+    }
+});
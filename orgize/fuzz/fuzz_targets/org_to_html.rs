@@ -0,0 +1,14 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(s) = std::str::from_utf8(data) {
+        // untrusted input must never panic, a panic is reported as a
+        // diagnostic instead
+        if let Err(diagnostics) = orgize::org_to_html(s, None) {
+            assert!(diagnostics
+                .iter()
+                .all(|diagnostic| !diagnostic.message.starts_with("orgize panicked")));
+        }
+    }
+});
@@ -0,0 +1,24 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+use orgize::{Org, ParseConfig};
+
+// The first line of the input is used as the keyword lists, e.g.
+// `TODO NEXT|DONE CANCELED`, the rest of the input is the document.
+fuzz_target!(|data: &[u8]| {
+    if let Ok(s) = std::str::from_utf8(data) {
+        let (keywords, text) = s.split_at(s.find('\n').unwrap_or_else(|| s.len()));
+        let (todo, done) = keywords.split_at(keywords.find('|').unwrap_or_else(|| keywords.len()));
+
+        let config = ParseConfig {
+            todo_keywords: todo.split(' ').map(String::from).collect(),
+            done_keywords: done
+                .split(|c| c == ' ' || c == '|')
+                .map(String::from)
+                .collect(),
+            ..Default::default()
+        };
+
+        let _ = Org::parse_with_config(text, &config);
+    }
+});
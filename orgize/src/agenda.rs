@@ -0,0 +1,104 @@
+use crate::elements::Timestamp;
+use crate::node::HeadlineNode;
+use crate::org::Org;
+
+/// Options of [`Org::agenda`]
+///
+/// [`Org::agenda`]: struct.Org.html#method.agenda
+#[derive(Clone, Debug)]
+pub struct AgendaOptions {
+    /// Include the active timestamps of titles and sections, like org-mode's
+    /// agenda does, and the dates of titles
+    ///
+    /// When `false`, only the `SCHEDULED` and `DEADLINE` planning timestamps
+    /// are included.
+    ///
+    /// Default: `true`
+    pub body_timestamps: bool,
+}
+
+impl Default for AgendaOptions {
+    fn default() -> Self {
+        AgendaOptions {
+            body_timestamps: true,
+        }
+    }
+}
+
+/// Why a headline appears in the agenda
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum AgendaKind {
+    /// `SCHEDULED` planning timestamp
+    Scheduled,
+    /// `DEADLINE` planning timestamp
+    Deadline,
+    /// Active timestamp in the title or the section
+    Timestamp,
+    /// Date of the title, see `ParseConfig::headline_date_formats`
+    HeadlineDate,
+}
+
+/// An agenda item, returned by [`Org::agenda`]
+///
+/// [`Org::agenda`]: struct.Org.html#method.agenda
+#[derive(Debug)]
+pub struct AgendaEntry<'a, 'b> {
+    pub headline: HeadlineNode,
+    pub kind: AgendaKind,
+    pub timestamp: &'b Timestamp<'a>,
+}
+
+pub(crate) fn agenda<'a, 'b>(
+    org: &'b Org<'a>,
+    options: &AgendaOptions,
+) -> Vec<AgendaEntry<'a, 'b>> {
+    let mut entries = Vec::new();
+
+    for headline in org.headlines() {
+        // `Title::deadline` and `Title::scheduled` shorten the lifetime
+        if let Some(planning) = &headline.title(org).planning {
+            let planning = [
+                (AgendaKind::Deadline, &planning.deadline),
+                (AgendaKind::Scheduled, &planning.scheduled),
+            ];
+            for (kind, timestamp) in planning.iter() {
+                if let Some(timestamp) = timestamp {
+                    entries.push(AgendaEntry {
+                        headline,
+                        kind: *kind,
+                        timestamp,
+                    });
+                }
+            }
+        }
+
+        if options.body_timestamps {
+            if let Some(timestamp) = &headline.title(org).date {
+                entries.push(AgendaEntry {
+                    headline,
+                    kind: AgendaKind::HeadlineDate,
+                    timestamp,
+                });
+            }
+            entries.extend(
+                headline
+                    .timestamps(org)
+                    .filter(|timestamp| is_active(timestamp))
+                    .map(|timestamp| AgendaEntry {
+                        headline,
+                        kind: AgendaKind::Timestamp,
+                        timestamp,
+                    }),
+            );
+        }
+    }
+
+    entries
+}
+
+fn is_active(timestamp: &Timestamp) -> bool {
+    match timestamp {
+        Timestamp::Active { .. } | Timestamp::ActiveRange { .. } | Timestamp::Diary { .. } => true,
+        Timestamp::Inactive { .. } | Timestamp::InactiveRange { .. } => false,
+    }
+}
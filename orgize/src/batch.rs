@@ -0,0 +1,149 @@
+use std::any::Any;
+use std::borrow::Cow;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+
+use crate::config::ParseConfig;
+use crate::decode::decode;
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::org::Org;
+
+/// Parses many files, on the rayon thread pool with the `rayon` feature
+///
+/// Returns the documents and their diagnostics in the order of `files`,
+/// whichever thread parsed them. Contents are decoded with [`decode`], and
+/// the documents own their contents, see [`Org::into_owned`]. The
+/// diagnostics are the ones of [`Org::parse_diagnostics`], with the path of
+/// their file.
+///
+/// A file which can't be parsed doesn't affect the others: if the parser
+/// panics, which is a bug, its document is empty and the panic is reported
+/// as a diagnostic. The panic message is still printed by the panic hook.
+///
+/// ```rust
+/// use orgize::{parse_files, ParseConfig};
+/// use std::path::Path;
+///
+/// let files = vec![
+///     ("a.org", "* a\n#+END_QUOTE\n"),
+///     ("b.org", "* b\n"),
+/// ];
+///
+/// let parsed = parse_files(files, &ParseConfig::default());
+///
+/// assert_eq!(parsed[0].0.to_str(), Some("a.org"));
+/// assert_eq!(parsed[0].2[0].span, Some(4..16));
+/// assert_eq!(parsed[0].2[0].path.as_deref(), Some(Path::new("a.org")));
+/// assert_eq!(parsed[1].1.headlines().count(), 1);
+/// assert!(parsed[1].2.is_empty());
+/// ```
+///
+/// [`decode`]: fn.decode.html
+/// [`Org::into_owned`]: struct.Org.html#method.into_owned
+/// [`Org::parse_diagnostics`]: struct.Org.html#method.parse_diagnostics
+pub fn parse_files<I, P, C>(
+    files: I,
+    config: &ParseConfig,
+) -> Vec<(PathBuf, Org<'static>, Vec<Diagnostic>)>
+where
+    I: IntoIterator<Item = (P, C)>,
+    P: Into<PathBuf>,
+    C: AsRef<[u8]> + Send,
+{
+    parse_files_with(files, config, |_| ())
+        .into_iter()
+        .map(|(path, org, diagnostics, _)| (path, org, diagnostics))
+        .collect()
+}
+
+/// Like `parse_files`, also returns the result of `index` for each document,
+/// which is run on the same thread as the parser
+pub(crate) fn parse_files_with<I, P, C, F, T>(
+    files: I,
+    config: &ParseConfig,
+    index: F,
+) -> Vec<(PathBuf, Org<'static>, Vec<Diagnostic>, T)>
+where
+    I: IntoIterator<Item = (P, C)>,
+    P: Into<PathBuf>,
+    C: AsRef<[u8]> + Send,
+    F: Fn(&Org<'static>) -> T + Sync,
+    T: Send,
+{
+    let files: Vec<_> = files
+        .into_iter()
+        .map(|(path, contents)| (path.into(), contents))
+        .collect();
+
+    let parse = |(path, contents): (PathBuf, C)| {
+        let (org, diagnostics) = parse_file(&path, contents.as_ref(), config);
+        let index = index(&org);
+        (path, org, diagnostics, index)
+    };
+
+    #[cfg(feature = "rayon")]
+    {
+        use rayon::prelude::*;
+        files.into_par_iter().map(parse).collect()
+    }
+
+    #[cfg(not(feature = "rayon"))]
+    {
+        files.into_iter().map(parse).collect()
+    }
+}
+
+fn parse_file(
+    path: &Path,
+    contents: &[u8],
+    config: &ParseConfig,
+) -> (Org<'static>, Vec<Diagnostic>) {
+    let mut diagnostics = Vec::new();
+
+    let text = decode(contents);
+    if let Cow::Owned(text) = &text {
+        if std::str::from_utf8(contents).is_err() && text.contains('\u{FFFD}') {
+            diagnostics.push(Diagnostic::new(
+                Severity::Warning,
+                None,
+                "invalid UTF-8 sequences were replaced with U+FFFD".to_string(),
+            ));
+        }
+    }
+
+    let parsed = panic::catch_unwind(AssertUnwindSafe(|| {
+        let org = Org::parse_with_config(&text, config);
+        let parse_diagnostics = org.parse_diagnostics();
+        (org.into_owned(), parse_diagnostics)
+    }));
+
+    let org = match parsed {
+        Ok((org, parse_diagnostics)) => {
+            diagnostics.extend(parse_diagnostics);
+            org
+        }
+        Err(payload) => {
+            let message = panic_message(&*payload);
+            diagnostics.push(Diagnostic::new(
+                Severity::Error,
+                None,
+                format!("parser panicked: {}", message),
+            ));
+            Org::new()
+        }
+    };
+
+    for diagnostic in &mut diagnostics {
+        diagnostic.path = Some(path.to_path_buf());
+    }
+    (org, diagnostics)
+}
+
+/// Returns the message of a panic caught by `catch_unwind`
+pub(crate) fn panic_message(payload: &(dyn Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_default()
+}
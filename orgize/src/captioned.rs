@@ -0,0 +1,121 @@
+use indextree::NodeId;
+
+use crate::elements::{Element, Table};
+use crate::org::Org;
+
+/// A table, a figure or a listing, with its affiliated caption and name
+///
+/// Returned by [`Org::tables`], [`Org::figures`] and [`Org::listings`].
+///
+/// [`Org::tables`]: struct.Org.html#method.tables
+/// [`Org::figures`]: struct.Org.html#method.figures
+/// [`Org::listings`]: struct.Org.html#method.listings
+#[derive(Debug)]
+pub struct Captioned<'a, 'b> {
+    pub node: NodeId,
+    pub element: &'b Element<'a>,
+    /// `Caption` element, its children are the objects of the caption
+    pub caption: Option<NodeId>,
+    /// Short caption, used in lists of figures or tables
+    pub short_caption: Option<&'b str>,
+    /// Value of the `#+NAME:` keyword
+    pub name: Option<&'b str>,
+    /// Number among the captioned elements of the same kind, starting at 1
+    ///
+    /// Elements without caption are not numbered.
+    pub number: Option<usize>,
+}
+
+pub(crate) fn captioned<'a, 'b>(
+    org: &'b Org<'a>,
+    is_kind: fn(&Org<'_>, NodeId) -> bool,
+) -> impl Iterator<Item = Captioned<'a, 'b>> + 'b {
+    let mut number = 0;
+
+    org.root
+        .descendants(&org.arena)
+        .filter(move |&node| is_kind(org, node))
+        .map(move |node| {
+            let mut captioned = Captioned {
+                node,
+                element: org.arena[node].get(),
+                caption: None,
+                short_caption: None,
+                name: None,
+                number: None,
+            };
+
+            // affiliated keywords directly precede the element
+            for sibling in node.preceding_siblings(&org.arena).skip(1) {
+                match org.arena[sibling].get() {
+                    Element::Caption(caption) => {
+                        if captioned.caption.is_none() {
+                            captioned.caption = Some(sibling);
+                            captioned.short_caption = caption.short.as_ref().map(AsRef::as_ref);
+                        }
+                    }
+                    Element::Keyword(keyword) if keyword.key.eq_ignore_ascii_case("NAME") => {
+                        if captioned.name.is_none() {
+                            captioned.name = Some(&keyword.value);
+                        }
+                    }
+                    Element::Keyword(keyword) if is_affiliated(&keyword.key) => (),
+                    // a comment, e.g. a review note, doesn't detach the
+                    // keywords before it
+                    Element::Comment { .. } => (),
+                    _ => break,
+                }
+            }
+
+            if captioned.caption.is_some() {
+                number += 1;
+                captioned.number = Some(number);
+            }
+
+            captioned
+        })
+}
+
+pub(crate) fn is_affiliated(key: &str) -> bool {
+    ["HEADER", "PLOT", "RESULTS"]
+        .iter()
+        .any(|k| key.eq_ignore_ascii_case(k))
+        || (key.len() > 5 && key[0..5].eq_ignore_ascii_case("ATTR_"))
+}
+
+pub(crate) fn is_table(org: &Org<'_>, node: NodeId) -> bool {
+    matches!(
+        org.arena[node].get(),
+        Element::Table(Table::Org { .. }) | Element::Table(Table::TableEl { .. })
+    )
+}
+
+pub(crate) fn is_listing(org: &Org<'_>, node: NodeId) -> bool {
+    matches!(org.arena[node].get(), Element::SourceBlock(_))
+}
+
+/// A figure is a paragraph containing nothing but an image link
+pub(crate) fn is_figure(org: &Org<'_>, node: NodeId) -> bool {
+    if let Element::Paragraph = org.arena[node].get() {
+        let mut children = node.children(&org.arena).filter(|&child| {
+            if let Element::Text { value } = org.arena[child].get() {
+                !value.trim().is_empty()
+            } else {
+                true
+            }
+        });
+
+        match (children.next(), children.next()) {
+            (Some(child), None) => {
+                if let Element::Link(link) = org.arena[child].get() {
+                    link.desc.is_none() && link.is_image()
+                } else {
+                    false
+                }
+            }
+            _ => false,
+        }
+    } else {
+        false
+    }
+}
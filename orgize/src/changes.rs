@@ -0,0 +1,229 @@
+use indextree::NodeId;
+
+use crate::config::DEFAULT_CONFIG;
+use crate::elements::Element;
+use crate::node::HeadlineNode;
+use crate::org::Org;
+use crate::parsers::{parse_container, Container, OwnedArena};
+use crate::OrgizeError;
+
+/// A mutation of a document, recorded by [`Org::enable_change_log`]
+///
+/// Headlines are addressed by their outline path, the index of the headline
+/// among the child headlines of its parent, for each level. The empty path
+/// is the document.
+///
+/// [`Org::enable_change_log`]: struct.Org.html#method.enable_change_log
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "ser", derive(serde::Serialize, serde::Deserialize))]
+pub struct Change {
+    /// Outline path of the changed headline, or of the inserted or removed
+    /// one
+    pub path: Vec<usize>,
+    pub kind: ChangeKind,
+}
+
+/// What a [`Change`] did, with the values needed to replay it and to detect
+/// that the replayed document diverged
+///
+/// Sections and subtrees are the text written by the org export.
+///
+/// [`Change`]: struct.Change.html
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "ser", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "ser", serde(tag = "kind", rename_all = "snake_case"))]
+pub enum ChangeKind {
+    /// Property `key` set, by `HeadlineNode::set_property`
+    Property {
+        key: String,
+        before: Option<String>,
+        after: String,
+    },
+    /// Raw title replaced, by `HeadlineNode::set_title_content`
+    Title { before: String, after: String },
+    /// Section replaced, by `set_section_content`
+    Section { before: String, after: String },
+    /// Headline and its subtree inserted, by `append`, `prepend`,
+    /// `insert_before` or `insert_after`
+    Insert { subtree: String },
+    /// Headline and its subtree detached, by `HeadlineNode::detach`
+    Remove { subtree: String },
+}
+
+impl<'a> Org<'a> {
+    /// Returns the outline path of `node` if changes are logged and it's
+    /// part of the document
+    pub(crate) fn change_path(&self, node: NodeId) -> Option<Vec<usize>> {
+        self.changes.as_ref()?;
+
+        let mut path = Vec::new();
+        for ancestor in node.ancestors(&self.arena) {
+            if ancestor == self.root {
+                path.reverse();
+                return Some(path);
+            }
+            let index = ancestor
+                .preceding_siblings(&self.arena)
+                .skip(1)
+                .filter(|&sibling| matches!(self.arena[sibling].get(), Element::Headline { .. }))
+                .count();
+            path.push(index);
+        }
+        None
+    }
+
+    pub(crate) fn log_change(&mut self, path: Vec<usize>, kind: ChangeKind) {
+        if let Some(changes) = &mut self.changes {
+            changes.push(Change { path, kind });
+        }
+    }
+
+    /// Logs the insertion of the headline `node`
+    pub(crate) fn log_insert(&mut self, node: NodeId) {
+        if let Some(path) = self.change_path(node) {
+            let subtree = self.org_text(node);
+            self.log_change(path, ChangeKind::Insert { subtree });
+        }
+    }
+
+    /// Logs the removal of the headline `node`, before it's detached
+    pub(crate) fn log_remove(&mut self, node: NodeId) {
+        if let Some(path) = self.change_path(node) {
+            let subtree = self.org_text(node);
+            self.log_change(path, ChangeKind::Remove { subtree });
+        }
+    }
+
+    /// Returns the org text of `node` and its descendants, or an empty
+    /// string if there's no `node`
+    pub(crate) fn org_text(&self, node: impl Into<Option<NodeId>>) -> String {
+        let mut writer = Vec::new();
+        if let Some(node) = node.into() {
+            // writing to a `Vec` never fails
+            self.org_subtree(node, &mut writer).unwrap();
+        }
+        String::from_utf8_lossy(&writer).into_owned()
+    }
+
+    /// Returns the headline at `path`, `None` for the document
+    fn resolve_path(&self, path: &[usize]) -> Option<Option<HeadlineNode>> {
+        let mut headline: Option<HeadlineNode> = None;
+        for &index in path {
+            headline = Some(self.child_headline(headline, index)?);
+        }
+        Some(headline)
+    }
+
+    /// Returns the child headline of `parent`, or of the document, at `index`
+    fn child_headline(&self, parent: Option<HeadlineNode>, index: usize) -> Option<HeadlineNode> {
+        match parent {
+            Some(parent) => parent.children(self).nth(index),
+            None => self.document().children(self).nth(index),
+        }
+    }
+
+    pub(crate) fn replay_changes(&mut self, changes: &[Change]) -> Result<(), OrgizeError> {
+        for (index, change) in changes.iter().enumerate() {
+            let conflict = |at: NodeId| OrgizeError::ChangeConflict { index, at };
+
+            // insertions are addressed by the parent of the inserted headline
+            let (path, position) = match (&change.kind, change.path.split_last()) {
+                (ChangeKind::Insert { .. }, Some((&position, path))) => (path, position),
+                (ChangeKind::Insert { .. }, None) => return Err(conflict(self.root)),
+                _ => (&change.path[..], 0),
+            };
+            let target = self.resolve_path(path).ok_or_else(|| conflict(self.root))?;
+            let at = target.map_or(self.root, HeadlineNode::headline_node);
+
+            match (&change.kind, target) {
+                (ChangeKind::Property { key, before, after }, Some(headline)) => {
+                    if headline.property(key, self) != before.as_deref() {
+                        return Err(conflict(at));
+                    }
+                    headline.set_property(key, after.clone(), self);
+                }
+                (ChangeKind::Title { before, after }, Some(headline)) => {
+                    if headline.title(self).raw != *before {
+                        return Err(conflict(at));
+                    }
+                    headline.set_title_content(after.clone(), self);
+                }
+                (ChangeKind::Section { before, after }, Some(headline)) => {
+                    if self.org_text(headline.section_node) != *before {
+                        return Err(conflict(at));
+                    }
+                    headline.set_section_content(after.clone(), self);
+                }
+                (ChangeKind::Section { before, after }, None) => {
+                    let document = self.document();
+                    if self.org_text(document.section_node) != *before {
+                        return Err(conflict(at));
+                    }
+                    document.set_section_content(after.clone(), self);
+                }
+                (ChangeKind::Remove { subtree }, Some(headline)) => {
+                    if self.org_text(at) != *subtree {
+                        return Err(conflict(at));
+                    }
+                    headline.detach(self);
+                }
+                (ChangeKind::Insert { subtree }, parent) => {
+                    let previous = match position {
+                        0 => None,
+                        _ => Some(
+                            self.child_headline(parent, position - 1)
+                                .ok_or_else(|| conflict(at))?,
+                        ),
+                    };
+                    let headline = self.parse_headline(subtree);
+                    match (previous, parent) {
+                        (Some(previous), _) => previous.insert_after(headline, self),
+                        (None, Some(parent)) => parent.prepend(headline, self),
+                        (None, None) => self.document().prepend(headline, self),
+                    }
+                    .map_err(|_| conflict(at))?;
+                }
+                _ => return Err(conflict(at)),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Parses `text`, the org text of a headline and its subtree, into a
+    /// detached headline
+    fn parse_headline(&mut self, text: &str) -> HeadlineNode {
+        let level = text.bytes().take_while(|&c| c == b'*').count();
+        let node = self.arena.new_node(Element::Headline { level });
+        let timestamps = parse_container(
+            &mut OwnedArena::new(&mut self.arena),
+            Container::Headline {
+                content: text,
+                node,
+            },
+            &DEFAULT_CONFIG,
+        );
+        self.index_timestamps(timestamps);
+
+        HeadlineNode::new(node, level, self)
+    }
+}
+
+#[test]
+fn change_path() {
+    let mut org = Org::parse("* a\n** b\n** c\n* d\n");
+    let headlines: Vec<_> = org.headlines().collect();
+    assert_eq!(org.change_path(headlines[2].headline_node()), None);
+
+    org.enable_change_log();
+    assert_eq!(org.change_path(org.root), Some(vec![]));
+    assert_eq!(
+        org.change_path(headlines[2].headline_node()),
+        Some(vec![0, 1])
+    );
+    assert_eq!(org.change_path(headlines[3].headline_node()), Some(vec![1]));
+
+    headlines[0].detach(&mut org);
+    assert_eq!(org.change_path(headlines[2].headline_node()), None);
+    assert_eq!(org.change_path(headlines[3].headline_node()), Some(vec![0]));
+}
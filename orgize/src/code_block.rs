@@ -0,0 +1,152 @@
+use std::borrow::Cow;
+use std::ops::Range;
+
+use indextree::NodeId;
+
+use crate::captioned::{captioned, is_affiliated, is_listing};
+use crate::elements::{Element, SourceBlock};
+use crate::export::block_text;
+use crate::node::HeadlineNode;
+use crate::org::Org;
+
+/// A source block, with its affiliated name and header arguments
+///
+/// Returned by [`Org::code_blocks`] and [`tools::extract_code`].
+///
+/// [`Org::code_blocks`]: struct.Org.html#method.code_blocks
+/// [`tools::extract_code`]: tools/fn.extract_code.html
+#[derive(Debug)]
+pub struct CodeBlock<'a, 'b> {
+    pub node: NodeId,
+    pub element: &'b SourceBlock<'a>,
+    /// Language of the code, e.g. `rust`
+    pub language: &'b str,
+    /// Contents as rendered by exporters, see [`export::block_text`]
+    ///
+    /// [`export::block_text`]: export/fn.block_text.html
+    pub contents: Cow<'b, str>,
+    /// Header arguments of the `#+HEADER:` keywords then of the block line,
+    /// e.g. `(":results", "output")`
+    pub header_args: Vec<(&'b str, &'b str)>,
+    /// Value of the `#+NAME:` keyword
+    pub name: Option<&'b str>,
+    /// Innermost headline containing the block
+    pub headline: Option<HeadlineNode>,
+    /// Byte range of the block in the parsed text, see [`Org::span_of`]
+    ///
+    /// [`Org::span_of`]: struct.Org.html#method.span_of
+    pub span: Option<Range<usize>>,
+    /// Zero-based line of the first line of contents in the parsed text
+    pub contents_line: Option<usize>,
+}
+
+impl CodeBlock<'_, '_> {
+    /// Returns the value of the header argument `key`, e.g. `:results`
+    ///
+    /// Arguments of the block line override the ones of `#+HEADER:` keywords.
+    pub fn header_arg(&self, key: &str) -> Option<&str> {
+        self.header_args
+            .iter()
+            .rev()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, value)| *value)
+    }
+
+    /// Converts the zero-based line `line` of the contents into a zero-based
+    /// line of the parsed text
+    ///
+    /// Use it to report the diagnostics of external tools, like linters, at
+    /// their place in the org document.
+    pub fn document_line(&self, line: usize) -> Option<usize> {
+        self.contents_line.map(|start| start + line)
+    }
+}
+
+pub(crate) fn code_blocks<'a, 'b>(
+    org: &'b Org<'a>,
+) -> impl Iterator<Item = CodeBlock<'a, 'b>> + 'b {
+    let index = org.line_index();
+
+    captioned(org, is_listing).filter_map(move |captioned| {
+        let block = match captioned.element {
+            Element::SourceBlock(block) => block,
+            _ => return None,
+        };
+
+        let mut header_args = Vec::new();
+        // affiliated keywords directly precede the element, in reverse order
+        for sibling in captioned.node.preceding_siblings(&org.arena).skip(1) {
+            let keyword = match org.arena[sibling].get() {
+                Element::Keyword(keyword) => keyword,
+                Element::Caption(_) | Element::Comment { .. } => continue,
+                _ => break,
+            };
+            if keyword.key.eq_ignore_ascii_case("HEADER")
+                || keyword.key.eq_ignore_ascii_case("HEADERS")
+            {
+                header_args.splice(0..0, parse_header_args(&keyword.value));
+            } else if !is_affiliated(&keyword.key) && !keyword.key.eq_ignore_ascii_case("NAME") {
+                break;
+            }
+        }
+        header_args.extend(parse_header_args(&block.arguments));
+
+        let span = org.span_of(captioned.node);
+
+        Some(CodeBlock {
+            node: captioned.node,
+            element: block,
+            language: &block.language,
+            contents: block_text(captioned.element).unwrap_or_default(),
+            header_args,
+            name: captioned.name,
+            headline: captioned.node.ancestors(&org.arena).find_map(|node| {
+                match org.arena[node].get() {
+                    Element::Headline { level } => Some(HeadlineNode::new(node, *level, org)),
+                    _ => None,
+                }
+            }),
+            contents_line: span
+                .as_ref()
+                .map(|span| index.offset_to_position(span.start).line + 1),
+            span,
+        })
+    })
+}
+
+/// Splits header arguments like `:results output :exports both` into keys
+/// and values, ignoring the switches before them
+pub(crate) fn parse_header_args(text: &str) -> Vec<(&str, &str)> {
+    // start and end of the keys
+    let mut keys = Vec::new();
+    let mut word_start = None;
+    for (i, c) in text.char_indices().chain(Some((text.len(), ' '))) {
+        match (word_start, c.is_whitespace()) {
+            (None, false) => word_start = Some(i),
+            (Some(start), true) => {
+                if text[start..].starts_with(':') {
+                    keys.push((start, i));
+                }
+                word_start = None;
+            }
+            _ => (),
+        }
+    }
+
+    keys.iter()
+        .enumerate()
+        .map(|(i, &(start, end))| {
+            let value_end = keys.get(i + 1).map_or(text.len(), |&(next, _)| next);
+            (&text[start..end], text[end..value_end].trim())
+        })
+        .collect()
+}
+
+#[test]
+fn header_args() {
+    assert_eq!(
+        parse_header_args("-n -r :results output silent :exports both"),
+        [(":results", "output silent"), (":exports", "both")]
+    );
+    assert_eq!(parse_header_args("-i"), []);
+}
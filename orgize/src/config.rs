@@ -1,5 +1,8 @@
 //! Parse configuration module
 
+use crate::diagnostics::Severity;
+use crate::elements::{ContentMode, ElementKind, Priority};
+
 /// Parse configuration
 #[derive(Clone, Debug)]
 pub struct ParseConfig {
@@ -7,6 +10,223 @@ pub struct ParseConfig {
     pub todo_keywords: Vec<String>,
     /// Headline's todo keywords, done type
     pub done_keywords: Vec<String>,
+    /// Org syntax version to be compatible with
+    pub syntax_compat: SyntaxCompat,
+    /// Accepts `*` as a plain list bullet, `true` by default
+    ///
+    /// Even when enabled, a `*` bullet must be indented, since a line
+    /// starting with `*` at column zero is always a headline.
+    pub allow_star_bullets: bool,
+    /// Highest priority of headlines, `A` by default
+    ///
+    /// Priority cookies between `highest_priority` and `lowest_priority` are
+    /// parsed, e.g. `[#1]` to `[#64]` with `Priority::Num(1)` and
+    /// `Priority::Num(64)`. Other cookies are part of the title.
+    pub highest_priority: Priority,
+    /// Lowest priority of headlines, `Z` by default
+    pub lowest_priority: Priority,
+    /// `strftime`-like patterns of titles which are dates, empty by default
+    ///
+    /// Titles matching one of them, e.g. `* Monday, 2024-03-04` with
+    /// `"%A, %Y-%m-%d"`, get their date in `Title::headline_date`, as used
+    /// by org-journal. Supports `%Y`, `%m`, `%d`, `%e`, `%H`, `%M`, `%A`,
+    /// `%a`, `%B`, `%b` and `%%`.
+    pub headline_date_formats: Vec<String>,
+    /// Lowest severity of the diagnostics which make
+    /// `Org::try_parse_with_config` fail, `Severity::Error` by default
+    ///
+    /// It doesn't affect `Org::parse_with_config`, which never fails.
+    pub strict_severity: Severity,
+    /// Which subscripts and superscripts are parsed, `SubSuperscripts::Braces`
+    /// by default, so that `file_name` stays text
+    pub use_sub_superscripts: SubSuperscripts,
+    /// Kinds of elements which aren't recognized, empty by default
+    ///
+    /// Their syntax is left as plain text, or as `Raw` elements for the
+    /// lines listed by `Element::Raw`, e.g. disabling `SourceBlock` leaves
+    /// `#+BEGIN_SRC` and the lines after it as paragraph text and turns
+    /// `#+END_SRC` into a `Raw` element. Disabling emphasis kinds, like `Bold` and
+    /// `Italic`, speeds up parsing documents mostly made of prose.
+    ///
+    /// Some elements depend on others:
+    ///
+    /// + disabling `Timestamp` also disables the planning of headlines and
+    ///   clocks, so a `SCHEDULED:` line after a title becomes a `Raw`
+    ///   element and `CLOCK:` lines become text
+    /// + disabling `List` or `Table` also disables their items, rows and
+    ///   cells
+    /// + `Document`, `Section`, `Headline`, `Title`, `Paragraph` and `Text`
+    ///   are always recognized
+    pub disabled_elements: Vec<ElementKind>,
+    /// How the contents of drawers are parsed, by drawer name compared
+    /// case-insensitively, empty by default
+    ///
+    /// Drawers which aren't listed are parsed with `ContentMode::Parse`,
+    /// except `PROPERTIES` drawers, like the one of the document, which are
+    /// parsed with `ContentMode::Properties`. It doesn't affect the property
+    /// drawers of headlines.
+    ///
+    /// ```rust
+    /// use orgize::{elements::ContentMode, Element, Org, ParseConfig};
+    ///
+    /// let config = ParseConfig {
+    ///     drawer_modes: vec![("REVIEW_DATA".to_string(), ContentMode::Properties)],
+    ///     ..Default::default()
+    /// };
+    /// let org = Org::parse_with_config(":REVIEW_DATA:\n:SCORE: 4\n:END:\n", &config);
+    ///
+    /// let score = org.iter().find_map(|event| match event {
+    ///     orgize::Event::Start(Element::Drawer(drawer)) => drawer.property_number("score"),
+    ///     _ => None,
+    /// });
+    /// assert_eq!(score, Some(4.0));
+    /// ```
+    pub drawer_modes: Vec<(String, ContentMode)>,
+    /// How the contents of special blocks, like `#+BEGIN_NOTE`, are parsed,
+    /// by block name compared case-insensitively, empty by default
+    ///
+    /// Blocks which aren't listed are parsed with `ContentMode::Parse`. It
+    /// doesn't affect the blocks orgize knows, like source blocks.
+    pub block_modes: Vec<(String, ContentMode)>,
+    /// Deepest level of headlines, unlimited by default
+    ///
+    /// Lines starting with more stars are parsed as text of the section,
+    /// like org-mode does for inline tasks. Use
+    /// `HeadlineNode::demote_within` to keep demoted headlines within this
+    /// level, so that they're parsed back as headlines.
+    pub max_headline_level: Option<usize>,
+    /// Parses every headline as a level 1 headline, `false` by default
+    ///
+    /// Documents like chat logs use stars as markers rather than to nest
+    /// headlines. The number of stars is kept in `Title::stars`, but the org
+    /// export writes a single star.
+    ///
+    /// ```rust
+    /// use orgize::{Org, ParseConfig};
+    ///
+    /// let config = ParseConfig {
+    ///     flatten_levels: true,
+    ///     ..Default::default()
+    /// };
+    /// let org = Org::parse_with_config("* alice\n** bob\n*** alice\n", &config);
+    ///
+    /// let headlines: Vec<_> = org.headlines().collect();
+    /// assert_eq!(headlines.len(), 3);
+    /// assert!(headlines.iter().all(|headline| headline.level() == 1));
+    /// assert_eq!(headlines[2].title(&org).stars, Some(3));
+    /// ```
+    pub flatten_levels: bool,
+    /// Protocols of plain links, like `https://orgmode.org`, and angle
+    /// links, like `<https://orgmode.org>`, `http`, `https`, `ftp`, `mailto`
+    /// and `file` by default
+    ///
+    /// Both are parsed as `Link` elements without description. Plain links
+    /// start at the beginning of a word and leave out trailing punctuation,
+    /// like the period ending a sentence. An empty list only disables them,
+    /// bracket links are parsed regardless.
+    ///
+    /// ```rust
+    /// use orgize::{Element, Org, ParseConfig};
+    ///
+    /// let config = ParseConfig {
+    ///     link_protocols: vec!["gemini".to_string()],
+    ///     ..Default::default()
+    /// };
+    /// let org = Org::parse_with_config("See gemini://example.org, not https://example.org.", &config);
+    ///
+    /// let paths: Vec<_> = org
+    ///     .iter()
+    ///     .filter_map(|event| match event {
+    ///         orgize::Event::Start(Element::Link(link)) => Some(link.path.to_string()),
+    ///         _ => None,
+    ///     })
+    ///     .collect();
+    /// assert_eq!(paths, ["gemini://example.org"]);
+    /// ```
+    pub link_protocols: Vec<String>,
+    /// Skips parsing the contents of headlines tagged `ARCHIVE`, `false` by
+    /// default
+    ///
+    /// Their section and child headlines are kept unparsed, as the text of a
+    /// section, which the org export writes back as is. It speeds up
+    /// parsing documents made mostly of archived subtrees.
+    ///
+    /// ```rust
+    /// use orgize::{Org, ParseConfig};
+    ///
+    /// let config = ParseConfig {
+    ///     skip_archived: true,
+    ///     ..Default::default()
+    /// };
+    /// let text = "* Done :ARCHIVE:\n/old/ notes\n** Old child\n* Current\n** Child\n";
+    /// let org = Org::parse_with_config(text, &config);
+    ///
+    /// let titles: Vec<_> = org.headlines().map(|headline| headline.title(&org).raw.to_string()).collect();
+    /// assert_eq!(titles, ["Done", "Current", "Child"]);
+    ///
+    /// let mut writer = Vec::new();
+    /// org.org(&mut writer).unwrap();
+    /// assert_eq!(String::from_utf8(writer).unwrap(), text);
+    /// ```
+    pub skip_archived: bool,
+}
+
+impl ParseConfig {
+    /// Returns `true` if elements of `kind` are recognized, see
+    /// `disabled_elements`
+    #[inline]
+    pub(crate) fn is_enabled(&self, kind: ElementKind) -> bool {
+        !self.disabled_elements.contains(&kind)
+    }
+
+    /// Returns the mode of the drawer `name`, see `drawer_modes`
+    pub(crate) fn drawer_mode(&self, name: &str) -> ContentMode {
+        match self
+            .drawer_modes
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        {
+            Some((_, mode)) => *mode,
+            None if name.eq_ignore_ascii_case("PROPERTIES") => ContentMode::Properties,
+            None => ContentMode::Parse,
+        }
+    }
+
+    /// Returns the mode of the special block `name`, see `block_modes`
+    pub(crate) fn block_mode(&self, name: &str) -> ContentMode {
+        find_mode(&self.block_modes, name)
+    }
+}
+
+fn find_mode(modes: &[(String, ContentMode)], name: &str) -> ContentMode {
+    modes
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(_, mode)| *mode)
+        .unwrap_or_default()
+}
+
+/// Subscripts and superscripts parsed, like the `^` option of `#+OPTIONS`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SubSuperscripts {
+    /// `H_2O`, `x^{2n+1}` and `x^(a)`, like `^:t`
+    Always,
+    /// Only the braced form, `H_{2}O` and `x^{2n+1}`, like `^:{}`
+    Braces,
+    /// None, like `^:nil`
+    Never,
+}
+
+/// Org syntax version
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SyntaxCompat {
+    /// Syntax of Org 9.x
+    Current,
+    /// Also accepts forms deprecated by Org 9.x, converting them to their
+    /// current equivalent: `#+BEGIN_HTML`, `#+BEGIN_LATEX` and other blocks
+    /// named after a backend become export blocks, and `#+RESULT:` becomes
+    /// `#+RESULTS:`.
+    Legacy,
 }
 
 impl Default for ParseConfig {
@@ -14,6 +234,23 @@ impl Default for ParseConfig {
         ParseConfig {
             todo_keywords: vec![String::from("TODO")],
             done_keywords: vec![String::from("DONE")],
+            syntax_compat: SyntaxCompat::Current,
+            allow_star_bullets: true,
+            highest_priority: Priority::Char('A'),
+            lowest_priority: Priority::Char('Z'),
+            headline_date_formats: Vec::new(),
+            strict_severity: Severity::Error,
+            use_sub_superscripts: SubSuperscripts::Braces,
+            disabled_elements: Vec::new(),
+            drawer_modes: Vec::new(),
+            block_modes: Vec::new(),
+            max_headline_level: None,
+            flatten_levels: false,
+            link_protocols: ["http", "https", "ftp", "mailto", "file"]
+                .iter()
+                .map(|protocol| protocol.to_string())
+                .collect(),
+            skip_archived: false,
         }
     }
 }
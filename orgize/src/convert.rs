@@ -0,0 +1,106 @@
+use crate::config::ParseConfig;
+use crate::diagnostics::{Diagnostic, Severity};
+#[cfg(feature = "export-html")]
+use crate::export::{DefaultHtmlHandler, HtmlExportOptions};
+use crate::org::Org;
+
+/// Converts the org document `src` to html, with the default options if
+/// `options` is `None`
+///
+/// The document is parsed with `options.parse_config` and converted whatever
+/// its diagnostics, like [`Org::parse_with_config`]. With `options.strict`,
+/// it fails with the diagnostics of the document instead if one of them is
+/// at least as severe as `parse_config.strict_severity`, see
+/// [`Org::try_parse_with_config`].
+///
+/// ```rust
+/// use orgize::export::HtmlExportOptions;
+///
+/// assert_eq!(
+///     orgize::org_to_html("* Title\n*bold*", None).unwrap(),
+///     "<main><h1>Title</h1><section><p><b>bold</b></p></section></main>"
+/// );
+///
+/// let src = "[fn:1] a\n\n[fn:1] b\n";
+/// assert!(orgize::org_to_html(src, None).is_ok());
+///
+/// let options = HtmlExportOptions {
+///     strict: true,
+///     ..Default::default()
+/// };
+/// let errors = orgize::org_to_html(src, Some(&options)).unwrap_err();
+/// assert_eq!(errors[0].message, "footnote label defined more than once: 1");
+/// ```
+///
+/// [`Org::parse_with_config`]: struct.Org.html#method.parse_with_config
+/// [`Org::try_parse_with_config`]: struct.Org.html#method.try_parse_with_config
+#[cfg(feature = "export-html")]
+pub fn org_to_html(
+    src: &str,
+    options: Option<&HtmlExportOptions>,
+) -> Result<String, Vec<Diagnostic>> {
+    let default_options;
+    let options = match options {
+        Some(options) => options,
+        None => {
+            default_options = HtmlExportOptions::default();
+            &default_options
+        }
+    };
+
+    let org = parse(src, &options.parse_config, options.strict)?;
+    let analysis = org.analyze_for_html(options);
+    let mut writer = Vec::new();
+    org.html_with_analysis(&mut writer, &mut DefaultHtmlHandler::default(), &analysis)
+        .map_err(|err| vec![error(format!("failed to write html: {}", err))])?;
+    String::from_utf8(writer).map_err(|err| vec![error(err.to_string())])
+}
+
+/// Converts the org document `src` to markdown, parsed with the default
+/// configuration if `config` is `None`
+///
+/// Like [`org_to_html`], it only fails with the diagnostics of the document
+/// if `strict` is `true`.
+///
+/// ```rust
+/// assert_eq!(
+///     orgize::org_to_markdown("* Title\n*bold*", None, false).unwrap(),
+///     "# Title\n\n**bold**\n"
+/// );
+/// ```
+///
+/// [`org_to_html`]: fn.org_to_html.html
+#[cfg(feature = "export-markdown")]
+pub fn org_to_markdown(
+    src: &str,
+    config: Option<&ParseConfig>,
+    strict: bool,
+) -> Result<String, Vec<Diagnostic>> {
+    let default_config;
+    let config = match config {
+        Some(config) => config,
+        None => {
+            default_config = ParseConfig::default();
+            &default_config
+        }
+    };
+
+    let org = parse(src, config, strict)?;
+    let mut writer = Vec::new();
+    org.markdown(&mut writer)
+        .map_err(|err| vec![error(format!("failed to write markdown: {}", err))])?;
+    String::from_utf8(writer).map_err(|err| vec![error(err.to_string())])
+}
+
+/// Parses `src`, failing with its diagnostics if `strict`
+fn parse<'a>(src: &'a str, config: &ParseConfig, strict: bool) -> Result<Org<'a>, Vec<Diagnostic>> {
+    if strict {
+        Org::try_parse_with_config(src, config)
+    } else {
+        Ok(Org::parse_with_config(src, config))
+    }
+}
+
+fn error(message: String) -> Diagnostic {
+    Diagnostic::new(Severity::Error, None, message)
+}
@@ -0,0 +1,284 @@
+use std::fmt;
+
+use indextree::{NodeEdge, NodeId};
+
+use crate::elements::{Clock, Datetime, Element, Modifiers, Table, TableCell, TableRow, Timestamp};
+use crate::org::Org;
+
+/// An indented outline of a document, one element per line
+///
+/// Each line starts with the kind of the element, followed by its most
+/// important data, the number of children of containers and the byte range
+/// of the element in the parsed text, if known. Strings are quoted and
+/// escaped like Rust strings, so the output only depends on the tree and is
+/// suitable for snapshot tests.
+///
+/// ```rust
+/// use orgize::Org;
+///
+/// let org = Org::parse("* TODO Title :tag:\n*bold* text\n");
+///
+/// assert_eq!(
+///     org.debug_tree(),
+///     r#"document (1 child) 0..31
+///   headline l1 "Title" [TODO] :tag: (2 children) 0..31
+///     title (1 child) 0..19
+///       text "Title" 7..12
+///     section (1 child) 19..31
+///       paragraph (2 children) 19..30
+///         bold (1 child) 19..25
+///           text "bold" 20..24
+///         text " text" 25..30
+/// "#
+/// );
+/// ```
+pub struct DebugTree<'a, 'b> {
+    org: &'b Org<'a>,
+    node: NodeId,
+}
+
+impl<'a, 'b> DebugTree<'a, 'b> {
+    /// Outline of the whole document
+    pub fn new(org: &'b Org<'a>) -> DebugTree<'a, 'b> {
+        DebugTree {
+            org,
+            node: org.root,
+        }
+    }
+
+    /// Outline of `node` and its descendants
+    pub fn of(org: &'b Org<'a>, node: NodeId) -> DebugTree<'a, 'b> {
+        DebugTree { org, node }
+    }
+}
+
+impl<'a> Org<'a> {
+    /// Returns an indented outline of the document, see [`DebugTree`]
+    ///
+    /// [`DebugTree`]: struct.DebugTree.html
+    pub fn debug_tree(&self) -> String {
+        DebugTree::new(self).to_string()
+    }
+}
+
+impl fmt::Display for DebugTree<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let arena = &self.org.arena;
+        let mut depth = 0;
+
+        for edge in self.node.traverse(arena) {
+            let node = match edge {
+                NodeEdge::Start(node) => node,
+                NodeEdge::End(_) => {
+                    depth -= 1;
+                    continue;
+                }
+            };
+
+            let element = arena[node].get();
+            write!(
+                f,
+                "{:indent$}{}",
+                "",
+                kind_name(element),
+                indent = depth * 2
+            )?;
+            write_data(f, self.org, element, node)?;
+            if element.is_container() {
+                match node.children(arena).count() {
+                    1 => write!(f, " (1 child)")?,
+                    count => write!(f, " ({} children)", count)?,
+                }
+            }
+            if let Some(span) = self.org.spans.get(&node) {
+                write!(f, " {}..{}", span.start, span.end)?;
+            }
+            writeln!(f)?;
+
+            depth += 1;
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the kind of an element in kebab case, like `list-item`
+fn kind_name(element: &Element<'_>) -> String {
+    let mut name = String::new();
+    for c in format!("{:?}", element.kind()).chars() {
+        if c.is_ascii_uppercase() && !name.is_empty() {
+            name.push('-');
+        }
+        name.push(c.to_ascii_lowercase());
+    }
+    name
+}
+
+fn write_data(
+    f: &mut fmt::Formatter<'_>,
+    org: &Org<'_>,
+    element: &Element<'_>,
+    node: NodeId,
+) -> fmt::Result {
+    match element {
+        Element::SpecialBlock(block) => {
+            write!(f, " {:?}", block.name)?;
+            write_optional(f, &block.parameters)
+        }
+        Element::QuoteBlock(block) => write_optional(f, &block.parameters),
+        Element::CenterBlock(block) => write_optional(f, &block.parameters),
+        Element::VerseBlock(block) => write_optional(f, &block.parameters),
+        Element::CommentBlock(block) => write!(f, " {:?}", block.contents),
+        Element::ExampleBlock(block) => write!(f, " {:?}", block.contents),
+        Element::ExportBlock(block) => write!(f, " {:?} {:?}", block.data, block.contents),
+        Element::SourceBlock(block) => write!(f, " {:?} {:?}", block.language, block.contents),
+        Element::BabelCall(call) => write!(f, " {:?}", call.value),
+        Element::Clock(Clock::Closed { start, end, .. }) => {
+            write_datetime(f, start)?;
+            write!(f, " --")?;
+            write_datetime(f, end)
+        }
+        Element::Clock(Clock::Running { start, .. }) => {
+            write_datetime(f, start)?;
+            write!(f, " running")
+        }
+        Element::Cookie(cookie) => write!(f, " {:?}", cookie.value),
+        Element::Drawer(drawer) => write!(f, " {:?}", drawer.name),
+        Element::DynBlock(block) => write!(f, " {:?}", block.block_name),
+        Element::FnDef(fn_def) => write!(f, " {:?}", fn_def.label),
+        Element::FnRef(fn_ref) => write!(f, " {:?}", fn_ref.label),
+        Element::Citation(citation) => {
+            for key in citation.keys() {
+                write!(f, " @{}", key)?;
+            }
+            Ok(())
+        }
+        Element::Headline { level } => {
+            let title = match org.arena[node]
+                .first_child()
+                .map(|child| org.arena[child].get())
+            {
+                Some(Element::Title(title)) => title,
+                _ => return write!(f, " l{}", level),
+            };
+            write!(f, " l{} {:?}", level, title.raw)?;
+            if let Some(keyword) = &title.keyword {
+                write!(f, " [{}]", keyword)?;
+            }
+            if let Some(priority) = &title.priority {
+                write!(f, " [#{}]", priority)?;
+            }
+            if !title.tags.is_empty() {
+                write!(f, " :{}:", title.tags.join(":"))?;
+            }
+            Ok(())
+        }
+        Element::Entity(entity) => write!(f, " {:?}", entity.name),
+        Element::InlineCall(call) => write!(f, " {:?}", call.name),
+        Element::InlineSrc(src) => write!(f, " {:?} {:?}", src.lang, src.body),
+        Element::Keyword(keyword) => write!(f, " {} {:?}", keyword.key, keyword.value),
+        Element::LatexFragment(fragment) => write!(f, " {:?} {:?}", fragment.kind, fragment.value),
+        Element::Link(link) => write!(f, " {:?}", link.path),
+        Element::List(list) if list.ordered => match list.start {
+            Some(start) => write!(f, " ordered {}", start),
+            None => write!(f, " ordered"),
+        },
+        Element::List(list) if list.description => write!(f, " description"),
+        Element::ListItem(item) => write!(f, " {:?}", item.bullet),
+        Element::Macros(macros) => write!(f, " {:?}", macros.name),
+        Element::Snippet(snippet) => write!(f, " {:?} {:?}", snippet.name, snippet.value),
+        Element::Timestamp(timestamp) => write_timestamp(f, timestamp),
+        Element::Target(target) => write!(f, " {:?}", target.target),
+        Element::RadioTarget(radio_target) => write!(f, " {:?}", radio_target.target),
+        Element::RawHtml(raw_html) => write!(f, " {:?}", raw_html.html()),
+        Element::Text { value }
+        | Element::Verbatim { value }
+        | Element::Code { value }
+        | Element::Comment { value }
+        | Element::FixedWidth { value }
+        | Element::Raw { value } => write!(f, " {:?}", value),
+        Element::Table(Table::Org { has_header, .. }) => {
+            write!(f, " org")?;
+            if *has_header {
+                write!(f, " with-header")?;
+            }
+            Ok(())
+        }
+        Element::Table(Table::TableEl { value }) => write!(f, " table.el {:?}", value),
+        Element::TableRow(TableRow::Header) => write!(f, " header"),
+        Element::TableRow(TableRow::HeaderRule) => write!(f, " header-rule"),
+        Element::TableRow(TableRow::BodyRule) => write!(f, " rule"),
+        Element::TableCell(TableCell::Header) => write!(f, " header"),
+        _ => Ok(()),
+    }
+}
+
+fn write_optional(f: &mut fmt::Formatter<'_>, value: &Option<impl fmt::Debug>) -> fmt::Result {
+    match value {
+        Some(value) => write!(f, " {:?}", value),
+        None => Ok(()),
+    }
+}
+
+fn write_timestamp(f: &mut fmt::Formatter<'_>, timestamp: &Timestamp<'_>) -> fmt::Result {
+    match timestamp {
+        Timestamp::Active { start, modifiers } => {
+            write!(f, " active")?;
+            write_datetime(f, start)?;
+            write_modifiers(f, modifiers)?;
+        }
+        Timestamp::Inactive { start, modifiers } => {
+            write!(f, " inactive")?;
+            write_datetime(f, start)?;
+            write_modifiers(f, modifiers)?;
+        }
+        Timestamp::ActiveRange {
+            start,
+            end,
+            modifiers,
+            end_modifiers,
+        } => {
+            write!(f, " active")?;
+            write_datetime(f, start)?;
+            write_modifiers(f, modifiers)?;
+            write!(f, " --")?;
+            write_datetime(f, end)?;
+            write_modifiers(f, end_modifiers)?;
+        }
+        Timestamp::InactiveRange {
+            start,
+            end,
+            modifiers,
+            end_modifiers,
+        } => {
+            write!(f, " inactive")?;
+            write_datetime(f, start)?;
+            write_modifiers(f, modifiers)?;
+            write!(f, " --")?;
+            write_datetime(f, end)?;
+            write_modifiers(f, end_modifiers)?;
+        }
+        Timestamp::Diary { value } => write!(f, " diary {:?}", value)?,
+    }
+    Ok(())
+}
+
+fn write_modifiers(f: &mut fmt::Formatter<'_>, modifiers: &Modifiers) -> fmt::Result {
+    if modifiers.is_empty() {
+        Ok(())
+    } else {
+        write!(f, " {}", modifiers)
+    }
+}
+
+fn write_datetime(f: &mut fmt::Formatter<'_>, datetime: &Datetime<'_>) -> fmt::Result {
+    write!(
+        f,
+        " {:04}-{:02}-{:02}",
+        datetime.year, datetime.month, datetime.day
+    )?;
+    if let (Some(hour), Some(minute)) = (datetime.hour, datetime.minute) {
+        write!(f, " {:02}:{:02}", hour, minute)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,90 @@
+use std::borrow::Cow;
+use std::char::{decode_utf16, REPLACEMENT_CHARACTER};
+use std::str;
+
+/// Decode the bytes of an org file
+///
+/// UTF-8, with or without a byte order mark, and UTF-16 with a byte order mark
+/// are detected. Otherwise, invalid sequences are replaced with
+/// `U+FFFD REPLACEMENT CHARACTER`. Use `decode_with_fallback`, behind the
+/// `encoding` feature, to decode files in other encodings like Latin-1.
+///
+/// The byte order mark is removed, so spans reported by the parser, like
+/// [`Org::source_of`], are relative to the decoded string, not to `bytes`.
+///
+/// [`Org::source_of`]: struct.Org.html#method.source_of
+///
+/// ```rust
+/// use orgize::decode;
+///
+/// assert_eq!(decode(b"\xEF\xBB\xBF* title"), "* title");
+/// assert_eq!(decode(b"\xFF\xFE*\x00 \x00a\x00"), "* a");
+/// assert_eq!(decode(b"caf\xE9"), "caf\u{FFFD}");
+/// ```
+pub fn decode(bytes: &[u8]) -> Cow<'_, str> {
+    detect(bytes, String::from_utf8_lossy)
+}
+
+/// Decode the bytes of an org file, using `fallback` if they are neither UTF-8
+/// nor UTF-16 with a byte order mark
+///
+/// See [`decode`] for details.
+///
+/// [`decode`]: fn.decode.html
+///
+/// ```rust
+/// use orgize::decode_with_fallback;
+///
+/// assert_eq!(
+///     decode_with_fallback(b"caf\xE9", encoding_rs::WINDOWS_1252),
+///     "café"
+/// );
+/// ```
+#[cfg(feature = "encoding")]
+pub fn decode_with_fallback<'a>(
+    bytes: &'a [u8],
+    fallback: &'static encoding_rs::Encoding,
+) -> Cow<'a, str> {
+    detect(bytes, |bytes| fallback.decode_without_bom_handling(bytes).0)
+}
+
+fn detect<'a, F>(bytes: &'a [u8], fallback: F) -> Cow<'a, str>
+where
+    F: FnOnce(&'a [u8]) -> Cow<'a, str>,
+{
+    if bytes.starts_with(b"\xEF\xBB\xBF") {
+        String::from_utf8_lossy(&bytes[3..])
+    } else if bytes.starts_with(b"\xFF\xFE") {
+        decode_utf16_bytes(&bytes[2..], u16::from_le_bytes).into()
+    } else if bytes.starts_with(b"\xFE\xFF") {
+        decode_utf16_bytes(&bytes[2..], u16::from_be_bytes).into()
+    } else if let Ok(text) = str::from_utf8(bytes) {
+        text.into()
+    } else {
+        fallback(bytes)
+    }
+}
+
+fn decode_utf16_bytes(bytes: &[u8], from_bytes: fn([u8; 2]) -> u16) -> String {
+    // a trailing odd byte becomes a replacement character
+    let units = bytes.chunks(2).map(|chunk| match chunk {
+        [a, b] => from_bytes([*a, *b]),
+        _ => 0xFFFD,
+    });
+
+    decode_utf16(units)
+        .map(|c| c.unwrap_or(REPLACEMENT_CHARACTER))
+        .collect()
+}
+
+#[test]
+fn detect_() {
+    assert!(matches!(decode(b"* title"), Cow::Borrowed("* title")));
+    assert_eq!(decode(b"\xEF\xBB\xBF* \xE2\x9C\x93"), "* \u{2713}");
+    assert_eq!(decode(b"\xFF\xFE\x13\x27"), "\u{2713}");
+    assert_eq!(decode(b"\xFE\xFF\x27\x13\x00"), "\u{2713}\u{FFFD}");
+    // unpaired surrogate
+    assert_eq!(decode(b"\xFE\xFF\xD8\x00\x00a"), "\u{FFFD}a");
+    assert_eq!(decode(b"a\xFFb"), "a\u{FFFD}b");
+    assert_eq!(decode(b""), "");
+}
@@ -0,0 +1,309 @@
+use std::collections::HashSet;
+use std::ops::Range;
+use std::path::PathBuf;
+
+use crate::config::{ParseConfig, DEFAULT_CONFIG};
+use crate::elements::{Datetime, Element, PropertyLine, Timestamp};
+use crate::line_index::{LineIndex, Position};
+use crate::org::Org;
+
+/// How serious a [`Diagnostic`] is
+///
+/// [`Diagnostic`]: struct.Diagnostic.html
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// Syntax which may be meant as text, e.g. an orphaned `:END:` line
+    Warning,
+    /// Malformed syntax, e.g. a footnote label defined more than once
+    Error,
+}
+
+/// A problem found while parsing or exporting a document
+///
+/// It's returned by [`Org::parse_diagnostics`], [`Org::export_diagnostics`],
+/// [`HtmlExportAnalysis::diagnostics`] and [`parse_files`].
+///
+/// [`Org::parse_diagnostics`]: struct.Org.html#method.parse_diagnostics
+/// [`Org::export_diagnostics`]: struct.Org.html#method.export_diagnostics
+/// [`HtmlExportAnalysis::diagnostics`]: export/struct.HtmlExportAnalysis.html#method.diagnostics
+/// [`parse_files`]: fn.parse_files.html
+#[derive(Clone, Debug, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// Path of the file, for the diagnostics of [`parse_files`]
+    ///
+    /// [`parse_files`]: fn.parse_files.html
+    pub path: Option<PathBuf>,
+    /// Byte range of the element in the parsed text, see [`Org::span_of`]
+    ///
+    /// [`Org::span_of`]: struct.Org.html#method.span_of
+    pub span: Option<Range<usize>>,
+    /// Line and byte column of the start of `span`, see [`LineIndex`]
+    ///
+    /// [`LineIndex`]: struct.LineIndex.html
+    pub position: Option<Position>,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub(crate) fn new(severity: Severity, span: Option<Range<usize>>, message: String) -> Self {
+        Diagnostic {
+            severity,
+            path: None,
+            span,
+            position: None,
+            message,
+        }
+    }
+}
+
+/// Sets the position of `diagnostics` from their span in `text`
+pub(crate) fn locate(diagnostics: &mut [Diagnostic], text: &str) {
+    if diagnostics
+        .iter()
+        .all(|diagnostic| diagnostic.span.is_none())
+    {
+        return;
+    }
+
+    let index = LineIndex::new(text);
+    for diagnostic in diagnostics {
+        diagnostic.position = diagnostic
+            .span
+            .as_ref()
+            .map(|span| index.offset_to_position(span.start));
+    }
+}
+
+impl<'a> Org<'a> {
+    /// Parses `text` with the default ParseConfig, failing on malformed
+    /// syntax, see [`Org::try_parse_with_config`]
+    ///
+    /// [`Org::try_parse_with_config`]: #method.try_parse_with_config
+    pub fn try_parse(text: &'a str) -> Result<Org<'a>, Vec<Diagnostic>> {
+        Org::try_parse_with_config(text, &DEFAULT_CONFIG)
+    }
+
+    /// Parses `text`, failing if one of its diagnostics is at least as
+    /// severe as the `strict_severity` of `config`
+    ///
+    /// The error holds all the diagnostics of the document, in document
+    /// order, so they can be fixed at once.
+    ///
+    /// ```rust
+    /// use orgize::{Org, Position, Severity};
+    ///
+    /// let errors = Org::try_parse("* a\nSCHEDULED: <soon>\n\n[fn:1] a\n\n[fn:1] b\n")
+    ///     .err()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(errors.len(), 2);
+    /// assert_eq!(errors[0].severity, Severity::Warning);
+    /// assert_eq!(errors[0].message, "invalid planning: SCHEDULED: <soon>");
+    /// assert_eq!(errors[1].severity, Severity::Error);
+    /// assert_eq!(errors[1].span, Some(33..42));
+    /// assert_eq!(errors[1].position, Some(Position { line: 5, column: 0 }));
+    ///
+    /// assert!(Org::try_parse("* a\nSCHEDULED: <soon>\n").is_ok());
+    /// ```
+    pub fn try_parse_with_config(
+        text: &'a str,
+        config: &ParseConfig,
+    ) -> Result<Org<'a>, Vec<Diagnostic>> {
+        let org = Org::parse_with_config(text, config);
+        let diagnostics = org.parse_diagnostics();
+
+        if diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.severity >= config.strict_severity)
+        {
+            Err(diagnostics)
+        } else {
+            Ok(org)
+        }
+    }
+}
+
+impl Org<'_> {
+    /// Returns the problems the parser recovered from, in document order
+    ///
+    /// These are the `Raw` elements left over from malformed syntax, i.e.
+    /// orphaned `:END:` and block end lines and invalid planning lines, the
+    /// blocks and drawers which are never closed and are parsed as text, as
+    /// well as malformed lines of property drawers, dates which don't exist
+    /// and footnote labels defined more than once. Parsing never fails on
+    /// them.
+    pub fn parse_diagnostics(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        let mut labels = HashSet::new();
+
+        for node in self.root.descendants(&self.arena) {
+            let mut push = |severity, message| {
+                diagnostics.push(Diagnostic::new(severity, self.span_of(node), message))
+            };
+
+            match self.arena[node].get() {
+                Element::Raw { value } => push(Severity::Warning, raw_message(value)),
+                Element::Title(title) => {
+                    let invalid = title
+                        .planning
+                        .iter()
+                        .flat_map(|planning| {
+                            vec![&planning.scheduled, &planning.deadline, &planning.closed]
+                        })
+                        .flatten()
+                        .any(|timestamp| !is_valid(timestamp));
+                    if invalid {
+                        push(
+                            Severity::Error,
+                            "planning timestamp with an invalid date".to_string(),
+                        );
+                    }
+                    for line in &title.property_lines {
+                        if let PropertyLine::Raw(line) = line {
+                            push(
+                                Severity::Warning,
+                                format!("malformed property line: {}", line.trim()),
+                            );
+                        }
+                    }
+                }
+                Element::Timestamp(timestamp) if !is_valid(timestamp) => push(
+                    Severity::Warning,
+                    "timestamp with an invalid date".to_string(),
+                ),
+                Element::FnDef(fn_def) if !labels.insert(&fn_def.label) => push(
+                    Severity::Error,
+                    format!("footnote label defined more than once: {}", fn_def.label),
+                ),
+                // dynamic blocks which are never closed are parsed as keywords
+                Element::Keyword(keyword) if keyword.key.eq_ignore_ascii_case("BEGIN") => push(
+                    Severity::Warning,
+                    format!("unclosed block: #+{}: {}", keyword.key, keyword.value),
+                ),
+                Element::Paragraph => {
+                    let (source, mut offset) = match (self.source_of(node), self.span_of(node)) {
+                        (Some(source), Some(span)) => (source, span.start),
+                        _ => continue,
+                    };
+                    for line in source.split_inclusive('\n') {
+                        if let Some(message) = unclosed_message(line) {
+                            diagnostics.push(Diagnostic::new(
+                                Severity::Warning,
+                                Some(offset..offset + line.len()),
+                                message,
+                            ));
+                        }
+                        offset += line.len();
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        locate(&mut diagnostics, self.text);
+        diagnostics
+    }
+}
+
+impl Org<'_> {
+    /// Returns the elements the export `backend`, like `"markdown"`, skips
+    /// because it can't write them, in document order
+    ///
+    /// These are the `RawHtml` elements, which only the html and org exports
+    /// write.
+    ///
+    /// ```rust
+    /// use orgize::{Element, Org, Severity};
+    ///
+    /// let mut org = Org::parse("Diagram:\n");
+    /// let paragraph = org
+    ///     .arena()
+    ///     .iter()
+    ///     .find(|node| *node.get() == Element::Paragraph)
+    ///     .and_then(|node| org.arena().get_node_id(node))
+    ///     .unwrap();
+    /// org.insert_raw_html_after(paragraph, "<svg></svg>");
+    ///
+    /// let diagnostics = org.export_diagnostics("markdown");
+    /// assert_eq!(diagnostics.len(), 1);
+    /// assert_eq!(diagnostics[0].severity, Severity::Warning);
+    /// assert_eq!(diagnostics[0].message, "raw html skipped by the markdown export");
+    /// assert!(org.export_diagnostics("html").is_empty());
+    /// ```
+    pub fn export_diagnostics(&self, backend: &str) -> Vec<Diagnostic> {
+        if backend.eq_ignore_ascii_case("html") || backend.eq_ignore_ascii_case("org") {
+            return Vec::new();
+        }
+
+        let mut diagnostics: Vec<_> = self
+            .root
+            .descendants(&self.arena)
+            .filter(|&node| matches!(self.arena[node].get(), Element::RawHtml(_)))
+            .map(|node| {
+                Diagnostic::new(
+                    Severity::Warning,
+                    self.span_of(node),
+                    format!("raw html skipped by the {} export", backend),
+                )
+            })
+            .collect();
+        locate(&mut diagnostics, self.text);
+        diagnostics
+    }
+}
+
+/// Returns the message of the diagnostic of a `Raw` element, see
+/// `Element::Raw` for the lines it may contain
+fn raw_message(value: &str) -> String {
+    let starts_with = |prefix: &str| {
+        value
+            .get(0..prefix.len())
+            .filter(|start| start.eq_ignore_ascii_case(prefix))
+            .is_some()
+    };
+
+    if starts_with("#+END") {
+        format!("block end line without block: {}", value)
+    } else if starts_with(":END:") {
+        "orphaned :END:".to_string()
+    } else {
+        format!("invalid planning: {}", value)
+    }
+}
+
+/// Returns the message of the diagnostic of a line of a paragraph which
+/// opens a block or a drawer, which is parsed as text since it's never closed
+fn unclosed_message(line: &str) -> Option<String> {
+    let line = line.trim();
+    let starts_with = |prefix: &str| {
+        line.len() > prefix.len()
+            && line.as_bytes()[0..prefix.len()].eq_ignore_ascii_case(prefix.as_bytes())
+    };
+    let is_drawer = line.len() > 2
+        && line.starts_with(':')
+        && line.ends_with(':')
+        && line[1..line.len() - 1]
+            .chars()
+            .all(|c| c.is_ascii_alphabetic() || c == '-' || c == '_')
+        && !line.eq_ignore_ascii_case(":END:");
+
+    if starts_with("#+BEGIN_") || starts_with("#+BEGIN:") {
+        Some(format!("unclosed block: {}", line))
+    } else if is_drawer {
+        Some(format!("unclosed drawer: {}", line))
+    } else {
+        None
+    }
+}
+
+fn is_valid(timestamp: &Timestamp<'_>) -> bool {
+    let datetimes: Vec<&Datetime<'_>> = match timestamp {
+        Timestamp::Active { start, .. } | Timestamp::Inactive { start, .. } => vec![start],
+        Timestamp::ActiveRange { start, end, .. } | Timestamp::InactiveRange { start, end, .. } => {
+            vec![start, end]
+        }
+        Timestamp::Diary { .. } => Vec::new(),
+    };
+    datetimes.iter().all(|datetime| datetime.is_valid())
+}
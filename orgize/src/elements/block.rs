@@ -1,35 +1,84 @@
 use std::borrow::Cow;
+use std::collections::HashMap;
 
 use nom::{
-    bytes::complete::tag_no_case, character::complete::alpha1, error::ParseError,
-    sequence::preceded, IResult,
+    bytes::complete::{tag_no_case, take_while1},
+    error::ParseError,
+    sequence::preceded,
+    IResult,
 };
 
-use crate::parsers::{line, take_lines_while};
+use crate::elements::{
+    drawer::{find_property, mode_contents, ContentMode},
+    title::{parse_number_value, parse_timestamp_value},
+    Timestamp,
+};
+use crate::intern::intern;
+use crate::parsers::{line, not_eof, take_lines_while};
 
 /// Special Block Element
-#[derive(Debug)]
-#[cfg_attr(test, derive(PartialEq))]
+#[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "ser", derive(serde::Serialize))]
 pub struct SpecialBlock<'a> {
     /// Optional block parameters
     pub parameters: Option<Cow<'a, str>>,
     /// Block name
     pub name: Cow<'a, str>,
+    /// How the block contents were parsed
+    pub mode: ContentMode,
+    /// Block contents, unescaped, unless they were parsed into child elements
+    pub contents: Option<Cow<'a, str>>,
+    /// Block properties, with `ContentMode::Properties`
+    pub properties: HashMap<Cow<'a, str>, Cow<'a, str>>,
 }
 
-impl SpecialBlock<'_> {
+impl<'a> SpecialBlock<'a> {
+    /// Returns the block holding `contents` parsed with `mode`, which keeps
+    /// them as child elements with `ContentMode::Parse`
+    pub(crate) fn with_contents(self, mode: ContentMode, contents: &'a str) -> SpecialBlock<'a> {
+        let (contents, properties) = mode_contents(mode, unescape_contents(contents), contents);
+        SpecialBlock {
+            mode,
+            contents,
+            properties,
+            ..self
+        }
+    }
+
+    /// Returns the value of the property `key`, compared case-insensitively
+    pub fn property(&self, key: &str) -> Option<&str> {
+        find_property(&self.properties, key)
+    }
+
+    /// Returns the value of the property `key` as a timestamp, see
+    /// `Title::property_timestamp`
+    pub fn property_timestamp(&self, key: &str) -> Option<Timestamp<'_>> {
+        self.property(key).and_then(parse_timestamp_value)
+    }
+
+    /// Returns the value of the property `key` as a number, see
+    /// `Title::property_number`
+    pub fn property_number(&self, key: &str) -> Option<f64> {
+        self.property(key).and_then(parse_number_value)
+    }
+
     pub fn into_owned(self) -> SpecialBlock<'static> {
         SpecialBlock {
-            name: self.name.into_owned().into(),
+            name: intern(self.name),
             parameters: self.parameters.map(Into::into).map(Cow::Owned),
+            mode: self.mode,
+            contents: self.contents.map(Into::into).map(Cow::Owned),
+            properties: self
+                .properties
+                .into_iter()
+                .map(|(k, v)| (intern(k), v.into_owned().into()))
+                .collect(),
         }
     }
 }
 
 /// Quote Block Element
-#[derive(Debug)]
-#[cfg_attr(test, derive(PartialEq))]
+#[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "ser", derive(serde::Serialize))]
 pub struct QuoteBlock<'a> {
     /// Optional block parameters
@@ -45,8 +94,7 @@ impl QuoteBlock<'_> {
 }
 
 /// Center Block Element
-#[derive(Debug)]
-#[cfg_attr(test, derive(PartialEq))]
+#[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "ser", derive(serde::Serialize))]
 pub struct CenterBlock<'a> {
     /// Optional block parameters
@@ -62,8 +110,7 @@ impl CenterBlock<'_> {
 }
 
 /// Verse Block Element
-#[derive(Debug)]
-#[cfg_attr(test, derive(PartialEq))]
+#[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "ser", derive(serde::Serialize))]
 pub struct VerseBlock<'a> {
     /// Optional block parameters
@@ -79,8 +126,7 @@ impl VerseBlock<'_> {
 }
 
 /// Comment Block Element
-#[derive(Debug)]
-#[cfg_attr(test, derive(PartialEq))]
+#[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "ser", derive(serde::Serialize))]
 pub struct CommentBlock<'a> {
     pub data: Option<Cow<'a, str>>,
@@ -98,8 +144,7 @@ impl CommentBlock<'_> {
 }
 
 /// Example Block Element
-#[derive(Debug)]
-#[cfg_attr(test, derive(PartialEq))]
+#[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "ser", derive(serde::Serialize))]
 pub struct ExampleBlock<'a> {
     pub data: Option<Cow<'a, str>>,
@@ -117,8 +162,7 @@ impl ExampleBlock<'_> {
 }
 
 /// Export Block Element
-#[derive(Debug)]
-#[cfg_attr(test, derive(PartialEq))]
+#[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "ser", derive(serde::Serialize))]
 pub struct ExportBlock<'a> {
     pub data: Cow<'a, str>,
@@ -129,15 +173,14 @@ pub struct ExportBlock<'a> {
 impl ExportBlock<'_> {
     pub fn into_owned(self) -> ExportBlock<'static> {
         ExportBlock {
-            data: self.data.into_owned().into(),
+            data: intern(self.data),
             contents: self.contents.into_owned().into(),
         }
     }
 }
 
 /// Src Block Element
-#[derive(Debug)]
-#[cfg_attr(test, derive(PartialEq))]
+#[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "ser", derive(serde::Serialize))]
 pub struct SourceBlock<'a> {
     ///  Block contents
@@ -150,7 +193,7 @@ pub struct SourceBlock<'a> {
 impl SourceBlock<'_> {
     pub fn into_owned(self) -> SourceBlock<'static> {
         SourceBlock {
-            language: self.language.into_owned().into(),
+            language: intern(self.language),
             arguments: self.arguments.into_owned().into(),
             contents: self.contents.into_owned().into(),
         }
@@ -163,6 +206,79 @@ impl SourceBlock<'_> {
     // TODO: fn retain_labels() -> bool {  }
 }
 
+/// Returns the length of the leading whitespace and commas of a line, if the
+/// rest of the line starts with `*` or `#+` and would be parsed as a headline
+/// or a keyword.
+fn escaped_prefix(line: &str) -> Option<(usize, usize)> {
+    let indent = line.len() - line.trim_start_matches(&[' ', '\t'][..]).len();
+    let commas = line[indent..].len() - line[indent..].trim_start_matches(',').len();
+    let rest = &line[indent + commas..];
+
+    if rest.starts_with('*') || rest.starts_with("#+") {
+        Some((indent, commas))
+    } else {
+        None
+    }
+}
+
+/// Removes the comma protecting lines starting with `*` or `#+` in the
+/// contents of example, export, source and comment blocks.
+///
+/// `\r\n` line endings are converted into `\n`.
+pub(crate) fn unescape_contents(contents: &str) -> Cow<'_, str> {
+    if !contents.contains("\r\n")
+        && !contents
+            .split('\n')
+            .filter_map(escaped_prefix)
+            .any(|(_, commas)| commas > 0)
+    {
+        return Cow::Borrowed(contents);
+    }
+
+    let mut unescaped = String::with_capacity(contents.len());
+    for (i, line) in contents.split('\n').enumerate() {
+        if i > 0 {
+            unescaped.push('\n');
+        }
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        match escaped_prefix(line) {
+            Some((indent, commas)) if commas > 0 => {
+                unescaped.push_str(&line[0..indent]);
+                unescaped.push_str(&line[indent + 1..]);
+            }
+            _ => unescaped.push_str(line),
+        }
+    }
+    Cow::Owned(unescaped)
+}
+
+/// Protects lines starting with `*` or `#+` in the contents of example,
+/// export, source and comment blocks by prefixing them with a comma.
+pub(crate) fn escape_contents(contents: &str) -> Cow<'_, str> {
+    if !contents
+        .split('\n')
+        .any(|line| escaped_prefix(line).is_some())
+    {
+        return Cow::Borrowed(contents);
+    }
+
+    let mut escaped = String::with_capacity(contents.len() + 8);
+    for (i, line) in contents.split('\n').enumerate() {
+        if i > 0 {
+            escaped.push('\n');
+        }
+        match escaped_prefix(line) {
+            Some((indent, _)) => {
+                escaped.push_str(&line[0..indent]);
+                escaped.push(',');
+                escaped.push_str(&line[indent..]);
+            }
+            None => escaped.push_str(line),
+        }
+    }
+    Cow::Owned(escaped)
+}
+
 #[inline]
 pub fn parse_block_element(input: &str) -> Option<(&str, (&str, Option<&str>, &str))> {
     parse_block_element_internal::<()>(input).ok()
@@ -172,11 +288,15 @@ pub fn parse_block_element(input: &str) -> Option<(&str, (&str, Option<&str>, &s
 fn parse_block_element_internal<'a, E: ParseError<&'a str>>(
     input: &'a str,
 ) -> IResult<&'a str, (&'a str, Option<&'a str>, &'a str), E> {
-    let (input, name) = preceded(tag_no_case("#+BEGIN_"), alpha1)(input)?;
+    let (input, name) = preceded(
+        tag_no_case("#+BEGIN_"),
+        take_while1(|c: char| c.is_ascii_alphanumeric() || c == '-' || c == '_'),
+    )(input)?;
     let (input, args) = line(input)?;
     let end_line = format!("#+END_{}", name);
     let (input, contents) =
         take_lines_while(|line| !line.trim().eq_ignore_ascii_case(&end_line))(input);
+    let (input, _) = not_eof(input)?;
     let (input, _) = line(input)?;
 
     Ok((
@@ -229,3 +349,18 @@ console.log('Hello World!');
     );
     // TODO: more testing
 }
+
+#[test]
+fn escape() {
+    assert_eq!(escape_contents("foo\nbar\n"), "foo\nbar\n");
+    assert_eq!(
+        escape_contents("* foo\n  #+END_SRC\n,#+bar\n"),
+        ",* foo\n  ,#+END_SRC\n,,#+bar\n"
+    );
+    assert_eq!(unescape_contents("foo\n,bar\n"), "foo\n,bar\n");
+    assert_eq!(unescape_contents("a\r\n,* b\r\n"), "a\n* b\n");
+    assert_eq!(
+        unescape_contents(",* foo\n  ,#+END_SRC\n,,#+bar\n"),
+        "* foo\n  #+END_SRC\n,#+bar\n"
+    );
+}
@@ -0,0 +1,210 @@
+use std::borrow::Cow;
+
+use memchr::memchr2_iter;
+use nom::{
+    bytes::complete::{tag, take_while1},
+    combinator::opt,
+    error::{ErrorKind, ParseError},
+    sequence::preceded,
+    Err, IResult,
+};
+
+/// Citation Object, e.g. `[cite/t:see @doe2020; @smith, p. 3]`
+///
+/// Prefixes and suffixes are kept as written, including their whitespace.
+#[cfg_attr(feature = "ser", derive(serde::Serialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Citation<'a> {
+    /// Citation style and variant, e.g. `t` in `[cite/t:@key]`
+    #[cfg_attr(feature = "ser", serde(skip_serializing_if = "Option::is_none"))]
+    pub style: Option<Cow<'a, str>>,
+    /// Text before the first reference, followed by a semicolon
+    #[cfg_attr(feature = "ser", serde(skip_serializing_if = "Option::is_none"))]
+    pub prefix: Option<Cow<'a, str>>,
+    /// Text after the last reference, preceded by a semicolon
+    #[cfg_attr(feature = "ser", serde(skip_serializing_if = "Option::is_none"))]
+    pub suffix: Option<Cow<'a, str>>,
+    pub references: Vec<CitationReference<'a>>,
+}
+
+/// Reference of a citation, e.g. `see @doe2020, p. 3`
+#[cfg_attr(feature = "ser", derive(serde::Serialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct CitationReference<'a> {
+    /// Citation key, without the `@`
+    pub key: Cow<'a, str>,
+    #[cfg_attr(feature = "ser", serde(skip_serializing_if = "Option::is_none"))]
+    pub prefix: Option<Cow<'a, str>>,
+    #[cfg_attr(feature = "ser", serde(skip_serializing_if = "Option::is_none"))]
+    pub suffix: Option<Cow<'a, str>>,
+}
+
+impl Citation<'_> {
+    pub(crate) fn parse(input: &str) -> Option<(&str, Citation<'_>)> {
+        parse_citation::<()>(input).ok()
+    }
+
+    /// Returns the keys of the references, in order
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.references
+            .iter()
+            .map(|reference| reference.key.as_ref())
+    }
+
+    pub fn into_owned(self) -> Citation<'static> {
+        Citation {
+            style: self.style.map(Into::into).map(Cow::Owned),
+            prefix: self.prefix.map(Into::into).map(Cow::Owned),
+            suffix: self.suffix.map(Into::into).map(Cow::Owned),
+            references: self
+                .references
+                .into_iter()
+                .map(CitationReference::into_owned)
+                .collect(),
+        }
+    }
+}
+
+impl CitationReference<'_> {
+    pub fn into_owned(self) -> CitationReference<'static> {
+        CitationReference {
+            key: self.key.into_owned().into(),
+            prefix: self.prefix.map(Into::into).map(Cow::Owned),
+            suffix: self.suffix.map(Into::into).map(Cow::Owned),
+        }
+    }
+}
+
+fn is_key_char(c: char) -> bool {
+    c.is_alphanumeric() || "-.:?!`'/*@+|(){}<>&_^$#%~".contains(c)
+}
+
+/// Returns `None` for empty strings
+fn non_empty(s: &str) -> Option<Cow<'_, str>> {
+    if s.is_empty() {
+        None
+    } else {
+        Some(s.into())
+    }
+}
+
+/// Parses a reference like `see @key, p. 3`
+fn parse_reference(input: &str) -> Option<CitationReference<'_>> {
+    let at = input.find('@')?;
+    let rest = &input[at + 1..];
+    let len = rest.find(|c| !is_key_char(c)).unwrap_or(rest.len());
+    if len == 0 {
+        return None;
+    }
+
+    Some(CitationReference {
+        key: rest[0..len].into(),
+        prefix: non_empty(&input[0..at]),
+        suffix: non_empty(&rest[len..]),
+    })
+}
+
+#[inline]
+fn parse_citation<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, Citation<'a>, E> {
+    let (input, _) = tag("[cite")(input)?;
+    let (input, style) = opt(preceded(
+        tag("/"),
+        take_while1(|c: char| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '/'),
+    ))(input)?;
+    let (input, _) = tag(":")(input)?;
+    let (input, contents) = balanced_brackets(input)?;
+    let (input, _) = tag("]")(input)?;
+
+    let error = || Err::Error(E::from_error_kind(input, ErrorKind::Verify));
+
+    let parts: Vec<_> = contents.split(';').collect();
+    let mut prefix = None;
+    let mut suffix = None;
+    let mut references = Vec::new();
+    for (i, part) in parts.iter().enumerate() {
+        match parse_reference(part) {
+            Some(reference) => references.push(reference),
+            None if i == 0 && parts.len() > 1 => prefix = Some((*part).into()),
+            None if i == parts.len() - 1 && !references.is_empty() => suffix = Some((*part).into()),
+            None => return Err(error()),
+        }
+    }
+    if references.is_empty() {
+        return Err(error());
+    }
+
+    Ok((
+        input,
+        Citation {
+            style: style.map(Into::into),
+            prefix,
+            suffix,
+            references,
+        },
+    ))
+}
+
+fn balanced_brackets<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, &'a str, E> {
+    let mut pairs = 1;
+    for i in memchr2_iter(b'[', b']', input.as_bytes()) {
+        if input.as_bytes()[i] == b'[' {
+            pairs += 1;
+        } else if pairs != 1 {
+            pairs -= 1;
+        } else {
+            return Ok((&input[i..], &input[0..i]));
+        }
+    }
+    Err(Err::Error(E::from_error_kind(input, ErrorKind::Tag)))
+}
+
+#[test]
+fn parse() {
+    use nom::error::VerboseError;
+
+    assert_eq!(
+        parse_citation::<VerboseError<&str>>("[cite:@doe2020]"),
+        Ok((
+            "",
+            Citation {
+                style: None,
+                prefix: None,
+                suffix: None,
+                references: vec![CitationReference {
+                    key: "doe2020".into(),
+                    prefix: None,
+                    suffix: None,
+                }],
+            }
+        ))
+    );
+    assert_eq!(
+        parse_citation::<VerboseError<&str>>(
+            "[cite/t/b:See;see @doe2020; @smith, p. 3; and more] x"
+        ),
+        Ok((
+            " x",
+            Citation {
+                style: Some("t/b".into()),
+                prefix: Some("See".into()),
+                suffix: Some(" and more".into()),
+                references: vec![
+                    CitationReference {
+                        key: "doe2020".into(),
+                        prefix: Some("see ".into()),
+                        suffix: None,
+                    },
+                    CitationReference {
+                        key: "smith".into(),
+                        prefix: Some(" ".into()),
+                        suffix: Some(", p. 3".into()),
+                    },
+                ],
+            }
+        ))
+    );
+    assert!(parse_citation::<()>("[cite:no key]").is_err());
+    assert!(parse_citation::<()>("[cite:@a; middle; @b]").is_err());
+    assert!(parse_citation::<()>("[cite:@a").is_err());
+    assert!(parse_citation::<()>("[cite: @]").is_err());
+}
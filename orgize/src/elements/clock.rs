@@ -9,15 +9,14 @@ use nom::{
     IResult,
 };
 
-use crate::elements::timestamp::{parse_inactive, Datetime, Timestamp};
+use crate::elements::timestamp::{parse_inactive, Datetime, Delay, Modifiers, Repeater, Timestamp};
 
 use crate::parsers::eol;
 
 /// Clock Element
-#[cfg_attr(test, derive(PartialEq))]
 #[cfg_attr(feature = "ser", derive(serde::Serialize))]
 #[cfg_attr(feature = "ser", serde(untagged))]
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Clock<'a> {
     /// Closed Clock
     Closed {
@@ -26,9 +25,9 @@ pub enum Clock<'a> {
         /// Time end
         end: Datetime<'a>,
         #[cfg_attr(feature = "ser", serde(skip_serializing_if = "Option::is_none"))]
-        repeater: Option<Cow<'a, str>>,
+        repeater: Option<Repeater>,
         #[cfg_attr(feature = "ser", serde(skip_serializing_if = "Option::is_none"))]
-        delay: Option<Cow<'a, str>>,
+        delay: Option<Delay>,
         /// Clock duration
         duration: Cow<'a, str>,
     },
@@ -37,9 +36,9 @@ pub enum Clock<'a> {
         /// Time start
         start: Datetime<'a>,
         #[cfg_attr(feature = "ser", serde(skip_serializing_if = "Option::is_none"))]
-        repeater: Option<Cow<'a, str>>,
+        repeater: Option<Repeater>,
         #[cfg_attr(feature = "ser", serde(skip_serializing_if = "Option::is_none"))]
-        delay: Option<Cow<'a, str>>,
+        delay: Option<Delay>,
     },
 }
 
@@ -59,8 +58,8 @@ impl Clock<'_> {
             } => Clock::Closed {
                 start: start.into_owned(),
                 end: end.into_owned(),
-                repeater: repeater.map(Into::into).map(Cow::Owned),
-                delay: delay.map(Into::into).map(Cow::Owned),
+                repeater,
+                delay,
                 duration: duration.into_owned().into(),
             },
             Clock::Running {
@@ -69,8 +68,8 @@ impl Clock<'_> {
                 delay,
             } => Clock::Running {
                 start: start.into_owned(),
-                repeater: repeater.map(Into::into).map(Cow::Owned),
-                delay: delay.map(Into::into).map(Cow::Owned),
+                repeater,
+                delay,
             },
         }
     }
@@ -111,8 +110,12 @@ impl Clock<'_> {
             } => Timestamp::InactiveRange {
                 start: start.clone(),
                 end: end.clone(),
-                repeater: repeater.clone(),
-                delay: delay.clone(),
+                modifiers: Modifiers {
+                    repeater: *repeater,
+                    delay: *delay,
+                    delay_first: false,
+                },
+                end_modifiers: Modifiers::default(),
             },
             Clock::Running {
                 start,
@@ -120,8 +123,11 @@ impl Clock<'_> {
                 delay,
             } => Timestamp::Inactive {
                 start: start.clone(),
-                repeater: repeater.clone(),
-                delay: delay.clone(),
+                modifiers: Modifiers {
+                    repeater: *repeater,
+                    delay: *delay,
+                    delay_first: false,
+                },
             },
         }
     }
@@ -136,8 +142,8 @@ fn parse_clock<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, C
         Timestamp::InactiveRange {
             start,
             end,
-            repeater,
-            delay,
+            modifiers,
+            ..
         } => {
             let (input, _) = space0(input)?;
             let (input, _) = tag("=>")(input)?;
@@ -149,24 +155,20 @@ fn parse_clock<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, C
                 Clock::Closed {
                     start,
                     end,
-                    repeater,
-                    delay,
+                    repeater: modifiers.repeater,
+                    delay: modifiers.delay,
                     duration: duration.into(),
                 },
             ))
         }
-        Timestamp::Inactive {
-            start,
-            repeater,
-            delay,
-        } => {
+        Timestamp::Inactive { start, modifiers } => {
             let (input, _) = eol(input)?;
             Ok((
                 input,
                 Clock::Running {
                     start,
-                    repeater,
-                    delay,
+                    repeater: modifiers.repeater,
+                    delay: modifiers.delay,
                 },
             ))
         }
@@ -11,9 +11,8 @@ use nom::{
 };
 
 /// Statistics Cookie Object
-#[cfg_attr(test, derive(PartialEq))]
 #[cfg_attr(feature = "ser", derive(serde::Serialize))]
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Cookie<'a> {
     /// Full cookie value
     pub value: Cow<'a, str>,
@@ -24,6 +23,36 @@ impl Cookie<'_> {
         parse_cookie::<()>(input).ok()
     }
 
+    /// Returns the progress of this cookie, as the number of done items and
+    /// the total number of items
+    ///
+    /// Percentage cookies have a total of `100`, and missing numbers are
+    /// `0`, so `[/]` and `[%]` have no progress.
+    ///
+    /// ```rust
+    /// use orgize::elements::Cookie;
+    ///
+    /// let progress = |value: &str| Cookie { value: value.into() }.progress();
+    ///
+    /// assert_eq!(progress("[3/7]"), (3, 7));
+    /// assert_eq!(progress("[40%]"), (40, 100));
+    /// assert_eq!(progress("[/]"), (0, 0));
+    /// ```
+    pub fn progress(&self) -> (u64, u64) {
+        let value = self.value.trim_start_matches('[').trim_end_matches(']');
+        let number = |s: &str| s.parse().unwrap_or(0);
+
+        match value.strip_suffix('%') {
+            Some(percent) => (number(percent), 100),
+            None => {
+                let mut parts = value.splitn(2, '/');
+                let done = parts.next().map(number).unwrap_or(0);
+                let total = parts.next().map(number).unwrap_or(0);
+                (done, total)
+            }
+        }
+    }
+
     pub fn into_owned(self) -> Cookie<'static> {
         Cookie {
             value: self.value.into_owned().into(),
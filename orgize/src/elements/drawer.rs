@@ -1,4 +1,5 @@
 use std::borrow::Cow;
+use std::collections::HashMap;
 
 use nom::{
     bytes::complete::{tag, take_while1},
@@ -7,29 +8,121 @@ use nom::{
     IResult,
 };
 
-use crate::parsers::{eol, line, take_lines_while};
+use crate::elements::{
+    title::{
+        fold_properties, parse_number_value, parse_property_lines, parse_timestamp_value,
+        property_entries,
+    },
+    Timestamp,
+};
+use crate::intern::intern;
+use crate::parsers::{eol, line, not_eof, take_lines_while};
+
+/// How the contents of a drawer or a special block are parsed, see
+/// `ParseConfig::drawer_modes` and `ParseConfig::block_modes`
+#[cfg_attr(feature = "ser", derive(serde::Serialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ContentMode {
+    /// Parsed into child elements, the default
+    #[default]
+    Parse,
+    /// Kept as is in `contents`, without child elements
+    Raw,
+    /// Parsed into `properties` from `:KEY: value` lines, like a property
+    /// drawer, and kept as is in `contents`, without child elements
+    Properties,
+}
 
 /// Drawer Element
-#[cfg_attr(test, derive(PartialEq))]
 #[cfg_attr(feature = "ser", derive(serde::Serialize))]
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Drawer<'a> {
     /// Drawer name
     pub name: Cow<'a, str>,
+    /// How the drawer contents were parsed
+    pub mode: ContentMode,
+    /// Drawer contents, unless they were parsed into child elements
+    pub contents: Option<Cow<'a, str>>,
+    /// Drawer properties, with `ContentMode::Properties`
+    pub properties: HashMap<Cow<'a, str>, Cow<'a, str>>,
 }
 
-impl Drawer<'_> {
+impl<'a> Drawer<'a> {
     pub(crate) fn parse(input: &str) -> Option<(&str, (Drawer<'_>, &str))> {
         parse_drawer::<()>(input).ok()
     }
 
+    /// Returns the drawer holding `contents` parsed with `mode`, which keeps
+    /// them as child elements with `ContentMode::Parse`
+    pub(crate) fn with_contents(self, mode: ContentMode, contents: &'a str) -> Drawer<'a> {
+        let (contents, properties) = mode_contents(mode, contents.into(), contents);
+        Drawer {
+            mode,
+            contents,
+            properties,
+            ..self
+        }
+    }
+
+    /// Returns the value of the property `key`, compared case-insensitively
+    pub fn property(&self, key: &str) -> Option<&str> {
+        find_property(&self.properties, key)
+    }
+
+    /// Returns the value of the property `key` as a timestamp, see
+    /// `Title::property_timestamp`
+    pub fn property_timestamp(&self, key: &str) -> Option<Timestamp<'_>> {
+        self.property(key).and_then(parse_timestamp_value)
+    }
+
+    /// Returns the value of the property `key` as a number, see
+    /// `Title::property_number`
+    pub fn property_number(&self, key: &str) -> Option<f64> {
+        self.property(key).and_then(parse_number_value)
+    }
+
     pub fn into_owned(self) -> Drawer<'static> {
         Drawer {
-            name: self.name.into_owned().into(),
+            name: intern(self.name),
+            mode: self.mode,
+            contents: self.contents.map(Into::into).map(Cow::Owned),
+            properties: self
+                .properties
+                .into_iter()
+                .map(|(k, v)| (intern(k), v.into_owned().into()))
+                .collect(),
         }
     }
 }
 
+/// Returns the `contents` and `properties` of an element whose contents
+/// `text` are parsed with `mode`, `contents` being `text` once unescaped
+pub(crate) fn mode_contents<'a>(
+    mode: ContentMode,
+    contents: Cow<'a, str>,
+    text: &'a str,
+) -> (Option<Cow<'a, str>>, HashMap<Cow<'a, str>, Cow<'a, str>>) {
+    match mode {
+        ContentMode::Parse => (None, HashMap::new()),
+        ContentMode::Raw => (Some(contents), HashMap::new()),
+        ContentMode::Properties => (
+            Some(contents),
+            fold_properties(property_entries(&parse_property_lines(text))),
+        ),
+    }
+}
+
+/// Returns the value of `key` in `properties`, compared case-insensitively
+pub(crate) fn find_property<'a>(
+    properties: &'a HashMap<Cow<'_, str>, Cow<'_, str>>,
+    key: &str,
+) -> Option<&'a str> {
+    properties
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case(key))
+        .map(|(_, value)| value.as_ref())
+}
+
 #[inline]
 pub fn parse_drawer<'a, E: ParseError<&'a str>>(
     input: &'a str,
@@ -42,9 +135,21 @@ pub fn parse_drawer<'a, E: ParseError<&'a str>>(
     let (input, _) = eol(input)?;
     let (input, contents) =
         take_lines_while(|line| !line.trim().eq_ignore_ascii_case(":END:"))(input);
+    let (input, _) = not_eof(input)?;
     let (input, _) = line(input)?;
 
-    Ok((input, (Drawer { name: name.into() }, contents)))
+    Ok((
+        input,
+        (
+            Drawer {
+                name: name.into(),
+                mode: ContentMode::Parse,
+                contents: None,
+                properties: HashMap::new(),
+            },
+            contents,
+        ),
+    ))
 }
 
 #[test]
@@ -57,7 +162,10 @@ fn parse() {
             "",
             (
                 Drawer {
-                    name: "PROPERTIES".into()
+                    name: "PROPERTIES".into(),
+                    mode: ContentMode::Parse,
+                    contents: None,
+                    properties: HashMap::new(),
                 },
                 "  :CUSTOM_ID: id\n"
             )
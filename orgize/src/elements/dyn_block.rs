@@ -7,12 +7,12 @@ use nom::{
     IResult,
 };
 
-use crate::parsers::{line, take_lines_while};
+use crate::intern::intern;
+use crate::parsers::{line, not_eof, take_lines_while};
 
 /// Dynamic Block Element
-#[cfg_attr(test, derive(PartialEq))]
 #[cfg_attr(feature = "ser", derive(serde::Serialize))]
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct DynBlock<'a> {
     /// Block name
     pub block_name: Cow<'a, str>,
@@ -28,7 +28,7 @@ impl DynBlock<'_> {
 
     pub fn into_owned(self) -> DynBlock<'static> {
         DynBlock {
-            block_name: self.block_name.into_owned().into(),
+            block_name: intern(self.block_name),
             arguments: self.arguments.map(Into::into).map(Cow::Owned),
         }
     }
@@ -44,6 +44,7 @@ fn parse_dyn_block<'a, E: ParseError<&'a str>>(
     let (input, args) = line(input)?;
     let (input, contents) =
         take_lines_while(|line| !line.trim().eq_ignore_ascii_case("#+END:"))(input);
+    let (input, _) = not_eof(input)?;
     let (input, _) = line(input)?;
 
     Ok((
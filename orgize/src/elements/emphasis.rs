@@ -11,7 +11,8 @@ pub(crate) fn parse_emphasis(text: &str, marker: u8) -> Option<(&str, &str)> {
         return None;
     }
 
-    for i in memchr_iter(marker, bytes).skip(1) {
+    // the closing marker must be preceded by at least one character
+    for i in memchr_iter(marker, bytes).skip(1).filter(|&i| i > 1) {
         if count(&bytes[1..i], b'\n') >= 2 {
             break;
         } else if validate_marker(i, text) {
@@ -45,4 +46,6 @@ fn parse() {
     assert_eq!(parse_emphasis("*bold *", b'*'), None);
     assert_eq!(parse_emphasis("* bold*", b'*'), None);
     assert_eq!(parse_emphasis("*b\nol\nd*", b'*'), None);
+    assert_eq!(parse_emphasis("//::", b'/'), None);
+    assert_eq!(parse_emphasis("**a*", b'*'), Some(("", "*a")));
 }
@@ -0,0 +1,480 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+/// Entity Object, e.g. `\alpha` or `\nbsp{}`
+#[cfg_attr(feature = "ser", derive(serde::Serialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entity<'a> {
+    /// Entity name, without the backslash, e.g. `alpha`
+    pub name: Cow<'a, str>,
+    /// Replacement in html, e.g. `&alpha;`
+    pub html: &'static str,
+    /// Replacement in plain text, e.g. `α`
+    pub utf8: &'static str,
+    /// Whether the name is followed by `{}`, which ends it like in
+    /// `\alpha{}beta`
+    #[cfg_attr(feature = "ser", serde(skip_serializing_if = "std::ops::Not::not"))]
+    pub braces: bool,
+}
+
+/// An entry of [`ENTITIES`]
+///
+/// [`ENTITIES`]: static.ENTITIES.html
+#[cfg_attr(feature = "ser", derive(serde::Serialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EntityDefinition {
+    /// Entity name, without the backslash, e.g. `alpha`
+    pub name: &'static str,
+    /// Replacement in html, e.g. `&alpha;`
+    pub html: &'static str,
+    /// Replacement in plain text, e.g. `α`
+    pub utf8: &'static str,
+}
+
+impl EntityDefinition {
+    /// Looks up an entity of [`ENTITIES`] by name, case-sensitively
+    ///
+    /// ```rust
+    /// use orgize::elements::EntityDefinition;
+    ///
+    /// assert_eq!(EntityDefinition::get("rightarrow").unwrap().utf8, "→");
+    /// assert_eq!(EntityDefinition::get("nbsp").unwrap().html, "&nbsp;");
+    /// assert_eq!(EntityDefinition::get("foo"), None);
+    /// ```
+    ///
+    /// [`ENTITIES`]: static.ENTITIES.html
+    pub fn get(name: &str) -> Option<&'static EntityDefinition> {
+        ENTITY_MAP.get(name).copied()
+    }
+}
+
+impl Entity<'_> {
+    /// Parses an entity at the start of `input`, which must be in
+    /// [`ENTITIES`]
+    ///
+    /// [`ENTITIES`]: static.ENTITIES.html
+    pub(crate) fn parse(input: &str) -> Option<(&str, Entity<'_>)> {
+        let rest = input.strip_prefix('\\')?;
+        let letters = rest.bytes().take_while(u8::is_ascii_alphabetic).count();
+        // names like `there4`, `sup2` or `frac12` end with digits
+        let digits = rest[letters..]
+            .bytes()
+            .take_while(u8::is_ascii_digit)
+            .count();
+        let (name, definition) = [letters + digits, letters]
+            .iter()
+            .find_map(|&len| EntityDefinition::get(&rest[0..len]).map(|d| (&rest[0..len], d)))?;
+
+        let tail = &rest[name.len()..];
+        let (tail, braces) = match tail.strip_prefix("{}") {
+            Some(tail) => (tail, true),
+            None if tail.starts_with(char::is_alphabetic) => return None,
+            None => (tail, false),
+        };
+
+        Some((
+            tail,
+            Entity {
+                name: name.into(),
+                html: definition.html,
+                utf8: definition.utf8,
+                braces,
+            },
+        ))
+    }
+
+    pub fn into_owned(self) -> Entity<'static> {
+        Entity {
+            name: self.name.into_owned().into(),
+            html: self.html,
+            utf8: self.utf8,
+            braces: self.braces,
+        }
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref ENTITY_MAP: HashMap<&'static str, &'static EntityDefinition> =
+        ENTITIES.iter().map(|entity| (entity.name, entity)).collect();
+}
+
+macro_rules! entities {
+    ($($name:expr, $html:expr, $utf8:expr;)*) => {
+        &[$(EntityDefinition { name: $name, html: $html, utf8: $utf8 },)*]
+    };
+}
+
+/// The entities of org-mode, from `org-entities`, with their html and plain
+/// text replacements
+///
+/// Exporters can map them to other replacements by name, e.g. to LaTeX
+/// commands.
+pub static ENTITIES: &[EntityDefinition] = entities! {
+    // letters with diacritics
+    "Agrave", "&Agrave;", "À";
+    "agrave", "&agrave;", "à";
+    "Aacute", "&Aacute;", "Á";
+    "aacute", "&aacute;", "á";
+    "Acirc", "&Acirc;", "Â";
+    "acirc", "&acirc;", "â";
+    "Atilde", "&Atilde;", "Ã";
+    "atilde", "&atilde;", "ã";
+    "Auml", "&Auml;", "Ä";
+    "auml", "&auml;", "ä";
+    "Aring", "&Aring;", "Å";
+    "AA", "&Aring;", "Å";
+    "aring", "&aring;", "å";
+    "AElig", "&AElig;", "Æ";
+    "aelig", "&aelig;", "æ";
+    "Ccedil", "&Ccedil;", "Ç";
+    "ccedil", "&ccedil;", "ç";
+    "Egrave", "&Egrave;", "È";
+    "egrave", "&egrave;", "è";
+    "Eacute", "&Eacute;", "É";
+    "eacute", "&eacute;", "é";
+    "Ecirc", "&Ecirc;", "Ê";
+    "ecirc", "&ecirc;", "ê";
+    "Euml", "&Euml;", "Ë";
+    "euml", "&euml;", "ë";
+    "Igrave", "&Igrave;", "Ì";
+    "igrave", "&igrave;", "ì";
+    "Iacute", "&Iacute;", "Í";
+    "iacute", "&iacute;", "í";
+    "Icirc", "&Icirc;", "Î";
+    "icirc", "&icirc;", "î";
+    "Iuml", "&Iuml;", "Ï";
+    "iuml", "&iuml;", "ï";
+    "Ntilde", "&Ntilde;", "Ñ";
+    "ntilde", "&ntilde;", "ñ";
+    "Ograve", "&Ograve;", "Ò";
+    "ograve", "&ograve;", "ò";
+    "Oacute", "&Oacute;", "Ó";
+    "oacute", "&oacute;", "ó";
+    "Ocirc", "&Ocirc;", "Ô";
+    "ocirc", "&ocirc;", "ô";
+    "Otilde", "&Otilde;", "Õ";
+    "otilde", "&otilde;", "õ";
+    "Ouml", "&Ouml;", "Ö";
+    "ouml", "&ouml;", "ö";
+    "Oslash", "&Oslash;", "Ø";
+    "oslash", "&oslash;", "ø";
+    "OElig", "&OElig;", "Œ";
+    "oelig", "&oelig;", "œ";
+    "Scaron", "&Scaron;", "Š";
+    "scaron", "&scaron;", "š";
+    "szlig", "&szlig;", "ß";
+    "Ugrave", "&Ugrave;", "Ù";
+    "ugrave", "&ugrave;", "ù";
+    "Uacute", "&Uacute;", "Ú";
+    "uacute", "&uacute;", "ú";
+    "Ucirc", "&Ucirc;", "Û";
+    "ucirc", "&ucirc;", "û";
+    "Uuml", "&Uuml;", "Ü";
+    "uuml", "&uuml;", "ü";
+    "Yacute", "&Yacute;", "Ý";
+    "yacute", "&yacute;", "ý";
+    "Yuml", "&Yuml;", "Ÿ";
+    "yuml", "&yuml;", "ÿ";
+    "fnof", "&fnof;", "ƒ";
+    "real", "&real;", "ℜ";
+    "image", "&image;", "ℑ";
+    "weierp", "&weierp;", "℘";
+    "ell", "&ell;", "ℓ";
+    "imath", "&imath;", "ı";
+    "jmath", "&#567;", "ȷ";
+    "ETH", "&ETH;", "Ð";
+    "eth", "&eth;", "ð";
+    "THORN", "&THORN;", "Þ";
+    "thorn", "&thorn;", "þ";
+    // greek
+    "Alpha", "&Alpha;", "Α";
+    "alpha", "&alpha;", "α";
+    "Beta", "&Beta;", "Β";
+    "beta", "&beta;", "β";
+    "Gamma", "&Gamma;", "Γ";
+    "gamma", "&gamma;", "γ";
+    "Delta", "&Delta;", "Δ";
+    "delta", "&delta;", "δ";
+    "Epsilon", "&Epsilon;", "Ε";
+    "epsilon", "&epsilon;", "ε";
+    "varepsilon", "&epsilon;", "ε";
+    "Zeta", "&Zeta;", "Ζ";
+    "zeta", "&zeta;", "ζ";
+    "Eta", "&Eta;", "Η";
+    "eta", "&eta;", "η";
+    "Theta", "&Theta;", "Θ";
+    "theta", "&theta;", "θ";
+    "thetasym", "&thetasym;", "ϑ";
+    "vartheta", "&thetasym;", "ϑ";
+    "Iota", "&Iota;", "Ι";
+    "iota", "&iota;", "ι";
+    "Kappa", "&Kappa;", "Κ";
+    "kappa", "&kappa;", "κ";
+    "Lambda", "&Lambda;", "Λ";
+    "lambda", "&lambda;", "λ";
+    "Mu", "&Mu;", "Μ";
+    "mu", "&mu;", "μ";
+    "Nu", "&Nu;", "Ν";
+    "nu", "&nu;", "ν";
+    "Xi", "&Xi;", "Ξ";
+    "xi", "&xi;", "ξ";
+    "Omicron", "&Omicron;", "Ο";
+    "omicron", "&omicron;", "ο";
+    "Pi", "&Pi;", "Π";
+    "pi", "&pi;", "π";
+    "piv", "&piv;", "ϖ";
+    "varpi", "&piv;", "ϖ";
+    "Rho", "&Rho;", "Ρ";
+    "rho", "&rho;", "ρ";
+    "Sigma", "&Sigma;", "Σ";
+    "sigma", "&sigma;", "σ";
+    "sigmaf", "&sigmaf;", "ς";
+    "varsigma", "&sigmaf;", "ς";
+    "Tau", "&Tau;", "Τ";
+    "tau", "&tau;", "τ";
+    "Upsilon", "&Upsilon;", "Υ";
+    "upsih", "&upsih;", "ϒ";
+    "upsilon", "&upsilon;", "υ";
+    "Phi", "&Phi;", "Φ";
+    "phi", "&phi;", "ɸ";
+    "varphi", "&varphi;", "φ";
+    "Chi", "&Chi;", "Χ";
+    "chi", "&chi;", "χ";
+    "Psi", "&Psi;", "Ψ";
+    "psi", "&psi;", "ψ";
+    "Omega", "&Omega;", "Ω";
+    "omega", "&omega;", "ω";
+    "ohm", "&ohm;", "Ω";
+    "alefsym", "&alefsym;", "ℵ";
+    "aleph", "&aleph;", "ℵ";
+    "hbar", "&hbar;", "ℏ";
+    // dashes and quotes
+    "ndash", "&ndash;", "–";
+    "mdash", "&mdash;", "—";
+    "hyphen", "&#8208;", "‐";
+    "quot", "&quot;", "\"";
+    "acute", "&acute;", "´";
+    "ldquo", "&ldquo;", "“";
+    "rdquo", "&rdquo;", "”";
+    "bdquo", "&bdquo;", "„";
+    "lsquo", "&lsquo;", "‘";
+    "rsquo", "&rsquo;", "’";
+    "sbquo", "&sbquo;", "‚";
+    "laquo", "&laquo;", "«";
+    "raquo", "&raquo;", "»";
+    "lsaquo", "&lsaquo;", "‹";
+    "rsaquo", "&rsaquo;", "›";
+    // other punctuation
+    "dots", "&hellip;", "…";
+    "hellip", "&hellip;", "…";
+    "middot", "&middot;", "·";
+    "iexcl", "&iexcl;", "¡";
+    "iquest", "&iquest;", "¿";
+    "shy", "&shy;", "\u{ad}";
+    "bull", "&bull;", "•";
+    "bullet", "&bull;", "•";
+    "star", "*", "⋆";
+    "sect", "&sect;", "§";
+    "S", "&sect;", "§";
+    "para", "&para;", "¶";
+    "P", "&para;", "¶";
+    "dagger", "&dagger;", "†";
+    "dag", "&dagger;", "†";
+    "Dagger", "&Dagger;", "‡";
+    "ddag", "&Dagger;", "‡";
+    "vert", "&vert;", "|";
+    "brvbar", "&brvbar;", "¦";
+    "vbar", "|", "|";
+    "amp", "&amp;", "&";
+    "lt", "&lt;", "<";
+    "gt", "&gt;", ">";
+    "tilde", "&tilde;", "~";
+    "slash", "/", "/";
+    "plus", "+", "+";
+    "under", "_", "_";
+    "equal", "=", "=";
+    "asciicirc", "&circ;", "^";
+    "macr", "&macr;", "¯";
+    "uml", "&uml;", "¨";
+    "cedil", "&cedil;", "¸";
+    "ordf", "&ordf;", "ª";
+    "ordm", "&ordm;", "º";
+    "oline", "&oline;", "‾";
+    "prime", "&prime;", "′";
+    "Prime", "&Prime;", "″";
+    "frasl", "&frasl;", "⁄";
+    "checkmark", "&#10003;", "✓";
+    // spaces
+    "nbsp", "&nbsp;", "\u{a0}";
+    "ensp", "&ensp;", "\u{2002}";
+    "emsp", "&emsp;", "\u{2003}";
+    "thinsp", "&thinsp;", "\u{2009}";
+    "zwnj", "&zwnj;", "\u{200c}";
+    "zwj", "&zwj;", "\u{200d}";
+    "lrm", "&lrm;", "\u{200e}";
+    "rlm", "&rlm;", "\u{200f}";
+    // currency and units
+    "cent", "&cent;", "¢";
+    "pound", "&pound;", "£";
+    "curren", "&curren;", "¤";
+    "yen", "&yen;", "¥";
+    "euro", "&euro;", "€";
+    "EUR", "&euro;", "€";
+    "copy", "&copy;", "©";
+    "reg", "&reg;", "®";
+    "trade", "&trade;", "™";
+    "deg", "&deg;", "°";
+    "micro", "&micro;", "µ";
+    "permil", "&permil;", "‰";
+    "sup1", "&sup1;", "¹";
+    "sup2", "&sup2;", "²";
+    "sup3", "&sup3;", "³";
+    "frac14", "&frac14;", "¼";
+    "frac12", "&frac12;", "½";
+    "frac34", "&frac34;", "¾";
+    // arithmetic and logic
+    "plusmn", "&plusmn;", "±";
+    "pm", "&plusmn;", "±";
+    "times", "&times;", "×";
+    "divide", "&divide;", "÷";
+    "div", "&divide;", "÷";
+    "minus", "&minus;", "−";
+    "mp", "&#8723;", "∓";
+    "lowast", "&lowast;", "∗";
+    "ast", "&lowast;", "∗";
+    "sdot", "&sdot;", "⋅";
+    "cdot", "&sdot;", "⋅";
+    "cdots", "&ctdot;", "⋯";
+    "radic", "&radic;", "√";
+    "sum", "&sum;", "∑";
+    "prod", "&prod;", "∏";
+    "int", "&int;", "∫";
+    "infin", "&infin;", "∞";
+    "infty", "&infin;", "∞";
+    "prop", "&prop;", "∝";
+    "propto", "&prop;", "∝";
+    "not", "&not;", "¬";
+    "neg", "&not;", "¬";
+    "and", "&and;", "∧";
+    "land", "&and;", "∧";
+    "wedge", "&and;", "∧";
+    "or", "&or;", "∨";
+    "lor", "&or;", "∨";
+    "vee", "&or;", "∨";
+    "forall", "&forall;", "∀";
+    "exist", "&exist;", "∃";
+    "exists", "&exist;", "∃";
+    "nexist", "&nexist;", "∄";
+    "nexists", "&nexist;", "∄";
+    "empty", "&empty;", "∅";
+    "emptyset", "&empty;", "∅";
+    "part", "&part;", "∂";
+    "partial", "&part;", "∂";
+    "nabla", "&nabla;", "∇";
+    "there4", "&there4;", "∴";
+    "oplus", "&oplus;", "⊕";
+    "otimes", "&otimes;", "⊗";
+    "perp", "&perp;", "⊥";
+    "ang", "&ang;", "∠";
+    "angle", "&ang;", "∠";
+    "lceil", "&lceil;", "⌈";
+    "rceil", "&rceil;", "⌉";
+    "lfloor", "&lfloor;", "⌊";
+    "rfloor", "&rfloor;", "⌋";
+    "lang", "&lang;", "⟨";
+    "rang", "&rang;", "⟩";
+    "langle", "&lang;", "⟨";
+    "rangle", "&rang;", "⟩";
+    // relations and sets
+    "sim", "&sim;", "∼";
+    "cong", "&cong;", "≅";
+    "simeq", "&cong;", "≅";
+    "asymp", "&asymp;", "≈";
+    "approx", "&asymp;", "≈";
+    "ne", "&ne;", "≠";
+    "neq", "&ne;", "≠";
+    "equiv", "&equiv;", "≡";
+    "le", "&le;", "≤";
+    "leq", "&le;", "≤";
+    "ge", "&ge;", "≥";
+    "geq", "&ge;", "≥";
+    "ll", "&Lt;", "≪";
+    "gg", "&Gt;", "≫";
+    "isin", "&isin;", "∈";
+    "in", "&isin;", "∈";
+    "notin", "&notin;", "∉";
+    "ni", "&ni;", "∋";
+    "cap", "&cap;", "∩";
+    "cup", "&cup;", "∪";
+    "sub", "&sub;", "⊂";
+    "subset", "&sub;", "⊂";
+    "sup", "&sup;", "⊃";
+    "supset", "&sup;", "⊃";
+    "nsub", "&nsub;", "⊄";
+    "sube", "&sube;", "⊆";
+    "subseteq", "&sube;", "⊆";
+    "supe", "&supe;", "⊇";
+    "supseteq", "&supe;", "⊇";
+    "setminus", "&setminus;", "∖";
+    "mid", "&mid;", "∣";
+    "parallel", "&parallel;", "∥";
+    // arrows
+    "larr", "&larr;", "←";
+    "leftarrow", "&larr;", "←";
+    "gets", "&larr;", "←";
+    "rarr", "&rarr;", "→";
+    "rightarrow", "&rarr;", "→";
+    "to", "&rarr;", "→";
+    "uarr", "&uarr;", "↑";
+    "uparrow", "&uarr;", "↑";
+    "darr", "&darr;", "↓";
+    "downarrow", "&darr;", "↓";
+    "harr", "&harr;", "↔";
+    "leftrightarrow", "&harr;", "↔";
+    "crarr", "&crarr;", "↵";
+    "hookleftarrow", "&crarr;", "↵";
+    "lArr", "&lArr;", "⇐";
+    "Leftarrow", "&lArr;", "⇐";
+    "rArr", "&rArr;", "⇒";
+    "Rightarrow", "&rArr;", "⇒";
+    "uArr", "&uArr;", "⇑";
+    "Uparrow", "&uArr;", "⇑";
+    "dArr", "&dArr;", "⇓";
+    "Downarrow", "&dArr;", "⇓";
+    "hArr", "&hArr;", "⇔";
+    "Leftrightarrow", "&hArr;", "⇔";
+    "iff", "&hArr;", "⇔";
+    "mapsto", "&mapsto;", "↦";
+    "leadsto", "&zigrarr;", "↝";
+    // shapes
+    "loz", "&loz;", "◊";
+    "spades", "&spades;", "♠";
+    "clubs", "&clubs;", "♣";
+    "hearts", "&hearts;", "♥";
+    "heartsuit", "&heartsuit;", "♥";
+    "diams", "&diams;", "♦";
+    "diamondsuit", "&diams;", "♦";
+    "smile", "&smile;", "⌣";
+    "frown", "&frown;", "⌢";
+};
+
+#[test]
+fn parse() {
+    let entity = |input| Entity::parse(input).map(|(tail, e)| (tail, e.name, e.braces));
+
+    assert_eq!(entity("\\alpha"), Some(("", "alpha".into(), false)));
+    assert_eq!(entity("\\alpha2 b"), Some(("2 b", "alpha".into(), false)));
+    assert_eq!(entity("\\nbsp{}x"), Some(("x", "nbsp".into(), true)));
+    assert_eq!(entity("\\frac12."), Some((".", "frac12".into(), false)));
+    assert_eq!(entity("\\sup2"), Some(("", "sup2".into(), false)));
+    assert_eq!(entity("\\to-do"), Some(("-do", "to".into(), false)));
+    assert_eq!(entity("\\alphabet"), None);
+    assert_eq!(entity("\\alphaé"), None);
+    assert_eq!(entity("\\foo"), None);
+    assert_eq!(entity("alpha"), None);
+
+    let mut names = std::collections::HashSet::new();
+    for entity in ENTITIES {
+        assert!(names.insert(entity.name), "duplicate {}", entity.name);
+    }
+}
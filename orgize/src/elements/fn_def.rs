@@ -10,9 +10,8 @@ use nom::{
 use crate::parsers::line;
 
 /// Footnote Definition Element
-#[cfg_attr(test, derive(PartialEq))]
 #[cfg_attr(feature = "ser", derive(serde::Serialize))]
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct FnDef<'a> {
     /// Footnote label, used for refrence
     pub label: Cow<'a, str>,
@@ -40,6 +39,8 @@ fn parse_fn_def<'a, E: ParseError<&'a str>>(
         tag("]"),
     )(input)?;
     let (input, content) = line(input)?;
+    // the contents start at the first non-blank character
+    let content = content.trim_start_matches(&[' ', '\t'][..]);
 
     Ok((
         input,
@@ -58,7 +59,7 @@ fn parse() {
 
     assert_eq!(
         parse_fn_def::<VerboseError<&str>>("[fn:1] https://orgmode.org"),
-        Ok(("", (FnDef { label: "1".into() }, " https://orgmode.org")))
+        Ok(("", (FnDef { label: "1".into() }, "https://orgmode.org")))
     );
     assert_eq!(
         parse_fn_def::<VerboseError<&str>>("[fn:word_1] https://orgmode.org"),
@@ -68,7 +69,7 @@ fn parse() {
                 FnDef {
                     label: "word_1".into()
                 },
-                " https://orgmode.org"
+                "https://orgmode.org"
             )
         ))
     );
@@ -80,7 +81,7 @@ fn parse() {
                 FnDef {
                     label: "WORD-1".into()
                 },
-                " https://orgmode.org"
+                "https://orgmode.org"
             )
         ))
     );
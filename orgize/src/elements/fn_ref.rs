@@ -10,9 +10,8 @@ use nom::{
 };
 
 /// Footnote Reference Element
-#[cfg_attr(test, derive(PartialEq))]
 #[cfg_attr(feature = "ser", derive(serde::Serialize))]
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct FnRef<'a> {
     /// Footnote label
     pub label: Cow<'a, str>,
@@ -8,10 +8,11 @@ use nom::{
     IResult,
 };
 
+use crate::intern::intern;
+
 /// Inline Src Block Object
-#[cfg_attr(test, derive(PartialEq))]
 #[cfg_attr(feature = "ser", derive(serde::Serialize))]
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct InlineSrc<'a> {
     /// Language of the code
     pub lang: Cow<'a, str>,
@@ -29,7 +30,7 @@ impl InlineSrc<'_> {
 
     pub fn into_owned(self) -> InlineSrc<'static> {
         InlineSrc {
-            lang: self.lang.into_owned().into(),
+            lang: intern(self.lang),
             options: self.options.map(Into::into).map(Cow::Owned),
             body: self.body.into_owned().into(),
         }
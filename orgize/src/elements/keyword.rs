@@ -8,35 +8,94 @@ use nom::{
     IResult,
 };
 
+use crate::intern::intern;
 use crate::parsers::line;
 
 /// Keyword Elemenet
-#[cfg_attr(test, derive(PartialEq))]
 #[cfg_attr(feature = "ser", derive(serde::Serialize))]
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Keyword<'a> {
     /// Keyword name
     pub key: Cow<'a, str>,
     #[cfg_attr(feature = "ser", serde(skip_serializing_if = "Option::is_none"))]
     pub optional: Option<Cow<'a, str>>,
-    /// Keyword value
+    /// Keyword value, without leading and trailing whitespace
     pub value: Cow<'a, str>,
 }
 
 impl Keyword<'_> {
+    /// Returns `true` if the key of this keyword is an in-buffer setting or an
+    /// affiliated keyword known to org-mode, like `TITLE` or `ATTR_HTML`
+    pub fn is_known(&self) -> bool {
+        is_known_keyword(&self.key)
+    }
+
     pub fn into_owned(self) -> Keyword<'static> {
         Keyword {
-            key: self.key.into_owned().into(),
+            key: intern(self.key),
             optional: self.optional.map(Into::into).map(Cow::Owned),
             value: self.value.into_owned().into(),
         }
     }
 }
 
+/// Keys of known keywords, in uppercase
+///
+/// `CAPTION`, `CALL` and `TBLFM` are parsed as other elements.
+const KNOWN_KEYWORDS: &[&str] = &[
+    "ARCHIVE",
+    "AUTHOR",
+    "BIBLIOGRAPHY",
+    "CATEGORY",
+    "CITE_EXPORT",
+    "COLUMNS",
+    "CONSTANTS",
+    "CREATOR",
+    "DATE",
+    "DESCRIPTION",
+    "EMAIL",
+    "EXCLUDE_TAGS",
+    "EXPORT_FILE_NAME",
+    "FILETAGS",
+    "HEADER",
+    "HTML",
+    "HTML_HEAD",
+    "HTML_HEAD_EXTRA",
+    "INCLUDE",
+    "KEYWORDS",
+    "LANGUAGE",
+    "LATEX",
+    "LATEX_CLASS",
+    "LATEX_CLASS_OPTIONS",
+    "LATEX_HEADER",
+    "LINK",
+    "NAME",
+    "OPTIONS",
+    "PLOT",
+    "PRINT_BIBLIOGRAPHY",
+    "PRIORITIES",
+    "PROPERTY",
+    "RESULTS",
+    "SELECT_TAGS",
+    "SEQ_TODO",
+    "SETUPFILE",
+    "STARTUP",
+    "SUBTITLE",
+    "TAGS",
+    "TITLE",
+    "TOC",
+    "TODO",
+    "TYP_TODO",
+];
+
+pub(crate) fn is_known_keyword(key: &str) -> bool {
+    let key = key.to_ascii_uppercase();
+    key.starts_with("ATTR_") || KNOWN_KEYWORDS.contains(&&*key)
+}
+
 /// Babel Call Elemenet
-#[cfg_attr(test, derive(PartialEq))]
 #[cfg_attr(feature = "ser", derive(serde::Serialize))]
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct BabelCall<'a> {
     pub value: Cow<'a, str>,
 }
@@ -49,6 +108,26 @@ impl BabelCall<'_> {
     }
 }
 
+/// Caption Element, parsed from a `#+CAPTION:` keyword
+///
+/// Its children are the objects of the caption, e.g. `*important*` in
+/// `#+CAPTION: The *important* figure`.
+#[cfg_attr(feature = "ser", derive(serde::Serialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Caption<'a> {
+    /// Short caption, `short` in `#+CAPTION[short]: long`
+    #[cfg_attr(feature = "ser", serde(skip_serializing_if = "Option::is_none"))]
+    pub short: Option<Cow<'a, str>>,
+}
+
+impl Caption<'_> {
+    pub fn into_owned(self) -> Caption<'static> {
+        Caption {
+            short: self.short.map(Into::into).map(Cow::Owned),
+        }
+    }
+}
+
 #[inline]
 pub fn parse_keyword(input: &str) -> Option<(&str, (&str, Option<&str>, &str))> {
     parse_keyword_internal::<()>(input).ok()
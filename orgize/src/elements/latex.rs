@@ -0,0 +1,208 @@
+use std::borrow::Cow;
+use std::iter::once;
+
+use memchr::memchr_iter;
+
+/// LaTeX Fragment Object, or LaTeX Environment Element
+#[cfg_attr(feature = "ser", derive(serde::Serialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct LatexFragment<'a> {
+    /// Delimiters of the fragment
+    pub kind: LatexKind,
+    /// Contents of the fragment, without its delimiters, or the whole
+    /// environment, from `\begin{NAME}` to `\end{NAME}`
+    pub value: Cow<'a, str>,
+}
+
+/// Delimiters of a LaTeX fragment
+#[cfg_attr(feature = "ser", derive(serde::Serialize))]
+#[cfg_attr(feature = "ser", serde(rename_all = "kebab-case"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LatexKind {
+    /// `$x^2$`
+    Dollar,
+    /// `$$x^2$$`
+    DoubleDollar,
+    /// `\(x^2\)`
+    Parenthesis,
+    /// `\[x^2\]`
+    Bracket,
+    /// `\begin{NAME}` and `\end{NAME}` on lines of their own, outside of
+    /// paragraphs
+    Environment,
+}
+
+impl LatexKind {
+    /// Returns `true` if the fragment is displayed on its own, i.e. for
+    /// `$$x$$`, `\[x\]` and environments
+    pub fn is_display(self) -> bool {
+        match self {
+            LatexKind::Dollar | LatexKind::Parenthesis => false,
+            LatexKind::DoubleDollar | LatexKind::Bracket | LatexKind::Environment => true,
+        }
+    }
+
+    /// Returns the opening and closing delimiters, which are part of the
+    /// value of environments
+    pub fn delimiters(self) -> (&'static str, &'static str) {
+        match self {
+            LatexKind::Dollar => ("$", "$"),
+            LatexKind::DoubleDollar => ("$$", "$$"),
+            LatexKind::Parenthesis => ("\\(", "\\)"),
+            LatexKind::Bracket => ("\\[", "\\]"),
+            LatexKind::Environment => ("", ""),
+        }
+    }
+}
+
+impl LatexFragment<'_> {
+    /// Parses a fragment at the start of `input`
+    pub(crate) fn parse(input: &str) -> Option<(&str, LatexFragment<'_>)> {
+        if let Some(rest) = input.strip_prefix("\\(") {
+            parse_delimited(rest, LatexKind::Parenthesis)
+        } else if let Some(rest) = input.strip_prefix("\\[") {
+            parse_delimited(rest, LatexKind::Bracket)
+        } else if let Some(rest) = input.strip_prefix("$$") {
+            parse_delimited(rest, LatexKind::DoubleDollar)
+        } else {
+            parse_dollar(input)
+        }
+    }
+
+    /// Parses an environment at the start of `input`, from `\begin{NAME}` to
+    /// a line `\end{NAME}`
+    pub(crate) fn parse_environment(input: &str) -> Option<(&str, LatexFragment<'_>)> {
+        let name = input.strip_prefix("\\begin{")?;
+        let name = &name[0..name.find('}')?];
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '*') {
+            return None;
+        }
+        let end = format!("\\end{{{}}}", name);
+
+        let mut lines = memchr_iter(b'\n', input.as_bytes())
+            .map(|i| i + 1)
+            .chain(once(input.len()));
+        let mut start = lines.next()?;
+        for i in lines {
+            let line = &input[start..i];
+            if line.trim() == end {
+                return Some((
+                    &input[i..],
+                    LatexFragment {
+                        kind: LatexKind::Environment,
+                        value: input[0..i].trim_end().into(),
+                    },
+                ));
+            }
+            start = i;
+        }
+
+        None
+    }
+
+    pub fn into_owned(self) -> LatexFragment<'static> {
+        LatexFragment {
+            kind: self.kind,
+            value: self.value.into_owned().into(),
+        }
+    }
+}
+
+/// Parses the contents of a fragment until the closing delimiter of `kind`
+fn parse_delimited(input: &str, kind: LatexKind) -> Option<(&str, LatexFragment<'_>)> {
+    let (_, close) = kind.delimiters();
+    let end = input.find(close)?;
+    let value = &input[0..end];
+    if value.trim().is_empty() {
+        return None;
+    }
+
+    Some((
+        &input[end + close.len()..],
+        LatexFragment {
+            kind,
+            value: value.into(),
+        },
+    ))
+}
+
+/// Parses `$x$`, whose contents don't start or end with whitespace and whose
+/// closing `$` is followed by whitespace, punctuation or the end of the text,
+/// so that `$5 or $10` isn't a fragment
+fn parse_dollar(input: &str) -> Option<(&str, LatexFragment<'_>)> {
+    let rest = input.strip_prefix('$')?;
+    let end = rest.find('$')?;
+    let value = &rest[0..end];
+    let first = value.chars().next()?;
+    let last = value.chars().last()?;
+    if first.is_whitespace()
+        || ",;.".contains(first)
+        || last.is_whitespace()
+        || ",.".contains(last)
+        || value.matches('\n').count() > 2
+    {
+        return None;
+    }
+
+    let tail = &rest[end + 1..];
+    match tail.chars().next() {
+        Some(c) if !c.is_whitespace() && !"-.,?;:'\")".contains(c) => None,
+        _ => Some((
+            tail,
+            LatexFragment {
+                kind: LatexKind::Dollar,
+                value: value.into(),
+            },
+        )),
+    }
+}
+
+#[test]
+fn parse() {
+    let fragment = |input| LatexFragment::parse(input).map(|(tail, f)| (tail, f.kind, f.value));
+
+    assert_eq!(
+        fragment("$x^2$ and"),
+        Some((" and", LatexKind::Dollar, "x^2".into()))
+    );
+    assert_eq!(fragment("$x$."), Some((".", LatexKind::Dollar, "x".into())));
+    assert_eq!(
+        fragment("\\(a+b\\)c"),
+        Some(("c", LatexKind::Parenthesis, "a+b".into()))
+    );
+    assert_eq!(
+        fragment("\\[ a \\]"),
+        Some(("", LatexKind::Bracket, " a ".into()))
+    );
+    assert_eq!(
+        fragment("$$ a\nb $$"),
+        Some(("", LatexKind::DoubleDollar, " a\nb ".into()))
+    );
+    assert_eq!(fragment("$5 or $10"), None);
+    assert_eq!(fragment("$ x$"), None);
+    assert_eq!(fragment("$x $"), None);
+    assert_eq!(fragment("$x$y"), None);
+    assert_eq!(fragment("$$x$"), None);
+    assert_eq!(fragment("$a\nb\nc\nd$"), None);
+    assert_eq!(fragment("\\(\\)"), None);
+    assert_eq!(fragment("\\alpha"), None);
+
+    assert_eq!(
+        LatexFragment::parse_environment("\\begin{align*}\na &= b\n  \\end{align*}  \ntext"),
+        Some((
+            "text",
+            LatexFragment {
+                kind: LatexKind::Environment,
+                value: "\\begin{align*}\na &= b\n  \\end{align*}".into()
+            }
+        ))
+    );
+    assert_eq!(
+        LatexFragment::parse_environment("\\begin{align}\na &= b\n\\end{equation}\n"),
+        None
+    );
+    assert_eq!(
+        LatexFragment::parse_environment("\\begin{x}a\\end{x}"),
+        None
+    );
+}
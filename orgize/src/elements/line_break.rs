@@ -0,0 +1,21 @@
+/// Parses `\\` followed by nothing but spaces and tabs until the end of the
+/// line, returns the text from the newline
+pub fn parse_line_break(input: &str) -> Option<&str> {
+    let input = input.strip_prefix("\\\\")?;
+    let tail = input.trim_start_matches(&[' ', '\t'][..]);
+    if tail.is_empty() || tail.starts_with('\n') || tail.starts_with("\r\n") {
+        Some(tail)
+    } else {
+        None
+    }
+}
+
+#[test]
+fn parse() {
+    assert_eq!(parse_line_break("\\\\"), Some(""));
+    assert_eq!(parse_line_break("\\\\\nb"), Some("\nb"));
+    assert_eq!(parse_line_break("\\\\ \t\r\nb"), Some("\r\nb"));
+    assert_eq!(parse_line_break("\\\\ b\n"), None);
+    assert_eq!(parse_line_break("\\\\\\\n"), None);
+    assert_eq!(parse_line_break("\\ \n"), None);
+}
@@ -1,22 +1,43 @@
 use std::borrow::Cow;
 
-use nom::{
-    bytes::complete::{tag, take_while},
-    combinator::opt,
-    error::ParseError,
-    sequence::delimited,
-    IResult,
-};
+use nom::{bytes::complete::tag, combinator::opt, error::ParseError, sequence::delimited, IResult};
 
 /// Link Object
-#[cfg_attr(test, derive(PartialEq))]
+///
+/// Links are containers: the objects of their description, like emphasis or
+/// text, are parsed as their children. Brackets in a description are escaped
+/// with a backslash, see [`unescape_desc`].
+///
+/// [`unescape_desc`]: #method.unescape_desc
 #[cfg_attr(feature = "ser", derive(serde::Serialize))]
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Link<'a> {
-    /// Link destination
+    /// Link destination, as written in the document except for its escaped
+    /// brackets, see [`path_decoded`]
+    ///
+    /// [`path_decoded`]: #method.path_decoded
     pub path: Cow<'a, str>,
+    /// Link description, as written in the document
     #[cfg_attr(feature = "ser", serde(skip_serializing_if = "Option::is_none"))]
     pub desc: Option<Cow<'a, str>>,
+    /// How the link is written in the document
+    pub format: LinkFormat,
+}
+
+/// How a link is written, which the org export writes back
+///
+/// Links with a description, and the paths which can't be written in
+/// another format, are written in brackets.
+#[cfg_attr(feature = "ser", derive(serde::Serialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LinkFormat {
+    /// `[[https://orgmode.org][description]]`, the default
+    #[default]
+    Bracket,
+    /// `https://orgmode.org`
+    Plain,
+    /// `<https://orgmode.org>`
+    Angle,
 }
 
 impl Link<'_> {
@@ -25,36 +46,276 @@ impl Link<'_> {
         parse_link::<()>(input).ok()
     }
 
+    /// Parses a plain link, like `https://orgmode.org`, whose protocol is
+    /// one of `protocols`
+    ///
+    /// The path ends before whitespace, brackets or a double quote. Like
+    /// `org-link-plain-re`, it only contains the parentheses balanced within
+    /// it, and trailing punctuation, like the period ending a sentence, is
+    /// left out of it.
+    pub(crate) fn parse_plain<'a>(
+        input: &'a str,
+        protocols: &[String],
+    ) -> Option<(&'a str, Link<'a>)> {
+        let colon = protocol(input, protocols)?;
+
+        // the end of the path, and the position of the outermost parenthesis
+        // which isn't closed yet
+        let mut end = input.len();
+        let mut depth = 0;
+        let mut open = 0;
+        for (i, c) in input[colon + 1..].char_indices() {
+            let i = colon + 1 + i;
+            match c {
+                '(' => {
+                    if depth == 0 {
+                        open = i;
+                    }
+                    depth += 1;
+                }
+                ')' if depth == 0 => {
+                    end = i;
+                    break;
+                }
+                ')' => depth -= 1,
+                c if c.is_whitespace() || "[]<>\"".contains(c) => {
+                    end = i;
+                    break;
+                }
+                _ => (),
+            }
+        }
+        if depth > 0 {
+            end = open;
+        }
+
+        let path = input[0..end].trim_end_matches(&['.', ',', ';', ':', '!', '?', '\'', '"'][..]);
+
+        if path.len() <= colon + 1 {
+            return None;
+        }
+        Some((
+            &input[path.len()..],
+            Link {
+                path: path.into(),
+                desc: None,
+                format: LinkFormat::Plain,
+            },
+        ))
+    }
+
+    /// Parses an angle link, like `<https://orgmode.org>`, whose protocol is
+    /// one of `protocols`
+    pub(crate) fn parse_angle<'a>(
+        input: &'a str,
+        protocols: &[String],
+    ) -> Option<(&'a str, Link<'a>)> {
+        let input = input.strip_prefix('<')?;
+        let colon = protocol(input, protocols)?;
+        let end = input.find(&['>', '<', '[', ']', '\n'][..])?;
+
+        if end == colon + 1 || input.as_bytes()[end] != b'>' {
+            return None;
+        }
+        Some((
+            &input[end + 1..],
+            Link {
+                path: input[0..end].into(),
+                desc: None,
+                format: LinkFormat::Angle,
+            },
+        ))
+    }
+
+    /// Returns the format the org export writes this link in, which is its own
+    /// one unless it has a description or its path would be parsed back
+    /// differently in it
+    pub(crate) fn org_format(&self) -> LinkFormat {
+        let protocols = [self.path.split(':').next().unwrap_or("").to_string()];
+        let parsed_back = |parsed: Option<(&str, Link)>| matches!(parsed, Some(("", link)) if link.path == self.path);
+        let kept = self.desc.is_none()
+            && match self.format {
+                LinkFormat::Bracket => true,
+                LinkFormat::Plain => parsed_back(Link::parse_plain(&self.path, &protocols)),
+                LinkFormat::Angle => {
+                    parsed_back(Link::parse_angle(&format!("<{}>", self.path), &protocols))
+                }
+            };
+        if kept {
+            self.format
+        } else {
+            LinkFormat::Bracket
+        }
+    }
+
+    /// Returns `true` if the path of this link ends with the extension of an
+    /// image, like `png` or `svg`
+    pub fn is_image(&self) -> bool {
+        const EXTENSIONS: &[&str] = &["gif", "jpeg", "jpg", "png", "svg", "webp"];
+
+        match self.path.rfind('.') {
+            Some(i) => EXTENSIONS
+                .iter()
+                .any(|extension| self.path[i + 1..].eq_ignore_ascii_case(extension)),
+            None => false,
+        }
+    }
+
+    /// Returns `text` with the escaped brackets of a link description, `\[`
+    /// and `\]`, unescaped
+    ///
+    /// ```rust
+    /// use orgize::elements::Link;
+    ///
+    /// assert_eq!(Link::unescape_desc(r"see \[1\]"), "see [1]");
+    /// assert_eq!(Link::unescape_desc(r"\(x\)"), r"\(x\)");
+    /// ```
+    pub fn unescape_desc(text: &str) -> Cow<'_, str> {
+        unescape_brackets(text)
+    }
+
+    /// Returns the path with its percent-encoded sequences, like `%20`,
+    /// decoded
+    ///
+    /// Invalid UTF-8 sequences are replaced with `U+FFFD`.
+    ///
+    /// ```rust
+    /// use orgize::elements::{Link, LinkFormat};
+    ///
+    /// let link = Link {
+    ///     path: "file:My%20Notes/caf%C3%A9.org".into(),
+    ///     desc: None,
+    ///     format: LinkFormat::Bracket,
+    /// };
+    /// assert_eq!(link.path_decoded(), "file:My Notes/café.org");
+    /// ```
+    pub fn path_decoded(&self) -> Cow<'_, str> {
+        fn hex(byte: u8) -> Option<u8> {
+            (byte as char).to_digit(16).map(|digit| digit as u8)
+        }
+
+        if !self.path.contains('%') {
+            return Cow::Borrowed(&self.path);
+        }
+
+        let bytes = self.path.as_bytes();
+        let mut decoded = Vec::with_capacity(bytes.len());
+        let mut i = 0;
+        while i < bytes.len() {
+            match (bytes[i], bytes.get(i + 1), bytes.get(i + 2)) {
+                (b'%', Some(&high), Some(&low)) if hex(high).is_some() && hex(low).is_some() => {
+                    decoded.push(hex(high).unwrap() * 16 + hex(low).unwrap());
+                    i += 3;
+                }
+                (byte, _, _) => {
+                    decoded.push(byte);
+                    i += 1;
+                }
+            }
+        }
+
+        Cow::Owned(String::from_utf8_lossy(&decoded).into_owned())
+    }
+
     pub fn into_owned(self) -> Link<'static> {
         Link {
             path: self.path.into_owned().into(),
             desc: self.desc.map(Into::into).map(Cow::Owned),
+            format: self.format,
         }
     }
 }
 
 #[inline]
 fn parse_link<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, Link<'a>, E> {
-    let (input, path) = delimited(
-        tag("[["),
-        take_while(|c: char| c != '<' && c != '>' && c != '\n' && c != ']'),
-        tag("]"),
-    )(input)?;
-    let (input, desc) = opt(delimited(
-        tag("["),
-        take_while(|c: char| c != '[' && c != ']'),
-        tag("]"),
-    ))(input)?;
+    let (input, path) = delimited(tag("[["), path, tag("]"))(input)?;
+    let (input, desc) = opt(delimited(tag("["), description, tag("]")))(input)?;
     let (input, _) = tag("]")(input)?;
     Ok((
         input,
         Link {
-            path: path.into(),
+            path: unescape_brackets(path),
             desc: desc.map(Into::into),
+            format: LinkFormat::Bracket,
         },
     ))
 }
 
+/// Returns the position of the colon ending the protocol `input` starts
+/// with, if it's one of `protocols`
+fn protocol(input: &str, protocols: &[String]) -> Option<usize> {
+    let colon = input.find(|c: char| !c.is_ascii_alphanumeric() && c != '+' && c != '-')?;
+
+    if input.as_bytes()[colon] == b':'
+        && protocols
+            .iter()
+            .any(|protocol| protocol == &input[0..colon])
+    {
+        Some(colon)
+    } else {
+        None
+    }
+}
+
+/// Takes the path until an unescaped closing bracket
+fn path<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, &'a str, E> {
+    take_escaped(input, |byte| {
+        byte == b'<' || byte == b'>' || byte == b'\n' || byte == b']'
+    })
+}
+
+/// Takes the description until an unescaped bracket, so that descriptions
+/// can't contain other bracket links
+fn description<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, &'a str, E> {
+    take_escaped(input, |byte| byte == b'[' || byte == b']')
+}
+
+/// Takes `input` until a byte matching `end`, skipping escaped brackets
+fn take_escaped<'a, E: ParseError<&'a str>>(
+    input: &'a str,
+    end: impl Fn(u8) -> bool,
+) -> IResult<&'a str, &'a str, E> {
+    let bytes = input.as_bytes();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' if i + 1 < bytes.len() && (bytes[i + 1] == b'[' || bytes[i + 1] == b']') => {
+                i += 2
+            }
+            byte if end(byte) => break,
+            _ => i += 1,
+        }
+    }
+
+    Ok((&input[i..], &input[0..i]))
+}
+
+fn unescape_brackets(text: &str) -> Cow<'_, str> {
+    if !text.contains("\\[") && !text.contains("\\]") {
+        return Cow::Borrowed(text);
+    }
+
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            ('\\', Some(&next)) if next == '[' || next == ']' => (),
+            _ => unescaped.push(c),
+        }
+    }
+    Cow::Owned(unescaped)
+}
+
+/// Escapes the brackets of a path, so that it can be written back in a link
+pub(crate) fn escape_brackets(text: &str) -> Cow<'_, str> {
+    if text.contains(&['[', ']'][..]) {
+        Cow::Owned(text.replace('[', "\\[").replace(']', "\\]"))
+    } else {
+        Cow::Borrowed(text)
+    }
+}
+
 #[test]
 fn parse() {
     use nom::error::VerboseError;
@@ -65,7 +326,8 @@ fn parse() {
             "",
             Link {
                 path: "#id".into(),
-                desc: None
+                desc: None,
+                format: LinkFormat::Bracket,
             }
         ))
     );
@@ -75,9 +337,83 @@ fn parse() {
             "",
             Link {
                 path: "#id".into(),
-                desc: Some("desc".into())
+                desc: Some("desc".into()),
+                format: LinkFormat::Bracket,
+            }
+        ))
+    );
+    assert_eq!(
+        parse_link::<VerboseError<&str>>(r"[[#id][see \[1\]]]"),
+        Ok((
+            "",
+            Link {
+                path: "#id".into(),
+                desc: Some(r"see \[1\]".into()),
+                format: LinkFormat::Bracket,
+            }
+        ))
+    );
+    assert_eq!(
+        parse_link::<VerboseError<&str>>(r"[[file:a\[b\].org]]"),
+        Ok((
+            "",
+            Link {
+                path: "file:a[b].org".into(),
+                desc: None,
+                format: LinkFormat::Bracket,
             }
         ))
     );
     assert!(parse_link::<VerboseError<&str>>("[[#id][desc]").is_err());
+    assert!(parse_link::<VerboseError<&str>>("[[#id][[[#other]]]]").is_err());
+}
+
+#[test]
+fn parse_plain() {
+    let protocols = ["https".to_string(), "mailto".to_string()];
+    let plain = |input| Link::parse_plain(input, &protocols).map(|(tail, link)| (link.path, tail));
+
+    assert_eq!(
+        plain("https://orgmode.org. Next"),
+        Some(("https://orgmode.org".into(), ". Next"))
+    );
+    assert_eq!(
+        plain("https://en.wikipedia.org/wiki/Org_(software)), or"),
+        Some((
+            "https://en.wikipedia.org/wiki/Org_(software)".into(),
+            "), or"
+        ))
+    );
+    assert_eq!(
+        plain("https://x.y)#+SELECT_TAGS"),
+        Some(("https://x.y".into(), ")#+SELECT_TAGS"))
+    );
+    assert_eq!(
+        plain("https://x.y/(a(b)c)d)e"),
+        Some(("https://x.y/(a(b)c)d".into(), ")e"))
+    );
+    assert_eq!(
+        plain("https://x.y/a(b. Next"),
+        Some(("https://x.y/a".into(), "(b. Next"))
+    );
+    assert_eq!(
+        plain("mailto:me@example.com?\""),
+        Some(("mailto:me@example.com".into(), "?\""))
+    );
+    assert_eq!(
+        plain("https://a.org/x[1]"),
+        Some(("https://a.org/x".into(), "[1]"))
+    );
+    assert_eq!(plain("https:."), None);
+    assert_eq!(plain("ftp://a.org"), None);
+    assert_eq!(plain("https"), None);
+
+    let angle = |input| Link::parse_angle(input, &protocols).map(|(tail, link)| (link.path, tail));
+    assert_eq!(
+        angle("<https://orgmode.org/a b>."),
+        Some(("https://orgmode.org/a b".into(), "."))
+    );
+    assert_eq!(angle("<https:>"), None);
+    assert_eq!(angle("<https://a\nb>"), None);
+    assert_eq!(angle("<file:a.org>"), None);
 }
@@ -1,29 +1,134 @@
 use std::borrow::Cow;
+use std::fmt;
 use std::iter::once;
 
 use memchr::memchr_iter;
 
+use crate::config::ParseConfig;
+
 /// Plain List Element
-#[cfg_attr(test, derive(PartialEq))]
 #[cfg_attr(feature = "ser", derive(serde::Serialize))]
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct List {
+    /// Column of the bullets of its items
     pub indent: usize,
+    /// Its items are numbered, e.g. `1.` or `1)`
     pub ordered: bool,
+    /// Bullet of its first item, e.g. `-` or `1)`
+    pub bullet: ListBullet,
+    /// No blank line separates its items
+    pub tight: bool,
+    /// Its first item has a tag, e.g. `- term :: definition`
+    pub description: bool,
+    /// Counter of its first item, e.g. `10` for `1. [@10] item`
+    #[cfg_attr(feature = "ser", serde(skip_serializing_if = "Option::is_none"))]
+    pub start: Option<usize>,
+}
+
+/// Bullet of a list item, without its number
+#[cfg_attr(feature = "ser", derive(serde::Serialize))]
+#[cfg_attr(feature = "ser", serde(rename_all = "kebab-case"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListBullet {
+    /// `-`
+    Hyphen,
+    /// `+`
+    Plus,
+    /// `*`, in indented lists only
+    Star,
+    /// A number followed by a period, e.g. `1.`
+    Period,
+    /// A number followed by a parenthesis, e.g. `1)`
+    Parenthesis,
+}
+
+impl ListBullet {
+    /// Returns the kind of a bullet like `- ` or `10) `
+    ///
+    /// ```rust
+    /// use orgize::elements::ListBullet;
+    ///
+    /// assert_eq!(ListBullet::of("+ "), Some(ListBullet::Plus));
+    /// assert_eq!(ListBullet::of("10)"), Some(ListBullet::Parenthesis));
+    /// assert_eq!(ListBullet::of("a."), None);
+    /// ```
+    pub fn of(bullet: &str) -> Option<ListBullet> {
+        let bullet = bullet.trim();
+        match bullet {
+            "-" => Some(ListBullet::Hyphen),
+            "+" => Some(ListBullet::Plus),
+            "*" => Some(ListBullet::Star),
+            _ => {
+                let number = &bullet[0..bullet.len().saturating_sub(1)];
+                if number.is_empty() || !number.bytes().all(|c| c.is_ascii_digit()) {
+                    None
+                } else if bullet.ends_with('.') {
+                    Some(ListBullet::Period)
+                } else if bullet.ends_with(')') {
+                    Some(ListBullet::Parenthesis)
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    /// Returns `true` for the bullets of ordered lists
+    pub fn is_ordered(self) -> bool {
+        match self {
+            ListBullet::Period | ListBullet::Parenthesis => true,
+            ListBullet::Hyphen | ListBullet::Plus | ListBullet::Star => false,
+        }
+    }
+}
+
+/// Kind of a list, see [`List::kind`]
+///
+/// [`List::kind`]: struct.List.html#method.kind
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListKind {
+    /// Items with bullets like `-`
+    Unordered,
+    /// Numbered items
+    Ordered,
+    /// Unordered items starting with a term, like `- term :: definition`
+    Descriptive,
 }
 
 impl List {
+    /// Returns whether its items are numbered or start with terms
+    pub fn kind(&self) -> ListKind {
+        if self.description {
+            ListKind::Descriptive
+        } else if self.ordered {
+            ListKind::Ordered
+        } else {
+            ListKind::Unordered
+        }
+    }
+
     #[inline]
-    pub(crate) fn parse(text: &str) -> Option<(&str, List, &str)> {
+    pub(crate) fn parse<'a>(
+        text: &'a str,
+        config: &ParseConfig,
+    ) -> Option<(&'a str, List, &'a str)> {
         let (indent, tail) = text
             .find(|c| c != ' ')
             .map(|off| (off, &text[off..]))
             .unwrap_or((0, text));
 
-        let ordered = is_item(tail)?;
+        // a star at column zero starts a headline
+        let star_bullet = config.allow_star_bullets && indent > 0;
+
+        let ordered = is_item(tail, star_bullet)?;
+        let (_, first, _) = ListItem::parse(text, indent, true);
+        let description = first.tag.is_some();
+        let counter = first.counter;
+        let bullet = ListBullet::of(&first.bullet).unwrap_or(ListBullet::Hyphen);
 
         let mut last_end = 0;
         let mut start = 0;
+        let mut tight = true;
 
         for i in memchr_iter(b'\n', text.as_bytes())
             .map(|i| i + 1)
@@ -32,14 +137,29 @@ impl List {
             let line = &text[start..i];
             if let Some(line_indent) = line.find(|c: char| !c.is_whitespace()) {
                 if line_indent < indent
-                    || (line_indent == indent && is_item(&line[line_indent..]).is_none())
+                    || (line_indent == indent
+                        && is_item(&line[line_indent..], star_bullet).is_none())
                 {
+                    // without the line ending, `\r\n` included
+                    let content = &text[0..start - 1];
+                    let content = content.strip_suffix('\r').unwrap_or(content);
                     return Some((
                         &text[start..],
-                        List { indent, ordered },
-                        &text[0..start - 1],
+                        List {
+                            indent,
+                            ordered,
+                            bullet,
+                            tight,
+                            description,
+                            start: counter,
+                        },
+                        content,
                     ));
                 } else {
+                    // an item following a blank line
+                    if last_end != 0 && line_indent == indent {
+                        tight = false;
+                    }
                     last_end = 0;
                     start = i;
                     continue;
@@ -47,7 +167,18 @@ impl List {
             } else {
                 // this line is empty
                 if last_end != 0 {
-                    return Some((&text[i..], List { indent, ordered }, &text[0..last_end]));
+                    return Some((
+                        &text[i..],
+                        List {
+                            indent,
+                            ordered,
+                            bullet,
+                            tight,
+                            description,
+                            start: counter,
+                        },
+                        &text[0..last_end],
+                    ));
                 } else {
                     last_end = start;
                     start = i;
@@ -57,27 +188,178 @@ impl List {
         }
 
         if last_end != 0 {
-            Some(("", List { indent, ordered }, &text[0..last_end]))
+            Some((
+                "",
+                List {
+                    indent,
+                    ordered,
+                    bullet,
+                    tight,
+                    description,
+                    start: counter,
+                },
+                &text[0..last_end],
+            ))
         } else {
-            Some(("", List { indent, ordered }, text))
+            Some((
+                "",
+                List {
+                    indent,
+                    ordered,
+                    bullet,
+                    tight,
+                    description,
+                    start: counter,
+                },
+                text,
+            ))
         }
     }
 }
 
 /// List Item Elemenet
-#[cfg_attr(test, derive(PartialEq))]
 #[cfg_attr(feature = "ser", derive(serde::Serialize))]
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct ListItem<'a> {
     /// List item bullet
     pub bullet: Cow<'a, str>,
+    /// List item indent, same as the indent of its list
+    pub indent: usize,
+    /// Checkbox following the bullet, which isn't part of the item contents
+    #[cfg_attr(feature = "ser", serde(skip_serializing_if = "Option::is_none"))]
+    pub checkbox: Option<Checkbox>,
+    /// Counter following the bullet of an ordered item, e.g. `10` for
+    /// `1. [@10] item`
+    #[cfg_attr(feature = "ser", serde(skip_serializing_if = "Option::is_none"))]
+    pub counter: Option<usize>,
+    /// Raw term of an item of a description list, e.g. `term` for
+    /// `- term :: definition`
+    ///
+    /// The term is parsed into the children of the `ListItemTag` element
+    /// which starts the item.
+    #[cfg_attr(feature = "ser", serde(skip_serializing_if = "Option::is_none"))]
+    pub tag: Option<Cow<'a, str>>,
+}
+
+/// Checkbox of a list item
+///
+/// ```rust
+/// use orgize::{elements::Checkbox, Element, Org};
+///
+/// let org = Org::parse("- [X] done\n- [-] partial\n- see [ ] reference\n");
+/// let checkboxes: Vec<_> = org
+///     .arena()
+///     .iter()
+///     .filter_map(|node| match node.get() {
+///         Element::ListItem(item) => Some(item.checkbox),
+///         _ => None,
+///     })
+///     .collect();
+/// assert_eq!(
+///     checkboxes,
+///     [Some(Checkbox::Checked), Some(Checkbox::Partial), None]
+/// );
+/// assert_eq!(Checkbox::Unchecked.to_string(), "[ ]");
+/// ```
+#[cfg_attr(feature = "ser", derive(serde::Serialize))]
+#[cfg_attr(feature = "ser", serde(rename_all = "kebab-case"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Checkbox {
+    /// `[ ]`
+    Unchecked,
+    /// `[X]`
+    Checked,
+    /// `[-]`, some of the items of its sublist are checked
+    Partial,
+}
+
+impl Checkbox {
+    /// Parses a checkbox at the start of `text`, returning it and the length
+    /// of the checkbox with the space or newline following it
+    fn parse(text: &str) -> Option<(Checkbox, usize)> {
+        let checkbox = match text.get(0..3)? {
+            "[ ]" => Checkbox::Unchecked,
+            "[X]" => Checkbox::Checked,
+            "[-]" => Checkbox::Partial,
+            _ => return None,
+        };
+        match text.as_bytes().get(3) {
+            None => Some((checkbox, 3)),
+            Some(b' ') | Some(b'\n') => Some((checkbox, 4)),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Checkbox {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Checkbox::Unchecked => write!(f, "[ ]"),
+            Checkbox::Checked => write!(f, "[X]"),
+            Checkbox::Partial => write!(f, "[-]"),
+        }
+    }
 }
 
 impl ListItem<'_> {
+    /// Parses the item at the start of `text`, looking for a tag if `tags`
+    /// is true, i.e. if the item belongs to a description list
     #[inline]
-    pub(crate) fn parse(text: &str, indent: usize) -> (&str, ListItem<'_>, &str) {
+    pub(crate) fn parse(text: &str, indent: usize, tags: bool) -> (&str, ListItem<'_>, &str) {
         debug_assert!(&text[0..indent].trim().is_empty());
-        let off = &text[indent..].find(' ').unwrap() + 1 + indent;
+        // `is_item` guarantees that the bullet is followed by a space or a newline
+        let mut off = text[indent..]
+            .find(&[' ', '\n'][..])
+            .map(|i| i + 1 + indent)
+            .unwrap_or_else(|| text.len());
+        // the bullet keeps its trailing space, but not the newline, nor the
+        // space if the contents start on the next line
+        let rest_blank = text[off..]
+            .split('\n')
+            .next()
+            .unwrap_or("")
+            .trim()
+            .is_empty();
+        let bullet_end = if text[..off].ends_with("\r\n") {
+            off - 2
+        } else if text[..off].ends_with('\n') || (rest_blank && text[..off].ends_with(' ')) {
+            off - 1
+        } else {
+            off
+        };
+        let ordered = text.as_bytes()[indent].is_ascii_digit();
+
+        // the counter, the checkbox and the tag follow each other on the
+        // first line, separated by blanks
+        let blanks = |off: usize| {
+            off + text[off..].len() - text[off..].trim_start_matches(&[' ', '\t'][..]).len()
+        };
+        let mut counter = None;
+        if ordered && text[..off].ends_with(' ') {
+            if let Some((value, len)) = parse_counter(&text[blanks(off)..]) {
+                counter = Some(value);
+                off = blanks(off) + len;
+            }
+        }
+        let mut checkbox = None;
+        if text[..off].ends_with(' ') {
+            if let Some((value, len)) = Checkbox::parse(&text[blanks(off)..]) {
+                checkbox = Some(value);
+                off = blanks(off) + len;
+            }
+        }
+        let mut tag = None;
+        if tags && !ordered && text[..off].ends_with(' ') {
+            if let Some((term, len)) = parse_tag(&text[off..]) {
+                tag = Some(term.into());
+                off += len;
+            }
+        }
+
+        // the contents start at the first non-blank character of the first line
+        if !text[..off].ends_with('\n') {
+            off = text.len() - text[off..].trim_start_matches(&[' ', '\t'][..]).len();
+        }
 
         let bytes = text.as_bytes();
         let mut lines = memchr_iter(b'\n', bytes)
@@ -92,7 +374,11 @@ impl ListItem<'_> {
                     return (
                         &text[pos..],
                         ListItem {
-                            bullet: text[indent..off].into(),
+                            bullet: text[indent..bullet_end].into(),
+                            indent,
+                            checkbox,
+                            counter,
+                            tag,
                         },
                         &text[off..pos],
                     );
@@ -104,7 +390,11 @@ impl ListItem<'_> {
         (
             "",
             ListItem {
-                bullet: text[indent..off].into(),
+                bullet: text[indent..bullet_end].into(),
+                indent,
+                checkbox,
+                counter,
+                tag,
             },
             &text[off..],
         )
@@ -113,16 +403,82 @@ impl ListItem<'_> {
     pub fn into_owned(self) -> ListItem<'static> {
         ListItem {
             bullet: self.bullet.into_owned().into(),
+            indent: self.indent,
+            checkbox: self.checkbox,
+            counter: self.counter,
+            tag: self.tag.map(|tag| tag.into_owned().into()),
         }
     }
 }
 
+/// Parses a counter like `[@10]` at the start of `text`, returning its value
+/// and its length with the space or newline following it
+fn parse_counter(text: &str) -> Option<(usize, usize)> {
+    let digits = text.strip_prefix("[@")?;
+    let end = digits.find(']')?;
+    if end == 0 || !digits[..end].bytes().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let value = digits[..end].parse().ok()?;
+    let len = end + 3;
+    match text.as_bytes().get(len) {
+        None => Some((value, len)),
+        Some(b' ') | Some(b'\n') => Some((value, len + 1)),
+        _ => None,
+    }
+}
+
+/// Parses the tag at the start of `text`, i.e. the text of its first line
+/// before a `::` surrounded by whitespace, returning the term and the length
+/// of the tag with the separator and the whitespace following it
+///
+/// A `::` inside a link, e.g. in `[[file:a.org::*Heading]]`, doesn't separate
+/// the tag.
+fn parse_tag(text: &str) -> Option<(&str, usize)> {
+    // the tag starts at the first non-blank character, like the contents
+    let blanks = text.len() - text.trim_start_matches(&[' ', '\t'][..]).len();
+    let text = &text[blanks..];
+    let line = &text[0..text.find('\n').unwrap_or(text.len())];
+    let bytes = line.as_bytes();
+    let is_blank = |c: Option<&u8>| matches!(c, Some(b' ') | Some(b'\t'));
+
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i..].starts_with(b"[[") {
+            i += line[i..].find("]]").map(|end| end + 2).unwrap_or(2);
+        } else if bytes[i..].starts_with(b"::")
+            && i > 0
+            && is_blank(bytes.get(i - 1))
+            && (i + 2 == bytes.len() || is_blank(bytes.get(i + 2)))
+        {
+            let term = line[0..i].trim_end();
+            if term.is_empty() {
+                return None;
+            }
+            return if i + 2 < text.len() {
+                Some((term, blanks + i + 3))
+            } else {
+                Some((term, blanks + i + 2))
+            };
+        } else {
+            i += 1;
+        }
+    }
+
+    None
+}
+
 #[inline]
-pub fn is_item(text: &str) -> Option<bool> {
+pub fn is_item(text: &str, star_bullet: bool) -> Option<bool> {
     let bytes = text.as_bytes();
+    // a bullet is followed by a space or a line ending
+    let ends_bullet = |rest: &[u8]| {
+        rest.is_empty() || rest[0] == b' ' || rest[0] == b'\n' || rest.starts_with(b"\r\n")
+    };
     match bytes.get(0)? {
+        b'*' if !star_bullet => None,
         b'*' | b'-' | b'+' => {
-            if text.len() > 1 && (bytes[1] == b' ' || bytes[1] == b'\n') {
+            if ends_bullet(&bytes[1..]) {
                 Some(false)
             } else {
                 None
@@ -133,10 +489,7 @@ pub fn is_item(text: &str) -> Option<bool> {
                 .iter()
                 .position(|&c| !c.is_ascii_digit())
                 .unwrap_or_else(|| text.len() - 1);
-            if (bytes[i] == b'.' || bytes[i] == b')')
-                && text.len() > i + 1
-                && (bytes[i + 1] == b' ' || bytes[i + 1] == b'\n')
-            {
+            if (bytes[i] == b'.' || bytes[i] == b')') && ends_bullet(&bytes[i + 1..]) {
                 Some(true)
             } else {
                 None
@@ -148,108 +501,307 @@ pub fn is_item(text: &str) -> Option<bool> {
 
 #[test]
 fn test_is_item() {
-    assert_eq!(is_item("+ item"), Some(false));
-    assert_eq!(is_item("- item"), Some(false));
-    assert_eq!(is_item("10. item"), Some(true));
-    assert_eq!(is_item("10) item"), Some(true));
-    assert_eq!(is_item("1. item"), Some(true));
-    assert_eq!(is_item("1) item"), Some(true));
-    assert_eq!(is_item("10. "), Some(true));
-    assert_eq!(is_item("10.\n"), Some(true));
-    assert_eq!(is_item("10."), None);
-    assert_eq!(is_item("+"), None);
-    assert_eq!(is_item("-item"), None);
-    assert_eq!(is_item("+item"), None);
+    assert_eq!(is_item("+ item", true), Some(false));
+    assert_eq!(is_item("- item", true), Some(false));
+    assert_eq!(is_item("10. item", true), Some(true));
+    assert_eq!(is_item("10) item", true), Some(true));
+    assert_eq!(is_item("1. item", true), Some(true));
+    assert_eq!(is_item("1) item", true), Some(true));
+    assert_eq!(is_item("10. ", true), Some(true));
+    assert_eq!(is_item("10.\n", true), Some(true));
+    assert_eq!(is_item("10.\r\n", true), Some(true));
+    assert_eq!(is_item("-\r\n", true), Some(false));
+    assert_eq!(is_item("-\r", true), None);
+    assert_eq!(is_item("10.", true), Some(true));
+    assert_eq!(is_item("+", true), Some(false));
+    assert_eq!(is_item("10", true), None);
+    assert_eq!(is_item("10.item", true), None);
+    assert_eq!(is_item("-item", true), None);
+    assert_eq!(is_item("+item", true), None);
+    assert_eq!(is_item("* item", true), Some(false));
+    assert_eq!(is_item("* item", false), None);
 }
 
 #[test]
 fn list_parse() {
+    use crate::config::DEFAULT_CONFIG;
+
+    assert_eq!(List::parse("* item1\n* item2", &DEFAULT_CONFIG), None);
+    assert_eq!(
+        List::parse("  * item1\n  * item2", &DEFAULT_CONFIG),
+        Some((
+            "",
+            List {
+                indent: 2,
+                ordered: false,
+                bullet: ListBullet::Star,
+                tight: true,
+                description: false,
+                start: None,
+            },
+            "  * item1\n  * item2"
+        ))
+    );
+    assert_eq!(
+        List::parse(
+            "  * item1",
+            &ParseConfig {
+                allow_star_bullets: false,
+                ..Default::default()
+            }
+        ),
+        None
+    );
     assert_eq!(
-        List::parse("+ item1\n+ item2"),
+        List::parse("+ item1\n+ item2", &DEFAULT_CONFIG),
         Some((
             "",
             List {
                 indent: 0,
                 ordered: false,
+                bullet: ListBullet::Plus,
+                tight: true,
+                description: false,
+                start: None,
             },
             "+ item1\n+ item2"
         ))
     );
     assert_eq!(
-        List::parse("* item1\n  \n* item2"),
+        List::parse("- item1\n  \n- item2", &DEFAULT_CONFIG),
         Some((
             "",
             List {
                 indent: 0,
                 ordered: false,
+                bullet: ListBullet::Hyphen,
+                tight: false,
+                description: false,
+                start: None,
             },
-            "* item1\n  \n* item2"
+            "- item1\n  \n- item2"
         ))
     );
     assert_eq!(
-        List::parse("* item1\n  \n   \n* item2"),
+        List::parse("- item1\n  \n   \n- item2", &DEFAULT_CONFIG),
         Some((
-            "* item2",
+            "- item2",
             List {
                 indent: 0,
                 ordered: false,
+                bullet: ListBullet::Hyphen,
+                tight: true,
+                description: false,
+                start: None,
             },
-            "* item1\n"
+            "- item1\n"
         ))
     );
     assert_eq!(
-        List::parse("* item1\n  \n   "),
+        List::parse("- item1\n  \n   ", &DEFAULT_CONFIG),
         Some((
             "",
             List {
                 indent: 0,
                 ordered: false,
+                bullet: ListBullet::Hyphen,
+                tight: true,
+                description: false,
+                start: None,
             },
-            "* item1\n"
+            "- item1\n"
         ))
     );
     assert_eq!(
-        List::parse("+ item1\n  + item2\n   "),
+        List::parse("+ item1\n  + item2\n   ", &DEFAULT_CONFIG),
         Some((
             "",
             List {
                 indent: 0,
                 ordered: false,
+                bullet: ListBullet::Plus,
+                tight: true,
+                description: false,
+                start: None,
             },
             "+ item1\n  + item2\n"
         ))
     );
     assert_eq!(
-        List::parse("+ item1\n  \n  + item2\n   \n+ item 3"),
+        List::parse("+ item1\n  \n  + item2\n   \n+ item 3", &DEFAULT_CONFIG),
         Some((
             "",
             List {
                 indent: 0,
                 ordered: false,
+                bullet: ListBullet::Plus,
+                tight: false,
+                description: false,
+                start: None,
             },
             "+ item1\n  \n  + item2\n   \n+ item 3"
         ))
     );
     assert_eq!(
-        List::parse("  + item1\n  \n  + item2"),
+        List::parse("  + item1\n  \n  + item2", &DEFAULT_CONFIG),
         Some((
             "",
             List {
                 indent: 2,
                 ordered: false,
+                bullet: ListBullet::Plus,
+                tight: false,
+                description: false,
+                start: None,
             },
             "  + item1\n  \n  + item2"
         ))
     );
     assert_eq!(
-        List::parse("+ 1\n\n  - 2\n\n  - 3\n\n+ 4"),
+        List::parse("+ 1\n\n  - 2\n\n  - 3\n\n+ 4", &DEFAULT_CONFIG),
         Some((
             "",
             List {
                 indent: 0,
                 ordered: false,
+                bullet: ListBullet::Plus,
+                tight: false,
+                description: false,
+                start: None,
             },
             "+ 1\n\n  - 2\n\n  - 3\n\n+ 4"
         ))
     );
 }
+
+#[test]
+fn list_item_parse() {
+    assert_eq!(
+        ListItem::parse("+ item1\n+ item2", 0, false),
+        (
+            "+ item2",
+            ListItem {
+                bullet: "+ ".into(),
+                indent: 0,
+                checkbox: None,
+                counter: None,
+                tag: None,
+            },
+            "item1\n"
+        )
+    );
+    assert_eq!(
+        ListItem::parse("  *\n  * item2", 2, false),
+        (
+            "  * item2",
+            ListItem {
+                bullet: "*".into(),
+                indent: 2,
+                checkbox: None,
+                counter: None,
+                tag: None,
+            },
+            ""
+        )
+    );
+    assert_eq!(
+        ListItem::parse("- [X] done\n  more\n- [ ]\n", 0, false),
+        (
+            "- [ ]\n",
+            ListItem {
+                bullet: "- ".into(),
+                indent: 0,
+                checkbox: Some(Checkbox::Checked),
+                counter: None,
+                tag: None,
+            },
+            "done\n  more\n"
+        )
+    );
+    assert_eq!(
+        ListItem::parse("- [ ]\n", 0, false),
+        (
+            "",
+            ListItem {
+                bullet: "- ".into(),
+                indent: 0,
+                checkbox: Some(Checkbox::Unchecked),
+                counter: None,
+                tag: None,
+            },
+            ""
+        )
+    );
+    assert_eq!(
+        ListItem::parse("1. [X]done [-] see [ ] reference", 0, false),
+        (
+            "",
+            ListItem {
+                bullet: "1. ".into(),
+                indent: 0,
+                checkbox: None,
+                counter: None,
+                tag: None,
+            },
+            "[X]done [-] see [ ] reference"
+        )
+    );
+}
+
+#[test]
+fn list_item_tag_and_counter() {
+    let (_, item, content) = ListItem::parse("- [X] term :: definition\n  more", 0, true);
+    assert_eq!(item.checkbox, Some(Checkbox::Checked));
+    assert_eq!(item.tag.as_deref(), Some("term"));
+    assert_eq!(content, "definition\n  more");
+
+    let (_, item, content) = ListItem::parse("- term ::\n  definition", 0, true);
+    assert_eq!(item.tag.as_deref(), Some("term"));
+    assert_eq!(content, "  definition");
+
+    let (_, item, content) = ListItem::parse("+   :: a :: definition", 0, true);
+    assert_eq!(item.tag.as_deref(), Some(":: a"));
+    assert_eq!(content, "definition");
+
+    for text in &[
+        "- a::b",
+        "- :: definition",
+        "- [[file:a.org::*Heading]]",
+        "- [[file:a.org :: b]]",
+    ] {
+        assert_eq!(ListItem::parse(text, 0, true).1.tag, None, "{}", text);
+    }
+    assert_eq!(
+        ListItem::parse("- [[file:a.org::x]] :: b", 0, true)
+            .1
+            .tag
+            .as_deref(),
+        Some("[[file:a.org::x]]")
+    );
+    assert_eq!(
+        ListItem::parse("- term :: definition", 0, false).1.tag,
+        None
+    );
+    assert_eq!(
+        ListItem::parse("1. term :: definition", 0, true).1.tag,
+        None
+    );
+
+    let (_, item, content) = ListItem::parse("4. [@10] [ ] item", 0, false);
+    assert_eq!(item.counter, Some(10));
+    assert_eq!(item.checkbox, Some(Checkbox::Unchecked));
+    assert_eq!(content, "item");
+    assert_eq!(ListItem::parse("- [@10] item", 0, false).1.counter, None);
+    assert_eq!(ListItem::parse("1. [@a] item", 0, false).1.counter, None);
+    assert_eq!(ListItem::parse("1. [@10]item", 0, false).1.counter, None);
+
+    let (_, item, content) = ListItem::parse("4.  [@10] \t[ ]  item", 0, false);
+    assert_eq!(item.bullet, "4. ");
+    assert_eq!(item.counter, Some(10));
+    assert_eq!(item.checkbox, Some(Checkbox::Unchecked));
+    assert_eq!(content, "item");
+    assert_eq!(
+        ListItem::parse("+  [X] ", 0, false).1.checkbox,
+        Some(Checkbox::Checked)
+    );
+}
@@ -1,22 +1,28 @@
 //! Org-mode elements
 
 pub(crate) mod block;
+pub(crate) mod citation;
 pub(crate) mod clock;
 pub(crate) mod cookie;
 pub(crate) mod drawer;
 pub(crate) mod dyn_block;
 pub(crate) mod emphasis;
+pub(crate) mod entity;
 pub(crate) mod fn_def;
 pub(crate) mod fn_ref;
 pub(crate) mod inline_call;
 pub(crate) mod inline_src;
 pub(crate) mod keyword;
+pub(crate) mod latex;
+pub(crate) mod line_break;
 pub(crate) mod link;
 pub(crate) mod list;
 pub(crate) mod macros;
 pub(crate) mod planning;
 pub(crate) mod radio_target;
+pub(crate) mod raw_html;
 pub(crate) mod rule;
+pub(crate) mod script;
 pub(crate) mod snippet;
 pub(crate) mod table;
 pub(crate) mod target;
@@ -28,31 +34,57 @@ pub use self::{
         CenterBlock, CommentBlock, ExampleBlock, ExportBlock, QuoteBlock, SourceBlock,
         SpecialBlock, VerseBlock,
     },
+    citation::{Citation, CitationReference},
     clock::Clock,
     cookie::Cookie,
-    drawer::Drawer,
+    drawer::{ContentMode, Drawer},
     dyn_block::DynBlock,
+    entity::{Entity, EntityDefinition, ENTITIES},
     fn_def::FnDef,
     fn_ref::FnRef,
     inline_call::InlineCall,
     inline_src::InlineSrc,
-    keyword::{BabelCall, Keyword},
-    link::Link,
-    list::{List, ListItem},
+    keyword::{BabelCall, Caption, Keyword},
+    latex::{LatexFragment, LatexKind},
+    link::{Link, LinkFormat},
+    list::{Checkbox, List, ListBullet, ListItem, ListKind},
     macros::Macros,
     planning::Planning,
+    radio_target::RadioTarget,
+    raw_html::RawHtml,
     snippet::Snippet,
-    table::{Table, TableRow},
+    table::{Table, TableCell, TableRow},
     target::Target,
-    timestamp::{Datetime, Timestamp},
-    title::Title,
+    timestamp::{
+        Datetime, Delay, DelayType, Modifiers, Repeater, RepeaterType, TimeUnit, Timestamp,
+    },
+    title::{Priority, PropertyLine, Title},
 };
 
 use std::borrow::Cow;
 
 /// Orgize Element Enum
-#[derive(Debug)]
-#[cfg_attr(test, derive(PartialEq))]
+///
+/// # Whitespace
+///
+/// String values follow the same rules for every element:
+///
+/// + single-line values, like keyword values, titles, tags, block
+///   parameters and property values, don't start or end with spaces or tabs
+/// + `Text` is the text between two objects as written, so the newline
+///   ending a line belongs to the text before it, whatever follows, but a
+///   paragraph doesn't end with spaces, tabs or a newline
+/// + the contents of list items and footnote definitions start at the
+///   first non-blank character after the bullet, the checkbox, the tag or
+///   the label
+/// + verbatim values, like block contents, `Comment` and `FixedWidth`, are
+///   kept as written, each line ending with a newline
+/// + blank lines between elements are never part of a value
+///
+/// `\r\n` line endings are converted into `\n`. The exact text an element
+/// was parsed from is returned by `Org::source_of`, and its position by
+/// `Org::span_of`.
+#[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "ser", derive(serde::Serialize))]
 #[cfg_attr(feature = "ser", serde(tag = "type", rename_all = "kebab-case"))]
 pub enum Element<'a> {
@@ -65,68 +97,183 @@ pub enum Element<'a> {
     ExportBlock(ExportBlock<'a>),
     SourceBlock(SourceBlock<'a>),
     BabelCall(BabelCall<'a>),
+    Caption(Caption<'a>),
+    Citation(Citation<'a>),
     Section,
     Clock(Clock<'a>),
     Cookie(Cookie<'a>),
-    RadioTarget,
+    RadioTarget(RadioTarget<'a>),
     Drawer(Drawer<'a>),
     Document,
     DynBlock(DynBlock<'a>),
+    Entity(Entity<'a>),
     FnDef(FnDef<'a>),
     FnRef(FnRef<'a>),
-    Headline { level: usize },
+    Headline {
+        level: usize,
+    },
     InlineCall(InlineCall<'a>),
     InlineSrc(InlineSrc<'a>),
     Keyword(Keyword<'a>),
+    LatexFragment(LatexFragment<'a>),
     Link(Link<'a>),
     List(List),
     ListItem(ListItem<'a>),
+    /// Term of an item of a description list, the first child of the item
+    ListItemTag,
     Macros(Macros<'a>),
     Snippet(Snippet<'a>),
-    Text { value: Cow<'a, str> },
+    /// Text between objects, see the whitespace rules above
+    Text {
+        value: Cow<'a, str>,
+    },
     Paragraph,
     Rule,
+    /// `\\` at the end of a line, which forces a line break
+    LineBreak,
     Timestamp(Timestamp<'a>),
     Target(Target<'a>),
     Bold,
     Strike,
     Italic,
     Underline,
-    Verbatim { value: Cow<'a, str> },
-    Code { value: Cow<'a, str> },
-    Comment { value: Cow<'a, str> },
-    FixedWidth { value: Cow<'a, str> },
+    /// `_2` in `H_2O`, or `_{2}` if `braces` is set
+    Subscript {
+        braces: bool,
+    },
+    /// `^2` in `x^2`, or `^{2}` if `braces` is set
+    Superscript {
+        braces: bool,
+    },
+    Verbatim {
+        value: Cow<'a, str>,
+    },
+    Code {
+        value: Cow<'a, str>,
+    },
+    Comment {
+        value: Cow<'a, str>,
+    },
+    FixedWidth {
+        value: Cow<'a, str>,
+    },
+    /// A line left over from malformed syntax, kept as is
+    ///
+    /// Only the following lines become `Raw` elements, other malformed
+    /// syntax, like a `#+BEGIN_QUOTE` without `#+END_QUOTE`, stays
+    /// paragraph text:
+    ///
+    /// + an `:END:` line which doesn't close a drawer
+    /// + a `#+END_…` or `#+END:` line which doesn't close a block
+    /// + a `SCHEDULED:`, `DEADLINE:` or `CLOSED:` line directly after a
+    ///   headline's title which isn't a valid planning
+    Raw {
+        value: Cow<'a, str>,
+    },
+    /// Html inserted by code, never parsed, see [`RawHtml`]
+    ///
+    /// [`RawHtml`]: struct.RawHtml.html
+    RawHtml(RawHtml<'a>),
     Title(Title<'a>),
     Table(Table<'a>),
     TableRow(TableRow),
-    TableCell,
+    TableCell(TableCell),
 }
 
 impl Element<'_> {
     pub fn is_container(&self) -> bool {
         use Element::*;
 
-        match self {
+        matches!(
+            self,
             SpecialBlock(_)
-            | QuoteBlock(_)
-            | CenterBlock(_)
-            | VerseBlock(_)
-            | Bold
-            | Document
-            | DynBlock(_)
-            | Headline { .. }
-            | Italic
-            | List(_)
-            | ListItem(_)
-            | Paragraph
-            | Section
-            | Strike
-            | Underline
-            | Title(_)
-            | Table(_)
-            | TableRow(_)
-            | TableCell => true,
-            _ => false,
+                | QuoteBlock(_)
+                | CenterBlock(_)
+                | VerseBlock(_)
+                | Bold
+                | Caption(_)
+                | Document
+                | Drawer(_)
+                | DynBlock(_)
+                | FnDef(_)
+                | Headline { .. }
+                | Italic
+                | Link(_)
+                | List(_)
+                | ListItem(_)
+                | ListItemTag
+                | Paragraph
+                | Section
+                | Strike
+                | Underline
+                | Subscript { .. }
+                | Superscript { .. }
+                | Title(_)
+                | Table(_)
+                | TableRow(_)
+                | TableCell(_)
+        )
+    }
+
+    /// Returns the kind of this element
+    pub fn kind(&self) -> ElementKind {
+        use Element::*;
+
+        match self {
+            SpecialBlock(_) => ElementKind::SpecialBlock,
+            QuoteBlock(_) => ElementKind::QuoteBlock,
+            CenterBlock(_) => ElementKind::CenterBlock,
+            VerseBlock(_) => ElementKind::VerseBlock,
+            CommentBlock(_) => ElementKind::CommentBlock,
+            ExampleBlock(_) => ElementKind::ExampleBlock,
+            ExportBlock(_) => ElementKind::ExportBlock,
+            SourceBlock(_) => ElementKind::SourceBlock,
+            BabelCall(_) => ElementKind::BabelCall,
+            Caption(_) => ElementKind::Caption,
+            Citation(_) => ElementKind::Citation,
+            Section => ElementKind::Section,
+            Clock(_) => ElementKind::Clock,
+            Cookie(_) => ElementKind::Cookie,
+            RadioTarget(_) => ElementKind::RadioTarget,
+            Drawer(_) => ElementKind::Drawer,
+            Document => ElementKind::Document,
+            DynBlock(_) => ElementKind::DynBlock,
+            Entity(_) => ElementKind::Entity,
+            FnDef(_) => ElementKind::FnDef,
+            FnRef(_) => ElementKind::FnRef,
+            Headline { .. } => ElementKind::Headline,
+            InlineCall(_) => ElementKind::InlineCall,
+            InlineSrc(_) => ElementKind::InlineSrc,
+            Keyword(_) => ElementKind::Keyword,
+            LatexFragment(_) => ElementKind::LatexFragment,
+            Link(_) => ElementKind::Link,
+            List(_) => ElementKind::List,
+            ListItem(_) => ElementKind::ListItem,
+            ListItemTag => ElementKind::ListItemTag,
+            Macros(_) => ElementKind::Macros,
+            Snippet(_) => ElementKind::Snippet,
+            Text { .. } => ElementKind::Text,
+            Paragraph => ElementKind::Paragraph,
+            Rule => ElementKind::Rule,
+            LineBreak => ElementKind::LineBreak,
+            Timestamp(_) => ElementKind::Timestamp,
+            Target(_) => ElementKind::Target,
+            Bold => ElementKind::Bold,
+            Strike => ElementKind::Strike,
+            Italic => ElementKind::Italic,
+            Underline => ElementKind::Underline,
+            Subscript { .. } => ElementKind::Subscript,
+            Superscript { .. } => ElementKind::Superscript,
+            Verbatim { .. } => ElementKind::Verbatim,
+            Code { .. } => ElementKind::Code,
+            Comment { .. } => ElementKind::Comment,
+            FixedWidth { .. } => ElementKind::FixedWidth,
+            Raw { .. } => ElementKind::Raw,
+            RawHtml(_) => ElementKind::RawHtml,
+            Title(_) => ElementKind::Title,
+            Table(_) => ElementKind::Table,
+            TableRow(_) => ElementKind::TableRow,
+            TableCell(_) => ElementKind::TableCell,
         }
     }
 
@@ -143,22 +290,27 @@ impl Element<'_> {
             ExportBlock(e) => ExportBlock(e.into_owned()),
             SourceBlock(e) => SourceBlock(e.into_owned()),
             BabelCall(e) => BabelCall(e.into_owned()),
+            Caption(e) => Caption(e.into_owned()),
+            Citation(e) => Citation(e.into_owned()),
             Section => Section,
             Clock(e) => Clock(e.into_onwed()),
             Cookie(e) => Cookie(e.into_owned()),
-            RadioTarget => RadioTarget,
+            RadioTarget(e) => RadioTarget(e.into_owned()),
             Drawer(e) => Drawer(e.into_owned()),
             Document => Document,
             DynBlock(e) => DynBlock(e.into_owned()),
+            Entity(e) => Entity(e.into_owned()),
             FnDef(e) => FnDef(e.into_owned()),
             FnRef(e) => FnRef(e.into_owned()),
             Headline { level } => Headline { level },
             InlineCall(e) => InlineCall(e.into_owned()),
             InlineSrc(e) => InlineSrc(e.into_owned()),
             Keyword(e) => Keyword(e.into_owned()),
+            LatexFragment(e) => LatexFragment(e.into_owned()),
             Link(e) => Link(e.into_owned()),
             List(e) => List(e),
             ListItem(e) => ListItem(e.into_owned()),
+            ListItemTag => ListItemTag,
             Macros(e) => Macros(e.into_owned()),
             Snippet(e) => Snippet(e.into_owned()),
             Text { value } => Text {
@@ -166,12 +318,15 @@ impl Element<'_> {
             },
             Paragraph => Paragraph,
             Rule => Rule,
+            LineBreak => LineBreak,
             Timestamp(e) => Timestamp(e.into_owned()),
             Target(e) => Target(e.into_owned()),
             Bold => Bold,
             Strike => Strike,
             Italic => Italic,
             Underline => Underline,
+            Subscript { braces } => Subscript { braces },
+            Superscript { braces } => Superscript { braces },
             Verbatim { value } => Verbatim {
                 value: value.into_owned().into(),
             },
@@ -184,14 +339,79 @@ impl Element<'_> {
             FixedWidth { value } => FixedWidth {
                 value: value.into_owned().into(),
             },
+            Raw { value } => Raw {
+                value: value.into_owned().into(),
+            },
+            RawHtml(e) => RawHtml(e.into_owned()),
             Title(e) => Title(e.into_owned()),
             Table(e) => Table(e.into_owned()),
             TableRow(e) => TableRow(e),
-            TableCell => TableCell,
+            TableCell(e) => TableCell(e),
         }
     }
 }
 
+/// Kind of an `Element`, without its data
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "ser", derive(serde::Serialize))]
+#[cfg_attr(feature = "ser", serde(rename_all = "kebab-case"))]
+pub enum ElementKind {
+    SpecialBlock,
+    QuoteBlock,
+    CenterBlock,
+    VerseBlock,
+    CommentBlock,
+    ExampleBlock,
+    ExportBlock,
+    SourceBlock,
+    BabelCall,
+    Caption,
+    Citation,
+    Section,
+    Clock,
+    Cookie,
+    RadioTarget,
+    Drawer,
+    Document,
+    DynBlock,
+    Entity,
+    FnDef,
+    FnRef,
+    Headline,
+    InlineCall,
+    InlineSrc,
+    Keyword,
+    LatexFragment,
+    Link,
+    List,
+    ListItem,
+    ListItemTag,
+    Macros,
+    Snippet,
+    Text,
+    Paragraph,
+    Rule,
+    LineBreak,
+    Timestamp,
+    Target,
+    Bold,
+    Strike,
+    Italic,
+    Underline,
+    Subscript,
+    Superscript,
+    Verbatim,
+    Code,
+    Comment,
+    FixedWidth,
+    Raw,
+    RawHtml,
+    Title,
+    Table,
+    TableRow,
+    TableCell,
+}
+
 macro_rules! impl_from {
     ($($ele0:ident),*; $($ele1:ident),*) => {
         $(
@@ -213,12 +433,15 @@ macro_rules! impl_from {
 
 impl_from!(
     BabelCall,
+    Caption,
+    Citation,
     CenterBlock,
     Clock,
     CommentBlock,
     Cookie,
     Drawer,
     DynBlock,
+    Entity,
     ExampleBlock,
     ExportBlock,
     FnDef,
@@ -226,10 +449,13 @@ impl_from!(
     InlineCall,
     InlineSrc,
     Keyword,
+    LatexFragment,
     Link,
     ListItem,
     Macros,
     QuoteBlock,
+    RadioTarget,
+    RawHtml,
     Snippet,
     SourceBlock,
     SpecialBlock,
@@ -239,5 +465,6 @@ impl_from!(
     Title,
     VerseBlock;
     List,
-    TableRow
+    TableRow,
+    TableCell
 );
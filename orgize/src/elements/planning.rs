@@ -3,9 +3,8 @@ use memchr::memchr;
 use crate::elements::Timestamp;
 
 /// Palnning element
-#[cfg_attr(test, derive(PartialEq))]
 #[cfg_attr(feature = "ser", derive(serde::Serialize))]
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Planning<'a> {
     /// Timestamp associated to deadline keyword
     #[cfg_attr(feature = "ser", serde(skip_serializing_if = "Option::is_none"))]
@@ -71,7 +70,7 @@ impl Planning<'_> {
 
 #[test]
 fn prase() {
-    use crate::elements::Datetime;
+    use crate::elements::{Datetime, Modifiers};
 
     assert_eq!(
         Planning::parse("SCHEDULED: <2019-04-08 Mon>\n"),
@@ -87,8 +86,7 @@ fn prase() {
                         hour: None,
                         minute: None
                     },
-                    repeater: None,
-                    delay: None
+                    modifiers: Modifiers::default()
                 }),
                 deadline: None,
                 closed: None,
@@ -1,3 +1,5 @@
+use std::borrow::Cow;
+
 use nom::{
     bytes::complete::{tag, take_while},
     combinator::verify,
@@ -8,9 +10,55 @@ use nom::{
 
 // TODO: text-markup, entities, latex-fragments, subscript and superscript
 
-#[inline]
-pub fn parse_radio_target(input: &str) -> Option<(&str, &str)> {
-    parse_radio_target_internal::<()>(input).ok()
+/// Radio Target Object
+///
+/// Its text is linked wherever it occurs in the document once
+/// `Org::resolve_radio_targets` is called.
+#[cfg_attr(feature = "ser", derive(serde::Serialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct RadioTarget<'a> {
+    /// Text of the target, as written in the document
+    pub target: Cow<'a, str>,
+}
+
+impl RadioTarget<'_> {
+    #[inline]
+    pub(crate) fn parse(input: &str) -> Option<(&str, RadioTarget<'_>)> {
+        let (input, target) = parse_radio_target_internal::<()>(input).ok()?;
+        Some((
+            input,
+            RadioTarget {
+                target: target.into(),
+            },
+        ))
+    }
+
+    /// Returns the id of the anchor of this target, made of its lowercase
+    /// words joined with dashes, so that targets differing only by their
+    /// case or spacing share it
+    ///
+    /// ```rust
+    /// use orgize::elements::RadioTarget;
+    ///
+    /// let target = RadioTarget {
+    ///     target: "Important  Term".into(),
+    /// };
+    /// assert_eq!(target.id(), "radio-important-term");
+    /// ```
+    pub fn id(&self) -> String {
+        let words: Vec<String> = self
+            .target
+            .split_whitespace()
+            .map(str::to_lowercase)
+            .collect();
+        format!("radio-{}", words.join("-"))
+    }
+
+    pub fn into_owned(self) -> RadioTarget<'static> {
+        RadioTarget {
+            target: self.target.into_owned().into(),
+        }
+    }
 }
 
 #[inline]
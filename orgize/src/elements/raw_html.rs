@@ -0,0 +1,34 @@
+use std::borrow::Cow;
+
+/// Html Element, written as is by the html export
+///
+/// It holds html produced by trusted code, like math rendered on a server,
+/// and is never produced by the parser: it's only created by
+/// `RawHtml::new`, and inserted with `Org::insert_raw_html_after` or
+/// through the arena.
+///
+/// The html export writes it as is, even in safe mode, and the org export
+/// as a `#+BEGIN_EXPORT html` block. Other exports skip it, see
+/// `Org::export_diagnostics`.
+#[cfg_attr(feature = "ser", derive(serde::Serialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct RawHtml<'a> {
+    html: Cow<'a, str>,
+}
+
+impl<'a> RawHtml<'a> {
+    pub fn new<S: Into<Cow<'a, str>>>(html: S) -> RawHtml<'a> {
+        RawHtml { html: html.into() }
+    }
+
+    /// Returns the html of this element
+    pub fn html(&self) -> &str {
+        &self.html
+    }
+
+    pub fn into_owned(self) -> RawHtml<'static> {
+        RawHtml {
+            html: self.html.into_owned().into(),
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use crate::config::SubSuperscripts;
+
+/// Parses the script following `_` or `^` at the start of `text`, returns
+/// the text after it, its contents and whether they're in braces
+///
+/// Scripts are `{...}` and `(...)` with balanced delimiters, `*`, or an
+/// optional sign followed by alphanumerics, `.`, `,` and `\`, ending with an
+/// alphanumeric. Parentheses are part of the contents, unlike braces.
+#[inline]
+pub(crate) fn parse_script(text: &str, mode: SubSuperscripts) -> Option<(&str, &str, bool)> {
+    debug_assert!(text.starts_with(&['_', '^'][..]));
+
+    let script = &text[1..];
+    if mode == SubSuperscripts::Never {
+        return None;
+    }
+    if script.starts_with('{') {
+        let end = balanced(script, b'{', b'}').filter(|&end| end > 1)?;
+        return Some((&script[end + 1..], &script[1..end], true));
+    }
+    if mode == SubSuperscripts::Braces {
+        return None;
+    }
+    if script.starts_with('(') {
+        let end = balanced(script, b'(', b')')?;
+        return Some((&script[end + 1..], &script[0..=end], false));
+    }
+    if script.starts_with('*') {
+        return Some((&script[1..], &script[0..1], false));
+    }
+
+    let sign = if script.starts_with(&['+', '-'][..]) {
+        1
+    } else {
+        0
+    };
+    let len = script[sign..]
+        .find(|c: char| !(c.is_alphanumeric() || c == '.' || c == ',' || c == '\\'))
+        .unwrap_or(script.len() - sign);
+    let end = script[sign..sign + len]
+        .rfind(char::is_alphanumeric)
+        .map(|i| sign + i + script[sign + i..].chars().next().unwrap().len_utf8())?;
+
+    Some((&script[end..], &script[0..end], false))
+}
+
+/// Returns the position of the delimiter closing the one at the start of
+/// `text`, on the same line
+fn balanced(text: &str, open: u8, close: u8) -> Option<usize> {
+    let mut depth = 0;
+    for (i, &byte) in text.as_bytes().iter().enumerate() {
+        if byte == open {
+            depth += 1;
+        } else if byte == close {
+            depth -= 1;
+            if depth == 0 {
+                return Some(i);
+            }
+        } else if byte == b'\n' {
+            return None;
+        }
+    }
+    None
+}
+
+#[test]
+fn parse() {
+    use SubSuperscripts::*;
+
+    assert_eq!(parse_script("_2O", Always), Some(("", "2O", false)));
+    assert_eq!(parse_script("_2 O", Always), Some((" O", "2", false)));
+    assert_eq!(parse_script("^2,", Always), Some((",", "2", false)));
+    assert_eq!(parse_script("^-1.5.", Always), Some((".", "-1.5", false)));
+    assert_eq!(parse_script("^*", Always), Some(("", "*", false)));
+    assert_eq!(
+        parse_script("^{2n{+}1} x", Always),
+        Some((" x", "2n{+}1", true))
+    );
+    assert_eq!(
+        parse_script("_(a(b))c", Always),
+        Some(("c", "(a(b))", false))
+    );
+    assert_eq!(parse_script("_{a", Always), None);
+    assert_eq!(parse_script("_{}", Always), None);
+    assert_eq!(parse_script("_{a\n}", Always), None);
+    assert_eq!(parse_script("_ a", Always), None);
+    assert_eq!(parse_script("_-", Always), None);
+    assert_eq!(parse_script("_name", Braces), None);
+    assert_eq!(parse_script("_{i}", Braces), Some(("", "i", true)));
+    assert_eq!(parse_script("_{i}", Never), None);
+}
@@ -9,14 +9,18 @@ use nom::{
 use crate::parsers::{line, take_lines_while};
 
 /// Table Elemenet
-#[derive(Debug)]
-#[cfg_attr(test, derive(PartialEq))]
+#[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "ser", derive(serde::Serialize))]
 #[cfg_attr(feature = "ser", serde(tag = "table_type"))]
 pub enum Table<'a> {
     /// "org" type table
     #[cfg_attr(feature = "ser", serde(rename = "org"))]
-    Org { tblfm: Option<Cow<'a, str>> },
+    Org {
+        /// Formulas of the `#+TBLFM:` lines following the table, one per line
+        tblfm: Option<Cow<'a, str>>,
+        /// `true` if a rule separates header rows from body rows
+        has_header: bool,
+    },
     /// "table.el" type table
     #[cfg_attr(feature = "ser", serde(rename = "table.el"))]
     TableEl { value: Cow<'a, str> },
@@ -25,8 +29,9 @@ pub enum Table<'a> {
 impl Table<'_> {
     pub fn into_owned(self) -> Table<'static> {
         match self {
-            Table::Org { tblfm } => Table::Org {
+            Table::Org { tblfm, has_header } => Table::Org {
                 tblfm: tblfm.map(Into::into).map(Cow::Owned),
+                has_header,
             },
             Table::TableEl { value } => Table::TableEl {
                 value: value.into_owned().into(),
@@ -36,25 +41,86 @@ impl Table<'_> {
 }
 
 /// Table Row Elemenet
-#[derive(Debug)]
-#[cfg_attr(test, derive(PartialEq))]
+///
+/// Rows before the first rule are header rows if the table has rows after
+/// that rule, other rows are body rows.
+#[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "ser", derive(serde::Serialize))]
 #[cfg_attr(feature = "ser", serde(tag = "table_row_type"))]
 #[cfg_attr(feature = "ser", serde(rename_all = "kebab-case"))]
 pub enum TableRow {
-    Standard,
-    Rule,
+    Header,
+    Body,
+    /// Rule separating the header rows from the body rows
+    HeaderRule,
+    /// Any other rule, like `|---+---|`
+    BodyRule,
 }
 
 impl TableRow {
-    pub(crate) fn parse(input: &str) -> Option<TableRow> {
-        if input.starts_with("|-") {
-            Some(TableRow::Rule)
-        } else if input.starts_with('|') {
-            Some(TableRow::Standard)
-        } else {
-            None
+    /// Returns `true` for rules, which have no cells
+    pub fn is_rule(&self) -> bool {
+        match self {
+            TableRow::HeaderRule | TableRow::BodyRule => true,
+            TableRow::Header | TableRow::Body => false,
+        }
+    }
+}
+
+/// Table Cell Element, whose contents are objects
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "ser", derive(serde::Serialize))]
+#[cfg_attr(feature = "ser", serde(tag = "table_cell_type"))]
+#[cfg_attr(feature = "ser", serde(rename_all = "kebab-case"))]
+pub enum TableCell {
+    Header,
+    Body,
+}
+
+/// Returns the rows of an org table and the index of the rule separating
+/// its header rows from its body rows, if any
+pub(crate) fn parse_org_table(input: &str) -> Option<(&str, Vec<&str>, Option<usize>)> {
+    if !input.trim_start().starts_with('|') {
+        return None;
+    }
+
+    let (input, content) = take_lines_while(|line| line.trim_start().starts_with('|'))(input);
+    let rows: Vec<_> = content.lines().map(str::trim).collect();
+
+    let is_rule = |row: &&str| row.starts_with("|-");
+    let header_rule = rows.iter().position(is_rule).filter(|&i| {
+        rows[0..i].iter().any(|row| !is_rule(row)) && rows[i..].iter().any(|row| !is_rule(row))
+    });
+
+    Some((input, rows, header_rule))
+}
+
+/// Parses the `#+TBLFM:` lines following a table
+pub(crate) fn parse_tblfm(input: &str) -> (&str, Option<Cow<'_, str>>) {
+    let (input, content) = take_lines_while(|line| tblfm_value(line).is_some())(input);
+
+    let mut formulas = content.lines().filter_map(tblfm_value);
+    let tblfm = match (formulas.next(), formulas.next()) {
+        (None, _) => None,
+        (Some(formula), None) => Some(formula.into()),
+        (Some(first), Some(second)) => {
+            let mut tblfm = format!("{}\n{}", first, second);
+            for formula in formulas {
+                tblfm.push('\n');
+                tblfm.push_str(formula);
+            }
+            Some(tblfm.into())
         }
+    };
+
+    (input, tblfm)
+}
+
+fn tblfm_value(line: &str) -> Option<&str> {
+    let line = line.trim();
+    match line.get(0..8) {
+        Some(key) if key.eq_ignore_ascii_case("#+TBLFM:") => Some(line[8..].trim()),
+        _ => None,
     }
 }
 
@@ -70,8 +136,10 @@ fn parse_table_el_internal<'a, E: ParseError<&'a str>>(
         s.starts_with("+-") && s.as_bytes().iter().all(|&c| c == b'+' || c == b'-')
     }))(input)?;
 
-    let (input, content) =
-        take_lines_while(|line| line.starts_with('|') || line.starts_with('+'))(input);
+    let (input, content) = take_lines_while(|line| {
+        let line = line.trim_start();
+        line.starts_with('|') || line.starts_with('+')
+    })(input);
 
     Ok((input, content))
 }
@@ -100,3 +168,27 @@ fn parse_table_el_() {
     assert!(parse_table_el_internal::<VerboseError<&str>>("").is_err());
     assert!(parse_table_el_internal::<VerboseError<&str>>("+----|---").is_err());
 }
+
+#[test]
+fn parse_org_table_() {
+    assert_eq!(
+        parse_org_table("| a |\n|---+---|\n| b |\n|-\n  | c |\n\ntext"),
+        Some((
+            "\ntext",
+            vec!["| a |", "|---+---|", "| b |", "|-", "| c |"],
+            Some(1)
+        ))
+    );
+    assert_eq!(
+        parse_org_table("|---|\n| a |\n|---|\n"),
+        Some(("", vec!["|---|", "| a |", "|---|"], None))
+    );
+    assert_eq!(parse_org_table("+---+\n"), None);
+
+    assert_eq!(
+        parse_tblfm("#+TBLFM: $2=1\n #+tblfm: @1=2 \ntext"),
+        ("text", Some("$2=1\n@1=2".into()))
+    );
+    assert_eq!(parse_tblfm("#+TBLFM: $2=1\n"), ("", Some("$2=1".into())));
+    assert_eq!(parse_tblfm("text"), ("text", None));
+}
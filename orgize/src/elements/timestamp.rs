@@ -1,18 +1,19 @@
 use std::borrow::Cow;
+use std::fmt;
 
 use nom::{
+    branch::alt,
     bytes::complete::{tag, take, take_till, take_while, take_while_m_n},
-    character::complete::{space0, space1},
-    combinator::{map, map_res, opt},
+    character::complete::{digit1, one_of, space0, space1},
+    combinator::{map, map_res, opt, value},
     error::ParseError,
     sequence::preceded,
     IResult,
 };
 
 /// Orgize Datetime Struct
-#[cfg_attr(test, derive(PartialEq))]
 #[cfg_attr(feature = "ser", derive(serde::Serialize))]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Datetime<'a> {
     pub year: u16,
     pub month: u8,
@@ -25,6 +26,18 @@ pub struct Datetime<'a> {
 }
 
 impl Datetime<'_> {
+    /// Returns `false` if the date or the time doesn't exist, like
+    /// `2021-02-29` or `25:00`
+    ///
+    /// Such datetimes are parsed, but can't be converted into `chrono` types.
+    pub fn is_valid(&self) -> bool {
+        (1..=12).contains(&self.month)
+            && self.day >= 1
+            && u32::from(self.day) <= days_in_month(self.year.into(), self.month.into())
+            && self.hour.unwrap_or(0) <= 23
+            && self.minute.unwrap_or(0) <= 59
+    }
+
     pub fn into_owned(self) -> Datetime<'static> {
         Datetime {
             year: self.year,
@@ -37,6 +50,15 @@ impl Datetime<'_> {
     }
 }
 
+pub(crate) fn days_in_month(year: u32, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
 #[cfg(feature = "chrono")]
 mod chrono {
     use super::Datetime;
@@ -96,41 +118,40 @@ mod chrono {
 }
 
 /// Timestamp Object
-#[cfg_attr(test, derive(PartialEq))]
 #[cfg_attr(feature = "ser", derive(serde::Serialize))]
 #[cfg_attr(feature = "ser", serde(rename_all = "kebab-case"))]
 #[cfg_attr(feature = "ser", serde(tag = "timestamp_type"))]
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Timestamp<'a> {
     Active {
         start: Datetime<'a>,
-        #[cfg_attr(feature = "ser", serde(skip_serializing_if = "Option::is_none"))]
-        repeater: Option<Cow<'a, str>>,
-        #[cfg_attr(feature = "ser", serde(skip_serializing_if = "Option::is_none"))]
-        delay: Option<Cow<'a, str>>,
+        #[cfg_attr(feature = "ser", serde(flatten))]
+        modifiers: Modifiers,
     },
     Inactive {
         start: Datetime<'a>,
-        #[cfg_attr(feature = "ser", serde(skip_serializing_if = "Option::is_none"))]
-        repeater: Option<Cow<'a, str>>,
-        #[cfg_attr(feature = "ser", serde(skip_serializing_if = "Option::is_none"))]
-        delay: Option<Cow<'a, str>>,
+        #[cfg_attr(feature = "ser", serde(flatten))]
+        modifiers: Modifiers,
     },
     ActiveRange {
         start: Datetime<'a>,
         end: Datetime<'a>,
-        #[cfg_attr(feature = "ser", serde(skip_serializing_if = "Option::is_none"))]
-        repeater: Option<Cow<'a, str>>,
-        #[cfg_attr(feature = "ser", serde(skip_serializing_if = "Option::is_none"))]
-        delay: Option<Cow<'a, str>>,
+        /// Modifiers of the start, or of the whole range if it's written
+        /// as a time range like `<2023-01-01 Sun 10:00-12:00 +1w>`
+        #[cfg_attr(feature = "ser", serde(flatten))]
+        modifiers: Modifiers,
+        #[cfg_attr(feature = "ser", serde(skip_serializing_if = "Modifiers::is_empty"))]
+        end_modifiers: Modifiers,
     },
     InactiveRange {
         start: Datetime<'a>,
         end: Datetime<'a>,
-        #[cfg_attr(feature = "ser", serde(skip_serializing_if = "Option::is_none"))]
-        repeater: Option<Cow<'a, str>>,
-        #[cfg_attr(feature = "ser", serde(skip_serializing_if = "Option::is_none"))]
-        delay: Option<Cow<'a, str>>,
+        /// Modifiers of the start, or of the whole range if it's written
+        /// as a time range like `[2023-01-01 Sun 10:00-12:00 +1w]`
+        #[cfg_attr(feature = "ser", serde(flatten))]
+        modifiers: Modifiers,
+        #[cfg_attr(feature = "ser", serde(skip_serializing_if = "Modifiers::is_empty"))]
+        end_modifiers: Modifiers,
     },
     Diary {
         value: Cow<'a, str>,
@@ -150,47 +171,88 @@ impl Timestamp<'_> {
         parse_diary::<()>(input).ok()
     }
 
+    /// Returns the repeater of the timestamp, e.g. `+1w` for
+    /// `<2023-01-01 Sun +1w>`
+    ///
+    /// The repeater of a range is the one of its start, or of its end if
+    /// its start has none.
+    pub fn repeater(&self) -> Option<&Repeater> {
+        match self {
+            Timestamp::Active { modifiers, .. } | Timestamp::Inactive { modifiers, .. } => {
+                modifiers.repeater.as_ref()
+            }
+            Timestamp::ActiveRange {
+                modifiers,
+                end_modifiers,
+                ..
+            }
+            | Timestamp::InactiveRange {
+                modifiers,
+                end_modifiers,
+                ..
+            } => modifiers
+                .repeater
+                .as_ref()
+                .or(end_modifiers.repeater.as_ref()),
+            Timestamp::Diary { .. } => None,
+        }
+    }
+
+    /// Returns the warning delay of the timestamp, e.g. `-2d` for
+    /// `<2023-01-01 Sun -2d>`
+    ///
+    /// Like the repeater, the delay of a range is the one of its start, or
+    /// of its end if its start has none.
+    pub fn delay(&self) -> Option<&Delay> {
+        match self {
+            Timestamp::Active { modifiers, .. } | Timestamp::Inactive { modifiers, .. } => {
+                modifiers.delay.as_ref()
+            }
+            Timestamp::ActiveRange {
+                modifiers,
+                end_modifiers,
+                ..
+            }
+            | Timestamp::InactiveRange {
+                modifiers,
+                end_modifiers,
+                ..
+            } => modifiers.delay.as_ref().or(end_modifiers.delay.as_ref()),
+            Timestamp::Diary { .. } => None,
+        }
+    }
+
     pub fn into_owned(self) -> Timestamp<'static> {
         match self {
-            Timestamp::Active {
-                start,
-                repeater,
-                delay,
-            } => Timestamp::Active {
+            Timestamp::Active { start, modifiers } => Timestamp::Active {
                 start: start.into_owned(),
-                repeater: repeater.map(Into::into).map(Cow::Owned),
-                delay: delay.map(Into::into).map(Cow::Owned),
+                modifiers,
             },
-            Timestamp::Inactive {
-                start,
-                repeater,
-                delay,
-            } => Timestamp::Inactive {
+            Timestamp::Inactive { start, modifiers } => Timestamp::Inactive {
                 start: start.into_owned(),
-                repeater: repeater.map(Into::into).map(Cow::Owned),
-                delay: delay.map(Into::into).map(Cow::Owned),
+                modifiers,
             },
             Timestamp::ActiveRange {
                 start,
                 end,
-                repeater,
-                delay,
+                modifiers,
+                end_modifiers,
             } => Timestamp::ActiveRange {
                 start: start.into_owned(),
                 end: end.into_owned(),
-                repeater: repeater.map(Into::into).map(Cow::Owned),
-                delay: delay.map(Into::into).map(Cow::Owned),
+                modifiers,
+                end_modifiers,
             },
             Timestamp::InactiveRange {
                 start,
                 end,
-                repeater,
-                delay,
+                modifiers,
+                end_modifiers,
             } => Timestamp::InactiveRange {
                 start: start.into_owned(),
                 end: end.into_owned(),
-                repeater: repeater.map(Into::into).map(Cow::Owned),
-                delay: delay.map(Into::into).map(Cow::Owned),
+                modifiers,
+                end_modifiers,
             },
             Timestamp::Diary { value } => Timestamp::Diary {
                 value: value.into_owned().into(),
@@ -207,8 +269,8 @@ pub fn parse_active<'a, E: ParseError<&'a str>>(
 
     if input.starts_with('-') {
         let (input, (hour, minute)) = parse_time(&input[1..])?;
+        let (input, modifiers) = parse_modifiers(input)?;
         let (input, _) = space0(input)?;
-        // TODO: delay-or-repeater
         let (input, _) = tag(">")(input)?;
         let mut end = start.clone();
         end.hour = Some(hour);
@@ -218,39 +280,32 @@ pub fn parse_active<'a, E: ParseError<&'a str>>(
             Timestamp::ActiveRange {
                 start,
                 end,
-                repeater: None,
-                delay: None,
+                modifiers,
+                end_modifiers: Modifiers::default(),
             },
         ));
     }
 
+    let (input, modifiers) = parse_modifiers(input)?;
     let (input, _) = space0(input)?;
-    // TODO: delay-or-repeater
     let (input, _) = tag(">")(input)?;
 
     if input.starts_with("--<") {
         let (input, end) = parse_datetime(&input["--<".len()..])?;
+        let (input, end_modifiers) = parse_modifiers(input)?;
         let (input, _) = space0(input)?;
-        // TODO: delay-or-repeater
         let (input, _) = tag(">")(input)?;
         Ok((
             input,
             Timestamp::ActiveRange {
                 start,
                 end,
-                repeater: None,
-                delay: None,
+                modifiers,
+                end_modifiers,
             },
         ))
     } else {
-        Ok((
-            input,
-            Timestamp::Active {
-                start,
-                repeater: None,
-                delay: None,
-            },
-        ))
+        Ok((input, Timestamp::Active { start, modifiers }))
     }
 }
 
@@ -262,8 +317,8 @@ pub fn parse_inactive<'a, E: ParseError<&'a str>>(
 
     if input.starts_with('-') {
         let (input, (hour, minute)) = parse_time(&input[1..])?;
+        let (input, modifiers) = parse_modifiers(input)?;
         let (input, _) = space0(input)?;
-        // TODO: delay-or-repeater
         let (input, _) = tag("]")(input)?;
         let mut end = start.clone();
         end.hour = Some(hour);
@@ -273,39 +328,32 @@ pub fn parse_inactive<'a, E: ParseError<&'a str>>(
             Timestamp::InactiveRange {
                 start,
                 end,
-                repeater: None,
-                delay: None,
+                modifiers,
+                end_modifiers: Modifiers::default(),
             },
         ));
     }
 
+    let (input, modifiers) = parse_modifiers(input)?;
     let (input, _) = space0(input)?;
-    // TODO: delay-or-repeater
     let (input, _) = tag("]")(input)?;
 
     if input.starts_with("--[") {
         let (input, end) = parse_datetime(&input["--[".len()..])?;
+        let (input, end_modifiers) = parse_modifiers(input)?;
         let (input, _) = space0(input)?;
-        // TODO: delay-or-repeater
         let (input, _) = tag("]")(input)?;
         Ok((
             input,
             Timestamp::InactiveRange {
                 start,
                 end,
-                repeater: None,
-                delay: None,
+                modifiers,
+                end_modifiers,
             },
         ))
     } else {
-        Ok((
-            input,
-            Timestamp::Inactive {
-                start,
-                repeater: None,
-                delay: None,
-            },
-        ))
+        Ok((input, Timestamp::Inactive { start, modifiers }))
     }
 }
 
@@ -367,52 +415,217 @@ fn parse_datetime<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str
     ))
 }
 
-// TODO
-// #[cfg_attr(test, derive(PartialEq))]
-// #[cfg_attr(feature = "ser", derive(serde::Serialize))]
-// #[derive(Debug, Copy, Clone)]
-// pub enum RepeaterType {
-//     Cumulate,
-//     CatchUp,
-//     Restart,
-// }
-
-// #[cfg_attr(test, derive(PartialEq))]
-// #[cfg_attr(feature = "ser", derive(serde::Serialize))]
-// #[derive(Debug, Copy, Clone)]
-// pub enum DelayType {
-//     All,
-//     First,
-// }
-
-// #[cfg_attr(test, derive(PartialEq))]
-// #[cfg_attr(feature = "ser", derive(serde::Serialize))]
-// #[derive(Debug, Copy, Clone)]
-// pub enum TimeUnit {
-//     Hour,
-//     Day,
-//     Week,
-//     Month,
-//     Year,
-// }
-
-// #[cfg_attr(test, derive(PartialEq))]
-// #[cfg_attr(feature = "ser", derive(serde::Serialize))]
-// #[derive(Debug, Copy, Clone)]
-// pub struct Repeater {
-//     pub ty: RepeaterType,
-//     pub value: usize,
-//     pub unit: TimeUnit,
-// }
-
-// #[cfg_attr(test, derive(PartialEq))]
-// #[cfg_attr(feature = "ser", derive(serde::Serialize))]
-// #[derive(Debug, Copy, Clone)]
-// pub struct Delay {
-//     pub ty: DelayType,
-//     pub value: usize,
-//     pub unit: TimeUnit,
-// }
+/// Parses the repeater and the delay following the date and the time of a
+/// timestamp, in any order
+fn parse_modifiers<'a, E: ParseError<&'a str>>(
+    mut input: &'a str,
+) -> IResult<&'a str, Modifiers, E> {
+    let mut modifiers = Modifiers::default();
+
+    loop {
+        if modifiers.repeater.is_none() {
+            if let Ok((rest, parsed)) = preceded(space1, parse_repeater::<E>)(input) {
+                input = rest;
+                modifiers.repeater = Some(parsed);
+                modifiers.delay_first = modifiers.delay.is_some();
+                continue;
+            }
+        }
+        if modifiers.delay.is_none() {
+            if let Ok((rest, parsed)) = preceded(space1, parse_delay::<E>)(input) {
+                input = rest;
+                modifiers.delay = Some(parsed);
+                continue;
+            }
+        }
+        return Ok((input, modifiers));
+    }
+}
+
+fn parse_repeater<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, Repeater, E> {
+    let (input, ty) = alt((
+        value(RepeaterType::CatchUp, tag("++")),
+        value(RepeaterType::Restart, tag(".+")),
+        value(RepeaterType::Cumulate, tag("+")),
+    ))(input)?;
+    let (input, (value, unit)) = parse_interval(input)?;
+    Ok((input, Repeater { ty, value, unit }))
+}
+
+fn parse_delay<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, Delay, E> {
+    let (input, ty) = alt((
+        value(DelayType::First, tag("--")),
+        value(DelayType::All, tag("-")),
+    ))(input)?;
+    let (input, (value, unit)) = parse_interval(input)?;
+    Ok((input, Delay { ty, value, unit }))
+}
+
+fn parse_interval<'a, E: ParseError<&'a str>>(
+    input: &'a str,
+) -> IResult<&'a str, (usize, TimeUnit), E> {
+    let (input, value) = map_res(digit1, str::parse)(input)?;
+    let (input, unit) = map(one_of("hdwmy"), |unit| match unit {
+        'h' => TimeUnit::Hour,
+        'd' => TimeUnit::Day,
+        'w' => TimeUnit::Week,
+        'm' => TimeUnit::Month,
+        _ => TimeUnit::Year,
+    })(input)?;
+    Ok((input, (value, unit)))
+}
+
+/// Kind of a repeater, given by its mark
+#[cfg_attr(feature = "ser", derive(serde::Serialize))]
+#[cfg_attr(feature = "ser", serde(rename_all = "kebab-case"))]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RepeaterType {
+    /// `+`, shifts the date by the interval once
+    Cumulate,
+    /// `++`, shifts the date by the interval until it's in the future
+    CatchUp,
+    /// `.+`, shifts the date to the interval after today
+    Restart,
+}
+
+/// Kind of a warning delay, given by its mark
+#[cfg_attr(feature = "ser", derive(serde::Serialize))]
+#[cfg_attr(feature = "ser", serde(rename_all = "kebab-case"))]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DelayType {
+    /// `-`, warns before each occurrence of a repeated timestamp
+    All,
+    /// `--`, warns before its first occurrence only
+    First,
+}
+
+/// Unit of the interval of a repeater or a delay
+#[cfg_attr(feature = "ser", derive(serde::Serialize))]
+#[cfg_attr(feature = "ser", serde(rename_all = "kebab-case"))]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TimeUnit {
+    Hour,
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+impl fmt::Display for TimeUnit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TimeUnit::Hour => write!(f, "h"),
+            TimeUnit::Day => write!(f, "d"),
+            TimeUnit::Week => write!(f, "w"),
+            TimeUnit::Month => write!(f, "m"),
+            TimeUnit::Year => write!(f, "y"),
+        }
+    }
+}
+
+/// Repeater of a timestamp, e.g. `.+1w`
+///
+/// ```rust
+/// use orgize::elements::{Repeater, RepeaterType, TimeUnit};
+///
+/// let repeater = Repeater {
+///     ty: RepeaterType::Restart,
+///     value: 1,
+///     unit: TimeUnit::Week,
+/// };
+/// assert_eq!(repeater.to_string(), ".+1w");
+/// ```
+#[cfg_attr(feature = "ser", derive(serde::Serialize))]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Repeater {
+    pub ty: RepeaterType,
+    pub value: usize,
+    pub unit: TimeUnit,
+}
+
+impl fmt::Display for Repeater {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mark = match self.ty {
+            RepeaterType::Cumulate => "+",
+            RepeaterType::CatchUp => "++",
+            RepeaterType::Restart => ".+",
+        };
+        write!(f, "{}{}{}", mark, self.value, self.unit)
+    }
+}
+
+/// Warning delay of a timestamp, e.g. `-2d`
+#[cfg_attr(feature = "ser", derive(serde::Serialize))]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Delay {
+    pub ty: DelayType,
+    pub value: usize,
+    pub unit: TimeUnit,
+}
+
+impl fmt::Display for Delay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mark = match self.ty {
+            DelayType::All => "-",
+            DelayType::First => "--",
+        };
+        write!(f, "{}{}{}", mark, self.value, self.unit)
+    }
+}
+
+/// Repeater and warning delay of a timestamp, or of one end of a range,
+/// e.g. `-2d +1w`
+///
+/// ```rust
+/// use orgize::elements::{Delay, DelayType, Modifiers, Repeater, RepeaterType, TimeUnit};
+///
+/// let modifiers = Modifiers {
+///     repeater: Some(Repeater {
+///         ty: RepeaterType::Cumulate,
+///         value: 1,
+///         unit: TimeUnit::Week,
+///     }),
+///     delay: Some(Delay {
+///         ty: DelayType::All,
+///         value: 2,
+///         unit: TimeUnit::Day,
+///     }),
+///     delay_first: true,
+/// };
+/// assert_eq!(modifiers.to_string(), "-2d +1w");
+/// ```
+#[cfg_attr(feature = "ser", derive(serde::Serialize))]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct Modifiers {
+    #[cfg_attr(feature = "ser", serde(skip_serializing_if = "Option::is_none"))]
+    pub repeater: Option<Repeater>,
+    #[cfg_attr(feature = "ser", serde(skip_serializing_if = "Option::is_none"))]
+    pub delay: Option<Delay>,
+    /// Whether the delay is written before the repeater
+    #[cfg_attr(feature = "ser", serde(skip))]
+    pub delay_first: bool,
+}
+
+impl Modifiers {
+    /// Returns `true` if there's neither a repeater nor a delay.
+    pub fn is_empty(&self) -> bool {
+        self.repeater.is_none() && self.delay.is_none()
+    }
+}
+
+impl fmt::Display for Modifiers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.repeater, &self.delay) {
+            (Some(repeater), Some(delay)) if self.delay_first => {
+                write!(f, "{} {}", delay, repeater)
+            }
+            (Some(repeater), Some(delay)) => write!(f, "{} {}", repeater, delay),
+            (Some(repeater), None) => write!(f, "{}", repeater),
+            (None, Some(delay)) => write!(f, "{}", delay),
+            (None, None) => Ok(()),
+        }
+    }
+}
 
 #[test]
 fn parse() {
@@ -431,8 +644,7 @@ fn parse() {
                     hour: None,
                     minute: None
                 },
-                repeater: None,
-                delay: None,
+                modifiers: Modifiers::default(),
             },
         ))
     );
@@ -457,8 +669,8 @@ fn parse() {
                     hour: Some(10),
                     minute: Some(39),
                 },
-                repeater: None,
-                delay: None
+                modifiers: Modifiers::default(),
+                end_modifiers: Modifiers::default(),
             },
         ))
     );
@@ -483,9 +695,63 @@ fn parse() {
                     hour: Some(10),
                     minute: Some(39),
                 },
-                repeater: None,
-                delay: None
+                modifiers: Modifiers::default(),
+                end_modifiers: Modifiers::default(),
             },
         ))
     );
+
+    let repeater = |ty, value, unit| Some(Repeater { ty, value, unit });
+    let delay = |ty, value, unit| Some(Delay { ty, value, unit });
+    let modifiers = |text| match parse_active::<VerboseError<&str>>(text) {
+        Ok(("", timestamp)) => (timestamp.repeater().cloned(), timestamp.delay().cloned()),
+        result => panic!("{:?}", result),
+    };
+
+    assert_eq!(
+        modifiers("<2023-01-01 Sun +1w -2d>"),
+        (
+            repeater(RepeaterType::Cumulate, 1, TimeUnit::Week),
+            delay(DelayType::All, 2, TimeUnit::Day)
+        )
+    );
+    assert_eq!(
+        modifiers("<2023-01-01 Sun 10:00 --3h .+12m>"),
+        (
+            repeater(RepeaterType::Restart, 12, TimeUnit::Month),
+            delay(DelayType::First, 3, TimeUnit::Hour)
+        )
+    );
+    assert_eq!(
+        modifiers("<2023-01-01 Sun 10:00-12:00 ++1y>"),
+        (repeater(RepeaterType::CatchUp, 1, TimeUnit::Year), None)
+    );
+    assert_eq!(
+        modifiers("<2023-01-01 Sun>--<2023-01-02 Mon -1d>"),
+        (None, delay(DelayType::All, 1, TimeUnit::Day))
+    );
+
+    // each end of a range keeps its own modifiers, in their order
+    match parse_active::<VerboseError<&str>>("<2023-01-01 Sun +1w>--<2023-01-02 Mon -2d +2w>") {
+        Ok((
+            "",
+            Timestamp::ActiveRange {
+                modifiers,
+                end_modifiers,
+                ..
+            },
+        )) => {
+            assert_eq!(modifiers.to_string(), "+1w");
+            assert_eq!(end_modifiers.to_string(), "-2d +2w");
+            assert!(end_modifiers.delay_first);
+        }
+        result => panic!("{:?}", result),
+    }
+    match parse_active::<VerboseError<&str>>("<2023-01-01 Sun>--<2023-01-02 Mon -1d>") {
+        Ok(("", Timestamp::ActiveRange { modifiers, .. })) => assert!(modifiers.is_empty()),
+        result => panic!("{:?}", result),
+    }
+    assert!(parse_active::<()>("<2023-01-01 Sun +1w +2w>").is_err());
+    assert!(parse_active::<()>("<2023-01-01 Sun +1d/3d>").is_err());
+    assert!(parse_active::<()>("<2023-01-01 Sun +1x>").is_err());
 }
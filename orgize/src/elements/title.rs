@@ -2,46 +2,153 @@
 
 use std::borrow::Cow;
 use std::collections::HashMap;
+use std::fmt;
 
-use memchr::memrchr;
+use memchr::memrchr2;
 use nom::{
-    bytes::complete::{tag, take_until, take_while},
-    character::complete::{anychar, space1},
-    combinator::{map, map_parser, opt, verify},
+    bytes::complete::{tag, take_while, take_while1},
+    character::complete::space1,
+    combinator::{map, map_opt, map_parser, opt, verify},
     error::{ErrorKind, ParseError},
-    multi::fold_many0,
     sequence::{delimited, preceded},
     Err, IResult,
 };
 
 use crate::config::ParseConfig;
-use crate::elements::{drawer::parse_drawer, Planning, Timestamp};
+use crate::elements::{
+    drawer::{find_property, parse_drawer},
+    timestamp::days_in_month,
+    Datetime, ElementKind, Modifiers, Planning, Timestamp,
+};
+use crate::intern::intern;
 use crate::parsers::{line, skip_empty_lines, take_one_word};
+use crate::settings::StartupOptions;
 
 /// Title Elemenet
-#[cfg_attr(test, derive(PartialEq))]
 #[cfg_attr(feature = "ser", derive(serde::Serialize))]
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Title<'a> {
     /// Headline level, number of stars
     pub level: usize,
+    /// Number of stars of the parsed headline, when
+    /// `ParseConfig::flatten_levels` made it a level 1 headline
+    ///
+    /// It isn't updated when the level changes.
+    #[cfg_attr(feature = "ser", serde(skip_serializing_if = "Option::is_none"))]
+    pub stars: Option<usize>,
     /// Headline priority cookie
     #[cfg_attr(feature = "ser", serde(skip_serializing_if = "Option::is_none"))]
-    pub priority: Option<char>,
+    pub priority: Option<Priority>,
     /// Headline title tags, including the sparated colons
     #[cfg_attr(feature = "ser", serde(skip_serializing_if = "Vec::is_empty"))]
     pub tags: Vec<Cow<'a, str>>,
     /// Headline title keyword
     #[cfg_attr(feature = "ser", serde(skip_serializing_if = "Option::is_none"))]
     pub keyword: Option<Cow<'a, str>>,
-    /// Raw headline's text, without the stars and the tags
+    /// Raw headline's text, without the stars, the tags and the whitespace
+    /// around them
+    ///
+    /// Tags follow a space or a tab.
     pub raw: Cow<'a, str>,
     /// Planning elemenet associated to this headline
     #[cfg_attr(feature = "ser", serde(skip_serializing_if = "Option::is_none"))]
     pub planning: Option<Box<Planning<'a>>>,
+    /// Date of the title, e.g. `2024-03-04` in `* Monday, 2024-03-04`
+    ///
+    /// Only parsed when the title matches one of
+    /// `ParseConfig::headline_date_formats`, as an active timestamp.
+    #[cfg_attr(feature = "ser", serde(skip_serializing_if = "Option::is_none"))]
+    pub date: Option<Timestamp<'a>>,
     /// Property drawer associated to this headline
     #[cfg_attr(feature = "ser", serde(skip_serializing_if = "HashMap::is_empty"))]
     pub properties: HashMap<Cow<'a, str>, Cow<'a, str>>,
+    /// Lines of the property drawer, in order, including duplicated keys and
+    /// lines which aren't properties
+    ///
+    /// `properties` is built from them when parsing. The org export writes
+    /// them back as they are, unless `properties` changed since.
+    #[cfg_attr(feature = "ser", serde(skip))]
+    pub property_lines: Vec<PropertyLine<'a>>,
+}
+
+/// Line of a property drawer
+#[derive(Debug, Clone, PartialEq)]
+pub enum PropertyLine<'a> {
+    /// `:KEY: value` line, whose key ends with `+` for `:KEY+: value`
+    Property {
+        key: Cow<'a, str>,
+        value: Cow<'a, str>,
+    },
+    /// Line which isn't a property, like `:KEY value`, kept as is
+    Raw(Cow<'a, str>),
+}
+
+impl PropertyLine<'_> {
+    pub fn into_owned(self) -> PropertyLine<'static> {
+        match self {
+            PropertyLine::Property { key, value } => PropertyLine::Property {
+                key: intern(key),
+                value: value.into_owned().into(),
+            },
+            PropertyLine::Raw(line) => PropertyLine::Raw(line.into_owned().into()),
+        }
+    }
+}
+
+/// Priority of a headline, `A` in `[#A]` or `1` in `[#1]`
+///
+/// Priorities are ordered like in org-mode: an earlier letter or a lower
+/// number is a higher priority, and compares as less, so sorting in ascending
+/// order puts the highest priorities first. Letters come before numbers.
+///
+/// ```rust
+/// use orgize::elements::Priority;
+///
+/// assert!(Priority::Char('A') < Priority::Char('B'));
+/// assert!(Priority::Num(2) < Priority::Num(10));
+/// assert_eq!(Priority::Num(10).to_string(), "10");
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Priority {
+    Char(char),
+    Num(u32),
+}
+
+impl Priority {
+    /// Parses the contents of a priority cookie: a single letter, or a number
+    ///
+    /// Numbers with leading zeros, like `007`, are rejected, since they
+    /// wouldn't be written back as they were.
+    pub fn parse(s: &str) -> Option<Priority> {
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) if c.is_ascii_alphabetic() => Some(Priority::Char(c)),
+            (Some('0'), Some(_)) => None,
+            (Some(_), _) if s.bytes().all(|b| b.is_ascii_digit()) => {
+                s.parse().ok().map(Priority::Num)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Priority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Priority::Char(c) => write!(f, "{}", c),
+            Priority::Num(n) => write!(f, "{}", n),
+        }
+    }
+}
+
+#[cfg(feature = "ser")]
+impl serde::Serialize for Priority {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Priority::Char(c) => serializer.serialize_char(*c),
+            Priority::Num(n) => serializer.serialize_u32(*n),
+        }
+    }
 }
 
 impl Title<'_> {
@@ -52,8 +159,6 @@ impl Title<'_> {
         parse_title::<()>(input, config).ok()
     }
 
-    // TODO: fn is_archived(&self) -> bool { }
-    // TODO: fn is_commented(&self) -> bool { }
     // TODO: fn is_quoted(&self) -> bool { }
     // TODO: fn is_footnote_section(&self) -> bool { }
 
@@ -78,27 +183,138 @@ impl Title<'_> {
             .and_then(|planning| planning.deadline.as_ref())
     }
 
+    /// Returns the value of the property `key`, compared case-insensitively
+    ///
+    /// ```rust
+    /// use orgize::Org;
+    ///
+    /// let org = Org::parse("* a\n:PROPERTIES:\n:Custom_ID: intro\n:TAGS: a\n:TAGS+: b\n:END:\n");
+    /// let title = org.headlines().next().unwrap().title(&org);
+    ///
+    /// assert_eq!(title.property("CUSTOM_ID"), Some("intro"));
+    /// assert_eq!(title.property("tags"), Some("a b"));
+    /// assert_eq!(title.property("ID"), None);
+    /// ```
+    pub fn property(&self, key: &str) -> Option<&str> {
+        find_property(&self.properties, key)
+    }
+
+    /// Returns every value of the property `key` in the property drawer, in
+    /// order, including the ones of `:KEY+:` lines and of duplicated keys
+    ///
+    /// ```rust
+    /// use orgize::Org;
+    ///
+    /// let org = Org::parse("* a\n:PROPERTIES:\n:ALIAS: a\n:alias: b\n:ALIAS+: c\n:END:\n");
+    /// let title = org.headlines().next().unwrap().title(&org);
+    ///
+    /// assert_eq!(title.property("ALIAS"), Some("b c"));
+    /// assert_eq!(title.properties_all("ALIAS"), vec!["a", "b", "c"]);
+    /// assert!(title.properties_all("ID").is_empty());
+    /// ```
+    pub fn properties_all(&self, key: &str) -> Vec<&str> {
+        self.property_lines
+            .iter()
+            .filter_map(|line| match line {
+                PropertyLine::Property { key: name, value }
+                    if name
+                        .strip_suffix('+')
+                        .unwrap_or(name)
+                        .eq_ignore_ascii_case(key) =>
+                {
+                    Some(value.as_ref())
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Returns the value of the property `key` as a timestamp, or `None` if
+    /// it's missing or isn't a single timestamp, like `[2024-01-01 Mon]`
+    pub fn property_timestamp(&self, key: &str) -> Option<Timestamp<'_>> {
+        self.property(key).and_then(parse_timestamp_value)
+    }
+
+    /// Returns the value of the property `key` as a number, or `None` if
+    /// it's missing or isn't a finite number, like `37` or `-1.5`
+    pub fn property_number(&self, key: &str) -> Option<f64> {
+        self.property(key).and_then(parse_number_value)
+    }
+
+    /// Returns the value of the property `key` split at `separator`, without
+    /// empty items, or `None` if it's missing
+    ///
+    /// ```rust
+    /// use orgize::Org;
+    ///
+    /// let org = Org::parse(
+    ///     "* a\n:PROPERTIES:\n:CREATED: [2024-01-01 Mon]\n:PAGES: 37\n:AUTHORS: alice  bob\n:END:\n",
+    /// );
+    /// let title = org.headlines().next().unwrap().title(&org);
+    ///
+    /// assert_eq!(title.property_list("AUTHORS", " "), Some(vec!["alice", "bob"]));
+    /// assert_eq!(title.property_number("PAGES"), Some(37.0));
+    /// assert_eq!(title.property_number("AUTHORS"), None);
+    /// assert!(title.property_timestamp("CREATED").is_some());
+    /// assert!(title.property_timestamp("PAGES").is_none());
+    /// ```
+    pub fn property_list(&self, key: &str, separator: &str) -> Option<Vec<&str>> {
+        self.property(key).map(|value| {
+            value
+                .split(separator)
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .collect()
+        })
+    }
+
+    /// Returns this headline's date, parsed from its title with
+    /// `ParseConfig::headline_date_formats`, or `None` if not set.
+    pub fn headline_date(&self) -> Option<&Timestamp> {
+        self.date.as_ref()
+    }
+
     /// checks if this headline is "archived"
     pub fn is_archived(&self) -> bool {
         self.tags.iter().any(|tag| tag == "ARCHIVE")
     }
 
+    /// Returns `true` if this headline is commented, its title starting with
+    /// the `COMMENT` keyword after its todo keyword and priority
+    ///
+    /// Commented headlines aren't exported, nor their subtree.
+    pub fn is_commented(&self) -> bool {
+        match self.raw.strip_prefix("COMMENT") {
+            Some(rest) => rest.is_empty() || rest.starts_with(char::is_whitespace),
+            None => false,
+        }
+    }
+
+    /// Returns the level this headline is displayed at, which differs from
+    /// its level with `#+STARTUP: odd`
+    pub fn display_level(&self, startup: &StartupOptions) -> usize {
+        startup.display_level(self.level)
+    }
+
     pub fn into_owned(self) -> Title<'static> {
         Title {
             level: self.level,
+            stars: self.stars,
             priority: self.priority,
-            tags: self
-                .tags
-                .into_iter()
-                .map(|s| s.into_owned().into())
-                .collect(),
-            keyword: self.keyword.map(Into::into).map(Cow::Owned),
+            tags: self.tags.into_iter().map(intern).collect(),
+            keyword: self.keyword.map(intern),
             raw: self.raw.into_owned().into(),
             planning: self.planning.map(|p| Box::new(p.into_owned())),
+            date: self.date.map(Timestamp::into_owned),
             properties: self
                 .properties
                 .into_iter()
-                .map(|(k, v)| (k.into_owned().into(), v.into_owned().into()))
+                .map(|(k, v)| (intern(k), v.into_owned().into()))
+                .collect(),
+            property_lines: self
+                .property_lines
+                .into_iter()
+                .map(PropertyLine::into_owned)
                 .collect(),
         }
     }
@@ -108,12 +324,15 @@ impl Default for Title<'_> {
     fn default() -> Title<'static> {
         Title {
             level: 1,
+            stars: None,
             priority: None,
             tags: Vec::new(),
             keyword: None,
             raw: Cow::Borrowed(""),
             planning: None,
+            date: None,
             properties: HashMap::new(),
+            property_lines: Vec::new(),
         }
     }
 }
@@ -141,15 +360,26 @@ fn parse_title<'a, E: ParseError<&'a str>>(
             take_one_word,
             delimited(
                 tag("[#"),
-                verify(anychar, |c: &char| c.is_ascii_uppercase()),
+                verify(
+                    map_opt(
+                        take_while1(|c: char| c.is_ascii_alphanumeric()),
+                        Priority::parse,
+                    ),
+                    |priority: &Priority| {
+                        config.highest_priority <= *priority && *priority <= config.lowest_priority
+                    },
+                ),
                 tag("]"),
             ),
         ),
     ))(input)?;
     let (input, tail) = line(input)?;
     let tail = tail.trim();
-    let (raw, tags) = memrchr(b' ', tail.as_bytes())
-        .map(|i| (tail[0..i].trim(), &tail[i + 1..]))
+    // tags may also make up the whole title, e.g. `* :tag:`
+    let i = memrchr2(b' ', b'\t', tail.as_bytes())
+        .map(|i| i + 1)
+        .unwrap_or(0);
+    let (raw, tags) = Some((tail[0..i].trim(), &tail[i..]))
         .filter(|(_, x)| x.len() > 2 && x.starts_with(':') && x.ends_with(':'))
         .unwrap_or((tail, ""));
 
@@ -159,23 +389,29 @@ fn parse_title<'a, E: ParseError<&'a str>>(
         .map(Into::into)
         .collect();
 
-    let (input, planning) = Planning::parse(input)
+    let (input, planning) = Some(input)
+        .filter(|_| config.is_enabled(ElementKind::Timestamp))
+        .and_then(Planning::parse)
         .map(|(input, planning)| (input, Some(Box::new(planning))))
         .unwrap_or((input, None));
 
-    let (input, properties) = opt(parse_properties_drawer)(input)?;
+    let (input, property_lines) = opt(parse_properties_drawer)(input)?;
+    let property_lines = property_lines.unwrap_or_default();
 
     Ok((
         input,
         (
             Title {
-                properties: properties.unwrap_or_default(),
-                level,
+                properties: fold_properties(property_entries(&property_lines)),
+                property_lines,
+                level: if config.flatten_levels { 1 } else { level },
+                stars: Some(level).filter(|_| config.flatten_levels),
                 keyword: keyword.map(Into::into),
                 priority,
                 tags,
                 raw: raw.into(),
                 planning,
+                date: parse_headline_date(raw, &config.headline_date_formats),
             },
             raw,
         ),
@@ -185,32 +421,438 @@ fn parse_title<'a, E: ParseError<&'a str>>(
 #[inline]
 fn parse_properties_drawer<'a, E: ParseError<&'a str>>(
     input: &'a str,
-) -> IResult<&'a str, HashMap<Cow<'a, str>, Cow<'a, str>>, E> {
+) -> IResult<&'a str, Vec<PropertyLine<'a>>, E> {
     let (input, (drawer, content)) = parse_drawer(input.trim_start())?;
     if drawer.name != "PROPERTIES" {
         return Err(Err::Error(E::from_error_kind(input, ErrorKind::Tag)));
     }
-    let (_, map) = fold_many0(
-        parse_node_property,
-        HashMap::new(),
-        |mut acc: HashMap<_, _>, (name, value)| {
-            acc.insert(name.into(), value.into());
-            acc
-        },
-    )(content)?;
-    Ok((input, map))
+    Ok((input, parse_property_lines(content)))
+}
+
+/// Parses the lines of the property drawer contents `content`, skipping
+/// empty lines
+///
+/// Lines which aren't `:KEY: value` lines are kept as `PropertyLine::Raw`.
+pub(crate) fn parse_property_lines(content: &str) -> Vec<PropertyLine<'_>> {
+    content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| match parse_node_property::<()>(line) {
+            Ok((_, (name, value))) => PropertyLine::Property {
+                key: name.into(),
+                value: value.into(),
+            },
+            Err(_) => PropertyLine::Raw(line.into()),
+        })
+        .collect()
 }
 
+/// Returns the `(name, value)` pairs of the properties among `lines`
+pub(crate) fn property_entries<'a, 'b>(
+    lines: &'b [PropertyLine<'a>],
+) -> impl Iterator<Item = (Cow<'a, str>, Cow<'a, str>)> + 'b {
+    lines.iter().filter_map(|line| match line {
+        PropertyLine::Property { key, value } => Some((key.clone(), value.clone())),
+        PropertyLine::Raw(_) => None,
+    })
+}
+
+/// Parses the property line `:name: value`
+///
+/// Like in org-mode, the name ends at the first colon followed by
+/// whitespace or the end of the line, so `:header-args:python: :session`
+/// is the property `header-args:python`, and it has no whitespace.
 #[inline]
-fn parse_node_property<'a, E: ParseError<&'a str>>(
+pub(crate) fn parse_node_property<'a, E: ParseError<&'a str>>(
     input: &'a str,
 ) -> IResult<&'a str, (&'a str, &'a str), E> {
-    let input = skip_empty_lines(input).trim_start();
-    let (input, name) = map(delimited(tag(":"), take_until(":"), tag(":")), |s: &str| {
-        s.trim_end_matches('+')
-    })(input)?;
-    let (input, value) = line(input)?;
-    Ok((input, (name, value.trim())))
+    let input = skip_empty_lines(input);
+    let (input, line) = line(input)?;
+    let error = || Err::Error(E::from_error_kind(line, ErrorKind::Tag));
+
+    let body = line.trim_start().strip_prefix(':').ok_or_else(error)?;
+    let end = body
+        .match_indices(':')
+        .map(|(i, _)| i)
+        .find(|&i| match body[i + 1..].chars().next() {
+            Some(c) => c.is_whitespace(),
+            None => true,
+        })
+        .filter(|&i| i > 0 && !body[..i].contains(char::is_whitespace))
+        .ok_or_else(error)?;
+
+    Ok((input, (&body[..end], body[end + 1..].trim())))
+}
+
+/// Returns the properties of the property lines `(name, value)`
+///
+/// Keys are case-insensitive, so the value of a key replaces the one of a
+/// key differing only in case. The value of a `:KEY+:` line is appended to
+/// the current value of `KEY`, separated by a space. Keys are looked up by
+/// their lowercase in an index, so that drawers of thousands of lines are
+/// folded in linear time.
+pub(crate) fn fold_properties<'a>(
+    lines: impl IntoIterator<Item = (Cow<'a, str>, Cow<'a, str>)>,
+) -> HashMap<Cow<'a, str>, Cow<'a, str>> {
+    let mut properties: HashMap<Cow<'a, str>, Cow<'a, str>> = HashMap::new();
+    // keys of `properties` by their lowercase
+    let mut keys: HashMap<String, Cow<'a, str>> = HashMap::new();
+
+    for (name, value) in lines {
+        let append = name.ends_with('+');
+        let key = match name {
+            Cow::Borrowed(name) if append => Cow::Borrowed(&name[..name.len() - 1]),
+            Cow::Owned(mut name) if append => {
+                name.pop();
+                Cow::Owned(name)
+            }
+            name => name,
+        };
+        let lowercase = key.to_ascii_lowercase();
+
+        match keys.get(&lowercase) {
+            Some(current) if append => {
+                if let Some(previous) = properties.get_mut(current) {
+                    if previous.is_empty() {
+                        *previous = value;
+                    } else if !value.is_empty() {
+                        let previous = previous.to_mut();
+                        previous.push(' ');
+                        previous.push_str(&value);
+                    }
+                }
+            }
+            Some(current) => {
+                properties.remove(current);
+                properties.insert(key.clone(), value);
+                keys.insert(lowercase, key);
+            }
+            None => {
+                properties.insert(key.clone(), value);
+                keys.insert(lowercase, key);
+            }
+        }
+    }
+
+    properties
+}
+
+/// Parses a property value holding a single timestamp
+pub(crate) fn parse_timestamp_value(value: &str) -> Option<Timestamp<'_>> {
+    let value = value.trim();
+    Timestamp::parse_inactive(value)
+        .or_else(|| Timestamp::parse_active(value))
+        .filter(|(rest, _)| rest.trim().is_empty())
+        .map(|(_, timestamp)| timestamp)
+}
+
+/// Parses a property value holding a finite number
+pub(crate) fn parse_number_value(value: &str) -> Option<f64> {
+    value
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|number| number.is_finite())
+}
+
+/// Formats `number` for a property value, without a fractional part if
+/// it's an integer
+pub(crate) fn format_number(number: f64) -> String {
+    if number.fract() == 0.0 && number.abs() < 1e15 {
+        format!("{}", number as i64)
+    } else {
+        number.to_string()
+    }
+}
+
+/// Parses the date of `raw` with the first of `formats` matching it
+fn parse_headline_date(raw: &str, formats: &[String]) -> Option<Timestamp<'static>> {
+    formats
+        .iter()
+        .find_map(|format| parse_date_format(raw, format))
+        .map(|start| Timestamp::Active {
+            start,
+            modifiers: Modifiers::default(),
+        })
+}
+
+const DAYS: [&str; 7] = [
+    "Sunday",
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+];
+
+const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+/// Fields of a date matched by [`match_date_format`], in decreasing order of
+/// significance
+#[derive(Default, Debug, PartialEq)]
+pub(crate) struct DateFields {
+    pub year: Option<u32>,
+    pub week_year: Option<u32>,
+    pub month: Option<u32>,
+    pub week: Option<u32>,
+    pub day: Option<u32>,
+    pub hour: Option<u32>,
+    pub minute: Option<u32>,
+}
+
+/// Matches the whole `input` against `format` and builds a date from it,
+/// which needs at least a year, a month and a day
+fn parse_date_format(input: &str, format: &str) -> Option<Datetime<'static>> {
+    let DateFields {
+        year,
+        month,
+        day,
+        hour,
+        minute,
+        ..
+    } = match_date_format(input, format)?;
+
+    let (year, month, day) = (year?, month?, day?);
+    if !(1..=12).contains(&month) || day < 1 || day > days_in_month(year, month) {
+        return None;
+    }
+    let minute = minute.or_else(|| hour.map(|_| 0));
+    if hour.unwrap_or(0) > 23 || minute.unwrap_or(0) > 59 || (hour.is_none() && minute.is_some()) {
+        return None;
+    }
+
+    Some(Datetime {
+        year: year as u16,
+        month: month as u8,
+        day: day as u8,
+        dayname: DAYS[weekday(year.into(), month, day)][0..3].into(),
+        hour: hour.map(|hour| hour as u8),
+        minute: minute.map(|minute| minute as u8),
+    })
+}
+
+/// Matches the whole `input` against `format`, a `strftime`-like pattern
+///
+/// Supports `%Y`, `%m`, `%d`, `%e`, `%H`, `%M`, `%A`, `%a`, `%B`, `%b`, the
+/// ISO 8601 week `%V` and its year `%G`, and `%%`. A space matches any
+/// amount of whitespace, other characters match themselves. Day names are
+/// accepted without checking them against the date.
+pub(crate) fn match_date_format(input: &str, format: &str) -> Option<DateFields> {
+    let mut fields = DateFields::default();
+    let mut input = input.trim();
+    let mut format = format.trim().chars();
+
+    while let Some(c) = format.next() {
+        if c.is_whitespace() {
+            if !input.starts_with(char::is_whitespace) {
+                return None;
+            }
+            input = input.trim_start();
+            continue;
+        }
+        if c != '%' {
+            input = input.strip_prefix(c)?;
+            continue;
+        }
+        input = match format.next()? {
+            'Y' => parse_number(input, 4, 4, &mut fields.year)?,
+            'G' => parse_number(input, 4, 4, &mut fields.week_year)?,
+            'm' => parse_number(input, 1, 2, &mut fields.month)?,
+            'V' => parse_number(input, 1, 2, &mut fields.week)?,
+            'd' => parse_number(input, 1, 2, &mut fields.day)?,
+            'e' => parse_number(input.trim_start(), 1, 2, &mut fields.day)?,
+            'H' => parse_number(input, 1, 2, &mut fields.hour)?,
+            'M' => parse_number(input, 1, 2, &mut fields.minute)?,
+            'A' => parse_name(input, &DAYS, None)?.0,
+            'a' => parse_name(input, &DAYS, Some(3))?.0,
+            'B' => {
+                let (input, i) = parse_name(input, &MONTHS, None)?;
+                fields.month = Some(i as u32 + 1);
+                input
+            }
+            'b' => {
+                let (input, i) = parse_name(input, &MONTHS, Some(3))?;
+                fields.month = Some(i as u32 + 1);
+                input
+            }
+            '%' => input.strip_prefix('%')?,
+            _ => return None,
+        };
+    }
+
+    if !input.is_empty() {
+        return None;
+    }
+
+    Some(fields)
+}
+
+/// Formats `datetime` with `format`, a pattern of [`match_date_format`]
+///
+/// Unknown specifiers are written as is, and a missing time as `00:00`.
+pub(crate) fn format_date(datetime: &Datetime, format: &str) -> String {
+    let (year, month, day) = (
+        u32::from(datetime.year),
+        u32::from(datetime.month),
+        u32::from(datetime.day),
+    );
+    let mut output = String::new();
+    let mut format = format.chars();
+
+    while let Some(c) = format.next() {
+        if c != '%' {
+            output.push(c);
+            continue;
+        }
+        match format.next() {
+            Some('Y') => output += &format!("{:04}", year),
+            Some('G') => output += &format!("{:04}", iso_week(year, month, day).0),
+            Some('m') => output += &format!("{:02}", month),
+            Some('V') => output += &format!("{:02}", iso_week(year, month, day).1),
+            Some('d') => output += &format!("{:02}", day),
+            Some('e') => output += &format!("{:>2}", day),
+            Some('H') => output += &format!("{:02}", datetime.hour.unwrap_or(0)),
+            Some('M') => output += &format!("{:02}", datetime.minute.unwrap_or(0)),
+            Some('A') => output += DAYS[weekday(year.into(), month, day)],
+            Some('a') => output += &DAYS[weekday(year.into(), month, day)][0..3],
+            Some('B') => output += MONTHS.get(month as usize - 1).unwrap_or(&""),
+            Some('b') => {
+                output += MONTHS
+                    .get(month as usize - 1)
+                    .map(|name| &name[0..3])
+                    .unwrap_or("")
+            }
+            Some('%') => output.push('%'),
+            Some(c) => {
+                output.push('%');
+                output.push(c);
+            }
+            None => output.push('%'),
+        }
+    }
+
+    output
+}
+
+/// Parses between `min` and `max` digits into `value`
+fn parse_number<'a>(
+    input: &'a str,
+    min: usize,
+    max: usize,
+    value: &mut Option<u32>,
+) -> Option<&'a str> {
+    let len = input
+        .bytes()
+        .take(max)
+        .take_while(u8::is_ascii_digit)
+        .count();
+    if len < min {
+        return None;
+    }
+    *value = Some(input[0..len].parse().ok()?);
+    Some(&input[len..])
+}
+
+/// Parses one of `names`, or of their first `len` characters, returns its
+/// index
+fn parse_name<'a>(input: &'a str, names: &[&str], len: Option<usize>) -> Option<(&'a str, usize)> {
+    names.iter().enumerate().find_map(|(i, name)| {
+        let name = &name[0..len.unwrap_or(name.len())];
+        match input.get(0..name.len()) {
+            Some(prefix) if prefix.eq_ignore_ascii_case(name) => Some((&input[name.len()..], i)),
+            _ => None,
+        }
+    })
+}
+
+/// Returns the day of the week of a date, `0` being Sunday
+///
+/// The year is signed since January and February count as part of the
+/// previous year, which is `-1` for the year `0`.
+fn weekday(year: i64, month: u32, day: u32) -> usize {
+    const OFFSETS: [u32; 12] = [0, 3, 2, 5, 0, 3, 5, 1, 4, 6, 2, 4];
+    let year = if month < 3 { year - 1 } else { year };
+    let days = year + year.div_euclid(4) - year.div_euclid(100)
+        + year.div_euclid(400)
+        + i64::from(OFFSETS[month as usize - 1] + day);
+    days.rem_euclid(7) as usize
+}
+
+/// Returns the ISO 8601 week of a date and the year it belongs to, whose
+/// first week contains its first Thursday
+fn iso_week(year: u32, month: u32, day: u32) -> (i64, u32) {
+    let ordinal = (1..month)
+        .map(|month| days_in_month(year, month))
+        .sum::<u32>()
+        + day;
+    let year = i64::from(year);
+    // Monday is 1 and Sunday is 7
+    let weekday = (weekday(year, month, day) as u32 + 6) % 7 + 1;
+    let week = (ordinal + 10 - weekday) / 7;
+    if week == 0 {
+        (year - 1, weeks_in_year(year - 1))
+    } else if week > weeks_in_year(year) {
+        (year + 1, 1)
+    } else {
+        (year, week)
+    }
+}
+
+/// Returns the number of ISO 8601 weeks of a year, 53 if it starts or ends
+/// on a Thursday
+fn weeks_in_year(year: i64) -> u32 {
+    if weekday(year, 1, 1) == 4 || weekday(year, 12, 31) == 4 {
+        53
+    } else {
+        52
+    }
+}
+
+#[test]
+fn format_date_() {
+    let date = |year, month, day| Datetime {
+        year,
+        month,
+        day,
+        dayname: "".into(),
+        hour: None,
+        minute: None,
+    };
+
+    assert_eq!(
+        format_date(&date(2024, 3, 4), "%Y-%m-%d %A, %e %b %% %H:%M %q"),
+        "2024-03-04 Monday,  4 Mar % 00:00 %q"
+    );
+    assert_eq!(format_date(&date(2024, 12, 30), "%G-W%V"), "2025-W01");
+    assert_eq!(format_date(&date(2021, 1, 3), "%G-W%V"), "2020-W53");
+    assert_eq!(format_date(&date(2020, 12, 31), "%G-W%V"), "2020-W53");
+    assert_eq!(format_date(&date(2024, 6, 15), "%G-W%V"), "2024-W24");
+    assert_eq!(
+        format_date(&date(0, 1, 1), "%A %G-W%V"),
+        "Saturday -001-W52"
+    );
+    assert_eq!(
+        match_date_format("2025-W01", "%G-W%V"),
+        Some(DateFields {
+            week_year: Some(2025),
+            week: Some(1),
+            ..DateFields::default()
+        })
+    );
 }
 
 #[test]
@@ -229,12 +871,15 @@ fn parse_title_() {
             (
                 Title {
                     level: 4,
+                    stars: None,
                     keyword: Some("DONE".into()),
-                    priority: Some('A'),
+                    priority: Some(Priority::Char('A')),
                     raw: "COMMENT Title".into(),
                     tags: vec!["tag".into(), "a2%".into()],
                     planning: None,
-                    properties: HashMap::new()
+                    date: None,
+                    properties: HashMap::new(),
+                    property_lines: Vec::new()
                 },
                 "COMMENT Title"
             )
@@ -247,12 +892,15 @@ fn parse_title_() {
             (
                 Title {
                     level: 4,
+                    stars: None,
                     keyword: None,
                     priority: None,
                     raw: "ToDO [#A] COMMENT Title".into(),
                     tags: vec![],
                     planning: None,
-                    properties: HashMap::new()
+                    date: None,
+                    properties: HashMap::new(),
+                    property_lines: Vec::new()
                 },
                 "ToDO [#A] COMMENT Title"
             )
@@ -265,12 +913,15 @@ fn parse_title_() {
             (
                 Title {
                     level: 4,
+                    stars: None,
                     keyword: None,
                     priority: None,
                     raw: "T0DO [#A] COMMENT Title".into(),
                     tags: vec![],
                     planning: None,
-                    properties: HashMap::new()
+                    date: None,
+                    properties: HashMap::new(),
+                    property_lines: Vec::new()
                 },
                 "T0DO [#A] COMMENT Title"
             )
@@ -283,12 +934,15 @@ fn parse_title_() {
             (
                 Title {
                     level: 4,
+                    stars: None,
                     keyword: Some("DONE".into()),
                     priority: None,
                     raw: "[#1] COMMENT Title".into(),
                     tags: vec![],
                     planning: None,
-                    properties: HashMap::new()
+                    date: None,
+                    properties: HashMap::new(),
+                    property_lines: Vec::new()
                 },
                 "[#1] COMMENT Title"
             )
@@ -301,17 +955,41 @@ fn parse_title_() {
             (
                 Title {
                     level: 4,
+                    stars: None,
                     keyword: Some("DONE".into()),
                     priority: None,
                     raw: "[#a] COMMENT Title".into(),
                     tags: vec![],
                     planning: None,
-                    properties: HashMap::new()
+                    date: None,
+                    properties: HashMap::new(),
+                    property_lines: Vec::new()
                 },
                 "[#a] COMMENT Title"
             )
         ))
     );
+    assert_eq!(
+        parse_title::<VerboseError<&str>>("**** TODO :tag:10:30:", &DEFAULT_CONFIG),
+        Ok((
+            "",
+            (
+                Title {
+                    level: 4,
+                    stars: None,
+                    keyword: Some("TODO".into()),
+                    priority: None,
+                    raw: "".into(),
+                    tags: vec!["tag".into(), "10".into(), "30".into()],
+                    planning: None,
+                    date: None,
+                    properties: HashMap::new(),
+                    property_lines: Vec::new()
+                },
+                ""
+            )
+        ))
+    );
     assert_eq!(
         parse_title::<VerboseError<&str>>("**** Title :tag:a2%", &DEFAULT_CONFIG),
         Ok((
@@ -319,12 +997,15 @@ fn parse_title_() {
             (
                 Title {
                     level: 4,
+                    stars: None,
                     keyword: None,
                     priority: None,
                     raw: "Title :tag:a2%".into(),
                     tags: vec![],
                     planning: None,
-                    properties: HashMap::new()
+                    date: None,
+                    properties: HashMap::new(),
+                    property_lines: Vec::new()
                 },
                 "Title :tag:a2%"
             )
@@ -337,12 +1018,15 @@ fn parse_title_() {
             (
                 Title {
                     level: 4,
+                    stars: None,
                     keyword: None,
                     priority: None,
                     raw: "Title tag:a2%:".into(),
                     tags: vec![],
                     planning: None,
-                    properties: HashMap::new()
+                    date: None,
+                    properties: HashMap::new(),
+                    property_lines: Vec::new()
                 },
                 "Title tag:a2%:"
             )
@@ -362,12 +1046,15 @@ fn parse_title_() {
             (
                 Title {
                     level: 4,
+                    stars: None,
                     keyword: None,
                     priority: None,
                     raw: "DONE Title".into(),
                     tags: vec![],
                     planning: None,
-                    properties: HashMap::new()
+                    date: None,
+                    properties: HashMap::new(),
+                    property_lines: Vec::new()
                 },
                 "DONE Title"
             )
@@ -386,12 +1073,15 @@ fn parse_title_() {
             (
                 Title {
                     level: 4,
+                    stars: None,
                     keyword: Some("TASK".into()),
-                    priority: Some('A'),
+                    priority: Some(Priority::Char('A')),
                     raw: "Title".into(),
                     tags: vec![],
                     planning: None,
-                    properties: HashMap::new()
+                    date: None,
+                    properties: HashMap::new(),
+                    property_lines: Vec::new()
                 },
                 "Title"
             )
@@ -399,33 +1089,144 @@ fn parse_title_() {
     );
 }
 
+#[test]
+fn parse_priority() {
+    let priority = |input: &str, config: &ParseConfig| {
+        let (_, (title, _)) = Title::parse(input, config).unwrap();
+        (title.priority, title.raw.into_owned())
+    };
+    let numeric = ParseConfig {
+        highest_priority: Priority::Num(1),
+        lowest_priority: Priority::Num(64),
+        ..Default::default()
+    };
+
+    assert_eq!(
+        priority("* [#B] a", &ParseConfig::default()),
+        (Some(Priority::Char('B')), "a".into())
+    );
+    assert_eq!(
+        priority("* [#10] a", &ParseConfig::default()),
+        (None, "[#10] a".into())
+    );
+    assert_eq!(
+        priority("* [#10] a", &numeric),
+        (Some(Priority::Num(10)), "a".into())
+    );
+    assert_eq!(priority("* [#65] a", &numeric), (None, "[#65] a".into()));
+    assert_eq!(priority("* [#007] a", &numeric), (None, "[#007] a".into()));
+    assert_eq!(priority("* [#A] a", &numeric), (None, "[#A] a".into()));
+    assert_eq!(
+        priority("* [#b] a", &ParseConfig::default()),
+        (None, "[#b] a".into())
+    );
+}
+
 #[test]
 fn parse_properties_drawer_() {
     use nom::error::VerboseError;
 
+    let properties = |input| {
+        parse_properties_drawer::<VerboseError<&str>>(input)
+            .map(|(input, lines)| (input, fold_properties(property_entries(&lines))))
+    };
+
     assert_eq!(
-        parse_properties_drawer::<VerboseError<&str>>(
-            "   :PROPERTIES:\n   :CUSTOM_ID: id\n   :END:"
-        ),
+        properties("   :PROPERTIES:\n   :CUSTOM_ID: id\n   :END:"),
         Ok((
             "",
             vec![("CUSTOM_ID".into(), "id".into())]
                 .into_iter()
                 .collect::<HashMap<_, _>>()
         ))
-    )
-}
-
-// #[test]
-// fn is_commented() {
-//     assert!(parse_title::<VerboseError<&str>>("* COMMENT Title", &DEFAULT_CONFIG)
-//         .1
-//         .is_commented());
-//     assert!(!parse_title::<VerboseError<&str>>("* Title", &DEFAULT_CONFIG).1.is_commented());
-//     assert!(!parse_title::<VerboseError<&str>>("* C0MMENT Title", &DEFAULT_CONFIG)
-//         .1
-//         .is_commented());
-//     assert!(!parse_title::<VerboseError<&str>>("* comment Title", &DEFAULT_CONFIG)
-//         .1
-//         .is_commented());
-// }
+    );
+    assert_eq!(
+        properties(":PROPERTIES:\n:Var: a=1\n:VAR+: b=2\n:Id: 1\n:ID: 2\n:New+: c\n:END:"),
+        Ok((
+            "",
+            vec![
+                ("Var".into(), "a=1 b=2".into()),
+                ("ID".into(), "2".into()),
+                ("New".into(), "c".into())
+            ]
+            .into_iter()
+            .collect::<HashMap<_, _>>()
+        ))
+    );
+    assert_eq!(
+        parse_properties_drawer::<VerboseError<&str>>(
+            ":PROPERTIES:\n:header-args:python: :session\n:KEY value\n\n:a:b\n:ID:\n:END:"
+        ),
+        Ok((
+            "",
+            vec![
+                PropertyLine::Property {
+                    key: "header-args:python".into(),
+                    value: ":session".into()
+                },
+                PropertyLine::Raw(":KEY value".into()),
+                PropertyLine::Raw(":a:b".into()),
+                PropertyLine::Property {
+                    key: "ID".into(),
+                    value: "".into()
+                },
+            ]
+        ))
+    );
+}
+
+#[test]
+fn is_commented() {
+    use crate::config::DEFAULT_CONFIG;
+
+    let commented = |input| {
+        parse_title::<()>(input, &DEFAULT_CONFIG)
+            .unwrap()
+            .1
+             .0
+            .is_commented()
+    };
+
+    assert!(commented("* COMMENT Title"));
+    assert!(commented("* TODO [#A] COMMENT"));
+    assert!(!commented("* Title"));
+    assert!(!commented("* C0MMENT Title"));
+    assert!(!commented("* comment Title"));
+    assert!(!commented("* COMMENTS"));
+}
+
+#[test]
+fn parse_date_format_() {
+    let date = |input: &str, format: &str| {
+        parse_date_format(input, format).map(|datetime| {
+            (
+                datetime.year,
+                datetime.month,
+                datetime.day,
+                datetime.dayname.into_owned(),
+                datetime.hour,
+            )
+        })
+    };
+
+    assert_eq!(
+        date("Monday, 2024-03-04", "%A, %Y-%m-%d"),
+        Some((2024, 3, 4, "Mon".into(), None))
+    );
+    assert_eq!(
+        date("2024-03-04  monday", "%Y-%m-%d %A"),
+        Some((2024, 3, 4, "Mon".into(), None))
+    );
+    assert_eq!(
+        date("Feb 29 2000 10:30", "%b %e %Y %H:%M"),
+        Some((2000, 2, 29, "Tue".into(), Some(10)))
+    );
+    assert_eq!(
+        date("0000-01-01", "%Y-%m-%d"),
+        Some((0, 1, 1, "Sat".into(), None))
+    );
+    assert_eq!(date("2023-02-29", "%Y-%m-%d"), None);
+    assert_eq!(date("2024-03-04 notes", "%Y-%m-%d"), None);
+    assert_eq!(date("Someday, 2024-03-04", "%A, %Y-%m-%d"), None);
+    assert_eq!(date("2024-03-04", "%Y-%m-%d %q"), None);
+}
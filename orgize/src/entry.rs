@@ -0,0 +1,69 @@
+use std::borrow::Cow;
+
+use crate::elements::Timestamp;
+use crate::node::HeadlineNode;
+use crate::org::Org;
+
+/// Metadata of a headline, as used by sync clients
+///
+/// Returned by [`Org::entries`].
+///
+/// [`Org::entries`]: struct.Org.html#method.entries
+#[derive(Debug)]
+pub struct Entry<'a, 'b> {
+    pub headline: HeadlineNode,
+    pub level: usize,
+    /// Raw title, without todo keyword, priority and tags
+    pub title: &'b str,
+    /// Todo keyword
+    pub state: Option<&'b str>,
+    pub tags: &'b [Cow<'a, str>],
+    pub scheduled: Option<&'b Timestamp<'b>>,
+    pub deadline: Option<&'b Timestamp<'b>>,
+    pub closed: Option<&'b Timestamp<'b>>,
+    /// Timestamp of the `CREATED` property
+    pub created: Option<Timestamp<'b>>,
+    /// Timestamp of the `LAST_REPEAT` property
+    pub last_repeat: Option<Timestamp<'b>>,
+    /// Hash of [`HeadlineNode::canonical_body`], stable across releases
+    ///
+    /// [`HeadlineNode::canonical_body`]: struct.HeadlineNode.html#method.canonical_body
+    pub body_hash: u64,
+}
+
+impl<'a, 'b> Entry<'a, 'b> {
+    pub(crate) fn new(headline: HeadlineNode, org: &'b Org<'a>) -> Entry<'a, 'b> {
+        let title = headline.title(org);
+
+        Entry {
+            headline,
+            level: headline.level(),
+            title: &title.raw,
+            state: title.keyword.as_ref().map(AsRef::as_ref),
+            tags: &title.tags,
+            scheduled: title.scheduled(),
+            deadline: title.deadline(),
+            closed: title.closed(),
+            created: headline.created(org),
+            last_repeat: headline.last_repeat(org),
+            body_hash: fnv1a(headline.canonical_body(org).as_bytes()),
+        }
+    }
+}
+
+// 64 bit FNV-1a, unlike `DefaultHasher` its output never changes
+fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash = 0xcbf2_9ce4_8422_2325;
+    for byte in bytes {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+#[test]
+fn fnv1a_() {
+    assert_eq!(fnv1a(b""), 0xcbf2_9ce4_8422_2325);
+    assert_eq!(fnv1a(b"a"), 0xaf63_dc4c_8601_ec8c);
+    assert_eq!(fnv1a(b"foobar"), 0x8594_4171_f739_67e8);
+}
@@ -24,6 +24,12 @@ pub enum OrgizeError {
         min: Option<usize>,
         at: NodeId,
     },
+    /// Expect a macro defined by a `#+MACRO:` keyword or built in, see
+    /// `Org::try_expand_macros`
+    UndefinedMacro { at: NodeId },
+    /// Expect the element changed by the change at `index` of the replayed
+    /// log to be in the state it was logged from, see `Org::apply_changes`
+    ChangeConflict { index: usize, at: NodeId },
 }
 
 impl OrgizeError {
@@ -35,7 +41,9 @@ impl OrgizeError {
             | OrgizeError::Title { at }
             | OrgizeError::Headline { at }
             | OrgizeError::Detached { at }
-            | OrgizeError::HeadlineLevel { at, .. } => org.arena[*at].get(),
+            | OrgizeError::HeadlineLevel { at, .. }
+            | OrgizeError::UndefinedMacro { at }
+            | OrgizeError::ChangeConflict { at, .. } => org.arena[*at].get(),
         }
     }
 }
@@ -90,50 +98,64 @@ impl Org<'_> {
                 | Element::InlineSrc(_)
                 | Element::Code { .. }
                 | Element::FnRef(_)
+                | Element::Citation(_)
                 | Element::InlineCall(_)
-                | Element::Link(_)
+                | Element::Link(Link { desc: None, .. })
                 | Element::Macros(_)
-                | Element::RadioTarget
+                | Element::RadioTarget(_)
                 | Element::Snippet(_)
                 | Element::Target(_)
                 | Element::Text { .. }
                 | Element::Timestamp(_)
                 | Element::Verbatim { .. }
-                | Element::FnDef(_)
                 | Element::Clock(_)
                 | Element::Comment { .. }
                 | Element::FixedWidth { .. }
+                | Element::Raw { .. }
+                | Element::RawHtml(_)
                 | Element::Keyword(_)
+                | Element::LatexFragment(_)
+                | Element::Entity(_)
                 | Element::Rule
+                | Element::LineBreak
                 | Element::Cookie(_)
                 | Element::Table(Table::TableEl { .. })
-                | Element::TableRow(TableRow::Rule) => {
+                | Element::TableRow(TableRow::HeaderRule)
+                | Element::TableRow(TableRow::BodyRule) => {
                     if node.first_child().is_some() {
                         return Err(OrgizeError::NoChildren { at: node_id });
                     }
                 }
-                Element::SpecialBlock(_)
-                | Element::QuoteBlock(_)
-                | Element::CenterBlock(_)
-                | Element::VerseBlock(_)
-                | Element::Paragraph
+                Element::Paragraph
+                | Element::ListItemTag
                 | Element::Section
                 | Element::Table(Table::Org { .. })
-                | Element::TableRow(TableRow::Standard)
                 | Element::Bold
                 | Element::Italic
                 | Element::Underline
+                | Element::Subscript { .. }
+                | Element::Superscript { .. }
                 | Element::Strike
-                | Element::DynBlock(_)
-                | Element::List(_)
-                | Element::ListItem(_) => {
+                | Element::List(_) => {
                     if node.first_child().is_none() {
                         return Err(OrgizeError::Children { at: node_id });
                     }
                 }
-                // TableCell is a container but it might
-                // not contains anything, e.g. `||||||`
-                Element::Drawer(_) | Element::TableCell => (),
+                // These elements are containers but they might not
+                // contain anything, e.g. `||||||`, `- ` or an empty block
+                Element::SpecialBlock(_)
+                | Element::QuoteBlock(_)
+                | Element::CenterBlock(_)
+                | Element::VerseBlock(_)
+                | Element::Caption(_)
+                | Element::DynBlock(_)
+                | Element::ListItem(_)
+                | Element::FnDef(_)
+                | Element::Link(Link { desc: Some(_), .. })
+                | Element::Drawer(_)
+                | Element::TableRow(TableRow::Header)
+                | Element::TableRow(TableRow::Body)
+                | Element::TableCell(_) => (),
             }
         }
         Ok(())
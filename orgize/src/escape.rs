@@ -0,0 +1,251 @@
+//! Escaping of plain text inserted into org documents
+//!
+//! Text like `*not bold*`, `[[not a link]]` or a line starting with `#+` is
+//! parsed as markup. These functions return org text which is parsed back
+//! into the plain text, by inserting zero-width spaces where markup could
+//! start, as recommended by the org manual, or by protecting lines of
+//! verbatim blocks with a comma.
+//!
+//! ```rust
+//! use orgize::{escape, Org};
+//!
+//! # #[cfg(feature = "export-html")] {
+//! let text = format!("* {}\n", escape::headline_title("*not bold* and [[not a link]]"));
+//! let org = Org::parse(&text);
+//!
+//! let mut writer = Vec::new();
+//! org.html(&mut writer).unwrap();
+//! assert_eq!(
+//!     String::from_utf8(writer).unwrap().replace(escape::ZERO_WIDTH_SPACE, ""),
+//!     "<main><h1>*not bold* and [[not a link]]</h1></main>"
+//! );
+//! # }
+//! ```
+
+use std::borrow::Cow;
+
+use crate::elements::block::escape_contents;
+
+/// The character inserted by [`text`] and [`headline_title`], `U+200B`
+///
+/// It's not whitespace, so it prevents markup from starting, and isn't
+/// displayed.
+///
+/// [`text`]: fn.text.html
+/// [`headline_title`]: fn.headline_title.html
+pub const ZERO_WIDTH_SPACE: char = '\u{200B}';
+
+/// Escapes the text of paragraphs
+///
+/// The escaped text is parsed into paragraphs containing only text, which
+/// is `text` once the zero-width spaces are removed. Blank lines are kept
+/// in the same paragraph, and spaces ending `text` are followed by a
+/// zero-width space.
+///
+/// ```rust
+/// use orgize::escape;
+///
+/// assert_eq!(escape::text("plain text"), "plain text");
+/// assert_eq!(escape::text("a *b*"), "a \u{200B}*b*");
+/// assert_eq!(escape::text("a\n#+TITLE: b"), "a\n\u{200B}#+TITLE: b");
+/// assert_eq!(escape::text("[[link]]"), "\u{200B}[\u{200B}[\u{200B}link]]");
+/// assert_eq!(escape::text("a "), "a \u{200B}");
+/// ```
+pub fn text(text: &str) -> Cow<'_, str> {
+    let mut escaped = String::with_capacity(text.len());
+    for (i, line) in text.split('\n').enumerate() {
+        if i > 0 {
+            escaped.push('\n');
+        }
+        if starts_element(line) {
+            escaped.push(ZERO_WIDTH_SPACE);
+        }
+        escape_objects(line, &mut escaped);
+    }
+    // the parser removes the spaces and tabs ending paragraphs
+    if text.ends_with(&[' ', '\t'][..]) {
+        escaped.push(ZERO_WIDTH_SPACE);
+    }
+
+    if escaped == text {
+        Cow::Borrowed(text)
+    } else {
+        Cow::Owned(escaped)
+    }
+}
+
+/// Escapes the text of a headline title
+///
+/// Besides the objects of [`text`], it escapes words which could be parsed
+/// as a todo keyword, a priority cookie or tags. Titles can't span several
+/// lines, so newlines are replaced with spaces.
+///
+/// ```rust
+/// use orgize::escape;
+///
+/// assert_eq!(escape::headline_title("Meeting notes"), "Meeting notes");
+/// assert_eq!(escape::headline_title("TODO list"), "\u{200B}TODO list");
+/// assert_eq!(escape::headline_title("Ratio :a:b:"), "Ratio :a:b:\u{200B}");
+/// ```
+///
+/// [`text`]: fn.text.html
+pub fn headline_title(title: &str) -> Cow<'_, str> {
+    let line = title.replace(&['\n', '\r'][..], " ");
+
+    let keyword = line
+        .split(' ')
+        .next()
+        .filter(|word| !word.is_empty() && word.chars().all(|c| c.is_ascii_uppercase()));
+
+    let mut escaped = String::with_capacity(line.len());
+    if keyword.is_some() || line.starts_with(char::is_whitespace) {
+        escaped.push(ZERO_WIDTH_SPACE);
+    }
+    escape_objects(&line, &mut escaped);
+    if line.ends_with(':') || line.ends_with(char::is_whitespace) {
+        escaped.push(ZERO_WIDTH_SPACE);
+    }
+
+    if escaped == title {
+        Cow::Borrowed(title)
+    } else {
+        Cow::Owned(escaped)
+    }
+}
+
+/// Escapes the contents of verbatim blocks, like source and example blocks
+///
+/// Lines starting with `*` or `#+`, like `#+END_SRC`, are protected with a
+/// comma, which the parser removes. The org writer escapes the contents of
+/// block elements itself.
+///
+/// ```rust
+/// use orgize::escape;
+///
+/// assert_eq!(
+///     escape::verbatim("* a\n  #+END_SRC\nb\n"),
+///     ",* a\n  ,#+END_SRC\nb\n"
+/// );
+/// ```
+pub fn verbatim(contents: &str) -> Cow<'_, str> {
+    escape_contents(contents)
+}
+
+/// Returns `true` if `line` could be parsed as the start of an element
+/// other than a paragraph, like a headline, a list item or a keyword
+fn starts_element(line: &str) -> bool {
+    let starts_with = |prefix: &str| {
+        line.get(0..prefix.len())
+            .filter(|start| start.eq_ignore_ascii_case(prefix))
+            .is_some()
+    };
+
+    match line.chars().next() {
+        None => true,
+        Some(c) if c.is_whitespace() || c.is_ascii_digit() => true,
+        Some('*') | Some('#') | Some(':') | Some('|') | Some('+') | Some('-') | Some('[') => true,
+        _ => ["CLOCK:", "SCHEDULED:", "DEADLINE:", "CLOSED:"]
+            .iter()
+            .any(|keyword| starts_with(keyword)),
+    }
+}
+
+/// Writes `text`, with zero-width spaces where objects could start
+///
+/// Objects start after the characters listed by `InlinePositions`, or at
+/// `@`, `<`, `[`, `{`, `$` and `\`, which are followed by a zero-width
+/// space.
+/// Emphasis markers and inline calls at the start of an object are preceded
+/// by one, and `_` and `^` followed by `{` are followed by one, so they
+/// don't start braced subscripts and superscripts.
+fn escape_objects(text: &str, escaped: &mut String) {
+    for (i, c) in text.char_indices() {
+        let object_start = matches!(
+            escaped.chars().last(),
+            None | Some(' ') | Some('(') | Some('\'') | Some('"') | Some('\n')
+        );
+        if object_start
+            && ("*+/_=~".contains(c)
+                || text[i..].starts_with("src_")
+                || text[i..].starts_with("call_"))
+        {
+            escaped.push(ZERO_WIDTH_SPACE);
+        }
+
+        escaped.push(c);
+
+        if "@<[{$\\".contains(c) || ("_^".contains(c) && text[i + 1..].starts_with('{')) {
+            escaped.push(ZERO_WIDTH_SPACE);
+        }
+    }
+}
+
+/// Returns the text of `events`, without zero-width spaces, or `None` if
+/// there are elements other than `allowed` and text
+#[cfg(test)]
+fn plain_text<'a: 'b, 'b>(
+    events: impl Iterator<Item = crate::Event<'a, 'b>>,
+    allowed: fn(&crate::Element<'_>) -> bool,
+) -> Option<String> {
+    use crate::{Element, Event};
+
+    let mut plain_text = String::new();
+    for event in events {
+        match event {
+            Event::Start(Element::Text { value }) => plain_text.push_str(value),
+            Event::End(Element::Text { .. }) => (),
+            Event::Start(element) | Event::End(element) if allowed(element) => (),
+            _ => return None,
+        }
+    }
+    Some(plain_text.replace(ZERO_WIDTH_SPACE, ""))
+}
+
+#[cfg(test)]
+proptest::proptest! {
+    #[test]
+    fn text_round_trip(
+        plain in r"[a-z0-9 *+/_=~@<>\[\]{}():#|.$^\\\-\n]{0,40}|(src_|call_|CLOCK: |SCHEDULED: )[a-z{}()\[\]]{0,10}"
+    ) {
+        use crate::{Element, Org};
+
+        let escaped = text(&plain);
+        let org = Org::parse(&escaped);
+        let allowed = |element: &Element<'_>| {
+            matches!(element, Element::Document | Element::Section | Element::Paragraph)
+        };
+        proptest::prop_assert_eq!(plain_text(org.iter(), allowed), Some(plain));
+    }
+
+    #[test]
+    fn headline_title_round_trip(
+        title in r"(TODO |COMMENT |\[#A\] )?[a-zA-Z0-9 *+/_=~@<>\[\]{}():#|.$^\\\-]{0,30}"
+    ) {
+        use crate::{Element, Org};
+
+        let text = format!("* {}\n", headline_title(&title));
+        let org = Org::parse(&text);
+        let headline = org.headlines().next().unwrap();
+        proptest::prop_assert_eq!(headline.title(&org).keyword.as_ref(), None);
+        proptest::prop_assert_eq!(headline.title(&org).priority, None);
+        proptest::prop_assert!(headline.title(&org).tags.is_empty());
+
+        let allowed = |element: &Element<'_>| {
+            matches!(element, Element::Document | Element::Headline { .. } | Element::Title(_))
+        };
+        proptest::prop_assert_eq!(plain_text(org.iter(), allowed), Some(title));
+    }
+
+    #[test]
+    fn verbatim_round_trip(contents in crate::test_support::block_contents()) {
+        use crate::{Element, Event, Org};
+
+        let text = format!("#+BEGIN_EXAMPLE\n{}#+END_EXAMPLE\n", verbatim(&contents));
+        let org = Org::parse(&text);
+        let value = org.iter().find_map(|event| match event {
+            Event::Start(Element::ExampleBlock(block)) => Some(block.contents.to_string()),
+            _ => None,
+        });
+        proptest::prop_assert_eq!(value, Some(contents));
+    }
+}
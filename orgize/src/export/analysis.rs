@@ -0,0 +1,1861 @@
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::io::{Error, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+use indextree::{NodeEdge, NodeId};
+
+use crate::captioned::{captioned, is_affiliated, is_figure, is_listing, is_table};
+use crate::code_block::parse_header_args;
+use crate::config::ParseConfig;
+use crate::diagnostics::{locate, Diagnostic, Severity};
+use crate::elements::{
+    Datetime, Element, ExportBlock, Link, LinkFormat, List, ListBullet, ListItem, Table, Target,
+};
+use crate::export::driver::{exclude, exported_edges, is_headline};
+use crate::export::html::image_src;
+use crate::export::macros::{expand_date_macro, format_date};
+use crate::export::{
+    block_contents, is_unknown_keyword, EncodeHref, Escape, ExportStrings, HtmlHandler,
+    SpecialBlockPolicy,
+};
+use crate::node::HeadlineNode;
+use crate::org::Org;
+use crate::settings::{ExportSettings, Levels, SettingsResolver};
+
+/// Options of the html export, see [`Org::analyze_for_html`]
+///
+/// [`Org::analyze_for_html`]: ../struct.Org.html#method.analyze_for_html
+#[derive(Clone, Debug)]
+pub struct HtmlExportOptions {
+    /// Strings written in the output, like `Table %d`, defaults to the
+    /// built-in strings of the `#+LANGUAGE:` of the document, or to English
+    pub strings: Option<ExportStrings>,
+    /// Headlines with one of these tags aren't exported, nor their subtree,
+    /// defaults to `noexport`
+    ///
+    /// The tags of the `#+EXCLUDE_TAGS:` keywords of the document are
+    /// excluded too, and commented headlines are never exported.
+    pub exclude_tags: Vec<String>,
+    /// If any headline has one of these tags, only these headlines, their
+    /// subtree and their ancestors are exported, empty by default
+    ///
+    /// The tags of the `#+SELECT_TAGS:` keywords of the document are
+    /// selected too.
+    pub select_tags: Vec<String>,
+    /// Exports only the title of headlines tagged `ARCHIVE`, without their
+    /// section and child headlines, `false` by default
+    pub skip_archived: bool,
+    /// Writes an anchor before every headline, not only when they're listed
+    /// by a `#+TOC:` keyword or referenced by a link
+    ///
+    /// Enable it when the table of contents is rendered separately.
+    pub headline_anchors: bool,
+    /// Format of the `#+DATE:` of full pages, with `strftime` specifiers like
+    /// `%Y-%m-%d`
+    ///
+    /// Formatting needs the `chrono` feature. Dates which aren't timestamps
+    /// are written as is, as are all dates without a format.
+    pub date_format: Option<String>,
+    /// Writes the date in a `<meta name="date">` tag of full pages, enabled
+    /// by default
+    pub date_meta: bool,
+    /// Writes the date in a `<p class="subtitle">` at the top of full pages
+    pub date_subtitle: bool,
+    /// Modification time of the document, written by the
+    /// `{{{modification-time(FORMAT)}}}` macro, which is empty otherwise
+    pub modification_time: Option<Datetime<'static>>,
+    /// Omits the `<p>` of paragraphs which are the only child of an item of
+    /// a tight list, a list without blank lines between its items
+    pub tight_lists: bool,
+    /// Configuration the document was parsed with, telling done keywords
+    /// from todo keywords unless the document has `#+TODO:` keywords
+    pub parse_config: ParseConfig,
+    /// Makes [`org_to_html`] fail with the diagnostics of the document if
+    /// one of them is at least as severe as `parse_config.strict_severity`,
+    /// `false` by default
+    ///
+    /// [`org_to_html`]: ../fn.org_to_html.html
+    pub strict: bool,
+    /// Writes the bibliography at `#+PRINT_BIBLIOGRAPHY:` keywords, which
+    /// are left empty without a renderer
+    pub bibliography_renderer: Option<Arc<dyn BibliographyRenderer>>,
+    /// Inlines the local images linked without description as `data:` URIs,
+    /// for pages which don't depend on other files
+    ///
+    /// Images the resolver declines keep their path as `src`, and are listed
+    /// by [`HtmlExportAnalysis::diagnostics`].
+    ///
+    /// [`HtmlExportAnalysis::diagnostics`]: struct.HtmlExportAnalysis.html#method.diagnostics
+    pub image_resolver: Option<Arc<dyn ImageResolver>>,
+    /// Leaves the images larger than this number of bytes with their path as
+    /// `src`, with a diagnostic, instead of inlining them
+    pub max_inline_image_size: Option<usize>,
+    /// Writes a table of contents of the headlines up to this level at the
+    /// start of the document, overriding `#+OPTIONS: toc:`
+    pub toc: Option<usize>,
+    /// Derives the ids of headlines without `CUSTOM_ID` from their title,
+    /// with [`slugify`], instead of numbering them like `headline-1`
+    ///
+    /// Repeated ids get a `-1`, `-2`… suffix. The ids are written on the
+    /// headings, like `<h1 id="getting-started">`, instead of on anchors
+    /// before them, see [`HtmlHandler::heading_start`].
+    ///
+    /// [`slugify`]: fn.slugify.html
+    /// [`HtmlHandler::heading_start`]: trait.HtmlHandler.html#method.heading_start
+    pub slug_ids: bool,
+    /// Writes [`DEFAULT_CSS`] in the `<head>` of full pages
+    ///
+    /// [`DEFAULT_CSS`]: constant.DEFAULT_CSS.html
+    #[cfg(feature = "css")]
+    pub embed_css: bool,
+}
+
+/// Generates the bibliography of a document, see
+/// [`HtmlExportOptions::bibliography_renderer`]
+///
+/// ```rust
+/// use std::sync::Arc;
+///
+/// use orgize::export::{BibliographyRenderer, DefaultHtmlHandler, HtmlExportOptions};
+/// use orgize::Org;
+///
+/// struct Keys;
+///
+/// impl BibliographyRenderer for Keys {
+///     fn render(&self, keys: &[&str], _options: &str) -> String {
+///         format!("<p>{}</p>", keys.join(", "))
+///     }
+/// }
+///
+/// let org = Org::parse("#+PRINT_BIBLIOGRAPHY:\n\n[cite:@doe]\n");
+/// let analysis = org.analyze_for_html(&HtmlExportOptions {
+///     bibliography_renderer: Some(Arc::new(Keys)),
+///     ..Default::default()
+/// });
+///
+/// let mut writer = Vec::new();
+/// org.html_with_analysis(&mut writer, &mut DefaultHtmlHandler::default(), &analysis)
+///     .unwrap();
+/// assert_eq!(
+///     String::from_utf8(writer).unwrap(),
+///     "<main><section><p>doe</p><p><cite>(doe)</cite></p></section></main>"
+/// );
+/// ```
+///
+/// [`HtmlExportOptions::bibliography_renderer`]: struct.HtmlExportOptions.html#structfield.bibliography_renderer
+pub trait BibliographyRenderer: Send + Sync {
+    /// Returns the html written at a `#+PRINT_BIBLIOGRAPHY:` keyword
+    ///
+    /// `keys` are the keys of all the exported citations, in the order they
+    /// are first cited, wherever the keyword is. `options` is the value of
+    /// the keyword, e.g. `:style numeric`.
+    fn render(&self, keys: &[&str], options: &str) -> String;
+}
+
+impl fmt::Debug for dyn BibliographyRenderer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("BibliographyRenderer")
+    }
+}
+
+/// Returns the mime type and the contents of a local image, see
+/// [`HtmlExportOptions::image_resolver`]
+///
+/// It's implemented by closures, so orgize doesn't read any file itself:
+///
+/// ```rust
+/// use std::sync::Arc;
+///
+/// use orgize::export::{DefaultHtmlHandler, HtmlExportOptions};
+/// use orgize::Org;
+///
+/// let org = Org::parse("[[file:dot.gif]]\n\n[[./missing.png]]\n");
+/// let analysis = org.analyze_for_html(&HtmlExportOptions {
+///     image_resolver: Some(Arc::new(|path: &std::path::Path| {
+///         if path.ends_with("dot.gif") {
+///             Some(("image/gif".to_string(), b"GIF89a".to_vec()))
+///         } else {
+///             None
+///         }
+///     })),
+///     ..Default::default()
+/// });
+///
+/// let mut writer = Vec::new();
+/// org.html_with_analysis(&mut writer, &mut DefaultHtmlHandler::default(), &analysis)
+///     .unwrap();
+/// assert_eq!(
+///     String::from_utf8(writer).unwrap(),
+///     "<main><section><p><img src=\"data:image/gif;base64,R0lGODlh\" alt=\"dot.gif\"></p>\
+///      <p><img src=\"./missing.png\" alt=\"./missing.png\"></p></section></main>"
+/// );
+/// assert_eq!(
+///     analysis.diagnostics()[0].message,
+///     "image ./missing.png isn't inlined: it can't be resolved"
+/// );
+/// ```
+///
+/// [`HtmlExportOptions::image_resolver`]: struct.HtmlExportOptions.html#structfield.image_resolver
+pub trait ImageResolver: Send + Sync {
+    /// Returns the mime type and the contents of the image at `path`, as
+    /// written in the link without its `file:` prefix, or `None` to keep
+    /// the path as `src`
+    fn resolve(&self, path: &Path) -> Option<(String, Vec<u8>)>;
+}
+
+impl<F> ImageResolver for F
+where
+    F: Fn(&Path) -> Option<(String, Vec<u8>)> + Send + Sync,
+{
+    fn resolve(&self, path: &Path) -> Option<(String, Vec<u8>)> {
+        self(path)
+    }
+}
+
+impl fmt::Debug for dyn ImageResolver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ImageResolver")
+    }
+}
+
+impl Default for HtmlExportOptions {
+    fn default() -> Self {
+        HtmlExportOptions {
+            strings: None,
+            exclude_tags: vec!["noexport".into()],
+            select_tags: Vec::new(),
+            skip_archived: false,
+            headline_anchors: false,
+            date_format: None,
+            date_meta: true,
+            date_subtitle: false,
+            modification_time: None,
+            tight_lists: false,
+            parse_config: ParseConfig::default(),
+            strict: false,
+            bibliography_renderer: None,
+            image_resolver: None,
+            max_inline_image_size: None,
+            toc: None,
+            slug_ids: false,
+            #[cfg(feature = "css")]
+            embed_css: false,
+        }
+    }
+}
+
+/// Document wide information needed by the html export, computed before
+/// rendering by [`Org::analyze_for_html`]
+///
+/// The same analysis can be used by several render calls, and is passed to
+/// handlers through [`HtmlHandler::set_context`].
+///
+/// [`Org::analyze_for_html`]: ../struct.Org.html#method.analyze_for_html
+/// [`HtmlHandler::set_context`]: trait.HtmlHandler.html#method.set_context
+#[derive(Debug)]
+pub struct HtmlExportAnalysis {
+    /// Ids of the headlines and of the numbered tables, figures and listings
+    ids: HashMap<NodeId, String>,
+    /// Ids of the anchors written before these nodes
+    anchors: HashMap<NodeId, String>,
+    /// Labels written at the start of these captions, e.g. `Table 1: `
+    caption_labels: HashMap<NodeId, String>,
+    /// Captions of the numbered tables, figures and listings, which are
+    /// written with them instead of before them
+    captions: HashMap<NodeId, NodeId>,
+    /// Numbers of the numbered headlines, see `UNNUMBERED` and `num:`
+    section_numbers: HashMap<NodeId, String>,
+    /// Headlines left out of tables of contents by `:UNNUMBERED: notoc`
+    unlisted: HashSet<NodeId>,
+    numbers: HashMap<NodeId, usize>,
+    footnote_numbers: HashMap<NodeId, usize>,
+    /// Ids of the footnote references, `fnr.N`, then `fnr.N.2`…
+    footnote_ids: HashMap<NodeId, String>,
+    /// Definitions of the footnotes by number, written at the end of the
+    /// document
+    footnotes: Vec<Option<Footnote>>,
+    /// Links without description to numbered elements, and the links to
+    /// render instead, e.g. `Table 1` pointing to the anchor of the table
+    references: HashMap<NodeId, Element<'static>>,
+    /// Expansions of the `date` and `modification-time` macros
+    macros: HashMap<NodeId, String>,
+    /// Paragraphs rendered without `<p>`, in items of tight lists
+    unwrapped: HashSet<NodeId>,
+    /// Todo keywords in order, if they're done keywords, and their numbers of
+    /// exported headlines
+    todo_keywords: Vec<(String, bool, usize)>,
+    /// Roots of the subtrees which aren't exported
+    excluded: HashSet<NodeId>,
+    /// Options of the exported `#+OPTIONS:` keywords
+    settings: ExportSettings,
+    /// Titles of the headlines deeper than `H:`, rendered as bold paragraphs
+    deep_titles: HashSet<NodeId>,
+    /// Headlines whose heading carries their id, with `slug_ids`, instead of
+    /// an anchor written before it
+    id_headings: HashSet<NodeId>,
+    /// Headlines listed by the table of contents at the start of the
+    /// document, see `toc` and `toc:`
+    toc: Option<Levels>,
+    /// Values of `#+TITLE:` and of the formatted `#+DATE:`
+    title: Option<String>,
+    date: Option<String>,
+    /// Value of the first `#+LANGUAGE:`
+    language: Option<String>,
+    strings: ExportStrings,
+    date_meta: bool,
+    date_subtitle: bool,
+    tables: Vec<TocEntry>,
+    figures: Vec<TocEntry>,
+    listings: Vec<TocEntry>,
+    /// Bibliographies written at `#+PRINT_BIBLIOGRAPHY:` keywords
+    bibliographies: HashMap<NodeId, String>,
+    /// `data:` URIs of the inlined images
+    images: HashMap<NodeId, String>,
+    /// Attributes of the `#+ATTR_HTML:` keywords of figures, by image link
+    image_attributes: HashMap<NodeId, Vec<(String, String)>>,
+    #[cfg(feature = "css")]
+    embed_css: bool,
+    diagnostics: Vec<Diagnostic>,
+}
+
+/// A headline of the outline of a document, see [`Org::outline`]
+///
+/// [`Org::outline`]: ../struct.Org.html#method.outline
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "ser", derive(serde::Serialize))]
+pub struct OutlineEntry {
+    /// Text of the title, without markup
+    pub title: String,
+    pub level: usize,
+    /// Id of the headline in the html export
+    pub id: String,
+    /// Section number, e.g. `2.1`, `None` for unnumbered headlines
+    #[cfg_attr(feature = "ser", serde(skip_serializing_if = "Option::is_none"))]
+    pub number: Option<String>,
+    /// Todo keyword of the headline, e.g. `TODO` or `DONE`
+    #[cfg_attr(feature = "ser", serde(skip_serializing_if = "Option::is_none"))]
+    pub todo: Option<String>,
+    #[cfg_attr(feature = "ser", serde(skip_serializing_if = "Vec::is_empty"))]
+    pub children: Vec<OutlineEntry>,
+}
+
+#[derive(Debug)]
+struct TocEntry {
+    id: String,
+    text: String,
+}
+
+/// Definition of a footnote
+#[derive(Debug)]
+enum Footnote {
+    /// `[fn:LABEL] ...`, whose children are written
+    Node(NodeId),
+    /// `[fn:LABEL: ...]` or `[fn:: ...]`, written as a paragraph of text
+    Inline(String),
+}
+
+/// A numbered element which can be the target of a link
+struct Numbered {
+    /// Node the anchor is written before
+    anchor: NodeId,
+    id: String,
+    /// Formatted number, e.g. `Table 1` or `Section 2.1`
+    label: String,
+}
+
+/// Contents of a `#+TOC:` keyword
+enum Toc {
+    Headlines { depth: Option<usize>, local: bool },
+    Tables,
+    Figures,
+    Listings,
+}
+
+impl Toc {
+    fn parse(value: &str) -> Option<Toc> {
+        let mut words = value.split_whitespace();
+
+        match &*words.next()?.to_lowercase() {
+            "headlines" => {
+                let mut depth = None;
+                let mut local = false;
+                for word in words {
+                    if word.eq_ignore_ascii_case("local") {
+                        local = true;
+                    } else if let Ok(n) = word.parse() {
+                        depth = Some(n);
+                    }
+                }
+                Some(Toc::Headlines { depth, local })
+            }
+            "tables" => Some(Toc::Tables),
+            "figures" => Some(Toc::Figures),
+            "listings" => Some(Toc::Listings),
+            _ => None,
+        }
+    }
+}
+
+impl HtmlExportAnalysis {
+    pub(crate) fn new(org: &Org<'_>, options: &HtmlExportOptions) -> HtmlExportAnalysis {
+        let mut analysis = HtmlExportAnalysis {
+            ids: HashMap::new(),
+            anchors: HashMap::new(),
+            caption_labels: HashMap::new(),
+            captions: HashMap::new(),
+            section_numbers: HashMap::new(),
+            unlisted: HashSet::new(),
+            numbers: HashMap::new(),
+            footnote_numbers: HashMap::new(),
+            footnote_ids: HashMap::new(),
+            footnotes: Vec::new(),
+            references: HashMap::new(),
+            macros: HashMap::new(),
+            unwrapped: HashSet::new(),
+            todo_keywords: SettingsResolver::new(org, &options.parse_config)
+                .todo_keyword_settings()
+                .into_iter()
+                .map(|keyword| (keyword.name, keyword.done, 0))
+                .collect(),
+            excluded: exclude(
+                org,
+                &options.exclude_tags,
+                &options.select_tags,
+                options.skip_archived,
+            ),
+            settings: ExportSettings::default(),
+            deep_titles: HashSet::new(),
+            id_headings: HashSet::new(),
+            toc: None,
+            title: None,
+            date: None,
+            language: None,
+            strings: ExportStrings::default(),
+            date_meta: options.date_meta,
+            date_subtitle: options.date_subtitle,
+            tables: Vec::new(),
+            figures: Vec::new(),
+            listings: Vec::new(),
+            bibliographies: HashMap::new(),
+            images: HashMap::new(),
+            image_attributes: image_attributes(org),
+            #[cfg(feature = "css")]
+            embed_css: options.embed_css,
+            diagnostics: Vec::new(),
+        };
+
+        // the elements listed by a `#+TOC:` keyword get an anchor, and full
+        // pages show the first `#+TITLE:` and `#+DATE:`
+        let (mut headlines, mut tables, mut figures, mut listings) = (
+            options.headline_anchors || options.slug_ids,
+            false,
+            false,
+            false,
+        );
+        let (mut title, mut date, mut language) = (None, None, None);
+        let mut export_settings = ExportSettings::default();
+        // bibliographies list the keys cited anywhere in the document
+        let (mut cited_keys, mut bibliographies) = (Vec::new(), Vec::new());
+        for node in analysis.exported(org, org.root) {
+            if let Element::Citation(citation) = org.arena[node].get() {
+                for key in citation.keys() {
+                    if !cited_keys.contains(&key) {
+                        cited_keys.push(key);
+                    }
+                }
+            }
+            if let Element::Keyword(keyword) = org.arena[node].get() {
+                if keyword.key.eq_ignore_ascii_case("PRINT_BIBLIOGRAPHY") {
+                    bibliographies.push((node, &keyword.value));
+                } else if keyword.key.eq_ignore_ascii_case("TITLE") {
+                    title = title.or(Some(&keyword.value));
+                } else if keyword.key.eq_ignore_ascii_case("DATE") {
+                    date = date.or(Some(&keyword.value));
+                } else if keyword.key.eq_ignore_ascii_case("LANGUAGE") {
+                    language = language.or(Some(keyword.value.trim()));
+                } else if keyword.key.eq_ignore_ascii_case("OPTIONS") {
+                    export_settings.update(&keyword.value);
+                } else if keyword.key.eq_ignore_ascii_case("TOC") {
+                    match Toc::parse(&keyword.value) {
+                        Some(Toc::Headlines { .. }) => headlines = true,
+                        Some(Toc::Tables) => tables = true,
+                        Some(Toc::Figures) => figures = true,
+                        Some(Toc::Listings) => listings = true,
+                        None => (),
+                    }
+                }
+            }
+        }
+
+        for (node, value) in bibliographies {
+            match &options.bibliography_renderer {
+                Some(renderer) => {
+                    let html = renderer.render(&cited_keys, value.trim());
+                    analysis.bibliographies.insert(node, html);
+                }
+                None => analysis.diagnostics.push(Diagnostic::new(
+                    Severity::Warning,
+                    org.spans.get(&node).cloned(),
+                    "#+PRINT_BIBLIOGRAPHY: needs a bibliography renderer".into(),
+                )),
+            }
+        }
+
+        if let Some(strings) = &options.strings {
+            analysis.strings = strings.clone();
+        } else if let Some(strings) = language.and_then(ExportStrings::for_language) {
+            analysis.strings = strings;
+        }
+        analysis.language = language.map(Into::into);
+        let strings = analysis.strings.clone();
+
+        // link targets, by `#+NAME:`, by `CUSTOM_ID` prefixed with `#` and
+        // by title prefixed with `*`
+        let mut targets: HashMap<String, Numbered> = HashMap::new();
+
+        let kinds: [(&str, fn(&Org<'_>, NodeId) -> bool, bool, &str); 3] = [
+            ("table", is_table, tables, &strings.table),
+            ("figure", is_figure, figures, &strings.figure),
+            ("listing", is_listing, listings, &strings.listing),
+        ];
+
+        for (i, (kind, is_kind, in_toc, format)) in kinds.iter().enumerate() {
+            let mut number = 0;
+
+            for item in captioned(org, *is_kind) {
+                let caption = match item.caption {
+                    Some(caption) if !analysis.is_excluded(org, item.node) => caption,
+                    _ => continue,
+                };
+                number += 1;
+
+                let label = format.replace("%d", &number.to_string());
+                let id = item
+                    .name
+                    .map(String::from)
+                    .unwrap_or_else(|| format!("{}-{}", kind, number));
+                let text = format!("{}: {}", label, plain_text(org, caption));
+
+                analysis.ids.insert(item.node, id.clone());
+                analysis.numbers.insert(item.node, number);
+                analysis
+                    .caption_labels
+                    .insert(caption, format!("{}: ", label));
+                analysis.captions.insert(item.node, caption);
+                if *in_toc {
+                    analysis.anchors.insert(caption, id.clone());
+                }
+
+                let entries = match i {
+                    0 => &mut analysis.tables,
+                    1 => &mut analysis.figures,
+                    _ => &mut analysis.listings,
+                };
+                entries.push(TocEntry {
+                    id: id.clone(),
+                    text,
+                });
+
+                if let Some(name) = item.name {
+                    targets.insert(
+                        name.to_string(),
+                        Numbered {
+                            anchor: caption,
+                            id,
+                            label,
+                        },
+                    );
+                }
+            }
+        }
+
+        // `num:nil` numbers no headline, `num:N` the headlines up to level N
+        let num_level = match export_settings.num {
+            Some(Levels::None) => Some(0),
+            Some(Levels::UpTo(level)) => Some(level),
+            Some(Levels::All) | None => None,
+        };
+        // `toc:t` and `toc:N` write a table of contents linking to headlines
+        analysis.toc = options.toc.map(Levels::UpTo).or(export_settings.toc);
+        if analysis.toc.filter(|&toc| toc != Levels::None).is_some() {
+            headlines = true;
+        }
+        // slugs don't take the custom ids of later headlines
+        let mut used_ids: HashSet<String> = if options.slug_ids {
+            org.headlines()
+                .filter_map(|headline| headline.title(org).property("CUSTOM_ID"))
+                .map(Into::into)
+                .collect()
+        } else {
+            HashSet::new()
+        };
+        let mut unnumbered = HashSet::new();
+
+        // sections are numbered like `2.1`, following the headline tree,
+        // skipping the unnumbered headlines
+        for (i, headline) in org.headlines().enumerate() {
+            if analysis.is_excluded(org, headline.node) {
+                continue;
+            }
+
+            let title = headline.title(org);
+            if let Some(keyword) = &title.keyword {
+                match analysis
+                    .todo_keywords
+                    .iter_mut()
+                    .find(|(name, _, _)| name == keyword)
+                {
+                    Some((_, _, count)) => *count += 1,
+                    None => analysis.todo_keywords.push((keyword.to_string(), false, 1)),
+                }
+            }
+
+            let property = |key: &str| title.property(key).map(str::trim);
+            let parent = headline.parent(org);
+
+            // `UNNUMBERED` is inherited, and `notoc` also leaves the toc
+            let inherited = |set: &HashSet<NodeId>| {
+                parent
+                    .as_ref()
+                    .map(|parent| set.contains(&parent.node))
+                    .unwrap_or(false)
+            };
+            match property("UNNUMBERED") {
+                Some(value) if value.eq_ignore_ascii_case("notoc") => {
+                    unnumbered.insert(headline.node);
+                    analysis.unlisted.insert(headline.node);
+                }
+                Some(value) if !value.eq_ignore_ascii_case("nil") => {
+                    unnumbered.insert(headline.node);
+                }
+                Some(_) => (),
+                None => {
+                    if inherited(&unnumbered) {
+                        unnumbered.insert(headline.node);
+                    }
+                }
+            }
+            if inherited(&analysis.unlisted) {
+                analysis.unlisted.insert(headline.node);
+            }
+
+            let numbered = !unnumbered.contains(&headline.node)
+                && (num_level
+                    .filter(|&level| headline.level() > level)
+                    .is_none()
+                    || property("NUMBERED") == Some("t"));
+            let id = match title.property("CUSTOM_ID") {
+                Some(id) => id.into(),
+                None if options.slug_ids => {
+                    let slug = slugify(&plain_text(org, headline.title_node));
+                    let id = std::iter::once(slug.clone())
+                        .chain((1..).map(|n| format!("{}-{}", slug, n)))
+                        .find(|id| !used_ids.contains(id))
+                        .unwrap();
+                    used_ids.insert(id.clone());
+                    id
+                }
+                None => format!("headline-{}", i + 1),
+            };
+
+            // unnumbered links show the title instead of a number
+            let label = if numbered {
+                let position = headline
+                    .node
+                    .preceding_siblings(&org.arena)
+                    .filter(|node| analysis.section_numbers.contains_key(node))
+                    .count()
+                    + 1;
+                let number =
+                    match parent.and_then(|parent| analysis.section_numbers.get(&parent.node)) {
+                        Some(parent) => format!("{}.{}", parent, position),
+                        None => position.to_string(),
+                    };
+                let label = strings.section.replace("%d", &number);
+                analysis.section_numbers.insert(headline.node, number);
+                label
+            } else {
+                plain_text(org, headline.title_node)
+            };
+
+            if export_settings
+                .headline_levels
+                .filter(|&max| headline.level() > max)
+                .is_some()
+            {
+                analysis.deep_titles.insert(headline.title_node);
+            } else if options.slug_ids {
+                analysis.id_headings.insert(headline.node);
+            }
+
+            analysis.ids.insert(headline.node, id.clone());
+            if headlines {
+                analysis.anchors.insert(headline.node, id.clone());
+            }
+
+            for key in title
+                .property("CUSTOM_ID")
+                .map(|value| format!("#{}", value))
+                .into_iter()
+                .chain(Some(format!("*{}", title.raw.trim())))
+            {
+                targets.entry(key).or_insert_with(|| Numbered {
+                    anchor: headline.node,
+                    id: id.clone(),
+                    label: label.clone(),
+                });
+            }
+        }
+
+        // footnotes are numbered in order of first reference
+        let mut count = 0;
+        let mut labels: HashMap<&str, usize> = HashMap::new();
+        let mut definitions: HashMap<&str, NodeId> = HashMap::new();
+        let mut repeats: Vec<usize> = Vec::new();
+        let nodes: Vec<_> = analysis.exported(org, org.root).collect();
+        for node in nodes {
+            match org.arena[node].get() {
+                Element::Link(Link {
+                    path, desc: None, ..
+                }) => {
+                    if let Some(target) = targets.get(&**path) {
+                        analysis
+                            .anchors
+                            .entry(target.anchor)
+                            .or_insert_with(|| target.id.clone());
+                        analysis.references.insert(
+                            node,
+                            Element::Link(Link {
+                                path: format!("#{}", target.id).into(),
+                                desc: Some(target.label.clone().into()),
+                                format: LinkFormat::Bracket,
+                            }),
+                        );
+                    }
+                }
+                Element::FnRef(fn_ref) => {
+                    // anonymous footnotes can't be referenced again
+                    let number = match labels.get(&*fn_ref.label) {
+                        Some(number) if !fn_ref.label.is_empty() => *number,
+                        _ => {
+                            count += 1;
+                            labels.insert(&fn_ref.label, count);
+                            analysis.footnotes.push(None);
+                            repeats.push(0);
+                            count
+                        }
+                    };
+                    analysis.footnote_numbers.insert(node, number);
+
+                    repeats[number - 1] += 1;
+                    let id = match repeats[number - 1] {
+                        1 => format!("fnr.{}", number),
+                        repeat => format!("fnr.{}.{}", number, repeat),
+                    };
+                    analysis.footnote_ids.insert(node, id);
+
+                    if let Some(definition) = &fn_ref.definition {
+                        let footnote = &mut analysis.footnotes[number - 1];
+                        if footnote.is_none() {
+                            *footnote = Some(Footnote::Inline(definition.to_string()));
+                        }
+                    }
+                }
+                Element::FnDef(fn_def) => {
+                    definitions.entry(&fn_def.label).or_insert(node);
+                }
+                _ => (),
+            }
+        }
+        // definitions of footnotes which aren't referenced aren't written
+        for (label, number) in labels {
+            let footnote = &mut analysis.footnotes[number - 1];
+            if let (None, Some(&node)) = (&footnote, definitions.get(label)) {
+                *footnote = Some(Footnote::Node(node));
+            }
+        }
+
+        if let Some(resolver) = &options.image_resolver {
+            analysis.inline_images(org, &**resolver, options.max_inline_image_size);
+        }
+
+        let macros: Vec<_> = analysis
+            .exported(org, org.root)
+            .filter_map(|node| match org.arena[node].get() {
+                Element::Macros(macros) => {
+                    let date = date.map(AsRef::as_ref);
+                    expand_date_macro(macros, date, options.modification_time.as_ref())
+                        .map(|value| (node, value))
+                }
+                _ => None,
+            })
+            .collect();
+        analysis.macros.extend(macros);
+
+        if options.tight_lists {
+            let unwrapped: Vec<_> = analysis
+                .exported(org, org.root)
+                .filter(|&node| match org.arena[node].get() {
+                    Element::List(list) => list.tight,
+                    _ => false,
+                })
+                .flat_map(|list| list.children(&org.arena))
+                .filter_map(|item| {
+                    let mut paragraph = org.arena[item].first_child()?;
+                    // the term of a description list item isn't a paragraph
+                    if let Element::ListItemTag = org.arena[paragraph].get() {
+                        paragraph = org.arena[paragraph].next_sibling()?;
+                    }
+                    match org.arena[paragraph].get() {
+                        Element::Paragraph if org.arena[paragraph].next_sibling().is_none() => {
+                            Some(paragraph)
+                        }
+                        _ => None,
+                    }
+                })
+                .collect();
+            analysis.unwrapped.extend(unwrapped);
+        }
+
+        analysis.title = title.map(|title| title.trim().to_string());
+        analysis.date = date.map(|date| format_date(date, options.date_format.as_deref()));
+        analysis.settings = export_settings;
+        locate(&mut analysis.diagnostics, org.text);
+
+        analysis
+    }
+
+    /// Resolves the local images linked without description, keeping the
+    /// `data:` URIs of the ones to inline
+    fn inline_images(
+        &mut self,
+        org: &Org<'_>,
+        resolver: &dyn ImageResolver,
+        max_size: Option<usize>,
+    ) {
+        let nodes: Vec<_> = self.exported(org, org.root).collect();
+        for node in nodes {
+            let path = match org.arena[node].get() {
+                Element::Link(link) if link.desc.is_none() && link.is_image() => {
+                    match local_path(&link.path) {
+                        Some(path) => path,
+                        None => continue,
+                    }
+                }
+                _ => continue,
+            };
+
+            let reason = match (resolver.resolve(Path::new(path)), max_size) {
+                (Some((_, data)), Some(max)) if data.len() > max => {
+                    format!("it's larger than {} bytes", max)
+                }
+                (Some((mime, data)), _) => {
+                    let uri = format!("data:{};base64,{}", mime, base64(&data));
+                    self.images.insert(node, uri);
+                    continue;
+                }
+                (None, _) => "it can't be resolved".to_string(),
+            };
+            self.diagnostics.push(Diagnostic::new(
+                Severity::Warning,
+                org.spans.get(&node).cloned(),
+                format!("image {} isn't inlined: {}", path, reason),
+            ));
+        }
+    }
+
+    /// Returns the id of a headline, or of a numbered table, figure or listing
+    ///
+    /// It's the `CUSTOM_ID` property of headlines or the `#+NAME:` of
+    /// captioned elements if any, otherwise an id like `headline-2` or
+    /// `table-1`.
+    pub fn id(&self, node: NodeId) -> Option<&str> {
+        self.ids.get(&node).map(AsRef::as_ref)
+    }
+
+    /// Returns the number of an exported headline, like `2.1`
+    ///
+    /// Headlines with the `UNNUMBERED` property, and their subtree, aren't
+    /// numbered and don't take a number from their siblings. With the
+    /// `#+OPTIONS:` `num:nil`, only the headlines with the `NUMBERED`
+    /// property `t` are numbered, and with `num:N`, the headlines up to
+    /// level N.
+    pub fn section_number(&self, node: NodeId) -> Option<&str> {
+        self.section_numbers.get(&node).map(AsRef::as_ref)
+    }
+
+    /// Returns the options of the `#+OPTIONS:` keywords of the exported part
+    /// of the document
+    ///
+    /// The render honors `num:`, `toc:`, `H:`, `-:` and `\n:`. Headings are
+    /// only numbered with `num:t` or `num:N`, and special strings and line
+    /// breaks only converted with `-:t` and `\n:t`.
+    pub fn export_settings(&self) -> &ExportSettings {
+        &self.settings
+    }
+
+    /// Returns the numbers of exported headlines of each todo keyword, e.g.
+    /// `[("TODO", 2), ("DONE", 0)]`
+    ///
+    /// Keywords are in the order of `#+TODO:`, or of the `parse_config` of
+    /// [`HtmlExportOptions`], followed by other keywords of the document.
+    ///
+    /// [`HtmlExportOptions`]: struct.HtmlExportOptions.html
+    pub fn todo_counts(&self) -> Vec<(&str, usize)> {
+        self.todo_keywords
+            .iter()
+            .map(|(name, _, count)| (name.as_str(), *count))
+            .collect()
+    }
+
+    /// Returns `true` if `keyword` is a done keyword, like `DONE`
+    pub fn is_done_keyword(&self, keyword: &str) -> bool {
+        self.todo_keywords
+            .iter()
+            .any(|(name, done, _)| *done && name == keyword)
+    }
+
+    /// Returns `true` if the exported headline is listed by tables of
+    /// contents and the outline, unlike the ones with `:UNNUMBERED: notoc`
+    /// and their subtree
+    pub fn in_toc(&self, org: &Org<'_>, node: NodeId) -> bool {
+        is_headline(org, node) && !self.is_excluded(org, node) && !self.unlisted.contains(&node)
+    }
+
+    /// Returns the problems found by the analysis, like `#+PRINT_BIBLIOGRAPHY:`
+    /// keywords without a [`BibliographyRenderer`]
+    ///
+    /// [`BibliographyRenderer`]: trait.BibliographyRenderer.html
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    /// Returns the number of a captioned table, figure or listing
+    pub fn number(&self, node: NodeId) -> Option<usize> {
+        self.numbers.get(&node).copied()
+    }
+
+    /// Returns the strings written in the output, from
+    /// [`HtmlExportOptions::strings`] or the `#+LANGUAGE:` of the document
+    ///
+    /// [`HtmlExportOptions::strings`]: struct.HtmlExportOptions.html#structfield.strings
+    pub fn strings(&self) -> &ExportStrings {
+        &self.strings
+    }
+
+    /// Returns the number of a footnote reference
+    ///
+    /// References to the same label share the same number.
+    pub fn footnote_number(&self, node: NodeId) -> Option<usize> {
+        self.footnote_numbers.get(&node).copied()
+    }
+
+    /// Returns the link rendered instead of the link `node`, if it has no
+    /// description and points to a numbered element
+    pub fn resolved_link(&self, node: NodeId) -> Option<&Link<'static>> {
+        match self.references.get(&node) {
+            Some(Element::Link(link)) => Some(link),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if `node` or one of its ancestors isn't exported
+    pub fn is_excluded(&self, org: &Org<'_>, node: NodeId) -> bool {
+        node.ancestors(&org.arena)
+            .any(|node| self.excluded.contains(&node))
+    }
+
+    /// Returns an iterator of the exported descendants of `node`
+    fn exported<'b>(&'b self, org: &'b Org<'_>, node: NodeId) -> impl Iterator<Item = NodeId> + 'b {
+        exported_edges(org, node, &self.excluded).filter_map(|edge| match edge {
+            NodeEdge::Start(node) => Some(node),
+            NodeEdge::End(_) => None,
+        })
+    }
+
+    /// Renders `node` and its descendants
+    pub(crate) fn render<W, H, E>(
+        &self,
+        org: &Org<'_>,
+        node: NodeId,
+        mut w: W,
+        handler: &mut H,
+    ) -> Result<(), E>
+    where
+        W: Write,
+        E: From<Error>,
+        H: HtmlHandler<E>,
+    {
+        if self.is_excluded(org, node) {
+            return Ok(());
+        }
+
+        // brackets are escaped in link descriptions
+        let mut links = 0;
+        // special blocks dropped or passed as is, whose contents are skipped
+        let mut skipping = None;
+        let root = node;
+
+        let edges = exported_edges(org, node, &self.excluded).filter(|edge| match edge {
+            NodeEdge::Start(node) | NodeEdge::End(node) => {
+                !is_unknown_keyword(org.arena[*node].get(), "html")
+            }
+        });
+
+        for edge in edges {
+            if let Some(skipped) = skipping {
+                if edge == NodeEdge::End(skipped) {
+                    skipping = None;
+                }
+                continue;
+            }
+
+            match edge {
+                NodeEdge::Start(node) => {
+                    let element = org.arena[node].get();
+
+                    handler.set_context(node, self);
+
+                    if self.unwrapped.contains(&node) {
+                        continue;
+                    }
+
+                    // definitions are written with the footnotes, and captions
+                    // with the element they caption
+                    if let Element::FnDef(_) = element {
+                        skipping = Some(node);
+                        continue;
+                    }
+                    if self.caption_labels.contains_key(&node) && node != root {
+                        skipping = Some(node);
+                        continue;
+                    }
+
+                    if let Some(&caption) = self.captions.get(&node) {
+                        if let Element::Table(Table::Org { has_header, .. }) = element {
+                            if let Some(id) = self.anchors.get(&caption) {
+                                let target = Element::Target(Target { target: id.into() });
+                                handler.start(&mut w, &target)?;
+                                handler.end(&mut w, &target)?;
+                            }
+                            let caption = self.caption_html(org, caption, handler)?;
+                            handler.set_context(node, self);
+                            handler.captioned_table_start(&mut w, *has_header, &caption)?;
+                            continue;
+                        }
+                        handler.figure_start(&mut w)?;
+                        self.render(org, caption, &mut w as &mut dyn Write, handler)?;
+                        handler.set_context(node, self);
+                    }
+
+                    if let Element::SpecialBlock(block) = element {
+                        match handler.special_block(block) {
+                            SpecialBlockPolicy::Render => (),
+                            SpecialBlockPolicy::Drop => {
+                                skipping = Some(node);
+                                continue;
+                            }
+                            SpecialBlockPolicy::Raw => {
+                                skipping = Some(node);
+                                let block = Element::ExportBlock(ExportBlock {
+                                    data: "html".into(),
+                                    contents: block_contents(org, node).into(),
+                                });
+                                handler.start(&mut w, &block)?;
+                                handler.end(&mut w, &block)?;
+                                continue;
+                            }
+                        }
+                    }
+
+                    if let Some(value) = self.macros.get(&node) {
+                        let text = Element::Text {
+                            value: value.into(),
+                        };
+                        handler.start(&mut w, &text)?;
+                        handler.end(&mut w, &text)?;
+                        continue;
+                    }
+
+                    if let Some(id) = self
+                        .anchors
+                        .get(&node)
+                        .filter(|_| !self.id_headings.contains(&node))
+                    {
+                        let target = Element::Target(Target { target: id.into() });
+                        handler.start(&mut w, &target)?;
+                        handler.end(&mut w, &target)?;
+                    }
+
+                    if let Element::Keyword(keyword) = element {
+                        if keyword.key.eq_ignore_ascii_case("TOC") {
+                            self.write_toc(org, node, &keyword.value, &mut w, handler)?;
+                        }
+                    }
+
+                    if let Some(html) = self.bibliographies.get(&node) {
+                        let block = Element::ExportBlock(ExportBlock {
+                            data: "html".into(),
+                            contents: html.into(),
+                        });
+                        handler.start(&mut w, &block)?;
+                        handler.end(&mut w, &block)?;
+                    }
+
+                    if let Element::Link(link) = element {
+                        let alt = image_src(&link.path);
+                        let src = match self.images.get(&node) {
+                            Some(uri) => Some(uri.clone()),
+                            None if link.desc.is_none() && handler.is_image(link) => handler
+                                .resolve_link(link)
+                                .map(|href| EncodeHref(image_src(&href)).to_string()),
+                            None => None,
+                        };
+                        if let Some(src) = src {
+                            let attributes = self
+                                .image_attributes
+                                .get(&node)
+                                .map_or(&[][..], |attributes| &attributes[..]);
+                            handler.image_with_attributes(&mut w, &src, alt, attributes)?;
+                            skipping = Some(node);
+                            continue;
+                        }
+                    }
+
+                    match (self.references.get(&node), element) {
+                        (Some(Element::Link(link)), _) => {
+                            write_link(&mut w, handler, &link.path, link.desc.as_ref().unwrap())?;
+                        }
+                        (_, Element::Text { value }) if self.rewrites_text() => {
+                            let value = if links > 0 {
+                                Link::unescape_desc(value)
+                            } else {
+                                Cow::Borrowed(&**value)
+                            };
+                            self.write_text(org, node, &value, &mut w, handler)?;
+                        }
+                        (_, Element::Text { value }) if links > 0 => {
+                            let text = Element::Text {
+                                value: Link::unescape_desc(value),
+                            };
+                            handler.start(&mut w, &text)?;
+                        }
+                        (_, Element::Title(_)) if self.deep_titles.contains(&node) => {
+                            handler.start(&mut w, &Element::Paragraph)?;
+                            handler.start(&mut w, &Element::Bold)?;
+                        }
+                        (_, Element::FnRef(_)) if self.footnote_ids.contains_key(&node) => {
+                            handler.footnote_reference(
+                                &mut w,
+                                self.footnote_numbers[&node],
+                                &self.footnote_ids[&node],
+                            )?;
+                        }
+                        (_, Element::Title(title)) if self.heading_id(org, node).is_some() => {
+                            handler.heading_start(
+                                &mut w,
+                                title,
+                                self.heading_id(org, node).unwrap(),
+                            )?;
+                        }
+                        _ => {
+                            if let Element::Link(_) = element {
+                                links += 1;
+                            }
+                            handler.start(&mut w, element)?;
+                        }
+                    }
+
+                    if let Element::Title(title) = element {
+                        if let Some(number) = self.heading_number(org, node) {
+                            handler.section_number(&mut w, number)?;
+                        }
+                        if let Some(keyword) = &title.keyword {
+                            handler.todo_keyword(&mut w, keyword, self.is_done_keyword(keyword))?;
+                        }
+                    }
+
+                    if let Element::Document = element {
+                        self.write_document_toc(org, &mut w, handler)?;
+                    }
+
+                    if let Some(label) = self.caption_labels.get(&node) {
+                        let text = Element::Text {
+                            value: label.into(),
+                        };
+                        handler.start(&mut w, &text)?;
+                        handler.end(&mut w, &text)?;
+                    }
+                }
+                NodeEdge::End(node) => {
+                    if self.macros.contains_key(&node) || self.unwrapped.contains(&node) {
+                        continue;
+                    }
+
+                    handler.set_context(node, self);
+
+                    let element = org.arena[node].get();
+                    match (self.references.get(&node), element) {
+                        (Some(_), _) => (),
+                        (_, Element::Text { .. }) if self.rewrites_text() => (),
+                        (_, Element::Title(_)) if self.deep_titles.contains(&node) => {
+                            handler.end(&mut w, &Element::Bold)?;
+                            handler.end(&mut w, &Element::Paragraph)?;
+                        }
+                        (_, Element::Text { value }) if links > 0 => {
+                            let text = Element::Text {
+                                value: Link::unescape_desc(value),
+                            };
+                            handler.end(&mut w, &text)?;
+                        }
+                        (_, Element::FnRef(_)) if self.footnote_ids.contains_key(&node) => (),
+                        (_, Element::Document) => {
+                            self.write_footnotes(org, &mut w, handler)?;
+                            handler.end(&mut w, element)?;
+                        }
+                        _ => {
+                            if let Element::Link(_) = element {
+                                links -= 1;
+                            }
+                            handler.end(&mut w, element)?;
+                        }
+                    }
+
+                    match element {
+                        Element::Table(Table::Org { .. }) => (),
+                        _ if self.captions.contains_key(&node) => handler.figure_end(&mut w)?,
+                        _ => (),
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Writes the table of contents of `toc:` at the start of the document
+    pub(crate) fn write_document_toc<W, H, E>(
+        &self,
+        org: &Org<'_>,
+        w: W,
+        handler: &mut H,
+    ) -> Result<(), E>
+    where
+        W: Write,
+        E: From<Error>,
+        H: HtmlHandler<E>,
+    {
+        match self.toc {
+            None | Some(Levels::None) => Ok(()),
+            Some(Levels::All) => self.write_headline_toc(org, None, w, handler),
+            Some(Levels::UpTo(depth)) => self.write_headline_toc(org, Some(depth), w, handler),
+        }
+    }
+
+    /// Returns the html of `caption` with its label, which is written in the
+    /// `<caption>` of its table
+    fn caption_html<H, E>(
+        &self,
+        org: &Org<'_>,
+        caption: NodeId,
+        handler: &mut H,
+    ) -> Result<String, E>
+    where
+        E: From<Error>,
+        H: HtmlHandler<E>,
+    {
+        let mut html = Vec::new();
+
+        handler.set_context(caption, self);
+        let label = Element::Text {
+            value: self.caption_labels[&caption].as_str().into(),
+        };
+        handler.start(&mut html, &label)?;
+        handler.end(&mut html, &label)?;
+        for child in caption.children(&org.arena) {
+            self.render(org, child, &mut html, handler)?;
+        }
+
+        Ok(String::from_utf8_lossy(&html).into_owned())
+    }
+
+    /// Writes the definitions of the referenced footnotes, in order of number
+    ///
+    /// The writer is a trait object, since the definitions are rendered by
+    /// `render`, which calls this function.
+    pub(crate) fn write_footnotes<H, E>(
+        &self,
+        org: &Org<'_>,
+        w: &mut dyn Write,
+        handler: &mut H,
+    ) -> Result<(), E>
+    where
+        E: From<Error>,
+        H: HtmlHandler<E>,
+    {
+        if self.footnotes.iter().all(Option::is_none) {
+            return Ok(());
+        }
+
+        handler.footnotes_start(&mut *w, &self.strings.footnotes)?;
+        for (i, footnote) in self.footnotes.iter().enumerate() {
+            let number = i + 1;
+            match footnote {
+                Some(Footnote::Node(node)) => {
+                    handler.footnote_start(&mut *w, number)?;
+                    for child in node.children(&org.arena) {
+                        self.render(org, child, &mut *w, handler)?;
+                    }
+                    handler.footnote_end(&mut *w, number)?;
+                }
+                Some(Footnote::Inline(definition)) => {
+                    handler.footnote_start(&mut *w, number)?;
+                    let text = Element::Text {
+                        value: definition.into(),
+                    };
+                    for element in &[&Element::Paragraph, &text] {
+                        handler.start(&mut *w, element)?;
+                    }
+                    for element in &[&text, &Element::Paragraph] {
+                        handler.end(&mut *w, element)?;
+                    }
+                    handler.footnote_end(&mut *w, number)?;
+                }
+                None => (),
+            }
+        }
+        handler.footnotes_end(&mut *w)
+    }
+
+    /// Returns the section number written in the heading of the title `node`,
+    /// with `num:t` or `num:N`
+    fn heading_number(&self, org: &Org<'_>, node: NodeId) -> Option<&str> {
+        if self.settings.num.is_none() || self.deep_titles.contains(&node) {
+            return None;
+        }
+        self.section_number(org.arena[node].parent()?)
+    }
+
+    /// Returns the id written on the heading of the title `node`, with
+    /// `slug_ids`
+    fn heading_id(&self, org: &Org<'_>, node: NodeId) -> Option<&str> {
+        org.arena[node]
+            .parent()
+            .filter(|headline| self.id_headings.contains(headline))
+            .and_then(|headline| self.id(headline))
+    }
+
+    /// Returns `true` if text is rewritten by `-:t` or `\n:t`
+    fn rewrites_text(&self) -> bool {
+        self.settings.special_strings == Some(true) || self.settings.preserve_breaks == Some(true)
+    }
+
+    /// Writes `value`, the text of `node`, with its special strings converted
+    /// and the line breaks of paragraphs preserved, as asked by `-:t` and
+    /// `\n:t`
+    fn write_text<W, H, E>(
+        &self,
+        org: &Org<'_>,
+        node: NodeId,
+        value: &str,
+        mut w: W,
+        handler: &mut H,
+    ) -> Result<(), E>
+    where
+        W: Write,
+        E: From<Error>,
+        H: HtmlHandler<E>,
+    {
+        let value = if self.settings.special_strings == Some(true) {
+            convert_special_strings(value)
+        } else {
+            Cow::Borrowed(value)
+        };
+
+        let in_paragraph = || {
+            node.ancestors(&org.arena)
+                .any(|ancestor| matches!(org.arena[ancestor].get(), Element::Paragraph))
+        };
+        if self.settings.preserve_breaks != Some(true) || !in_paragraph() {
+            return write_text(&mut w, handler, value);
+        }
+
+        // the newline following a line break object already has one
+        let after_break = node
+            .preceding_siblings(&org.arena)
+            .nth(1)
+            .map(|sibling| matches!(org.arena[sibling].get(), Element::LineBreak))
+            .unwrap_or(false);
+
+        for (i, line) in value.split('\n').enumerate() {
+            if i == 0 {
+                if !line.is_empty() {
+                    write_text(&mut w, handler, line.into())?;
+                }
+                continue;
+            }
+            if !(i == 1 && after_break && value.starts_with('\n')) {
+                handler.start(&mut w, &Element::LineBreak)?;
+                handler.end(&mut w, &Element::LineBreak)?;
+            }
+            write_text(&mut w, handler, format!("\n{}", line).into())?;
+        }
+        Ok(())
+    }
+
+    /// Renders the document in a full html page, with its title and date
+    pub(crate) fn render_page<W, H, E>(
+        &self,
+        org: &Org<'_>,
+        mut w: W,
+        handler: &mut H,
+    ) -> Result<(), E>
+    where
+        W: Write,
+        E: From<Error>,
+        H: HtmlHandler<E>,
+    {
+        writeln!(
+            w,
+            "<!DOCTYPE html>\n<html{}>\n<head>\n<meta charset=\"utf-8\">",
+            match &self.language {
+                Some(language) => format!(" lang=\"{}\"", Escape(language)),
+                None => String::new(),
+            }
+        )?;
+        if let Some(title) = &self.title {
+            writeln!(w, "<title>{}</title>", Escape(title))?;
+        }
+        if let (Some(date), true) = (&self.date, self.date_meta) {
+            writeln!(w, "<meta name=\"date\" content=\"{}\">", Escape(date))?;
+        }
+        #[cfg(feature = "css")]
+        {
+            if self.embed_css {
+                crate::export::write_default_css(&mut w)?;
+            }
+        }
+        writeln!(w, "</head>\n<body>")?;
+        if let Some(title) = &self.title {
+            writeln!(w, "<h1 class=\"title\">{}</h1>", Escape(title))?;
+        }
+        if let (Some(date), true) = (&self.date, self.date_subtitle) {
+            writeln!(w, "<p class=\"subtitle\">{}</p>", Escape(date))?;
+        }
+        self.render(org, org.root, &mut w, handler)?;
+        writeln!(w, "\n</body>\n</html>")?;
+
+        Ok(())
+    }
+
+    /// Writes the list generated by the `#+TOC:` keyword `node`
+    fn write_toc<W, H, E>(
+        &self,
+        org: &Org<'_>,
+        node: NodeId,
+        value: &str,
+        mut w: W,
+        handler: &mut H,
+    ) -> Result<(), E>
+    where
+        W: Write,
+        E: From<Error>,
+        H: HtmlHandler<E>,
+    {
+        let entries = match Toc::parse(value) {
+            Some(Toc::Headlines { depth, local }) => {
+                let parent = if local {
+                    node.ancestors(&org.arena).find_map(|ancestor| {
+                        match org.arena[ancestor].get() {
+                            Element::Headline { level } => {
+                                Some(HeadlineNode::new(ancestor, *level, org))
+                            }
+                            _ => None,
+                        }
+                    })
+                } else {
+                    None
+                };
+
+                return match parent {
+                    Some(parent) => self.write_headlines(
+                        org,
+                        parent.children(org).collect(),
+                        depth.map_or(usize::max_value(), |depth| parent.level() + depth),
+                        &mut w,
+                        handler,
+                    ),
+                    None if local => Ok(()),
+                    None => self.write_headline_toc(org, depth, w, handler),
+                };
+            }
+            Some(Toc::Tables) => &self.tables,
+            Some(Toc::Figures) => &self.figures,
+            Some(Toc::Listings) => &self.listings,
+            None => return Ok(()),
+        };
+
+        if !entries.is_empty() {
+            write_list_start(&mut w, handler)?;
+            for entry in entries {
+                write_item_start(&mut w, handler, &entry.id, &entry.text)?;
+                write_item_end(&mut w, handler)?;
+            }
+            write_list_end(&mut w, handler)?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the exported headlines of the document, up to level `max_level`
+    pub(crate) fn outline(&self, org: &Org<'_>, max_level: usize) -> Vec<OutlineEntry> {
+        self.outline_entries(org, org.document().children(org), max_level)
+    }
+
+    fn outline_entries(
+        &self,
+        org: &Org<'_>,
+        headlines: impl Iterator<Item = HeadlineNode>,
+        max_level: usize,
+    ) -> Vec<OutlineEntry> {
+        headlines
+            .filter(|headline| {
+                headline.level() <= max_level
+                    && !self.excluded.contains(&headline.node)
+                    && !self.unlisted.contains(&headline.node)
+            })
+            .map(|headline| {
+                let title = headline.title(org);
+                OutlineEntry {
+                    title: plain_text(org, headline.title_node),
+                    level: headline.level(),
+                    id: self.ids[&headline.node].clone(),
+                    number: self.section_numbers.get(&headline.node).cloned(),
+                    todo: title.keyword.as_ref().map(|keyword| keyword.to_string()),
+                    children: self.outline_entries(org, headline.children(org), max_level),
+                }
+            })
+            .collect()
+    }
+
+    /// Writes the nested list of the headlines of the document, up to level
+    /// `depth` if any
+    pub(crate) fn write_headline_toc<W, H, E>(
+        &self,
+        org: &Org<'_>,
+        depth: Option<usize>,
+        mut w: W,
+        handler: &mut H,
+    ) -> Result<(), E>
+    where
+        W: Write,
+        E: From<Error>,
+        H: HtmlHandler<E>,
+    {
+        self.write_headlines(
+            org,
+            org.document().children(org).collect(),
+            depth.unwrap_or_else(usize::max_value),
+            &mut w,
+            handler,
+        )
+    }
+
+    fn write_headlines<W, H, E>(
+        &self,
+        org: &Org<'_>,
+        headlines: Vec<HeadlineNode>,
+        max_level: usize,
+        w: &mut W,
+        handler: &mut H,
+    ) -> Result<(), E>
+    where
+        W: Write,
+        E: From<Error>,
+        H: HtmlHandler<E>,
+    {
+        let headlines: Vec<_> = headlines
+            .into_iter()
+            .filter(|headline| {
+                headline.level() <= max_level
+                    && !self.excluded.contains(&headline.node)
+                    && !self.unlisted.contains(&headline.node)
+            })
+            .collect();
+
+        if headlines.is_empty() {
+            return Ok(());
+        }
+
+        write_list_start(&mut *w, handler)?;
+        for headline in headlines {
+            let id = &self.ids[&headline.node];
+            let text = plain_text(org, headline.title_node);
+            write_item_start(&mut *w, handler, id, &text)?;
+            self.write_headlines(org, headline.children(org).collect(), max_level, w, handler)?;
+            write_item_end(&mut *w, handler)?;
+        }
+        write_list_end(w, handler)
+    }
+}
+
+/// Returns the text of the descendants of `node`, without markup
+pub(crate) fn plain_text(org: &Org<'_>, node: NodeId) -> String {
+    let mut text = String::new();
+
+    for descendant in node.descendants(&org.arena) {
+        match org.arena[descendant].get() {
+            Element::Text { value } if in_link(org, descendant) => {
+                text += &Link::unescape_desc(value)
+            }
+            Element::Text { value } | Element::Verbatim { value } | Element::Code { value } => {
+                text += value
+            }
+            Element::Link(Link {
+                path, desc: None, ..
+            }) => text += path,
+            Element::RadioTarget(radio_target) => text += &radio_target.target,
+            Element::Entity(entity) => text += entity.utf8,
+            _ => (),
+        }
+    }
+
+    text
+}
+
+/// Returns the id derived from the text of a title, made of its lowercase
+/// letters and digits, with dashes in place of the other characters
+///
+/// It's used for the ids of headlines with `HtmlExportOptions::slug_ids`.
+///
+/// ```rust
+/// use orgize::export::slugify;
+///
+/// assert_eq!(slugify("Getting Started!"), "getting-started");
+/// assert_eq!(slugify("  C++ & Rust: 2.0 "), "c-rust-2-0");
+/// assert_eq!(slugify("Über uns"), "über-uns");
+/// assert_eq!(slugify("???"), "headline");
+/// ```
+pub fn slugify(text: &str) -> String {
+    let mut slug = String::with_capacity(text.len());
+    for c in text.chars() {
+        if c.is_alphanumeric() {
+            slug.extend(c.to_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    if slug.ends_with('-') {
+        slug.pop();
+    }
+    if slug.is_empty() {
+        slug.push_str("headline");
+    }
+    slug
+}
+
+/// Converts the special strings of `text`, see `ExportSettings::special_strings`
+fn convert_special_strings(text: &str) -> Cow<'_, str> {
+    if !text.contains("\\-") && !text.contains("--") && !text.contains("...") {
+        return Cow::Borrowed(text);
+    }
+    Cow::Owned(
+        text.replace("\\-", "\u{ad}")
+            .replace("---", "\u{2014}")
+            .replace("--", "\u{2013}")
+            .replace("...", "\u{2026}"),
+    )
+}
+
+/// Returns the attributes of the `#+ATTR_HTML:` keywords of figures, by
+/// image link, without the `:` of their keys
+///
+/// Keys that aren't made of alphanumerics, `-` and `_` are ignored, and a
+/// key repeated in the keywords keeps its last value.
+fn image_attributes(org: &Org<'_>) -> HashMap<NodeId, Vec<(String, String)>> {
+    let mut attributes = HashMap::new();
+    for node in org.root.descendants(&org.arena) {
+        if !is_figure(org, node) {
+            continue;
+        }
+
+        let mut keywords = Vec::new();
+        for sibling in node.preceding_siblings(&org.arena).skip(1) {
+            match org.arena[sibling].get() {
+                Element::Keyword(keyword) if keyword.key.eq_ignore_ascii_case("ATTR_HTML") => {
+                    keywords.push(&keyword.value)
+                }
+                // like the other affiliated keywords of `captioned`
+                Element::Keyword(keyword)
+                    if keyword.key.eq_ignore_ascii_case("NAME") || is_affiliated(&keyword.key) => {}
+                Element::Caption(_) | Element::Comment { .. } => (),
+                _ => break,
+            }
+        }
+
+        let mut figure: Vec<(String, String)> = Vec::new();
+        // keywords are collected from the closest to the element
+        for value in keywords.into_iter().rev() {
+            for (key, value) in parse_header_args(value) {
+                let key = &key[1..];
+                if key.is_empty()
+                    || !key
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+                {
+                    continue;
+                }
+                match figure.iter_mut().find(|(k, _)| k == key) {
+                    Some((_, v)) => *v = value.into(),
+                    None => figure.push((key.into(), value.into())),
+                }
+            }
+        }
+
+        let link = node
+            .children(&org.arena)
+            .find(|&child| matches!(org.arena[child].get(), Element::Link(_)));
+        if let (Some(link), false) = (link, figure.is_empty()) {
+            attributes.insert(link, figure);
+        }
+    }
+    attributes
+}
+
+/// Returns the path of a link to a local file, without its `file:` prefix
+fn local_path(path: &str) -> Option<&str> {
+    if let Some(path) = path.strip_prefix("file:") {
+        return Some(path);
+    }
+    // other link types, like `https:` or `id:`, start with a scheme
+    match path.find(':') {
+        Some(i) if i > 1 && path[..i].chars().all(|c| c.is_ascii_alphanumeric()) => None,
+        _ => Some(path),
+    }
+}
+
+/// Encodes `data` in base64, with padding
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut encoded = String::with_capacity(data.len() / 3 * 4 + 4);
+    for chunk in data.chunks(3) {
+        let bytes = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = (u32::from(bytes[0]) << 16) | (u32::from(bytes[1]) << 8) | u32::from(bytes[2]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[((n >> (18 - 6 * i)) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+fn in_link(org: &Org<'_>, node: NodeId) -> bool {
+    node.ancestors(&org.arena).any(|ancestor| {
+        if let Element::Link(_) = org.arena[ancestor].get() {
+            true
+        } else {
+            false
+        }
+    })
+}
+
+fn write_text<W: Write, H: HtmlHandler<E>, E: From<Error>>(
+    mut w: W,
+    handler: &mut H,
+    value: Cow<'_, str>,
+) -> Result<(), E> {
+    let text = Element::Text { value };
+    handler.start(&mut w, &text)?;
+    handler.end(&mut w, &text)
+}
+
+/// Writes a link whose description is `text`
+fn write_link<W: Write, H: HtmlHandler<E>, E: From<Error>>(
+    mut w: W,
+    handler: &mut H,
+    path: &str,
+    text: &str,
+) -> Result<(), E> {
+    let link = Element::Link(Link {
+        path: path.into(),
+        desc: Some(text.into()),
+        format: LinkFormat::Bracket,
+    });
+    let text = Element::Text { value: text.into() };
+    handler.start(&mut w, &link)?;
+    handler.start(&mut w, &text)?;
+    handler.end(&mut w, &text)?;
+    handler.end(&mut w, &link)
+}
+
+fn write_list_start<W: Write, H: HtmlHandler<E>, E: From<Error>>(
+    w: W,
+    handler: &mut H,
+) -> Result<(), E> {
+    handler.start(
+        w,
+        &Element::List(List {
+            indent: 0,
+            ordered: false,
+            bullet: ListBullet::Hyphen,
+            tight: true,
+            description: false,
+            start: None,
+        }),
+    )
+}
+
+fn write_list_end<W: Write, H: HtmlHandler<E>, E: From<Error>>(
+    w: W,
+    handler: &mut H,
+) -> Result<(), E> {
+    handler.end(
+        w,
+        &Element::List(List {
+            indent: 0,
+            ordered: false,
+            bullet: ListBullet::Hyphen,
+            tight: true,
+            description: false,
+            start: None,
+        }),
+    )
+}
+
+fn write_item_start<W: Write, H: HtmlHandler<E>, E: From<Error>>(
+    mut w: W,
+    handler: &mut H,
+    id: &str,
+    text: &str,
+) -> Result<(), E> {
+    handler.start(&mut w, &toc_item())?;
+    write_link(w, handler, &format!("#{}", id), text)
+}
+
+fn write_item_end<W: Write, H: HtmlHandler<E>, E: From<Error>>(
+    w: W,
+    handler: &mut H,
+) -> Result<(), E> {
+    handler.end(w, &toc_item())
+}
+
+fn toc_item() -> Element<'static> {
+    Element::ListItem(ListItem {
+        bullet: "- ".into(),
+        indent: 0,
+        checkbox: None,
+        counter: None,
+        tag: None,
+    })
+}
@@ -0,0 +1,135 @@
+use std::borrow::Cow;
+
+use crate::elements::Element;
+
+/// Returns the text exporters render for an example block, a source block or
+/// a fixed-width area, `None` for other elements
+///
+/// Every backend renders these elements from this text, so they agree on
+/// whitespace:
+///
+/// + line endings are `\n`, `\r\n` is converted
+/// + the common indentation of the lines is removed, unless the block has
+///   the `-i` switch, tabs and further indentation are kept
+/// + the `: ` of fixed-width lines is removed
+/// + trailing blank lines are removed and the text ends with exactly one
+///   newline, except that blank contents give an empty text
+///
+/// Commas escaping lines in blocks, e.g. `,* not a headline`, have already
+/// been removed by the parser.
+///
+/// ```rust
+/// use orgize::export::block_text;
+/// use orgize::Org;
+///
+/// let org = Org::parse("#+BEGIN_EXAMPLE\r\n    a\r\n      b\r\n\r\n#+END_EXAMPLE\r\n");
+/// let block = org.arena().iter().find_map(|node| block_text(node.get())).unwrap();
+///
+/// assert_eq!(block, "a\n  b\n");
+/// ```
+pub fn block_text<'a>(element: &'a Element<'_>) -> Option<Cow<'a, str>> {
+    let (contents, preserve_indentation) = match element {
+        Element::ExampleBlock(block) => (
+            Cow::Borrowed(&*block.contents),
+            block.data.iter().any(|data| has_preserve_switch(data)),
+        ),
+        Element::SourceBlock(block) => (
+            Cow::Borrowed(&*block.contents),
+            has_preserve_switch(&block.arguments),
+        ),
+        Element::FixedWidth { value } => (remove_colons(value), false),
+        _ => return None,
+    };
+
+    Some(normalize(contents, preserve_indentation))
+}
+
+fn has_preserve_switch(switches: &str) -> bool {
+    switches.split_whitespace().any(|switch| switch == "-i")
+}
+
+/// Removes the `: ` or `:` starting the lines of a fixed-width area
+fn remove_colons(value: &str) -> Cow<'_, str> {
+    let lines: Vec<_> = value
+        .split('\n')
+        .map(|line| {
+            let line = line.trim_start();
+            line.strip_prefix(": ")
+                .or_else(|| line.strip_prefix(':'))
+                .unwrap_or(line)
+        })
+        .collect();
+    Cow::Owned(lines.join("\n"))
+}
+
+pub(crate) fn normalize(contents: Cow<'_, str>, preserve_indentation: bool) -> Cow<'_, str> {
+    let contents = if contents.contains('\r') {
+        Cow::Owned(contents.replace("\r\n", "\n"))
+    } else {
+        contents
+    };
+
+    let contents = if preserve_indentation {
+        contents
+    } else {
+        match remove_indentation(&contents) {
+            Cow::Borrowed(_) => contents,
+            Cow::Owned(removed) => Cow::Owned(removed),
+        }
+    };
+
+    // the end of the last line which isn't blank
+    let end = contents.trim_end_matches(|c: char| c.is_whitespace()).len();
+    let end = contents[end..]
+        .find('\n')
+        .map(|i| end + i)
+        .unwrap_or_else(|| contents.len());
+
+    if contents[0..end].trim().is_empty() {
+        Cow::Borrowed("")
+    } else if end + 1 == contents.len() {
+        contents
+    } else {
+        let mut text = contents[0..end].to_string();
+        text.push('\n');
+        Cow::Owned(text)
+    }
+}
+
+/// Removes the common leading whitespace of the non-blank lines
+pub(crate) fn remove_indentation(contents: &str) -> Cow<'_, str> {
+    let indentation = |line: &str| line.len() - line.trim_start_matches(&[' ', '\t'][..]).len();
+
+    let common = contents
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(indentation)
+        .min()
+        .unwrap_or(0);
+
+    if common == 0 {
+        return Cow::Borrowed(contents);
+    }
+
+    let lines: Vec<_> = contents
+        .split('\n')
+        .map(|line| &line[indentation(line).min(common)..])
+        .collect();
+    Cow::Owned(lines.join("\n"))
+}
+
+#[test]
+fn normalize_() {
+    let text = |contents: &str, preserve| normalize(Cow::Borrowed(contents), preserve).into_owned();
+
+    assert_eq!(text("", false), "");
+    assert_eq!(text("\n  \n\t\n", false), "");
+    assert_eq!(text("a", false), "a\n");
+    assert_eq!(text("a\n", false), "a\n");
+    assert_eq!(text("a\n\n  \n", false), "a\n");
+    assert_eq!(text("a  \n", false), "a  \n");
+    assert_eq!(text("\n  a\r\n  \tb\r\n", false), "\na\n\tb\n");
+    assert_eq!(text("  a\n    b\n", true), "  a\n    b\n");
+    assert_eq!(remove_indentation("  a\n\n    b\n"), "a\n\n  b\n");
+    assert_eq!(remove_colons(": a\n:\n:   b"), "a\n\n  b");
+}
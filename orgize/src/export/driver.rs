@@ -0,0 +1,410 @@
+use std::collections::HashSet;
+use std::io::{Error, Write};
+
+use indextree::{NodeEdge, NodeId};
+
+use crate::config::DEFAULT_CONFIG;
+use crate::elements::{Element, ExportBlock, SpecialBlock};
+#[cfg(feature = "export-html")]
+use crate::export::HtmlHandler;
+use crate::export::{
+    block_contents, is_unknown_keyword, raw_export, OrgHandler, SpecialBlockPolicy,
+};
+use crate::node::HeadlineNode;
+use crate::org::{Event, Org};
+use crate::settings::SettingsResolver;
+
+/// Rendering of elements by an export backend
+///
+/// [`Org::export_with_handler`] drives it and takes care of what every
+/// backend shares: excluding tagged subtrees, exporting a single subtree,
+/// reporting progress and safe mode. A backend only has to render elements.
+///
+/// `HtmlHandler`s, `MarkdownHandler`s and `OrgHandler`s can be used through
+/// [`HtmlBackend`], [`MarkdownBackend`] and [`OrgBackend`], and closures
+/// receiving events through [`EventBackend`].
+///
+/// [`Org::export_with_handler`]: ../struct.Org.html#method.export_with_handler
+/// [`HtmlBackend`]: struct.HtmlBackend.html
+/// [`MarkdownBackend`]: struct.MarkdownBackend.html
+/// [`OrgBackend`]: struct.OrgBackend.html
+/// [`EventBackend`]: struct.EventBackend.html
+pub trait ExportHandler<E: From<Error>> {
+    /// Name of the backend, matched against export blocks, export snippets
+    /// and keywords like `#+HTML:`, see [`raw_export`]
+    ///
+    /// [`raw_export`]: fn.raw_export.html
+    fn backend(&self) -> &str;
+
+    fn start<W: Write>(&mut self, w: W, element: &Element) -> Result<(), E>;
+
+    fn end<W: Write>(&mut self, w: W, element: &Element) -> Result<(), E>;
+
+    /// Called before the start and the end of every exported node
+    ///
+    /// Backends which need the surroundings of the element being rendered,
+    /// like [`MarkdownBackend`], can keep the node, the default
+    /// implementation does nothing.
+    ///
+    /// [`MarkdownBackend`]: struct.MarkdownBackend.html
+    fn set_node(&mut self, _node: NodeId) {}
+
+    /// How the special block `block`, e.g. `#+BEGIN_warning`, is rendered,
+    /// defaults to `SpecialBlockPolicy::Render`
+    fn special_block(&self, _block: &SpecialBlock) -> SpecialBlockPolicy {
+        SpecialBlockPolicy::Render
+    }
+
+    /// Called after each exported headline, with the number of headlines
+    /// exported so far and the total number of headlines to export
+    fn progress(&mut self, _done: usize, _total: usize) {}
+
+    /// Returns `true` if keywords orgize doesn't know, like `#+BIND:`, are
+    /// passed to the backend, defaults to `false`
+    ///
+    /// See [`Org::unknown_keywords`].
+    ///
+    /// [`Org::unknown_keywords`]: ../struct.Org.html#method.unknown_keywords
+    fn keeps_unknown_keywords(&self) -> bool {
+        false
+    }
+}
+
+/// Options of [`Org::export_with_handler`]
+///
+/// [`Org::export_with_handler`]: ../struct.Org.html#method.export_with_handler
+#[derive(Clone, Debug)]
+pub struct ExportOptions {
+    /// Headlines with one of these tags aren't exported, nor their subtree,
+    /// defaults to `noexport`
+    ///
+    /// The tags of the `#+EXCLUDE_TAGS:` keywords of the document are
+    /// excluded too, and commented headlines are never exported.
+    pub exclude_tags: Vec<String>,
+    /// If any headline has one of these tags, only these headlines, their
+    /// subtree and their ancestors are exported, empty by default
+    ///
+    /// The tags of the `#+SELECT_TAGS:` keywords of the document are
+    /// selected too.
+    pub select_tags: Vec<String>,
+    /// Exports only the title of headlines tagged `ARCHIVE`, without their
+    /// section and child headlines, `false` by default
+    ///
+    /// Like `ParseConfig::skip_archived`, but for documents parsed in full.
+    pub skip_archived: bool,
+    /// Exports only this node and its descendants, e.g. the headline node of
+    /// [`HeadlineNode::headline_node`]
+    ///
+    /// [`HeadlineNode::headline_node`]: ../struct.HeadlineNode.html#method.headline_node
+    pub subtree: Option<NodeId>,
+    /// Passes the contents of export blocks, export snippets and keywords
+    /// targeting the backend as text, instead of as is
+    pub safe_mode: bool,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        ExportOptions {
+            exclude_tags: vec!["noexport".into()],
+            select_tags: Vec::new(),
+            skip_archived: false,
+            subtree: None,
+            safe_mode: false,
+        }
+    }
+}
+
+/// Exports with an `HtmlHandler`
+#[cfg(feature = "export-html")]
+pub struct HtmlBackend<H>(pub H);
+
+#[cfg(feature = "export-html")]
+impl<E: From<Error>, H: HtmlHandler<E>> ExportHandler<E> for HtmlBackend<H> {
+    fn backend(&self) -> &str {
+        "html"
+    }
+
+    fn special_block(&self, block: &SpecialBlock) -> SpecialBlockPolicy {
+        self.0.special_block(block)
+    }
+
+    fn start<W: Write>(&mut self, w: W, element: &Element) -> Result<(), E> {
+        self.0.start(w, element)
+    }
+
+    fn end<W: Write>(&mut self, w: W, element: &Element) -> Result<(), E> {
+        self.0.end(w, element)
+    }
+}
+
+/// Exports with an `OrgHandler`
+pub struct OrgBackend<H>(pub H);
+
+impl<E: From<Error>, H: OrgHandler<E>> ExportHandler<E> for OrgBackend<H> {
+    fn backend(&self) -> &str {
+        "org"
+    }
+
+    fn keeps_unknown_keywords(&self) -> bool {
+        true
+    }
+
+    fn start<W: Write>(&mut self, w: W, element: &Element) -> Result<(), E> {
+        self.0.start(w, element)
+    }
+
+    fn end<W: Write>(&mut self, w: W, element: &Element) -> Result<(), E> {
+        self.0.end(w, element)
+    }
+}
+
+/// Passes the exported elements to a closure as `Event`s, ignoring the writer
+///
+/// ```rust
+/// use orgize::export::{EventBackend, ExportOptions};
+/// use orgize::{Event, Org};
+///
+/// let org = Org::parse("* kept\n* dropped :noexport:\n");
+///
+/// let mut titles = Vec::new();
+/// let mut backend = EventBackend(|event: Event| {
+///     if let Event::Start(orgize::Element::Title(title)) = event {
+///         titles.push(title.raw.to_string());
+///     }
+///     Ok::<(), std::io::Error>(())
+/// });
+/// org.export_with_handler(std::io::sink(), &mut backend, &ExportOptions::default())
+///     .unwrap();
+///
+/// assert_eq!(titles, ["kept"]);
+/// ```
+pub struct EventBackend<F>(pub F);
+
+impl<E, F> ExportHandler<E> for EventBackend<F>
+where
+    E: From<Error>,
+    F: FnMut(Event<'_, '_>) -> Result<(), E>,
+{
+    fn backend(&self) -> &str {
+        ""
+    }
+
+    fn start<W: Write>(&mut self, _w: W, element: &Element) -> Result<(), E> {
+        (self.0)(Event::Start(element))
+    }
+
+    fn end<W: Write>(&mut self, _w: W, element: &Element) -> Result<(), E> {
+        (self.0)(Event::End(element))
+    }
+}
+
+pub(crate) fn export<W, H, E>(
+    org: &Org<'_>,
+    mut w: W,
+    handler: &mut H,
+    options: &ExportOptions,
+) -> Result<(), E>
+where
+    W: Write,
+    E: From<Error>,
+    H: ExportHandler<E>,
+{
+    let excluded = exclude(
+        org,
+        &options.exclude_tags,
+        &options.select_tags,
+        options.skip_archived,
+    );
+    let node = options.subtree.unwrap_or(org.root);
+
+    if node
+        .ancestors(&org.arena)
+        .any(|node| excluded.contains(&node))
+    {
+        return Ok(());
+    }
+
+    let total = exported_edges(org, node, &excluded)
+        .filter(|edge| match edge {
+            NodeEdge::Start(node) => is_headline(org, *node),
+            _ => false,
+        })
+        .count();
+    let mut done = 0;
+
+    // special blocks dropped or passed as is, whose contents are skipped
+    let mut skipping = None;
+
+    for edge in exported_edges(org, node, &excluded) {
+        let (node, start) = match edge {
+            NodeEdge::Start(node) => (node, true),
+            NodeEdge::End(node) => (node, false),
+        };
+
+        if let Some(skipped) = skipping {
+            if !start && node == skipped {
+                skipping = None;
+            }
+            continue;
+        }
+
+        let element = org.arena[node].get();
+
+        if !handler.keeps_unknown_keywords() && is_unknown_keyword(element, handler.backend()) {
+            continue;
+        }
+
+        if let (true, Element::SpecialBlock(block)) = (start, element) {
+            match handler.special_block(block) {
+                SpecialBlockPolicy::Render => (),
+                SpecialBlockPolicy::Drop => {
+                    skipping = Some(node);
+                    continue;
+                }
+                SpecialBlockPolicy::Raw => {
+                    skipping = Some(node);
+                    let contents = block_contents(org, node).into();
+                    let element = if options.safe_mode {
+                        Element::Text { value: contents }
+                    } else {
+                        Element::ExportBlock(ExportBlock {
+                            data: handler.backend().to_string().into(),
+                            contents,
+                        })
+                    };
+                    handler.set_node(node);
+                    handler.start(&mut w, &element)?;
+                    handler.set_node(node);
+                    handler.end(&mut w, &element)?;
+                    continue;
+                }
+            }
+        }
+
+        let text;
+        let element = match raw_export(element, handler.backend()) {
+            Some(value) if options.safe_mode => {
+                text = Element::Text {
+                    value: value.into(),
+                };
+                &text
+            }
+            _ => element,
+        };
+
+        handler.set_node(node);
+        if start {
+            handler.start(&mut w, element)?;
+        } else {
+            handler.end(&mut w, element)?;
+
+            if is_headline(org, node) {
+                done += 1;
+                handler.progress(done, total);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the edges of the traversal of `node`, skipping the subtrees of
+/// `excluded` nodes
+pub(crate) fn exported_edges<'b>(
+    org: &'b Org<'_>,
+    node: NodeId,
+    excluded: &'b HashSet<NodeId>,
+) -> impl Iterator<Item = NodeEdge> + 'b {
+    let mut skipping = None;
+
+    node.traverse(&org.arena)
+        .filter(move |edge| match (skipping, edge) {
+            (Some(skipped), NodeEdge::End(node)) if skipped == *node => {
+                skipping = None;
+                false
+            }
+            (Some(_), _) => false,
+            (None, NodeEdge::Start(node)) if excluded.contains(node) => {
+                skipping = Some(*node);
+                false
+            }
+            (None, _) => true,
+        })
+}
+
+/// Returns the headlines excluded by `exclude_tags` and `select_tags`, as
+/// well as by the tags of the `#+EXCLUDE_TAGS:` and `#+SELECT_TAGS:`
+/// keywords of `org`, and the commented headlines
+///
+/// With `skip_archived`, the sections and child headlines of archived
+/// headlines are excluded too, but not their titles.
+pub(crate) fn exclude(
+    org: &Org<'_>,
+    exclude_tags: &[String],
+    select_tags: &[String],
+    skip_archived: bool,
+) -> HashSet<NodeId> {
+    let settings = SettingsResolver::new(org, &DEFAULT_CONFIG);
+    let keyword_exclude_tags = settings.exclude_tags();
+    let keyword_select_tags = settings.select_tags();
+    let has_tag = |headline: HeadlineNode, tags: &[String], keyword_tags: &[&str]| {
+        headline.title(org).tags.iter().any(|tag| {
+            tags.iter().any(|t| t == tag) || keyword_tags.iter().any(|t| *t == tag.as_ref())
+        })
+    };
+
+    let mut excluded: HashSet<NodeId> = org
+        .headlines()
+        .filter(|&headline| has_tag(headline, exclude_tags, &keyword_exclude_tags))
+        .map(|headline| headline.node)
+        .collect();
+
+    let selected: Vec<_> = org
+        .headlines()
+        .filter(|&headline| has_tag(headline, select_tags, &keyword_select_tags))
+        .map(|headline| headline.node)
+        .collect();
+
+    if !selected.is_empty() {
+        // ancestors and descendants of selected headlines are kept
+        let kept: HashSet<NodeId> = selected
+            .iter()
+            .flat_map(|node| {
+                node.ancestors(&org.arena)
+                    .chain(node.descendants(&org.arena))
+            })
+            .collect();
+
+        excluded.extend(
+            org.headlines()
+                .map(|headline| headline.node)
+                .filter(|node| !kept.contains(node)),
+        );
+    }
+
+    // commented subtrees are never exported, even when selected
+    excluded.extend(
+        org.headlines()
+            .filter(|headline| headline.title(org).is_commented())
+            .map(|headline| headline.node),
+    );
+
+    if skip_archived {
+        for headline in org
+            .headlines()
+            .filter(|headline| headline.title(org).is_archived())
+        {
+            excluded.extend(headline.title_node.following_siblings(&org.arena).skip(1));
+        }
+    }
+
+    excluded
+}
+
+pub(crate) fn is_headline(org: &Org<'_>, node: NodeId) -> bool {
+    if let Element::Headline { .. } = org.arena[node].get() {
+        true
+    } else {
+        false
+    }
+}
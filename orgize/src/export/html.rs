@@ -1,11 +1,32 @@
+use std::borrow::Cow;
 use std::fmt;
 use std::io::{Error, Write};
+use std::sync::Arc;
 
+use indextree::NodeId;
 use jetscii::{bytes, BytesConst};
 
-use crate::elements::Element;
-use crate::export::write_datetime;
+use crate::elements::{
+    Checkbox, Citation, ContentMode, Datetime, Element, LatexKind, Link, SpecialBlock, Timestamp,
+    Title,
+};
+pub use crate::export::ADMONITIONS;
+use crate::export::{
+    block_text, citation_placeholder, raw_export, write_datetime, ExportStrings,
+    HtmlExportAnalysis, SpecialBlockPolicy,
+};
 
+/// Escapes `<`, `>`, `&` and quotes of a string, for html text and
+/// attribute values
+///
+/// ```rust
+/// use orgize::export::Escape;
+///
+/// assert_eq!(
+///     Escape("<a href=\"x\">&</a>").to_string(),
+///     "&lt;a href=&quot;x&quot;&gt;&amp;&lt;/a&gt;"
+/// );
+/// ```
 pub struct Escape<S: AsRef<str>>(pub S);
 
 impl<S: AsRef<str>> fmt::Display for Escape<S> {
@@ -36,58 +57,495 @@ impl<S: AsRef<str>> fmt::Display for Escape<S> {
     }
 }
 
+/// Writer adapter escaping everything written through it like [`Escape`]
+///
+/// It lets custom handlers escape text written by `write!` or by other
+/// writers, without formatting it into a string first.
+///
+/// ```rust
+/// use orgize::export::HtmlEscape;
+/// use std::io::Write;
+///
+/// let mut writer = Vec::new();
+/// write!(HtmlEscape(&mut writer), "{} & {}", "<b>", '"').unwrap();
+/// assert_eq!(writer, b"&lt;b&gt; &amp; &quot;");
+/// ```
+///
+/// [`Escape`]: struct.Escape.html
+pub struct HtmlEscape<W: Write>(pub W);
+
+impl<W: Write> Write for HtmlEscape<W> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        let mut pos = 0;
+
+        for (i, &byte) in buf.iter().enumerate() {
+            let escaped: &[u8] = match byte {
+                b'<' => b"&lt;",
+                b'>' => b"&gt;",
+                b'&' => b"&amp;",
+                b'\'' => b"&#39;",
+                b'"' => b"&quot;",
+                _ => continue,
+            };
+            self.0.write_all(&buf[pos..i])?;
+            self.0.write_all(escaped)?;
+            pos = i + 1;
+        }
+        self.0.write_all(&buf[pos..])?;
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        self.0.flush()
+    }
+}
+
+/// Percent-encodes the spaces, control and non-ASCII characters of a link path
+///
+/// Existing `%XX` sequences aren't encoded again, and query strings and
+/// fragments are kept as is. The result still has to be escaped with
+/// [`Escape`] before being written in an attribute.
+///
+/// ```rust
+/// use orgize::export::EncodeHref;
+///
+/// assert_eq!(
+///     EncodeHref("a b%20c?q=100%#été").to_string(),
+///     "a%20b%20c?q=100%25#%C3%A9t%C3%A9"
+/// );
+/// ```
+///
+/// [`Escape`]: struct.Escape.html
+pub struct EncodeHref<S: AsRef<str>>(pub S);
+
+impl<S: AsRef<str>> fmt::Display for EncodeHref<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = self.0.as_ref();
+        let bytes = s.as_bytes();
+        let mut pos = 0;
+
+        for (i, &byte) in bytes.iter().enumerate() {
+            let encode = match byte {
+                b'%' => {
+                    !(i + 2 < bytes.len()
+                        && bytes[i + 1].is_ascii_hexdigit()
+                        && bytes[i + 2].is_ascii_hexdigit())
+                }
+                b' ' | 0x00..=0x1f | 0x7f..=0xff => true,
+                _ => false,
+            };
+
+            if encode {
+                // runs of unencoded bytes are ASCII, so they're sliced at
+                // character boundaries
+                if pos < i {
+                    write!(f, "{}", &s[pos..i])?;
+                }
+                write!(f, "%{:02X}", byte)?;
+                pos = i + 1;
+            }
+        }
+
+        write!(f, "{}", &s[pos..])
+    }
+}
+
 pub trait HtmlHandler<E: From<Error>> {
+    /// Whether `Raw` elements, which are left over from malformed syntax, are
+    /// written as escaped text or skipped, defaults to `true`
+    fn render_raw(&self) -> bool {
+        true
+    }
+
+    /// Whether the contents of html export blocks, export snippets and
+    /// `#+HTML:` keywords are escaped instead of written as is, defaults to
+    /// `false`
+    ///
+    /// Enable it when rendering documents from untrusted sources. `RawHtml`
+    /// elements, which only code creates, are written as is regardless.
+    fn safe_mode(&self) -> bool {
+        false
+    }
+
+    /// Whether statistics cookies, like `[3/7]` or `[40%]`, are written as a
+    /// `<progress>` element followed by the cookie, instead of the cookie
+    /// alone, defaults to `false`
+    fn progress_cookies(&self) -> bool {
+        false
+    }
+
+    /// Whether comment lines and comment blocks are written as
+    /// `<aside class="margin-note">` elements where they appear, instead of
+    /// being dropped, defaults to `false`
+    ///
+    /// Enable it for review builds of documents whose review notes are
+    /// comments, like `# TODO(review): ...`.
+    fn margin_notes(&self) -> bool {
+        false
+    }
+
+    /// Whether `link`, a link without description, is written as an image
+    /// instead of an anchor, defaults to [`Link::is_image`]
+    ///
+    /// The `src` of the image is the path of the link without its `file:`
+    /// prefix.
+    ///
+    /// [`Link::is_image`]: ../elements/struct.Link.html#method.is_image
+    fn is_image(&self, link: &Link) -> bool {
+        link.is_image()
+    }
+
+    /// Returns the href of `link`, or `None` to write its description, or
+    /// its path if it has none, as plain text instead of an anchor,
+    /// defaults to the path of the link
+    ///
+    /// The path is the raw one, with its type like `id:` or `https:`, or
+    /// `#ID` for the links to targets resolved by an
+    /// [`HtmlExportAnalysis`]. The `src` of images, see [`is_image`], is the
+    /// href without its `file:` prefix.
+    ///
+    /// [`HtmlExportAnalysis`]: struct.HtmlExportAnalysis.html
+    /// [`is_image`]: #method.is_image
+    fn resolve_link<'a>(&self, link: &'a Link) -> Option<Cow<'a, str>> {
+        Some(Cow::Borrowed(&link.path))
+    }
+
+    /// How the special block `block`, e.g. `#+BEGIN_warning`, is rendered,
+    /// defaults to `SpecialBlockPolicy::Render`
+    ///
+    /// Rendered blocks are written as `<div class="warning">` by `start` and
+    /// `end`, which can be overridden for specific names.
+    fn special_block(&self, _block: &SpecialBlock) -> SpecialBlockPolicy {
+        SpecialBlockPolicy::Render
+    }
+
+    /// Returns the title of the special block `block` if it's an admonition,
+    /// rendered as `<aside class="admonition warning">` instead of a `<div>`,
+    /// defaults to `None`
+    fn admonition_title(&self, _block: &SpecialBlock) -> Option<String> {
+        None
+    }
+
+    /// Writes the todo keyword of a headline, after the start of its title,
+    /// `done` is set for done keywords, defaults to writing nothing
+    ///
+    /// It's called by `Org::html` and the other renderings using an
+    /// [`HtmlExportAnalysis`], which tells done keywords apart.
+    ///
+    /// [`HtmlExportAnalysis`]: struct.HtmlExportAnalysis.html
+    fn todo_keyword<W: Write>(&mut self, _w: W, _keyword: &str, _done: bool) -> Result<(), E> {
+        Ok(())
+    }
+
+    /// Writes the section number of a headline, like `2.1`, after the start
+    /// of its title, defaults to `<span class="section-number">2.1</span> `
+    ///
+    /// It's called with an [`HtmlExportAnalysis`] for documents numbering
+    /// their headings with `#+OPTIONS: num:t` or `num:N`.
+    ///
+    /// [`HtmlExportAnalysis`]: struct.HtmlExportAnalysis.html
+    fn section_number<W: Write>(&mut self, mut w: W, number: &str) -> Result<(), E> {
+        write!(
+            w,
+            "<span class=\"section-number\">{}</span> ",
+            Escape(number)
+        )?;
+        Ok(())
+    }
+
+    /// Writes a citation, defaults to a placeholder listing its keys, like
+    /// `<cite>(see doe2020; smith, p. 3)</cite>`
+    ///
+    /// Formatting citations needs a bibliography and a citation style, which
+    /// are left to the caller.
+    fn citation<W: Write>(&mut self, mut w: W, citation: &Citation) -> Result<(), E> {
+        write!(w, "<cite>{}</cite>", Escape(citation_placeholder(citation)))?;
+        Ok(())
+    }
+
+    /// Writes the checkbox of a list item, after the start of the item,
+    /// defaults to a disabled `<input type="checkbox">`
+    ///
+    /// Partially checked boxes are unchecked, with `aria-checked="mixed"`.
+    fn checkbox<W: Write>(&mut self, mut w: W, checkbox: Checkbox) -> Result<(), E> {
+        match checkbox {
+            Checkbox::Unchecked => write!(w, "<input type=\"checkbox\" disabled>")?,
+            Checkbox::Checked => write!(w, "<input type=\"checkbox\" checked disabled>")?,
+            Checkbox::Partial => write!(
+                w,
+                "<input type=\"checkbox\" aria-checked=\"mixed\" disabled>"
+            )?,
+        }
+        Ok(())
+    }
+
+    /// Writes an image, defaults to `<img src="SRC" alt="ALT">`
+    ///
+    /// It's called for the images inlined by
+    /// [`HtmlExportOptions::image_resolver`], with a `data:` URI and the path
+    /// of the link.
+    ///
+    /// [`HtmlExportOptions::image_resolver`]: struct.HtmlExportOptions.html#structfield.image_resolver
+    fn image<W: Write>(&mut self, mut w: W, src: &str, alt: &str) -> Result<(), E> {
+        write!(w, "<img src=\"{}\" alt=\"{}\">", Escape(src), Escape(alt))?;
+        Ok(())
+    }
+
+    /// Writes an image with the attributes of its `#+ATTR_HTML:` keyword,
+    /// e.g. `("width", "300")`, defaults to `image` without attributes
+    ///
+    /// `:alt` replaces `alt` and the other attributes are written after it.
+    /// It's called instead of `image` by an [`HtmlExportAnalysis`], for the
+    /// links without description that are images, see [`is_image`].
+    ///
+    /// [`HtmlExportAnalysis`]: struct.HtmlExportAnalysis.html
+    /// [`is_image`]: #method.is_image
+    fn image_with_attributes<W: Write>(
+        &mut self,
+        mut w: W,
+        src: &str,
+        alt: &str,
+        attributes: &[(String, String)],
+    ) -> Result<(), E> {
+        if attributes.is_empty() {
+            return self.image(w, src, alt);
+        }
+
+        let alt = attributes
+            .iter()
+            .find(|(key, _)| key == "alt")
+            .map_or(alt, |(_, value)| value);
+        write!(w, "<img src=\"{}\" alt=\"{}\"", Escape(src), Escape(alt))?;
+        for (key, value) in attributes.iter().filter(|(key, _)| key != "alt") {
+            write!(w, " {}=\"{}\"", key, Escape(value))?;
+        }
+        write!(w, ">")?;
+        Ok(())
+    }
+
+    /// Writes a footnote reference, numbered in order of first reference,
+    /// defaults to `<sup><a href="#fn.1" id="fnr.1">1</a></sup>`
+    ///
+    /// `id` is `fnr.N` for the first reference to the footnote `N`, and
+    /// `fnr.N.2`, `fnr.N.3`… for the next ones. It's called with an
+    /// [`HtmlExportAnalysis`] instead of `start` and the definitions are
+    /// written at the end of the document, see [`footnotes_start`].
+    ///
+    /// [`HtmlExportAnalysis`]: struct.HtmlExportAnalysis.html
+    /// [`footnotes_start`]: #method.footnotes_start
+    fn footnote_reference<W: Write>(&mut self, mut w: W, number: usize, id: &str) -> Result<(), E> {
+        write!(
+            w,
+            "<sup><a href=\"#fn.{}\" id=\"{}\">{}</a></sup>",
+            number,
+            Escape(id),
+            number
+        )?;
+        Ok(())
+    }
+
+    /// Writes the start of the footnotes at the end of the document, with
+    /// their title, defaults to
+    /// `<div id="footnotes"><h2 class="footnotes">Footnotes</h2><div id="text-footnotes">`
+    ///
+    /// It's only called if a referenced footnote is defined. Each definition
+    /// is written between [`footnote_start`] and [`footnote_end`], in order of
+    /// number.
+    ///
+    /// [`footnote_start`]: #method.footnote_start
+    /// [`footnote_end`]: #method.footnote_end
+    fn footnotes_start<W: Write>(&mut self, mut w: W, title: &str) -> Result<(), E> {
+        write!(
+            w,
+            "<div id=\"footnotes\"><h2 class=\"footnotes\">{}</h2><div id=\"text-footnotes\">",
+            Escape(title)
+        )?;
+        Ok(())
+    }
+
+    /// Writes the end of the footnotes, defaults to `</div></div>`
+    fn footnotes_end<W: Write>(&mut self, mut w: W) -> Result<(), E> {
+        write!(w, "</div></div>")?;
+        Ok(())
+    }
+
+    /// Writes the start of the definition of a footnote, defaults to its
+    /// number linking back to the first reference,
+    /// `<div class="footdef"><sup><a id="fn.1" class="footnum" href="#fnr.1">1</a></sup> <div class="footpara">`
+    fn footnote_start<W: Write>(&mut self, mut w: W, number: usize) -> Result<(), E> {
+        write!(
+            w,
+            "<div class=\"footdef\"><sup><a id=\"fn.{0}\" class=\"footnum\" href=\"#fnr.{0}\">{0}</a></sup> \
+             <div class=\"footpara\">",
+            number
+        )?;
+        Ok(())
+    }
+
+    /// Writes the end of the definition of a footnote, defaults to
+    /// `</div></div>`
+    fn footnote_end<W: Write>(&mut self, mut w: W, _number: usize) -> Result<(), E> {
+        write!(w, "</div></div>")?;
+        Ok(())
+    }
+
+    /// Writes the start of an org table with a caption, whose html with its
+    /// label is `caption`, defaults to `<table><caption>CAPTION</caption>`
+    /// followed by `<thead>` or `<tbody>`
+    ///
+    /// It's called with an [`HtmlExportAnalysis`] instead of `start`, and the
+    /// table ends with `end`.
+    ///
+    /// [`HtmlExportAnalysis`]: struct.HtmlExportAnalysis.html
+    fn captioned_table_start<W: Write>(
+        &mut self,
+        mut w: W,
+        has_header: bool,
+        caption: &str,
+    ) -> Result<(), E> {
+        write!(
+            w,
+            "<table><caption>{}</caption>{}",
+            caption,
+            if has_header { "<thead>" } else { "<tbody>" }
+        )?;
+        Ok(())
+    }
+
+    /// Writes the start of a figure or listing with a caption, before the
+    /// caption, defaults to `<figure>`
+    ///
+    /// It's called with an [`HtmlExportAnalysis`], which writes the caption
+    /// and then the element between `figure_start` and [`figure_end`].
+    ///
+    /// [`HtmlExportAnalysis`]: struct.HtmlExportAnalysis.html
+    /// [`figure_end`]: #method.figure_end
+    fn figure_start<W: Write>(&mut self, mut w: W) -> Result<(), E> {
+        write!(w, "<figure>")?;
+        Ok(())
+    }
+
+    /// Writes the end of a figure or listing with a caption, defaults to
+    /// `</figure>`
+    fn figure_end<W: Write>(&mut self, mut w: W) -> Result<(), E> {
+        write!(w, "</figure>")?;
+        Ok(())
+    }
+
+    /// Called before the start and the end of every exported node, with the
+    /// analysis of the document
+    ///
+    /// Handlers which need the numbers or ids of the node being rendered can
+    /// keep them, the default implementation does nothing.
+    fn set_context(&mut self, _node: NodeId, _analysis: &HtmlExportAnalysis) {}
+
     fn start<W: Write>(&mut self, mut w: W, element: &Element) -> Result<(), E> {
         use Element::*;
 
         match element {
             // container elements
-            SpecialBlock(_) => (),
+            SpecialBlock(block) => {
+                match self.admonition_title(block) {
+                    Some(title) => write!(
+                        w,
+                        "<aside class=\"admonition {}\"><p class=\"admonition-title\">{}</p>",
+                        Escape(block.name.to_lowercase()),
+                        Escape(title)
+                    )?,
+                    None => write!(w, "<div class=\"{}\">", Escape(&block.name))?,
+                }
+                if let (ContentMode::Raw, Some(contents)) = (block.mode, &block.contents) {
+                    write!(w, "<pre>{}</pre>", Escape(contents))?;
+                }
+            }
             QuoteBlock(_) => write!(w, "<blockquote>")?,
             CenterBlock(_) => write!(w, "<div class=\"center\">")?,
             VerseBlock(_) => write!(w, "<p class=\"verse\">")?,
             Bold => write!(w, "<b>")?,
+            Caption(_) => write!(w, "<figcaption>")?,
             Document => write!(w, "<main>")?,
             DynBlock(_dyn_block) => (),
             Headline { .. } => (),
             List(list) => {
-                if list.ordered {
+                if list.description {
+                    write!(w, "<dl>")?;
+                } else if let Some(start) = list.start {
+                    write!(w, "<ol start=\"{}\">", start)?;
+                } else if list.ordered {
                     write!(w, "<ol>")?;
                 } else {
                     write!(w, "<ul>")?;
                 }
             }
             Italic => write!(w, "<i>")?,
-            ListItem(_) => write!(w, "<li>")?,
+            Link(link) => match self.resolve_link(link) {
+                Some(href) if link.desc.is_none() && self.is_image(link) => {
+                    let src = EncodeHref(image_src(&href)).to_string();
+                    self.image(&mut w, &src, image_src(&link.path))?;
+                }
+                Some(href) => {
+                    write!(w, "<a href=\"{}\">", Escape(EncodeHref(href).to_string()))?;
+                    if link.desc.is_none() {
+                        write!(w, "{}", Escape(&link.path))?;
+                    }
+                }
+                None if link.desc.is_none() => write!(w, "{}", Escape(&link.path))?,
+                None => (),
+            },
+            ListItem(item) => {
+                if item.tag.is_some() {
+                    write!(w, "<dt>")?;
+                } else if let Some(counter) = item.counter {
+                    write!(w, "<li value=\"{}\">", counter)?;
+                } else {
+                    write!(w, "<li>")?;
+                }
+                if let Some(checkbox) = item.checkbox {
+                    self.checkbox(&mut w, checkbox)?;
+                }
+            }
+            ListItemTag => (),
             Paragraph => write!(w, "<p>")?,
             Section => write!(w, "<section>")?,
             Strike => write!(w, "<s>")?,
             Underline => write!(w, "<u>")?,
+            Subscript { .. } => write!(w, "<sub>")?,
+            Superscript { .. } => write!(w, "<sup>")?,
             // non-container elements
-            CommentBlock(_) => (),
-            ExampleBlock(block) => write!(
+            CommentBlock(block) => {
+                if self.margin_notes() {
+                    write!(
+                        w,
+                        "<aside class=\"margin-note\">{}</aside>",
+                        Escape(block.contents.trim_end())
+                    )?;
+                }
+            }
+            ExampleBlock(_) | FixedWidth { .. } => write!(
                 w,
                 "<pre class=\"example\">{}</pre>",
-                Escape(&block.contents)
+                Escape(block_text(element).unwrap_or_default())
             )?,
-            ExportBlock(block) => {
-                if block.data.eq_ignore_ascii_case("HTML") {
-                    write!(w, "{}", block.contents)?
+            ExportBlock(_) | Snippet(_) | Keyword(_) => {
+                if let Some(value) = raw_export(element, "html") {
+                    if self.safe_mode() {
+                        write!(w, "{}", Escape(value))?
+                    } else {
+                        write!(w, "{}", value)?
+                    }
                 }
             }
             SourceBlock(block) => {
+                let contents = block_text(element).unwrap_or_default();
                 if block.language.is_empty() {
-                    write!(
-                        w,
-                        "<pre class=\"example\">{}</pre>",
-                        Escape(&block.contents)
-                    )?;
+                    write!(w, "<pre class=\"example\">{}</pre>", Escape(contents))?;
                 } else {
                     write!(
                         w,
                         "<div class=\"org-src-container\"><pre class=\"src src-{}\">{}</pre></div>",
-                        block.language,
-                        Escape(&block.contents)
+                        Escape(&block.language),
+                        Escape(contents)
                     )?;
                 }
             }
@@ -95,26 +553,21 @@ pub trait HtmlHandler<E: From<Error>> {
             InlineSrc(inline_src) => write!(
                 w,
                 "<code class=\"src src-{}\">{}</code>",
-                inline_src.lang,
+                Escape(&inline_src.lang),
                 Escape(&inline_src.body)
             )?,
             Code { value } => write!(w, "<code>{}</code>", Escape(value))?,
             FnRef(_fn_ref) => (),
+            Citation(citation) => self.citation(&mut w, citation)?,
             InlineCall(_) => (),
-            Link(link) => write!(
+            Macros(_macros) => (),
+            RadioTarget(radio_target) => write!(
                 w,
-                "<a href=\"{}\">{}</a>",
-                Escape(&link.path),
-                Escape(link.desc.as_ref().unwrap_or(&link.path)),
+                "<a id=\"{}\">{}</a>",
+                Escape(radio_target.id()),
+                Escape(&radio_target.target)
             )?,
-            Macros(_macros) => (),
-            RadioTarget => (),
-            Snippet(snippet) => {
-                if snippet.name.eq_ignore_ascii_case("HTML") {
-                    write!(w, "{}", snippet.value)?;
-                }
-            }
-            Target(_target) => (),
+            Target(target) => write!(w, "<a id=\"{}\"></a>", Escape(&target.target))?,
             Text { value } => write!(w, "{}", Escape(value))?,
             Timestamp(timestamp) => {
                 use crate::elements::Timestamp;
@@ -126,18 +579,18 @@ pub trait HtmlHandler<E: From<Error>> {
 
                 match timestamp {
                     Timestamp::Active { start, .. } => {
-                        write_datetime(&mut w, "&lt;", start, "&gt;")?;
+                        write_html_datetime(&mut w, "&lt;", start, "&gt;")?;
                     }
                     Timestamp::Inactive { start, .. } => {
-                        write_datetime(&mut w, "[", start, "]")?;
+                        write_html_datetime(&mut w, "[", start, "]")?;
                     }
                     Timestamp::ActiveRange { start, end, .. } => {
-                        write_datetime(&mut w, "&lt;", start, "&gt;&#x2013;")?;
-                        write_datetime(&mut w, "&lt;", end, "&gt;")?;
+                        write_html_datetime(&mut w, "&lt;", start, "&gt;&#x2013;")?;
+                        write_html_datetime(&mut w, "&lt;", end, "&gt;")?;
                     }
                     Timestamp::InactiveRange { start, end, .. } => {
-                        write_datetime(&mut w, "[", start, "]&#x2013;")?;
-                        write_datetime(&mut w, "[", end, "]")?;
+                        write_html_datetime(&mut w, "[", start, "]&#x2013;")?;
+                        write_html_datetime(&mut w, "[", end, "]")?;
                     }
                     Timestamp::Diary { value } => write!(&mut w, "&lt;%%({})&gt;", Escape(value))?,
                 }
@@ -145,52 +598,166 @@ pub trait HtmlHandler<E: From<Error>> {
                 write!(&mut w, "</span></span>")?;
             }
             Verbatim { value } => write!(&mut w, "<code>{}</code>", Escape(value))?,
+            Entity(entity) => write!(w, "{}", entity.html)?,
+            LatexFragment(fragment) => match fragment.kind {
+                LatexKind::Environment => {
+                    write!(w, "<div class=\"math\">{}</div>", Escape(&fragment.value))?
+                }
+                kind if kind.is_display() => write!(
+                    w,
+                    "<span class=\"math\">\\[{}\\]</span>",
+                    Escape(&fragment.value)
+                )?,
+                _ => write!(
+                    w,
+                    "<span class=\"math\">\\({}\\)</span>",
+                    Escape(&fragment.value)
+                )?,
+            },
             FnDef(_fn_def) => (),
             Clock(_clock) => (),
-            Comment { .. } => (),
-            FixedWidth { value } => write!(w, "<pre class=\"example\">{}</pre>", Escape(value))?,
-            Keyword(_keyword) => (),
+            Comment { value } => {
+                if self.margin_notes() {
+                    write!(
+                        w,
+                        "<aside class=\"margin-note\">{}</aside>",
+                        Escape(comment_text(value))
+                    )?;
+                }
+            }
+            Raw { value } => {
+                if self.render_raw() {
+                    write!(w, "{}", Escape(value))?
+                }
+            }
+            // only code creates them, so they're trusted even in safe mode
+            RawHtml(raw_html) => write!(w, "{}", raw_html.html())?,
             Drawer(_drawer) => (),
             Rule => write!(w, "<hr>")?,
-            Cookie(cookie) => write!(w, "<code>{}</code>", cookie.value)?,
-            Title(title) => write!(w, "<h{}>", if title.level <= 6 { title.level } else { 6 })?,
-            Table(_) => (),
-            TableRow(_) => (),
-            TableCell => (),
+            LineBreak => write!(w, "<br>")?,
+            Cookie(cookie) if self.progress_cookies() => {
+                let (done, total) = cookie.progress();
+                // an empty cookie like `[0/0]` has no progress
+                let (value, max) = if total == 0 {
+                    (0, 1)
+                } else {
+                    (done.min(total), total)
+                };
+                write!(
+                    w,
+                    "<progress max=\"{}\" value=\"{}\">{}%</progress> <code>{}</code>",
+                    max,
+                    value,
+                    // in u128 since `value * 100` overflows for large cookies
+                    u128::from(value) * 100 / u128::from(max),
+                    Escape(&cookie.value)
+                )?
+            }
+            Cookie(cookie) => write!(w, "<code>{}</code>", Escape(&cookie.value))?,
+            Title(title) => {
+                write!(w, "<h{}", if title.level <= 6 { title.level } else { 6 })?;
+                self.title_attributes(&mut w, title)?;
+                write!(w, ">")?;
+            }
+            Table(crate::elements::Table::Org { has_header, .. }) => {
+                if *has_header {
+                    write!(w, "<table><thead>")?
+                } else {
+                    write!(w, "<table><tbody>")?
+                }
+            }
+            Table(crate::elements::Table::TableEl { value }) => {
+                write!(w, "<pre class=\"table-el\">{}</pre>", Escape(value))?
+            }
+            TableRow(crate::elements::TableRow::HeaderRule) => write!(w, "</thead><tbody>")?,
+            TableRow(crate::elements::TableRow::BodyRule) => (),
+            TableRow(_) => write!(w, "<tr>")?,
+            TableCell(crate::elements::TableCell::Header) => write!(w, "<th>")?,
+            TableCell(crate::elements::TableCell::Body) => write!(w, "<td>")?,
         }
 
         Ok(())
     }
+
+    /// Writes the start of the heading of `title`, whose id is `id`, defaults
+    /// to `<h1 id="ID">` with the attributes of `title_attributes`
+    ///
+    /// It's called instead of `start` for the titles of headlines with
+    /// [`HtmlExportOptions::slug_ids`].
+    ///
+    /// [`HtmlExportOptions::slug_ids`]: struct.HtmlExportOptions.html#structfield.slug_ids
+    fn heading_start<W: Write>(&mut self, mut w: W, title: &Title, id: &str) -> Result<(), E> {
+        write!(w, "<h{} id=\"{}\"", title.level.min(6), Escape(id))?;
+        self.title_attributes(&mut w, title)?;
+        write!(w, ">")?;
+        Ok(())
+    }
+
+    /// Writes extra attributes of the heading of `title`, each preceded by a space
+    fn title_attributes<W: Write>(&mut self, _w: W, _title: &Title) -> Result<(), E> {
+        Ok(())
+    }
+
     fn end<W: Write>(&mut self, mut w: W, element: &Element) -> Result<(), E> {
         use Element::*;
 
         match element {
             // container elements
-            SpecialBlock(_) => (),
+            SpecialBlock(block) => {
+                if self.admonition_title(block).is_some() {
+                    write!(w, "</aside>")?
+                } else {
+                    write!(w, "</div>")?
+                }
+            }
             QuoteBlock(_) => write!(w, "</blockquote>")?,
             CenterBlock(_) => write!(w, "</div>")?,
             VerseBlock(_) => write!(w, "</p>")?,
             Bold => write!(w, "</b>")?,
+            Caption(_) => write!(w, "</figcaption>")?,
             Document => write!(w, "</main>")?,
             DynBlock(_dyn_block) => (),
             Headline { .. } => (),
             List(list) => {
-                if list.ordered {
+                if list.description {
+                    write!(w, "</dl>")?;
+                } else if list.ordered {
                     write!(w, "</ol>")?;
                 } else {
                     write!(w, "</ul>")?;
                 }
             }
             Italic => write!(w, "</i>")?,
-            ListItem(_) => write!(w, "</li>")?,
+            Link(link) => {
+                if self.resolve_link(link).is_some()
+                    && (link.desc.is_some() || !self.is_image(link))
+                {
+                    write!(w, "</a>")?;
+                }
+            }
+            ListItem(item) => {
+                if item.tag.is_some() {
+                    write!(w, "</dd>")?;
+                } else {
+                    write!(w, "</li>")?;
+                }
+            }
+            ListItemTag => write!(w, "</dt><dd>")?,
             Paragraph => write!(w, "</p>")?,
             Section => write!(w, "</section>")?,
             Strike => write!(w, "</s>")?,
             Underline => write!(w, "</u>")?,
+            Subscript { .. } => write!(w, "</sub>")?,
+            Superscript { .. } => write!(w, "</sup>")?,
             Title(title) => write!(w, "</h{}>", if title.level <= 6 { title.level } else { 6 })?,
-            Table(_) => (),
-            TableRow(_) => (),
-            TableCell => (),
+            Drawer(_) => (),
+            FnDef(_) => (),
+            Table(crate::elements::Table::Org { .. }) => write!(w, "</tbody></table>")?,
+            Table(crate::elements::Table::TableEl { .. }) => (),
+            TableRow(row) if row.is_rule() => (),
+            TableRow(_) => write!(w, "</tr>")?,
+            TableCell(crate::elements::TableCell::Header) => write!(w, "</th>")?,
+            TableCell(crate::elements::TableCell::Body) => write!(w, "</td>")?,
             // non-container elements
             _ => debug_assert!(!element.is_container()),
         }
@@ -199,37 +766,468 @@ pub trait HtmlHandler<E: From<Error>> {
     }
 }
 
-pub struct DefaultHtmlHandler;
+/// Writes a citation, see [`DefaultHtmlHandler::citation_renderer`]
+///
+/// [`DefaultHtmlHandler::citation_renderer`]: struct.DefaultHtmlHandler.html#structfield.citation_renderer
+pub type CitationRenderer =
+    Arc<dyn Fn(&Citation, &mut dyn Write) -> Result<(), Error> + Send + Sync>;
+
+/// Returns the href of a link, see [`DefaultHtmlHandler::link_resolver`]
+///
+/// [`DefaultHtmlHandler::link_resolver`]: struct.DefaultHtmlHandler.html#structfield.link_resolver
+pub type LinkResolver = Arc<dyn Fn(&Link) -> Option<String> + Send + Sync>;
+
+/// Writes the todo keyword of a headline, see [`DefaultHtmlHandler::todo_renderer`]
+///
+/// [`DefaultHtmlHandler::todo_renderer`]: struct.DefaultHtmlHandler.html#structfield.todo_renderer
+pub type TodoRenderer = Arc<dyn Fn(&str, bool, &mut dyn Write) -> Result<(), Error> + Send + Sync>;
+
+#[derive(Clone, Default)]
+pub struct DefaultHtmlHandler {
+    /// Escapes raw html, see [`HtmlHandler::safe_mode`]
+    ///
+    /// [`HtmlHandler::safe_mode`]: trait.HtmlHandler.html#method.safe_mode
+    pub safe_mode: bool,
+    /// Skips `Raw` elements, see [`HtmlHandler::render_raw`]
+    ///
+    /// [`HtmlHandler::render_raw`]: trait.HtmlHandler.html#method.render_raw
+    pub skip_raw: bool,
+    /// Headline data written as `data-*` attributes of headings, empty by default
+    ///
+    /// `TODO`, `PRIORITY` and `TAGS` are taken from the title, `SCHEDULED`,
+    /// `DEADLINE` and `CLOSED` are the start of the planning timestamps in
+    /// ISO 8601 form, any other key is looked up in the properties. For
+    /// example, `ID` is written as `data-id="..."`.
+    pub headline_data: Vec<String>,
+    /// Writes the `HTML_CLASS` property of headlines as the class of headings
+    pub headline_class: bool,
+    /// Policies of special blocks by name, compared case-insensitively, e.g.
+    /// `("comment", SpecialBlockPolicy::Drop)`, see
+    /// [`HtmlHandler::special_block`]
+    ///
+    /// [`HtmlHandler::special_block`]: trait.HtmlHandler.html#method.special_block
+    pub special_blocks: Vec<(String, SpecialBlockPolicy)>,
+    /// Policy of the special blocks not in `special_blocks`, defaults to
+    /// `SpecialBlockPolicy::Render`
+    pub unknown_blocks: SpecialBlockPolicy,
+    /// Renders `note`, `tip`, `important`, `warning` and `caution` blocks as
+    /// plain `<div>`s instead of admonitions
+    pub plain_admonitions: bool,
+    /// Titles of admonitions by name, e.g. `("warning", "Achtung")`, the
+    /// titles of `strings` are used for the others
+    pub admonition_titles: Vec<(String, String)>,
+    /// Strings of admonition titles, English by default
+    ///
+    /// They're replaced by the strings of the analysis in
+    /// [`HtmlHandler::set_context`], so they follow the `#+LANGUAGE:` of the
+    /// document and [`HtmlExportOptions::strings`] when rendering with an
+    /// analysis, like `Org::html` does.
+    ///
+    /// [`HtmlHandler::set_context`]: trait.HtmlHandler.html#method.set_context
+    /// [`HtmlExportOptions::strings`]: struct.HtmlExportOptions.html#structfield.strings
+    pub strings: ExportStrings,
+    /// Writes the todo keyword of headlines, with whether it's a done keyword,
+    /// e.g. as a status badge, keywords aren't written by default
+    ///
+    /// ```rust
+    /// use std::sync::Arc;
+    ///
+    /// use orgize::export::DefaultHtmlHandler;
+    /// use orgize::Org;
+    ///
+    /// let mut handler = DefaultHtmlHandler {
+    ///     todo_renderer: Some(Arc::new(|keyword, done, w| {
+    ///         let class = if done { "done" } else { "todo" };
+    ///         write!(w, "<span class=\"badge {}\">{}</span> ", class, keyword)
+    ///     })),
+    ///     ..Default::default()
+    /// };
+    /// let mut writer = Vec::new();
+    /// Org::parse("* DONE Ship").html_with_handler(&mut writer, &mut handler).unwrap();
+    ///
+    /// assert_eq!(
+    ///     String::from_utf8(writer).unwrap(),
+    ///     "<main><h1><span class=\"badge done\">DONE</span> Ship</h1></main>"
+    /// );
+    /// ```
+    pub todo_renderer: Option<TodoRenderer>,
+    /// Writes citations, e.g. with a citation processor, instead of the
+    /// placeholder of [`HtmlHandler::citation`]
+    ///
+    /// ```rust
+    /// use std::sync::Arc;
+    ///
+    /// use orgize::export::DefaultHtmlHandler;
+    /// use orgize::Org;
+    ///
+    /// let mut handler = DefaultHtmlHandler {
+    ///     citation_renderer: Some(Arc::new(|citation, w| {
+    ///         let keys: Vec<_> = citation.keys().collect();
+    ///         write!(w, "({})", keys.join(", "))
+    ///     })),
+    ///     ..Default::default()
+    /// };
+    /// let mut writer = Vec::new();
+    /// Org::parse("As shown [cite:@doe2020; @smith].")
+    ///     .html_with_handler(&mut writer, &mut handler)
+    ///     .unwrap();
+    ///
+    /// assert_eq!(
+    ///     String::from_utf8(writer).unwrap(),
+    ///     "<main><section><p>As shown (doe2020, smith).</p></section></main>"
+    /// );
+    /// ```
+    ///
+    /// [`HtmlHandler::citation`]: trait.HtmlHandler.html#method.citation
+    pub citation_renderer: Option<CitationRenderer>,
+    /// Writes statistics cookies as progress bars, see
+    /// [`HtmlHandler::progress_cookies`]
+    ///
+    /// [`HtmlHandler::progress_cookies`]: trait.HtmlHandler.html#method.progress_cookies
+    pub progress_cookies: bool,
+    /// Writes comments as margin notes, see [`HtmlHandler::margin_notes`]
+    ///
+    /// [`HtmlHandler::margin_notes`]: trait.HtmlHandler.html#method.margin_notes
+    pub margin_notes: bool,
+    /// Extensions of the links written as images, compared
+    /// case-insensitively, e.g. `["png", "avif"]`, those of
+    /// [`Link::is_image`] if `None`
+    ///
+    /// [`Link::is_image`]: ../elements/struct.Link.html#method.is_image
+    pub image_extensions: Option<Vec<String>>,
+    /// Returns the href of links, or `None` to write them as plain text, see
+    /// [`HtmlHandler::resolve_link`]
+    ///
+    /// ```rust
+    /// use std::sync::Arc;
+    ///
+    /// use orgize::export::DefaultHtmlHandler;
+    /// use orgize::Org;
+    ///
+    /// let mut handler = DefaultHtmlHandler {
+    ///     link_resolver: Some(Arc::new(|link| {
+    ///         if let Some(id) = link.path.strip_prefix("id:") {
+    ///             Some(format!("/notes/{}.html", id))
+    ///         } else if link.path.starts_with("roam:") {
+    ///             None
+    ///         } else {
+    ///             Some(link.path.to_string())
+    ///         }
+    ///     })),
+    ///     ..Default::default()
+    /// };
+    /// let mut writer = Vec::new();
+    /// Org::parse("[[id:42][Answer]], [[roam:Draft][draft]] and [[https://x.org]]")
+    ///     .html_with_handler(&mut writer, &mut handler)
+    ///     .unwrap();
+    ///
+    /// assert_eq!(
+    ///     String::from_utf8(writer).unwrap(),
+    ///     "<main><section><p><a href=\"/notes/42.html\">Answer</a>, draft and \
+    ///      <a href=\"https://x.org\">https://x.org</a></p></section></main>"
+    /// );
+    /// ```
+    ///
+    /// [`HtmlHandler::resolve_link`]: trait.HtmlHandler.html#method.resolve_link
+    pub link_resolver: Option<LinkResolver>,
+}
+
+/// Returns the `src` of an image link, its path without the `file:` prefix
+pub(crate) fn image_src(path: &str) -> &str {
+    path.strip_prefix("file:").unwrap_or(path)
+}
+
+/// Returns the text of comment lines, without their `#`
+fn comment_text(value: &str) -> String {
+    value
+        .lines()
+        .map(|line| {
+            let line = line.trim_start();
+            let line = line.strip_prefix('#').unwrap_or(line);
+            line.strip_prefix(' ').unwrap_or(line)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// A small stylesheet for the classes written by `DefaultHtmlHandler` and
+/// `Org::html_page`
+///
+/// These classes are `title`, `subtitle`, `admonition`, `admonition-title`,
+/// the names of admonitions and special blocks, `center`, `verse`,
+/// `example`, `org-src-container`, `src`, `src-LANGUAGE`,
+/// `timestamp-wrapper`, `timestamp`, `margin-note`, `footnotes`, `footdef`,
+/// `footnum` and `footpara`. Renaming one of them is a breaking change.
+#[cfg(feature = "css")]
+pub const DEFAULT_CSS: &str = r#".title { text-align: center; margin-bottom: 0.2em; }
+.subtitle { text-align: center; margin-top: 0; color: #666; }
+.center { margin-left: auto; margin-right: auto; text-align: center; }
+.verse { white-space: pre-line; font-style: italic; }
+pre.example, pre.src {
+  padding: 0.5em 1em;
+  overflow: auto;
+  background-color: #f6f8fa;
+  border: 1px solid #e1e4e8;
+  border-radius: 3px;
+}
+.org-src-container { margin: 1em 0; }
+code.src { background-color: #f6f8fa; padding: 0.1em 0.3em; border-radius: 3px; }
+.timestamp-wrapper { white-space: nowrap; }
+.timestamp { color: #6f42c1; }
+.admonition {
+  margin: 1em 0;
+  padding: 0.5em 1em;
+  border-left: 4px solid #0969da;
+  background-color: #f6f8fa;
+}
+.admonition-title { margin-top: 0; font-weight: bold; }
+.admonition.tip { border-left-color: #1a7f37; }
+.admonition.important { border-left-color: #8250df; }
+.admonition.warning { border-left-color: #9a6700; }
+.admonition.caution { border-left-color: #cf222e; }
+.margin-note {
+  float: right;
+  clear: right;
+  width: 30%;
+  margin: 0 0 0.5em 1em;
+  padding: 0.3em 0.6em;
+  font-size: 0.85em;
+  white-space: pre-line;
+  border-left: 3px solid #d4a72c;
+  background-color: #fff8c5;
+}
+.footnotes { font-size: 1.2em; margin-top: 2em; border-top: 1px solid #e1e4e8; }
+.footdef { display: flex; gap: 0.5em; margin-bottom: 0.5em; font-size: 0.9em; }
+.footnum { text-decoration: none; }
+.footpara > p { margin: 0; }
+"#;
+
+/// Writes [`DEFAULT_CSS`] in a `<style>` element, e.g. in the `<head>` of a
+/// page
+///
+/// [`DEFAULT_CSS`]: constant.DEFAULT_CSS.html
+#[cfg(feature = "css")]
+pub fn write_default_css<W: Write>(mut w: W) -> Result<(), Error> {
+    writeln!(w, "<style>\n{}</style>", DEFAULT_CSS)
+}
+
+impl HtmlHandler<Error> for DefaultHtmlHandler {
+    fn render_raw(&self) -> bool {
+        !self.skip_raw
+    }
+
+    fn safe_mode(&self) -> bool {
+        self.safe_mode
+    }
+
+    fn progress_cookies(&self) -> bool {
+        self.progress_cookies
+    }
+
+    fn margin_notes(&self) -> bool {
+        self.margin_notes
+    }
+
+    fn is_image(&self, link: &Link) -> bool {
+        match (&self.image_extensions, link.path.rfind('.')) {
+            (None, _) => link.is_image(),
+            (Some(extensions), Some(i)) => extensions
+                .iter()
+                .any(|extension| link.path[i + 1..].eq_ignore_ascii_case(extension)),
+            (Some(_), None) => false,
+        }
+    }
 
-impl HtmlHandler<Error> for DefaultHtmlHandler {}
+    fn resolve_link<'a>(&self, link: &'a Link) -> Option<Cow<'a, str>> {
+        match &self.link_resolver {
+            Some(resolver) => resolver(link).map(Cow::Owned),
+            None => Some(Cow::Borrowed(&link.path)),
+        }
+    }
+
+    fn admonition_title(&self, block: &SpecialBlock) -> Option<String> {
+        if self.plain_admonitions
+            || !ADMONITIONS
+                .iter()
+                .any(|name| name.eq_ignore_ascii_case(&block.name))
+        {
+            return None;
+        }
+
+        let title = match self
+            .admonition_titles
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(&block.name))
+        {
+            Some((_, title)) => title.clone(),
+            None => self.strings.admonition(&block.name)?.to_string(),
+        };
+        Some(title)
+    }
+
+    fn set_context(&mut self, _node: NodeId, analysis: &HtmlExportAnalysis) {
+        if self.strings != *analysis.strings() {
+            self.strings = analysis.strings().clone();
+        }
+    }
+
+    fn todo_keyword<W: Write>(&mut self, mut w: W, keyword: &str, done: bool) -> Result<(), Error> {
+        match &self.todo_renderer {
+            Some(render) => render(keyword, done, &mut w),
+            None => Ok(()),
+        }
+    }
+
+    fn citation<W: Write>(&mut self, mut w: W, citation: &Citation) -> Result<(), Error> {
+        match &self.citation_renderer {
+            Some(render) => render(citation, &mut w),
+            None => write!(w, "<cite>{}</cite>", Escape(citation_placeholder(citation))),
+        }
+    }
+
+    fn special_block(&self, block: &SpecialBlock) -> SpecialBlockPolicy {
+        self.special_blocks
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(&block.name))
+            .map(|(_, policy)| *policy)
+            .unwrap_or(self.unknown_blocks)
+    }
+
+    fn title_attributes<W: Write>(&mut self, mut w: W, title: &Title) -> Result<(), Error> {
+        if self.headline_class {
+            if let Some(class) = title.property("HTML_CLASS") {
+                write!(w, " class=\"{}\"", Escape(class))?;
+            }
+        }
+
+        for key in &self.headline_data {
+            let value = match &*key.to_uppercase() {
+                "TODO" => title.keyword.as_ref().map(|keyword| keyword.to_string()),
+                "PRIORITY" => title.priority.map(|priority| priority.to_string()),
+                "TAGS" if !title.tags.is_empty() => Some(title.tags.join(" ")),
+                "TAGS" => None,
+                "SCHEDULED" => title.scheduled().and_then(iso_timestamp),
+                "DEADLINE" => title.deadline().and_then(iso_timestamp),
+                "CLOSED" => title.closed().and_then(iso_timestamp),
+                _ => title.property(key).map(Into::into),
+            };
+
+            if let Some(value) = value {
+                let name: String = key
+                    .chars()
+                    .map(|c| {
+                        if c.is_ascii_alphanumeric() {
+                            c.to_ascii_lowercase()
+                        } else {
+                            '-'
+                        }
+                    })
+                    .collect();
+                write!(w, " data-{}=\"{}\"", name, Escape(value))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Writes `datetime` between the `start` and `end` markup, escaping its
+/// day name, which may contain any character but whitespace and digits
+fn write_html_datetime<W: Write>(
+    mut w: W,
+    start: &str,
+    datetime: &Datetime<'_>,
+    end: &str,
+) -> Result<(), Error> {
+    write!(w, "{}", start)?;
+    write_datetime(HtmlEscape(&mut w), "", datetime, "")?;
+    write!(w, "{}", end)
+}
+
+/// Returns the start of `timestamp` as `2019-10-10` or `2019-10-10T10:00`
+fn iso_timestamp(timestamp: &Timestamp) -> Option<String> {
+    let start = match timestamp {
+        Timestamp::Active { start, .. }
+        | Timestamp::Inactive { start, .. }
+        | Timestamp::ActiveRange { start, .. }
+        | Timestamp::InactiveRange { start, .. } => start,
+        Timestamp::Diary { .. } => return None,
+    };
+
+    let mut iso = format!("{}-{:02}-{:02}", start.year, start.month, start.day);
+    if let (Some(hour), Some(minute)) = (start.hour, start.minute) {
+        iso += &format!("T{:02}:{:02}", hour, minute);
+    }
+    Some(iso)
+}
 
 #[cfg(feature = "syntect")]
 pub mod syntect_feature {
     use super::*;
     use std::marker::PhantomData;
+    use std::path::Path;
 
     use syntect::{
         easy::HighlightLines,
         highlighting::ThemeSet,
-        html::{styled_line_to_highlighted_html, IncludeBackground},
-        parsing::SyntaxSet,
+        html::{
+            css_for_theme, styled_line_to_highlighted_html, ClassedHTMLGenerator, IncludeBackground,
+        },
+        parsing::{SyntaxReference, SyntaxSet},
+        LoadingError,
     };
 
+    /// Html handler highlighting source blocks, inline source blocks and
+    /// examples with syntect, and rendering the rest with `inner`
+    ///
+    /// Code is highlighted with inline styles from the `theme` of
+    /// `theme_set`, or with css classes styled by a stylesheet, like the
+    /// one of [`theme_css`], if `css_classes` is set. Code in a language
+    /// without syntax, or with a theme missing from `theme_set`, is written
+    /// by `inner`, escaped.
+    ///
+    /// ```rust
+    /// use orgize::export::SyntectHtmlHandler;
+    /// use orgize::Org;
+    ///
+    /// let mut handler = SyntectHtmlHandler {
+    ///     css_classes: true,
+    ///     ..SyntectHtmlHandler::default()
+    /// };
+    /// let mut writer = format!("<style>{}</style>", handler.theme_css().unwrap()).into_bytes();
+    /// Org::parse("#+BEGIN_SRC elisp\n(message \"hi\")\n#+END_SRC")
+    ///     .html_with_handler(&mut writer, &mut handler)
+    ///     .unwrap();
+    /// assert!(String::from_utf8(writer).unwrap().contains("<span class=\"source"));
+    /// ```
+    ///
+    /// [`theme_css`]: #method.theme_css
     pub struct SyntectHtmlHandler<E: From<Error>, H: HtmlHandler<E>> {
+        /// Syntaxes, the default ones of syntect, see [`load_syntaxes`]
+        ///
+        /// [`load_syntaxes`]: #method.load_syntaxes
         pub syntax_set: SyntaxSet,
+        /// Themes, the default ones of syntect
         pub theme_set: ThemeSet,
+        /// Name of the theme in `theme_set`, `InspiredGitHub` by default
+        pub theme: String,
+        /// Writes css classes, like `<span class="source rust">`, instead of
+        /// the inline styles of `theme`, `false` by default
+        pub css_classes: bool,
+        /// Languages of source blocks highlighted with the syntax of another
+        /// one, compared case-insensitively, e.g. `("elisp", "lisp")`
+        ///
+        /// Languages are looked up by the name or file extensions of
+        /// syntaxes. By default `elisp` and `emacs-lisp` are aliases of
+        /// `lisp`.
+        pub language_aliases: Vec<(String, String)>,
         pub inner: H,
         error_type: PhantomData<E>,
     }
 
     impl Default for SyntectHtmlHandler<Error, DefaultHtmlHandler> {
         fn default() -> Self {
-            SyntectHtmlHandler {
-                syntax_set: SyntaxSet::load_defaults_newlines(),
-                theme_set: ThemeSet::load_defaults(),
-                inner: DefaultHtmlHandler,
-                error_type: PhantomData,
-            }
+            SyntectHtmlHandler::new(DefaultHtmlHandler::default())
         }
     }
 
@@ -238,53 +1236,207 @@ pub mod syntect_feature {
             SyntectHtmlHandler {
                 syntax_set: SyntaxSet::load_defaults_newlines(),
                 theme_set: ThemeSet::load_defaults(),
+                theme: "InspiredGitHub".into(),
+                css_classes: false,
+                language_aliases: vec![
+                    ("elisp".into(), "lisp".into()),
+                    ("emacs-lisp".into(), "lisp".into()),
+                ],
                 inner,
                 error_type: PhantomData,
             }
         }
 
-        fn highlight(&self, language: Option<&str>, content: &str) -> String {
-            let mut highlighter = HighlightLines::new(
-                language
-                    .and_then(|lang| self.syntax_set.find_syntax_by_token(lang))
-                    .unwrap_or_else(|| self.syntax_set.find_syntax_plain_text()),
-                &self.theme_set.themes["InspiredGitHub"],
-            );
-            let regions = highlighter.highlight(content, &self.syntax_set);
-            styled_line_to_highlighted_html(&regions[..], IncludeBackground::No)
+        /// Adds the `.sublime-syntax` files of `folder` and its subfolders
+        /// to `syntax_set`
+        pub fn load_syntaxes<P: AsRef<Path>>(&mut self, folder: P) -> Result<(), LoadingError> {
+            let mut builder = self.syntax_set.clone().into_builder();
+            builder.add_from_folder(folder, true)?;
+            self.syntax_set = builder.build();
+            Ok(())
+        }
+
+        /// Returns the stylesheet of `theme` for the classes written with
+        /// `css_classes`, or `None` if it isn't in `theme_set`
+        pub fn theme_css(&self) -> Option<String> {
+            self.theme_set.themes.get(&self.theme).map(css_for_theme)
+        }
+
+        /// Returns the syntax of `language`, or the plain text one for
+        /// examples, which have no language
+        fn syntax(&self, language: Option<&str>) -> Option<&SyntaxReference> {
+            let language = match language {
+                Some(language) => language,
+                None => return Some(self.syntax_set.find_syntax_plain_text()),
+            };
+            let language = self
+                .language_aliases
+                .iter()
+                .find(|(alias, _)| alias.eq_ignore_ascii_case(language))
+                .map(|(_, language)| &**language)
+                .unwrap_or(language);
+            self.syntax_set.find_syntax_by_token(language)
+        }
+
+        /// Returns the highlighted html of `content`, or `None` if there's
+        /// no syntax for `language` or no `theme`
+        fn highlight(&self, language: Option<&str>, content: &str) -> Option<String> {
+            let syntax = self.syntax(language)?;
+            if self.css_classes {
+                let mut generator = ClassedHTMLGenerator::new(syntax, &self.syntax_set);
+                for line in content.lines() {
+                    generator.parse_html_for_line(line);
+                }
+                Some(generator.finalize())
+            } else {
+                let theme = self.theme_set.themes.get(&self.theme)?;
+                let mut highlighter = HighlightLines::new(syntax, theme);
+                let regions = highlighter.highlight(content, &self.syntax_set);
+                Some(styled_line_to_highlighted_html(
+                    &regions[..],
+                    IncludeBackground::No,
+                ))
+            }
         }
     }
 
     impl<E: From<Error>, H: HtmlHandler<E>> HtmlHandler<E> for SyntectHtmlHandler<E, H> {
+        fn render_raw(&self) -> bool {
+            self.inner.render_raw()
+        }
+
+        fn safe_mode(&self) -> bool {
+            self.inner.safe_mode()
+        }
+
+        fn progress_cookies(&self) -> bool {
+            self.inner.progress_cookies()
+        }
+
+        fn margin_notes(&self) -> bool {
+            self.inner.margin_notes()
+        }
+
+        fn is_image(&self, link: &Link) -> bool {
+            self.inner.is_image(link)
+        }
+
+        fn resolve_link<'a>(&self, link: &'a Link) -> Option<Cow<'a, str>> {
+            self.inner.resolve_link(link)
+        }
+
+        fn special_block(&self, block: &SpecialBlock) -> SpecialBlockPolicy {
+            self.inner.special_block(block)
+        }
+
+        fn admonition_title(&self, block: &SpecialBlock) -> Option<String> {
+            self.inner.admonition_title(block)
+        }
+
+        fn todo_keyword<W: Write>(&mut self, w: W, keyword: &str, done: bool) -> Result<(), E> {
+            self.inner.todo_keyword(w, keyword, done)
+        }
+
+        fn section_number<W: Write>(&mut self, w: W, number: &str) -> Result<(), E> {
+            self.inner.section_number(w, number)
+        }
+
+        fn heading_start<W: Write>(&mut self, w: W, title: &Title, id: &str) -> Result<(), E> {
+            self.inner.heading_start(w, title, id)
+        }
+
+        fn citation<W: Write>(&mut self, w: W, citation: &Citation) -> Result<(), E> {
+            self.inner.citation(w, citation)
+        }
+
+        fn checkbox<W: Write>(&mut self, w: W, checkbox: Checkbox) -> Result<(), E> {
+            self.inner.checkbox(w, checkbox)
+        }
+
+        fn image<W: Write>(&mut self, w: W, src: &str, alt: &str) -> Result<(), E> {
+            self.inner.image(w, src, alt)
+        }
+
+        fn image_with_attributes<W: Write>(
+            &mut self,
+            w: W,
+            src: &str,
+            alt: &str,
+            attributes: &[(String, String)],
+        ) -> Result<(), E> {
+            self.inner.image_with_attributes(w, src, alt, attributes)
+        }
+
+        fn footnote_reference<W: Write>(&mut self, w: W, number: usize, id: &str) -> Result<(), E> {
+            self.inner.footnote_reference(w, number, id)
+        }
+
+        fn footnotes_start<W: Write>(&mut self, w: W, title: &str) -> Result<(), E> {
+            self.inner.footnotes_start(w, title)
+        }
+
+        fn footnotes_end<W: Write>(&mut self, w: W) -> Result<(), E> {
+            self.inner.footnotes_end(w)
+        }
+
+        fn footnote_start<W: Write>(&mut self, w: W, number: usize) -> Result<(), E> {
+            self.inner.footnote_start(w, number)
+        }
+
+        fn footnote_end<W: Write>(&mut self, w: W, number: usize) -> Result<(), E> {
+            self.inner.footnote_end(w, number)
+        }
+
+        fn captioned_table_start<W: Write>(
+            &mut self,
+            w: W,
+            has_header: bool,
+            caption: &str,
+        ) -> Result<(), E> {
+            self.inner.captioned_table_start(w, has_header, caption)
+        }
+
+        fn figure_start<W: Write>(&mut self, w: W) -> Result<(), E> {
+            self.inner.figure_start(w)
+        }
+
+        fn figure_end<W: Write>(&mut self, w: W) -> Result<(), E> {
+            self.inner.figure_end(w)
+        }
+
+        fn set_context(&mut self, node: NodeId, analysis: &HtmlExportAnalysis) {
+            self.inner.set_context(node, analysis)
+        }
+
         fn start<W: Write>(&mut self, mut w: W, element: &Element<'_>) -> Result<(), E> {
+            // code which can't be highlighted is escaped by the inner handler
             match element {
-                Element::InlineSrc(inline_src) => write!(
-                    w,
-                    "<code>{}</code>",
-                    self.highlight(Some(&inline_src.lang), &inline_src.body)
-                )?,
+                Element::InlineSrc(inline_src) => {
+                    match self.highlight(Some(&inline_src.lang), &inline_src.body) {
+                        Some(html) => write!(w, "<code>{}</code>", html)?,
+                        None => self.inner.start(w, element)?,
+                    }
+                }
                 Element::SourceBlock(block) => {
-                    if block.language.is_empty() {
-                        write!(w, "<pre class=\"example\">{}</pre>", block.contents)?;
-                    } else {
-                        write!(
-                        w,
-                        "<div class=\"org-src-container\"><pre class=\"src src-{}\">{}</pre></div>",
-                        block.language,
-                        self.highlight(Some(&block.language), &block.contents)
-                    )?
+                    let contents = block_text(element).unwrap_or_default();
+                    let language = Some(&*block.language).filter(|language| !language.is_empty());
+                    match (language, self.highlight(language, &contents)) {
+                        (None, Some(html)) => write!(w, "<pre class=\"example\">{}</pre>", html)?,
+                        (Some(language), Some(html)) => write!(
+                            w,
+                            "<div class=\"org-src-container\"><pre class=\"src src-{}\">{}</pre></div>",
+                            Escape(language),
+                            html
+                        )?,
+                        (_, None) => self.inner.start(w, element)?,
+                    }
+                }
+                Element::FixedWidth { .. } | Element::ExampleBlock(_) => {
+                    match self.highlight(None, &block_text(element).unwrap_or_default()) {
+                        Some(html) => write!(w, "<pre class=\"example\">{}</pre>", html)?,
+                        None => self.inner.start(w, element)?,
                     }
                 }
-                Element::FixedWidth { value } => write!(
-                    w,
-                    "<pre class=\"example\">{}</pre>",
-                    self.highlight(None, value)
-                )?,
-                Element::ExampleBlock(block) => write!(
-                    w,
-                    "<pre class=\"example\">{}</pre>",
-                    self.highlight(None, &block.contents)
-                )?,
                 _ => self.inner.start(w, element)?,
             }
             Ok(())
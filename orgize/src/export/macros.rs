@@ -0,0 +1,365 @@
+use std::collections::HashMap;
+
+use indextree::NodeId;
+
+use crate::elements::{Datetime, Element, Macros, Timestamp};
+#[cfg(feature = "export-html")]
+use crate::export::write_datetime;
+use crate::parsers::{parse_container, Container, OwnedArena};
+use crate::{Org, OrgizeError, SettingsResolver};
+
+/// Depth of nested expansions above which a macro is considered recursive
+const MAX_DEPTH: usize = 16;
+
+/// Expands the macros of a document, see `Org::expand_macros`
+struct MacroExpander<'s> {
+    settings: &'s SettingsResolver<'s, 's>,
+    /// Templates of `#+MACRO:` keywords, by lowercase name
+    templates: HashMap<String, &'s str>,
+}
+
+impl<'s> MacroExpander<'s> {
+    fn new(settings: &'s SettingsResolver<'s, 's>) -> MacroExpander<'s> {
+        // later definitions replace earlier ones
+        let templates = settings
+            .keywords("MACRO")
+            .filter_map(|value| {
+                let value = value.trim();
+                let (name, template) = value.split_at(value.find(char::is_whitespace)?);
+                Some((name.to_lowercase(), template.trim()))
+            })
+            .collect();
+
+        MacroExpander {
+            settings,
+            templates,
+        }
+    }
+
+    /// Returns the expansion of `macros`, or `None` if it's undefined or
+    /// recursive
+    fn expand(&self, macros: &Macros, depth: usize) -> Option<String> {
+        if depth > MAX_DEPTH {
+            return None;
+        }
+
+        let arguments = arguments(macros);
+        let name = macros.name.to_lowercase();
+        let expansion = match (self.templates.get(&name), &*name) {
+            // templates evaluating lisp can't be expanded
+            (Some(template), _) if template.starts_with("(eval") => return None,
+            (Some(template), _) => substitute(template, &arguments),
+            (None, "title") | (None, "author") | (None, "email") => {
+                self.keyword_value(&name.to_uppercase())
+            }
+            (None, "keyword") => self.keyword_value(arguments.first()?.trim()),
+            (None, "date") => {
+                let format = arguments
+                    .first()
+                    .map(|format| format.trim())
+                    .filter(|format| !format.is_empty());
+                self.settings
+                    .keywords("DATE")
+                    .next()
+                    .map(|date| format_date(date, format))
+                    .unwrap_or_default()
+            }
+            _ => return None,
+        };
+
+        self.expand_text(&expansion, depth + 1)
+    }
+
+    /// Expands the macros of `text`, keeping `modification-time` macros
+    /// which are expanded by the html export
+    fn expand_text(&self, text: &str, depth: usize) -> Option<String> {
+        let mut expanded = String::with_capacity(text.len());
+        let mut text = text;
+
+        while let Some(i) = text.find("{{{") {
+            expanded.push_str(&text[0..i]);
+            match Macros::parse(&text[i..]) {
+                Some((tail, macros)) if macros.name.eq_ignore_ascii_case("modification-time") => {
+                    expanded.push_str(&text[i..text.len() - tail.len()]);
+                    text = tail;
+                }
+                Some((tail, macros)) => {
+                    expanded.push_str(&self.expand(&macros, depth)?);
+                    text = tail;
+                }
+                None => {
+                    expanded.push_str("{{{");
+                    text = &text[i + 3..];
+                }
+            }
+        }
+        expanded.push_str(text);
+
+        Some(expanded)
+    }
+
+    /// Returns the values of the keywords `key`, separated by spaces
+    fn keyword_value(&self, key: &str) -> String {
+        self.settings
+            .keywords(key)
+            .map(str::trim)
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// Replaces `$1` to `$n` in `template` with `arguments`, missing arguments
+/// with empty strings
+fn substitute(template: &str, arguments: &[String]) -> String {
+    let mut output = String::with_capacity(template.len());
+    let mut chars = template.char_indices();
+
+    while let Some((i, c)) = chars.next() {
+        if c != '$' {
+            output.push(c);
+            continue;
+        }
+        let digits = template[i + 1..]
+            .bytes()
+            .take_while(u8::is_ascii_digit)
+            .count();
+        match template[i + 1..i + 1 + digits].parse::<usize>() {
+            Ok(n) if n > 0 => {
+                if let Some(argument) = arguments.get(n - 1) {
+                    output.push_str(argument);
+                }
+                for _ in 0..digits {
+                    chars.next();
+                }
+            }
+            _ => output.push(c),
+        }
+    }
+
+    output
+}
+
+/// Replaces the macros of `org` with their expansion, see
+/// `Org::expand_macros` and `Org::try_expand_macros`
+pub(crate) fn expand_macros(org: &mut Org<'_>, strict: bool) -> Result<(), OrgizeError> {
+    // expansions are parsed like the rest of the document
+    let config = org.config.clone();
+    let expansions = {
+        let settings = SettingsResolver::new(org, &config);
+        let expander = MacroExpander::new(&settings);
+        let mut expansions: Vec<(NodeId, String)> = Vec::new();
+        for node in org.root.descendants(&org.arena) {
+            let macros = match org.arena[node].get() {
+                Element::Macros(macros) => macros,
+                _ => continue,
+            };
+            if macros.name.eq_ignore_ascii_case("modification-time") {
+                continue;
+            }
+            match expander.expand(macros, 0) {
+                Some(expansion) => expansions.push((node, expansion)),
+                None if strict => return Err(OrgizeError::UndefinedMacro { at: node }),
+                None => expansions.push((node, String::new())),
+            }
+        }
+        expansions
+    };
+
+    for (node, expansion) in expansions {
+        org.forget_source(node);
+
+        // objects are parsed into a detached paragraph, then moved in place of the macro
+        let paragraph = org.arena.new_node(Element::Paragraph);
+        let timestamps = parse_container(
+            &mut OwnedArena::new(&mut org.arena),
+            Container::Inline {
+                content: &expansion,
+                node: paragraph,
+            },
+            &config,
+        );
+        let children: Vec<_> = paragraph.children(&org.arena).collect();
+        for child in children {
+            child.detach(&mut org.arena);
+            node.insert_before(child, &mut org.arena);
+        }
+        paragraph.remove(&mut org.arena);
+        let parent = org.arena[node].parent();
+        node.remove(&mut org.arena);
+        org.index_timestamps(timestamps);
+
+        if let Some(parent) = parent {
+            remove_empty_containers(org, parent);
+        }
+    }
+
+    org.debug_validate();
+
+    Ok(())
+}
+
+/// Removes `node` and its ancestors if a macro expanding to nothing left them
+/// without the children they need, like an empty paragraph or section
+fn remove_empty_containers(org: &mut Org<'_>, mut node: NodeId) {
+    while org.arena[node].first_child().is_none() {
+        match org.arena[node].get_mut() {
+            Element::Paragraph
+            | Element::Section
+            | Element::ListItemTag
+            | Element::Bold
+            | Element::Italic
+            | Element::Underline
+            | Element::Strike
+            | Element::Subscript { .. }
+            | Element::Superscript { .. } => (),
+            // a title without objects is empty
+            Element::Title(title) => {
+                title.raw = "".into();
+                return;
+            }
+            _ => return,
+        }
+        let parent = org.arena[node].parent();
+        node.remove(&mut org.arena);
+        match parent {
+            Some(parent) => node = parent,
+            None => return,
+        }
+    }
+}
+
+/// Returns the expansion of the `date` and `modification-time` macros, or
+/// `None` for other macros
+///
+/// `date` is the value of `#+DATE:`, and `{{{date(FORMAT)}}}` formats it if
+/// it's a timestamp. `modification_time` is supplied by the caller, since
+/// documents don't know their files.
+#[cfg(feature = "export-html")]
+pub(crate) fn expand_date_macro(
+    macros: &Macros,
+    date: Option<&str>,
+    modification_time: Option<&Datetime>,
+) -> Option<String> {
+    let arguments = arguments(macros);
+    let format = arguments
+        .first()
+        .map(|format| format.trim())
+        .filter(|format| !format.is_empty());
+
+    if macros.name.eq_ignore_ascii_case("date") {
+        Some(
+            date.map(|date| format_date(date, format))
+                .unwrap_or_default(),
+        )
+    } else if macros.name.eq_ignore_ascii_case("modification-time") {
+        Some(
+            modification_time
+                .map(|time| {
+                    format
+                        .and_then(|format| format_datetime(time, format))
+                        .unwrap_or_else(|| plain_datetime(time))
+                })
+                .unwrap_or_default(),
+        )
+    } else {
+        None
+    }
+}
+
+/// Formats `date` with `format` if it's a single timestamp, returns it as
+/// is otherwise
+pub(crate) fn format_date(date: &str, format: Option<&str>) -> String {
+    let date = date.trim();
+
+    format
+        .and_then(|format| {
+            let datetime = parse_timestamp(date)?;
+            format_datetime(&datetime, format)
+        })
+        .unwrap_or_else(|| date.to_string())
+}
+
+/// Returns the start of the timestamp `value`, if `value` is a timestamp
+fn parse_timestamp(value: &str) -> Option<Datetime<'_>> {
+    let (rest, timestamp) =
+        Timestamp::parse_active(value).or_else(|| Timestamp::parse_inactive(value))?;
+
+    if !rest.trim().is_empty() {
+        return None;
+    }
+
+    match timestamp {
+        Timestamp::Active { start, .. } | Timestamp::Inactive { start, .. } => Some(start),
+        _ => None,
+    }
+}
+
+/// Splits the arguments of a macro at unescaped commas
+fn arguments(macros: &Macros) -> Vec<String> {
+    let mut arguments = Vec::new();
+    let mut argument = String::new();
+
+    if let Some(value) = &macros.arguments {
+        let mut chars = value.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' if chars.peek() == Some(&',') => {
+                    argument.push(',');
+                    chars.next();
+                }
+                ',' => arguments.push(std::mem::take(&mut argument)),
+                _ => argument.push(c),
+            }
+        }
+        arguments.push(argument);
+    }
+
+    arguments
+}
+
+/// Formats `datetime` with the `strftime` specifiers of `format`, e.g.
+/// `%Y-%m-%d`
+///
+/// Returns `None` if `format` is invalid, or without the `chrono` feature.
+#[cfg(feature = "chrono")]
+pub(crate) fn format_datetime(datetime: &Datetime, format: &str) -> Option<String> {
+    use chrono::{NaiveDate, NaiveTime};
+    use std::fmt::Write;
+
+    let date = NaiveDate::from_ymd_opt(
+        datetime.year.into(),
+        datetime.month.into(),
+        datetime.day.into(),
+    )?;
+    let time = NaiveTime::from_hms_opt(
+        datetime.hour.unwrap_or_default().into(),
+        datetime.minute.unwrap_or_default().into(),
+        0,
+    )?;
+
+    let mut output = String::new();
+    write!(output, "{}", date.and_time(time).format(format)).ok()?;
+    Some(output)
+}
+
+#[cfg(not(feature = "chrono"))]
+pub(crate) fn format_datetime(_datetime: &Datetime, _format: &str) -> Option<String> {
+    None
+}
+
+/// Writes `datetime` like a timestamp without brackets, e.g. `2020-01-02 Thu`
+#[cfg(feature = "export-html")]
+fn plain_datetime(datetime: &Datetime) -> String {
+    let mut output = Vec::new();
+    // writing into a vector can't fail
+    let _ = write_datetime(&mut output, "", datetime, "");
+    String::from_utf8(output).unwrap_or_default()
+}
+
+#[test]
+fn substitute_() {
+    let arguments = ["a".to_string(), "b".to_string()];
+    assert_eq!(substitute("$2-$1$3", &arguments), "b-a");
+    assert_eq!(substitute("$0 $ $$1 $10", &arguments), "$0 $ $a ");
+    assert_eq!(substitute("€$1€", &arguments), "€a€");
+}
@@ -0,0 +1,630 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{Error, Write};
+
+use indextree::NodeId;
+
+use crate::elements::{Checkbox, ContentMode, Element, LatexKind, ListItem, SpecialBlock};
+use crate::export::{
+    block_text, citation_placeholder, raw_export, write_datetime, ExportHandler, ADMONITIONS,
+};
+use crate::org::Org;
+
+/// Escapes the characters which start markdown syntax with a backslash
+struct Escape<S: AsRef<str>>(S);
+
+impl<S: AsRef<str>> fmt::Display for Escape<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = self.0.as_ref();
+        let mut pos = 0;
+
+        for (i, c) in s.char_indices() {
+            if "\\`*_[]<>#|~".contains(c) {
+                write!(f, "{}\\{}", &s[pos..i], c)?;
+                pos = i + 1;
+            }
+        }
+
+        write!(f, "{}", &s[pos..])
+    }
+}
+
+/// Writes `code` in a code span, delimited by more backticks than it contains
+fn write_code_span<W: Write>(mut w: W, code: &str) -> Result<(), Error> {
+    let fence = "`".repeat(longest_backtick_run(code) + 1);
+    if code.starts_with('`') || code.ends_with('`') {
+        write!(w, "{} {} {}", fence, code, fence)
+    } else {
+        write!(w, "{}{}{}", fence, code, fence)
+    }
+}
+
+/// Writes `contents` in a fenced code block, with the info string `language`
+fn write_code_block<W: Write>(mut w: W, language: &str, contents: &str) -> Result<(), Error> {
+    let fence = "`".repeat(longest_backtick_run(contents).max(2) + 1);
+    writeln!(w, "{}{}", fence, language)?;
+    write!(w, "{}", contents)?;
+    if !contents.is_empty() && !contents.ends_with('\n') {
+        writeln!(w)?;
+    }
+    write!(w, "{}\n\n", fence)
+}
+
+fn longest_backtick_run(s: &str) -> usize {
+    s.split(|c| c != '`').map(str::len).max().unwrap_or(0)
+}
+
+/// Returns the markdown bullet of a list item, keeping the number and the
+/// delimiter of ordered items, e.g. `3) ` for `3)`
+///
+/// The markdown export writes the items of ordered lists with explicit
+/// numbers, see [`markdown_item_number`].
+///
+/// [`markdown_item_number`]: fn.markdown_item_number.html
+pub fn markdown_bullet(item: &ListItem) -> String {
+    let bullet = item.bullet.trim();
+    match bullet.strip_suffix(|c| c == '.' || c == ')') {
+        Some(number) if !number.is_empty() && number.chars().all(|c| c.is_ascii_digit()) => {
+            format!("{} ", bullet)
+        }
+        Some(_) => "1. ".to_string(),
+        None => "- ".to_string(),
+    }
+}
+
+/// Returns the number and the delimiter of an item of an ordered list,
+/// following an item numbered `previous`
+///
+/// Items are numbered like org-mode does: the first one keeps its number,
+/// the others follow the previous one, unless they have a counter, like
+/// `[@10]`. Since markdown only keeps the number of the first item of a
+/// list, the delimiter switches between `.` and `)` at each jump of the
+/// counter, which starts a new list.
+///
+/// ```rust
+/// use orgize::{export::markdown_item_number, elements::ListItem};
+///
+/// let item = |bullet: &str, counter| ListItem {
+///     bullet: bullet.to_string().into(),
+///     indent: 0,
+///     checkbox: None,
+///     counter,
+///     tag: None,
+/// };
+/// assert_eq!(markdown_item_number(&item("3)", None), None), (3, '.'));
+/// assert_eq!(markdown_item_number(&item("1.", None), Some((3, '.'))), (4, '.'));
+/// assert_eq!(markdown_item_number(&item("2.", Some(10)), Some((4, '.'))), (10, ')'));
+/// assert_eq!(markdown_item_number(&item("3.", Some(11)), Some((10, ')'))), (11, ')'));
+/// ```
+pub fn markdown_item_number(item: &ListItem, previous: Option<(usize, char)>) -> (usize, char) {
+    match (item.counter, previous) {
+        (Some(counter), Some((number, delimiter))) if counter != number + 1 => {
+            (counter, if delimiter == '.' { ')' } else { '.' })
+        }
+        (Some(counter), previous) => (counter, previous.map(|(_, d)| d).unwrap_or('.')),
+        (None, Some((number, delimiter))) => (number + 1, delimiter),
+        (None, None) => {
+            let number = item
+                .bullet
+                .trim()
+                .trim_end_matches(&['.', ')'][..])
+                .parse()
+                .unwrap_or(1);
+            (number, '.')
+        }
+    }
+}
+
+/// Returns the markdown task list marker of a checkbox, partial checkboxes
+/// being unchecked
+fn task_marker(checkbox: Checkbox) -> &'static str {
+    match checkbox {
+        Checkbox::Checked => "[x]",
+        Checkbox::Unchecked | Checkbox::Partial => "[ ]",
+    }
+}
+
+/// Returns `true` if a link to `path` is written as an autolink, like
+/// `<https://orgmode.org>`, when it has no description
+fn is_autolink(path: &str) -> bool {
+    (path.starts_with("http://") || path.starts_with("https://") || path.starts_with("mailto:"))
+        && !path.contains(|c: char| c.is_whitespace() || c == '<' || c == '>')
+}
+
+pub trait MarkdownHandler<E: From<Error>> {
+    /// Returns the type of the GitHub alert, e.g. `WARNING`, if the special
+    /// block `block` is written as an alert, like `> [!WARNING]`, defaults to
+    /// `None`
+    fn admonition(&self, _block: &SpecialBlock) -> Option<String> {
+        None
+    }
+
+    /// Returns the prefix of the lines inside `element`, like `> ` for quote
+    /// blocks and the indentation of list items
+    ///
+    /// It's pushed after the start of `element` is written, so it doesn't
+    /// apply to the rest of the line holding a bullet, and popped before its
+    /// end. Blank lines get the prefix without trailing whitespace.
+    fn line_prefix(&self, element: &Element) -> Option<String> {
+        match element {
+            Element::QuoteBlock(_) => Some("> ".to_string()),
+            Element::SpecialBlock(block) if self.admonition(block).is_some() => {
+                Some("> ".to_string())
+            }
+            // items of description lists are written as paragraphs
+            Element::ListItem(item) if item.tag.is_none() => {
+                Some(" ".repeat(markdown_bullet(item).len()))
+            }
+            _ => None,
+        }
+    }
+
+    /// Writes the delimiter row of a table with `columns` columns, after its
+    /// first row, which is its header
+    fn table_rule<W: Write>(&mut self, mut w: W, columns: usize) -> Result<(), E> {
+        write!(w, "|")?;
+        for _ in 0..columns {
+            write!(w, " --- |")?;
+        }
+        writeln!(w)?;
+        Ok(())
+    }
+
+    fn start<W: Write>(&mut self, mut w: W, element: &Element) -> Result<(), E> {
+        use Element::*;
+
+        match element {
+            // container elements
+            SpecialBlock(block) => {
+                if let Some(admonition) = self.admonition(block) {
+                    writeln!(w, "> [!{}]", admonition)?;
+                }
+                if let (ContentMode::Raw, Some(contents)) = (block.mode, &block.contents) {
+                    write_code_block(&mut w, "", contents)?;
+                }
+            }
+            QuoteBlock(_) => (),
+            CenterBlock(_) => (),
+            VerseBlock(_) => (),
+            Bold => write!(w, "**")?,
+            Caption(_) => write!(w, "*")?,
+            Document => (),
+            DynBlock(_dyn_block) => (),
+            Headline { .. } => (),
+            List(_list) => (),
+            Italic => write!(w, "*")?,
+            Link(link) => {
+                if link.desc.is_some() {
+                    write!(w, "[")?;
+                } else if is_autolink(&link.path) {
+                    write!(w, "<{}>", link.path)?;
+                } else {
+                    write!(w, "[{}", Escape(&link.path))?;
+                }
+            }
+            ListItem(item) if item.tag.is_some() => (),
+            ListItem(item) => {
+                write!(w, "{}", markdown_bullet(item))?;
+                if let Some(checkbox) = item.checkbox {
+                    write!(w, "{} ", task_marker(checkbox))?;
+                }
+            }
+            // markdown has no description lists, terms are bold paragraphs
+            ListItemTag => write!(w, "**")?,
+            Paragraph => (),
+            Section => (),
+            Strike => write!(w, "~~")?,
+            Underline => (),
+            // markdown has no syntax for them, html tags are allowed
+            Subscript { .. } => write!(w, "<sub>")?,
+            Superscript { .. } => write!(w, "<sup>")?,
+            // non-container elements
+            CommentBlock(_) => (),
+            ExampleBlock(_) | FixedWidth { .. } => {
+                write_code_block(&mut w, "", &block_text(element).unwrap_or_default())?
+            }
+            ExportBlock(_) | Snippet(_) | Keyword(_) => {
+                if let Some(value) = raw_export(element, "markdown") {
+                    write!(w, "{}", value)?;
+                    if let ExportBlock(_) = element {
+                        writeln!(w)?;
+                    }
+                }
+            }
+            SourceBlock(block) => write_code_block(
+                &mut w,
+                &block.language,
+                &block_text(element).unwrap_or_default(),
+            )?,
+            BabelCall(_) => (),
+            InlineSrc(inline_src) => write_code_span(&mut w, &inline_src.body)?,
+            Code { value } | Verbatim { value } => write_code_span(&mut w, value)?,
+            Entity(entity) => write!(w, "{}", Escape(entity.utf8))?,
+            LatexFragment(fragment) => match fragment.kind {
+                LatexKind::Environment => write!(w, "$$\n{}\n$$\n\n", fragment.value)?,
+                kind if kind.is_display() => write!(w, "$${}$$", fragment.value.trim())?,
+                _ => write!(w, "${}$", fragment.value.trim())?,
+            },
+            FnRef(fn_ref) => match &fn_ref.definition {
+                // inline definitions are written in parentheses
+                Some(definition) => write!(w, " ({})", Escape(definition))?,
+                None => write!(w, "[^{}]", fn_ref.label)?,
+            },
+            Citation(citation) => write!(w, "{}", Escape(citation_placeholder(citation)))?,
+            InlineCall(_) => (),
+            Macros(_macros) => (),
+            RadioTarget(radio_target) => write!(w, "{}", Escape(&radio_target.target))?,
+            Target(_target) => (),
+            Text { value } => write!(w, "{}", Escape(value))?,
+            Timestamp(timestamp) => {
+                use crate::elements::Timestamp;
+
+                match timestamp {
+                    Timestamp::Active { start, .. } => {
+                        write_datetime(&mut w, "\\<", start, ">")?;
+                    }
+                    Timestamp::Inactive { start, .. } => {
+                        write_datetime(&mut w, "\\[", start, "\\]")?;
+                    }
+                    Timestamp::ActiveRange { start, end, .. } => {
+                        write_datetime(&mut w, "\\<", start, ">--")?;
+                        write_datetime(&mut w, "\\<", end, ">")?;
+                    }
+                    Timestamp::InactiveRange { start, end, .. } => {
+                        write_datetime(&mut w, "\\[", start, "\\]--")?;
+                        write_datetime(&mut w, "\\[", end, "\\]")?;
+                    }
+                    Timestamp::Diary { value } => write!(w, "\\<%%({})>", Escape(value))?,
+                }
+            }
+            FnDef(fn_def) => write!(w, "[^{}]: ", fn_def.label)?,
+            Clock(_clock) => (),
+            Comment { .. } => (),
+            Raw { value } => write!(w, "{}\n\n", Escape(value))?,
+            // skipped, see `Org::export_diagnostics`
+            RawHtml(_) => (),
+            Drawer(_drawer) => (),
+            Rule => write!(w, "---\n\n")?,
+            LineBreak => write!(w, "\\")?,
+            // markdown has no statistics cookies, `[3/7]` is written `(3/7)`
+            Cookie(cookie) => write!(
+                w,
+                "({})",
+                cookie.value.trim_start_matches('[').trim_end_matches(']')
+            )?,
+            Title(title) => write!(w, "{} ", "#".repeat(title.level.min(6)))?,
+            Table(crate::elements::Table::Org { .. }) => (),
+            Table(crate::elements::Table::TableEl { value }) => {
+                write_code_block(&mut w, "", value)?
+            }
+            // markdown tables have a single rule, after their first row
+            TableRow(row) if row.is_rule() => (),
+            TableRow(_) => write!(w, "|")?,
+            TableCell(_) => write!(w, " ")?,
+        }
+
+        Ok(())
+    }
+
+    fn end<W: Write>(&mut self, mut w: W, element: &Element) -> Result<(), E> {
+        use Element::*;
+
+        match element {
+            // container elements
+            SpecialBlock(_) => (),
+            QuoteBlock(_) => (),
+            CenterBlock(_) => (),
+            VerseBlock(_) => (),
+            Bold => write!(w, "**")?,
+            Caption(_) => write!(w, "*\n\n")?,
+            Document => (),
+            DynBlock(_dyn_block) => (),
+            Headline { .. } => (),
+            List(_list) => (),
+            Italic => write!(w, "*")?,
+            Link(link) => {
+                if link.desc.is_some() || !is_autolink(&link.path) {
+                    if link
+                        .path
+                        .contains(|c: char| c.is_whitespace() || c == '(' || c == ')')
+                    {
+                        write!(w, "](<{}>)", link.path)?;
+                    } else {
+                        write!(w, "]({})", link.path)?;
+                    }
+                }
+            }
+            ListItem(_) => (),
+            ListItemTag => write!(w, "**\n\n")?,
+            Paragraph => write!(w, "\n\n")?,
+            Section => (),
+            Strike => write!(w, "~~")?,
+            Underline => (),
+            Subscript { .. } => write!(w, "</sub>")?,
+            Superscript { .. } => write!(w, "</sup>")?,
+            Title(_) => write!(w, "\n\n")?,
+            Drawer(_) => (),
+            FnDef(_) => (),
+            Table(crate::elements::Table::Org { .. }) => writeln!(w)?,
+            Table(crate::elements::Table::TableEl { .. }) => (),
+            TableRow(row) if row.is_rule() => (),
+            TableRow(_) => writeln!(w)?,
+            TableCell(_) => write!(w, " |")?,
+            // non-container elements
+            _ => debug_assert!(!element.is_container()),
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct DefaultMarkdownHandler {
+    /// Writes `note`, `tip`, `important`, `warning` and `caution` blocks as
+    /// plain text instead of GitHub alerts
+    pub plain_admonitions: bool,
+}
+
+impl MarkdownHandler<Error> for DefaultMarkdownHandler {
+    fn admonition(&self, block: &SpecialBlock) -> Option<String> {
+        if self.plain_admonitions
+            || !ADMONITIONS
+                .iter()
+                .any(|name| name.eq_ignore_ascii_case(&block.name))
+        {
+            None
+        } else {
+            Some(block.name.to_uppercase())
+        }
+    }
+}
+
+/// Lets a borrowed handler be used by a [`MarkdownBackend`]
+///
+/// [`MarkdownBackend`]: struct.MarkdownBackend.html
+impl<E: From<Error>, H: MarkdownHandler<E>> MarkdownHandler<E> for &mut H {
+    fn admonition(&self, block: &SpecialBlock) -> Option<String> {
+        (**self).admonition(block)
+    }
+
+    fn line_prefix(&self, element: &Element) -> Option<String> {
+        (**self).line_prefix(element)
+    }
+
+    fn table_rule<W: Write>(&mut self, w: W, columns: usize) -> Result<(), E> {
+        (**self).table_rule(w, columns)
+    }
+
+    fn start<W: Write>(&mut self, w: W, element: &Element) -> Result<(), E> {
+        (**self).start(w, element)
+    }
+
+    fn end<W: Write>(&mut self, w: W, element: &Element) -> Result<(), E> {
+        (**self).end(w, element)
+    }
+}
+
+/// Exports with a `MarkdownHandler`
+///
+/// Unlike the other backends it borrows the document, which the markdown
+/// export needs to number the items of ordered lists, to separate the items
+/// of lists and to write the rule after the first row of tables.
+///
+/// ```rust
+/// use orgize::export::{DefaultMarkdownHandler, ExportOptions, MarkdownBackend};
+/// use orgize::Org;
+///
+/// let org = Org::parse("* Notes\n* Old :ARCHIVE:\ntext\n* Draft :noexport:\n");
+///
+/// let mut writer = Vec::new();
+/// let mut backend = MarkdownBackend::new(&org, DefaultMarkdownHandler::default());
+/// let options = ExportOptions {
+///     skip_archived: true,
+///     ..Default::default()
+/// };
+/// org.export_with_handler(&mut writer, &mut backend, &options)
+///     .unwrap();
+/// assert_eq!(String::from_utf8(writer).unwrap(), "# Notes\n\n# Old\n");
+/// ```
+pub struct MarkdownBackend<'b, 'a, H> {
+    org: &'b Org<'a>,
+    handler: H,
+    /// Node of the element being written, see `ExportHandler::set_node`
+    node: Option<NodeId>,
+    lines: MarkdownLines,
+    /// Number and delimiter of the last item of each ordered list
+    numbers: HashMap<NodeId, (usize, char)>,
+}
+
+impl<'b, 'a, H> MarkdownBackend<'b, 'a, H> {
+    pub fn new(org: &'b Org<'a>, handler: H) -> Self {
+        MarkdownBackend {
+            org,
+            handler,
+            node: None,
+            lines: MarkdownLines::default(),
+            numbers: HashMap::new(),
+        }
+    }
+}
+
+impl<E: From<Error>, H: MarkdownHandler<E>> ExportHandler<E> for MarkdownBackend<'_, '_, H> {
+    fn backend(&self) -> &str {
+        "markdown"
+    }
+
+    fn set_node(&mut self, node: NodeId) {
+        self.node = Some(node);
+    }
+
+    fn start<W: Write>(&mut self, w: W, element: &Element) -> Result<(), E> {
+        let mut w = MarkdownWriter::new(w, &mut self.lines);
+        let numbered = match self.node {
+            Some(node) => self.org.numbered_item(node, &mut self.numbers, true),
+            None => None,
+        };
+        let element = numbered.as_ref().unwrap_or(element);
+
+        self.handler.start(&mut w, element)?;
+        if let Some(prefix) = self.handler.line_prefix(element) {
+            w.push_prefix(prefix)?;
+        }
+        if let Element::Paragraph = element {
+            w.set_paragraph(true);
+        }
+        Ok(())
+    }
+
+    fn end<W: Write>(&mut self, w: W, element: &Element) -> Result<(), E> {
+        let mut w = MarkdownWriter::new(w, &mut self.lines);
+        let org = self.org;
+        let numbered = match self.node {
+            Some(node) => org.numbered_item(node, &mut self.numbers, false),
+            None => None,
+        };
+        let element = numbered.as_ref().unwrap_or(element);
+
+        if self.handler.line_prefix(element).is_some() {
+            w.pop_prefix();
+        }
+        if let Element::Paragraph = element {
+            w.set_paragraph(false);
+        }
+        // items of description lists are written as paragraphs, separated
+        // by blank lines, and ordered lists starting with another number
+        // than 1 can't interrupt paragraphs
+        let tight = match self
+            .node
+            .and_then(|node| org.paragraph_list(node).map(|list| (node, list)))
+            .map(|(node, list)| (node, org.arena[list].get()))
+        {
+            Some((node, Element::List(list))) => {
+                list.tight && !list.description && !org.precedes_numbered_list(node)
+            }
+            _ => false,
+        };
+        if tight {
+            let mut buffer = Vec::new();
+            self.handler.end(&mut buffer, element)?;
+            if buffer.ends_with(b"\n\n") {
+                buffer.pop();
+            }
+            w.write_all(&buffer)?;
+        } else {
+            self.handler.end(&mut w, element)?;
+        }
+        if let Some(columns) = self.node.and_then(|node| org.table_header_columns(node)) {
+            self.handler.table_rule(&mut w, columns)?;
+        }
+        Ok(())
+    }
+}
+
+/// State of the lines written by [`MarkdownWriter`]s, kept between the
+/// writes of the elements of a document
+///
+/// [`MarkdownWriter`]: struct.MarkdownWriter.html
+pub(crate) struct MarkdownLines {
+    prefixes: Vec<String>,
+    line_start: bool,
+    blank_lines: usize,
+    written: bool,
+    paragraph: bool,
+    skip_indentation: bool,
+}
+
+impl Default for MarkdownLines {
+    fn default() -> Self {
+        MarkdownLines {
+            prefixes: Vec::new(),
+            line_start: true,
+            blank_lines: 0,
+            written: false,
+            paragraph: false,
+            skip_indentation: false,
+        }
+    }
+}
+
+/// Writer prefixing lines with the line prefixes of the elements being
+/// written, see [`MarkdownHandler::line_prefix`]
+///
+/// Blank lines are written when the next line starts, so the ones ending
+/// an element get the prefixes of its parent, and the ones ending the
+/// document are dropped. The lines of paragraphs are written without their
+/// indentation, which is meaningful in markdown.
+///
+/// [`MarkdownHandler::line_prefix`]: trait.MarkdownHandler.html#method.line_prefix
+pub(crate) struct MarkdownWriter<'l, W: Write> {
+    inner: W,
+    lines: &'l mut MarkdownLines,
+}
+
+impl<'l, W: Write> MarkdownWriter<'l, W> {
+    pub(crate) fn new(inner: W, lines: &'l mut MarkdownLines) -> Self {
+        MarkdownWriter { inner, lines }
+    }
+
+    pub(crate) fn push_prefix(&mut self, prefix: String) -> std::io::Result<()> {
+        // blank lines before the element get the prefixes of its parent
+        if self.lines.line_start {
+            self.write_blank_lines()?;
+        }
+        self.lines.prefixes.push(prefix);
+        Ok(())
+    }
+
+    pub(crate) fn pop_prefix(&mut self) {
+        self.lines.prefixes.pop();
+    }
+
+    pub(crate) fn set_paragraph(&mut self, paragraph: bool) {
+        self.lines.paragraph = paragraph;
+        self.lines.skip_indentation = paragraph;
+    }
+
+    fn write_blank_lines(&mut self) -> std::io::Result<()> {
+        let prefix = self.lines.prefixes.concat();
+        for _ in 0..self.lines.blank_lines {
+            writeln!(self.inner, "{}", prefix.trim_end())?;
+        }
+        self.lines.blank_lines = 0;
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for MarkdownWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        for mut line in buf.split_inclusive(|&byte| byte == b'\n') {
+            if self.lines.skip_indentation {
+                while let Some((b' ', rest)) | Some((b'\t', rest)) = line.split_first() {
+                    line = rest;
+                }
+                if line.is_empty() {
+                    continue;
+                }
+            }
+            if line == b"\n" && self.lines.line_start {
+                // blank lines at the start of the document are dropped too
+                if self.lines.written {
+                    self.lines.blank_lines += 1;
+                }
+                continue;
+            }
+            if self.lines.line_start {
+                self.write_blank_lines()?;
+                self.inner
+                    .write_all(self.lines.prefixes.concat().as_bytes())?;
+            }
+            self.inner.write_all(line)?;
+            self.lines.line_start = line.ends_with(b"\n");
+            self.lines.written = true;
+            self.lines.skip_indentation = self.lines.paragraph && self.lines.line_start;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
@@ -1,14 +1,168 @@
 //! Export `Org` struct to various formats.
 
+#[cfg(feature = "export-html")]
+mod analysis;
+mod block;
+pub(crate) mod driver;
+#[cfg(feature = "export-html")]
 pub mod html;
+pub(crate) mod macros;
+#[cfg(feature = "export-markdown")]
+pub mod markdown;
 pub mod org;
+#[cfg(feature = "export-html")]
+mod strings;
 
+#[cfg(feature = "export-html")]
+pub use analysis::{
+    slugify, BibliographyRenderer, HtmlExportAnalysis, HtmlExportOptions, ImageResolver,
+    OutlineEntry,
+};
+pub use block::block_text;
+#[cfg(feature = "export-html")]
+pub use driver::HtmlBackend;
+pub use driver::{EventBackend, ExportHandler, ExportOptions, OrgBackend};
+#[cfg(feature = "export-html")]
 pub use html::*;
+#[cfg(feature = "export-markdown")]
+pub use markdown::*;
 pub use org::*;
+#[cfg(feature = "export-html")]
+pub use strings::ExportStrings;
 
 use std::io::{Error, Write};
 
-use crate::elements::Datetime;
+use indextree::{NodeEdge, NodeId};
+
+#[cfg(any(feature = "export-html", feature = "export-markdown"))]
+use crate::elements::Citation;
+use crate::elements::{Datetime, Element, SpecialBlock};
+use crate::org::Org;
+
+/// Returns the contents of an export block, an export snippet or a keyword
+/// like `#+HTML:` if it targets `backend`, e.g. `"html"` or `"latex"`
+///
+/// These contents are meant to be written as is by the matching exporter and
+/// dropped by the others. Affiliated keywords like `#+ATTR_LATEX:` are
+/// metadata of the following element, not contents.
+pub fn raw_export<'a>(element: &'a Element<'_>, backend: &str) -> Option<&'a str> {
+    match element {
+        Element::ExportBlock(block) if block.data.eq_ignore_ascii_case(backend) => {
+            Some(&block.contents)
+        }
+        Element::Snippet(snippet) if snippet.name.eq_ignore_ascii_case(backend) => {
+            Some(&snippet.value)
+        }
+        Element::Keyword(keyword) if keyword.key.eq_ignore_ascii_case(backend) => {
+            Some(&keyword.value)
+        }
+        _ => None,
+    }
+}
+
+/// Returns `true` if `element` is a keyword orgize doesn't know, like
+/// `#+BIND:`, which doesn't target `backend`
+///
+/// Exporters ignore these keywords, only the org writer keeps them.
+pub(crate) fn is_unknown_keyword(element: &Element<'_>, backend: &str) -> bool {
+    match element {
+        Element::Keyword(keyword) => {
+            !keyword.is_known() && !keyword.key.eq_ignore_ascii_case(backend)
+        }
+        _ => false,
+    }
+}
+
+/// How exporters render a special block, e.g. `#+BEGIN_warning`, whose
+/// meaning orgize doesn't know
+///
+/// See [`HtmlHandler::special_block`] and [`ExportHandler::special_block`].
+///
+/// [`HtmlHandler::special_block`]: trait.HtmlHandler.html#method.special_block
+/// [`ExportHandler::special_block`]: trait.ExportHandler.html#method.special_block
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum SpecialBlockPolicy {
+    /// Renders the block and its parsed contents, as a `<div>` whose class is
+    /// the name of the block in html
+    #[default]
+    Render,
+    /// Drops the block and its contents
+    Drop,
+    /// Passes the contents as is, like the contents of an export block
+    /// targeting the backend
+    Raw,
+}
+
+/// Returns the text between the delimiters of the block `node`
+///
+/// Contents modified since parsing are written back with the org exporter.
+pub(crate) fn block_contents(org: &Org<'_>, node: NodeId) -> String {
+    if let Element::SpecialBlock(SpecialBlock {
+        contents: Some(contents),
+        ..
+    }) = org.arena[node].get()
+    {
+        return contents.to_string();
+    }
+
+    let spans: Option<Vec<_>> = node
+        .children(&org.arena)
+        .map(|child| org.span_of(child))
+        .collect();
+
+    if let Some(spans) = spans {
+        return match (spans.first(), spans.last()) {
+            (Some(first), Some(last)) => org.text[first.start..last.end].to_string(),
+            _ => String::new(),
+        };
+    }
+
+    let mut contents = Vec::new();
+    for edge in node.traverse(&org.arena) {
+        // writing into a vector can't fail
+        let _ = match edge {
+            NodeEdge::Start(child) if child != node => {
+                DefaultOrgHandler.start(&mut contents, org.arena[child].get())
+            }
+            NodeEdge::End(child) if child != node => {
+                DefaultOrgHandler.end(&mut contents, org.arena[child].get())
+            }
+            _ => Ok(()),
+        };
+    }
+    String::from_utf8(contents).unwrap_or_default()
+}
+
+/// Names of the special blocks rendered as admonitions by `DefaultHtmlHandler`
+pub const ADMONITIONS: [&str; 5] = ["note", "tip", "important", "warning", "caution"];
+
+/// Returns the keys of a citation with their prefixes and suffixes, in
+/// parentheses
+#[cfg(any(feature = "export-html", feature = "export-markdown"))]
+pub(crate) fn citation_placeholder(citation: &Citation) -> String {
+    let mut parts = Vec::new();
+    parts.extend(
+        citation
+            .prefix
+            .as_ref()
+            .map(|prefix| prefix.trim().to_string()),
+    );
+    parts.extend(citation.references.iter().map(|reference| {
+        format!(
+            "{}{}{}",
+            reference.prefix.as_deref().unwrap_or_default().trim_start(),
+            reference.key,
+            reference.suffix.as_deref().unwrap_or_default().trim_end()
+        )
+    }));
+    parts.extend(
+        citation
+            .suffix
+            .as_ref()
+            .map(|suffix| suffix.trim().to_string()),
+    );
+    format!("({})", parts.join("; "))
+}
 
 pub(crate) fn write_datetime<W: Write>(
     mut w: W,
@@ -19,7 +173,7 @@ pub(crate) fn write_datetime<W: Write>(
     write!(w, "{}", start)?;
     write!(
         w,
-        "{}-{:02}-{:02} {}",
+        "{:04}-{:02}-{:02} {}",
         datetime.year, datetime.month, datetime.day, datetime.dayname
     )?;
     if let (Some(hour), Some(minute)) = (datetime.hour, datetime.minute) {
@@ -1,6 +1,11 @@
 use std::io::{Error, Write};
 
-use crate::elements::{Element, Timestamp};
+use crate::elements::{
+    block::escape_contents,
+    link::escape_brackets,
+    title::{fold_properties, property_entries},
+    Element, LatexKind, LinkFormat, Modifiers, PropertyLine, Timestamp,
+};
 use crate::export::write_datetime;
 
 pub trait OrgHandler<E: From<Error>> {
@@ -9,11 +14,23 @@ pub trait OrgHandler<E: From<Error>> {
 
         match element {
             // container elements
-            SpecialBlock(block) => writeln!(w, "#+BEGIN_{}", block.name)?,
-            QuoteBlock(_) => write!(w, "#+BEGIN_QUOTE")?,
-            CenterBlock(_) => write!(w, "#+BEGIN_CENTER")?,
-            VerseBlock(_) => write!(w, "#+BEGIN_VERSE")?,
+            SpecialBlock(block) => {
+                writeln!(w, "#+BEGIN_{}", block.name)?;
+                if let Some(contents) = &block.contents {
+                    write!(w, "{}", escape_contents(contents))?;
+                }
+            }
+            QuoteBlock(_) => writeln!(w, "#+BEGIN_QUOTE")?,
+            CenterBlock(_) => writeln!(w, "#+BEGIN_CENTER")?,
+            VerseBlock(_) => writeln!(w, "#+BEGIN_VERSE")?,
             Bold => write!(w, "*")?,
+            Caption(caption) => {
+                write!(w, "#+CAPTION")?;
+                if let Some(short) = &caption.short {
+                    write!(w, "[{}]", short)?;
+                }
+                write!(w, ": ")?;
+            }
             Document => (),
             DynBlock(dyn_block) => {
                 write!(&mut w, "#+BEGIN: {}", dyn_block.block_name)?;
@@ -25,30 +42,92 @@ pub trait OrgHandler<E: From<Error>> {
             Headline { .. } => (),
             List(_list) => (),
             Italic => write!(w, "/")?,
-            ListItem(list_item) => write!(w, "{}", list_item.bullet)?,
+            Link(link) => match link.org_format() {
+                LinkFormat::Plain => write!(&mut w, "{}", link.path)?,
+                LinkFormat::Angle => write!(&mut w, "<{}>", link.path)?,
+                LinkFormat::Bracket => {
+                    write!(&mut w, "[[{}]", escape_brackets(&link.path))?;
+                    if link.desc.is_some() {
+                        write!(&mut w, "[")?;
+                    }
+                }
+            },
+            ListItem(list_item) => {
+                for _ in 0..list_item.indent {
+                    write!(&mut w, " ")?;
+                }
+                write!(w, "{}", list_item.bullet)?;
+                let prefixed = list_item.counter.is_some()
+                    || list_item.checkbox.is_some()
+                    || list_item.tag.is_some();
+                if !list_item.bullet.ends_with(' ') {
+                    if prefixed {
+                        write!(w, " ")?;
+                    } else {
+                        // a bullet without trailing space is followed by a newline
+                        writeln!(w)?;
+                    }
+                }
+                if let Some(counter) = list_item.counter {
+                    write!(w, "[@{}] ", counter)?;
+                }
+                if let Some(checkbox) = list_item.checkbox {
+                    write!(w, "{} ", checkbox)?;
+                }
+            }
+            ListItemTag => (),
             Paragraph => (),
             Section => (),
             Strike => write!(w, "+")?,
             Underline => write!(w, "_")?,
-            Drawer(drawer) => writeln!(w, ":{}:", drawer.name)?,
+            Subscript { braces } => write!(w, "{}", if *braces { "_{" } else { "_" })?,
+            Superscript { braces } => write!(w, "{}", if *braces { "^{" } else { "^" })?,
+            Drawer(drawer) => {
+                writeln!(w, ":{}:", drawer.name)?;
+                if let Some(contents) = &drawer.contents {
+                    write!(w, "{}", contents)?;
+                }
+            }
             // non-container elements
             CommentBlock(block) => {
-                writeln!(w, "#+BEGIN_COMMENT\n{}\n#+END_COMMENT", block.contents)?
+                write!(w, "#+BEGIN_COMMENT")?;
+                if let Some(data) = &block.data {
+                    write!(w, " {}", data)?;
+                }
+                writeln!(w)?;
+                write_block_contents(&mut w, &block.contents)?;
+                writeln!(w, "#+END_COMMENT")?;
             }
             ExampleBlock(block) => {
-                writeln!(w, "#+BEGIN_EXAMPLE\n{}\n#+END_EXAMPLE", block.contents)?
-            }
-            ExportBlock(block) => writeln!(
-                w,
-                "#+BEGIN_EXPORT {}\n{}\n#+END_EXPORT",
-                block.data, block.contents
-            )?,
-            SourceBlock(block) => writeln!(
-                w,
-                "#+BEGIN_SRC {}\n{}\n#+END_SRC",
-                block.language, block.contents
-            )?,
-            BabelCall(_babel_call) => (),
+                write!(w, "#+BEGIN_EXAMPLE")?;
+                if let Some(data) = &block.data {
+                    write!(w, " {}", data)?;
+                }
+                writeln!(w)?;
+                write_block_contents(&mut w, &block.contents)?;
+                writeln!(w, "#+END_EXAMPLE")?;
+            }
+            ExportBlock(block) => {
+                writeln!(w, "#+BEGIN_EXPORT {}", block.data)?;
+                write_block_contents(&mut w, &block.contents)?;
+                writeln!(w, "#+END_EXPORT")?;
+            }
+            SourceBlock(block) => {
+                write!(w, "#+BEGIN_SRC {}", block.language)?;
+                if !block.arguments.is_empty() {
+                    write!(w, " {}", block.arguments)?;
+                }
+                writeln!(w)?;
+                write_block_contents(&mut w, &block.contents)?;
+                writeln!(w, "#+END_SRC")?;
+            }
+            BabelCall(babel_call) => {
+                if babel_call.value.is_empty() {
+                    writeln!(w, "#+CALL:")?;
+                } else {
+                    writeln!(w, "#+CALL: {}", babel_call.value)?;
+                }
+            }
             InlineSrc(inline_src) => {
                 write!(&mut w, "src_{}", inline_src.lang)?;
                 if let Some(options) = &inline_src.options {
@@ -64,6 +143,32 @@ pub trait OrgHandler<E: From<Error>> {
                 }
                 write!(&mut w, "]")?;
             }
+            Citation(citation) => {
+                write!(&mut w, "[cite")?;
+                if let Some(style) = &citation.style {
+                    write!(&mut w, "/{}", style)?;
+                }
+                write!(&mut w, ":")?;
+                if let Some(prefix) = &citation.prefix {
+                    write!(&mut w, "{};", prefix)?;
+                }
+                for (i, reference) in citation.references.iter().enumerate() {
+                    if i > 0 {
+                        write!(&mut w, ";")?;
+                    }
+                    if let Some(prefix) = &reference.prefix {
+                        write!(&mut w, "{}", prefix)?;
+                    }
+                    write!(&mut w, "@{}", reference.key)?;
+                    if let Some(suffix) = &reference.suffix {
+                        write!(&mut w, "{}", suffix)?;
+                    }
+                }
+                if let Some(suffix) = &citation.suffix {
+                    write!(&mut w, ";{}", suffix)?;
+                }
+                write!(&mut w, "]")?;
+            }
             InlineCall(inline_call) => {
                 write!(&mut w, "call_{}", inline_call.name)?;
                 if let Some(header) = &inline_call.inside_header {
@@ -74,23 +179,36 @@ pub trait OrgHandler<E: From<Error>> {
                     write!(&mut w, "[{}]", header)?;
                 }
             }
-            Link(link) => {
-                write!(&mut w, "[[{}]", link.path)?;
-                if let Some(desc) = &link.desc {
-                    write!(&mut w, "[{}]", desc)?;
+            Macros(macros) => {
+                write!(w, "{{{{{{{}", macros.name)?;
+                if let Some(arguments) = &macros.arguments {
+                    write!(w, "({})", arguments)?;
                 }
-                write!(&mut w, "]")?;
+                write!(w, "}}}}}}")?;
             }
-            Macros(_macros) => (),
-            RadioTarget => (),
+            RadioTarget(radio_target) => write!(w, "<<<{}>>>", radio_target.target)?,
             Snippet(snippet) => write!(w, "@@{}:{}@@", snippet.name, snippet.value)?,
-            Target(_target) => (),
+            Target(target) => write!(w, "<<{}>>", target.target)?,
             Text { value } => write!(w, "{}", value)?,
             Timestamp(timestamp) => {
                 write_timestamp(&mut w, &timestamp)?;
             }
             Verbatim { value } => write!(w, "={}=", value)?,
-            FnDef(_fn_def) => (),
+            LineBreak => write!(w, "\\\\")?,
+            Entity(entity) => {
+                write!(w, "\\{}", entity.name)?;
+                if entity.braces {
+                    write!(w, "{{}}")?;
+                }
+            }
+            LatexFragment(fragment) => {
+                let (open, close) = fragment.kind.delimiters();
+                write!(w, "{}{}{}", open, fragment.value, close)?;
+                if fragment.kind == LatexKind::Environment {
+                    writeln!(w)?;
+                }
+            }
+            FnDef(fn_def) => write!(w, "[fn:{}]", fn_def.label)?,
             Clock(clock) => {
                 use crate::elements::Clock;
 
@@ -114,15 +232,25 @@ pub trait OrgHandler<E: From<Error>> {
             }
             Comment { value } => write!(w, "{}", value)?,
             FixedWidth { value } => write!(w, "{}", value)?,
+            Raw { value } => writeln!(w, "{}", value)?,
+            RawHtml(raw_html) => {
+                writeln!(w, "#+BEGIN_EXPORT html")?;
+                write_block_contents(&mut w, raw_html.html())?;
+                writeln!(w, "#+END_EXPORT")?;
+            }
             Keyword(keyword) => {
                 write!(&mut w, "#+{}", keyword.key)?;
                 if let Some(optional) = &keyword.optional {
                     write!(&mut w, "[{}]", optional)?;
                 }
-                writeln!(&mut w, ": {}", keyword.value)?;
+                if keyword.value.is_empty() {
+                    writeln!(&mut w, ":")?;
+                } else {
+                    writeln!(&mut w, ": {}", keyword.value)?;
+                }
             }
             Rule => writeln!(w, "-----")?,
-            Cookie(_cookie) => (),
+            Cookie(cookie) => write!(w, "{}", cookie.value)?,
             Title(title) => {
                 for _ in 0..title.level {
                     write!(&mut w, "*")?;
@@ -135,9 +263,21 @@ pub trait OrgHandler<E: From<Error>> {
                 }
                 write!(&mut w, " ")?;
             }
-            Table(_) => (),
-            TableRow(_) => (),
-            TableCell => (),
+            Table(table) => {
+                use crate::elements::Table;
+
+                if let Table::TableEl { value } = table {
+                    write!(w, "{}", value)?;
+                }
+            }
+            TableRow(row) => {
+                if row.is_rule() {
+                    write!(w, "|-")?;
+                } else {
+                    write!(w, "|")?;
+                }
+            }
+            TableCell(_) => write!(w, " ")?,
         }
 
         Ok(())
@@ -153,17 +293,39 @@ pub trait OrgHandler<E: From<Error>> {
             CenterBlock(_) => writeln!(w, "#+END_CENTER")?,
             VerseBlock(_) => writeln!(w, "#+END_VERSE")?,
             Bold => write!(w, "*")?,
+            Caption(_) => writeln!(w)?,
             Document => (),
             DynBlock(_dyn_block) => writeln!(w, "#+END:")?,
             Headline { .. } => (),
-            List(_list) => (),
+            // two blank lines end a list, so adjacent lists don't get merged,
+            // but they also end all outer lists
+            List(list) => {
+                if list.indent == 0 {
+                    writeln!(w)?;
+                }
+            }
             Italic => write!(w, "/")?,
+            Link(link) => {
+                if link.org_format() == LinkFormat::Bracket {
+                    if link.desc.is_some() {
+                        write!(&mut w, "]")?;
+                    }
+                    write!(&mut w, "]")?;
+                }
+            }
             ListItem(_) => (),
+            ListItemTag => write!(w, " :: ")?,
             Paragraph => write!(w, "\n\n")?,
             Section => (),
             Strike => write!(w, "+")?,
             Underline => write!(w, "_")?,
+            Subscript { braces } | Superscript { braces } => {
+                if *braces {
+                    write!(w, "}}")?;
+                }
+            }
             Drawer(_) => writeln!(w, ":END:")?,
+            FnDef(_) => (),
             Title(title) => {
                 if !title.tags.is_empty() {
                     write!(&mut w, " :")?;
@@ -185,7 +347,7 @@ pub trait OrgHandler<E: From<Error>> {
                         write_timestamp(&mut w, &deadline)?;
                     }
                     if let Some(closed) = &planning.closed {
-                        if planning.deadline.is_some() {
+                        if planning.scheduled.is_some() || planning.deadline.is_some() {
                             write!(&mut w, " ")?;
                         }
                         write!(&mut w, "CLOSED: ")?;
@@ -193,17 +355,51 @@ pub trait OrgHandler<E: From<Error>> {
                     }
                     writeln!(&mut w)?;
                 }
-                if !title.properties.is_empty() {
+                if fold_properties(property_entries(&title.property_lines)) == title.properties {
+                    // the lines are written as they were parsed, unless the
+                    // properties changed since
+                    if !title.property_lines.is_empty() {
+                        writeln!(&mut w, ":PROPERTIES:")?;
+                        for line in &title.property_lines {
+                            match line {
+                                PropertyLine::Property { key, value } if value.is_empty() => {
+                                    writeln!(&mut w, ":{}:", key)?
+                                }
+                                PropertyLine::Property { key, value } => {
+                                    writeln!(&mut w, ":{}: {}", key, value)?
+                                }
+                                PropertyLine::Raw(line) => writeln!(&mut w, "{}", line)?,
+                            }
+                        }
+                        writeln!(&mut w, ":END:")?;
+                    }
+                } else {
                     writeln!(&mut w, ":PROPERTIES:")?;
-                    for (key, value) in &title.properties {
+                    // sorted, so that the output doesn't depend on the hash map order
+                    let mut properties: Vec<_> = title.properties.iter().collect();
+                    properties.sort();
+                    for (key, value) in properties {
                         writeln!(&mut w, ":{}: {}", key, value)?;
                     }
                     writeln!(&mut w, ":END:")?;
                 }
             }
-            Table(_) => (),
-            TableRow(_) => (),
-            TableCell => (),
+            Table(table) => {
+                use crate::elements::Table;
+
+                if let Table::Org {
+                    tblfm: Some(tblfm), ..
+                } = table
+                {
+                    for formula in tblfm.lines() {
+                        writeln!(w, "#+TBLFM: {}", formula)?;
+                    }
+                }
+                // a blank line ends a table, so adjacent tables don't get merged
+                writeln!(w)?;
+            }
+            TableRow(_) => writeln!(w)?,
+            TableCell(_) => write!(w, " |")?,
             // non-container elements
             _ => debug_assert!(!element.is_container()),
         }
@@ -212,21 +408,48 @@ pub trait OrgHandler<E: From<Error>> {
     }
 }
 
-fn write_timestamp<W: Write>(mut w: W, timestamp: &Timestamp) -> std::io::Result<()> {
+fn write_block_contents<W: Write>(mut w: W, contents: &str) -> std::io::Result<()> {
+    write!(w, "{}", escape_contents(contents))?;
+    if !contents.is_empty() && !contents.ends_with('\n') {
+        writeln!(w)?;
+    }
+    Ok(())
+}
+
+pub(crate) fn write_timestamp<W: Write>(mut w: W, timestamp: &Timestamp) -> std::io::Result<()> {
+    // each end gets its own repeater and delay back, in their order
+    let suffix = |modifiers: &Modifiers, close: &str| {
+        if modifiers.is_empty() {
+            close.to_string()
+        } else {
+            format!(" {}{}", modifiers, close)
+        }
+    };
+
     match timestamp {
-        Timestamp::Active { start, .. } => {
-            write_datetime(w, "<", start, ">")?;
+        Timestamp::Active { start, modifiers } => {
+            write_datetime(w, "<", start, &suffix(modifiers, ">"))?;
         }
-        Timestamp::Inactive { start, .. } => {
-            write_datetime(w, "[", start, "]")?;
+        Timestamp::Inactive { start, modifiers } => {
+            write_datetime(w, "[", start, &suffix(modifiers, "]"))?;
         }
-        Timestamp::ActiveRange { start, end, .. } => {
-            write_datetime(&mut w, "<", start, ">--")?;
-            write_datetime(&mut w, "<", end, ">")?;
+        Timestamp::ActiveRange {
+            start,
+            end,
+            modifiers,
+            end_modifiers,
+        } => {
+            write_datetime(&mut w, "<", start, &suffix(modifiers, ">--"))?;
+            write_datetime(&mut w, "<", end, &suffix(end_modifiers, ">"))?;
         }
-        Timestamp::InactiveRange { start, end, .. } => {
-            write_datetime(&mut w, "[", start, "]--")?;
-            write_datetime(&mut w, "[", end, "]")?;
+        Timestamp::InactiveRange {
+            start,
+            end,
+            modifiers,
+            end_modifiers,
+        } => {
+            write_datetime(&mut w, "[", start, &suffix(modifiers, "]--"))?;
+            write_datetime(&mut w, "[", end, &suffix(end_modifiers, "]"))?;
         }
         Timestamp::Diary { value } => write!(w, "<%%({})>", value)?,
     }
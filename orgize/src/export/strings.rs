@@ -0,0 +1,184 @@
+/// Strings written by exporters in the output, e.g. `Table 1: ` at the start
+/// of captions or the titles of admonitions
+///
+/// Exporters use the strings of the `#+LANGUAGE:` of the document if they're
+/// built in, see [`ExportStrings::for_language`], and English otherwise,
+/// unless strings are set in their options, e.g. in
+/// [`HtmlExportOptions::strings`].
+///
+/// ```rust
+/// use orgize::Org;
+///
+/// let mut writer = Vec::new();
+/// Org::parse("#+LANGUAGE: de\n#+CAPTION: Daten\n| a |\n")
+///     .html(&mut writer)
+///     .unwrap();
+/// assert!(String::from_utf8(writer)
+///     .unwrap()
+///     .contains("<caption>Tabelle 1: Daten</caption>"));
+/// ```
+///
+/// [`ExportStrings::for_language`]: #method.for_language
+/// [`HtmlExportOptions::strings`]: struct.HtmlExportOptions.html#structfield.strings
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExportStrings {
+    /// Number of a table, where `%d` is replaced with the number, written at
+    /// the start of its caption and as the description of links to it,
+    /// defaults to `"Table %d"`
+    pub table: String,
+    /// Number of a figure, defaults to `"Figure %d"`
+    pub figure: String,
+    /// Number of a listing, defaults to `"Listing %d"`
+    pub listing: String,
+    /// Number of a section, written as the description of links to it,
+    /// defaults to `"Section %d"`, the number of a section looks like `2.1`
+    pub section: String,
+    /// Title of `note` admonitions, defaults to `"Note"`
+    pub note: String,
+    /// Title of `tip` admonitions, defaults to `"Tip"`
+    pub tip: String,
+    /// Title of `important` admonitions, defaults to `"Important"`
+    pub important: String,
+    /// Title of `warning` admonitions, defaults to `"Warning"`
+    pub warning: String,
+    /// Title of `caution` admonitions, defaults to `"Caution"`
+    pub caution: String,
+    /// Title of the footnotes written at the end of the document, defaults
+    /// to `"Footnotes"`
+    pub footnotes: String,
+}
+
+impl Default for ExportStrings {
+    fn default() -> Self {
+        ExportStrings::new([
+            "Table %d",
+            "Figure %d",
+            "Listing %d",
+            "Section %d",
+            "Note",
+            "Tip",
+            "Important",
+            "Warning",
+            "Caution",
+            "Footnotes",
+        ])
+    }
+}
+
+impl ExportStrings {
+    fn new(strings: [&str; 10]) -> ExportStrings {
+        let [table, figure, listing, section, note, tip, important, warning, caution, footnotes] =
+            strings;
+        ExportStrings {
+            table: table.into(),
+            figure: figure.into(),
+            listing: listing.into(),
+            section: section.into(),
+            note: note.into(),
+            tip: tip.into(),
+            important: important.into(),
+            warning: warning.into(),
+            caution: caution.into(),
+            footnotes: footnotes.into(),
+        }
+    }
+
+    /// Returns the built-in strings of a language, given as a code like
+    /// `de` or `zh-CN`, compared by its primary subtag
+    ///
+    /// English (`en`), German (`de`), French (`fr`), Spanish (`es`), Chinese
+    /// (`zh`), Japanese (`ja`) and Russian (`ru`) are built in.
+    ///
+    /// ```rust
+    /// use orgize::export::ExportStrings;
+    ///
+    /// assert_eq!(ExportStrings::for_language("fr_CA").unwrap().table, "Tableau %d");
+    /// assert_eq!(ExportStrings::for_language("en"), Some(ExportStrings::default()));
+    /// assert_eq!(ExportStrings::for_language("eo"), None);
+    /// ```
+    pub fn for_language(language: &str) -> Option<ExportStrings> {
+        let primary = language.trim().split(&['-', '_'][..]).next()?;
+        let strings = match &*primary.to_ascii_lowercase() {
+            "en" => return Some(ExportStrings::default()),
+            "de" => [
+                "Tabelle %d",
+                "Abbildung %d",
+                "Programmlisting %d",
+                "Abschnitt %d",
+                "Hinweis",
+                "Tipp",
+                "Wichtig",
+                "Warnung",
+                "Vorsicht",
+                "Fußnoten",
+            ],
+            "fr" => [
+                "Tableau %d",
+                "Figure %d",
+                "Programme %d",
+                "Section %d",
+                "Remarque",
+                "Astuce",
+                "Important",
+                "Avertissement",
+                "Attention",
+                "Notes de bas de page",
+            ],
+            "es" => [
+                "Tabla %d",
+                "Figura %d",
+                "Listado de programa %d",
+                "Sección %d",
+                "Nota",
+                "Consejo",
+                "Importante",
+                "Advertencia",
+                "Precaución",
+                "Notas al pie de página",
+            ],
+            "zh" => [
+                "表%d", "图%d", "代码%d", "第%d节", "注意", "提示", "重要", "警告", "小心", "脚注",
+            ],
+            "ja" => [
+                "表%d",
+                "図%d",
+                "ソースコード%d",
+                "セクション%d",
+                "注記",
+                "ヒント",
+                "重要",
+                "警告",
+                "注意",
+                "脚注",
+            ],
+            "ru" => [
+                "Таблица %d",
+                "Рисунок %d",
+                "Распечатка %d",
+                "Раздел %d",
+                "Примечание",
+                "Совет",
+                "Важно",
+                "Предупреждение",
+                "Осторожно",
+                "Сноски",
+            ],
+            _ => return None,
+        };
+        Some(ExportStrings::new(strings))
+    }
+
+    /// Returns the title of an admonition by name, compared
+    /// case-insensitively, e.g. `warning`
+    pub fn admonition(&self, name: &str) -> Option<&str> {
+        let title = match &*name.to_ascii_lowercase() {
+            "note" => &self.note,
+            "tip" => &self.tip,
+            "important" => &self.important,
+            "warning" => &self.warning,
+            "caution" => &self.caution,
+            _ => return None,
+        };
+        Some(title)
+    }
+}
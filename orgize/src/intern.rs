@@ -0,0 +1,59 @@
+//! Interner for the strings repeated all over owned documents
+//!
+//! Tags, TODO keywords, property keys, keyword keys, block names and
+//! languages are borrowed from the text in a parsed document, but every
+//! element of an owned document, built by `Org::into_owned`,
+//! `Org::from_markdown` or `parse_files`, would store its own copy of them.
+//! Instead, they're borrowed from a table shared by the whole process.
+//!
+//! Strings in the table are never freed, so the table only takes short
+//! strings and stops growing once it's full, after which the new strings are
+//! copied like before. That's at most `MAX_STRINGS * MAX_LEN` bytes.
+
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::sync::{Mutex, PoisonError};
+
+/// Longest string to intern, in bytes
+const MAX_LEN: usize = 64;
+
+/// Number of strings the table takes
+const MAX_STRINGS: usize = 1 << 16;
+
+lazy_static::lazy_static! {
+    static ref STRINGS: Mutex<HashSet<&'static str>> = Mutex::new(HashSet::new());
+}
+
+/// Returns the interned copy of `s`, or an owned one if it's too long or
+/// the table is full
+pub(crate) fn intern(s: Cow<'_, str>) -> Cow<'static, str> {
+    if s.len() > MAX_LEN {
+        return Cow::Owned(s.into_owned());
+    }
+
+    let mut strings = STRINGS.lock().unwrap_or_else(PoisonError::into_inner);
+
+    if let Some(interned) = strings.get(&*s) {
+        return Cow::Borrowed(interned);
+    }
+
+    if strings.len() >= MAX_STRINGS {
+        return Cow::Owned(s.into_owned());
+    }
+
+    let interned: &'static str = Box::leak(s.into_owned().into_boxed_str());
+    strings.insert(interned);
+    Cow::Borrowed(interned)
+}
+
+#[test]
+fn shared() {
+    let a = intern(Cow::Owned(String::from("work")));
+    let b = intern(Cow::Borrowed("work"));
+
+    assert!(matches!((&a, &b), (Cow::Borrowed(_), Cow::Borrowed(_))));
+    assert_eq!(a.as_ptr(), b.as_ptr());
+
+    let long = "x".repeat(MAX_LEN + 1);
+    assert!(matches!(intern(Cow::Borrowed(&long)), Cow::Owned(_)));
+}
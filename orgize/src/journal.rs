@@ -0,0 +1,234 @@
+//! Appending dated entries to journal documents
+
+use std::borrow::Cow;
+use std::ops::Range;
+
+use crate::elements::title::{format_date, match_date_format};
+use crate::elements::{Datetime, Title};
+use crate::{HeadlineNode, Org, OrgizeError};
+
+/// Headlines of the dates of a journal, see [`append_entry`]
+///
+/// [`append_entry`]: fn.append_entry.html
+#[derive(Clone, Debug, PartialEq)]
+pub struct JournalLayout {
+    /// Formats of the titles of the date headlines, from the top level to the
+    /// level of the entries' parent
+    ///
+    /// They're `strftime`-like patterns like
+    /// `ParseConfig::headline_date_formats`, with `%G` and `%V` for ISO 8601
+    /// weeks, e.g. `["%G", "%G-W%V", "%Y-%m-%d %A"]`. Defaults to the
+    /// headlines of `org-capture` date trees: `["%Y", "%Y-%m %B",
+    /// "%Y-%m-%d %A"]`. Entries are top level headlines if it's empty.
+    pub levels: Vec<String>,
+}
+
+impl Default for JournalLayout {
+    fn default() -> Self {
+        JournalLayout {
+            levels: vec!["%Y".into(), "%Y-%m %B".into(), "%Y-%m-%d %A".into()],
+        }
+    }
+}
+
+/// A date headline which doesn't match the format of its level, see
+/// [`append_entry_with_diagnostics`]
+///
+/// [`append_entry_with_diagnostics`]: fn.append_entry_with_diagnostics.html
+#[derive(Clone, Debug, PartialEq)]
+pub struct JournalDiagnostic {
+    /// Byte range of the headline in the parsed text, `None` if it was
+    /// added or modified after parsing
+    pub span: Option<Range<usize>>,
+    pub message: String,
+}
+
+/// Appends an entry dated `date` to a journal, and returns its headline
+///
+/// The date headlines of `layout` are found, or created in chronological
+/// order among their siblings, which are compared by the dates their titles
+/// match. The entry is appended after the existing entries of its date, with
+/// `title` as title and `body` as section, both parsed as org syntax.
+///
+/// A date headline is appended after its siblings if one of them doesn't
+/// match the format of its level, see [`append_entry_with_diagnostics`].
+/// Combined with [`Org::write_org_minimal`], the rest of the file isn't
+/// rewritten.
+///
+/// ```rust
+/// use orgize::elements::Datetime;
+/// use orgize::journal::{append_entry, JournalLayout};
+/// use orgize::Org;
+///
+/// let text = "* 2024\n** 2024-03 March\n*** 2024-03-09 Saturday\n**** Hike\n";
+/// let mut org = Org::parse(text);
+/// let date = Datetime {
+///     year: 2024,
+///     month: 3,
+///     day: 4,
+///     dayname: "Mon".into(),
+///     hour: None,
+///     minute: None,
+/// };
+///
+/// append_entry(&mut org, &date, "Standup", "Notes.\n", &JournalLayout::default()).unwrap();
+///
+/// let mut writer = Vec::new();
+/// org.write_org_minimal(text, &mut writer).unwrap();
+/// assert_eq!(
+///     String::from_utf8(writer).unwrap(),
+///     "* 2024\n** 2024-03 March\n*** 2024-03-04 Monday\n**** Standup\nNotes.\n\n\
+///      *** 2024-03-09 Saturday\n**** Hike\n"
+/// );
+/// ```
+///
+/// [`append_entry_with_diagnostics`]: fn.append_entry_with_diagnostics.html
+/// [`Org::write_org_minimal`]: ../struct.Org.html#method.write_org_minimal
+pub fn append_entry<'a, T, B>(
+    org: &mut Org<'a>,
+    date: &Datetime<'_>,
+    title: T,
+    body: B,
+    layout: &JournalLayout,
+) -> Result<HeadlineNode, OrgizeError>
+where
+    T: Into<Cow<'a, str>>,
+    B: Into<Cow<'a, str>>,
+{
+    append_entry_with_diagnostics(org, date, title, body, layout).map(|(entry, _)| entry)
+}
+
+/// Appends an entry to a journal like [`append_entry`], also returning the
+/// siblings of the created date headlines which don't match the format of
+/// their level
+///
+/// [`append_entry`]: fn.append_entry.html
+pub fn append_entry_with_diagnostics<'a, T, B>(
+    org: &mut Org<'a>,
+    date: &Datetime<'_>,
+    title: T,
+    body: B,
+    layout: &JournalLayout,
+) -> Result<(HeadlineNode, Vec<JournalDiagnostic>), OrgizeError>
+where
+    T: Into<Cow<'a, str>>,
+    B: Into<Cow<'a, str>>,
+{
+    let mut diagnostics = Vec::new();
+    let mut parent = None;
+
+    for format in &layout.levels {
+        let text = format_date(date, format);
+        let key = date_key(&text, format);
+        let siblings = children(parent, org);
+
+        let mut existing = None;
+        let mut next = None;
+        let mut non_conforming = Vec::new();
+        for sibling in &siblings {
+            let raw = &sibling.title(org).raw;
+            match (date_key(raw, format), &key) {
+                (Some(sibling_key), Some(key)) if sibling_key == *key => existing = Some(*sibling),
+                (Some(sibling_key), Some(key)) if sibling_key > *key => {
+                    next = next.or(Some(*sibling))
+                }
+                (Some(_), Some(_)) => (),
+                _ if raw.trim() == text.trim() => existing = Some(*sibling),
+                _ => non_conforming.push(*sibling),
+            }
+            if existing.is_some() {
+                break;
+            }
+        }
+        if let Some(existing) = existing {
+            parent = Some(existing);
+            continue;
+        }
+
+        for sibling in &non_conforming {
+            diagnostics.push(JournalDiagnostic {
+                span: org.span_of(sibling.headline_node()),
+                message: format!(
+                    "headline doesn't match the journal format {:?}: {}",
+                    format,
+                    sibling.title(org).raw
+                ),
+            });
+        }
+        let level = child_level(parent, &siblings);
+        let headline = org.new_headline(Title {
+            level,
+            raw: text.into(),
+            ..Title::default()
+        });
+        match next {
+            Some(next) if non_conforming.is_empty() => next.insert_before(headline, org)?,
+            _ => append(parent, headline, org)?,
+        }
+        parent = Some(headline);
+    }
+
+    let siblings = children(parent, org);
+    let entry = org.new_headline(Title {
+        level: child_level(parent, &siblings),
+        raw: title.into(),
+        ..Title::default()
+    });
+    let body = body.into();
+    if !body.is_empty() {
+        entry.set_section_content(body, org);
+    }
+    append(parent, entry, org)?;
+
+    Ok((entry, diagnostics))
+}
+
+/// Returns the fields of the date `title` matches with `format`, in
+/// decreasing order of significance
+fn date_key(title: &str, format: &str) -> Option<Vec<u32>> {
+    let fields = match_date_format(title, format)?;
+    Some(
+        [
+            fields.year,
+            fields.week_year,
+            fields.month,
+            fields.week,
+            fields.day,
+            fields.hour,
+            fields.minute,
+        ]
+        .iter()
+        .flatten()
+        .copied()
+        .collect(),
+    )
+}
+
+/// Returns the children of `parent`, or the top level headlines
+fn children(parent: Option<HeadlineNode>, org: &Org<'_>) -> Vec<HeadlineNode> {
+    match parent {
+        Some(parent) => parent.children(org).collect(),
+        None => org.document().children(org).collect(),
+    }
+}
+
+/// Returns the level of a new child of `parent`, the level of its first
+/// child if any
+fn child_level(parent: Option<HeadlineNode>, siblings: &[HeadlineNode]) -> usize {
+    match (siblings.first(), parent) {
+        (Some(sibling), _) => sibling.level(),
+        (None, Some(parent)) => parent.level() + 1,
+        (None, None) => 1,
+    }
+}
+
+fn append(
+    parent: Option<HeadlineNode>,
+    headline: HeadlineNode,
+    org: &mut Org<'_>,
+) -> Result<(), OrgizeError> {
+    match parent {
+        Some(parent) => parent.append(headline, org),
+        None => org.document().append(headline, org),
+    }
+}
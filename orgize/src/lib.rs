@@ -2,6 +2,22 @@
 //!
 //! Live demo: https://orgize.herokuapp.com/
 //!
+//! # Convert
+//!
+//! To convert an org document to html or markdown in one call, use
+//! [`org_to_html`] or [`org_to_markdown`], which convert any document, or
+//! fail with the diagnostics of invalid documents when asked to be strict:
+//!
+//! [`org_to_html`]: fn.org_to_html.html
+//! [`org_to_markdown`]: fn.org_to_markdown.html
+//!
+//! ```rust
+//! # #[cfg(all(feature = "export-html", feature = "export-markdown"))] {
+//! let html = orgize::org_to_html("* DONE Title :tag:", None).unwrap();
+//! let markdown = orgize::org_to_markdown("* DONE Title :tag:", None, false).unwrap();
+//! # }
+//! ```
+//!
 //! # Parse
 //!
 //! To parse a orgmode string, simply invoking the [`Org::parse`] function:
@@ -31,6 +47,10 @@
 //! );
 //! ```
 //!
+//! Parsing never fails and never panics: any `&str` is a valid org document, and
+//! input the parser doesn't understand ends up as plain text. This is checked by
+//! the fuzz targets in the `fuzz` directory.
+//!
 //! # Iter
 //!
 //! [`Org::iter`] function will returns an iteractor of [`Event`]s, which is
@@ -65,6 +85,7 @@
 //! ```rust
 //! use orgize::Org;
 //!
+//! # #[cfg(feature = "export-html")] {
 //! let mut writer = Vec::new();
 //! Org::parse("* title\n*section*").html(&mut writer).unwrap();
 //!
@@ -72,6 +93,7 @@
 //!     String::from_utf8(writer).unwrap(),
 //!     "<main><h1>title</h1><section><p><b>section</b></p></section></main>"
 //! );
+//! # }
 //! ```
 //!
 //! # Render html with custom `HtmlHandler`
@@ -90,10 +112,12 @@
 //! use std::io::{Error as IOError, Write};
 //! use std::string::FromUtf8Error;
 //!
+//! # #[cfg(feature = "export-html")]
 //! use orgize::export::{DefaultHtmlHandler, HtmlHandler};
 //! use orgize::{Element, Org};
 //! use slugify::slugify;
 //!
+//! # #[cfg(feature = "export-html")]
 //! #[derive(Debug)]
 //! enum MyError {
 //!     IO(IOError),
@@ -102,20 +126,24 @@
 //! }
 //!
 //! // From<std::io::Error> trait is required for custom error type
+//! # #[cfg(feature = "export-html")]
 //! impl From<IOError> for MyError {
 //!     fn from(err: IOError) -> Self {
 //!         MyError::IO(err)
 //!     }
 //! }
 //!
+//! # #[cfg(feature = "export-html")]
 //! impl From<FromUtf8Error> for MyError {
 //!     fn from(err: FromUtf8Error) -> Self {
 //!         MyError::Utf8(err)
 //!     }
 //! }
 //!
+//! # #[cfg(feature = "export-html")]
 //! struct MyHtmlHandler(DefaultHtmlHandler);
 //!
+//! # #[cfg(feature = "export-html")]
 //! impl HtmlHandler<MyError> for MyHtmlHandler {
 //!     fn start<W: Write>(&mut self, mut w: W, element: &Element<'_>) -> Result<(), MyError> {
 //!         if let Element::Title(title) = element {
@@ -146,9 +174,10 @@
 //!     }
 //! }
 //!
+//! # #[cfg(feature = "export-html")]
 //! fn main() -> Result<(), MyError> {
 //!     let mut writer = Vec::new();
-//!     let mut handler = MyHtmlHandler(DefaultHtmlHandler);
+//!     let mut handler = MyHtmlHandler(DefaultHtmlHandler::default());
 //!     Org::parse("* title\n*section*").html_with_handler(&mut writer, &mut handler)?;
 //!
 //!     assert_eq!(
@@ -159,6 +188,9 @@
 //!
 //!     Ok(())
 //! }
+//! #
+//! # #[cfg(not(feature = "export-html"))]
+//! # fn main() {}
 //! ```
 //!
 //! **Note**: as I mentioned above, each element will appears two times while iterating.
@@ -167,6 +199,26 @@
 //! So if you want to change how a non-container element renders, just redefine the `start`
 //! function and leave the `end` function unchanged.
 //!
+//! # Render markdown
+//!
+//! [`Org::markdown`] writes the document as CommonMark, with GitHub flavored
+//! tables, footnotes and alerts. [`MarkdownHandler`] is customized like
+//! `HtmlHandler`.
+//!
+//! ```rust
+//! use orgize::Org;
+//!
+//! # #[cfg(feature = "export-markdown")] {
+//! let mut writer = Vec::new();
+//! Org::parse("* title\n*section*").markdown(&mut writer).unwrap();
+//!
+//! assert_eq!(String::from_utf8(writer).unwrap(), "# title\n\n**section**\n");
+//! # }
+//! ```
+//!
+//! [`Org::markdown`]: org/struct.Org.html#method.markdown
+//! [`MarkdownHandler`]: export/markdown/trait.MarkdownHandler.html
+//!
 //! # Serde
 //!
 //! `Org` struct have already implemented serde's `Serialize` trait. It means you can
@@ -176,8 +228,10 @@
 //! use orgize::Org;
 //! use serde_json::{json, to_string};
 //!
+//! # #[cfg(feature = "ser")] {
 //! let org = Org::parse("I 'm *bold*.");
 //! println!("{}", to_string(&org).unwrap());
+//! # }
 //!
 //! // {
 //! //     "type": "document",
@@ -203,15 +257,67 @@
 //! // }
 //! ```
 //!
+//! For large documents, `Org::serialize_events` writes a flat sequence of
+//! events instead, without building the whole tree in memory, and
+//! `nest_events` rebuilds the tree from them.
+//!
+//! # Prelude
+//!
+//! [`prelude`] re-exports the types most programs need, like [`Org`],
+//! [`Element`] and the export handlers:
+//!
+//! [`prelude`]: prelude/index.html
+//! [`Org`]: struct.Org.html
+//! [`Element`]: elements/enum.Element.html
+//!
+//! ```rust
+//! use orgize::prelude::*;
+//!
+//! let org = Org::parse("* DONE Title :tag:");
+//! assert_eq!(org.headlines().count(), 1);
+//! ```
+//!
+//! # Stability
+//!
+//! The parser, the elements, the nodes and the exporters are the stable
+//! core, and follow semantic versioning.
+//!
+//! The modules enabled by the `query` and `workspace` features are
+//! experimental: their API may change in minor releases.
+//!
 //! # Features
 //!
-//! By now, orgize provides two features:
+//! By now, orgize provides the following features:
 //!
 //! + `ser`: adds the ability to serialize `Org` and other elements using `serde`, enabled by default.
 //!
-//! + `chrono`: adds the ability to convert `Datetime` into `chrono` structs, disabled by default.
+//! + `export-html`: provides the html export, `Org::html` and `org_to_html`, enabled by default.
+//!
+//! + `export-markdown`: provides the markdown export, `Org::markdown` and `org_to_markdown`, enabled by default.
+//!
+//! + `tools`: provides the `tools` module, enabled by default.
+//!
+//! + `chrono`: adds the ability to convert `Datetime` into `chrono` structs and to format dates in the html export, disabled by default.
+//!
+//! + `css`: provides `DEFAULT_CSS`, a small stylesheet for the classes of the html export, implies `export-html`, disabled by default.
+//!
+//! + `encoding`: provides `decode_with_fallback` for decoding files in encodings other than UTF-8 and UTF-16, disabled by default.
+//!
+//! + `md`: provides `from_markdown` for converting markdown documents into org documents, disabled by default.
+//!
+//! + `query`: provides the `query` module, matching headlines with agenda match strings, disabled by default.
+//!
+//! + `rayon`: provides `Org::html_parallel` for rendering large documents on multiple threads, and makes `parse_files` parse files in parallel, disabled by default.
+//!
+//! + `syntect`: provides `SyntectHtmlHandler` for highlighting code block, with a configurable theme, css classes and language aliases, requires `export-html`, disabled by default.
+//!
+//! + `test-support`: provides `proptest` strategies for generating random documents, disabled by default.
+//!
+//! + `workspace`: provides `Workspace` for checking the links between documents, disabled by default.
 //!
-//! + `syntect`: provides `SyntectHtmlHandler` for highlighting code block, disabled by default.
+//! The parser, the mutation methods and the org export are always
+//! available, so `default-features = false` keeps only them, and
+//! `features = ["export-html"]` adds the html export.
 //!
 //! # License
 //!
@@ -219,17 +325,81 @@
 
 #![allow(clippy::range_plus_one)]
 
+mod agenda;
+mod batch;
+mod captioned;
+mod changes;
+mod code_block;
 mod config;
+#[cfg(any(feature = "export-html", feature = "export-markdown"))]
+mod convert;
+mod debug_tree;
+mod decode;
+mod diagnostics;
 pub mod elements;
+mod entry;
+pub mod escape;
 pub mod export;
+mod intern;
+pub mod journal;
+mod line_index;
+mod logbook;
+#[cfg(feature = "md")]
+mod markdown;
 mod node;
 mod org;
 mod parsers;
+pub mod prelude;
+#[cfg(feature = "query")]
+pub mod query;
+mod radio;
+mod report;
+mod settings;
+mod stats;
+#[cfg(feature = "ser")]
+mod stream;
+mod template;
+#[cfg(any(test, feature = "test-support"))]
+pub mod test_support;
+#[cfg(feature = "tools")]
+pub mod tools;
+pub mod width;
+#[cfg(feature = "workspace")]
+mod workspace;
 
 mod error;
 
-pub use config::ParseConfig;
+pub use agenda::{AgendaEntry, AgendaKind, AgendaOptions};
+pub use batch::parse_files;
+pub use captioned::Captioned;
+pub use changes::{Change, ChangeKind};
+pub use code_block::CodeBlock;
+pub use config::{ParseConfig, SubSuperscripts, SyntaxCompat};
+#[cfg(feature = "export-html")]
+pub use convert::org_to_html;
+#[cfg(feature = "export-markdown")]
+pub use convert::org_to_markdown;
+pub use debug_tree::DebugTree;
+pub use decode::decode;
+#[cfg(feature = "encoding")]
+pub use decode::decode_with_fallback;
+pub use diagnostics::{Diagnostic, Severity};
 pub use elements::Element;
+pub use entry::Entry;
 pub use error::OrgizeError;
-pub use node::{DocumentNode, HeadlineNode};
-pub use org::{Event, Org};
+pub use line_index::{LineIndex, Position};
+pub use logbook::{NormalizeOptions, NormalizeReport};
+#[cfg(feature = "md")]
+pub use markdown::{from_markdown, from_markdown_with_diagnostics, MarkdownDiagnostic};
+pub use node::{DocumentNode, HeadlineNode, OrderBy};
+pub use org::{Event, OrderKey, Org};
+pub use report::{ParseReport, SyntaxFeature, UnknownKeyword};
+pub use settings::{
+    ExportSettings, Levels, SettingsResolver, StartupOptions, TodoKeyword, TodoLogging,
+};
+pub use stats::{CountOptions, HeadlineStats};
+#[cfg(feature = "ser")]
+pub use stream::{nest_events, EventTree, NestError, StreamEvent, StreamEventKind};
+pub use template::TemplateOptions;
+#[cfg(feature = "workspace")]
+pub use workspace::{LinkIssue, LinkIssueKind, Workspace};
@@ -0,0 +1,284 @@
+/// Zero-based line and column of a position in a text
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Position {
+    pub line: usize,
+    /// Column, counted in bytes or in UTF-16 code units depending on the
+    /// method which returned it
+    pub column: usize,
+}
+
+/// Conversions between byte offsets and line/column positions
+///
+/// Building the index takes linear time, then every conversion takes
+/// logarithmic time. Lines end with `\n` or `\r\n`, like in the parser, and
+/// the line terminator isn't part of the columns of a line.
+///
+/// UTF-16 columns are the ones used by the language server protocol.
+///
+/// ```rust
+/// use orgize::{LineIndex, Position};
+///
+/// let index = LineIndex::new("* title\r\n𝄞 note\n");
+///
+/// assert_eq!(index.offset_to_position(14), Position { line: 1, column: 5 });
+/// assert_eq!(index.offset_to_utf16_position(14), Position { line: 1, column: 3 });
+/// assert_eq!(index.position_to_offset(Position { line: 0, column: 100 }), 7);
+/// assert_eq!(index.utf16_position_to_offset(Position { line: 1, column: 2 }), 13);
+/// ```
+#[derive(Clone, Debug)]
+pub struct LineIndex {
+    /// Byte offsets of the start of each line
+    line_starts: Vec<usize>,
+    /// Byte offsets of the end of each line, before its terminator
+    line_ends: Vec<usize>,
+    /// Non-ASCII characters, in order
+    wide_chars: Vec<WideChar>,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct WideChar {
+    offset: usize,
+    utf8_len: usize,
+    utf16_len: usize,
+    /// Sum of the differences between the UTF-8 and the UTF-16 lengths of
+    /// the preceding wide characters
+    shift: usize,
+}
+
+impl WideChar {
+    fn shift_after(&self) -> usize {
+        self.shift + self.utf8_len - self.utf16_len
+    }
+}
+
+impl LineIndex {
+    pub fn new(text: &str) -> LineIndex {
+        let mut line_starts = vec![0];
+        let mut line_ends = Vec::new();
+        let mut wide_chars = Vec::new();
+        let mut shift = 0;
+
+        for (offset, c) in text.char_indices() {
+            if c == '\n' {
+                if offset > 0 && text.as_bytes()[offset - 1] == b'\r' {
+                    line_ends.push(offset - 1);
+                } else {
+                    line_ends.push(offset);
+                }
+                line_starts.push(offset + 1);
+            } else if !c.is_ascii() {
+                let wide_char = WideChar {
+                    offset,
+                    utf8_len: c.len_utf8(),
+                    utf16_len: c.len_utf16(),
+                    shift,
+                };
+                shift = wide_char.shift_after();
+                wide_chars.push(wide_char);
+            }
+        }
+        line_ends.push(text.len());
+
+        LineIndex {
+            line_starts,
+            line_ends,
+            wide_chars,
+        }
+    }
+
+    /// Returns the number of lines, a text ending with a newline has an empty
+    /// last line
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// Returns the position of `offset`, with a column in bytes
+    ///
+    /// Offsets past the end of the text are clamped to it, and offsets inside
+    /// a character to its start.
+    pub fn offset_to_position(&self, offset: usize) -> Position {
+        let offset = self.clamp(offset);
+        let line = self.line_of(offset);
+
+        Position {
+            line,
+            column: offset - self.line_starts[line],
+        }
+    }
+
+    /// Returns the position of `offset`, with a column in UTF-16 code units
+    pub fn offset_to_utf16_position(&self, offset: usize) -> Position {
+        let offset = self.clamp(offset);
+        let line = self.line_of(offset);
+        let start = self.line_starts[line];
+
+        Position {
+            line,
+            column: offset - start - (self.shift(offset) - self.shift(start)),
+        }
+    }
+
+    /// Returns the offset of `position`, whose column is in bytes
+    ///
+    /// Lines past the end of the text are clamped to it, columns past the end
+    /// of a line to the end of this line, and columns inside a character to
+    /// its start.
+    pub fn position_to_offset(&self, position: Position) -> usize {
+        match self.line_starts.get(position.line) {
+            Some(start) => self.clamp((start + position.column).min(self.line_ends[position.line])),
+            None => self.text_len(),
+        }
+    }
+
+    /// Returns the offset of `position`, whose column is in UTF-16 code units
+    pub fn utf16_position_to_offset(&self, position: Position) -> usize {
+        let start = match self.line_starts.get(position.line) {
+            Some(start) => *start,
+            None => return self.text_len(),
+        };
+        let end = self.line_ends[position.line];
+        let start_shift = self.shift(start);
+        let column =
+            |wide_char: &WideChar| wide_char.offset - start - (wide_char.shift - start_shift);
+
+        // the wide characters of this line, then the last one before the column
+        let first = self.wide_chars_before(start);
+        let last = self.wide_chars_before(end);
+        let before = self.wide_chars[first..last]
+            .binary_search_by(|wide_char| {
+                if column(wide_char) < position.column {
+                    std::cmp::Ordering::Less
+                } else {
+                    std::cmp::Ordering::Greater
+                }
+            })
+            .unwrap_err();
+
+        let offset = match self.wide_chars[first..last][..before].last() {
+            Some(wide_char) if column(wide_char) + wide_char.utf16_len > position.column => {
+                // inside a surrogate pair
+                wide_char.offset
+            }
+            Some(wide_char) => start + position.column + (wide_char.shift_after() - start_shift),
+            None => start + position.column,
+        };
+
+        offset.min(end)
+    }
+
+    fn text_len(&self) -> usize {
+        *self.line_ends.last().unwrap()
+    }
+
+    fn line_of(&self, offset: usize) -> usize {
+        match self.line_starts.binary_search(&offset) {
+            Ok(line) => line,
+            Err(line) => line - 1,
+        }
+    }
+
+    /// Returns the number of wide characters starting before `offset`
+    fn wide_chars_before(&self, offset: usize) -> usize {
+        match self
+            .wide_chars
+            .binary_search_by_key(&offset, |wide_char| wide_char.offset)
+        {
+            Ok(i) | Err(i) => i,
+        }
+    }
+
+    /// Returns the difference between the UTF-8 and the UTF-16 lengths of
+    /// the text before `offset`
+    fn shift(&self, offset: usize) -> usize {
+        match self.wide_chars_before(offset) {
+            0 => 0,
+            i => self.wide_chars[i - 1].shift_after(),
+        }
+    }
+
+    fn clamp(&self, offset: usize) -> usize {
+        let offset = offset.min(self.text_len());
+
+        match self.wide_chars_before(offset) {
+            0 => offset,
+            i => {
+                let wide_char = &self.wide_chars[i - 1];
+                if offset < wide_char.offset + wide_char.utf8_len {
+                    wide_char.offset
+                } else {
+                    offset
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn conversions() {
+    let text = "a\r\né𝄞b\n\nc";
+    let index = LineIndex::new(text);
+
+    assert_eq!(index.line_count(), 4);
+
+    let positions: Vec<_> = text
+        .char_indices()
+        .map(|(offset, _)| index.offset_to_position(offset))
+        .collect();
+    let utf16_positions: Vec<_> = text
+        .char_indices()
+        .map(|(offset, _)| index.offset_to_utf16_position(offset))
+        .collect();
+
+    let position = |line, column| Position { line, column };
+    assert_eq!(
+        positions,
+        [
+            position(0, 0),
+            position(0, 1),
+            position(0, 2),
+            position(1, 0),
+            position(1, 2),
+            position(1, 6),
+            position(1, 7),
+            position(2, 0),
+            position(3, 0),
+        ]
+    );
+    assert_eq!(
+        utf16_positions,
+        [
+            position(0, 0),
+            position(0, 1),
+            position(0, 2),
+            position(1, 0),
+            position(1, 1),
+            position(1, 3),
+            position(1, 4),
+            position(2, 0),
+            position(3, 0),
+        ]
+    );
+
+    // the terminator isn't part of the line
+    for ((offset, _), (position, utf16_position)) in text
+        .char_indices()
+        .zip(positions.iter().zip(&utf16_positions))
+        .filter(|((offset, _), _)| !text[..*offset].ends_with('\r'))
+    {
+        assert_eq!(index.position_to_offset(*position), offset);
+        assert_eq!(index.utf16_position_to_offset(*utf16_position), offset);
+    }
+
+    // clamping
+    assert_eq!(index.offset_to_position(4), position(1, 0));
+    assert_eq!(index.offset_to_position(100), position(3, 1));
+    assert_eq!(index.position_to_offset(position(0, 10)), 1);
+    assert_eq!(index.position_to_offset(position(1, 1)), 3);
+    assert_eq!(index.position_to_offset(position(10, 0)), text.len());
+    assert_eq!(index.utf16_position_to_offset(position(1, 2)), 5);
+    assert_eq!(index.utf16_position_to_offset(position(1, 10)), 10);
+
+    let empty = LineIndex::new("");
+    assert_eq!(empty.offset_to_utf16_position(0), position(0, 0));
+    assert_eq!(empty.utf16_position_to_offset(position(0, 3)), 0);
+}
@@ -0,0 +1,257 @@
+use std::cmp::Reverse;
+
+use indextree::NodeId;
+
+use crate::elements::{Clock, Datetime, Element};
+use crate::org::Org;
+
+/// Options of [`Org::normalize_logbooks`]
+///
+/// [`Org::normalize_logbooks`]: struct.Org.html#method.normalize_logbooks
+#[derive(Clone, Debug)]
+pub struct NormalizeOptions {
+    /// Merge closed clocks whose intervals overlap into one clock spanning
+    /// both
+    ///
+    /// Default: `false`
+    pub merge_overlapping: bool,
+    /// Remove closed clocks lasting zero minutes
+    ///
+    /// Default: `true`
+    pub remove_zero_length: bool,
+}
+
+impl Default for NormalizeOptions {
+    fn default() -> Self {
+        NormalizeOptions {
+            merge_overlapping: false,
+            remove_zero_length: true,
+        }
+    }
+}
+
+/// What [`Org::normalize_logbooks`] changed
+///
+/// [`Org::normalize_logbooks`]: struct.Org.html#method.normalize_logbooks
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct NormalizeReport {
+    /// Clocks removed because an identical clock is in the same logbook
+    pub duplicates: usize,
+    /// Clocks merged into an overlapping clock
+    pub merged: usize,
+    /// Zero-length clocks removed
+    pub zero_length: usize,
+    /// Closed clocks whose `=> H:MM` duration was recomputed
+    pub durations: usize,
+    /// Logbooks whose clocks weren't sorted newest-first
+    pub sorted: usize,
+}
+
+pub(crate) fn normalize_logbooks(org: &mut Org<'_>, options: &NormalizeOptions) -> NormalizeReport {
+    let mut report = NormalizeReport::default();
+
+    let logbooks: Vec<NodeId> = org
+        .root
+        .descendants(&org.arena)
+        .filter(|&node| match org.arena[node].get() {
+            Element::Drawer(drawer) => drawer.name.eq_ignore_ascii_case("LOGBOOK"),
+            _ => false,
+        })
+        .collect();
+
+    for logbook in logbooks {
+        let (nodes, clocks): (Vec<NodeId>, Vec<Clock>) = logbook
+            .children(&org.arena)
+            .filter_map(|node| match org.arena[node].get() {
+                Element::Clock(clock) => Some((node, clock.clone())),
+                _ => None,
+            })
+            .unzip();
+
+        let normalized = normalize(clocks.clone(), options, &mut report);
+        if normalized == clocks {
+            continue;
+        }
+
+        // other elements of the logbook, like notes, stay in place
+        for (i, node) in nodes.into_iter().enumerate() {
+            org.forget_source(node);
+            match normalized.get(i) {
+                Some(clock) => *org.arena[node].get_mut() = Element::Clock(clock.clone()),
+                None => node.remove(&mut org.arena),
+            }
+        }
+    }
+
+    org.debug_validate();
+
+    report
+}
+
+/// Returns the clocks of a logbook with their durations recomputed,
+/// deduplicated, without zero-length clocks, sorted newest-first and merged
+fn normalize<'a>(
+    clocks: Vec<Clock<'a>>,
+    options: &NormalizeOptions,
+    report: &mut NormalizeReport,
+) -> Vec<Clock<'a>> {
+    let mut unique: Vec<Clock> = Vec::with_capacity(clocks.len());
+    for mut clock in clocks {
+        // clocks differing only by their duration are identical once it's recomputed
+        if let Clock::Closed {
+            start,
+            end,
+            duration,
+            ..
+        } = &mut clock
+        {
+            if let Some(length) = length(start, end) {
+                let length = format_duration(length);
+                if *duration != length {
+                    *duration = length.into();
+                    report.durations += 1;
+                }
+            }
+        }
+
+        if unique.contains(&clock) {
+            report.duplicates += 1;
+        } else {
+            unique.push(clock);
+        }
+    }
+    let mut clocks = unique;
+
+    if options.remove_zero_length {
+        let len = clocks.len();
+        clocks.retain(|clock| match clock {
+            Clock::Closed { start, end, .. } => length(start, end) != Some(0),
+            Clock::Running { .. } => true,
+        });
+        report.zero_length += len - clocks.len();
+    }
+
+    // clocks with invalid datetimes go last, in their order
+    let key = |clock: &Clock| {
+        Reverse(match clock {
+            Clock::Closed { start, .. } | Clock::Running { start, .. } => minutes(start),
+        })
+    };
+    if clocks.windows(2).any(|pair| key(&pair[0]) > key(&pair[1])) {
+        clocks.sort_by_key(key);
+        report.sorted += 1;
+    }
+
+    if options.merge_overlapping {
+        let mut merged: Vec<Clock> = Vec::with_capacity(clocks.len());
+        for clock in clocks {
+            let merges = match merged.last_mut() {
+                Some(newer) => merge(newer, &clock),
+                None => false,
+            };
+            if merges {
+                report.merged += 1;
+            } else {
+                merged.push(clock);
+            }
+        }
+        clocks = merged;
+    }
+
+    clocks
+}
+
+/// Extends the closed clock `newer` to cover `older` if their intervals
+/// overlap, returns `false` otherwise
+///
+/// `older` doesn't start after `newer`.
+fn merge<'a>(newer: &mut Clock<'a>, older: &Clock<'a>) -> bool {
+    match (newer, older) {
+        (
+            Clock::Closed {
+                start,
+                end,
+                duration,
+                ..
+            },
+            Clock::Closed {
+                start: older_start,
+                end: older_end,
+                ..
+            },
+        ) => {
+            if length(start, end).is_none()
+                || length(older_start, older_end).is_none()
+                || minutes(start) >= minutes(older_end)
+            {
+                return false;
+            }
+
+            if minutes(older_end) > minutes(end) {
+                *end = older_end.clone();
+            }
+            *start = older_start.clone();
+            *duration = format_duration(length(start, end).unwrap_or_default()).into();
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Returns the minutes from `start` to `end`, or `None` if one of them
+/// doesn't exist or `end` comes first
+fn length(start: &Datetime, end: &Datetime) -> Option<i64> {
+    Some(minutes(end)? - minutes(start)?).filter(|&length| length >= 0)
+}
+
+/// Formats a duration like org-mode's clocks, as `H:MM`
+fn format_duration(minutes: i64) -> String {
+    format!("{}:{:02}", minutes / 60, minutes % 60)
+}
+
+/// Returns the minutes from 1970-01-01 00:00 to `datetime`, or `None` if it
+/// doesn't exist
+fn minutes(datetime: &Datetime) -> Option<i64> {
+    if !datetime.is_valid() {
+        return None;
+    }
+
+    // years start in March, so leap days end them, and are grouped in
+    // 400-year eras of 146097 days
+    let (month, day) = (i64::from(datetime.month), i64::from(datetime.day));
+    let year = i64::from(datetime.year) - if month <= 2 { 1 } else { 0 };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146_097 + day_of_era - 719_468;
+
+    Some(
+        days * 1440
+            + i64::from(datetime.hour.unwrap_or(0)) * 60
+            + i64::from(datetime.minute.unwrap_or(0)),
+    )
+}
+
+#[test]
+fn minutes_() {
+    let datetime = |year, month, day, hour, minute| Datetime {
+        year,
+        month,
+        day,
+        dayname: "".into(),
+        hour: Some(hour),
+        minute: Some(minute),
+    };
+
+    assert_eq!(minutes(&datetime(1970, 1, 1, 0, 0)), Some(0));
+    assert_eq!(minutes(&datetime(1970, 1, 2, 1, 30)), Some(1440 + 90));
+    assert_eq!(minutes(&datetime(2000, 3, 1, 0, 0)), Some(11_017 * 1440));
+    assert_eq!(minutes(&datetime(1969, 12, 31, 23, 59)), Some(-1));
+    assert_eq!(minutes(&datetime(2021, 2, 29, 0, 0)), None);
+    assert_eq!(
+        length(&datetime(2020, 2, 28, 23, 0), &datetime(2020, 3, 1, 1, 5)),
+        Some(26 * 60 + 5)
+    );
+    assert_eq!(format_duration(26 * 60 + 5), "26:05");
+}
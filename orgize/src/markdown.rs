@@ -0,0 +1,540 @@
+use std::mem;
+use std::ops::Range;
+
+use pulldown_cmark::{CodeBlockKind, Event, LinkType, Options, Parser, Tag};
+
+use crate::elements::{link::escape_brackets, Title};
+use crate::node::HeadlineNode;
+use crate::org::Org;
+use crate::width::{pad, width, WidthOptions};
+
+/// A markdown construct without an org equivalent, see
+/// [`from_markdown_with_diagnostics`]
+///
+/// [`from_markdown_with_diagnostics`]: fn.from_markdown_with_diagnostics.html
+#[derive(Clone, Debug, PartialEq)]
+pub struct MarkdownDiagnostic {
+    /// Byte range of the construct in the markdown text
+    pub span: Range<usize>,
+    pub message: String,
+}
+
+/// Converts a markdown document into an org document
+///
+/// Headings become headlines, fenced code blocks become source blocks and
+/// task list items become checkboxes. Links, emphasis, block quotes, tables
+/// and footnotes are converted too. Raw html is kept in html export blocks
+/// and snippets, and html comments become comments.
+///
+/// The document is built with [`Org::new_headline`] and the
+/// `set_section_content` methods, and its headlines can be copied into other
+/// documents with [`Org::graft`].
+///
+/// ```rust
+/// use orgize::from_markdown;
+///
+/// let org = from_markdown("# Notes\n\nSee *this* and [the docs](https://x.org).\n\n```rust\nfn main() {}\n```\n");
+///
+/// let mut writer = Vec::new();
+/// org.org(&mut writer).unwrap();
+/// assert_eq!(
+///     String::from_utf8(writer).unwrap(),
+///     "* Notes\nSee /this/ and [[https://x.org][the docs]].\n\n#+BEGIN_SRC rust\nfn main() {}\n#+END_SRC\n"
+/// );
+/// ```
+///
+/// [`Org::new_headline`]: struct.Org.html#method.new_headline
+/// [`Org::graft`]: struct.Org.html#method.graft
+pub fn from_markdown(markdown: &str) -> Org<'static> {
+    from_markdown_with_diagnostics(markdown).0
+}
+
+/// Converts a markdown document into an org document like [`from_markdown`],
+/// also returning the constructs which were kept as raw html or comments
+///
+/// [`from_markdown`]: fn.from_markdown.html
+pub fn from_markdown_with_diagnostics(markdown: &str) -> (Org<'static>, Vec<MarkdownDiagnostic>) {
+    let options = Options::ENABLE_TABLES
+        | Options::ENABLE_FOOTNOTES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS;
+
+    let mut converter = Converter {
+        org: Org::new(),
+        headlines: Vec::new(),
+        section: String::new(),
+        frames: Vec::new(),
+        inline: 0,
+        html: None,
+        diagnostics: Vec::new(),
+    };
+
+    for (event, span) in Parser::new_ext(markdown, options).into_offset_iter() {
+        converter.event(event, span);
+    }
+    converter.flush_html();
+    converter.flush_section();
+
+    (converter.org, converter.diagnostics)
+}
+
+/// An open markdown element, and the org text written inside it
+enum Frame {
+    Heading(u32),
+    BlockQuote,
+    CodeBlock(Option<String>),
+    List(Option<u64>),
+    Item,
+    FootnoteDefinition(String),
+    Table { rows: Vec<Vec<String>>, head: usize },
+    TableCell,
+    Link(LinkType, String),
+    Image(String),
+}
+
+struct Converter {
+    org: Org<'static>,
+    /// Open headlines, the innermost last
+    headlines: Vec<HeadlineNode>,
+    /// Text of the section of the last headline, or of the document
+    section: String,
+    frames: Vec<(Frame, String)>,
+    /// Number of open paragraphs, headings and table cells
+    inline: usize,
+    /// Lines of the html block being read
+    html: Option<(String, Range<usize>)>,
+    diagnostics: Vec<MarkdownDiagnostic>,
+}
+
+impl Converter {
+    /// Returns the text of the innermost frame
+    fn buffer(&mut self) -> &mut String {
+        match self.frames.last_mut() {
+            Some((_, buffer)) => buffer,
+            None => &mut self.section,
+        }
+    }
+
+    /// Starts a new line in the innermost frame, if needed
+    fn start_block(&mut self) {
+        let buffer = self.buffer();
+        if !buffer.is_empty() && !buffer.ends_with('\n') {
+            buffer.push('\n');
+        }
+    }
+
+    fn push(&mut self, frame: Frame) {
+        self.frames.push((frame, String::new()));
+    }
+
+    fn event(&mut self, event: Event, span: Range<usize>) {
+        if let Event::Html(html) = &event {
+            // html blocks are made of whole lines
+            if self.inline == 0 && html.ends_with('\n') {
+                // consecutive html events belong to the same block
+                if let Some((_, html_span)) = &self.html {
+                    if html_span.end < span.start {
+                        self.flush_html();
+                    }
+                }
+                let (text, html_span) = self
+                    .html
+                    .get_or_insert_with(|| (String::new(), span.clone()));
+                text.push_str(html);
+                html_span.end = span.end;
+                return;
+            }
+        }
+        self.flush_html();
+
+        match event {
+            Event::Start(tag) => self.start(tag),
+            Event::End(tag) => self.end(tag),
+            Event::Text(text) => {
+                let text = match self.frames.last() {
+                    Some((Frame::Link(..), _)) => escape_brackets(&text).into_owned(),
+                    Some((Frame::TableCell, _)) => text.replace('|', "\\vert{}"),
+                    _ => text.to_string(),
+                };
+                self.buffer().push_str(&text);
+            }
+            Event::Code(code) => {
+                let marker = if code.contains('~') { '=' } else { '~' };
+                let buffer = self.buffer();
+                buffer.push(marker);
+                buffer.push_str(&code);
+                buffer.push(marker);
+            }
+            Event::Html(html) => {
+                self.diagnostics.push(MarkdownDiagnostic {
+                    span,
+                    message: "inline html is kept in an export snippet".into(),
+                });
+                let snippet = format!("@@html:{}@@", html);
+                self.buffer().push_str(&snippet);
+            }
+            Event::FootnoteReference(label) => {
+                let reference = format!("[fn:{}]", label);
+                self.buffer().push_str(&reference);
+            }
+            Event::SoftBreak => self.buffer().push('\n'),
+            Event::HardBreak => self.buffer().push_str("\\\\\n"),
+            Event::Rule => {
+                self.start_block();
+                self.buffer().push_str("-----\n\n");
+            }
+            Event::TaskListMarker(checked) => {
+                let checkbox = if checked { "[X] " } else { "[ ] " };
+                self.buffer().push_str(checkbox);
+            }
+        }
+    }
+
+    fn start(&mut self, tag: Tag) {
+        match tag {
+            Tag::Paragraph => {
+                self.start_block();
+                self.inline += 1;
+            }
+            Tag::Heading(level) => {
+                self.inline += 1;
+                if self.frames.is_empty() {
+                    self.flush_section();
+                    self.push(Frame::Heading(level));
+                } else {
+                    // headings in list items or quotes are written in bold
+                    self.start_block();
+                    self.buffer().push('*');
+                }
+            }
+            Tag::BlockQuote => {
+                self.start_block();
+                self.push(Frame::BlockQuote);
+            }
+            Tag::CodeBlock(kind) => {
+                self.start_block();
+                let language = match kind {
+                    CodeBlockKind::Fenced(info) => info
+                        .split_whitespace()
+                        .next()
+                        .map(|language| language.to_string()),
+                    CodeBlockKind::Indented => None,
+                };
+                self.push(Frame::CodeBlock(language));
+            }
+            Tag::List(start) => {
+                self.start_block();
+                self.push(Frame::List(start));
+            }
+            Tag::Item => self.push(Frame::Item),
+            Tag::FootnoteDefinition(label) => {
+                self.start_block();
+                self.push(Frame::FootnoteDefinition(label.to_string()));
+            }
+            Tag::Table(_) => {
+                self.start_block();
+                self.push(Frame::Table {
+                    rows: Vec::new(),
+                    head: 0,
+                });
+            }
+            Tag::TableHead | Tag::TableRow => {
+                if let Some((Frame::Table { rows, .. }, _)) = self.frames.last_mut() {
+                    // header cells may or may not be wrapped in a row
+                    if rows.last().map(Vec::len) != Some(0) {
+                        rows.push(Vec::new());
+                    }
+                }
+            }
+            Tag::TableCell => {
+                self.push(Frame::TableCell);
+                self.inline += 1;
+            }
+            Tag::Emphasis => self.buffer().push('/'),
+            Tag::Strong => self.buffer().push('*'),
+            Tag::Strikethrough => self.buffer().push('+'),
+            Tag::Link(link_type, url, _) => self.push(Frame::Link(link_type, url.to_string())),
+            Tag::Image(_, url, _) => self.push(Frame::Image(url.to_string())),
+        }
+    }
+
+    fn end(&mut self, tag: Tag) {
+        match tag {
+            Tag::Paragraph => {
+                self.inline -= 1;
+                self.buffer().push_str("\n\n");
+            }
+            Tag::Emphasis => self.buffer().push('/'),
+            Tag::Strong => self.buffer().push('*'),
+            Tag::Strikethrough => self.buffer().push('+'),
+            Tag::TableHead => {
+                if let Some((Frame::Table { rows, head }, _)) = self.frames.last_mut() {
+                    *head = rows.len();
+                }
+            }
+            Tag::TableRow => (),
+            Tag::Heading(_) => match self.frames.last() {
+                Some((Frame::Heading(_), _)) => self.pop(),
+                _ => {
+                    self.inline -= 1;
+                    self.buffer().push_str("*\n\n");
+                }
+            },
+            _ => self.pop(),
+        }
+    }
+
+    /// Closes the innermost frame and writes its text in its parent
+    fn pop(&mut self) {
+        let (frame, text) = match self.frames.pop() {
+            Some(frame) => frame,
+            None => return,
+        };
+
+        match frame {
+            Frame::Heading(level) => {
+                self.inline -= 1;
+                self.add_headline(level as usize, text.trim());
+            }
+            Frame::BlockQuote => {
+                let quote = format!("#+BEGIN_QUOTE\n{}\n#+END_QUOTE\n\n", text.trim_end());
+                self.buffer().push_str(&quote);
+            }
+            Frame::CodeBlock(language) => {
+                let block = match language {
+                    Some(language) => format!(
+                        "#+BEGIN_SRC {}\n{}#+END_SRC\n\n",
+                        language,
+                        escape_block(&text)
+                    ),
+                    None => format!("#+BEGIN_EXAMPLE\n{}#+END_EXAMPLE\n\n", escape_block(&text)),
+                };
+                self.buffer().push_str(&block);
+            }
+            Frame::List(_) => {
+                let buffer = self.buffer();
+                buffer.push_str(&text);
+                buffer.push('\n');
+            }
+            Frame::Item => {
+                let bullet = match self.frames.last_mut() {
+                    Some((Frame::List(Some(number)), _)) => {
+                        *number += 1;
+                        format!("{}. ", *number - 1)
+                    }
+                    _ => "- ".to_string(),
+                };
+                let item = indent(text.trim_end(), &bullet);
+                let buffer = self.buffer();
+                buffer.push_str(&bullet);
+                buffer.push_str(&item);
+                buffer.push('\n');
+            }
+            Frame::FootnoteDefinition(label) => {
+                let definition = format!("[fn:{}] {}\n\n", label, text.trim());
+                self.buffer().push_str(&definition);
+            }
+            Frame::Table { rows, head } => {
+                let table = write_table(&rows, head);
+                self.buffer().push_str(&table);
+            }
+            Frame::TableCell => {
+                self.inline -= 1;
+                if let Some((Frame::Table { rows, .. }, _)) = self.frames.last_mut() {
+                    if let Some(row) = rows.last_mut() {
+                        row.push(text.trim().to_string());
+                    }
+                }
+            }
+            Frame::Link(link_type, url) => {
+                let path = match link_type {
+                    LinkType::Email => format!("mailto:{}", url),
+                    _ => url,
+                };
+                let link = if text.is_empty() || text == path || text == escape_brackets(&path) {
+                    format!("[[{}]]", escape_brackets(&path))
+                } else {
+                    format!("[[{}][{}]]", escape_brackets(&path), text)
+                };
+                self.buffer().push_str(&link);
+            }
+            Frame::Image(url) => {
+                let link = format!("[[{}]]", escape_brackets(&url));
+                self.buffer().push_str(&link);
+            }
+        }
+    }
+
+    /// Writes the html block being read as an export block, or as comments
+    /// if it's an html comment
+    fn flush_html(&mut self) {
+        let (html, span) = match self.html.take() {
+            Some(html) => html,
+            None => return,
+        };
+
+        self.start_block();
+        let trimmed = html.trim();
+        let comment = if trimmed.starts_with("<!--") && trimmed.ends_with("-->") {
+            Some(&trimmed[4..trimmed.len() - 3])
+        } else {
+            None
+        };
+
+        let (block, message) = match comment {
+            Some(comment) => {
+                let lines: String = comment
+                    .trim()
+                    .lines()
+                    .map(|line| format!("# {}\n", line.trim()))
+                    .collect();
+                (lines, "html comment is kept as a comment")
+            }
+            None => (
+                format!("#+BEGIN_EXPORT html\n{}#+END_EXPORT\n", escape_block(&html)),
+                "raw html is kept in an export block",
+            ),
+        };
+
+        let buffer = self.buffer();
+        buffer.push_str(&block);
+        buffer.push('\n');
+        self.diagnostics.push(MarkdownDiagnostic {
+            span,
+            message: message.into(),
+        });
+    }
+
+    /// Sets the text read so far as the section of the last headline, or of
+    /// the document
+    fn flush_section(&mut self) {
+        let section = mem::take(&mut self.section);
+        let section = section.trim_end();
+
+        if section.trim().is_empty() {
+            return;
+        }
+
+        let content = format!("{}\n", section);
+        match self.headlines.last() {
+            Some(headline) => headline.set_section_content(content, &mut self.org),
+            None => self
+                .org
+                .document()
+                .set_section_content(content, &mut self.org),
+        }
+    }
+
+    fn add_headline(&mut self, level: usize, title: &str) {
+        let headline = self.org.new_headline(Title {
+            level,
+            raw: title.to_string().into(),
+            ..Title::default()
+        });
+
+        while let Some(parent) = self.headlines.last() {
+            if parent.level() < level {
+                break;
+            }
+            self.headlines.pop();
+        }
+
+        // the parent has a lower level and its last child a higher one
+        let result = match self.headlines.last() {
+            Some(parent) => parent.append(headline, &mut self.org),
+            None => self.org.document().append(headline, &mut self.org),
+        };
+        debug_assert!(result.is_ok());
+
+        self.headlines.push(headline);
+    }
+}
+
+/// Escapes the lines of a block which would end it or start a headline
+fn escape_block(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for line in text.lines() {
+        let content = line.trim_start();
+        if content.starts_with('*')
+            || content.starts_with("#+")
+            || content.starts_with(",*")
+            || content.starts_with(",#+")
+        {
+            escaped.push_str(&line[0..line.len() - content.len()]);
+            escaped.push(',');
+            escaped.push_str(content);
+        } else {
+            escaped.push_str(line);
+        }
+        escaped.push('\n');
+    }
+
+    escaped
+}
+
+/// Indents the lines of a list item after the first by the width of its
+/// bullet
+fn indent(text: &str, bullet: &str) -> String {
+    let padding = " ".repeat(bullet.len());
+
+    text.lines()
+        .enumerate()
+        .map(|(i, line)| {
+            if i == 0 || line.is_empty() {
+                line.to_string()
+            } else {
+                format!("{}{}", padding, line)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn write_table(rows: &[Vec<String>], head: usize) -> String {
+    let options = WidthOptions::default();
+    let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let widths: Vec<usize> = (0..columns)
+        .map(|column| {
+            rows.iter()
+                .filter_map(|row| row.get(column))
+                .map(|cell| width(cell, &options))
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    let mut table = String::new();
+    for (i, row) in rows.iter().enumerate() {
+        if i == head && head > 0 {
+            let rule: Vec<_> = widths.iter().map(|width| "-".repeat(width + 2)).collect();
+            table.push_str(&format!("|{}|\n", rule.join("+")));
+        }
+
+        table.push('|');
+        for (column, width) in widths.iter().enumerate() {
+            let cell = row.get(column).map(String::as_str).unwrap_or("");
+            table.push_str(&format!(" {} |", pad(cell, *width, &options)));
+        }
+        table.push('\n');
+    }
+    table.push('\n');
+
+    table
+}
+
+#[test]
+fn table_width() {
+    let rows = vec![
+        vec!["name".to_string(), "note".to_string()],
+        vec!["漢字".to_string(), "e\u{301}e\u{301}".to_string()],
+        vec!["👨\u{200D}👩\u{200D}👧".to_string(), "ascii".to_string()],
+    ];
+    assert_eq!(
+        write_table(&rows, 1),
+        "| name | note  |\n\
+         |------+-------|\n\
+         | 漢字 | e\u{301}e\u{301}    |\n\
+         | 👨\u{200D}👩\u{200D}👧   | ascii |\n\n"
+    );
+}
@@ -1,10 +1,16 @@
-use indextree::NodeId;
+use indextree::{NodeEdge, NodeId};
 use std::borrow::Cow;
+use std::collections::HashMap;
 
-use crate::config::ParseConfig;
-use crate::elements::{Element, Title};
+use crate::changes::ChangeKind;
+use crate::config::{ParseConfig, DEFAULT_CONFIG};
+use crate::elements::title::format_number;
+use crate::elements::{Element, Timestamp, Title};
+use crate::export::org::write_timestamp;
+use crate::export::{DefaultOrgHandler, OrgHandler};
 use crate::parsers::{parse_container, Container, OwnedArena};
-use crate::{Org, OrgizeError};
+use crate::settings::{SettingsResolver, StartupOptions};
+use crate::{escape, OrderKey, Org, OrgizeError};
 
 #[derive(Copy, Clone, Debug)]
 pub struct HeadlineNode {
@@ -38,6 +44,21 @@ impl HeadlineNode {
         self.level
     }
 
+    /// Return the id of the `Headline` element in the arena
+    pub fn headline_node(self) -> NodeId {
+        self.node
+    }
+
+    /// Return the text this headline and its subtree were parsed from
+    ///
+    /// Returns `None` if the headline was created with `Org::new_headline`,
+    /// or if it has been modified since parsing. See [`Org::source_of`].
+    ///
+    /// [`Org::source_of`]: struct.Org.html#method.source_of
+    pub fn source<'a>(self, org: &Org<'a>) -> Option<&'a str> {
+        org.source_of(self.node)
+    }
+
     pub fn title<'a: 'b, 'b>(self, org: &'b Org<'a>) -> &'b Title<'a> {
         if let Element::Title(title) = org.arena[self.title_node].get() {
             title
@@ -47,6 +68,7 @@ impl HeadlineNode {
     }
 
     pub fn title_mut<'a: 'b, 'b>(self, org: &'b mut Org<'a>) -> &'b mut Title<'a> {
+        org.forget_source(self.title_node);
         if let Element::Title(title) = org.arena[self.title_node].get_mut() {
             title
         } else {
@@ -54,15 +76,238 @@ impl HeadlineNode {
         }
     }
 
+    /// Returns the value of this headline's property `key`, see
+    /// [`Title::property`]
+    ///
+    /// [`Title::property`]: elements/struct.Title.html#method.property
+    pub fn property<'a: 'b, 'b>(self, key: &str, org: &'b Org<'a>) -> Option<&'b str> {
+        self.title(org).property(key)
+    }
+
+    /// Returns the timestamp of this headline's `CREATED` property
+    pub fn created<'a: 'b, 'b>(self, org: &'b Org<'a>) -> Option<Timestamp<'b>> {
+        self.property_timestamp("CREATED", org)
+    }
+
+    /// Returns the timestamp of this headline's `LAST_REPEAT` property
+    pub fn last_repeat<'a: 'b, 'b>(self, org: &'b Org<'a>) -> Option<Timestamp<'b>> {
+        self.property_timestamp("LAST_REPEAT", org)
+    }
+
+    /// Returns `true` if this headline or one of its ancestors is tagged
+    /// `ARCHIVE`, see [`Title::is_archived`]
+    ///
+    /// ```rust
+    /// use orgize::Org;
+    ///
+    /// let org = Org::parse("* Old :ARCHIVE:\n** Child\n* New\n");
+    /// let archived: Vec<_> = org.headlines().map(|headline| headline.is_archived(&org)).collect();
+    ///
+    /// assert_eq!(archived, [true, true, false]);
+    /// ```
+    ///
+    /// [`Title::is_archived`]: elements/struct.Title.html#method.is_archived
+    pub fn is_archived(self, org: &Org<'_>) -> bool {
+        let mut headline = Some(self);
+        while let Some(current) = headline {
+            if current.title(org).is_archived() {
+                return true;
+            }
+            headline = current.parent(org);
+        }
+        false
+    }
+
+    /// Returns this headline's closed timestamp
+    pub fn closed<'a: 'b, 'b>(self, org: &'b Org<'a>) -> Option<&'b Timestamp<'b>> {
+        self.title(org).closed()
+    }
+
+    /// Returns the timestamp objects in this headline's title and section,
+    /// in document order, without the ones of child headlines
+    ///
+    /// Timestamps are indexed while parsing and by `set_title_content` and
+    /// `set_section_content`, so this doesn't walk the section. Timestamps
+    /// added directly to the arena aren't indexed.
+    pub fn timestamps<'a: 'b, 'b>(
+        self,
+        org: &'b Org<'a>,
+    ) -> impl Iterator<Item = &'b Timestamp<'a>> + 'b {
+        org.timestamps
+            .get(&self.node)
+            .into_iter()
+            .flatten()
+            // elements removed from the arena stay in the index until pruned
+            .filter(move |&&node| org.timestamp_owner(node) == self.node)
+            .filter_map(move |&node| match org.arena[node].get() {
+                Element::Timestamp(timestamp) => Some(timestamp),
+                _ => None,
+            })
+    }
+
+    /// Returns the value of this headline's property `key` as a timestamp,
+    /// see [`Title::property_timestamp`]
+    ///
+    /// [`Title::property_timestamp`]: elements/struct.Title.html#method.property_timestamp
+    pub fn property_timestamp<'a: 'b, 'b>(
+        self,
+        key: &str,
+        org: &'b Org<'a>,
+    ) -> Option<Timestamp<'b>> {
+        self.title(org).property_timestamp(key)
+    }
+
+    /// Returns the value of this headline's property `key` as a number, see
+    /// [`Title::property_number`]
+    ///
+    /// [`Title::property_number`]: elements/struct.Title.html#method.property_number
+    pub fn property_number(self, key: &str, org: &Org<'_>) -> Option<f64> {
+        self.title(org).property_number(key)
+    }
+
+    /// Returns the value of this headline's property `key` split at
+    /// `separator`, see [`Title::property_list`]
+    ///
+    /// [`Title::property_list`]: elements/struct.Title.html#method.property_list
+    pub fn property_list<'a: 'b, 'b>(
+        self,
+        key: &str,
+        separator: &str,
+        org: &'b Org<'a>,
+    ) -> Option<Vec<&'b str>> {
+        self.title(org).property_list(key, separator)
+    }
+
+    /// Sets this headline's property `key` to `value`
+    ///
+    /// A property whose key differs only in case is replaced, keeping its
+    /// key. The headline isn't modified if the value doesn't change.
+    ///
+    /// ```rust
+    /// use orgize::Org;
+    ///
+    /// let mut org = Org::parse("* a\n:PROPERTIES:\n:Pages: 37\n:END:\n");
+    /// let headline = org.headlines().next().unwrap();
+    ///
+    /// headline.set_property_number("PAGES", 38.0, &mut org);
+    /// headline.set_property_list("AUTHORS", &["alice", "bob"], " ", &mut org);
+    /// assert_eq!(headline.property_number("pages", &org), Some(38.0));
+    ///
+    /// let mut writer = Vec::new();
+    /// org.org(&mut writer).unwrap();
+    /// assert_eq!(
+    ///     String::from_utf8(writer).unwrap(),
+    ///     "* a\n:PROPERTIES:\n:AUTHORS: alice bob\n:Pages: 38\n:END:\n"
+    /// );
+    /// ```
+    pub fn set_property<'a, S: Into<Cow<'a, str>>>(self, key: &str, value: S, org: &mut Org<'a>) {
+        let value = value.into();
+        let title = self.title(org);
+        let current = title
+            .properties
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key));
+
+        let before = match current {
+            Some((_, v)) if *v == value => return,
+            Some((k, v)) => {
+                let (k, v) = (k.clone(), v.to_string());
+                self.title_mut(org).properties.insert(k, value.clone());
+                Some(v)
+            }
+            None => {
+                self.title_mut(org)
+                    .properties
+                    .insert(key.to_string().into(), value.clone());
+                None
+            }
+        };
+
+        if let Some(path) = org.change_path(self.node) {
+            let kind = ChangeKind::Property {
+                key: key.to_string(),
+                before,
+                after: value.into_owned(),
+            };
+            org.log_change(path, kind);
+        }
+    }
+
+    /// Sets this headline's property `key` to `timestamp`, written like in
+    /// the org export
+    pub fn set_property_timestamp(self, key: &str, timestamp: &Timestamp<'_>, org: &mut Org<'_>) {
+        let mut value = Vec::new();
+        // writing to a `Vec` never fails
+        write_timestamp(&mut value, timestamp).unwrap();
+        self.set_property(key, String::from_utf8_lossy(&value).into_owned(), org);
+    }
+
+    /// Sets this headline's property `key` to `number`, without a
+    /// fractional part if it's an integer, like `37` or `1.5`
+    pub fn set_property_number(self, key: &str, number: f64, org: &mut Org<'_>) {
+        self.set_property(key, format_number(number), org);
+    }
+
+    /// Sets this headline's property `key` to `items` joined with `separator`
+    pub fn set_property_list(self, key: &str, items: &[&str], separator: &str, org: &mut Org<'_>) {
+        self.set_property(key, items.join(separator), org);
+    }
+
+    /// Returns the contents of this headline's section in a canonical form
+    ///
+    /// The section is written with the default org handler, then trailing
+    /// whitespaces are removed, and runs of blank lines are collapsed into
+    /// one, so reformatting a section doesn't change its canonical form.
+    /// Title, planning, property drawer and child headlines aren't included.
+    pub fn canonical_body(self, org: &Org<'_>) -> String {
+        let section = match self.section_node {
+            Some(section) => section,
+            None => return String::new(),
+        };
+
+        let mut writer = Vec::new();
+        for edge in section.traverse(&org.arena) {
+            match edge {
+                NodeEdge::Start(node) => {
+                    DefaultOrgHandler.start(&mut writer, org.arena[node].get())
+                }
+                NodeEdge::End(node) => DefaultOrgHandler.end(&mut writer, org.arena[node].get()),
+            }
+            // writing to a `Vec` never fails
+            .unwrap();
+        }
+        let text = String::from_utf8_lossy(&writer);
+
+        let mut canonical = String::with_capacity(text.len());
+        let mut blank = false;
+        for line in text.lines().map(str::trim_end) {
+            if line.is_empty() {
+                blank = !canonical.is_empty();
+            } else {
+                if blank {
+                    canonical.push('\n');
+                    blank = false;
+                }
+                canonical.push_str(line);
+                canonical.push('\n');
+            }
+        }
+        canonical
+    }
+
     pub fn set_title_content<'a, S: Into<Cow<'a, str>>>(self, content: S, org: &mut Org<'a>) {
         let content = content.into();
+        let change = org
+            .change_path(self.node)
+            .map(|path| (path, self.title(org).raw.to_string()));
 
+        org.forget_source(self.title_node);
         let children: Vec<_> = self.title_node.children(&org.arena).collect();
         for child in children {
             child.detach(&mut org.arena);
         }
 
-        match &content {
+        let timestamps = match &content {
             Cow::Borrowed(content) => parse_container(
                 &mut org.arena,
                 Container::Inline {
@@ -79,14 +324,41 @@ impl HeadlineNode {
                 },
                 &ParseConfig::default(),
             ),
-        }
+        };
+        org.prune_timestamps(self.node);
+        org.index_timestamps(timestamps);
 
         self.title_mut(org).raw = content;
 
+        if let Some((path, before)) = change {
+            let after = self.title(org).raw.to_string();
+            org.log_change(path, ChangeKind::Title { before, after });
+        }
+
         org.debug_validate();
     }
 
+    /// Sets the title to plain `text`, escaped with [`escape::headline_title`]
+    /// so it isn't parsed as markup
+    ///
+    /// [`escape::headline_title`]: escape/fn.headline_title.html
+    pub fn set_title_text<'a>(self, text: &'a str, org: &mut Org<'a>) {
+        self.set_title_content(escape::headline_title(text), org);
+    }
+
+    /// Sets the section to plain `text`, escaped with [`escape::text`] so
+    /// it isn't parsed as markup
+    ///
+    /// [`escape::text`]: escape/fn.text.html
+    pub fn set_section_text<'a>(self, text: &'a str, org: &mut Org<'a>) {
+        self.set_section_content(escape::text(text), org);
+    }
+
     pub fn set_section_content<'a, S: Into<Cow<'a, str>>>(self, content: S, org: &mut Org<'a>) {
+        let change = org
+            .change_path(self.node)
+            .map(|path| (path, org.org_text(self.section_node)));
+
         let node = if let Some(node) = self.section_node {
             let children: Vec<_> = node.children(&org.arena).collect();
             for child in children {
@@ -98,8 +370,9 @@ impl HeadlineNode {
             self.node.append(node, &mut org.arena);
             node
         };
+        org.forget_source(node);
 
-        match content.into() {
+        let timestamps = match content.into() {
             Cow::Borrowed(content) => parse_container(
                 &mut org.arena,
                 Container::Block { node, content },
@@ -110,17 +383,25 @@ impl HeadlineNode {
                 Container::Block { node, content },
                 &ParseConfig::default(),
             ),
+        };
+        org.prune_timestamps(self.node);
+        org.index_timestamps(timestamps);
+
+        if let Some((path, before)) = change {
+            let after = org.org_text(node);
+            org.log_change(path, ChangeKind::Section { before, after });
         }
 
         org.debug_validate();
     }
 
     pub fn parent(self, org: &Org<'_>) -> Option<HeadlineNode> {
-        org.arena[self.node].parent().map(|node| {
+        // top level headlines are children of the document
+        org.arena[self.node].parent().and_then(|node| {
             if let Element::Headline { level } = *org.arena[node].get() {
-                HeadlineNode::new(node, level, org)
+                Some(HeadlineNode::new(node, level, org))
             } else {
-                unreachable!()
+                None
             }
         })
     }
@@ -135,12 +416,31 @@ impl HeadlineNode {
         })
     }
 
+    /// Return the children of this headline, sorted by `order`
+    pub fn children_ordered(self, order: OrderBy<'_>, org: &Org<'_>) -> Vec<HeadlineNode> {
+        sort_headlines(self.children(org).collect(), order, org)
+    }
+
+    /// Set the property `key` of the children of this headline to `spacing`,
+    /// `2 * spacing`, etc., in their current order by `key`
+    ///
+    /// The gaps leave room to insert a headline between two others by only
+    /// setting its own property.
+    pub fn assign_order_properties(self, key: &str, spacing: usize, org: &mut Org<'_>) {
+        let children = self.children_ordered(OrderBy::Property(key), org);
+        assign_order_properties(children, key, spacing, org);
+    }
+
     pub fn previous_headline(self, org: &Org<'_>) -> Option<HeadlineNode> {
         if let Some(node) = org.arena[self.node].previous_sibling() {
             if let Element::Headline { level } = *org.arena[node].get() {
                 Some(HeadlineNode::new(node, level, org))
             } else {
-                debug_assert_eq!(node, self.section_node.unwrap());
+                // the title or section of the parent
+                debug_assert!(matches!(
+                    org.arena[node].get(),
+                    Element::Title(_) | Element::Section
+                ));
                 None
             }
         } else {
@@ -160,14 +460,81 @@ impl HeadlineNode {
         }
     }
 
+    /// Return the position of this headline in document order, see
+    /// [`Org::order_key`]
+    ///
+    /// [`Org::order_key`]: struct.Org.html#method.order_key
+    pub fn order_key(self, org: &Org<'_>) -> Option<OrderKey> {
+        org.order_key(self.node)
+    }
+
     pub fn detach(self, org: &mut Org<'_>) {
+        org.log_remove(self.node);
+        org.forget_parent_source(self.node);
         self.node.detach(&mut org.arena);
 
         org.debug_validate();
     }
 
     pub fn is_detached(self, org: &Org<'_>) -> bool {
-        self.parent(&org).is_none()
+        org.arena[self.node].parent().is_none()
+    }
+
+    /// Sets the level of this detached headline, shifting the levels of its
+    /// descendants by the same amount
+    ///
+    /// Headlines are inserted at a level fitting their new siblings, so a
+    /// subtree is moved elsewhere by detaching it, setting its level and
+    /// inserting it again.
+    ///
+    /// ```rust
+    /// use orgize::Org;
+    ///
+    /// let mut org = Org::parse("* Inbox\n** Task\n*** Note\n* Project\n");
+    /// let headlines: Vec<_> = org.headlines().collect();
+    /// let (mut task, project) = (headlines[1], headlines[3]);
+    ///
+    /// task.detach(&mut org);
+    /// assert!(org.document().append(task, &mut org).is_err());
+    /// task.set_level(2, &mut org).unwrap();
+    /// project.append(task, &mut org).unwrap();
+    ///
+    /// let mut writer = Vec::new();
+    /// org.org(&mut writer).unwrap();
+    /// assert_eq!(
+    ///     String::from_utf8(writer).unwrap(),
+    ///     "* Inbox\n* Project\n** Task\n*** Note\n"
+    /// );
+    /// ```
+    pub fn set_level(&mut self, level: usize, org: &mut Org<'_>) -> Result<(), OrgizeError> {
+        if !self.is_detached(org) {
+            return Err(OrgizeError::Detached { at: self.node });
+        }
+        if level == 0 {
+            return Err(OrgizeError::HeadlineLevel {
+                min: Some(1),
+                max: None,
+                at: self.node,
+            });
+        }
+
+        let descendants: Vec<_> = self.node.descendants(&org.arena).collect();
+        for node in descendants {
+            match org.arena[node].get_mut() {
+                Element::Headline { level: current }
+                | Element::Title(Title { level: current, .. }) => {
+                    // descendants are deeper than this headline, so levels stay positive
+                    *current = *current + level - self.level;
+                }
+                _ => continue,
+            }
+            org.forget_source(node);
+        }
+        self.level = level;
+
+        org.debug_validate();
+
+        Ok(())
     }
 
     fn check_level(self, min: usize, max: Option<usize>) -> Result<(), OrgizeError> {
@@ -191,13 +558,15 @@ impl HeadlineNode {
             return Err(OrgizeError::Detached { at: headline.node });
         }
 
-        if let Some(last_headline) = org.headlines().last() {
+        if let Some(last_headline) = self.children(org).last() {
             headline.check_level(self.level + 1, Some(last_headline.level))?;
         } else {
             headline.check_level(self.level + 1, None)?;
         }
 
         self.node.append(headline.node, &mut org.arena);
+        org.forget_parent_source(headline.node);
+        org.log_insert(headline.node);
 
         org.debug_validate();
 
@@ -220,6 +589,8 @@ impl HeadlineNode {
         } else {
             self.title_node.insert_after(headline.node, &mut org.arena);
         }
+        org.forget_parent_source(headline.node);
+        org.log_insert(headline.node);
 
         org.debug_validate();
 
@@ -242,6 +613,8 @@ impl HeadlineNode {
         }
 
         self.node.insert_before(headline.node, &mut org.arena);
+        org.forget_parent_source(headline.node);
+        org.log_insert(headline.node);
 
         org.debug_validate();
 
@@ -266,11 +639,169 @@ impl HeadlineNode {
         }
 
         self.node.insert_after(headline.node, &mut org.arena);
+        org.forget_parent_source(headline.node);
+        org.log_insert(headline.node);
 
         org.debug_validate();
 
         Ok(())
     }
+
+    /// Decreases the level of this headline and its descendants by one, like
+    /// `org-promote-subtree`, or by two under `#+STARTUP: odd`
+    ///
+    /// The following siblings which are deeper than the promoted headline
+    /// become its last children, and it becomes the next sibling of its
+    /// parent if it reaches the level of its parent, so the document is
+    /// parsed back into the same tree. Returns an error for a top level
+    /// headline.
+    ///
+    /// ```rust
+    /// use orgize::Org;
+    ///
+    /// let mut org = Org::parse("* Project\n** Task\n*** Note\n** Later\n");
+    /// let mut task = org.headlines().nth(1).unwrap();
+    ///
+    /// task.promote(&mut org).unwrap();
+    /// assert_eq!(task.title(&org).level, 1);
+    ///
+    /// let mut writer = Vec::new();
+    /// org.org(&mut writer).unwrap();
+    /// assert_eq!(
+    ///     String::from_utf8(writer).unwrap(),
+    ///     "* Project\n* Task\n** Note\n** Later\n"
+    /// );
+    /// ```
+    pub fn promote(&mut self, org: &mut Org<'_>) -> Result<(), OrgizeError> {
+        if self.level == 1 {
+            return Err(OrgizeError::HeadlineLevel {
+                min: Some(2),
+                max: None,
+                at: self.node,
+            });
+        }
+
+        let level = self.startup_options(org).change_level(self.level, -1);
+        let parent = self.parent(org);
+        let previous = self.previous_headline(org);
+        let mut followers = Vec::new();
+        let mut next = self.next_headline(org);
+        while let Some(headline) = next.filter(|headline| headline.level > level) {
+            followers.push(headline);
+            next = headline.next_headline(org);
+        }
+
+        self.detach(org);
+        for follower in &followers {
+            follower.detach(org);
+        }
+        self.set_level(level, org)?;
+
+        match parent {
+            Some(parent) if parent.level == level => parent.insert_after(*self, org)?,
+            _ => self.insert_at(previous, parent, org)?,
+        }
+        for follower in followers {
+            self.append(follower, org)?;
+        }
+
+        Ok(())
+    }
+
+    /// Increases the level of this headline and its descendants by one, like
+    /// `org-demote-subtree`, or by two under `#+STARTUP: odd`
+    ///
+    /// The demoted headline becomes the last child of its previous sibling if
+    /// it gets deeper than it, so the document is parsed back into the same
+    /// tree. Use [`demote_within`] to limit the depth of the descendants.
+    ///
+    /// ```rust
+    /// use orgize::Org;
+    ///
+    /// let mut org = Org::parse("* Project\n* Task\n** Note\n");
+    /// let mut task = org.headlines().nth(1).unwrap();
+    ///
+    /// task.demote(&mut org).unwrap();
+    /// assert_eq!(task.title(&org).level, 2);
+    /// assert_eq!(task.parent(&org).unwrap().title(&org).raw, "Project");
+    ///
+    /// let mut writer = Vec::new();
+    /// org.org(&mut writer).unwrap();
+    /// assert_eq!(
+    ///     String::from_utf8(writer).unwrap(),
+    ///     "* Project\n** Task\n*** Note\n"
+    /// );
+    /// ```
+    ///
+    /// [`demote_within`]: #method.demote_within
+    pub fn demote(&mut self, org: &mut Org<'_>) -> Result<(), OrgizeError> {
+        let level = self.startup_options(org).change_level(self.level, 1);
+        let parent = self.parent(org);
+        let previous = self.previous_headline(org);
+
+        self.detach(org);
+        self.set_level(level, org)?;
+
+        match previous {
+            Some(previous) if previous.level < level => previous.append(*self, org),
+            _ => self.insert_at(previous, parent, org),
+        }
+    }
+
+    /// Demotes this headline like [`demote`], unless it would push one of
+    /// its descendants deeper than `max_level`
+    ///
+    /// The document is left unchanged when an error is returned, which
+    /// points at the deepest headline.
+    ///
+    /// [`demote`]: #method.demote
+    pub fn demote_within(
+        &mut self,
+        max_level: usize,
+        org: &mut Org<'_>,
+    ) -> Result<(), OrgizeError> {
+        let startup = self.startup_options(org);
+        let deepest = self
+            .node
+            .descendants(&org.arena)
+            .filter_map(|node| match org.arena[node].get() {
+                Element::Headline { level } => Some((node, *level)),
+                _ => None,
+            })
+            .max_by_key(|&(_, level)| level);
+
+        match deepest {
+            Some((node, level)) if startup.change_level(level, 1) > max_level => {
+                Err(OrgizeError::HeadlineLevel {
+                    min: None,
+                    max: Some(max_level),
+                    at: node,
+                })
+            }
+            _ => self.demote(org),
+        }
+    }
+
+    /// Returns the startup options of the document, which give the number
+    /// of levels a headline is promoted or demoted by
+    fn startup_options(self, org: &Org<'_>) -> StartupOptions {
+        SettingsResolver::new(org, &DEFAULT_CONFIG).startup_options()
+    }
+
+    /// Inserts this detached headline after `previous`, or as the first
+    /// child of `parent`, or of the document if it's `None`
+    fn insert_at(
+        self,
+        previous: Option<HeadlineNode>,
+        parent: Option<HeadlineNode>,
+        org: &mut Org<'_>,
+    ) -> Result<(), OrgizeError> {
+        match (previous, parent) {
+            (Some(previous), _) => previous.insert_after(self, org),
+            (None, Some(parent)) => parent.prepend(self, org),
+            (None, None) => org.document().prepend(self, org),
+        }
+    }
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -303,7 +834,108 @@ impl DocumentNode {
         })
     }
 
+    /// Return the top level headlines, sorted by `order`
+    pub fn children_ordered(self, order: OrderBy<'_>, org: &Org<'_>) -> Vec<HeadlineNode> {
+        sort_headlines(self.children(org).collect(), order, org)
+    }
+
+    /// Set the property `key` of the top level headlines, see
+    /// [`HeadlineNode::assign_order_properties`]
+    ///
+    /// [`HeadlineNode::assign_order_properties`]: struct.HeadlineNode.html#method.assign_order_properties
+    pub fn assign_order_properties(self, key: &str, spacing: usize, org: &mut Org<'_>) {
+        let children = self.children_ordered(OrderBy::Property(key), org);
+        assign_order_properties(children, key, spacing, org);
+    }
+
+    /// Returns the properties of the property drawer at the top of the
+    /// document, which may only be preceded by comments
+    ///
+    /// Keys and `:KEY+:` lines are handled like the ones of headlines, see
+    /// [`Title::property`].
+    ///
+    /// ```rust
+    /// use orgize::Org;
+    ///
+    /// let org = Org::parse("# comment\n:PROPERTIES:\n:ID: doc\n:TAGS: a\n:TAGS+: b\n:END:\n* a\n");
+    /// let document = org.document();
+    ///
+    /// assert_eq!(document.property("id", &org).as_deref(), Some("doc"));
+    /// assert_eq!(document.property("TAGS", &org).as_deref(), Some("a b"));
+    /// assert_eq!(document.properties(&org).len(), 2);
+    /// ```
+    ///
+    /// [`Title::property`]: elements/struct.Title.html#method.property
+    pub fn properties<'a: 'b, 'b>(self, org: &'b Org<'a>) -> HashMap<Cow<'b, str>, Cow<'b, str>> {
+        let drawer = self.section_node.and_then(|section| {
+            section
+                .children(&org.arena)
+                .find(|&node| !matches!(org.arena[node].get(), Element::Comment { .. }))
+                .filter(|&node| match org.arena[node].get() {
+                    Element::Drawer(drawer) => drawer.name.eq_ignore_ascii_case("PROPERTIES"),
+                    _ => false,
+                })
+        });
+
+        match drawer.map(|drawer| org.arena[drawer].get()) {
+            Some(Element::Drawer(drawer)) => drawer
+                .properties
+                .iter()
+                .map(|(key, value)| (Cow::Borrowed(&**key), Cow::Borrowed(&**value)))
+                .collect(),
+            _ => HashMap::new(),
+        }
+    }
+
+    /// Returns the value of the document's property `key`, see
+    /// [`DocumentNode::properties`]
+    ///
+    /// [`DocumentNode::properties`]: #method.properties
+    pub fn property<'a: 'b, 'b>(self, key: &str, org: &'b Org<'a>) -> Option<Cow<'b, str>> {
+        self.properties(org)
+            .into_iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, value)| value)
+    }
+
+    /// Returns the value of the first `#+ARCHIVE:` keyword of the document,
+    /// the location where archived entries are moved, like `%s_archive::`
+    ///
+    /// Entries aren't moved by orgize.
+    ///
+    /// ```rust
+    /// use orgize::Org;
+    ///
+    /// let org = Org::parse("#+ARCHIVE: archive.org::* Done\n* a\n");
+    /// assert_eq!(org.document().archive_location(&org), Some("archive.org::* Done"));
+    ///
+    /// let org = Org::parse("* a\n");
+    /// assert_eq!(org.document().archive_location(&org), None);
+    /// ```
+    pub fn archive_location<'a: 'b, 'b>(self, org: &'b Org<'a>) -> Option<&'b str> {
+        org.root
+            .descendants(&org.arena)
+            .find_map(|node| match org.arena[node].get() {
+                Element::Keyword(keyword) if keyword.key.eq_ignore_ascii_case("ARCHIVE") => {
+                    Some(keyword.value.as_ref())
+                }
+                _ => None,
+            })
+    }
+
+    /// Sets the zeroth section to plain `text`, see
+    /// [`HeadlineNode::set_section_text`]
+    ///
+    /// [`HeadlineNode::set_section_text`]: struct.HeadlineNode.html#method.set_section_text
+    pub fn set_section_text<'a>(self, text: &'a str, org: &mut Org<'a>) {
+        self.set_section_content(escape::text(text), org);
+    }
+
     pub fn set_section_content<'a, S: Into<Cow<'a, str>>>(self, content: S, org: &mut Org<'a>) {
+        let change = org
+            .change_path(org.root)
+            .map(|path| (path, org.org_text(self.section_node)));
+
         let node = if let Some(node) = self.section_node {
             let children: Vec<_> = node.children(&org.arena).collect();
             for child in children {
@@ -315,8 +947,9 @@ impl DocumentNode {
             org.root.append(node, &mut org.arena);
             node
         };
+        org.forget_source(node);
 
-        match content.into() {
+        let timestamps = match content.into() {
             Cow::Borrowed(content) => parse_container(
                 &mut org.arena,
                 Container::Block { node, content },
@@ -327,6 +960,13 @@ impl DocumentNode {
                 Container::Block { node, content },
                 &ParseConfig::default(),
             ),
+        };
+        org.prune_timestamps(org.root);
+        org.index_timestamps(timestamps);
+
+        if let Some((path, before)) = change {
+            let after = org.org_text(node);
+            org.log_change(path, ChangeKind::Section { before, after });
         }
 
         org.debug_validate();
@@ -337,11 +977,13 @@ impl DocumentNode {
             return Err(OrgizeError::Detached { at: headline.node });
         }
 
-        if let Some(last_headline) = org.headlines().last() {
+        if let Some(last_headline) = self.children(org).last() {
             headline.check_level(1, Some(last_headline.level))?;
         }
 
         org.root.append(headline.node, &mut org.arena);
+        org.forget_parent_source(headline.node);
+        org.log_insert(headline.node);
 
         org.debug_validate();
 
@@ -362,9 +1004,67 @@ impl DocumentNode {
         } else {
             org.root.prepend(headline.node, &mut org.arena);
         }
+        org.forget_parent_source(headline.node);
+        org.log_insert(headline.node);
 
         org.debug_validate();
 
         Ok(())
     }
 }
+
+/// Order of sibling headlines
+#[derive(Copy, Clone, Debug)]
+pub enum OrderBy<'s> {
+    /// Order of the text
+    Document,
+    /// Ascending integer value of a property, like `ORDER`
+    ///
+    /// Headlines without this property, or with a value which isn't an
+    /// integer, come last. Ties are broken by document order.
+    Property(&'s str),
+    /// Highest priority first
+    ///
+    /// Headlines without priority come last. Ties are broken by document
+    /// order.
+    Priority,
+}
+
+fn sort_headlines(
+    mut headlines: Vec<HeadlineNode>,
+    order: OrderBy<'_>,
+    org: &Org<'_>,
+) -> Vec<HeadlineNode> {
+    // sort_by_key is stable, so ties keep document order
+    match order {
+        OrderBy::Document => (),
+        OrderBy::Property(key) => {
+            headlines.sort_by_key(|headline| match order_property(headline.title(org), key) {
+                Some(value) => (false, value),
+                None => (true, 0),
+            })
+        }
+        OrderBy::Priority => headlines.sort_by_key(|headline| match headline.title(org).priority {
+            Some(priority) => (false, Some(priority)),
+            None => (true, None),
+        }),
+    }
+    headlines
+}
+
+fn order_property(title: &Title, key: &str) -> Option<i64> {
+    title
+        .property(key)
+        .and_then(|value| value.trim().parse().ok())
+}
+
+fn assign_order_properties(
+    headlines: Vec<HeadlineNode>,
+    key: &str,
+    spacing: usize,
+    org: &mut Org<'_>,
+) {
+    for (i, headline) in headlines.into_iter().enumerate() {
+        headline.set_property(key, ((i + 1) * spacing).to_string(), org);
+    }
+}
@@ -1,15 +1,44 @@
 use indextree::{Arena, NodeEdge, NodeId};
+use std::borrow::Cow;
+use std::collections::HashMap;
 use std::io::{Error, Write};
+use std::ops::Range;
 
+use crate::agenda::{agenda, AgendaEntry, AgendaOptions};
+use crate::captioned::{captioned, is_figure, is_listing, is_table, Captioned};
+use crate::changes::Change;
+use crate::code_block::{code_blocks, CodeBlock};
 use crate::config::{ParseConfig, DEFAULT_CONFIG};
-use crate::elements::{Element, Title};
+use crate::decode::decode;
+#[cfg(feature = "export-markdown")]
+use crate::elements::Table;
+use crate::elements::{Element, RawHtml, Title};
+use crate::entry::Entry;
+use crate::export::macros::expand_macros;
 use crate::export::*;
+use crate::line_index::LineIndex;
+use crate::logbook::{normalize_logbooks, NormalizeOptions, NormalizeReport};
 use crate::node::{DocumentNode, HeadlineNode};
-use crate::parsers::{parse_container, Container};
+use crate::parsers::{is_planning_line, parse_container, Container, OwnedArena, SpanArena};
+use crate::radio::resolve_radio_targets;
+use crate::settings::{ExportSettings, SettingsResolver};
+use crate::OrgizeError;
 
 pub struct Org<'a> {
     pub(crate) arena: Arena<Element<'a>>,
     pub(crate) root: NodeId,
+    /// Parsed text, empty if this struct was created with `Org::new`
+    pub(crate) text: &'a str,
+    /// Byte ranges in `text` of the elements which haven't been modified since parsing
+    pub(crate) spans: HashMap<NodeId, Range<usize>>,
+    /// Timestamp objects of each headline, and of the document under `root`,
+    /// indexed while parsing
+    pub(crate) timestamps: HashMap<NodeId, Vec<NodeId>>,
+    /// Changes logged since `Org::enable_change_log`, `None` if disabled
+    pub(crate) changes: Option<Vec<Change>>,
+    /// Config the document was parsed with, which parses the expansions of
+    /// `Org::expand_macros`
+    pub(crate) config: ParseConfig,
 }
 
 #[derive(Debug)]
@@ -18,16 +47,36 @@ pub enum Event<'a, 'b> {
     End(&'b Element<'a>),
 }
 
+/// Position of an element in document order, see [`Org::order_key`]
+///
+/// It's the path from the document to the element, made of the index of
+/// each element among its siblings, so an element compares after its
+/// ancestors and the elements before it.
+///
+/// [`Org::order_key`]: struct.Org.html#method.order_key
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct OrderKey(Vec<usize>);
+
 impl<'a> Org<'a> {
     /// Create a new empty Org struct
     pub fn new() -> Org<'static> {
         let mut arena = Arena::new();
         let root = arena.new_node(Element::Document);
 
-        Org { arena, root }
+        Org {
+            arena,
+            root,
+            text: "",
+            spans: HashMap::new(),
+            timestamps: HashMap::new(),
+            changes: None,
+            config: ParseConfig::default(),
+        }
     }
 
     /// Create a new Org struct from parsing `text`, using the default ParseConfig
+    ///
+    /// This function never panics, no matter what `text` contains.
     pub fn parse(text: &'a str) -> Org<'a> {
         Org::parse_with_config(text, &DEFAULT_CONFIG)
     }
@@ -35,27 +84,66 @@ impl<'a> Org<'a> {
     /// Create a new Org struct from parsing `text`, using a custom ParseConfig
     pub fn parse_with_config(content: &'a str, config: &ParseConfig) -> Org<'a> {
         let mut org = Org::new();
+        org.text = content;
+        org.spans.insert(org.root, 0..content.len());
+        org.config = config.clone();
 
-        parse_container(
-            &mut org.arena,
+        let timestamps = parse_container(
+            &mut SpanArena::new(&mut org.arena, &mut org.spans, content),
             Container::Document {
                 content,
                 node: org.root,
             },
             config,
         );
+        org.index_timestamps(timestamps);
 
         org.debug_validate();
 
         org
     }
 
+    /// Create a new Org struct from parsing `bytes`, using the default ParseConfig
+    ///
+    /// `bytes` are decoded with [`decode`]. If they had to be converted, the
+    /// returned struct owns its contents and [`Org::source_of`] returns `None`.
+    ///
+    /// [`decode`]: fn.decode.html
+    /// [`Org::source_of`]: #method.source_of
+    pub fn parse_lossy(bytes: &'a [u8]) -> Org<'a> {
+        match decode(bytes) {
+            Cow::Borrowed(text) => Org::parse(text),
+            Cow::Owned(text) => {
+                let mut org = Org::new();
+
+                let timestamps = parse_container(
+                    &mut OwnedArena::new(&mut org.arena),
+                    Container::Document {
+                        content: &text,
+                        node: org.root,
+                    },
+                    &DEFAULT_CONFIG,
+                );
+                org.index_timestamps(timestamps);
+
+                org.debug_validate();
+
+                org
+            }
+        }
+    }
+
     /// Return a DocumentNode
     pub fn document(&self) -> DocumentNode {
         DocumentNode::new(self)
     }
 
-    /// Return an iterator of HeadlineNode
+    /// Return an iterator of HeadlineNode, in document order
+    ///
+    /// The nth headline is the one of the nth `Event::Start(Element::Title)`
+    /// of [`Org::iter`].
+    ///
+    /// [`Org::iter`]: #method.iter
     pub fn headlines<'b>(&'b self) -> impl Iterator<Item = HeadlineNode> + 'b {
         self.root
             .descendants(&self.arena)
@@ -66,16 +154,547 @@ impl<'a> Org<'a> {
             })
     }
 
+    /// Return an iterator of the metadata of every headline, in document order
+    pub fn entries<'b>(&'b self) -> impl Iterator<Item = Entry<'a, 'b>> + 'b {
+        self.headlines()
+            .map(move |headline| Entry::new(headline, self))
+    }
+
+    /// Return the agenda items of every headline, in document order
+    ///
+    /// Planning timestamps come before the timestamps of the title and
+    /// section, which are looked up in an index built while parsing.
+    pub fn agenda<'b>(&'b self, options: &AgendaOptions) -> Vec<AgendaEntry<'a, 'b>> {
+        agenda(self, options)
+    }
+
+    /// Return an iterator of the tables, in document order
+    pub fn tables<'b>(&'b self) -> impl Iterator<Item = Captioned<'a, 'b>> + 'b {
+        captioned(self, is_table)
+    }
+
+    /// Return an iterator of the figures, i.e. paragraphs containing nothing
+    /// but an image link, in document order
+    pub fn figures<'b>(&'b self) -> impl Iterator<Item = Captioned<'a, 'b>> + 'b {
+        captioned(self, is_figure)
+    }
+
+    /// Return an iterator of the source blocks, in document order
+    pub fn listings<'b>(&'b self) -> impl Iterator<Item = Captioned<'a, 'b>> + 'b {
+        captioned(self, is_listing)
+    }
+
+    /// Return an iterator of the source blocks with their name, header
+    /// arguments and position, in document order
+    pub fn code_blocks<'b>(&'b self) -> impl Iterator<Item = CodeBlock<'a, 'b>> + 'b {
+        code_blocks(self)
+    }
+
+    /// Return the keys of the citations, without duplicates, in the order
+    /// they're first cited
+    ///
+    /// ```rust
+    /// use orgize::Org;
+    ///
+    /// let org = Org::parse("[cite:@doe2020; see @smith, p. 3]\n* Later\n[cite/t:@smith]");
+    ///
+    /// assert_eq!(org.cited_keys(), ["doe2020", "smith"]);
+    /// ```
+    pub fn cited_keys(&self) -> Vec<&str> {
+        let mut keys = Vec::new();
+        for node in self.root.descendants(&self.arena) {
+            if let Element::Citation(citation) = self.arena[node].get() {
+                for key in citation.keys() {
+                    if !keys.contains(&key) {
+                        keys.push(key);
+                    }
+                }
+            }
+        }
+        keys
+    }
+
+    /// Return the export options set by the `#+OPTIONS:` keywords of the
+    /// document, the last value of an option wins
+    ///
+    /// ```rust
+    /// use orgize::{Levels, Org};
+    ///
+    /// let org = Org::parse("#+OPTIONS: toc:nil num:t\n#+OPTIONS: H:2 num:1\n* Title");
+    /// let options = org.export_settings();
+    ///
+    /// assert_eq!(options.toc, Some(Levels::None));
+    /// assert_eq!(options.num, Some(Levels::UpTo(1)));
+    /// assert_eq!(options.headline_levels, Some(2));
+    /// ```
+    pub fn export_settings(&self) -> ExportSettings {
+        SettingsResolver::new(self, &DEFAULT_CONFIG).export_settings(None)
+    }
+
     /// Return a refrence to underlay arena
     pub fn arena(&self) -> &Arena<Element<'a>> {
         &self.arena
     }
 
     /// Return a mutual reference to underlay arena
+    ///
+    /// Since changes made through the arena can't be tracked, this also
+    /// forgets the source text of all elements, see [`Org::source_of`].
+    ///
+    /// [`Org::source_of`]: #method.source_of
     pub fn arena_mut(&mut self) -> &mut Arena<Element<'a>> {
+        self.spans.clear();
         &mut self.arena
     }
 
+    /// Drop detached elements from the underlay arena and release unused memory
+    ///
+    /// Detached headlines and the elements removed by `set_title_content` or
+    /// `set_section_content` stay in the arena until this is called, so it's
+    /// worth calling after heavy mutation.
+    ///
+    /// Since elements are moved to a new arena, this invalidates all the
+    /// `NodeId`s, `HeadlineNode`s and `DocumentNode`s obtained before.
+    pub fn shrink_to_fit(&mut self) {
+        let (arena, root, new_nodes) = self.rebuild(|element| element);
+
+        self.arena = arena;
+        self.root = root;
+        self.spans = self
+            .spans
+            .iter()
+            .filter_map(|(node, span)| Some((*new_nodes.get(node)?, span.clone())))
+            .collect();
+        self.timestamps = remap_timestamps(&self.timestamps, &new_nodes);
+
+        self.debug_validate();
+    }
+
+    /// Convert into an Org struct owning its contents, which outlives the
+    /// parsed text
+    ///
+    /// Like `shrink_to_fit`, this drops detached elements and invalidates all
+    /// the `NodeId`s obtained before. [`Org::source_of`] and [`Org::span_of`]
+    /// return `None` afterwards.
+    ///
+    /// Short strings repeated all over a document, like tags, TODO keywords,
+    /// property keys, keyword keys and languages, are shared by all the owned
+    /// documents instead of copied into every element.
+    ///
+    /// [`Org::source_of`]: #method.source_of
+    /// [`Org::span_of`]: #method.span_of
+    pub fn into_owned(mut self) -> Org<'static> {
+        let (arena, root, new_nodes) = self.rebuild(Element::into_owned);
+
+        let org = Org {
+            arena,
+            root,
+            text: "",
+            spans: HashMap::new(),
+            timestamps: remap_timestamps(&self.timestamps, &new_nodes),
+            changes: self.changes.take(),
+            config: std::mem::take(&mut self.config),
+        };
+
+        org.debug_validate();
+
+        org
+    }
+
+    /// Moves the elements attached to the root to a new arena, converting
+    /// them with `convert`
+    ///
+    /// Returns the new arena, the new root and the new id of every element.
+    #[allow(clippy::type_complexity)]
+    fn rebuild<'b, F>(
+        &mut self,
+        mut convert: F,
+    ) -> (Arena<Element<'b>>, NodeId, HashMap<NodeId, NodeId>)
+    where
+        F: FnMut(Element<'a>) -> Element<'b>,
+    {
+        let edges: Vec<_> = self.root.traverse(&self.arena).collect();
+
+        let mut arena = Arena::with_capacity(edges.len() / 2);
+        let mut new_nodes = HashMap::with_capacity(edges.len() / 2);
+        let mut parents: Vec<NodeId> = Vec::new();
+        let mut root = None;
+
+        for edge in edges {
+            match edge {
+                NodeEdge::Start(node) => {
+                    let element = std::mem::replace(self.arena[node].get_mut(), Element::Document);
+                    let new_node = arena.new_node(convert(element));
+                    if let Some(parent) = parents.last() {
+                        parent.append(new_node, &mut arena);
+                    }
+                    new_nodes.insert(node, new_node);
+                    parents.push(new_node);
+                }
+                NodeEdge::End(_) => {
+                    root = parents.pop();
+                }
+            }
+        }
+
+        (arena, root.unwrap(), new_nodes)
+    }
+
+    /// Return the exact text `node` was parsed from, including its children
+    ///
+    /// Returns `None` if the element wasn't parsed from a text, or if it or
+    /// any of its descendants has been modified since parsing.
+    pub fn source_of(&self, node: NodeId) -> Option<&'a str> {
+        self.spans.get(&node).map(|span| &self.text[span.clone()])
+    }
+
+    /// Return the byte range in the parsed text of `node`, see [`Org::source_of`]
+    ///
+    /// Use [`Org::line_index`] to convert it to lines and columns.
+    ///
+    /// [`Org::source_of`]: #method.source_of
+    /// [`Org::line_index`]: #method.line_index
+    pub fn span_of(&self, node: NodeId) -> Option<Range<usize>> {
+        self.spans.get(&node).cloned()
+    }
+
+    /// Replace the macros with their expansion, parsed as org objects
+    ///
+    /// Macros are defined by `#+MACRO: name template` keywords, where `$1`
+    /// to `$n` are replaced with the arguments, separated by commas which
+    /// are escaped as `\,` inside arguments. Expansions can contain other
+    /// macros. Besides the `title`, `author` and `email` macros, which
+    /// expand to the values of these keywords, there are:
+    ///
+    /// + `{{{date(FORMAT)}}}`, the first `#+DATE:`, formatted with the
+    ///   `strftime` specifiers of `FORMAT` if it's a timestamp and with the
+    ///   `chrono` feature
+    /// + `{{{keyword(KEY)}}}`, the values of the `#+KEY:` keywords
+    /// + `{{{modification-time(FORMAT)}}}`, which is left to the html
+    ///   export, see `HtmlExportOptions::modification_time`
+    ///
+    /// Undefined macros, and templates evaluating lisp, are replaced with
+    /// nothing, see [`Org::try_expand_macros`]. Expansions are parsed with the
+    /// config the document was parsed with.
+    ///
+    /// ```rust
+    /// use orgize::Org;
+    ///
+    /// let mut org = Org::parse(
+    ///     "#+AUTHOR: Jane\n#+MACRO: greet /Hello $1/\n\n\
+    ///      {{{greet(World\\, {{{author}}})}}}{{{undefined}}}\n",
+    /// );
+    /// org.expand_macros();
+    ///
+    /// # #[cfg(feature = "export-html")] {
+    /// let mut writer = Vec::new();
+    /// org.html(&mut writer).unwrap();
+    /// assert!(String::from_utf8(writer).unwrap().contains("<p><i>Hello World, Jane</i></p>"));
+    /// # }
+    /// ```
+    ///
+    /// [`Org::try_expand_macros`]: #method.try_expand_macros
+    pub fn expand_macros(&mut self) {
+        // undefined macros aren't errors
+        let _ = expand_macros(self, false);
+    }
+
+    /// Replace the macros with their expansion like [`Org::expand_macros`],
+    /// or fail on the first undefined macro, leaving the document unchanged
+    ///
+    /// [`Org::expand_macros`]: #method.expand_macros
+    pub fn try_expand_macros(&mut self) -> Result<(), OrgizeError> {
+        expand_macros(self, true)
+    }
+
+    /// Link the occurrences of the radio targets, like `<<<important
+    /// term>>>`, in the text of the document and return the number of links
+    ///
+    /// Like in org-mode, occurrences anywhere in the document are linked,
+    /// before or after the target. They are whole words, compared
+    /// case-insensitively, and the words of a target match words separated
+    /// by any whitespace, even a newline. The text of code, verbatim and
+    /// existing links isn't searched, and longer targets win over the
+    /// targets they contain.
+    ///
+    /// Occurrences become links to `#` followed by `RadioTarget::id`, the id
+    /// of the anchor the html export writes for the target. The org export
+    /// writes them as bracket links, so call it on a copy of the document to
+    /// keep its text.
+    ///
+    /// ```rust
+    /// use orgize::Org;
+    ///
+    /// let mut org = Org::parse(
+    ///     "An <<<important term>>> is defined once.\n\n\
+    ///      Important terms are not linked, but an IMPORTANT\nterm is, not ~important term~.\n",
+    /// );
+    /// assert_eq!(org.resolve_radio_targets(), 1);
+    ///
+    /// # #[cfg(feature = "export-html")] {
+    /// let mut writer = Vec::new();
+    /// org.html(&mut writer).unwrap();
+    /// assert_eq!(
+    ///     String::from_utf8(writer).unwrap(),
+    ///     "<main><section><p>An <a id=\"radio-important-term\">important term</a> is defined once.</p>\
+    ///      <p>Important terms are not linked, but an \
+    ///      <a href=\"#radio-important-term\">IMPORTANT\nterm</a> is, not <code>important term</code>.</p>\
+    ///      </section></main>"
+    /// );
+    /// # }
+    /// ```
+    pub fn resolve_radio_targets(&mut self) -> usize {
+        resolve_radio_targets(self)
+    }
+
+    /// Clean up the clocks of the `LOGBOOK` drawers and return what changed
+    ///
+    /// The `=> H:MM` durations of closed clocks are recomputed from their
+    /// timestamps, then identical clocks are removed, as well as zero-length
+    /// ones if `remove_zero_length` is set. Clocks are sorted newest-first,
+    /// like org-mode writes them, and overlapping ones are merged if
+    /// `merge_overlapping` is set. Clocks with datetimes which don't exist
+    /// are only deduplicated.
+    ///
+    /// ```rust
+    /// use orgize::{NormalizeOptions, Org};
+    ///
+    /// let mut org = Org::parse(
+    ///     "* Work\n:LOGBOOK:\n\
+    ///      CLOCK: [2023-05-01 Mon 09:00]--[2023-05-01 Mon 10:00] =>  2:00\n\
+    ///      CLOCK: [2023-05-02 Tue 09:00]--[2023-05-02 Tue 09:00] =>  0:00\n\
+    ///      CLOCK: [2023-05-03 Wed 14:00]--[2023-05-03 Wed 15:30] =>  1:30\n\
+    ///      CLOCK: [2023-05-01 Mon 09:00]--[2023-05-01 Mon 10:00] =>  1:00\n\
+    ///      :END:\n",
+    /// );
+    /// let report = org.normalize_logbooks(&NormalizeOptions::default());
+    /// assert_eq!((report.durations, report.duplicates, report.zero_length), (1, 1, 1));
+    ///
+    /// let mut writer = Vec::new();
+    /// org.org(&mut writer).unwrap();
+    /// assert_eq!(
+    ///     String::from_utf8(writer).unwrap(),
+    ///     "* Work\n:LOGBOOK:\n\
+    ///      CLOCK: [2023-05-03 Wed 14:00]--[2023-05-03 Wed 15:30] => 1:30\n\
+    ///      CLOCK: [2023-05-01 Mon 09:00]--[2023-05-01 Mon 10:00] => 1:00\n\
+    ///      :END:\n"
+    /// );
+    /// ```
+    pub fn normalize_logbooks(&mut self, options: &NormalizeOptions) -> NormalizeReport {
+        normalize_logbooks(self, options)
+    }
+
+    /// Start logging the changes made by the mutation methods of
+    /// `HeadlineNode` and `DocumentNode`, see [`Org::take_changes`]
+    ///
+    /// Setting properties, titles and sections, and inserting and detaching
+    /// headlines are logged, moves being a removal followed by an insertion.
+    /// Changes to detached headlines aren't, they're part of the subtree
+    /// logged when the headline is inserted. Neither are the changes made
+    /// through `title_mut` or `arena_mut`, nor bulk rewrites like
+    /// `expand_macros` or `normalize_logbooks`.
+    ///
+    /// [`Org::take_changes`]: #method.take_changes
+    pub fn enable_change_log(&mut self) {
+        if self.changes.is_none() {
+            self.changes = Some(Vec::new());
+        }
+    }
+
+    /// Return the changes logged since the change log was enabled or the
+    /// last call, and clear them
+    ///
+    /// Returns nothing if the change log isn't enabled.
+    pub fn take_changes(&mut self) -> Vec<Change> {
+        self.changes
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
+    /// Replay `changes`, logged on another copy of the same document, see
+    /// [`Org::enable_change_log`]
+    ///
+    /// Each change first checks that the element it changes is in the state
+    /// it was logged from, e.g. that the title it replaces is the same.
+    /// Otherwise the document diverged, and it returns an error pointing at
+    /// the index of the change in `changes`, the previous ones staying
+    /// applied. Replayed changes are logged like the others.
+    ///
+    /// ```rust
+    /// use orgize::Org;
+    ///
+    /// let text = "* Inbox\n** Call Bob\n* Projects\n";
+    /// let mut org = Org::parse(text);
+    /// org.enable_change_log();
+    ///
+    /// let headlines: Vec<_> = org.headlines().collect();
+    /// let (inbox, call, projects) = (headlines[0], headlines[1], headlines[2]);
+    /// call.set_title_content("Call Alice", &mut org);
+    /// call.detach(&mut org);
+    /// projects.append(call, &mut org).unwrap();
+    /// inbox.set_property("ARCHIVE", "yes", &mut org);
+    /// let changes = org.take_changes();
+    /// assert_eq!(changes.len(), 4);
+    ///
+    /// let mut copy = Org::parse(text);
+    /// copy.apply_changes(&changes).unwrap();
+    ///
+    /// let mut writer = Vec::new();
+    /// copy.org(&mut writer).unwrap();
+    /// assert_eq!(
+    ///     String::from_utf8(writer).unwrap(),
+    ///     "* Inbox\n:PROPERTIES:\n:ARCHIVE: yes\n:END:\n* Projects\n** Call Alice\n"
+    /// );
+    ///
+    /// // the title replaced by the first change isn't "Call Bob" anymore
+    /// let mut diverged = Org::parse("* Inbox\n** Call Carol\n* Projects\n");
+    /// assert!(diverged.apply_changes(&changes).is_err());
+    /// ```
+    pub fn apply_changes(&mut self, changes: &[Change]) -> Result<(), OrgizeError> {
+        self.replay_changes(changes)
+    }
+
+    /// Return the position of `node` in document order, or `None` if it
+    /// isn't part of the document, e.g. a detached headline
+    ///
+    /// Keys change when elements before `node` are inserted or removed.
+    ///
+    /// ```rust
+    /// use orgize::Org;
+    ///
+    /// let mut org = Org::parse("* a\n** b\n* c\n");
+    /// let headlines: Vec<_> = org.headlines().collect();
+    /// let key = |index: usize, org: &Org| org.order_key(headlines[index].headline_node());
+    /// assert!(key(0, &org) < key(1, &org) && key(1, &org) < key(2, &org));
+    ///
+    /// headlines[0].detach(&mut org);
+    /// assert_eq!(key(0, &org), None);
+    ///
+    /// headlines[2].insert_after(headlines[0], &mut org).unwrap();
+    /// assert!(key(2, &org) < key(0, &org) && key(0, &org) < key(1, &org));
+    /// ```
+    pub fn order_key(&self, node: NodeId) -> Option<OrderKey> {
+        if self.arena[node].is_removed() {
+            return None;
+        }
+        let mut path = Vec::new();
+        for ancestor in node.ancestors(&self.arena) {
+            if ancestor == self.root {
+                path.reverse();
+                return Some(OrderKey(path));
+            }
+            path.push(ancestor.preceding_siblings(&self.arena).count() - 1);
+        }
+        None
+    }
+
+    /// Build a `LineIndex` of the parsed text
+    ///
+    /// It takes linear time, so keep it around when converting many offsets.
+    pub fn line_index(&self) -> LineIndex {
+        LineIndex::new(self.text)
+    }
+
+    /// Adds the timestamp objects `nodes` to the index of their headline
+    pub(crate) fn index_timestamps(&mut self, nodes: Vec<NodeId>) {
+        for node in nodes {
+            let owner = self.timestamp_owner(node);
+            self.timestamps.entry(owner).or_default().push(node);
+        }
+    }
+
+    /// Drops the timestamps of `owner` which were removed or moved from the
+    /// index, after its content is replaced
+    pub(crate) fn prune_timestamps(&mut self, owner: NodeId) {
+        let mut timestamps = self.timestamps.remove(&owner).unwrap_or_default();
+        timestamps.retain(|&node| self.timestamp_owner(node) == owner);
+        if !timestamps.is_empty() {
+            self.timestamps.insert(owner, timestamps);
+        }
+    }
+
+    /// Returns the headline containing the timestamp `node`, or the root
+    ///
+    /// Detached timestamps are their own owner.
+    pub(crate) fn timestamp_owner(&self, node: NodeId) -> NodeId {
+        if self.arena[node].is_removed() {
+            return node;
+        }
+        node.ancestors(&self.arena)
+            .find(|&node| {
+                matches!(
+                    self.arena[node].get(),
+                    Element::Headline { .. } | Element::Document
+                )
+            })
+            .unwrap_or(node)
+    }
+
+    /// Forget the source text of `node` and its ancestors, after it's modified
+    pub(crate) fn forget_source(&mut self, node: NodeId) {
+        for node in node.ancestors(&self.arena) {
+            self.spans.remove(&node);
+        }
+    }
+
+    /// Forget the source text of the ancestors of `node`, after it's inserted or detached
+    pub(crate) fn forget_parent_source(&mut self, node: NodeId) {
+        if let Some(parent) = self.arena[node].parent() {
+            self.forget_source(parent);
+        }
+    }
+
+    /// Inserts the html `html` after the element `node`, returning the
+    /// inserted `RawHtml` element, see [`RawHtml`]
+    ///
+    /// `node` is a block-level element, like a paragraph, since the org
+    /// export writes the html as an export block.
+    ///
+    /// ```rust
+    /// use orgize::{Element, Org};
+    ///
+    /// let mut org = Org::parse("* Math\nThe area is:\n");
+    /// let paragraph = org
+    ///     .arena()
+    ///     .iter()
+    ///     .find(|node| *node.get() == Element::Paragraph)
+    ///     .and_then(|node| org.arena().get_node_id(node))
+    ///     .unwrap();
+    /// org.insert_raw_html_after(paragraph, "<math><mi>π</mi><msup><mi>r</mi><mn>2</mn></msup></math>");
+    ///
+    /// # #[cfg(feature = "export-html")] {
+    /// let mut writer = Vec::new();
+    /// org.html(&mut writer).unwrap();
+    /// assert_eq!(
+    ///     String::from_utf8(writer).unwrap(),
+    ///     "<main><h1>Math</h1><section><p>The area is:</p>\
+    ///      <math><mi>π</mi><msup><mi>r</mi><mn>2</mn></msup></math></section></main>"
+    /// );
+    /// # }
+    ///
+    /// let mut writer = Vec::new();
+    /// org.org(&mut writer).unwrap();
+    /// assert_eq!(
+    ///     String::from_utf8(writer).unwrap(),
+    ///     "* Math\nThe area is:\n\n#+BEGIN_EXPORT html\n\
+    ///      <math><mi>π</mi><msup><mi>r</mi><mn>2</mn></msup></math>\n#+END_EXPORT\n"
+    /// );
+    /// ```
+    ///
+    /// [`RawHtml`]: elements/struct.RawHtml.html
+    pub fn insert_raw_html_after<S: Into<Cow<'a, str>>>(
+        &mut self,
+        node: NodeId,
+        html: S,
+    ) -> NodeId {
+        let raw_html = self.arena.new_node(Element::RawHtml(RawHtml::new(html)));
+        node.insert_after(raw_html, &mut self.arena);
+        self.forget_parent_source(raw_html);
+        self.debug_validate();
+        raw_html
+    }
+
     /// Create a new headline and return it's HeadlineNode
     pub fn new_headline(&mut self, title: Title<'a>) -> HeadlineNode {
         let level = title.level;
@@ -93,7 +712,97 @@ impl<'a> Org<'a> {
         headline_node
     }
 
-    /// Return an iterator of Event
+    /// Copies `headline` of `other` and its descendants into this document,
+    /// and returns the copy, which is detached
+    ///
+    /// ```rust
+    /// use orgize::Org;
+    ///
+    /// let notes = Org::parse("* Notes\n** TODO Call <2024-01-02 Tue>\n").into_owned();
+    /// let mut org = Org::parse("* Inbox\n");
+    ///
+    /// let call = org.graft(&notes, notes.headlines().nth(1).unwrap());
+    /// let inbox = org.headlines().next().unwrap();
+    /// inbox.append(call, &mut org).unwrap();
+    /// assert_eq!(call.timestamps(&org).count(), 1);
+    ///
+    /// let mut writer = Vec::new();
+    /// org.org(&mut writer).unwrap();
+    /// assert_eq!(
+    ///     String::from_utf8(writer).unwrap(),
+    ///     "* Inbox\n** TODO Call <2024-01-02 Tue>\n"
+    /// );
+    /// ```
+    pub fn graft(&mut self, other: &Org<'a>, headline: HeadlineNode) -> HeadlineNode {
+        let mut new_nodes = HashMap::new();
+        let mut parents: Vec<NodeId> = Vec::new();
+        let mut root = None;
+
+        for edge in headline.node.traverse(&other.arena) {
+            match edge {
+                NodeEdge::Start(node) => {
+                    let new_node = self.arena.new_node(other.arena[node].get().clone());
+                    if let Some(parent) = parents.last() {
+                        parent.append(new_node, &mut self.arena);
+                    }
+                    new_nodes.insert(node, new_node);
+                    parents.push(new_node);
+                }
+                NodeEdge::End(_) => {
+                    root = parents.pop();
+                }
+            }
+        }
+        self.timestamps
+            .extend(remap_timestamps(&other.timestamps, &new_nodes));
+
+        self.debug_validate();
+
+        HeadlineNode::new(root.unwrap(), headline.level, self)
+    }
+
+    /// Returns `true` if both documents have the same tree of elements,
+    /// ignoring spans and detached elements
+    ///
+    /// Documents with `\n` or `\r\n` line endings, with or without a final
+    /// newline, are structurally equal.
+    ///
+    /// ```rust
+    /// use orgize::Org;
+    ///
+    /// let lf = Org::parse("* Title :tag:\nText\n#+BEGIN_SRC rust\nmain();\n#+END_SRC\n");
+    /// let crlf = Org::parse("* Title :tag:\r\nText\r\n#+BEGIN_SRC rust\r\nmain();\r\n#+END_SRC");
+    ///
+    /// assert!(lf.structural_eq(&crlf));
+    /// assert!(!lf.structural_eq(&Org::parse("* Title\nText\n")));
+    /// ```
+    pub fn structural_eq(&self, other: &Org<'_>) -> bool {
+        let mut edges = self.root.traverse(&self.arena);
+        let mut other_edges = other.root.traverse(&other.arena);
+
+        loop {
+            match (edges.next(), other_edges.next()) {
+                (None, None) => return true,
+                (Some(NodeEdge::Start(node)), Some(NodeEdge::Start(other_node))) => {
+                    if self.arena[node].get() != other.arena[other_node].get() {
+                        return false;
+                    }
+                }
+                (Some(NodeEdge::End(_)), Some(NodeEdge::End(_))) => (),
+                _ => return false,
+            }
+        }
+    }
+
+    /// Return an iterator of Event, in document order
+    ///
+    /// Document order is the order of the output of the serializers, like
+    /// [`Org::org`], whichever order elements were parsed or inserted in:
+    /// the start of an element comes before its children, which come before
+    /// its end. Compare the positions of elements with [`Org::order_key`].
+    ///
+    /// [`Org::org`]: #method.org
+    /// [`Org::order_key`]: #method.order_key
     pub fn iter<'b>(&'b self) -> impl Iterator<Item = Event<'a, 'b>> + 'b {
         self.root.traverse(&self.arena).map(move |edge| match edge {
             NodeEdge::Start(node) => Event::Start(self.arena[node].get()),
@@ -101,26 +810,272 @@ impl<'a> Org<'a> {
         })
     }
 
+    #[cfg(feature = "export-html")]
     pub fn html<W: Write>(&self, wrtier: W) -> Result<(), Error> {
-        self.html_with_handler(wrtier, &mut DefaultHtmlHandler)
+        self.html_with_handler(wrtier, &mut DefaultHtmlHandler::default())
     }
 
-    pub fn html_with_handler<W, H, E>(&self, mut writer: W, handler: &mut H) -> Result<(), E>
+    #[cfg(feature = "export-html")]
+    pub fn html_with_handler<W, H, E>(&self, writer: W, handler: &mut H) -> Result<(), E>
     where
         W: Write,
         E: From<Error>,
         H: HtmlHandler<E>,
     {
-        for event in self.iter() {
-            match event {
-                Event::Start(element) => handler.start(&mut writer, element)?,
-                Event::End(element) => handler.end(&mut writer, element)?,
-            }
+        let analysis = self.analyze_for_html(&HtmlExportOptions::default());
+        self.html_with_analysis(writer, handler, &analysis)
+    }
+
+    /// Compute the numbers, ids and links needed by the html export
+    ///
+    /// The result can be passed to several render calls, e.g. to render the
+    /// whole document, some of its subtrees and its table of contents.
+    ///
+    /// ```rust
+    /// use orgize::export::{DefaultHtmlHandler, HtmlExportOptions};
+    /// use orgize::Org;
+    ///
+    /// let org = Org::parse("* One\n* Two\n** Three");
+    /// let analysis = org.analyze_for_html(&HtmlExportOptions {
+    ///     headline_anchors: true,
+    ///     ..Default::default()
+    /// });
+    ///
+    /// let mut toc = Vec::new();
+    /// org.html_toc_with_analysis(&mut toc, &mut DefaultHtmlHandler::default(), &analysis, Some(1))
+    ///     .unwrap();
+    /// assert_eq!(
+    ///     String::from_utf8(toc).unwrap(),
+    ///     "<ul><li><a href=\"#headline-1\">One</a></li><li><a href=\"#headline-2\">Two</a></li></ul>"
+    /// );
+    ///
+    /// let three = org.headlines().nth(2).unwrap();
+    /// assert_eq!(analysis.section_number(three.headline_node()), Some("2.1"));
+    /// ```
+    #[cfg(feature = "export-html")]
+    pub fn analyze_for_html(&self, options: &HtmlExportOptions) -> HtmlExportAnalysis {
+        HtmlExportAnalysis::new(self, options)
+    }
+
+    /// Render the document with an analysis returned by `Org::analyze_for_html`
+    #[cfg(feature = "export-html")]
+    pub fn html_with_analysis<W, H, E>(
+        &self,
+        writer: W,
+        handler: &mut H,
+        analysis: &HtmlExportAnalysis,
+    ) -> Result<(), E>
+    where
+        W: Write,
+        E: From<Error>,
+        H: HtmlHandler<E>,
+    {
+        analysis.render(self, self.root, writer, handler)
+    }
+
+    /// Render the document in a full html page, with its `#+TITLE:` and
+    /// `#+DATE:`
+    ///
+    /// Where the date is written and how it's formatted is set by `options`.
+    ///
+    /// ```rust
+    /// use orgize::export::HtmlExportOptions;
+    /// use orgize::Org;
+    ///
+    /// let org = Org::parse("#+TITLE: Notes\n#+DATE: 2020-01-02\n\nUpdated {{{date}}}.");
+    ///
+    /// let mut writer = Vec::new();
+    /// org.html_page(&mut writer, &HtmlExportOptions::default()).unwrap();
+    /// let page = String::from_utf8(writer).unwrap();
+    ///
+    /// assert!(page.contains("<title>Notes</title>"));
+    /// assert!(page.contains("<meta name=\"date\" content=\"2020-01-02\">"));
+    /// assert!(page.contains("<p>Updated 2020-01-02.</p>"));
+    /// ```
+    #[cfg(feature = "export-html")]
+    pub fn html_page<W: Write>(&self, writer: W, options: &HtmlExportOptions) -> Result<(), Error> {
+        let analysis = self.analyze_for_html(options);
+        self.html_page_with_analysis(writer, &mut DefaultHtmlHandler::default(), &analysis)
+    }
+
+    /// Render the document in a full html page with an analysis returned by
+    /// `Org::analyze_for_html`
+    #[cfg(feature = "export-html")]
+    pub fn html_page_with_analysis<W, H, E>(
+        &self,
+        writer: W,
+        handler: &mut H,
+        analysis: &HtmlExportAnalysis,
+    ) -> Result<(), E>
+    where
+        W: Write,
+        E: From<Error>,
+        H: HtmlHandler<E>,
+    {
+        analysis.render_page(self, writer, handler)
+    }
+
+    /// Render `node` and its descendants with an analysis returned by
+    /// `Org::analyze_for_html`
+    ///
+    /// Numbers and ids are the ones of the whole document.
+    #[cfg(feature = "export-html")]
+    pub fn html_fragment_with_analysis<W, H, E>(
+        &self,
+        node: NodeId,
+        writer: W,
+        handler: &mut H,
+        analysis: &HtmlExportAnalysis,
+    ) -> Result<(), E>
+    where
+        W: Write,
+        E: From<Error>,
+        H: HtmlHandler<E>,
+    {
+        analysis.render(self, node, writer, handler)
+    }
+
+    /// Render the table of contents of the document, up to level `depth` if any,
+    /// with an analysis returned by `Org::analyze_for_html`
+    ///
+    /// The headings only have matching anchors or ids if `headline_anchors`
+    /// or `slug_ids` was enabled.
+    #[cfg(feature = "export-html")]
+    pub fn html_toc_with_analysis<W, H, E>(
+        &self,
+        writer: W,
+        handler: &mut H,
+        analysis: &HtmlExportAnalysis,
+        depth: Option<usize>,
+    ) -> Result<(), E>
+    where
+        W: Write,
+        E: From<Error>,
+        H: HtmlHandler<E>,
+    {
+        analysis.write_headline_toc(self, depth, writer, handler)
+    }
+
+    /// Returns the tree of the exported headlines up to level `max_depth`,
+    /// with the ids used by `Org::html`
+    ///
+    /// It's the data of the table of contents, e.g. to build a navigation
+    /// menu. Headlines tagged `noexport` and their subtrees are left out.
+    ///
+    /// ```rust
+    /// use orgize::Org;
+    ///
+    /// let org = Org::parse("* TODO *Intro*\n** Details\n*** Notes\n* Draft :noexport:\n");
+    /// let outline = org.outline(2);
+    ///
+    /// assert_eq!(outline.len(), 1);
+    /// assert_eq!(outline[0].title, "Intro");
+    /// assert_eq!(outline[0].id, "headline-1");
+    /// assert_eq!(outline[0].todo.as_ref().unwrap(), "TODO");
+    /// assert_eq!(outline[0].children[0].title, "Details");
+    /// assert!(outline[0].children[0].children.is_empty());
+    /// ```
+    #[cfg(feature = "export-html")]
+    pub fn outline(&self, max_depth: usize) -> Vec<OutlineEntry> {
+        let analysis = self.analyze_for_html(&HtmlExportOptions::default());
+        self.outline_with_analysis(&analysis, max_depth)
+    }
+
+    /// Returns the tree of the exported headlines up to level `max_depth`,
+    /// with an analysis returned by `Org::analyze_for_html`
+    #[cfg(feature = "export-html")]
+    pub fn outline_with_analysis(
+        &self,
+        analysis: &HtmlExportAnalysis,
+        max_depth: usize,
+    ) -> Vec<OutlineEntry> {
+        analysis.outline(self, max_depth)
+    }
+
+    /// Render the document like `Org::html`, rendering each top-level
+    /// subtree on the rayon thread pool
+    ///
+    /// Small documents are rendered sequentially.
+    #[cfg(all(feature = "export-html", feature = "rayon"))]
+    pub fn html_parallel<W: Write>(
+        &self,
+        writer: W,
+        options: &HtmlExportOptions,
+    ) -> Result<(), Error> {
+        let analysis = self.analyze_for_html(options);
+        self.html_parallel_with_analysis(writer, &DefaultHtmlHandler::default(), &analysis)
+    }
+
+    /// Render the document like `Org::html_with_analysis`, rendering each
+    /// top-level subtree on the rayon thread pool with a clone of `handler`
+    ///
+    /// Numbers and ids come from `analysis`, so they're the same as in a
+    /// sequential rendering.
+    #[cfg(all(feature = "export-html", feature = "rayon"))]
+    pub fn html_parallel_with_analysis<W, H, E>(
+        &self,
+        mut writer: W,
+        handler: &H,
+        analysis: &HtmlExportAnalysis,
+    ) -> Result<(), E>
+    where
+        W: Write,
+        E: From<Error> + Send,
+        H: HtmlHandler<E> + Clone + Send + Sync,
+    {
+        use rayon::prelude::*;
+
+        // below this number of elements, spawning tasks costs more than it saves
+        const MIN_PARALLEL_ELEMENTS: usize = 10_000;
+
+        let children: Vec<_> = self.root.children(&self.arena).collect();
+
+        if children.len() < 2 || self.arena.len() < MIN_PARALLEL_ELEMENTS {
+            return self.html_with_analysis(writer, &mut handler.clone(), analysis);
         }
 
+        let chunks = children
+            .par_iter()
+            .map(|&child| {
+                let mut buffer = Vec::new();
+                analysis.render(self, child, &mut buffer, &mut handler.clone())?;
+                Ok(buffer)
+            })
+            .collect::<Result<Vec<_>, E>>()?;
+
+        let mut handler = handler.clone();
+        let document = self.arena[self.root].get();
+
+        handler.set_context(self.root, analysis);
+        handler.start(&mut writer, document)?;
+        analysis.write_document_toc(self, &mut writer, &mut handler)?;
+        for chunk in chunks {
+            writer.write_all(&chunk)?;
+        }
+        handler.set_context(self.root, analysis);
+        analysis.write_footnotes(self, &mut writer, &mut handler)?;
+        handler.end(&mut writer, document)?;
+
         Ok(())
     }
 
+    /// Export the document with any backend, see [`ExportHandler`]
+    ///
+    /// [`ExportHandler`]: export/trait.ExportHandler.html
+    pub fn export_with_handler<W, H, E>(
+        &self,
+        writer: W,
+        handler: &mut H,
+        options: &ExportOptions,
+    ) -> Result<(), E>
+    where
+        W: Write,
+        E: From<Error>,
+        H: ExportHandler<E>,
+    {
+        crate::export::driver::export(self, writer, handler, options)
+    }
+
     pub fn org<W: Write>(&self, wrtier: W) -> Result<(), Error> {
         self.org_with_handler(wrtier, &mut DefaultOrgHandler)
     }
@@ -131,15 +1086,495 @@ impl<'a> Org<'a> {
         E: From<Error>,
         H: OrgHandler<E>,
     {
-        for event in self.iter() {
-            match event {
-                Event::Start(element) => handler.start(&mut writer, element)?,
-                Event::End(element) => handler.end(&mut writer, element)?,
+        self.org_subtree_with_handler(self.root, &mut writer, handler)
+    }
+
+    /// Writes `node` and its descendants as org, with the default handler
+    pub(crate) fn org_subtree<W: Write>(&self, node: NodeId, mut writer: W) -> Result<(), Error> {
+        self.org_subtree_with_handler(node, &mut writer, &mut DefaultOrgHandler)
+    }
+
+    fn org_subtree_with_handler<W, H, E>(
+        &self,
+        node: NodeId,
+        mut writer: W,
+        handler: &mut H,
+    ) -> Result<(), E>
+    where
+        W: Write,
+        E: From<Error>,
+        H: OrgHandler<E>,
+    {
+        let root = node;
+        let mut edges = root.traverse(&self.arena);
+
+        while let Some(edge) = edges.next() {
+            match edge {
+                NodeEdge::Start(node) => {
+                    let column = Some(node)
+                        .filter(|node| *node != root)
+                        .and_then(|node| self.list_item_column(node));
+                    if let Some((column, on_bullet_line)) = column {
+                        // the lines written by the handler start at the first
+                        // column, which would end the list
+                        let mut buffer = Vec::new();
+                        self.org_subtree_with_handler(node, &mut buffer, handler)?;
+                        for (i, line) in buffer.split_inclusive(|&c| c == b'\n').enumerate() {
+                            let indented =
+                                line.len() >= column && line[0..column].iter().all(|&c| c == b' ');
+                            let blank = line.iter().all(u8::is_ascii_whitespace);
+                            let after_bullet = i == 0 && on_bullet_line;
+                            if !(after_bullet || indented || blank) {
+                                write!(writer, "{:1$}", "", column)?;
+                            }
+                            writer.write_all(line)?;
+                        }
+                        for edge in &mut edges {
+                            if edge == NodeEdge::End(node) {
+                                break;
+                            }
+                        }
+                    } else if node != root && self.starts_headline_section(node) {
+                        // a blank line keeps a paragraph looking like a
+                        // planning line from being parsed as one
+                        let mut buffer = Vec::new();
+                        self.org_subtree_with_handler(node, &mut buffer, handler)?;
+                        let line = buffer.split(|&c| c == b'\n').next().unwrap_or_default();
+                        if is_planning_line(String::from_utf8_lossy(line).trim()) {
+                            writeln!(writer)?;
+                        }
+                        writer.write_all(&buffer)?;
+                        for edge in &mut edges {
+                            if edge == NodeEdge::End(node) {
+                                break;
+                            }
+                        }
+                    } else {
+                        handler.start(&mut writer, self.arena[node].get())?;
+                    }
+                }
+                NodeEdge::End(node) => self.org_end(&mut writer, node, handler)?,
             }
         }
 
         Ok(())
     }
+
+    /// Returns `true` if `node` starts the section of a headline, right
+    /// after its title, planning and properties
+    fn starts_headline_section(&self, node: NodeId) -> bool {
+        let section = match self.arena[node].parent() {
+            Some(section) if self.arena[section].first_child() == Some(node) => section,
+            _ => return false,
+        };
+        matches!(self.arena[section].get(), Element::Section)
+            && !matches!(self.arena[node].get(), Element::Raw { .. })
+            && matches!(
+                self.arena[section]
+                    .parent()
+                    .map(|parent| self.arena[parent].get()),
+                Some(Element::Headline { .. })
+            )
+    }
+
+    /// Returns the column of the contents of the list item `node` is in, and
+    /// whether `node` starts on the line of the bullet, if it's written
+    /// without indentation, unlike paragraphs and lists
+    fn list_item_column(&self, node: NodeId) -> Option<(usize, bool)> {
+        if matches!(
+            self.arena[node].get(),
+            Element::Paragraph | Element::List(_) | Element::ListItemTag
+        ) {
+            return None;
+        }
+
+        let item = match self.arena[self.arena[node].parent()?].get() {
+            Element::ListItem(item) => item,
+            _ => return None,
+        };
+
+        let on_bullet_line = match self.arena[node].previous_sibling() {
+            Some(sibling) => matches!(self.arena[sibling].get(), Element::ListItemTag),
+            None => {
+                item.bullet.ends_with(' ')
+                    || item.counter.is_some()
+                    || item.checkbox.is_some()
+                    || item.tag.is_some()
+            }
+        };
+
+        Some((
+            item.indent + item.bullet.trim_end().len() + 1,
+            on_bullet_line,
+        ))
+    }
+
+    /// Export the document as markdown, see [`DefaultMarkdownHandler`]
+    ///
+    /// Subtrees are excluded like with the default [`ExportOptions`], use
+    /// [`MarkdownBackend`] to export with other options.
+    ///
+    /// ```rust
+    /// use orgize::Org;
+    ///
+    /// let org = Org::parse("* Notes\nSee *this* and [[https://x.org][the docs]].\n\n- a\n  1. b\n");
+    ///
+    /// let mut writer = Vec::new();
+    /// org.markdown(&mut writer).unwrap();
+    /// assert_eq!(
+    ///     String::from_utf8(writer).unwrap(),
+    ///     "# Notes\n\nSee **this** and [the docs](https://x.org).\n\n- a\n  1. b\n"
+    /// );
+    /// ```
+    ///
+    /// [`DefaultMarkdownHandler`]: export/struct.DefaultMarkdownHandler.html
+    /// [`ExportOptions`]: export/struct.ExportOptions.html
+    /// [`MarkdownBackend`]: export/struct.MarkdownBackend.html
+    #[cfg(feature = "export-markdown")]
+    pub fn markdown<W: Write>(&self, writer: W) -> Result<(), Error> {
+        self.markdown_with_handler(writer, &mut DefaultMarkdownHandler::default())
+    }
+
+    #[cfg(feature = "export-markdown")]
+    pub fn markdown_with_handler<W, H, E>(&self, writer: W, handler: &mut H) -> Result<(), E>
+    where
+        W: Write,
+        E: From<Error>,
+        H: MarkdownHandler<E>,
+    {
+        self.export_with_handler(
+            writer,
+            &mut MarkdownBackend::new(self, handler),
+            &ExportOptions::default(),
+        )
+    }
+
+    /// Returns the item `node` of an ordered list with its number in its
+    /// bullet, see [`markdown_item_number`]
+    ///
+    /// `numbers` holds the number of the last item of each list, updated
+    /// when the item starts.
+    ///
+    /// [`markdown_item_number`]: export/fn.markdown_item_number.html
+    #[cfg(feature = "export-markdown")]
+    pub(crate) fn numbered_item(
+        &self,
+        node: NodeId,
+        numbers: &mut HashMap<NodeId, (usize, char)>,
+        start: bool,
+    ) -> Option<Element<'a>> {
+        let item = match self.arena[node].get() {
+            Element::ListItem(item) => item,
+            _ => return None,
+        };
+        let list = self.arena[node].parent()?;
+        match self.arena[list].get() {
+            Element::List(list) if list.ordered => (),
+            _ => return None,
+        }
+
+        let (number, delimiter) = if start {
+            let number = markdown_item_number(item, numbers.get(&list).copied());
+            numbers.insert(list, number);
+            number
+        } else {
+            *numbers.get(&list)?
+        };
+        let mut item = item.clone();
+        item.bullet = format!("{}{}", number, delimiter).into();
+        Some(Element::ListItem(item))
+    }
+
+    /// Returns `true` if `node` is followed by an ordered list whose first
+    /// item isn't numbered 1 in markdown
+    #[cfg(feature = "export-markdown")]
+    pub(crate) fn precedes_numbered_list(&self, node: NodeId) -> bool {
+        let list = match self.arena[node].next_sibling() {
+            Some(list) => list,
+            None => return false,
+        };
+        match self.arena[list].get() {
+            Element::List(list) if list.ordered => (),
+            _ => return false,
+        }
+        match list
+            .children(&self.arena)
+            .next()
+            .map(|item| self.arena[item].get())
+        {
+            Some(Element::ListItem(item)) => markdown_item_number(item, None).0 != 1,
+            _ => false,
+        }
+    }
+
+    /// Returns the number of columns of the table if `node` is its first row
+    #[cfg(feature = "export-markdown")]
+    pub(crate) fn table_header_columns(&self, node: NodeId) -> Option<usize> {
+        let is_row = |node: &NodeId| match self.arena[*node].get() {
+            Element::TableRow(row) => !row.is_rule(),
+            _ => false,
+        };
+
+        let table = self.arena[node].parent()?;
+        match self.arena[table].get() {
+            Element::Table(Table::Org { .. }) => (),
+            _ => return None,
+        }
+        if table.children(&self.arena).find(is_row) != Some(node) {
+            return None;
+        }
+
+        table
+            .children(&self.arena)
+            .filter(is_row)
+            .map(|row| row.children(&self.arena).count())
+            .max()
+    }
+
+    /// Export the document like `Org::org`, but copy the elements which
+    /// haven't been modified since parsing from `original`, the parsed text
+    ///
+    /// Only the lines of modified elements differ from `original`, so the
+    /// changes stay small in version control. Modified elements are written
+    /// with the default org handler, as well as every element if `original`
+    /// isn't the parsed text.
+    ///
+    /// ```rust
+    /// use orgize::Org;
+    ///
+    /// let text = "* a   :tag:\n:PROPERTIES:\n:ID:   1\n:END:\n* b   :tag:\n";
+    /// let mut org = Org::parse(text);
+    ///
+    /// let headline = org.headlines().last().unwrap();
+    /// headline.title_mut(&mut org).properties.insert("ID".into(), "2".into());
+    ///
+    /// let mut writer = Vec::new();
+    /// org.write_org_minimal(text, &mut writer).unwrap();
+    /// assert_eq!(
+    ///     String::from_utf8(writer).unwrap(),
+    ///     "* a   :tag:\n:PROPERTIES:\n:ID:   1\n:END:\n* b :tag:\n:PROPERTIES:\n:ID: 2\n:END:\n"
+    /// );
+    /// ```
+    pub fn write_org_minimal<W: Write>(&self, original: &str, mut writer: W) -> Result<(), Error> {
+        if self.text != original {
+            return self.org(writer);
+        }
+
+        self.org_minimal(&mut writer, self.root, &mut DefaultOrgHandler)
+    }
+
+    fn org_minimal<W, H, E>(&self, w: &mut W, node: NodeId, handler: &mut H) -> Result<(), E>
+    where
+        W: Write,
+        E: From<Error>,
+        H: OrgHandler<E>,
+    {
+        if let Some(source) = self.source_of(node) {
+            w.write_all(source.as_bytes())?;
+            return Ok(());
+        }
+
+        handler.start(&mut *w, self.arena[node].get())?;
+
+        // blank lines between unmodified elements, the handler writes the
+        // separators of objects, table rows and cells
+        let blank_lines = matches!(
+            self.arena[node].get(),
+            Element::SpecialBlock(_)
+                | Element::QuoteBlock(_)
+                | Element::CenterBlock(_)
+                | Element::VerseBlock(_)
+                | Element::Document
+                | Element::Drawer(_)
+                | Element::DynBlock(_)
+                | Element::FnDef(_)
+                | Element::Headline { .. }
+                | Element::List(_)
+                | Element::ListItem(_)
+                | Element::Section
+        );
+
+        let mut previous: Option<Range<usize>> = None;
+        for child in node.children(&self.arena) {
+            let span = self.span_of(child);
+            if let (true, Some(previous)) = (blank_lines, &previous) {
+                let blank = match &span {
+                    Some(span) => &self.text[previous.end..span.start],
+                    None => self.blank_after(previous),
+                };
+                w.write_all(blank.as_bytes())?;
+            }
+            self.org_minimal(w, child, handler)?;
+            previous = span;
+        }
+        if let (true, Some(previous)) = (blank_lines, &previous) {
+            w.write_all(self.blank_after(previous).as_bytes())?;
+        }
+
+        self.org_end(w, node, handler)
+    }
+
+    /// Returns the line break and the blank lines following `span`
+    fn blank_after(&self, span: &Range<usize>) -> &'a str {
+        let tail = &self.text[span.end..];
+        let blank = &tail[0..tail.len() - tail.trim_start().len()];
+        blank.rfind('\n').map(|i| &blank[0..=i]).unwrap_or("")
+    }
+
+    fn org_end<W, H, E>(&self, mut w: W, node: NodeId, handler: &mut H) -> Result<(), E>
+    where
+        W: Write,
+        E: From<Error>,
+        H: OrgHandler<E>,
+    {
+        // an empty item or footnote definition, or an item with only a tag,
+        // would be continued by what follows it
+        let empty = node
+            .children(&self.arena)
+            .all(|child| matches!(self.arena[child].get(), Element::ListItemTag));
+        if empty {
+            match self.arena[node].get() {
+                Element::ListItem(item) => {
+                    handler.end(&mut w, self.arena[node].get())?;
+                    // otherwise the bullet is followed by a newline already
+                    if item.bullet.ends_with(' ')
+                        || item.counter.is_some()
+                        || item.checkbox.is_some()
+                        || item.tag.is_some()
+                    {
+                        writeln!(w)?;
+                    }
+                    if let Some(Element::List(list)) =
+                        self.arena[node].parent().map(|list| self.arena[list].get())
+                    {
+                        if !list.tight && self.arena[node].next_sibling().is_some() {
+                            writeln!(w)?;
+                        }
+                    }
+                    // the outermost list ends with a single blank line, which
+                    // doesn't end it after an empty item
+                    if self.ends_outer_list(node) {
+                        writeln!(w)?;
+                    }
+                    return Ok(());
+                }
+                Element::FnDef(_) => {
+                    handler.end(&mut w, self.arena[node].get())?;
+                    writeln!(w)?;
+                    return Ok(());
+                }
+                _ => (),
+            }
+        }
+
+        // a blank line after a paragraph or a table would make a tight list
+        // loose, and the end of a table without formulas is only a blank line
+        if self.in_tight_list_item(node) {
+            let mut buffer = Vec::new();
+            handler.end(&mut buffer, self.arena[node].get())?;
+            let table = matches!(self.arena[node].get(), Element::Table(_));
+            if buffer.ends_with(b"\n\n") || (table && buffer == b"\n") {
+                buffer.pop();
+            }
+            w.write_all(&buffer)?;
+            Ok(())
+        } else {
+            handler.end(w, self.arena[node].get())
+        }
+    }
+
+    /// Returns `true` if `node` is a paragraph followed by another item of a
+    /// tight list, or by something other than a paragraph in such an item,
+    /// or if it's a table ending such an item
+    fn in_tight_list_item(&self, node: NodeId) -> bool {
+        let list = match self.arena[node].get() {
+            // tables are followed by a blank line too
+            Element::Table(_) if self.arena[node].next_sibling().is_none() => {
+                self.last_in_list_item(node)
+            }
+            _ => self.paragraph_list(node),
+        };
+        match list.map(|list| self.arena[list].get()) {
+            Some(Element::List(list)) => list.tight,
+            _ => false,
+        }
+    }
+
+    /// Returns the list whose items are separated by the blank line
+    /// following `node`, if it's a paragraph followed by another of its
+    /// items, or by something other than a paragraph in such an item
+    pub(crate) fn paragraph_list(&self, node: NodeId) -> Option<NodeId> {
+        let is_paragraph = |node: NodeId| matches!(self.arena[node].get(), Element::Paragraph);
+
+        if !is_paragraph(node) {
+            return None;
+        }
+
+        if let Some(sibling) = self.arena[node].next_sibling() {
+            if is_paragraph(sibling) {
+                return None;
+            }
+            return self.arena[node]
+                .parent()
+                .and_then(|item| self.arena[item].parent());
+        }
+
+        self.last_in_list_item(node)
+    }
+
+    /// Returns the list whose items are separated by the blank line
+    /// following `node`, if it ends an item followed by another one
+    fn last_in_list_item(&self, node: NodeId) -> Option<NodeId> {
+        let mut node = node;
+        while let Some(parent) = self.arena[node].parent() {
+            match self.arena[parent].get() {
+                Element::ListItem(_) if self.arena[parent].next_sibling().is_some() => {
+                    return self.arena[parent].parent();
+                }
+                Element::ListItem(_) => node = parent,
+                Element::List(_) if self.arena[parent].next_sibling().is_none() => node = parent,
+                _ => return None,
+            }
+        }
+
+        None
+    }
+
+    /// Returns `true` if `node` is the last thing in the outermost list it's
+    /// in, whose end is written as a blank line
+    fn ends_outer_list(&self, node: NodeId) -> bool {
+        let mut node = node;
+        while let Some(parent) = self.arena[node].parent() {
+            if self.arena[node].next_sibling().is_some() {
+                return false;
+            }
+            match self.arena[parent].get() {
+                Element::List(list) if list.indent == 0 => return true,
+                Element::List(_) | Element::ListItem(_) => node = parent,
+                _ => return false,
+            }
+        }
+
+        false
+    }
+}
+
+/// Moves the timestamp index to the new ids of its elements, dropping the
+/// ones of detached elements
+fn remap_timestamps(
+    timestamps: &HashMap<NodeId, Vec<NodeId>>,
+    new_nodes: &HashMap<NodeId, NodeId>,
+) -> HashMap<NodeId, Vec<NodeId>> {
+    timestamps
+        .iter()
+        .filter_map(|(owner, nodes)| {
+            let nodes = nodes
+                .iter()
+                .filter_map(|node| new_nodes.get(node).cloned())
+                .collect();
+            Some((*new_nodes.get(owner)?, nodes))
+        })
+        .collect()
 }
 
 impl Default for Org<'static> {
@@ -159,3 +1594,45 @@ impl Serialize for Org<'_> {
         serializer.serialize_newtype_struct("Org", &Node::new(self.root, &self.arena))
     }
 }
+
+#[test]
+fn sync() {
+    fn assert_sync<T: Sync>() {}
+
+    assert_sync::<Org<'_>>();
+    #[cfg(feature = "export-html")]
+    assert_sync::<HtmlExportAnalysis>();
+}
+
+#[test]
+fn source_of() {
+    let text = "#+TITLE: spans\n\n* TODO title *bold*\n- item\n  | a | b |\n\n** child\n=code= and [[link]]\n";
+    let org = Org::parse(text);
+
+    let (mut keywords, mut headlines, mut titles, mut inlines, mut cells) =
+        (vec![], vec![], vec![], vec![], vec![]);
+
+    for node in org.root.descendants(&org.arena) {
+        let source = org.source_of(node).unwrap();
+        match org.arena[node].get() {
+            Element::Keyword(_) => keywords.push(source),
+            Element::Headline { .. } => headlines.push(source),
+            Element::Title(_) => titles.push(source),
+            Element::Bold | Element::Verbatim { .. } | Element::Link(_) => inlines.push(source),
+            Element::TableCell(_) => cells.push(source),
+            _ => (),
+        }
+    }
+
+    assert_eq!(keywords, ["#+TITLE: spans\n"]);
+    assert_eq!(
+        headlines,
+        [
+            "* TODO title *bold*\n- item\n  | a | b |\n\n** child\n=code= and [[link]]\n",
+            "** child\n=code= and [[link]]\n"
+        ]
+    );
+    assert_eq!(titles, ["* TODO title *bold*\n", "** child\n"]);
+    assert_eq!(inlines, ["*bold*", "=code=", "[[link]]"]);
+    assert_eq!(cells, [" a ", " b "]);
+}
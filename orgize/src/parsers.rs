@@ -1,21 +1,35 @@
 // parser related functions
 
 use std::borrow::Cow;
+use std::collections::HashMap;
 use std::iter::once;
 use std::marker::PhantomData;
+use std::ops::Range;
 
 use indextree::{Arena, NodeId};
 use jetscii::{bytes, BytesConst};
-use memchr::{memchr, memchr_iter};
-use nom::{bytes::complete::take_while1, combinator::verify, error::ParseError, IResult};
+use memchr::{memchr, memchr_iter, memrchr};
+use nom::{
+    bytes::complete::take_while1,
+    combinator::verify,
+    error::{ErrorKind, ParseError},
+    IResult,
+};
 
-use crate::config::ParseConfig;
+use crate::config::{ParseConfig, SyntaxCompat};
 use crate::elements::{
-    block::parse_block_element, emphasis::parse_emphasis, keyword::parse_keyword,
-    radio_target::parse_radio_target, rule::parse_rule, table::parse_table_el, BabelCall,
-    CenterBlock, Clock, CommentBlock, Cookie, Drawer, DynBlock, Element, ExampleBlock, ExportBlock,
-    FnDef, FnRef, InlineCall, InlineSrc, Keyword, Link, List, ListItem, Macros, QuoteBlock,
-    Snippet, SourceBlock, SpecialBlock, Table, TableRow, Target, Timestamp, Title, VerseBlock,
+    block::{parse_block_element, unescape_contents},
+    emphasis::parse_emphasis,
+    keyword::parse_keyword,
+    line_break::parse_line_break,
+    rule::parse_rule,
+    script::parse_script,
+    table::{parse_org_table, parse_table_el, parse_tblfm},
+    BabelCall, Caption, CenterBlock, Citation, Clock, CommentBlock, ContentMode, Cookie, Drawer,
+    DynBlock, Element, ElementKind, Entity, ExampleBlock, ExportBlock, FnDef, FnRef, InlineCall,
+    InlineSrc, Keyword, LatexFragment, Link, List, ListItem, Macros, QuoteBlock, RadioTarget,
+    Snippet, SourceBlock, SpecialBlock, Table, TableCell, TableRow, Target, Timestamp, Title,
+    VerseBlock,
 };
 
 pub trait ElementArena<'a> {
@@ -25,6 +39,12 @@ pub trait ElementArena<'a> {
         element: T,
         parent: NodeId,
     ) -> NodeId;
+    fn last_child(&self, parent: NodeId) -> Option<NodeId>;
+    /// Returns `true` if `node` is a link or is inside one, whose description
+    /// escapes brackets as `\[` and `\]`
+    fn in_link(&self, node: NodeId) -> bool;
+    /// Records the text `node` was parsed from, which must be a slice of the parsed text
+    fn set_span(&mut self, _node: NodeId, _span: &'a str) {}
 }
 
 impl<'a> ElementArena<'a> for Arena<Element<'a>> {
@@ -47,6 +67,15 @@ impl<'a> ElementArena<'a> for Arena<Element<'a>> {
             self.append_element(element, parent)
         }
     }
+
+    fn last_child(&self, parent: NodeId) -> Option<NodeId> {
+        self[parent].last_child()
+    }
+
+    fn in_link(&self, node: NodeId) -> bool {
+        node.ancestors(self)
+            .any(|node| matches!(self[node].get(), Element::Link(_)))
+    }
 }
 
 pub struct OwnedArena<'a, 'b, 'c> {
@@ -78,6 +107,58 @@ impl<'a> ElementArena<'a> for OwnedArena<'a, '_, '_> {
         self.arena
             .insert_before_last_child(element.into().into_owned(), parent)
     }
+
+    fn last_child(&self, parent: NodeId) -> Option<NodeId> {
+        self.arena[parent].last_child()
+    }
+
+    fn in_link(&self, node: NodeId) -> bool {
+        self.arena.in_link(node)
+    }
+}
+
+pub struct SpanArena<'a, 'b> {
+    arena: &'b mut Arena<Element<'a>>,
+    spans: &'b mut HashMap<NodeId, Range<usize>>,
+    text: &'a str,
+}
+
+impl<'a, 'b> SpanArena<'a, 'b> {
+    pub fn new(
+        arena: &'b mut Arena<Element<'a>>,
+        spans: &'b mut HashMap<NodeId, Range<usize>>,
+        text: &'a str,
+    ) -> SpanArena<'a, 'b> {
+        SpanArena { arena, spans, text }
+    }
+}
+
+impl<'a> ElementArena<'a> for SpanArena<'a, '_> {
+    fn append_element<T: Into<Element<'a>>>(&mut self, element: T, parent: NodeId) -> NodeId {
+        self.arena.append_element(element, parent)
+    }
+
+    fn insert_before_last_child<T: Into<Element<'a>>>(
+        &mut self,
+        element: T,
+        parent: NodeId,
+    ) -> NodeId {
+        self.arena.insert_before_last_child(element, parent)
+    }
+
+    fn last_child(&self, parent: NodeId) -> Option<NodeId> {
+        self.arena[parent].last_child()
+    }
+
+    fn in_link(&self, node: NodeId) -> bool {
+        self.arena.in_link(node)
+    }
+
+    fn set_span(&mut self, node: NodeId, span: &'a str) {
+        let start = span.as_ptr() as usize - self.text.as_ptr() as usize;
+        debug_assert!(start + span.len() <= self.text.len());
+        self.spans.insert(node, start..start + span.len());
+    }
 }
 
 #[derive(Debug)]
@@ -87,6 +168,7 @@ pub enum Container<'a> {
         content: &'a str,
         node: NodeId,
         indent: usize,
+        description: bool,
     },
     // Block, List Item
     Block {
@@ -110,36 +192,42 @@ pub enum Container<'a> {
     },
 }
 
+/// Parses `container` and the containers found in it, returns the timestamp
+/// objects found
 pub fn parse_container<'a, T: ElementArena<'a>>(
     arena: &mut T,
     container: Container<'a>,
     config: &ParseConfig,
-) {
+) -> Vec<NodeId> {
     let containers = &mut vec![container];
+    let mut timestamps = Vec::new();
 
     while let Some(container) = containers.pop() {
         match container {
             Container::Document { content, node } => {
-                parse_section_and_headlines(arena, content, node, containers);
+                parse_section_and_headlines(arena, content, node, containers, config);
             }
             Container::Headline { content, node } => {
                 parse_headline_content(arena, content, node, containers, config);
             }
             Container::Block { content, node } => {
-                parse_blocks(arena, content, node, containers);
+                parse_blocks(arena, content, node, containers, config);
             }
             Container::Inline { content, node } => {
-                parse_inlines(arena, content, node, containers);
+                parse_inlines(arena, content, node, containers, &mut timestamps, config);
             }
             Container::List {
                 content,
                 node,
                 indent,
+                description,
             } => {
-                parse_list_items(arena, content, indent, node, containers);
+                parse_list_items(arena, content, indent, description, node, containers);
             }
         }
     }
+
+    timestamps
 }
 
 pub fn parse_headline_content<'a, T: ElementArena<'a>>(
@@ -149,10 +237,30 @@ pub fn parse_headline_content<'a, T: ElementArena<'a>>(
     containers: &mut Vec<Container<'a>>,
     config: &ParseConfig,
 ) {
-    let (tail, (title, content)) = Title::parse(content, config).unwrap();
+    let (tail, (title, title_content)) = Title::parse(content, config).unwrap();
+    let archived = config.skip_archived && title.is_archived();
     let node = arena.append_element(title, parent);
-    containers.push(Container::Inline { content, node });
-    parse_section_and_headlines(arena, tail, parent, containers);
+    arena.set_span(node, &content[0..content.len() - tail.len()]);
+    containers.push(Container::Inline {
+        content: title_content,
+        node,
+    });
+
+    let body = skip_empty_lines(tail);
+    // a planning line which failed to parse, right after the title
+    let planning = memchr(b'\n', tail.as_bytes())
+        .map(|i| &tail[0..i])
+        .map(|line| is_planning_line(line.trim()))
+        .unwrap_or_else(|| is_planning_line(tail.trim()));
+    if archived && !body.is_empty() {
+        // kept unparsed, see `ParseConfig::skip_archived`
+        let section = arena.append_element(Element::Section, parent);
+        arena.set_span(section, body);
+        let text = arena.append_element(Element::Text { value: body.into() }, section);
+        arena.set_span(text, body);
+    } else {
+        parse_sections(arena, tail, parent, containers, config, planning);
+    }
 }
 
 pub fn parse_section_and_headlines<'a, T: ElementArena<'a>>(
@@ -160,6 +268,20 @@ pub fn parse_section_and_headlines<'a, T: ElementArena<'a>>(
     content: &'a str,
     parent: NodeId,
     containers: &mut Vec<Container<'a>>,
+    config: &ParseConfig,
+) {
+    parse_sections(arena, content, parent, containers, config, false);
+}
+
+/// Parses the section and the headlines in `content`, the section starting
+/// with a malformed planning line if `planning` is true
+fn parse_sections<'a, T: ElementArena<'a>>(
+    arena: &mut T,
+    content: &'a str,
+    parent: NodeId,
+    containers: &mut Vec<Container<'a>>,
+    config: &ParseConfig,
+    planning: bool,
 ) {
     let content = skip_empty_lines(content);
     if content.is_empty() {
@@ -168,22 +290,31 @@ pub fn parse_section_and_headlines<'a, T: ElementArena<'a>>(
 
     let mut last_end = 0;
     for i in memchr_iter(b'\n', content.as_bytes()).chain(once(content.len())) {
-        if let Some((mut tail, (headline_content, level))) = parse_headline(&content[last_end..]) {
+        if let Some((mut tail, (headline_content, level))) =
+            parse_headline(&content[last_end..], config)
+        {
             if last_end != 0 {
-                let node = arena.append_element(Element::Section, parent);
-                let content = &content[0..last_end];
-                containers.push(Container::Block { content, node });
+                append_section(
+                    arena,
+                    &content[0..last_end],
+                    parent,
+                    containers,
+                    config,
+                    planning,
+                );
             }
 
             let node = arena.append_element(Element::Headline { level }, parent);
+            arena.set_span(node, headline_content);
             containers.push(Container::Headline {
                 content: headline_content,
                 node,
             });
 
-            while let Some((new_tail, (content, level))) = parse_headline(tail) {
+            while let Some((new_tail, (content, level))) = parse_headline(tail, config) {
                 debug_assert_ne!(tail, new_tail);
                 let node = arena.append_element(Element::Headline { level }, parent);
+                arena.set_span(node, content);
                 containers.push(Container::Headline { content, node });
                 tail = new_tail;
             }
@@ -192,7 +323,27 @@ pub fn parse_section_and_headlines<'a, T: ElementArena<'a>>(
         last_end = i + 1;
     }
 
+    append_section(arena, content, parent, containers, config, planning);
+}
+
+fn append_section<'a, T: ElementArena<'a>>(
+    arena: &mut T,
+    content: &'a str,
+    parent: NodeId,
+    containers: &mut Vec<Container<'a>>,
+    config: &ParseConfig,
+    planning: bool,
+) {
     let node = arena.append_element(Element::Section, parent);
+    arena.set_span(node, content);
+
+    let mut content = content;
+    if planning {
+        if let Some(tail) = parse_raw(arena, content, node, config, is_planning_line) {
+            set_last_child_span(arena, node, &content[0..content.len() - tail.len()]);
+            content = tail;
+        }
+    }
     containers.push(Container::Block { content, node });
 }
 
@@ -201,10 +352,12 @@ pub fn parse_blocks<'a, T: ElementArena<'a>>(
     content: &'a str,
     parent: NodeId,
     containers: &mut Vec<Container<'a>>,
+    config: &ParseConfig,
 ) {
     let mut tail = skip_empty_lines(content);
 
-    if let Some(new_tail) = parse_block(content, arena, parent, containers) {
+    if let Some(new_tail) = parse_block(content, arena, parent, containers, config) {
+        set_last_child_span(arena, parent, &content[0..content.len() - new_tail.len()]);
         tail = skip_empty_lines(new_tail);
     }
 
@@ -217,24 +370,25 @@ pub fn parse_blocks<'a, T: ElementArena<'a>>(
             .unwrap_or_else(|| tail.len());
         if tail.as_bytes()[0..i].iter().all(u8::is_ascii_whitespace) {
             let node = arena.append_element(Element::Paragraph, parent);
+            let content = text[0..pos].trim_end_matches(&['\n', '\r'][..]);
+            arena.set_span(node, content);
 
-            containers.push(Container::Inline {
-                content: &text[0..pos].trim_end_matches('\n'),
-                node,
-            });
+            let content = trim_paragraph(content);
+            containers.push(Container::Inline { content, node });
 
             pos = 0;
             debug_assert_ne!(tail, skip_empty_lines(&tail[i..]));
             tail = skip_empty_lines(&tail[i..]);
             text = tail;
-        } else if let Some(new_tail) = parse_block(tail, arena, parent, containers) {
+        } else if let Some(new_tail) = parse_block(tail, arena, parent, containers, config) {
+            set_last_child_span(arena, parent, &tail[0..tail.len() - new_tail.len()]);
             if pos != 0 {
                 let node = arena.insert_before_last_child(Element::Paragraph, parent);
+                let content = text[0..pos].trim_end_matches(&['\n', '\r'][..]);
+                arena.set_span(node, content);
 
-                containers.push(Container::Inline {
-                    content: &text[0..pos].trim_end_matches('\n'),
-                    node,
-                });
+                let content = trim_paragraph(content);
+                containers.push(Container::Inline { content, node });
 
                 pos = 0;
             }
@@ -250,11 +404,17 @@ pub fn parse_blocks<'a, T: ElementArena<'a>>(
 
     if !text.is_empty() {
         let node = arena.append_element(Element::Paragraph, parent);
+        let content = text[0..pos].trim_end_matches(&['\n', '\r'][..]);
+        arena.set_span(node, content);
 
-        containers.push(Container::Inline {
-            content: &text[0..pos].trim_end_matches('\n'),
-            node,
-        });
+        let content = trim_paragraph(content);
+        containers.push(Container::Inline { content, node });
+    }
+}
+
+fn set_last_child_span<'a, T: ElementArena<'a>>(arena: &mut T, parent: NodeId, span: &'a str) {
+    if let Some(node) = arena.last_child(parent) {
+        arena.set_span(node, span);
     }
 }
 
@@ -263,18 +423,29 @@ pub fn parse_block<'a, T: ElementArena<'a>>(
     arena: &mut T,
     parent: NodeId,
     containers: &mut Vec<Container<'a>>,
+    config: &ParseConfig,
 ) -> Option<&'a str> {
-    if let Some((tail, (fn_def, content))) = FnDef::parse(contents) {
+    let enabled = |kind| config.is_enabled(kind);
+
+    if let Some((tail, (fn_def, content))) = Some(contents)
+        .filter(|_| enabled(ElementKind::FnDef))
+        .and_then(FnDef::parse)
+    {
         let node = arena.append_element(fn_def, parent);
         containers.push(Container::Block { content, node });
         return Some(tail);
-    } else if let Some((tail, list, content)) = List::parse(contents) {
+    } else if let Some((tail, list, content)) = Some(contents)
+        .filter(|_| enabled(ElementKind::List))
+        .and_then(|contents| List::parse(contents, config))
+    {
         let indent = list.indent;
+        let description = list.description;
         let node = arena.append_element(list, parent);
         containers.push(Container::List {
             content,
             node,
             indent,
+            description,
         });
         return Some(tail);
     }
@@ -283,37 +454,61 @@ pub fn parse_block<'a, T: ElementArena<'a>>(
 
     match contents.as_bytes().get(0)? {
         b'C' => {
-            let (tail, clock) = Clock::parse(contents)?;
-            arena.append_element(clock, parent);
-            Some(tail)
+            // clocks are made of timestamps
+            if let Some((tail, clock)) = Some(contents)
+                .filter(|_| enabled(ElementKind::Clock) && enabled(ElementKind::Timestamp))
+                .and_then(Clock::parse)
+            {
+                arena.append_element(clock, parent);
+                Some(tail)
+            } else {
+                None
+            }
         }
-        b'\'' => {
-            // TODO: LaTeX environment
-            None
+        b'\\' if enabled(ElementKind::LatexFragment) => {
+            let (tail, environment) = LatexFragment::parse_environment(contents)?;
+            arena.append_element(environment, parent);
+            Some(tail)
         }
-        b'-' => {
+        b'-' if enabled(ElementKind::Rule) => {
             let tail = parse_rule(contents)?;
             arena.append_element(Element::Rule, parent);
             Some(tail)
         }
         b':' => {
-            if let Some((tail, (drawer, content))) = Drawer::parse(contents) {
-                let node = arena.append_element(drawer, parent);
-                containers.push(Container::Block { content, node });
+            if let Some((tail, (drawer, content))) = Some(contents)
+                .filter(|_| enabled(ElementKind::Drawer))
+                .and_then(Drawer::parse)
+            {
+                match config.drawer_mode(&drawer.name) {
+                    ContentMode::Parse => {
+                        let node = arena.append_element(drawer, parent);
+                        containers.push(Container::Block { content, node });
+                    }
+                    mode => {
+                        arena.append_element(drawer.with_contents(mode, content), parent);
+                    }
+                }
                 Some(tail)
-            } else {
-                let (tail, value) = parse_fixed_width(contents)?;
-                let value = value.into();
+            } else if let Some((tail, value)) = Some(contents)
+                .filter(|_| enabled(ElementKind::FixedWidth))
+                .and_then(parse_fixed_width)
+            {
+                let value = normalize_lines(value);
                 arena.append_element(Element::FixedWidth { value }, parent);
                 Some(tail)
+            } else {
+                parse_raw(arena, contents, parent, config, is_orphaned_end)
             }
         }
-        b'|' => {
+        b'|' | b'+' if enabled(ElementKind::Table) => {
             let tail = parse_table(arena, contents, containers, parent)?;
             Some(tail)
         }
         b'#' => {
-            if let Some((tail, (name, args, content))) = parse_block_element(contents) {
+            if let Some((tail, (name, args, content))) = parse_block_element(contents)
+                .filter(|(_, (name, _, _))| enabled(block_kind(name, config)))
+            {
                 match_block(
                     arena,
                     parent,
@@ -321,42 +516,151 @@ pub fn parse_block<'a, T: ElementArena<'a>>(
                     name.into(),
                     args.map(Into::into),
                     content,
+                    config,
                 );
                 Some(tail)
-            } else if let Some((tail, (dyn_block, content))) = DynBlock::parse(contents) {
+            } else if let Some((tail, (dyn_block, content))) = Some(contents)
+                .filter(|_| enabled(ElementKind::DynBlock))
+                .and_then(DynBlock::parse)
+            {
                 let node = arena.append_element(dyn_block, parent);
                 containers.push(Container::Block { content, node });
                 Some(tail)
+            } else if let Some(tail) = parse_raw(arena, contents, parent, config, is_orphaned_end) {
+                Some(tail)
             } else if let Some((tail, (key, optional, value))) = parse_keyword(contents) {
-                if (&*key).eq_ignore_ascii_case("CALL") {
-                    arena.append_element(
-                        BabelCall {
-                            value: value.into(),
-                        },
-                        parent,
-                    );
-                } else {
-                    arena.append_element(
-                        Keyword {
-                            key: key.into(),
-                            optional: optional.map(Into::into),
-                            value: value.into(),
-                        },
-                        parent,
-                    );
+                let key = match config.syntax_compat {
+                    SyntaxCompat::Legacy if key.eq_ignore_ascii_case("RESULT") => "RESULTS",
+                    _ => key,
+                };
+                match keyword_kind(key) {
+                    kind if !enabled(kind) => return None,
+                    ElementKind::Caption => {
+                        let node = arena.append_element(
+                            Caption {
+                                short: optional.map(Into::into),
+                            },
+                            parent,
+                        );
+                        containers.push(Container::Inline {
+                            content: value,
+                            node,
+                        });
+                    }
+                    ElementKind::BabelCall => {
+                        arena.append_element(
+                            BabelCall {
+                                value: value.into(),
+                            },
+                            parent,
+                        );
+                    }
+                    _ => {
+                        arena.append_element(
+                            Keyword {
+                                key: key.into(),
+                                optional: optional.map(Into::into),
+                                value: value.into(),
+                            },
+                            parent,
+                        );
+                    }
                 }
                 Some(tail)
-            } else {
+            } else if enabled(ElementKind::Comment) {
                 let (tail, value) = parse_comment(contents)?;
-                let value = value.into();
+                let value = normalize_lines(value);
                 arena.append_element(Element::Comment { value }, parent);
                 Some(tail)
+            } else {
+                None
             }
         }
         _ => None,
     }
 }
 
+/// Returns the kind of the element `#+KEY:` is parsed into
+fn keyword_kind(key: &str) -> ElementKind {
+    if key.eq_ignore_ascii_case("CAPTION") {
+        ElementKind::Caption
+    } else if key.eq_ignore_ascii_case("CALL") {
+        ElementKind::BabelCall
+    } else {
+        ElementKind::Keyword
+    }
+}
+
+/// Appends the first line of `contents` as a `Raw` element if `is_raw`
+/// returns `true` for it, see `Element::Raw`
+fn parse_raw<'a, T: ElementArena<'a>>(
+    arena: &mut T,
+    contents: &'a str,
+    parent: NodeId,
+    config: &ParseConfig,
+    is_raw: fn(&str) -> bool,
+) -> Option<&'a str> {
+    if !config.is_enabled(ElementKind::Raw) {
+        return None;
+    }
+
+    let (line, tail) = memchr(b'\n', contents.as_bytes())
+        .map(|i| (&contents[0..i], &contents[i + 1..]))
+        .unwrap_or((contents, ""));
+    let line = line.trim();
+
+    if is_raw(line) {
+        arena.append_element(Element::Raw { value: line.into() }, parent);
+        Some(tail)
+    } else {
+        None
+    }
+}
+
+/// Returns `true` for the end of a drawer or a block, which reaches the
+/// block parser only when nothing opened it
+fn is_orphaned_end(line: &str) -> bool {
+    // compares bytes, since `line` may not have a char boundary there
+    let starts_with = |prefix: &str| {
+        line.len() >= prefix.len()
+            && line.as_bytes()[0..prefix.len()].eq_ignore_ascii_case(prefix.as_bytes())
+    };
+
+    line.eq_ignore_ascii_case(":END:") || starts_with("#+END_") || starts_with("#+END:")
+}
+
+/// Returns `true` for a `SCHEDULED:`, `DEADLINE:` or `CLOSED:` line
+pub(crate) fn is_planning_line(line: &str) -> bool {
+    ["SCHEDULED:", "DEADLINE:", "CLOSED:"]
+        .iter()
+        .any(|keyword| {
+            line.len() >= keyword.len()
+                && line.as_bytes()[0..keyword.len()].eq_ignore_ascii_case(keyword.as_bytes())
+                && line[keyword.len()..]
+                    .trim_start()
+                    .starts_with(&['<', '['][..])
+        })
+}
+
+/// Returns the kind of the element `match_block` parses the block `name` into
+fn block_kind(name: &str, config: &ParseConfig) -> ElementKind {
+    match &*name.to_uppercase() {
+        "CENTER" => ElementKind::CenterBlock,
+        "QUOTE" => ElementKind::QuoteBlock,
+        "COMMENT" => ElementKind::CommentBlock,
+        "EXAMPLE" => ElementKind::ExampleBlock,
+        "EXPORT" => ElementKind::ExportBlock,
+        "SRC" => ElementKind::SourceBlock,
+        "ASCII" | "BEAMER" | "HTML" | "LATEX" | "ODT" | "TEXINFO"
+            if config.syntax_compat == SyntaxCompat::Legacy =>
+        {
+            ElementKind::ExportBlock
+        }
+        "VERSE" => ElementKind::VerseBlock,
+        _ => ElementKind::SpecialBlock,
+    }
+}
+
 pub fn match_block<'a, T: ElementArena<'a>>(
     arena: &mut T,
     parent: NodeId,
@@ -364,6 +668,7 @@ pub fn match_block<'a, T: ElementArena<'a>>(
     name: Cow<'a, str>,
     args: Option<Cow<'a, str>>,
     content: &'a str,
+    config: &ParseConfig,
 ) {
     match &*name.to_uppercase() {
         "CENTER" => {
@@ -378,7 +683,7 @@ pub fn match_block<'a, T: ElementArena<'a>>(
             arena.append_element(
                 CommentBlock {
                     data: args,
-                    contents: content.into(),
+                    contents: unescape_contents(content),
                 },
                 parent,
             );
@@ -387,7 +692,7 @@ pub fn match_block<'a, T: ElementArena<'a>>(
             arena.append_element(
                 ExampleBlock {
                     data: args,
-                    contents: content.into(),
+                    contents: unescape_contents(content),
                 },
                 parent,
             );
@@ -396,7 +701,7 @@ pub fn match_block<'a, T: ElementArena<'a>>(
             arena.append_element(
                 ExportBlock {
                     data: args.unwrap_or_default(),
-                    contents: content.into(),
+                    contents: unescape_contents(content),
                 },
                 parent,
             );
@@ -406,7 +711,7 @@ pub fn match_block<'a, T: ElementArena<'a>>(
                 Some(Cow::Borrowed(args)) => {
                     let (language, arguments) =
                         args.split_at(args.find(' ').unwrap_or_else(|| args.len()));
-                    (language.into(), arguments.into())
+                    (language.into(), arguments.trim_start().into())
                 }
                 None => (Cow::Borrowed(""), Cow::Borrowed("")),
                 _ => unreachable!("`parse_block_element` returns `Some(Cow::Borrowed)` or `None`"),
@@ -415,7 +720,18 @@ pub fn match_block<'a, T: ElementArena<'a>>(
                 SourceBlock {
                     arguments,
                     language,
-                    contents: content.into(),
+                    contents: unescape_contents(content),
+                },
+                parent,
+            );
+        }
+        "ASCII" | "BEAMER" | "HTML" | "LATEX" | "ODT" | "TEXINFO"
+            if config.syntax_compat == SyntaxCompat::Legacy =>
+        {
+            arena.append_element(
+                ExportBlock {
+                    data: name.to_lowercase().into(),
+                    contents: unescape_contents(content),
                 },
                 parent,
             );
@@ -425,14 +741,20 @@ pub fn match_block<'a, T: ElementArena<'a>>(
             containers.push(Container::Block { content, node });
         }
         _ => {
-            let node = arena.append_element(
-                SpecialBlock {
-                    parameters: args,
-                    name,
-                },
-                parent,
-            );
-            containers.push(Container::Block { content, node });
+            let mode = config.block_mode(&name);
+            let block = SpecialBlock {
+                parameters: args,
+                name,
+                mode: ContentMode::Parse,
+                contents: None,
+                properties: HashMap::new(),
+            };
+            if mode == ContentMode::Parse {
+                let node = arena.append_element(block, parent);
+                containers.push(Container::Block { content, node });
+            } else {
+                arena.append_element(block.with_contents(mode, content), parent);
+            }
         }
     }
 }
@@ -458,24 +780,44 @@ impl Iterator for InlinePositions<'_> {
 
     fn next(&mut self) -> Option<Self::Item> {
         lazy_static::lazy_static! {
-            static ref PRE_BYTES: BytesConst =
-                bytes!(b'@', b'<', b'[', b' ', b'(', b'{', b'\'', b'"', b'\n');
+            static ref PRE_BYTES: BytesConst = bytes!(
+                b'@', b'<', b'[', b' ', b'(', b'{', b'\'', b'"', b'\n', b'$', b'\\', b'_', b'^'
+            );
+        }
+
+        if let Some(next) = self.next.take() {
+            return Some(next);
         }
 
-        self.next.take().or_else(|| {
-            PRE_BYTES.find(&self.bytes[self.position..]).map(|i| {
-                self.position += i + 1;
+        loop {
+            let i = PRE_BYTES.find(&self.bytes[self.position..])?;
+            self.position += i + 1;
 
-                match self.bytes[self.position - 1] {
-                    b'{' => {
-                        self.next = Some(self.position);
-                        self.position - 1
-                    }
-                    b' ' | b'(' | b'\'' | b'"' | b'\n' => self.position,
-                    _ => self.position - 1,
+            return Some(match self.bytes[self.position - 1] {
+                b'{' => {
+                    self.next = Some(self.position);
+                    self.position - 1
                 }
-            })
-        })
+                b' ' | b'(' | b'\'' | b'"' | b'\n' => self.position,
+                // the second `$` of `$$` doesn't open a fragment
+                b'$' if self.position > 1 && self.bytes[self.position - 2] == b'$' => self.position,
+                b'_' | b'^' if !is_script_position(self.bytes, self.position - 1) => continue,
+                _ => self.position - 1,
+            });
+        }
+    }
+}
+
+/// Returns `true` if the `_` or `^` at `i` follows a character which isn't
+/// whitespace, so it starts a subscript or a superscript, and not an
+/// underline
+fn is_script_position(bytes: &[u8], i: usize) -> bool {
+    match (bytes.get(i), i.checked_sub(1).map(|i| bytes[i])) {
+        (Some(b'_'), Some(pre)) | (Some(b'^'), Some(pre)) => match pre {
+            b'(' | b'{' | b'\'' | b'"' => false,
+            pre => !pre.is_ascii_whitespace(),
+        },
+        _ => false,
     }
 }
 
@@ -484,30 +826,44 @@ pub fn parse_inlines<'a, T: ElementArena<'a>>(
     content: &'a str,
     parent: NodeId,
     containers: &mut Vec<Container<'a>>,
+    timestamps: &mut Vec<NodeId>,
+    config: &ParseConfig,
 ) {
     let mut tail = content;
 
-    if let Some(tail_) = parse_inline(tail, arena, containers, parent) {
+    if let Some(tail_) = parse_inline(tail, arena, containers, parent, timestamps, config) {
+        set_last_child_span(arena, parent, &tail[0..tail.len() - tail_.len()]);
         tail = tail_;
     }
 
     while let Some((tail_, i)) = InlinePositions::new(tail.as_bytes())
-        .filter_map(|i| parse_inline(&tail[i..], arena, containers, parent).map(|tail| (tail, i)))
+        .filter_map(|i| {
+            if is_script_position(tail.as_bytes(), i) {
+                parse_script_object(&tail[i..], arena, containers, parent, config)
+            } else {
+                parse_inline(&tail[i..], arena, containers, parent, timestamps, config)
+            }
+            .map(|tail| (tail, i))
+        })
         .next()
     {
+        set_last_child_span(arena, parent, &tail[i..tail.len() - tail_.len()]);
         if i != 0 {
-            arena.insert_before_last_child(
+            let node = arena.insert_before_last_child(
                 Element::Text {
-                    value: tail[0..i].into(),
+                    value: normalize_newlines(&tail[0..i]),
                 },
                 parent,
             );
+            arena.set_span(node, &tail[0..i]);
         }
         tail = tail_;
     }
 
     if !tail.is_empty() {
-        arena.append_element(Element::Text { value: tail.into() }, parent);
+        let value = normalize_newlines(tail);
+        let node = arena.append_element(Element::Text { value }, parent);
+        arena.set_span(node, tail);
     }
 }
 
@@ -516,114 +872,234 @@ pub fn parse_inline<'a, T: ElementArena<'a>>(
     arena: &mut T,
     containers: &mut Vec<Container<'a>>,
     parent: NodeId,
+    timestamps: &mut Vec<NodeId>,
+    config: &ParseConfig,
 ) -> Option<&'a str> {
+    let enabled = |kind| config.is_enabled(kind);
+
+    // a line break may end the paragraph, so it can be shorter than other objects
+    if contents.starts_with("\\\\") && enabled(ElementKind::LineBreak) {
+        if let Some(tail) = parse_line_break(contents) {
+            arena.append_element(Element::LineBreak, parent);
+            return Some(tail);
+        }
+    }
+
     if contents.len() < 3 {
         return None;
     }
 
+    // plain links start at the beginning of a word, where objects start
+    if contents.as_bytes()[0].is_ascii_alphabetic()
+        && enabled(ElementKind::Link)
+        && !arena.in_link(parent)
+    {
+        if let Some((tail, link)) = Link::parse_plain(contents, &config.link_protocols) {
+            arena.append_element(link, parent);
+            return Some(tail);
+        }
+    }
+
     match contents.as_bytes()[0] {
-        b'@' => {
+        b'@' if enabled(ElementKind::Snippet) => {
             let (tail, snippet) = Snippet::parse(contents)?;
             arena.append_element(snippet, parent);
             Some(tail)
         }
-        b'{' => {
+        b'{' if enabled(ElementKind::Macros) => {
             let (tail, macros) = Macros::parse(contents)?;
             arena.append_element(macros, parent);
             Some(tail)
         }
         b'<' => {
-            if let Some((tail, _content)) = parse_radio_target(contents) {
-                arena.append_element(Element::RadioTarget, parent);
+            if let Some((tail, radio_target)) = Some(contents)
+                .filter(|_| enabled(ElementKind::RadioTarget))
+                .and_then(RadioTarget::parse)
+            {
+                arena.append_element(radio_target, parent);
                 Some(tail)
-            } else if let Some((tail, target)) = Target::parse(contents) {
+            } else if let Some((tail, target)) = Some(contents)
+                .filter(|_| enabled(ElementKind::Target))
+                .and_then(Target::parse)
+            {
                 arena.append_element(target, parent);
                 Some(tail)
+            } else if let Some((tail, link)) = Some(contents)
+                .filter(|_| enabled(ElementKind::Link) && !arena.in_link(parent))
+                .and_then(|contents| Link::parse_angle(contents, &config.link_protocols))
+            {
+                arena.append_element(link, parent);
+                Some(tail)
+            } else if !enabled(ElementKind::Timestamp) {
+                None
             } else if let Some((tail, timestamp)) = Timestamp::parse_active(contents) {
-                arena.append_element(timestamp, parent);
+                timestamps.push(arena.append_element(timestamp, parent));
                 Some(tail)
             } else {
                 let (tail, timestamp) = Timestamp::parse_diary(contents)?;
-                arena.append_element(timestamp, parent);
+                timestamps.push(arena.append_element(timestamp, parent));
                 Some(tail)
             }
         }
         b'[' => {
-            if let Some((tail, fn_ref)) = FnRef::parse(contents) {
+            if let Some((tail, fn_ref)) = Some(contents)
+                .filter(|_| enabled(ElementKind::FnRef))
+                .and_then(FnRef::parse)
+            {
                 arena.append_element(fn_ref, parent);
                 Some(tail)
-            } else if let Some((tail, link)) = Link::parse(contents) {
-                arena.append_element(link, parent);
+            } else if let Some((tail, citation)) = Some(contents)
+                .filter(|_| enabled(ElementKind::Citation))
+                .and_then(Citation::parse)
+            {
+                arena.append_element(citation, parent);
+                Some(tail)
+            } else if let Some((tail, mut link)) = Some(contents)
+                .filter(|_| enabled(ElementKind::Link))
+                .and_then(Link::parse)
+            {
+                let desc = match link.desc {
+                    Some(Cow::Borrowed(desc)) => Some(desc),
+                    _ => None,
+                };
+                link.desc = desc.map(normalize_newlines);
+                let node = arena.append_element(link, parent);
+                if let Some(content) = desc {
+                    containers.push(Container::Inline { content, node });
+                }
                 Some(tail)
-            } else if let Some((tail, cookie)) = Cookie::parse(contents) {
+            } else if let Some((tail, cookie)) = Some(contents)
+                .filter(|_| enabled(ElementKind::Cookie))
+                .and_then(Cookie::parse)
+            {
                 arena.append_element(cookie, parent);
                 Some(tail)
-            } else {
+            } else if enabled(ElementKind::Timestamp) {
                 let (tail, timestamp) = Timestamp::parse_inactive(contents)?;
-                arena.append_element(timestamp, parent);
+                timestamps.push(arena.append_element(timestamp, parent));
                 Some(tail)
+            } else {
+                None
             }
         }
-        b'*' => {
+        b'*' if enabled(ElementKind::Bold) => {
             let (tail, content) = parse_emphasis(contents, b'*')?;
             let node = arena.append_element(Element::Bold, parent);
             containers.push(Container::Inline { content, node });
             Some(tail)
         }
-        b'+' => {
+        b'+' if enabled(ElementKind::Strike) => {
             let (tail, content) = parse_emphasis(contents, b'+')?;
             let node = arena.append_element(Element::Strike, parent);
             containers.push(Container::Inline { content, node });
             Some(tail)
         }
-        b'/' => {
+        b'/' if enabled(ElementKind::Italic) => {
             let (tail, content) = parse_emphasis(contents, b'/')?;
             let node = arena.append_element(Element::Italic, parent);
             containers.push(Container::Inline { content, node });
             Some(tail)
         }
-        b'_' => {
+        b'_' if enabled(ElementKind::Underline) => {
             let (tail, content) = parse_emphasis(contents, b'_')?;
             let node = arena.append_element(Element::Underline, parent);
             containers.push(Container::Inline { content, node });
             Some(tail)
         }
-        b'=' => {
+        b'=' if enabled(ElementKind::Verbatim) => {
             let (tail, value) = parse_emphasis(contents, b'=')?;
-            let value = value.into();
+            let value = normalize_newlines(value);
             arena.append_element(Element::Verbatim { value }, parent);
             Some(tail)
         }
-        b'~' => {
+        b'~' if enabled(ElementKind::Code) => {
             let (tail, value) = parse_emphasis(contents, b'~')?;
-            let value = value.into();
+            let value = normalize_newlines(value);
             arena.append_element(Element::Code { value }, parent);
             Some(tail)
         }
-        b's' => {
+        b's' if enabled(ElementKind::InlineSrc) => {
             let (tail, inline_src) = InlineSrc::parse(contents)?;
             arena.append_element(inline_src, parent);
             Some(tail)
         }
-        b'c' => {
+        b'c' if enabled(ElementKind::InlineCall) => {
             let (tail, inline_call) = InlineCall::parse(contents)?;
             arena.append_element(inline_call, parent);
             Some(tail)
         }
+        b'$' | b'\\' => {
+            // `\[` is an escaped bracket in link descriptions
+            if contents.starts_with("\\[") && arena.in_link(parent) {
+                return None;
+            }
+            if let Some((tail, fragment)) = Some(contents)
+                .filter(|_| enabled(ElementKind::LatexFragment))
+                .and_then(LatexFragment::parse)
+            {
+                arena.append_element(fragment, parent);
+                Some(tail)
+            } else if enabled(ElementKind::Entity) {
+                let (tail, entity) = Entity::parse(contents)?;
+                arena.append_element(entity, parent);
+                Some(tail)
+            } else {
+                None
+            }
+        }
         _ => None,
     }
 }
 
+fn parse_script_object<'a, T: ElementArena<'a>>(
+    contents: &'a str,
+    arena: &mut T,
+    containers: &mut Vec<Container<'a>>,
+    parent: NodeId,
+    config: &ParseConfig,
+) -> Option<&'a str> {
+    let kind = if contents.starts_with('_') {
+        ElementKind::Subscript
+    } else {
+        ElementKind::Superscript
+    };
+    if !config.is_enabled(kind) {
+        return None;
+    }
+    let (tail, content, braces) = parse_script(contents, config.use_sub_superscripts)?;
+    let element = match kind {
+        ElementKind::Subscript => Element::Subscript { braces },
+        _ => Element::Superscript { braces },
+    };
+    let node = arena.append_element(element, parent);
+    containers.push(Container::Inline { content, node });
+    Some(tail)
+}
+
 pub fn parse_list_items<'a, T: ElementArena<'a>>(
     arena: &mut T,
     mut contents: &'a str,
     indent: usize,
+    description: bool,
     parent: NodeId,
     containers: &mut Vec<Container<'a>>,
 ) {
     while !contents.is_empty() {
-        let (tail, list_item, content) = ListItem::parse(contents, indent);
+        let (tail, list_item, content) = ListItem::parse(contents, indent, description);
+        let tag = match list_item.tag {
+            Some(Cow::Borrowed(tag)) => Some(tag),
+            _ => None,
+        };
         let node = arena.append_element(list_item, parent);
+        arena.set_span(node, &contents[0..contents.len() - tail.len()]);
+        if let Some(tag) = tag {
+            let tag_node = arena.append_element(Element::ListItemTag, node);
+            arena.set_span(tag_node, tag);
+            containers.push(Container::Inline {
+                content: tag,
+                node: tag_node,
+            });
+        }
         containers.push(Container::Block { content, node });
         contents = tail;
     }
@@ -635,41 +1111,111 @@ pub fn parse_table<'a, T: ElementArena<'a>>(
     containers: &mut Vec<Container<'a>>,
     parent: NodeId,
 ) -> Option<&'a str> {
-    if contents.trim_start().starts_with('|') {
-        let table_node = arena.append_element(Table::Org { tblfm: None }, parent);
+    if let Some((tail, rows, header_rule)) = parse_org_table(contents) {
+        let (tail, tblfm) = parse_tblfm(tail);
+        let has_header = header_rule.is_some();
+        let table_node = arena.append_element(Table::Org { tblfm, has_header }, parent);
 
-        let mut last_end = 0;
-        for start in memchr_iter(b'\n', contents.as_bytes()).chain(once(contents.len())) {
-            let line = contents[last_end..start].trim();
-            match TableRow::parse(line) {
-                Some(TableRow::Standard) => {
-                    let row_node = arena.append_element(TableRow::Standard, table_node);
-                    for cell in line[1..].split_terminator('|') {
-                        let cell_node = arena.append_element(Element::TableCell, row_node);
-                        containers.push(Container::Inline {
-                            content: cell.trim(),
-                            node: cell_node,
-                        });
-                    }
-                }
-                Some(TableRow::Rule) => {
-                    arena.append_element(TableRow::Rule, table_node);
+        for (i, line) in rows.into_iter().enumerate() {
+            let header = match header_rule {
+                Some(rule) => i < rule,
+                None => false,
+            };
+            let row = if line.starts_with("|-") {
+                if header_rule == Some(i) {
+                    TableRow::HeaderRule
+                } else {
+                    TableRow::BodyRule
                 }
-                None => return Some(&contents[last_end..]),
+            } else if header {
+                TableRow::Header
+            } else {
+                TableRow::Body
+            };
+            let is_rule = row.is_rule();
+            let row_node = arena.append_element(row, table_node);
+            arena.set_span(row_node, line);
+            if is_rule {
+                continue;
+            }
+            for cell in line[1..].split_terminator('|') {
+                let kind = if header {
+                    TableCell::Header
+                } else {
+                    TableCell::Body
+                };
+                let cell_node = arena.append_element(kind, row_node);
+                arena.set_span(cell_node, cell);
+                containers.push(Container::Inline {
+                    content: cell.trim(),
+                    node: cell_node,
+                });
             }
-            last_end = start + 1;
         }
 
-        Some("")
+        Some(tail)
     } else {
         let (tail, value) = parse_table_el(contents)?;
-        let value = value.into();
+        // `contents` starts after the indentation of the first line
+        let value = if value
+            .lines()
+            .any(|line| line.starts_with(char::is_whitespace))
+        {
+            value
+                .lines()
+                .map(|line| format!("{}\n", line.trim_start()))
+                .collect::<String>()
+                .into()
+        } else {
+            normalize_lines(value)
+        };
         arena.append_element(Table::TableEl { value }, parent);
 
         Some(tail)
     }
 }
 
+/// Converts the `\r\n` line endings of `text` into `\n`, so documents with
+/// either line endings have the same elements
+pub fn normalize_newlines(text: &str) -> Cow<'_, str> {
+    if text.contains("\r\n") {
+        Cow::Owned(text.replace("\r\n", "\n"))
+    } else {
+        Cow::Borrowed(text)
+    }
+}
+
+/// Returns the contents of a paragraph without the spaces and tabs ending
+/// its last line
+///
+/// They're kept when the last line would start another element without
+/// them, like `:` or `-`, so that writing the paragraph back doesn't change
+/// its kind.
+fn trim_paragraph(content: &str) -> &str {
+    let trimmed = content.trim_end_matches(&[' ', '\t'][..]);
+    let last_line = trimmed[memrchr(b'\n', trimmed.as_bytes()).map_or(0, |i| i + 1)..].trim_start();
+    let is_bullet = |line: &str| {
+        line.len() > 1
+            && line.ends_with(&['.', ')'][..])
+            && line[..line.len() - 1].bytes().all(|c| c.is_ascii_digit())
+    };
+
+    if matches!(last_line, ":" | "#" | "-" | "+" | "*") || is_bullet(last_line) {
+        content
+    } else {
+        trimmed
+    }
+}
+
+/// Like `normalize_newlines`, also ending `text` with a newline if it's
+/// missing at the end of the document
+fn normalize_lines(text: &str) -> Cow<'_, str> {
+    match normalize_newlines(text) {
+        value if value.ends_with('\n') => value,
+        value => Cow::Owned(value.into_owned() + "\n"),
+    }
+}
+
 pub fn line<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, &'a str, E> {
     if let Some(i) = memchr(b'\n', input.as_bytes()) {
         if i > 0 && input.as_bytes()[i - 1] == b'\r' {
@@ -682,6 +1228,15 @@ pub fn line<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, &'a
     }
 }
 
+/// Fails at the end of input, e.g. when the end line of a block is missing
+pub fn not_eof<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, (), E> {
+    if input.is_empty() {
+        Err(nom::Err::Error(E::from_error_kind(input, ErrorKind::Eof)))
+    } else {
+        Ok((input, ()))
+    }
+}
+
 pub fn eol<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, &'a str, E> {
     verify(line, |s: &str| s.trim().is_empty())(input)
 }
@@ -711,20 +1266,36 @@ pub fn skip_empty_lines(input: &str) -> &str {
     take_lines_while(|line| line.trim().is_empty())(input).0
 }
 
-pub fn parse_headline(input: &str) -> Option<(&str, (&str, usize))> {
-    let (input_, level) = parse_headline_level(input)?;
+/// Parses a headline and its descendants, returns them with its level
+///
+/// Flattened headlines have no descendants.
+pub fn parse_headline<'a>(
+    input: &'a str,
+    config: &ParseConfig,
+) -> Option<(&'a str, (&'a str, usize))> {
+    let (input_, stars) = parse_headline_level(input, config)?;
+    let level = if config.flatten_levels { 1 } else { stars };
     let (input_, content) = take_lines_while(move |line| {
-        parse_headline_level(line)
-            .map(|(_, l)| l > level)
+        parse_headline_level(line, config)
+            .map(|(_, l)| !config.flatten_levels && l > level)
             .unwrap_or(true)
     })(input_);
-    Some((input_, (&input[0..level + content.len()], level)))
+    Some((input_, (&input[0..stars + content.len()], level)))
 }
 
-pub fn parse_headline_level(input: &str) -> Option<(&str, usize)> {
+/// Parses the stars of a headline, up to `ParseConfig::max_headline_level`
+pub fn parse_headline_level<'a>(input: &'a str, config: &ParseConfig) -> Option<(&'a str, usize)> {
     let (input, stars) = take_while1::<_, _, ()>(|c: char| c == '*')(input).ok()?;
 
-    if input.starts_with(' ') || input.starts_with('\n') || input.is_empty() {
+    if (input.starts_with(' ')
+        || input.starts_with('\n')
+        || input.starts_with("\r\n")
+        || input.is_empty())
+        && config
+            .max_headline_level
+            .map(|max| stars.len() <= max)
+            .unwrap_or(true)
+    {
         Some((input, stars.len()))
     } else {
         None
@@ -764,3 +1335,19 @@ pub fn test_skip_empty_lines() {
     assert_eq!(skip_empty_lines(" \n  \n\nfoo\n"), "foo\n");
     assert_eq!(skip_empty_lines(" \n  \n\n   foo\n"), "   foo\n");
 }
+
+#[test]
+fn raw() {
+    assert!(is_orphaned_end(":END:"));
+    assert!(is_orphaned_end("#+end_src"));
+    assert!(is_orphaned_end("#+END:"));
+    assert!(!is_orphaned_end(":PROPERTIES:"));
+    assert!(!is_orphaned_end(":ID: 1234"));
+    assert!(!is_orphaned_end("#+BEGIN_QUOTE"));
+    assert!(!is_orphaned_end("#+TITLE: title"));
+    assert!(is_planning_line("SCHEDULED: <2019-10-10 Thu>"));
+    assert!(is_planning_line("closed: [2019-10-10 Thu]"));
+    assert!(!is_planning_line("CLOCK: [2019-10-10 Thu]--"));
+    assert!(!is_planning_line("DEADLINE: tomorrow"));
+    assert!(!is_planning_line("Scheduled for tomorrow"));
+}
@@ -0,0 +1,84 @@
+//! Re-exports of the types most programs need
+//!
+//! ```rust
+//! use orgize::prelude::*;
+//! ```
+//!
+//! It covers parsing, iterating, editing and exporting documents. Types of
+//! the experimental modules, like [`query`], aren't part of it.
+//!
+//! The examples of the README only need the prelude:
+//!
+//! ```rust
+//! use orgize::prelude::*;
+//! use std::io::{Error, Write};
+//!
+//! Org::parse("* DONE Title :tag:");
+//!
+//! let org = Org::parse_with_config(
+//!     "* TASK Title 1",
+//!     &ParseConfig {
+//!         todo_keywords: vec!["TASK".to_string()],
+//!         ..Default::default()
+//!     },
+//! );
+//! assert_eq!(org.headlines().next().unwrap().title(&org).keyword.as_deref(), Some("TASK"));
+//!
+//! for event in org.iter() {
+//!     match event {
+//!         Event::Start(Element::Title(title)) => assert_eq!(title.raw, "Title 1"),
+//!         Event::Start(_) | Event::End(_) => (),
+//!     }
+//! }
+//!
+//! # #[cfg(feature = "export-html")] {
+//! let mut writer = Vec::new();
+//! Org::parse("* title\n*section*").html(&mut writer).unwrap();
+//! assert_eq!(
+//!     String::from_utf8(writer).unwrap(),
+//!     "<main><h1>title</h1><section><p><b>section</b></p></section></main>"
+//! );
+//! # }
+//!
+//! # #[cfg(feature = "export-markdown")] {
+//! let mut writer = Vec::new();
+//! Org::parse("* title\n*section*").markdown(&mut writer).unwrap();
+//! assert_eq!(String::from_utf8(writer).unwrap(), "# title\n\n**section**\n");
+//! # }
+//!
+//! # #[cfg(feature = "export-html")] {
+//! #[derive(Default)]
+//! struct IdHandler(DefaultHtmlHandler);
+//!
+//! impl HtmlHandler<Error> for IdHandler {
+//!     fn start<W: Write>(&mut self, mut w: W, element: &Element) -> Result<(), Error> {
+//!         match element {
+//!             Element::Title(title) => write!(w, "<h{} id=\"{}\">", title.level, title.raw),
+//!             _ => self.0.start(w, element),
+//!         }
+//!     }
+//! }
+//!
+//! let mut writer = Vec::new();
+//! Org::parse("* title")
+//!     .html_with_handler(&mut writer, &mut IdHandler::default())
+//!     .unwrap();
+//! assert_eq!(
+//!     String::from_utf8(writer).unwrap(),
+//!     "<main><h1 id=\"title\">title</h1></main>"
+//! );
+//! # }
+//! ```
+//!
+//! [`query`]: ../query/index.html
+
+pub use crate::elements::{Datetime, Element, Timestamp, Title};
+#[cfg(feature = "export-html")]
+pub use crate::export::{DefaultHtmlHandler, HtmlHandler};
+#[cfg(feature = "export-markdown")]
+pub use crate::export::{DefaultMarkdownHandler, MarkdownHandler};
+pub use crate::export::{DefaultOrgHandler, ExportOptions, OrgHandler};
+pub use crate::{
+    AgendaOptions, Captioned, CodeBlock, DocumentNode, Entry, Event, HeadlineNode, Org,
+    OrgizeError, ParseConfig, TemplateOptions,
+};
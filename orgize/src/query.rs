@@ -0,0 +1,611 @@
+//! Tag and property matching, like org-mode agenda match strings
+//!
+//! ```rust
+//! use orgize::{query::Matcher, Org};
+//!
+//! let org = Org::parse(
+//!     r#"#+FILETAGS: :home:
+//! * TODO Paint the fence :garden:
+//! ** TODO Buy paint :errand:urgent:
+//! ** TODO Sand the planks :errand:
+//! "#,
+//! );
+//!
+//! let matcher = Matcher::parse(r#"+errand-urgent+TODO="TODO""#).unwrap();
+//! let titles: Vec<_> = org
+//!     .match_entries(&matcher)
+//!     .iter()
+//!     .map(|headline| headline.title(&org).raw.as_ref())
+//!     .collect();
+//!
+//! assert_eq!(titles, ["Sand the planks"]);
+//! ```
+
+use std::cmp::Ordering;
+
+use crate::config::ParseConfig;
+use crate::elements::title::parse_number_value;
+use crate::elements::{Datetime, Timestamp};
+use crate::node::HeadlineNode;
+use crate::org::Org;
+use crate::settings::SettingsResolver;
+
+/// A parsed match string, like `+work-urgent+TODO="NEXT"|LEVEL=1`
+///
+/// The string is a list of alternatives separated by `|`, each of them a
+/// list of terms which must all match, optionally separated by `&`:
+///
+/// - `tag` or `+tag` requires a tag, `-tag` forbids it. Tags are inherited
+///   from the ancestors of a headline and from `#+FILETAGS:`.
+/// - `KEY=value` compares the value of a property with `=`, `<>`, `<`, `<=`,
+///   `>` or `>=`. Values are numbers, like `LEVEL>1`, or quoted strings, like
+///   `TODO="NEXT"`. Quoted timestamps, like `SCHEDULED<"<2020-01-01>"`, are
+///   compared as dates. A property comparison can be negated with `-`.
+///   Property values are read like [`Title::property_number`] and
+///   [`Title::property_timestamp`] for these comparisons.
+///
+/// The special properties `TODO`, `LEVEL`, `PRIORITY`, `CATEGORY`, `ITEM`,
+/// `SCHEDULED`, `DEADLINE` and `CLOSED` are supported, and missing properties
+/// compare as the empty string, or as `0` with numbers.
+///
+/// After a `/`, a list of todo keywords separated by `|` matches headlines
+/// having one of them, like `work/NEXT|WAITING`. `-KEYWORD` forbids a
+/// keyword instead, and a leading `!` only matches headlines with a todo
+/// keyword which isn't done.
+///
+/// [`Title::property_number`]: ../elements/struct.Title.html#method.property_number
+/// [`Title::property_timestamp`]: ../elements/struct.Title.html#method.property_timestamp
+#[derive(Clone, Debug, PartialEq)]
+pub struct Matcher {
+    alternatives: Vec<Vec<Term>>,
+    todo: Option<TodoMatch>,
+}
+
+/// Error of [`Matcher::parse`], with the byte offset where parsing failed
+///
+/// [`Matcher::parse`]: struct.Matcher.html#method.parse
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct QueryError {
+    pub offset: usize,
+    pub message: &'static str,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Term {
+    Tag {
+        name: String,
+        negated: bool,
+    },
+    Property {
+        key: String,
+        op: Operator,
+        value: Value,
+        negated: bool,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Operator {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Value {
+    Number(f64),
+    String(String),
+    Date(DateKey),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct TodoMatch {
+    not_done: bool,
+    alternatives: Vec<Vec<(String, bool)>>,
+}
+
+/// Year, month, day, hour and minute of a timestamp
+type DateKey = (u16, u8, u8, u8, u8);
+
+impl Matcher {
+    /// Parses a match string
+    ///
+    /// ```rust
+    /// use orgize::query::Matcher;
+    ///
+    /// assert!(Matcher::parse(r#"CATEGORY="home"&LEVEL=2"#).is_ok());
+    /// assert!(Matcher::parse(r#"TODO="NEXT"#).is_err());
+    /// ```
+    pub fn parse(text: &str) -> Result<Matcher, QueryError> {
+        let (tags, todo) = match split_todo(text) {
+            Some(i) => (&text[0..i], Some(parse_todo(&text[i + 1..], i + 1)?)),
+            None => (text, None),
+        };
+
+        let mut alternatives = Vec::new();
+        let mut start = 0;
+        for alternative in split_alternatives(tags) {
+            alternatives.push(parse_terms(alternative, start)?);
+            start += alternative.len() + 1;
+        }
+
+        Ok(Matcher { alternatives, todo })
+    }
+
+    /// Returns `true` if `headline` matches
+    ///
+    /// Done keywords are the ones set by `#+TODO:` and similar keywords, or
+    /// `DONE` by default.
+    pub fn matches(&self, org: &Org<'_>, headline: HeadlineNode) -> bool {
+        let config = ParseConfig::default();
+        let resolver = SettingsResolver::new(org, &config);
+        let (_, done_keywords) = resolver.todo_keywords();
+        self.matches_with(&Context {
+            org,
+            resolver: &resolver,
+            file_tags: &resolver.file_tags(),
+            done_keywords: &done_keywords,
+            headline,
+        })
+    }
+
+    fn matches_with(&self, context: &Context<'_, '_, '_>) -> bool {
+        let tags_match = self
+            .alternatives
+            .iter()
+            .any(|terms| terms.iter().all(|term| term.matches(context)));
+
+        tags_match
+            && self
+                .todo
+                .as_ref()
+                .map(|todo| todo.matches(context))
+                .unwrap_or(true)
+    }
+}
+
+impl<'a> Org<'a> {
+    /// Returns the headlines matching `matcher`, in document order
+    pub fn match_entries(&self, matcher: &Matcher) -> Vec<HeadlineNode> {
+        let config = ParseConfig::default();
+        let resolver = SettingsResolver::new(self, &config);
+        let (_, done_keywords) = resolver.todo_keywords();
+        let file_tags = resolver.file_tags();
+
+        self.headlines()
+            .filter(|&headline| {
+                matcher.matches_with(&Context {
+                    org: self,
+                    resolver: &resolver,
+                    file_tags: &file_tags,
+                    done_keywords: &done_keywords,
+                    headline,
+                })
+            })
+            .collect()
+    }
+}
+
+struct Context<'a, 'b, 'c> {
+    org: &'b Org<'a>,
+    resolver: &'c SettingsResolver<'a, 'b>,
+    file_tags: &'c [&'c str],
+    done_keywords: &'c [String],
+    headline: HeadlineNode,
+}
+
+impl Context<'_, '_, '_> {
+    fn has_tag(&self, name: &str) -> bool {
+        let mut headline = Some(self.headline);
+        while let Some(current) = headline {
+            if current.title(self.org).tags.iter().any(|tag| tag == name) {
+                return true;
+            }
+            headline = current.parent(self.org);
+        }
+        self.file_tags.contains(&name)
+    }
+
+    fn keyword(&self) -> Option<&str> {
+        self.headline.title(self.org).keyword.as_deref()
+    }
+
+    fn property(&self, key: &str) -> Option<String> {
+        let title = self.headline.title(self.org);
+        let planning = |timestamp: Option<&Timestamp>| timestamp.map(timestamp_text);
+
+        match &*key.to_ascii_uppercase() {
+            "TODO" => title.keyword.as_ref().map(|keyword| keyword.to_string()),
+            "LEVEL" => Some(self.headline.level().to_string()),
+            "PRIORITY" => Some(
+                title
+                    .priority
+                    .map(|priority| priority.to_string())
+                    .unwrap_or_else(|| "B".into()),
+            ),
+            "CATEGORY" => self
+                .resolver
+                .property("CATEGORY", self.headline)
+                .or_else(|| self.resolver.keyword("CATEGORY"))
+                .map(Into::into),
+            "ITEM" => Some(title.raw.to_string()),
+            "SCHEDULED" => planning(title.planning.as_ref().and_then(|p| p.scheduled.as_ref())),
+            "DEADLINE" => planning(title.planning.as_ref().and_then(|p| p.deadline.as_ref())),
+            "CLOSED" => planning(title.planning.as_ref().and_then(|p| p.closed.as_ref())),
+            _ => title.property(key).map(Into::into),
+        }
+    }
+
+    /// Returns the date of the property `key` whose value is `property`,
+    /// reading it like [`Title::property_timestamp`], or like the timestamps
+    /// of match strings, which may omit the day name
+    ///
+    /// [`Title::property_timestamp`]: ../elements/struct.Title.html#method.property_timestamp
+    fn date(&self, key: &str, property: &str) -> Option<DateKey> {
+        let start = match self.headline.property_timestamp(key, self.org) {
+            Some(Timestamp::Active { start, .. })
+            | Some(Timestamp::Inactive { start, .. })
+            | Some(Timestamp::ActiveRange { start, .. })
+            | Some(Timestamp::InactiveRange { start, .. }) => Some(datetime_key(&start)),
+            _ => None,
+        };
+        start.or_else(|| date_key(property))
+    }
+}
+
+impl Term {
+    fn matches(&self, context: &Context<'_, '_, '_>) -> bool {
+        match self {
+            Term::Tag { name, negated } => context.has_tag(name) != *negated,
+            Term::Property {
+                key,
+                op,
+                value,
+                negated,
+            } => {
+                let property = context.property(key).unwrap_or_default();
+                let property = property.trim();
+                let ordering = match value {
+                    Value::Number(number) if property.is_empty() => 0f64.partial_cmp(number),
+                    Value::Number(number) => parse_number_value(property)
+                        .and_then(|property| property.partial_cmp(number)),
+                    Value::String(string) => Some(property.cmp(string.as_str())),
+                    Value::Date(date) => context
+                        .date(key, property)
+                        .map(|property| property.cmp(date)),
+                };
+                ordering
+                    .map(|ordering| op.accepts(ordering))
+                    .unwrap_or(false)
+                    != *negated
+            }
+        }
+    }
+}
+
+impl Operator {
+    fn accepts(self, ordering: Ordering) -> bool {
+        match self {
+            Operator::Eq => ordering == Ordering::Equal,
+            Operator::Ne => ordering != Ordering::Equal,
+            Operator::Lt => ordering == Ordering::Less,
+            Operator::Le => ordering != Ordering::Greater,
+            Operator::Gt => ordering == Ordering::Greater,
+            Operator::Ge => ordering != Ordering::Less,
+        }
+    }
+}
+
+impl TodoMatch {
+    fn matches(&self, context: &Context<'_, '_, '_>) -> bool {
+        let keyword = context.keyword();
+
+        if self.not_done
+            && keyword
+                .filter(|keyword| !context.done_keywords.iter().any(|done| done == keyword))
+                .is_none()
+        {
+            return false;
+        }
+
+        self.alternatives.is_empty()
+            || self.alternatives.iter().any(|keywords| {
+                keywords
+                    .iter()
+                    .all(|(name, negated)| (keyword == Some(name.as_str())) != *negated)
+            })
+    }
+}
+
+/// Returns the offset of the `/` starting the todo part, outside of quotes
+fn split_todo(text: &str) -> Option<usize> {
+    let mut quoted = false;
+    for (i, c) in text.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            '/' if !quoted => return Some(i),
+            _ => (),
+        }
+    }
+    None
+}
+
+/// Splits the tags of a match string at the `|` outside quotes
+fn split_alternatives(text: &str) -> Vec<&str> {
+    let mut alternatives = Vec::new();
+    let mut quoted = false;
+    let mut start = 0;
+    for (i, c) in text.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            '|' if !quoted => {
+                alternatives.push(&text[start..i]);
+                start = i + 1;
+            }
+            _ => (),
+        }
+    }
+    alternatives.push(&text[start..]);
+    alternatives
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '@' || c == '#' || c == '%'
+}
+
+fn parse_terms(text: &str, offset: usize) -> Result<Vec<Term>, QueryError> {
+    let error = |i: usize, message| QueryError {
+        offset: offset + i,
+        message,
+    };
+
+    let mut terms = Vec::new();
+    let mut i = 0;
+    while i < text.len() {
+        let rest = &text[i..];
+        let negated = match rest.chars().next() {
+            Some('&') => {
+                i += 1;
+                continue;
+            }
+            Some(c) if c.is_whitespace() => {
+                i += c.len_utf8();
+                continue;
+            }
+            Some('-') => {
+                i += 1;
+                true
+            }
+            Some('+') => {
+                i += 1;
+                false
+            }
+            _ => false,
+        };
+
+        let rest = &text[i..];
+        let len = rest.find(|c| !is_name_char(c)).unwrap_or(rest.len());
+        if len == 0 {
+            return Err(error(i, "expected a tag or a property"));
+        }
+        let name = &rest[0..len];
+        i += len;
+
+        let rest = &text[i..];
+        let op = [
+            ("<=", Operator::Le),
+            (">=", Operator::Ge),
+            ("<>", Operator::Ne),
+            ("!=", Operator::Ne),
+            ("=", Operator::Eq),
+            ("<", Operator::Lt),
+            (">", Operator::Gt),
+        ]
+        .iter()
+        .find(|(op, _)| rest.starts_with(op));
+
+        let (op_text, op) = match op {
+            Some(op) => *op,
+            None => {
+                terms.push(Term::Tag {
+                    name: name.into(),
+                    negated,
+                });
+                continue;
+            }
+        };
+        i += op_text.len();
+
+        let rest = &text[i..];
+        let value = if let Some(quoted) = rest.strip_prefix('"') {
+            let end = quoted
+                .find('"')
+                .ok_or_else(|| error(i, "unterminated string"))?;
+            i += end + 2;
+            let string = &quoted[0..end];
+            match date_key(string) {
+                Some(date) => Value::Date(date),
+                None => Value::String(string.into()),
+            }
+        } else {
+            let len = rest
+                .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == '-'))
+                .unwrap_or(rest.len());
+            let number = rest[0..len]
+                .parse()
+                .map_err(|_| error(i, "expected a number or a quoted string"))?;
+            i += len;
+            Value::Number(number)
+        };
+
+        terms.push(Term::Property {
+            key: name.into(),
+            op,
+            value,
+            negated,
+        });
+    }
+
+    Ok(terms)
+}
+
+fn parse_todo(text: &str, offset: usize) -> Result<TodoMatch, QueryError> {
+    let (not_done, text, offset) = match text.strip_prefix('!') {
+        Some(text) => (true, text, offset + 1),
+        None => (false, text, offset),
+    };
+
+    let mut alternatives = Vec::new();
+    let mut start = offset;
+    for alternative in text.split('|').filter(|_| !text.trim().is_empty()) {
+        let keywords = parse_terms(alternative, start)?
+            .into_iter()
+            .map(|term| match term {
+                Term::Tag { name, negated } => Ok((name, negated)),
+                Term::Property { .. } => Err(QueryError {
+                    offset: start,
+                    message: "expected a todo keyword",
+                }),
+            })
+            .collect::<Result<_, _>>()?;
+        alternatives.push(keywords);
+        start += alternative.len() + 1;
+    }
+
+    Ok(TodoMatch {
+        not_done,
+        alternatives,
+    })
+}
+
+/// Parses a timestamp like `<2020-01-01>`, `[2020-01-01 Wed 10:00]` or
+/// `2020-01-01`, where the day name is optional
+fn date_key(text: &str) -> Option<DateKey> {
+    let text = text.trim();
+    let text = text
+        .strip_prefix('<')
+        .and_then(|text| text.strip_suffix('>'))
+        .or_else(|| {
+            text.strip_prefix('[')
+                .and_then(|text| text.strip_suffix(']'))
+        })
+        .unwrap_or(text);
+
+    let mut words = text.split_whitespace();
+    let mut date = words.next()?.splitn(3, '-');
+    let year = date.next().filter(|year| year.len() == 4)?.parse().ok()?;
+    let month = date.next()?.parse().ok()?;
+    let day = date.next()?.parse().ok()?;
+
+    // the time may be followed by the end of a range, like `10:00-12:00`
+    let (hour, minute) = match words.find(|word| word.contains(':')) {
+        Some(time) => {
+            let time = time.split('-').next()?;
+            let mut parts = time.splitn(2, ':');
+            (parts.next()?.parse().ok()?, parts.next()?.parse().ok()?)
+        }
+        None => (0, 0),
+    };
+
+    Some((year, month, day, hour, minute))
+}
+
+fn datetime_key(datetime: &Datetime<'_>) -> DateKey {
+    (
+        datetime.year,
+        datetime.month,
+        datetime.day,
+        datetime.hour.unwrap_or(0),
+        datetime.minute.unwrap_or(0),
+    )
+}
+
+/// Formats a planning timestamp so that `date_key` parses it back
+fn timestamp_text(timestamp: &Timestamp<'_>) -> String {
+    match timestamp {
+        Timestamp::Active { start, .. }
+        | Timestamp::Inactive { start, .. }
+        | Timestamp::ActiveRange { start, .. }
+        | Timestamp::InactiveRange { start, .. } => {
+            let (year, month, day, hour, minute) = datetime_key(start);
+            format!(
+                "<{:04}-{:02}-{:02} {:02}:{:02}>",
+                year, month, day, hour, minute
+            )
+        }
+        Timestamp::Diary { value } => value.to_string(),
+    }
+}
+
+#[test]
+fn parse() {
+    assert_eq!(
+        Matcher::parse(r#"+work-urgent+TODO="NEXT"|LEVEL>=2"#),
+        Ok(Matcher {
+            alternatives: vec![
+                vec![
+                    Term::Tag {
+                        name: "work".into(),
+                        negated: false
+                    },
+                    Term::Tag {
+                        name: "urgent".into(),
+                        negated: true
+                    },
+                    Term::Property {
+                        key: "TODO".into(),
+                        op: Operator::Eq,
+                        value: Value::String("NEXT".into()),
+                        negated: false
+                    },
+                ],
+                vec![Term::Property {
+                    key: "LEVEL".into(),
+                    op: Operator::Ge,
+                    value: Value::Number(2.),
+                    negated: false
+                }],
+            ],
+            todo: None,
+        })
+    );
+    assert_eq!(
+        Matcher::parse("work/!-WAITING").map(|matcher| matcher.todo),
+        Ok(Some(TodoMatch {
+            not_done: true,
+            alternatives: vec![vec![("WAITING".into(), true)]],
+        }))
+    );
+    assert_eq!(
+        Matcher::parse(r#"CATEGORY="a|b"|work"#).map(|matcher| matcher.alternatives),
+        Ok(vec![
+            vec![Term::Property {
+                key: "CATEGORY".into(),
+                op: Operator::Eq,
+                value: Value::String("a|b".into()),
+                negated: false
+            }],
+            vec![Term::Tag {
+                name: "work".into(),
+                negated: false
+            }],
+        ])
+    );
+    assert_eq!(
+        Matcher::parse("LEVEL=two"),
+        Err(QueryError {
+            offset: 6,
+            message: "expected a number or a quoted string"
+        })
+    );
+    assert_eq!(
+        Matcher::parse("a&&|+"),
+        Err(QueryError {
+            offset: 5,
+            message: "expected a tag or a property"
+        })
+    );
+}
@@ -0,0 +1,191 @@
+use std::borrow::Cow;
+use std::cmp::Reverse;
+use std::ops::Range;
+
+use indextree::NodeId;
+
+use crate::elements::{Element, Link, LinkFormat};
+use crate::org::Org;
+
+/// A radio target to link: its lowercase words and the id of its anchor
+struct Radio {
+    words: Vec<String>,
+    id: String,
+}
+
+/// Links the occurrences of the radio targets of `org` in its text, see
+/// `Org::resolve_radio_targets`
+pub(crate) fn resolve_radio_targets(org: &mut Org<'_>) -> usize {
+    let mut radios: Vec<Radio> = Vec::new();
+    for node in org.root.descendants(&org.arena) {
+        if let Element::RadioTarget(radio_target) = org.arena[node].get() {
+            let id = radio_target.id();
+            if radios.iter().any(|radio| radio.id == id) {
+                continue;
+            }
+            let words = radio_target
+                .target
+                .split_whitespace()
+                .map(|word| word.chars().flat_map(char::to_lowercase).collect())
+                .collect();
+            radios.push(Radio { words, id });
+        }
+    }
+    if radios.is_empty() {
+        return 0;
+    }
+    // longer targets come first, so that they win over the targets they contain
+    radios.sort_by_key(|radio| Reverse(radio.words.iter().map(String::len).sum::<usize>()));
+
+    let texts: Vec<NodeId> = org
+        .root
+        .descendants(&org.arena)
+        .filter(|&node| match org.arena[node].get() {
+            Element::Text { .. } => !node
+                .ancestors(&org.arena)
+                .any(|ancestor| matches!(org.arena[ancestor].get(), Element::Link(_))),
+            _ => false,
+        })
+        .collect();
+
+    let mut links = 0;
+    for node in texts {
+        let value = match org.arena[node].get() {
+            Element::Text { value } => value.clone(),
+            _ => continue,
+        };
+        let occurrences = find_occurrences(&value, &radios);
+        if occurrences.is_empty() {
+            continue;
+        }
+        org.forget_source(node);
+
+        let mut start = 0;
+        for (range, id) in occurrences {
+            if range.start > start {
+                let text = org.arena.new_node(Element::Text {
+                    value: slice(&value, start..range.start),
+                });
+                node.insert_before(text, &mut org.arena);
+            }
+            let link = org.arena.new_node(Element::Link(Link {
+                path: format!("#{}", id).into(),
+                desc: Some(slice(&value, range.clone())),
+                format: LinkFormat::Bracket,
+            }));
+            let text = org.arena.new_node(Element::Text {
+                value: slice(&value, range.clone()),
+            });
+            link.append(text, &mut org.arena);
+            node.insert_before(link, &mut org.arena);
+            start = range.end;
+            links += 1;
+        }
+
+        if start < value.len() {
+            *org.arena[node].get_mut() = Element::Text {
+                value: slice(&value, start..value.len()),
+            };
+        } else {
+            node.remove(&mut org.arena);
+        }
+    }
+
+    org.debug_validate();
+
+    links
+}
+
+/// Returns the ranges of the occurrences of `radios` in `text`, with the id
+/// of their target
+///
+/// Occurrences are whole words, compared case-insensitively, and the words
+/// of a target match words separated by any whitespace.
+fn find_occurrences<'r>(text: &str, radios: &'r [Radio]) -> Vec<(Range<usize>, &'r str)> {
+    let is_boundary = |c: Option<char>| match c {
+        Some(c) => !c.is_alphanumeric(),
+        None => true,
+    };
+
+    let mut occurrences = Vec::new();
+    let mut i = 0;
+    while let Some(c) = text[i..].chars().next() {
+        if is_boundary(text[..i].chars().next_back()) {
+            let occurrence = radios.iter().find_map(|radio| {
+                let end = i + match_words(&text[i..], &radio.words)?;
+                if is_boundary(text[end..].chars().next()) {
+                    Some((i..end, radio.id.as_str()))
+                } else {
+                    None
+                }
+            });
+            if let Some(occurrence) = occurrence {
+                i = occurrence.0.end;
+                occurrences.push(occurrence);
+                continue;
+            }
+        }
+        i += c.len_utf8();
+    }
+    occurrences
+}
+
+/// Returns the length of the prefix of `text` made of `words`, lowercase,
+/// separated by whitespace
+fn match_words(text: &str, words: &[String]) -> Option<usize> {
+    let mut chars = text.char_indices().peekable();
+
+    for (i, word) in words.iter().enumerate() {
+        if i > 0 {
+            let mut spaces = 0;
+            while chars.next_if(|(_, c)| c.is_whitespace()).is_some() {
+                spaces += 1;
+            }
+            if spaces == 0 {
+                return None;
+            }
+        }
+
+        let mut rest = word.as_str();
+        while !rest.is_empty() {
+            let (_, c) = chars.next()?;
+            for lowercase in c.to_lowercase() {
+                rest = rest.strip_prefix(lowercase)?;
+            }
+        }
+    }
+
+    Some(chars.peek().map_or(text.len(), |&(i, _)| i))
+}
+
+/// Returns `range` of `value`, borrowed if `value` is
+fn slice<'a>(value: &Cow<'a, str>, range: Range<usize>) -> Cow<'a, str> {
+    match value {
+        Cow::Borrowed(value) => Cow::Borrowed(&value[range]),
+        Cow::Owned(value) => Cow::Owned(value[range].to_string()),
+    }
+}
+
+#[test]
+fn find_occurrences_() {
+    let radios = [
+        Radio {
+            words: vec!["org".into(), "mode".into()],
+            id: "radio-org-mode".into(),
+        },
+        Radio {
+            words: vec!["straße".into()],
+            id: "radio-straße".into(),
+        },
+    ];
+    let ranges = |text| {
+        find_occurrences(text, &radios)
+            .into_iter()
+            .map(|(range, _)| range)
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(ranges("ORG  Mode, org\nmode."), [0..9, 11..19]);
+    assert_eq!(ranges("Straße and STRASSE"), vec![0..7]);
+    assert_eq!(ranges("org modes, borg mode, org-mode"), []);
+}
@@ -0,0 +1,237 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::ops::Range;
+
+use indextree::NodeId;
+
+use crate::elements::{Element, ElementKind};
+use crate::org::{Event, Org};
+
+/// Syntax feature used by a document
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SyntaxFeature {
+    Headlines,
+    /// Todo keywords of headlines, e.g. `TODO`
+    TodoKeywords,
+    /// Priority cookies of headlines, e.g. `[#A]`
+    Priorities,
+    Tags,
+    /// `SCHEDULED:`, `DEADLINE:` and `CLOSED:` lines
+    Planning,
+    /// Property drawers of headlines
+    Properties,
+    Drawers,
+    Clocks,
+    Lists,
+    Tables,
+    /// Footnote definitions and references
+    Footnotes,
+    /// Citations, e.g. `[cite:@doe2020]`
+    Citations,
+    Links,
+    /// Bold, italic, underlined, strike-through, verbatim and code text
+    Emphasis,
+    Timestamps,
+    /// Statistics cookies, e.g. `[1/3]`
+    Cookies,
+    /// Source blocks and inline source blocks
+    SourceBlocks,
+    /// Babel calls and inline babel calls
+    BabelCalls,
+    /// Export blocks and export snippets
+    Export,
+    DynamicBlocks,
+    /// Special blocks, e.g. `#+BEGIN_warning`
+    SpecialBlocks,
+    Macros,
+    /// Targets and radio targets
+    Targets,
+    Captions,
+    /// Comment lines and comment blocks
+    Comments,
+}
+
+/// Summary of the syntax found in a document
+///
+/// See [`Org::parse_report`].
+///
+/// [`Org::parse_report`]: struct.Org.html#method.parse_report
+#[derive(Clone, Debug, Default)]
+pub struct ParseReport {
+    /// Number of elements of each kind
+    pub element_counts: BTreeMap<ElementKind, usize>,
+    /// Names of special blocks, e.g. `warning` in `#+BEGIN_warning`, whose
+    /// meaning orgize doesn't know
+    pub unknown_blocks: BTreeSet<String>,
+    /// Uppercased keys of keywords orgize and org-mode don't know, e.g.
+    /// `BIND` in `#+BIND: x 1`
+    pub unknown_keywords: BTreeSet<String>,
+    /// Lines left over from malformed or unsupported syntax, which are kept as
+    /// `Raw` elements
+    pub raw_lines: Vec<String>,
+    /// Syntax features used by the document
+    pub features: BTreeSet<SyntaxFeature>,
+}
+
+impl ParseReport {
+    /// Returns the number of elements of kind `kind`
+    pub fn count(&self, kind: ElementKind) -> usize {
+        self.element_counts.get(&kind).cloned().unwrap_or(0)
+    }
+
+    /// Returns `true` if the document uses `feature`
+    pub fn uses(&self, feature: SyntaxFeature) -> bool {
+        self.features.contains(&feature)
+    }
+}
+
+/// A keyword orgize and org-mode don't know, see [`Org::unknown_keywords`]
+///
+/// [`Org::unknown_keywords`]: struct.Org.html#method.unknown_keywords
+#[derive(Clone, Debug, PartialEq)]
+pub struct UnknownKeyword<'b> {
+    pub node: NodeId,
+    /// Key as written, e.g. `BIND` in `#+BIND: x 1`
+    pub key: &'b str,
+    pub value: &'b str,
+    /// Byte range of the keyword in the parsed text, see [`Org::span_of`]
+    ///
+    /// [`Org::span_of`]: struct.Org.html#method.span_of
+    pub span: Option<Range<usize>>,
+}
+
+impl Org<'_> {
+    /// Returns the keywords orgize and org-mode don't know, in document order
+    ///
+    /// They're kept by the org writer, and ignored by exporters unless they
+    /// target the backend, like `#+MD:` for a backend named `md`.
+    ///
+    /// ```rust
+    /// use orgize::Org;
+    ///
+    /// let org = Org::parse("#+TITLE: Known\n#+BIND: x 1\n* Headline\n#+HUGO_DRAFT:\n");
+    /// let keywords = org.unknown_keywords();
+    ///
+    /// assert_eq!(keywords.len(), 2);
+    /// assert_eq!((keywords[0].key, keywords[0].value), ("BIND", "x 1"));
+    /// assert_eq!(keywords[0].span, Some(15..27));
+    /// assert_eq!(keywords[1].key, "HUGO_DRAFT");
+    /// ```
+    pub fn unknown_keywords(&self) -> Vec<UnknownKeyword<'_>> {
+        self.root
+            .descendants(&self.arena)
+            .filter_map(|node| match self.arena[node].get() {
+                Element::Keyword(keyword) if !keyword.is_known() => Some(UnknownKeyword {
+                    node,
+                    key: &keyword.key,
+                    value: &keyword.value,
+                    span: self.span_of(node),
+                }),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Returns a summary of the syntax of this document: what orgize parsed,
+    /// and what it passed through
+    ///
+    /// The report is computed from the elements, so it reflects changes made
+    /// after parsing. Syntax orgize doesn't recognize at all, like LaTeX
+    /// fragments, is parsed as text and isn't reported.
+    ///
+    /// ```rust
+    /// use orgize::{elements::ElementKind, Org, SyntaxFeature};
+    ///
+    /// let org = Org::parse("#+BIND: x 1\n* TODO a\n#+BEGIN_warning\nb\n#+END_warning\n:END:\n");
+    /// let report = org.parse_report();
+    ///
+    /// assert_eq!(report.count(ElementKind::Headline), 1);
+    /// assert!(report.uses(SyntaxFeature::TodoKeywords));
+    /// assert!(report.unknown_blocks.contains("warning"));
+    /// assert!(report.unknown_keywords.contains("BIND"));
+    /// assert_eq!(report.raw_lines, [":END:"]);
+    /// ```
+    pub fn parse_report(&self) -> ParseReport {
+        use SyntaxFeature::*;
+
+        let mut report = ParseReport::default();
+
+        for event in self.iter() {
+            let element = match event {
+                Event::Start(element) => element,
+                Event::End(_) => continue,
+            };
+
+            *report.element_counts.entry(element.kind()).or_insert(0) += 1;
+
+            let feature = match element {
+                Element::SpecialBlock(block) => {
+                    report.unknown_blocks.insert(block.name.to_string());
+                    Some(SpecialBlocks)
+                }
+                Element::Keyword(keyword) => {
+                    if !keyword.is_known() {
+                        report
+                            .unknown_keywords
+                            .insert(keyword.key.to_ascii_uppercase());
+                    }
+                    None
+                }
+                Element::Raw { value } => {
+                    report.raw_lines.push(value.to_string());
+                    None
+                }
+                Element::Title(title) => {
+                    if title.keyword.is_some() {
+                        report.features.insert(TodoKeywords);
+                    }
+                    if title.priority.is_some() {
+                        report.features.insert(Priorities);
+                    }
+                    if !title.tags.is_empty() {
+                        report.features.insert(Tags);
+                    }
+                    if title.planning.is_some() {
+                        report.features.insert(Planning);
+                    }
+                    if !title.properties.is_empty() {
+                        report.features.insert(Properties);
+                    }
+                    None
+                }
+                Element::Headline { .. } => Some(Headlines),
+                Element::Drawer(_) => Some(Drawers),
+                Element::Clock(_) => Some(Clocks),
+                Element::List(_) => Some(Lists),
+                Element::Table(_) => Some(Tables),
+                Element::FnDef(_) | Element::FnRef(_) => Some(Footnotes),
+                Element::Citation(_) => Some(Citations),
+                Element::Link(_) => Some(Links),
+                Element::Bold
+                | Element::Italic
+                | Element::Underline
+                | Element::Subscript { .. }
+                | Element::Superscript { .. }
+                | Element::Strike
+                | Element::Verbatim { .. }
+                | Element::Code { .. } => Some(Emphasis),
+                Element::Timestamp(_) => Some(Timestamps),
+                Element::Cookie(_) => Some(Cookies),
+                Element::SourceBlock(_) | Element::InlineSrc(_) => Some(SourceBlocks),
+                Element::BabelCall(_) | Element::InlineCall(_) => Some(BabelCalls),
+                Element::ExportBlock(_) | Element::Snippet(_) => Some(Export),
+                Element::DynBlock(_) => Some(DynamicBlocks),
+                Element::Macros(_) => Some(Macros),
+                Element::Target(_) | Element::RadioTarget(_) => Some(Targets),
+                Element::Caption(_) => Some(Captions),
+                Element::Comment { .. } | Element::CommentBlock(_) => Some(Comments),
+                _ => None,
+            };
+
+            if let Some(feature) = feature {
+                report.features.insert(feature);
+            }
+        }
+
+        report
+    }
+}
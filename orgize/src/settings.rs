@@ -0,0 +1,676 @@
+use crate::config::{ParseConfig, SubSuperscripts};
+use crate::elements::{Element, Priority};
+use crate::node::HeadlineNode;
+use crate::org::{Event, Org};
+
+/// Startup flags changing how headline levels are displayed and edited
+///
+/// With `#+STARTUP: odd`, only odd levels are used: headlines have 1, 3, 5…
+/// stars, displayed as levels 1, 2, 3…, and promoting or demoting a headline
+/// changes its level by 2.
+///
+/// ```rust
+/// use orgize::StartupOptions;
+///
+/// let startup = StartupOptions::from_flags(&["odd", "hidestars"]);
+///
+/// assert_eq!(startup.display_level(5), 3);
+/// assert_eq!(startup.real_level(3), 5);
+/// assert_eq!(startup.change_level(5, -1), 3);
+/// assert_eq!(startup.change_level(1, -1), 1);
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StartupOptions {
+    /// Set by `odd`, unset by `oddeven`
+    pub odd: bool,
+    /// Set by `hidestars`, unset by `showstars`: all stars of a headline but
+    /// the last one are hidden
+    pub hide_stars: bool,
+}
+
+impl StartupOptions {
+    /// Create startup options from `#+STARTUP:` flags, the last flag wins
+    pub fn from_flags<S: AsRef<str>>(flags: &[S]) -> StartupOptions {
+        let mut options = StartupOptions::default();
+        for flag in flags {
+            match flag.as_ref() {
+                "odd" => options.odd = true,
+                "oddeven" => options.odd = false,
+                "hidestars" => options.hide_stars = true,
+                "showstars" => options.hide_stars = false,
+                _ => (),
+            }
+        }
+        options
+    }
+
+    /// Returns the number of levels between a headline and its children
+    pub fn level_step(&self) -> usize {
+        if self.odd {
+            2
+        } else {
+            1
+        }
+    }
+
+    /// Returns the level `level` is displayed as
+    ///
+    /// Under `odd`, even levels are displayed like the next odd level, as in
+    /// Emacs.
+    pub fn display_level(&self, level: usize) -> usize {
+        if self.odd {
+            level / 2 + 1
+        } else {
+            level
+        }
+    }
+
+    /// Returns the level of headlines displayed as `display_level`
+    pub fn real_level(&self, display_level: usize) -> usize {
+        if self.odd {
+            display_level.max(1) * 2 - 1
+        } else {
+            display_level
+        }
+    }
+
+    /// Returns `level` changed by `change` displayed levels, e.g. `-1` to
+    /// promote a headline or `1` to demote it
+    ///
+    /// The returned level is at least 1.
+    pub fn change_level(&self, level: usize, change: isize) -> usize {
+        let level = level as isize + change * self.level_step() as isize;
+        level.max(1) as usize
+    }
+}
+
+/// Export settings of `#+OPTIONS:`, e.g. `#+OPTIONS: toc:nil num:t H:3`
+///
+/// Options which aren't given are `None`, unknown options and invalid
+/// values are ignored. Exporters use their own defaults for missing
+/// options, which may differ from Emacs: the html export of orgize numbers
+/// no heading and converts no special string unless asked to.
+///
+/// ```rust
+/// use orgize::{ExportSettings, Levels, SubSuperscripts};
+///
+/// let mut options = ExportSettings::parse("toc:nil num:2 H:3 ^:{} tex:t");
+///
+/// assert_eq!(options.toc, Some(Levels::None));
+/// assert_eq!(options.num, Some(Levels::UpTo(2)));
+/// assert_eq!(options.headline_levels, Some(3));
+/// assert_eq!(options.sub_superscripts, Some(SubSuperscripts::Braces));
+/// assert_eq!(options.special_strings, None);
+///
+/// options.update("num:t -:t");
+/// assert_eq!(options.num, Some(Levels::All));
+/// assert_eq!(options.special_strings, Some(true));
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ExportSettings {
+    /// `num:`, headlines whose section number is written in their heading
+    pub num: Option<Levels>,
+    /// `toc:`, headlines listed in a table of contents at the start of the
+    /// document
+    pub toc: Option<Levels>,
+    /// `H:`, level of the deepest headlines exported as headings
+    pub headline_levels: Option<usize>,
+    /// `-:`, converts special strings: `--` and `---` to dashes, `...` to an
+    /// ellipsis and `\-` to a soft hyphen
+    pub special_strings: Option<bool>,
+    /// `\n:`, preserves the line breaks of paragraphs
+    pub preserve_breaks: Option<bool>,
+    /// `^:`, subscripts and superscripts parsed
+    pub sub_superscripts: Option<SubSuperscripts>,
+}
+
+/// Headlines an export option applies to, by level
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Levels {
+    /// No headline, `nil`
+    None,
+    /// All headlines, `t`
+    All,
+    /// Headlines up to this level, e.g. `2`
+    UpTo(usize),
+}
+
+impl Levels {
+    fn parse(value: &str) -> Option<Levels> {
+        match value {
+            "nil" => Some(Levels::None),
+            "t" => Some(Levels::All),
+            _ => value.parse().ok().map(Levels::UpTo),
+        }
+    }
+
+    /// Returns `true` if the option applies to headlines of level `level`
+    pub fn contains(self, level: usize) -> bool {
+        match self {
+            Levels::None => false,
+            Levels::All => true,
+            Levels::UpTo(max) => level <= max,
+        }
+    }
+}
+
+impl ExportSettings {
+    /// Parses the value of an `#+OPTIONS:` keyword, made of `KEY:VALUE`
+    /// items separated by whitespace
+    pub fn parse(options: &str) -> ExportSettings {
+        let mut settings = ExportSettings::default();
+        settings.update(options);
+        settings
+    }
+
+    /// Updates these options with the ones given by `options`, the value of
+    /// an `#+OPTIONS:` keyword, the last value of an option wins
+    pub fn update(&mut self, options: &str) {
+        let flag = |value: &str| match value {
+            "t" => Some(true),
+            "nil" => Some(false),
+            _ => None,
+        };
+
+        for option in options.split_whitespace() {
+            let i = match option.find(':') {
+                Some(i) => i,
+                None => continue,
+            };
+            let value = &option[i + 1..];
+            match &option[0..i] {
+                "num" => self.num = Levels::parse(value).or(self.num),
+                "toc" => self.toc = Levels::parse(value).or(self.toc),
+                "H" => self.headline_levels = value.parse().ok().or(self.headline_levels),
+                "-" => self.special_strings = flag(value).or(self.special_strings),
+                "\\n" => self.preserve_breaks = flag(value).or(self.preserve_breaks),
+                "^" => {
+                    self.sub_superscripts = match value {
+                        "t" => Some(SubSuperscripts::Always),
+                        "{}" => Some(SubSuperscripts::Braces),
+                        "nil" => Some(SubSuperscripts::Never),
+                        _ => self.sub_superscripts,
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+}
+
+/// Resolves the effective value of in-buffer settings
+///
+/// Settings are looked up in the following order, from the lowest
+/// precedence to the highest:
+///
+/// 1. `ParseConfig`
+/// 2. contents of the files included by `#+SETUPFILE:`
+/// 3. in-buffer keywords, e.g. `#+TODO:` or `#+OPTIONS:`
+/// 4. properties of a headline or of its ancestors, when a headline is given
+///
+/// Settings which can be given multiple times, like todo keywords, tags,
+/// startup flags and link abbreviations, accumulate in that order. For the
+/// other settings, the value of highest precedence wins.
+///
+/// ```rust
+/// use orgize::{Org, ParseConfig, SettingsResolver};
+///
+/// let org = Org::parse("#+SETUPFILE: common.org\n#+STARTUP: overview");
+/// let config = ParseConfig::default();
+/// let settings = SettingsResolver::with_setup_files(&org, &config, |file| {
+///     // orgize never reads files by itself
+///     assert_eq!(file, "common.org");
+///     Some("#+TODO: TODO NEXT | DONE".to_string())
+/// });
+///
+/// assert_eq!(settings.todo_keywords().0, ["TODO", "NEXT"]);
+/// assert_eq!(settings.startup(), ["overview"]);
+/// ```
+pub struct SettingsResolver<'a, 'b> {
+    org: &'b Org<'a>,
+    config: &'b ParseConfig,
+    /// Uppercased keys and values of the keywords, from the lowest precedence
+    keywords: Vec<(String, String)>,
+}
+
+impl<'a, 'b> SettingsResolver<'a, 'b> {
+    /// Create a resolver ignoring `#+SETUPFILE:` keywords
+    pub fn new(org: &'b Org<'a>, config: &'b ParseConfig) -> SettingsResolver<'a, 'b> {
+        SettingsResolver::with_setup_files(org, config, |_| None)
+    }
+
+    /// Create a resolver which gets the contents of the file named by a
+    /// `#+SETUPFILE:` keyword from `load`
+    ///
+    /// `load` returns `None` if the file can't be found. Setup files can
+    /// include other setup files, but each file is only loaded once.
+    pub fn with_setup_files<F>(
+        org: &'b Org<'a>,
+        config: &'b ParseConfig,
+        mut load: F,
+    ) -> SettingsResolver<'a, 'b>
+    where
+        F: FnMut(&str) -> Option<String>,
+    {
+        let mut keywords = Vec::new();
+        let mut loaded = Vec::new();
+        collect_setup_files(org, &mut load, &mut loaded, &mut keywords);
+        collect_keywords(org, &mut keywords);
+
+        SettingsResolver {
+            org,
+            config,
+            keywords,
+        }
+    }
+
+    /// Return the values of keyword `key`, from the lowest precedence
+    pub fn keywords<'c>(&'c self, key: &'c str) -> impl Iterator<Item = &'c str> + 'c {
+        self.keywords
+            .iter()
+            .filter(move |(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Return the value of keyword `key` with the highest precedence
+    pub fn keyword(&self, key: &str) -> Option<&str> {
+        self.keywords
+            .iter()
+            .rev()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Return the todo and done keywords
+    ///
+    /// Keywords from `#+TODO:`, `#+SEQ_TODO:` and `#+TYP_TODO:` replace the ones
+    /// from `ParseConfig`.
+    pub fn todo_keywords(&self) -> (Vec<String>, Vec<String>) {
+        let (done_keywords, todo_keywords): (Vec<_>, Vec<_>) = self
+            .todo_keyword_settings()
+            .into_iter()
+            .partition(|keyword| keyword.done);
+
+        (
+            todo_keywords
+                .into_iter()
+                .map(|keyword| keyword.name)
+                .collect(),
+            done_keywords
+                .into_iter()
+                .map(|keyword| keyword.name)
+                .collect(),
+        )
+    }
+
+    /// Return the todo and done keywords, in order, with their fast access
+    /// keys and logging settings, e.g. `WAIT(w@/!)`
+    ///
+    /// Keywords from `ParseConfig` have no fast access key nor logging.
+    ///
+    /// ```rust
+    /// use orgize::{Org, ParseConfig, SettingsResolver, TodoLogging};
+    ///
+    /// let org = Org::parse("#+TODO: TODO(t) WAIT(w@/!) | DONE(d!)");
+    /// let config = ParseConfig::default();
+    /// let keywords = SettingsResolver::new(&org, &config).todo_keyword_settings();
+    ///
+    /// assert_eq!(keywords[1].name, "WAIT");
+    /// assert_eq!(keywords[1].fast_key, Some('w'));
+    /// assert_eq!(keywords[1].log_on_enter, Some(TodoLogging::Note));
+    /// assert_eq!(keywords[1].log_on_leave, Some(TodoLogging::Timestamp));
+    /// assert!(keywords[2].done);
+    /// ```
+    pub fn todo_keyword_settings(&self) -> Vec<TodoKeyword> {
+        let mut keywords = Vec::new();
+
+        for value in self
+            .keywords("TODO")
+            .chain(self.keywords("SEQ_TODO"))
+            .chain(self.keywords("TYP_TODO"))
+        {
+            let words: Vec<_> = value.split_whitespace().collect();
+            // without a `|`, the last keyword is the done keyword
+            let done_start = words
+                .iter()
+                .position(|word| *word == "|")
+                .unwrap_or_else(|| words.len().saturating_sub(1));
+            keywords.extend(
+                words
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, word)| **word != "|")
+                    .map(|(i, word)| TodoKeyword::parse(word, i >= done_start)),
+            );
+        }
+
+        if keywords.is_empty() {
+            let keyword = |name: &String, done| TodoKeyword {
+                name: name.clone(),
+                done,
+                fast_key: None,
+                log_on_enter: None,
+                log_on_leave: None,
+            };
+            keywords.extend(
+                self.config
+                    .todo_keywords
+                    .iter()
+                    .map(|name| keyword(name, false)),
+            );
+            keywords.extend(
+                self.config
+                    .done_keywords
+                    .iter()
+                    .map(|name| keyword(name, true)),
+            );
+        }
+
+        keywords
+    }
+
+    /// Return the highest, the lowest and the default priorities set by
+    /// `#+PRIORITIES:`, e.g. `#+PRIORITIES: A E C` or `#+PRIORITIES: 1 64 32`
+    pub fn priorities(&self) -> Option<(Priority, Priority, Priority)> {
+        let mut priorities = self
+            .keyword("PRIORITIES")?
+            .split_whitespace()
+            .map(Priority::parse);
+        match (priorities.next(), priorities.next(), priorities.next()) {
+            (Some(Some(highest)), Some(Some(lowest)), default) => {
+                let default = default.and_then(|default| default).unwrap_or(lowest);
+                Some((highest, lowest, default))
+            }
+            _ => None,
+        }
+    }
+
+    /// Return a `ParseConfig` using the effective todo keywords, priorities
+    /// and `^:` export option
+    pub fn parse_config(&self) -> ParseConfig {
+        let (todo_keywords, done_keywords) = self.todo_keywords();
+        let (highest_priority, lowest_priority) = match self.priorities() {
+            Some((highest, lowest, _)) => (highest, lowest),
+            None => (self.config.highest_priority, self.config.lowest_priority),
+        };
+        ParseConfig {
+            todo_keywords,
+            done_keywords,
+            highest_priority,
+            lowest_priority,
+            use_sub_superscripts: self
+                .export_settings(None)
+                .sub_superscripts
+                .unwrap_or(self.config.use_sub_superscripts),
+            ..self.config.clone()
+        }
+    }
+
+    /// Return the tags defined by `#+TAGS:`, without their fast access keys
+    pub fn tags(&self) -> Vec<&str> {
+        self.keywords("TAGS")
+            .flat_map(str::split_whitespace)
+            .filter(|word| !["{", "}", "[", "]", ":", "\\n"].contains(word))
+            .map(strip_fast_access_key)
+            .collect()
+    }
+
+    /// Return the tags defined by `#+FILETAGS:`
+    pub fn file_tags(&self) -> Vec<&str> {
+        self.keywords("FILETAGS")
+            .flat_map(|value| value.split(':'))
+            .map(str::trim)
+            .filter(|tag| !tag.is_empty())
+            .collect()
+    }
+
+    /// Return the tags set by `#+EXCLUDE_TAGS:`
+    pub fn exclude_tags(&self) -> Vec<&str> {
+        self.keywords("EXCLUDE_TAGS")
+            .flat_map(str::split_whitespace)
+            .collect()
+    }
+
+    /// Return the tags set by `#+SELECT_TAGS:`
+    pub fn select_tags(&self) -> Vec<&str> {
+        self.keywords("SELECT_TAGS")
+            .flat_map(str::split_whitespace)
+            .collect()
+    }
+
+    /// Return the bibliography files set by `#+BIBLIOGRAPHY:`
+    pub fn bibliography(&self) -> Vec<&str> {
+        self.keywords("BIBLIOGRAPHY")
+            .map(str::trim)
+            .filter(|file| !file.is_empty())
+            .collect()
+    }
+
+    /// Return the citation processor set by `#+CITE_EXPORT:`, e.g. `csl` in
+    /// `#+CITE_EXPORT: csl ieee.csl`, followed by its options
+    pub fn cite_export(&self) -> Option<&str> {
+        self.keyword("CITE_EXPORT").map(str::trim)
+    }
+
+    /// Return the flags set by `#+STARTUP:`
+    pub fn startup(&self) -> Vec<&str> {
+        self.keywords("STARTUP")
+            .flat_map(str::split_whitespace)
+            .collect()
+    }
+
+    /// Return the startup options set by `#+STARTUP:`
+    pub fn startup_options(&self) -> StartupOptions {
+        StartupOptions::from_flags(&self.startup())
+    }
+
+    /// Return the url of link abbreviation `key`, defined by `#+LINK:`
+    pub fn link_abbreviation(&self, key: &str) -> Option<&str> {
+        self.keywords("LINK")
+            .filter_map(|value| {
+                let value = value.trim();
+                let i = value.find(char::is_whitespace)?;
+                if &value[0..i] == key {
+                    Some(value[i..].trim())
+                } else {
+                    None
+                }
+            })
+            .last()
+    }
+
+    /// Return the value of export option `key`, e.g. `toc` or `num`
+    ///
+    /// If `headline` is given, the `EXPORT_OPTIONS` property of it and of its
+    /// ancestors are looked up before `#+OPTIONS:`.
+    pub fn export_option(&self, key: &str, headline: Option<HeadlineNode>) -> Option<&str> {
+        headline
+            .and_then(|headline| {
+                self.inherited_properties(headline, "EXPORT_OPTIONS")
+                    .find_map(|options| find_option(options, key))
+            })
+            .or_else(|| {
+                self.keywords("OPTIONS")
+                    .filter_map(|options| find_option(options, key))
+                    .last()
+            })
+    }
+
+    /// Return the export options set by `#+OPTIONS:` keywords
+    ///
+    /// If `headline` is given, the `EXPORT_OPTIONS` properties of it and of
+    /// its ancestors override them, nearest last.
+    pub fn export_settings(&self, headline: Option<HeadlineNode>) -> ExportSettings {
+        let mut options = ExportSettings::default();
+        for value in self.keywords("OPTIONS") {
+            options.update(value);
+        }
+        if let Some(headline) = headline {
+            let properties: Vec<_> = self
+                .inherited_properties(headline, "EXPORT_OPTIONS")
+                .collect();
+            for value in properties.into_iter().rev() {
+                options.update(value);
+            }
+        }
+        options
+    }
+
+    /// Return the value of property `key` at `headline`
+    ///
+    /// Looks up the properties of `headline` and of its ancestors, then the
+    /// values set by `#+PROPERTY:`.
+    pub fn property(&self, key: &str, headline: HeadlineNode) -> Option<&str> {
+        self.inherited_properties(headline, key).next().or_else(|| {
+            self.keywords("PROPERTY")
+                .filter_map(|value| {
+                    let value = value.trim();
+                    let i = value.find(char::is_whitespace)?;
+                    if value[0..i].eq_ignore_ascii_case(key) {
+                        Some(value[i..].trim())
+                    } else {
+                        None
+                    }
+                })
+                .last()
+        })
+    }
+
+    /// Return the values of property `key` of `headline` and of its ancestors,
+    /// nearest first
+    fn inherited_properties<'c>(
+        &self,
+        headline: HeadlineNode,
+        key: &'c str,
+    ) -> impl Iterator<Item = &'b str> + 'c
+    where
+        'b: 'c,
+    {
+        let org = self.org;
+        let mut headline = Some(headline);
+
+        std::iter::from_fn(move || {
+            let current = headline?;
+            headline = current.parent(org);
+            Some(current)
+        })
+        .filter_map(move |headline| headline.property(key, org))
+    }
+}
+
+/// A todo keyword with its settings, see
+/// [`SettingsResolver::todo_keyword_settings`]
+///
+/// [`SettingsResolver::todo_keyword_settings`]: struct.SettingsResolver.html#method.todo_keyword_settings
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TodoKeyword {
+    pub name: String,
+    /// Set for the keywords after the `|`, or for the last keyword without `|`
+    pub done: bool,
+    /// Key selecting the keyword, e.g. `w` for `WAIT(w)`
+    pub fast_key: Option<char>,
+    /// What is logged when a headline enters this state, e.g. `@` for
+    /// `WAIT(w@)`
+    pub log_on_enter: Option<TodoLogging>,
+    /// What is logged when a headline leaves this state for one without
+    /// logging, e.g. `!` for `WAIT(w/!)`
+    pub log_on_leave: Option<TodoLogging>,
+}
+
+/// Logging of a todo state change
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TodoLogging {
+    /// `!`, a timestamp
+    Timestamp,
+    /// `@`, a timestamp and a note
+    Note,
+}
+
+impl TodoKeyword {
+    fn parse(word: &str, done: bool) -> TodoKeyword {
+        let name = strip_fast_access_key(word);
+        let settings = &word[name.len()..];
+        let settings = settings
+            .strip_prefix('(')
+            .and_then(|settings| settings.strip_suffix(')'))
+            .unwrap_or_default();
+
+        let fast_key = settings.chars().next().filter(|c| !"@!/".contains(*c));
+        let logging = &settings[fast_key.map_or(0, char::len_utf8)..];
+        let (enter, leave) = match logging.find('/') {
+            Some(i) => (&logging[0..i], &logging[i + 1..]),
+            None => (logging, ""),
+        };
+        let logging = |flag: &str| match flag {
+            "!" => Some(TodoLogging::Timestamp),
+            "@" => Some(TodoLogging::Note),
+            _ => None,
+        };
+
+        TodoKeyword {
+            name: name.to_string(),
+            done,
+            fast_key,
+            log_on_enter: logging(enter),
+            log_on_leave: logging(leave),
+        }
+    }
+}
+
+fn strip_fast_access_key(word: &str) -> &str {
+    match word.find('(') {
+        Some(i) if i > 0 && word.ends_with(')') => &word[0..i],
+        _ => word,
+    }
+}
+
+// finds `key:value` in `options` and returns `value`, the last one wins
+pub(crate) fn find_option<'a>(options: &'a str, key: &str) -> Option<&'a str> {
+    options.split_whitespace().rev().find_map(|option| {
+        let i = option.find(':')?;
+        if &option[0..i] == key {
+            Some(&option[i + 1..])
+        } else {
+            None
+        }
+    })
+}
+
+fn collect_keywords(org: &Org<'_>, keywords: &mut Vec<(String, String)>) {
+    for event in org.iter() {
+        if let Event::Start(Element::Keyword(keyword)) = event {
+            if !keyword.key.eq_ignore_ascii_case("SETUPFILE") {
+                keywords.push((keyword.key.to_uppercase(), keyword.value.to_string()));
+            }
+        }
+    }
+}
+
+fn collect_setup_files<F>(
+    org: &Org<'_>,
+    load: &mut F,
+    loaded: &mut Vec<String>,
+    keywords: &mut Vec<(String, String)>,
+) where
+    F: FnMut(&str) -> Option<String>,
+{
+    for event in org.iter() {
+        if let Event::Start(Element::Keyword(keyword)) = event {
+            if !keyword.key.eq_ignore_ascii_case("SETUPFILE") {
+                continue;
+            }
+
+            let file = keyword.value.trim().trim_matches('"');
+            if file.is_empty() || loaded.iter().any(|f| f == file) {
+                continue;
+            }
+            loaded.push(file.to_string());
+
+            if let Some(contents) = load(file) {
+                let setup = Org::parse(&contents);
+                collect_setup_files(&setup, load, loaded, keywords);
+                collect_keywords(&setup, keywords);
+            }
+        }
+    }
+}
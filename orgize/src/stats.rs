@@ -0,0 +1,263 @@
+use std::time::Duration;
+
+use indextree::NodeId;
+
+use crate::elements::Element;
+use crate::node::HeadlineNode;
+use crate::org::Org;
+
+/// Options of [`HeadlineNode::word_count`] and [`Org::per_headline_stats`]
+///
+/// [`HeadlineNode::word_count`]: struct.HeadlineNode.html#method.word_count
+/// [`Org::per_headline_stats`]: struct.Org.html#method.per_headline_stats
+#[derive(Clone, Debug)]
+pub struct CountOptions {
+    /// Count the words of headline titles
+    ///
+    /// Default: `true`
+    pub titles: bool,
+    /// Count the words of source, example and export blocks, fixed-width
+    /// lines and inline source blocks
+    ///
+    /// Default: `false`
+    pub code: bool,
+    /// Count the words of drawers, like `LOGBOOK`, except property drawers
+    ///
+    /// Default: `false`
+    pub drawers: bool,
+    /// Count the words of `#+CAPTION:` keywords
+    ///
+    /// Default: `true`
+    pub captions: bool,
+    /// Count each Chinese character and Japanese kana as a word, since
+    /// these scripts don't separate words with spaces, instead of counting
+    /// the characters between two spaces as one word
+    ///
+    /// Default: `true`
+    pub cjk_characters: bool,
+}
+
+impl Default for CountOptions {
+    fn default() -> Self {
+        CountOptions {
+            titles: true,
+            code: false,
+            drawers: false,
+            captions: true,
+            cjk_characters: true,
+        }
+    }
+}
+
+/// Words of a headline, returned by [`Org::per_headline_stats`]
+///
+/// [`Org::per_headline_stats`]: struct.Org.html#method.per_headline_stats
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HeadlineStats {
+    pub headline: NodeId,
+    pub level: usize,
+    /// Raw title of the headline
+    pub title: String,
+    /// Words of the title and the section of the headline
+    pub words: usize,
+    /// Words of the headline and its subtree
+    pub subtree_words: usize,
+}
+
+impl HeadlineStats {
+    /// Returns the time needed to read the subtree at `wpm` words per
+    /// minute, see [`HeadlineNode::reading_time`]
+    ///
+    /// [`HeadlineNode::reading_time`]: struct.HeadlineNode.html#method.reading_time
+    pub fn reading_time(&self, wpm: usize) -> Duration {
+        reading_time(self.subtree_words, wpm)
+    }
+}
+
+impl HeadlineNode {
+    /// Returns the number of words of this headline and its subtree
+    ///
+    /// Words are counted in the text of paragraphs, lists, tables, quotes
+    /// and footnote definitions, and in verbatim and code objects.
+    /// Comments, keywords, planning and clock lines are never counted.
+    ///
+    /// ```rust
+    /// use orgize::{CountOptions, Org};
+    ///
+    /// let org = Org::parse(
+    ///     "* Trip to 東京\nWe ate /ramen/ at 一蘭.\n\
+    ///      #+BEGIN_SRC sh\nls -la\n#+END_SRC\n\
+    ///      :LOGBOOK:\nNote taken\n:END:\n",
+    /// );
+    /// let headline = org.headlines().next().unwrap();
+    ///
+    /// assert_eq!(headline.word_count(&CountOptions::default(), &org), 10);
+    /// let options = CountOptions {
+    ///     titles: false,
+    ///     code: true,
+    ///     drawers: true,
+    ///     cjk_characters: false,
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(headline.word_count(&options, &org), 9);
+    /// ```
+    pub fn word_count(self, options: &CountOptions, org: &Org<'_>) -> usize {
+        count_node(org, self.node, options)
+    }
+
+    /// Returns the time needed to read this headline and its subtree at
+    /// `wpm` words per minute, rounded up to the second
+    ///
+    /// The words are counted with the default [`CountOptions`], so Chinese
+    /// and Japanese characters count as words.
+    ///
+    /// ```rust
+    /// use std::time::Duration;
+    ///
+    /// use orgize::Org;
+    ///
+    /// let text = format!("* Post\n{}", "word ".repeat(299));
+    /// let org = Org::parse(&text);
+    /// let headline = org.headlines().next().unwrap();
+    ///
+    /// assert_eq!(headline.reading_time(200, &org), Duration::from_secs(90));
+    /// ```
+    ///
+    /// [`CountOptions`]: struct.CountOptions.html
+    pub fn reading_time(self, wpm: usize, org: &Org<'_>) -> Duration {
+        reading_time(self.word_count(&CountOptions::default(), org), wpm)
+    }
+}
+
+impl Org<'_> {
+    /// Returns the word counts of the headlines up to `max_level`, in
+    /// document order, e.g. to render a summary table
+    ///
+    /// ```rust
+    /// use orgize::{CountOptions, Org};
+    ///
+    /// let org = Org::parse("* Intro\nHello world.\n** Details\nOne two three.\n* End\nBye.\n");
+    /// let stats = org.per_headline_stats(1, &CountOptions::default());
+    ///
+    /// assert_eq!(stats.len(), 2);
+    /// assert_eq!(stats[0].title, "Intro");
+    /// assert_eq!((stats[0].words, stats[0].subtree_words), (3, 7));
+    /// assert_eq!((stats[1].words, stats[1].subtree_words), (2, 2));
+    /// ```
+    pub fn per_headline_stats(
+        &self,
+        max_level: usize,
+        options: &CountOptions,
+    ) -> Vec<HeadlineStats> {
+        self.headlines()
+            .filter(|headline| headline.level() <= max_level)
+            .map(|headline| {
+                let mut words = count_node(self, headline.title_node, options);
+                if let Some(section) = headline.section_node {
+                    words += count_node(self, section, options);
+                }
+                HeadlineStats {
+                    headline: headline.headline_node(),
+                    level: headline.level(),
+                    title: headline.title(self).raw.to_string(),
+                    words,
+                    subtree_words: headline.word_count(options, self),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Returns the number of words of `node` and its descendants
+fn count_node(org: &Org<'_>, node: NodeId, options: &CountOptions) -> usize {
+    let count = |text: &str| count_words(text, options.cjk_characters);
+
+    let own = match org.arena[node].get() {
+        Element::Text { value } | Element::Verbatim { value } | Element::Code { value } => {
+            count(value)
+        }
+        Element::RadioTarget(radio_target) => count(&radio_target.target),
+        Element::Title(_) if !options.titles => return 0,
+        Element::Caption(_) if !options.captions => return 0,
+        Element::Drawer(_) if !options.drawers => return 0,
+        Element::SourceBlock(block) if options.code => count(&block.contents),
+        Element::ExampleBlock(block) if options.code => count(&block.contents),
+        Element::ExportBlock(block) if options.code => count(&block.contents),
+        Element::InlineSrc(inline_src) if options.code => count(&inline_src.body),
+        Element::FixedWidth { value } if options.code => value
+            .lines()
+            .map(|line| count(line.trim_start().trim_start_matches(':')))
+            .sum(),
+        _ => 0,
+    };
+
+    own + node
+        .children(&org.arena)
+        .map(|child| count_node(org, child, options))
+        .sum::<usize>()
+}
+
+/// Returns the number of words of `text`, the runs of characters between
+/// whitespace which contain a letter or a digit
+///
+/// With `cjk_characters`, Chinese characters and kana are words of their
+/// own, and split the runs they're part of.
+fn count_words(text: &str, cjk_characters: bool) -> usize {
+    let mut words = 0;
+    for run in text.split_whitespace() {
+        if !cjk_characters {
+            words += if run.chars().any(char::is_alphanumeric) {
+                1
+            } else {
+                0
+            };
+            continue;
+        }
+
+        let mut in_word = false;
+        for c in run.chars() {
+            if is_cjk(c) {
+                words += 1;
+                in_word = false;
+            } else if c.is_alphanumeric() && !in_word {
+                words += 1;
+                in_word = true;
+            }
+        }
+    }
+    words
+}
+
+/// Returns `true` for Chinese characters, and Japanese hiragana and katakana
+fn is_cjk(c: char) -> bool {
+    matches!(
+        c,
+        '\u{3040}'..='\u{30FF}'
+            | '\u{31F0}'..='\u{31FF}'
+            | '\u{3400}'..='\u{4DBF}'
+            | '\u{4E00}'..='\u{9FFF}'
+            | '\u{F900}'..='\u{FAFF}'
+            | '\u{FF66}'..='\u{FF9F}'
+            | '\u{20000}'..='\u{2FA1F}'
+    )
+}
+
+/// Returns the time needed to read `words` at `wpm` words per minute,
+/// rounded up to the second
+fn reading_time(words: usize, wpm: usize) -> Duration {
+    // exact quotients, like 90.0, aren't rounded up
+    let seconds = (words * 60) as f64 / wpm.max(1) as f64;
+    Duration::from_secs(seconds.ceil() as u64)
+}
+
+#[test]
+fn count_words_() {
+    assert_eq!(count_words("Hello, world!", true), 2);
+    assert_eq!(count_words("- a -- b —", true), 2);
+    assert_eq!(count_words("日本語のtext", true), 5);
+    assert_eq!(count_words("日本語のtext", false), 1);
+    assert_eq!(count_words("한국어 단어", true), 2);
+    assert_eq!(count_words("x2 3.14 don't", false), 3);
+    assert_eq!(reading_time(1, 200), Duration::from_secs(1));
+    assert_eq!(reading_time(400, 200), Duration::from_secs(120));
+}
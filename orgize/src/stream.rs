@@ -0,0 +1,242 @@
+use std::ops::Range;
+
+use indextree::NodeEdge;
+use serde::ser::{SerializeMap, Serializer};
+use serde::Serialize;
+
+use crate::elements::{Element, ElementKind};
+use crate::org::Org;
+
+/// An event of the flat serialization of a document, see
+/// [`Org::serialize_events`]
+///
+/// [`Org::serialize_events`]: struct.Org.html#method.serialize_events
+#[derive(Debug)]
+pub enum StreamEvent<'a, 'b> {
+    /// Start of a container element, followed by its children and an `End`
+    Start {
+        element: &'b Element<'a>,
+        span: Option<Range<usize>>,
+    },
+    /// End of a container element
+    End { kind: ElementKind },
+    /// An element which isn't a container
+    Element {
+        element: &'b Element<'a>,
+        span: Option<Range<usize>>,
+    },
+}
+
+/// Kind of a `StreamEvent`, the `event` field of its serialization
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StreamEventKind {
+    Start,
+    End,
+    Element,
+}
+
+impl StreamEventKind {
+    /// Returns the kind named `name`, which is `start`, `end` or `element`
+    pub fn from_name(name: &str) -> Option<StreamEventKind> {
+        match name {
+            "start" => Some(StreamEventKind::Start),
+            "end" => Some(StreamEventKind::End),
+            "element" => Some(StreamEventKind::Element),
+            _ => None,
+        }
+    }
+}
+
+impl StreamEvent<'_, '_> {
+    pub fn kind(&self) -> StreamEventKind {
+        match self {
+            StreamEvent::Start { .. } => StreamEventKind::Start,
+            StreamEvent::End { .. } => StreamEventKind::End,
+            StreamEvent::Element { .. } => StreamEventKind::Element,
+        }
+    }
+}
+
+/// Fields of an element next to the `event` and `span` fields
+#[derive(Serialize)]
+struct Flattened<'a, 'b> {
+    event: &'static str,
+    #[serde(flatten)]
+    element: &'b Element<'a>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    span: &'b Option<Range<usize>>,
+}
+
+impl Serialize for StreamEvent<'_, '_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            StreamEvent::Start { element, span } => Flattened {
+                event: "start",
+                element,
+                span,
+            }
+            .serialize(serializer),
+            StreamEvent::Element { element, span } => Flattened {
+                event: "element",
+                element,
+                span,
+            }
+            .serialize(serializer),
+            StreamEvent::End { kind } => {
+                let mut map = serializer.serialize_map(Some(2))?;
+                map.serialize_entry("event", "end")?;
+                map.serialize_entry("type", kind)?;
+                map.end()
+            }
+        }
+    }
+}
+
+impl<'a> Org<'a> {
+    /// Returns the events of the flat serialization of the document
+    ///
+    /// Serialize each event on its own line for newline-delimited json.
+    pub fn stream_events<'b>(&'b self) -> impl Iterator<Item = StreamEvent<'a, 'b>> + 'b {
+        self.root
+            .traverse(&self.arena)
+            .filter_map(move |edge| match edge {
+                NodeEdge::Start(node) => {
+                    let element = self.arena[node].get();
+                    let span = self.spans.get(&node).cloned();
+                    if element.is_container() {
+                        Some(StreamEvent::Start { element, span })
+                    } else {
+                        Some(StreamEvent::Element { element, span })
+                    }
+                }
+                NodeEdge::End(node) => {
+                    let element = self.arena[node].get();
+                    if element.is_container() {
+                        Some(StreamEvent::End {
+                            kind: element.kind(),
+                        })
+                    } else {
+                        None
+                    }
+                }
+            })
+    }
+
+    /// Serializes the document as a flat sequence of events, instead of a
+    /// tree like its `Serialize` implementation
+    ///
+    /// Containers are written as a `start` event, their children and an
+    /// `end` event, other elements as an `element` event. Events are written
+    /// one at a time, so the memory used doesn't grow with the size of the
+    /// document. [`nest_events`] rebuilds the tree.
+    ///
+    /// ```rust
+    /// use orgize::Org;
+    ///
+    /// let org = Org::parse("*bold*");
+    /// let mut json = Vec::new();
+    /// org.serialize_events(&mut serde_json::Serializer::new(&mut json)).unwrap();
+    ///
+    /// assert_eq!(
+    ///     String::from_utf8(json).unwrap(),
+    ///     r#"[{"event":"start","type":"document","span":{"start":0,"end":6}},{"event":"start","type":"section","span":{"start":0,"end":6}},{"event":"start","type":"paragraph","span":{"start":0,"end":6}},{"event":"start","type":"bold","span":{"start":0,"end":6}},{"event":"element","type":"text","value":"bold","span":{"start":1,"end":5}},{"event":"end","type":"bold"},{"event":"end","type":"paragraph"},{"event":"end","type":"section"},{"event":"end","type":"document"}]"#
+    /// );
+    /// ```
+    ///
+    /// [`nest_events`]: fn.nest_events.html
+    pub fn serialize_events<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.stream_events())
+    }
+}
+
+/// An event and the events nested in it, see [`nest_events`]
+///
+/// [`nest_events`]: fn.nest_events.html
+#[derive(Clone, Debug, PartialEq)]
+pub struct EventTree<T> {
+    /// The `start` or `element` event
+    pub event: T,
+    pub children: Vec<EventTree<T>>,
+}
+
+/// Error of [`nest_events`], with the index of the offending event
+///
+/// [`nest_events`]: fn.nest_events.html
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NestError {
+    /// An `end` event without `start` event
+    UnmatchedEnd { index: usize },
+    /// A `start` event without `end` event
+    Unclosed { index: usize },
+    /// An event whose kind is unknown
+    UnknownEvent { index: usize },
+}
+
+/// Rebuilds the tree of the events written by [`Org::serialize_events`]
+///
+/// `events` can be any representation of the events, like json values read
+/// line by line, and `kind` returns their kind. `end` events are dropped.
+///
+/// ```rust
+/// use orgize::{nest_events, Org, StreamEventKind};
+/// use serde_json::Value;
+///
+/// let org = Org::parse("* Title");
+/// let events: Vec<Value> = org
+///     .stream_events()
+///     .map(|event| serde_json::to_value(event).unwrap())
+///     .collect();
+///
+/// let tree = nest_events(events, |event| {
+///     event["event"].as_str().and_then(StreamEventKind::from_name)
+/// })
+/// .unwrap();
+///
+/// assert_eq!(tree[0].event["type"], "document");
+/// assert_eq!(tree[0].children[0].children[0].event["raw"], "Title");
+/// ```
+///
+/// [`Org::serialize_events`]: struct.Org.html#method.serialize_events
+pub fn nest_events<T, I, F>(events: I, mut kind: F) -> Result<Vec<EventTree<T>>, NestError>
+where
+    I: IntoIterator<Item = T>,
+    F: FnMut(&T) -> Option<StreamEventKind>,
+{
+    let mut roots = Vec::new();
+    // open containers, with the index of their start event
+    let mut stack: Vec<(usize, EventTree<T>)> = Vec::new();
+
+    for (index, event) in events.into_iter().enumerate() {
+        let tree = match kind(&event) {
+            Some(StreamEventKind::Start) => {
+                stack.push((
+                    index,
+                    EventTree {
+                        event,
+                        children: Vec::new(),
+                    },
+                ));
+                continue;
+            }
+            Some(StreamEventKind::End) => match stack.pop() {
+                Some((_, tree)) => tree,
+                None => return Err(NestError::UnmatchedEnd { index }),
+            },
+            Some(StreamEventKind::Element) => EventTree {
+                event,
+                children: Vec::new(),
+            },
+            None => return Err(NestError::UnknownEvent { index }),
+        };
+
+        match stack.last_mut() {
+            Some((_, parent)) => parent.children.push(tree),
+            None => roots.push(tree),
+        }
+    }
+
+    match stack.first() {
+        Some((index, _)) => Err(NestError::Unclosed { index: *index }),
+        None => Ok(roots),
+    }
+}
@@ -0,0 +1,276 @@
+use indextree::NodeId;
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+use crate::elements::{Datetime, Element, PropertyLine};
+use crate::export::macros::format_datetime;
+use crate::{HeadlineNode, Org, OrgizeError};
+
+/// Options of [`HeadlineNode::instantiate_template_with_options`]
+///
+/// [`HeadlineNode::instantiate_template_with_options`]: struct.HeadlineNode.html#method.instantiate_template_with_options
+#[derive(Clone, Debug, Default)]
+pub struct TemplateOptions {
+    /// Date of the `%{date:FORMAT}` placeholders, today if `None`
+    ///
+    /// Dates are formatted with the `chrono` feature, these placeholders are
+    /// kept as is without it.
+    pub date: Option<Datetime<'static>>,
+    /// Substitutes placeholders in code too: source, example and fixed width
+    /// blocks, as well as inline source, code and verbatim objects
+    pub include_code: bool,
+}
+
+impl HeadlineNode {
+    /// Copies this headline and its subtree under `target`, or at the top
+    /// level of the document if `target` is `None`, see
+    /// [`instantiate_template_with_options`]
+    ///
+    /// [`instantiate_template_with_options`]: #method.instantiate_template_with_options
+    pub fn instantiate_template(
+        self,
+        target: Option<HeadlineNode>,
+        vars: &HashMap<&str, &str>,
+        org: &mut Org<'_>,
+    ) -> Result<HeadlineNode, OrgizeError> {
+        self.instantiate_template_with_options(target, vars, &TemplateOptions::default(), org)
+    }
+
+    /// Copies this headline and its subtree under `target`, or at the top
+    /// level of the document if `target` is `None`, substituting
+    /// placeholders, and returns the copy
+    ///
+    /// `%{name}` placeholders are replaced with the value of `name` in
+    /// `vars`, and `%{date:FORMAT}` with the date of `options` formatted
+    /// with `strftime` specifiers. Unknown placeholders are kept as is.
+    ///
+    /// Placeholders are substituted in titles, property values, text and
+    /// link descriptions, but not in code unless `options.include_code` is
+    /// set. Values are inserted as plain text, they aren't parsed as markup
+    /// outside of titles.
+    ///
+    /// The levels of the copied headlines are shifted to fit under `target`,
+    /// and the `template` tag of the copied headline is removed.
+    ///
+    /// ```rust
+    /// use orgize::{Org, TemplateOptions};
+    /// use std::collections::HashMap;
+    ///
+    /// let mut org = Org::parse(
+    ///     "* Weekly review %{week} :template:\n:PROPERTIES:\n:OWNER: %{owner}\n:END:\n\
+    ///      Review with %{owner}.\n#+BEGIN_SRC sh\necho %{owner}\n#+END_SRC\n\
+    ///      ** Inbox\n* Reviews\n",
+    /// );
+    /// let template = org.headlines().next().unwrap();
+    /// let reviews = org.headlines().last().unwrap();
+    ///
+    /// let mut vars = HashMap::new();
+    /// vars.insert("week", "42");
+    /// vars.insert("owner", "Alice");
+    /// template
+    ///     .instantiate_template_with_options(
+    ///         Some(reviews),
+    ///         &vars,
+    ///         &TemplateOptions::default(),
+    ///         &mut org,
+    ///     )
+    ///     .unwrap();
+    ///
+    /// let mut writer = Vec::new();
+    /// org.org(&mut writer).unwrap();
+    /// assert!(String::from_utf8(writer).unwrap().ends_with(
+    ///     "* Reviews\n** Weekly review 42\n:PROPERTIES:\n:OWNER: Alice\n:END:\n\
+    ///      Review with Alice.\n\n#+BEGIN_SRC sh\necho %{owner}\n#+END_SRC\n*** Inbox\n"
+    /// ));
+    /// ```
+    pub fn instantiate_template_with_options(
+        self,
+        target: Option<HeadlineNode>,
+        vars: &HashMap<&str, &str>,
+        options: &TemplateOptions,
+        org: &mut Org<'_>,
+    ) -> Result<HeadlineNode, OrgizeError> {
+        let date = options.date.clone().or_else(today);
+        let placeholders = Placeholders {
+            vars,
+            date: date.as_ref(),
+            include_code: options.include_code,
+        };
+        let level = target.map_or(1, |target| target.level + 1);
+
+        let mut titles = Vec::new();
+        let copy = copy_subtree(
+            self.node,
+            level,
+            self.level,
+            &placeholders,
+            org,
+            &mut titles,
+        );
+
+        let timestamps = copy
+            .descendants(&org.arena)
+            .filter(|&node| matches!(org.arena[node].get(), Element::Timestamp(_)))
+            .collect();
+        org.index_timestamps(timestamps);
+
+        // titles are parsed again, so placeholders may expand to markup
+        for (title, raw) in titles {
+            let node = org.arena[title].parent().unwrap();
+            let level = match org.arena[node].get() {
+                Element::Headline { level } => *level,
+                _ => unreachable!(),
+            };
+            HeadlineNode::new(node, level, org).set_title_content(raw, org);
+        }
+
+        let headline = HeadlineNode::new(copy, level, org);
+        headline.title_mut(org).tags.retain(|tag| tag != "template");
+
+        match target {
+            Some(target) => target.append(headline, org)?,
+            None => org.document().append(headline, org)?,
+        }
+
+        Ok(headline)
+    }
+}
+
+struct Placeholders<'p> {
+    vars: &'p HashMap<&'p str, &'p str>,
+    date: Option<&'p Datetime<'static>>,
+    include_code: bool,
+}
+
+impl Placeholders<'_> {
+    /// Returns `value` with its placeholders substituted, borrowing it if
+    /// there are none
+    fn substitute<'a>(&self, value: &Cow<'a, str>) -> Cow<'a, str> {
+        if !value.contains("%{") {
+            return value.clone();
+        }
+
+        let mut substituted = String::with_capacity(value.len());
+        let mut rest = &value[..];
+        while let Some(start) = rest.find("%{") {
+            substituted.push_str(&rest[0..start]);
+            rest = &rest[start..];
+
+            let placeholder = rest[2..]
+                .find('}')
+                .map(|end| &rest[2..end + 2])
+                .filter(|name| !name.contains('\n'));
+            match placeholder.and_then(|name| Some((name, self.expand(name)?))) {
+                Some((name, expanded)) => {
+                    substituted.push_str(&expanded);
+                    rest = &rest[name.len() + 3..];
+                }
+                None => {
+                    substituted.push_str("%{");
+                    rest = &rest[2..];
+                }
+            }
+        }
+        substituted.push_str(rest);
+
+        Cow::Owned(substituted)
+    }
+
+    fn expand(&self, name: &str) -> Option<String> {
+        match name.strip_prefix("date:") {
+            Some(format) => format_datetime(self.date?, format),
+            None => self.vars.get(name).map(|value| value.to_string()),
+        }
+    }
+
+    fn substitute_code<'a>(&self, value: &Cow<'a, str>) -> Cow<'a, str> {
+        if self.include_code {
+            self.substitute(value)
+        } else {
+            value.clone()
+        }
+    }
+
+    fn substitute_element<'a>(&self, element: &Element<'a>) -> Element<'a> {
+        let mut element = element.clone();
+        match &mut element {
+            Element::Text { value } => *value = self.substitute(value),
+            Element::Link(link) => link.desc = link.desc.as_ref().map(|desc| self.substitute(desc)),
+            Element::Title(title) => {
+                for value in title.properties.values_mut() {
+                    *value = self.substitute(value);
+                }
+                for line in &mut title.property_lines {
+                    if let PropertyLine::Property { value, .. } = line {
+                        *value = self.substitute(value);
+                    }
+                }
+            }
+            Element::SourceBlock(block) => block.contents = self.substitute_code(&block.contents),
+            Element::ExampleBlock(block) => block.contents = self.substitute_code(&block.contents),
+            Element::InlineSrc(inline_src) => {
+                inline_src.body = self.substitute_code(&inline_src.body)
+            }
+            Element::Code { value }
+            | Element::Verbatim { value }
+            | Element::FixedWidth { value } => *value = self.substitute_code(value),
+            _ => (),
+        }
+        element
+    }
+}
+
+/// Copies `node` and its descendants, except the contents of titles, and
+/// pushes the copied titles with their substituted raw text
+fn copy_subtree<'a>(
+    node: NodeId,
+    level: usize,
+    template_level: usize,
+    placeholders: &Placeholders<'_>,
+    org: &mut Org<'a>,
+    titles: &mut Vec<(NodeId, Cow<'a, str>)>,
+) -> NodeId {
+    let mut element = placeholders.substitute_element(org.arena[node].get());
+    match &mut element {
+        Element::Headline { level: copy_level } => {
+            *copy_level = *copy_level - template_level + level
+        }
+        Element::Title(title) => title.level = title.level - template_level + level,
+        _ => (),
+    }
+    let copy = org.arena.new_node(element);
+
+    if let Element::Title(title) = org.arena[node].get() {
+        let raw = placeholders.substitute(&title.raw);
+        titles.push((copy, raw));
+        // filled in by set_title_content
+        return copy;
+    }
+
+    let children: Vec<_> = node.children(&org.arena).collect();
+    for child in children {
+        let child = copy_subtree(child, level, template_level, placeholders, org, titles);
+        copy.append(child, &mut org.arena);
+    }
+    copy
+}
+
+#[cfg(feature = "chrono")]
+fn today() -> Option<Datetime<'static>> {
+    use chrono::{Datelike, Local};
+
+    let date = Local::now().naive_local().date();
+    Some(Datetime {
+        year: date.year() as u16,
+        month: date.month() as u8,
+        day: date.day() as u8,
+        dayname: date.format("%a").to_string().into(),
+        hour: None,
+        minute: None,
+    })
+}
+
+#[cfg(not(feature = "chrono"))]
+fn today() -> Option<Datetime<'static>> {
+    None
+}
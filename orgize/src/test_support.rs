@@ -0,0 +1,625 @@
+//! Proptest strategies for generating random org documents.
+//!
+//! Requires the `test-support` feature. Each strategy generates a plain
+//! description of a document, which [`DocumentSpec::build`] turns into an
+//! [`Org`] using element constructors directly, without going through the
+//! parser. The generated documents are canonical: writing them with the
+//! org exporter and parsing the output back gives the same tree.
+//!
+//! ```rust,ignore
+//! use orgize::test_support::{assert_round_trip, document};
+//! use proptest::proptest;
+//!
+//! proptest! {
+//!     #[test]
+//!     fn round_trip(doc in document()) {
+//!         assert_round_trip(&doc.build());
+//!     }
+//! }
+//! ```
+//!
+//! [`DocumentSpec::build`]: struct.DocumentSpec.html#method.build
+//! [`Org`]: ../struct.Org.html
+
+use std::collections::HashMap;
+
+use indextree::NodeId;
+use proptest::prelude::*;
+
+use crate::elements::{
+    BabelCall, Datetime, Delay, DelayType, Element, ExampleBlock, List, ListBullet, ListItem,
+    Macros, Modifiers, Planning, Priority, PropertyLine, Repeater, RepeaterType, SourceBlock,
+    Target, TimeUnit, Timestamp, Title,
+};
+use crate::org::{Event, Org};
+
+/// A randomly generated document
+#[derive(Debug, Clone)]
+pub struct DocumentSpec {
+    /// Contents of the zeroth section
+    pub section: Vec<BlockSpec>,
+    /// Headlines, in document order
+    pub headlines: Vec<HeadlineSpec>,
+}
+
+/// A randomly generated headline
+#[derive(Debug, Clone)]
+pub struct HeadlineSpec {
+    pub level: usize,
+    pub keyword: Option<&'static str>,
+    pub priority: Option<char>,
+    /// Title text, a few plain words
+    pub raw: String,
+    pub tags: Vec<String>,
+    pub scheduled: Option<TimestampSpec>,
+    pub deadline: Option<TimestampSpec>,
+    pub properties: Vec<(String, String)>,
+    pub section: Vec<BlockSpec>,
+}
+
+/// A randomly generated block inside a section
+#[derive(Debug, Clone)]
+pub enum BlockSpec {
+    /// A paragraph of plain words
+    Paragraph(String),
+    /// A paragraph with a timestamp in the middle of it
+    Timestamp {
+        before: String,
+        timestamp: TimestampSpec,
+        after: String,
+    },
+    /// A flat list, each item holding a paragraph of plain words
+    List {
+        ordered: bool,
+        /// Whether items are separated by blank lines, ignored for a
+        /// single item
+        tight: bool,
+        items: Vec<String>,
+    },
+    /// A source block, whose contents may require escaping
+    Source { language: String, contents: String },
+    /// An example block, whose contents may require escaping
+    Example { contents: String },
+    /// A paragraph with a macro and a target in the middle of it
+    Objects {
+        before: String,
+        name: String,
+        arguments: Option<String>,
+        target: String,
+        after: String,
+    },
+    /// A `#+CALL:` line
+    BabelCall(String),
+}
+
+/// A randomly generated timestamp
+#[derive(Debug, Clone)]
+pub struct TimestampSpec {
+    pub active: bool,
+    pub start: Datetime<'static>,
+    pub end: Option<Datetime<'static>>,
+    pub modifiers: Modifiers,
+    pub end_modifiers: Modifiers,
+}
+
+impl TimestampSpec {
+    pub fn to_timestamp(&self) -> Timestamp<'static> {
+        let (start, modifiers, end_modifiers) =
+            (self.start.clone(), self.modifiers, self.end_modifiers);
+        match (self.active, self.end.clone()) {
+            (true, None) => Timestamp::Active { start, modifiers },
+            (false, None) => Timestamp::Inactive { start, modifiers },
+            (true, Some(end)) => Timestamp::ActiveRange {
+                start,
+                end,
+                modifiers,
+                end_modifiers,
+            },
+            (false, Some(end)) => Timestamp::InactiveRange {
+                start,
+                end,
+                modifiers,
+                end_modifiers,
+            },
+        }
+    }
+}
+
+impl DocumentSpec {
+    /// Builds an `Org` from this description
+    pub fn build(&self) -> Org<'static> {
+        let mut org = Org::new();
+
+        let root = org.root;
+        append_section(&mut org, root, &self.section);
+
+        // pairs of level and node of the headlines that may still get children
+        let mut parents: Vec<(usize, NodeId)> = Vec::new();
+
+        for spec in &self.headlines {
+            let headline = org.new_headline(spec.title());
+            append_section(&mut org, headline.node, &spec.section);
+
+            while let Some(&(level, _)) = parents.last() {
+                if level < spec.level {
+                    break;
+                }
+                parents.pop();
+            }
+            let parent = parents.last().map_or(root, |(_, node)| *node);
+            parent.append(headline.node, &mut org.arena);
+            parents.push((spec.level, headline.node));
+        }
+
+        org.debug_validate();
+
+        org
+    }
+}
+
+impl HeadlineSpec {
+    pub fn title(&self) -> Title<'static> {
+        let planning = if self.scheduled.is_some() || self.deadline.is_some() {
+            Some(Box::new(Planning {
+                scheduled: self.scheduled.as_ref().map(TimestampSpec::to_timestamp),
+                deadline: self.deadline.as_ref().map(TimestampSpec::to_timestamp),
+                closed: None,
+            }))
+        } else {
+            None
+        };
+
+        Title {
+            level: self.level,
+            stars: None,
+            keyword: self.keyword.map(Into::into),
+            priority: self.priority.map(Priority::Char),
+            raw: self.raw.clone().into(),
+            tags: self.tags.iter().cloned().map(Into::into).collect(),
+            planning,
+            date: None,
+            properties: self
+                .properties
+                .iter()
+                .map(|(key, value)| (key.clone().into(), value.clone().into()))
+                .collect::<HashMap<_, _>>(),
+            property_lines: self
+                .properties
+                .iter()
+                .map(|(key, value)| PropertyLine::Property {
+                    key: key.clone().into(),
+                    value: value.clone().into(),
+                })
+                .collect(),
+        }
+    }
+}
+
+fn append_section(org: &mut Org<'static>, parent: NodeId, blocks: &[BlockSpec]) {
+    if blocks.is_empty() {
+        return;
+    }
+
+    let section = org.arena.new_node(Element::Section);
+    parent.append(section, &mut org.arena);
+
+    for block in blocks {
+        append_block(org, section, block);
+    }
+}
+
+fn append_block(org: &mut Org<'static>, parent: NodeId, block: &BlockSpec) {
+    let arena = &mut org.arena;
+
+    let append = |arena: &mut indextree::Arena<Element<'static>>, element, parent: NodeId| {
+        let node = arena.new_node(element);
+        parent.append(node, arena);
+        node
+    };
+
+    match block {
+        BlockSpec::Paragraph(text) => {
+            let paragraph = append(arena, Element::Paragraph, parent);
+            append(
+                arena,
+                Element::Text {
+                    value: text.clone().into(),
+                },
+                paragraph,
+            );
+        }
+        BlockSpec::Timestamp {
+            before,
+            timestamp,
+            after,
+        } => {
+            let paragraph = append(arena, Element::Paragraph, parent);
+            append(
+                arena,
+                Element::Text {
+                    value: format!("{} ", before).into(),
+                },
+                paragraph,
+            );
+            append(
+                arena,
+                Element::Timestamp(timestamp.to_timestamp()),
+                paragraph,
+            );
+            append(
+                arena,
+                Element::Text {
+                    value: format!(" {}", after).into(),
+                },
+                paragraph,
+            );
+        }
+        BlockSpec::List {
+            ordered,
+            tight,
+            items,
+        } => {
+            let list = append(
+                arena,
+                Element::List(List {
+                    indent: 0,
+                    ordered: *ordered,
+                    bullet: if *ordered {
+                        ListBullet::Period
+                    } else {
+                        ListBullet::Hyphen
+                    },
+                    tight: *tight || items.len() < 2,
+                    description: false,
+                    start: None,
+                }),
+                parent,
+            );
+            for (i, text) in items.iter().enumerate() {
+                let bullet = if *ordered {
+                    format!("{}. ", i + 1)
+                } else {
+                    "- ".to_string()
+                };
+                let item = append(
+                    arena,
+                    Element::ListItem(ListItem {
+                        bullet: bullet.into(),
+                        indent: 0,
+                        checkbox: None,
+                        counter: None,
+                        tag: None,
+                    }),
+                    list,
+                );
+                let paragraph = append(arena, Element::Paragraph, item);
+                append(
+                    arena,
+                    Element::Text {
+                        value: text.clone().into(),
+                    },
+                    paragraph,
+                );
+            }
+        }
+        BlockSpec::Source { language, contents } => {
+            append(
+                arena,
+                Element::SourceBlock(SourceBlock {
+                    language: language.clone().into(),
+                    arguments: "".into(),
+                    contents: contents.clone().into(),
+                }),
+                parent,
+            );
+        }
+        BlockSpec::Example { contents } => {
+            append(
+                arena,
+                Element::ExampleBlock(ExampleBlock {
+                    data: None,
+                    contents: contents.clone().into(),
+                }),
+                parent,
+            );
+        }
+        BlockSpec::Objects {
+            before,
+            name,
+            arguments,
+            target,
+            after,
+        } => {
+            let paragraph = append(arena, Element::Paragraph, parent);
+            append(
+                arena,
+                Element::Text {
+                    value: format!("{} ", before).into(),
+                },
+                paragraph,
+            );
+            append(
+                arena,
+                Element::Macros(Macros {
+                    name: name.clone().into(),
+                    arguments: arguments.clone().map(Into::into),
+                }),
+                paragraph,
+            );
+            append(arena, Element::Text { value: " ".into() }, paragraph);
+            append(
+                arena,
+                Element::Target(Target {
+                    target: target.clone().into(),
+                }),
+                paragraph,
+            );
+            append(
+                arena,
+                Element::Text {
+                    value: format!(" {}", after).into(),
+                },
+                paragraph,
+            );
+        }
+        BlockSpec::BabelCall(value) => {
+            append(
+                arena,
+                Element::BabelCall(BabelCall {
+                    value: value.clone().into(),
+                }),
+                parent,
+            );
+        }
+    }
+}
+
+/// Generates a lowercase word
+pub fn word() -> impl Strategy<Value = String> {
+    "[a-z]{1,8}"
+}
+
+/// Generates one to five words separated by a space
+pub fn words() -> impl Strategy<Value = String> {
+    prop::collection::vec(word(), 1..5).prop_map(|words| words.join(" "))
+}
+
+/// Generates a datetime, with or without a time
+///
+/// Years may have less than four digits, which are written with leading
+/// zeros.
+pub fn datetime() -> impl Strategy<Value = Datetime<'static>> {
+    (
+        prop_oneof![1970u16..2100, 0u16..10000],
+        1u8..=12,
+        1u8..=28,
+        prop::sample::select(vec!["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"]),
+        prop::option::of((0u8..24, 0u8..60)),
+    )
+        .prop_map(|(year, month, day, dayname, time)| Datetime {
+            year,
+            month,
+            day,
+            dayname: dayname.into(),
+            hour: time.map(|(hour, _)| hour),
+            minute: time.map(|(_, minute)| minute),
+        })
+}
+
+/// Generates an active or inactive timestamp, possibly a range, with or
+/// without a repeater and a delay on each end
+pub fn timestamp() -> impl Strategy<Value = TimestampSpec> {
+    (
+        any::<bool>(),
+        datetime(),
+        prop::option::of(datetime()),
+        modifiers(),
+        modifiers(),
+    )
+        .prop_map(
+            |(active, start, end, modifiers, end_modifiers)| TimestampSpec {
+                active,
+                start,
+                // only ranges have modifiers on their end
+                end_modifiers: if end.is_some() {
+                    end_modifiers
+                } else {
+                    Modifiers::default()
+                },
+                end,
+                modifiers,
+            },
+        )
+}
+
+/// Generates the repeater and the delay of a timestamp, in either order
+fn modifiers() -> impl Strategy<Value = Modifiers> {
+    let unit = prop::sample::select(vec![
+        TimeUnit::Hour,
+        TimeUnit::Day,
+        TimeUnit::Week,
+        TimeUnit::Month,
+        TimeUnit::Year,
+    ]);
+    let repeater = (
+        prop::sample::select(vec![
+            RepeaterType::Cumulate,
+            RepeaterType::CatchUp,
+            RepeaterType::Restart,
+        ]),
+        1usize..100,
+        unit.clone(),
+    )
+        .prop_map(|(ty, value, unit)| Repeater { ty, value, unit });
+    let delay = (
+        prop::sample::select(vec![DelayType::All, DelayType::First]),
+        1usize..100,
+        unit,
+    )
+        .prop_map(|(ty, value, unit)| Delay { ty, value, unit });
+
+    (
+        prop::option::of(repeater),
+        prop::option::of(delay),
+        any::<bool>(),
+    )
+        .prop_map(|(repeater, delay, delay_first)| Modifiers {
+            repeater,
+            delay,
+            // the order only matters when there're both
+            delay_first: delay_first && repeater.is_some() && delay.is_some(),
+        })
+}
+
+/// Generates block contents, including lines which need to be escaped
+pub fn block_contents() -> impl Strategy<Value = String> {
+    prop::collection::vec(
+        prop_oneof![
+            words(),
+            Just("* not a headline".to_string()),
+            Just("#+END_SRC".to_string()),
+            Just("  #+END_EXAMPLE".to_string()),
+            Just(",#+already escaped".to_string()),
+            Just(String::new()),
+        ],
+        0..4,
+    )
+    .prop_map(|lines| lines.iter().map(|line| format!("{}\n", line)).collect())
+}
+
+/// Generates a block inside a section
+pub fn block() -> impl Strategy<Value = BlockSpec> {
+    prop_oneof![
+        words().prop_map(BlockSpec::Paragraph),
+        (words(), timestamp(), words()).prop_map(|(before, timestamp, after)| {
+            BlockSpec::Timestamp {
+                before,
+                timestamp,
+                after,
+            }
+        }),
+        (
+            any::<bool>(),
+            any::<bool>(),
+            prop::collection::vec(words(), 1..4)
+        )
+            .prop_map(|(ordered, tight, items)| BlockSpec::List {
+                ordered,
+                tight,
+                items
+            }),
+        (word(), block_contents())
+            .prop_map(|(language, contents)| BlockSpec::Source { language, contents }),
+        block_contents().prop_map(|contents| BlockSpec::Example { contents }),
+        (
+            (words(), word()),
+            (prop::option::of(words()), words(), words())
+        )
+            .prop_map(|((before, name), (arguments, target, after))| {
+                BlockSpec::Objects {
+                    before,
+                    name,
+                    arguments,
+                    target,
+                    after,
+                }
+            }),
+        (word(), word()).prop_map(|(name, argument)| {
+            BlockSpec::BabelCall(format!("{}(n={})", name, argument))
+        }),
+    ]
+}
+
+/// Generates a section of up to three blocks
+pub fn section() -> impl Strategy<Value = Vec<BlockSpec>> {
+    prop::collection::vec(block(), 0..3)
+}
+
+/// Generates a headline of level between one and four
+///
+/// Titles may end with a colon and tags may look like times.
+pub fn headline() -> impl Strategy<Value = HeadlineSpec> {
+    (
+        (
+            1usize..=4,
+            prop::option::of(prop::sample::select(vec!["TODO", "DONE"])),
+            prop::option::of(prop::sample::select(vec!['A', 'B', 'C'])),
+            prop_oneof![Just(String::new()), words(), words().prop_map(|w| w + ":")],
+            prop::collection::vec("[a-z0-9_@#%]{1,6}", 0..3),
+        ),
+        (
+            prop::option::of(timestamp()),
+            prop::option::of(timestamp()),
+            prop::collection::vec(("[A-Z_]{1,8}", words()), 0..3),
+            section(),
+        ),
+    )
+        .prop_map(
+            |(
+                (level, keyword, priority, raw, tags),
+                (scheduled, deadline, properties, section),
+            )| {
+                HeadlineSpec {
+                    level,
+                    keyword,
+                    priority,
+                    raw,
+                    tags,
+                    scheduled,
+                    deadline,
+                    // keys are unique in a property drawer
+                    properties: properties
+                        .into_iter()
+                        .collect::<HashMap<_, _>>()
+                        .into_iter()
+                        .collect(),
+                    section,
+                }
+            },
+        )
+}
+
+/// Generates a document of up to eight headlines
+pub fn document() -> impl Strategy<Value = DocumentSpec> {
+    (section(), prop::collection::vec(headline(), 0..8))
+        .prop_map(|(section, headlines)| DocumentSpec { section, headlines })
+}
+
+/// Writes `org` with the default org handler
+pub fn write_org(org: &Org<'_>) -> String {
+    let mut writer = Vec::new();
+    org.org(&mut writer).unwrap();
+    String::from_utf8(writer).unwrap()
+}
+
+/// Panics if two documents don't have the same tree of elements
+pub fn assert_same_tree(left: &Org<'_>, right: &Org<'_>) {
+    let events = |org| {
+        Org::iter(org)
+            .map(|event| match event {
+                Event::Start(element) => (true, element),
+                Event::End(element) => (false, element),
+            })
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(events(left), events(right));
+}
+
+/// Panics if `org` doesn't survive a trip through the org exporter and the
+/// parser, or if writing the parsed document again gives a different text
+pub fn assert_round_trip(org: &Org<'_>) {
+    let text = write_org(org);
+    let parsed = Org::parse(&text);
+
+    assert_same_tree(org, &parsed);
+    assert_eq!(write_org(&parsed), text);
+}
+
+#[cfg(test)]
+proptest! {
+    #[test]
+    fn round_trip(doc in document()) {
+        assert_round_trip(&doc.build());
+    }
+}
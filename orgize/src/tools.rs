@@ -0,0 +1,29 @@
+//! Helpers for tools processing org documents
+
+use crate::code_block::CodeBlock;
+use crate::org::Org;
+
+/// Returns the source blocks of the document in `language`, or all of them,
+/// in document order
+///
+/// Line numbers map the contents of the blocks back to the document, e.g. to
+/// report the errors of a linter.
+///
+/// ```rust
+/// use orgize::{tools::extract_code, Org};
+///
+/// let org = Org::parse("* Queries\n#+NAME: users\n#+BEGIN_SRC sql :engine postgres\n  select *\n  from users;\n#+END_SRC\n#+BEGIN_SRC sh\nls\n#+END_SRC\n");
+/// let blocks = extract_code(&org, Some("sql"));
+///
+/// assert_eq!(blocks.len(), 1);
+/// assert_eq!(blocks[0].name, Some("users"));
+/// assert_eq!(blocks[0].contents, "select *\nfrom users;\n");
+/// assert_eq!(blocks[0].header_arg(":engine"), Some("postgres"));
+/// // an error on the second line of the query is on the fifth line of the document
+/// assert_eq!(blocks[0].document_line(1), Some(4));
+/// ```
+pub fn extract_code<'a, 'b>(org: &'b Org<'a>, language: Option<&str>) -> Vec<CodeBlock<'a, 'b>> {
+    org.code_blocks()
+        .filter(|block| language.is_none() || language == Some(block.language))
+        .collect()
+}
@@ -0,0 +1,173 @@
+//! Display width of text in monospace fonts and terminals
+//!
+//! East Asian wide characters take two columns, combining characters and
+//! zero-width joiners none, and emoji joined with zero-width joiners are
+//! displayed as a single wide character. Characters of ambiguous width,
+//! like `±` or `·`, take two columns in CJK terminals and one elsewhere.
+//!
+//! ```rust
+//! use orgize::width::{width, WidthOptions};
+//!
+//! let narrow = WidthOptions::default();
+//! let wide = WidthOptions { ambiguous_wide: true };
+//!
+//! assert_eq!(width("漢字", &narrow), 4);
+//! assert_eq!(width("e\u{301}", &narrow), 1);
+//! assert_eq!(width("👨\u{200D}👩\u{200D}👧", &narrow), 2);
+//! assert_eq!(width("±1", &narrow), 2);
+//! assert_eq!(width("±1", &wide), 3);
+//! ```
+
+use std::borrow::Cow;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+const ZERO_WIDTH_JOINER: char = '\u{200D}';
+
+/// Options of the width computation
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct WidthOptions {
+    /// Counts characters of ambiguous East Asian width as wide, like CJK
+    /// terminals do
+    pub ambiguous_wide: bool,
+}
+
+/// Returns the number of columns taken by `s`
+pub fn width(s: &str, options: &WidthOptions) -> usize {
+    if options.ambiguous_wide {
+        s.width_cjk()
+    } else {
+        s.width()
+    }
+}
+
+/// Returns `s` padded with spaces at its end to `columns` columns
+///
+/// ```rust
+/// use orgize::width::{pad, WidthOptions};
+///
+/// assert_eq!(pad("漢字", 6, &WidthOptions::default()), "漢字  ");
+/// assert_eq!(pad("abc", 2, &WidthOptions::default()), "abc");
+/// ```
+pub fn pad<'a>(s: &'a str, columns: usize, options: &WidthOptions) -> Cow<'a, str> {
+    let padding = columns.saturating_sub(width(s, options));
+    if padding == 0 {
+        Cow::Borrowed(s)
+    } else {
+        Cow::Owned(format!("{}{}", s, " ".repeat(padding)))
+    }
+}
+
+/// Wraps `text` into lines of at most `columns` columns
+///
+/// Lines are broken at whitespace, which is collapsed, and words wider than
+/// `columns` are broken between characters, keeping combining characters and
+/// joined emoji with their base. A single character wider than `columns` is
+/// put on a line of its own.
+///
+/// ```rust
+/// use orgize::width::{wrap, WidthOptions};
+///
+/// assert_eq!(
+///     wrap("漢字 かな and ascii", 8, &WidthOptions::default()),
+///     ["漢字", "かな and", "ascii"]
+/// );
+/// ```
+pub fn wrap(text: &str, columns: usize, options: &WidthOptions) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    let mut line_width = 0;
+
+    for word in text.split_whitespace() {
+        let word_width = width(word, options);
+        if !line.is_empty() && line_width + 1 + word_width <= columns {
+            line.push(' ');
+            line.push_str(word);
+            line_width += 1 + word_width;
+            continue;
+        }
+        if !line.is_empty() {
+            lines.push(std::mem::take(&mut line));
+            line_width = 0;
+        }
+        if word_width <= columns {
+            line.push_str(word);
+            line_width += word_width;
+            continue;
+        }
+        for cluster in clusters(word) {
+            let cluster_width = width(cluster, options);
+            if !line.is_empty() && line_width + cluster_width > columns {
+                lines.push(std::mem::take(&mut line));
+                line_width = 0;
+            }
+            line.push_str(cluster);
+            line_width += cluster_width;
+        }
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+
+    lines
+}
+
+/// Splits `word` before each character which isn't displayed with the
+/// previous one, i.e. which has a width and doesn't follow a zero-width
+/// joiner
+fn clusters(word: &str) -> impl Iterator<Item = &str> {
+    let mut rest = word;
+    std::iter::from_fn(move || {
+        let mut chars = rest.char_indices().peekable();
+        let (_, mut previous) = chars.next()?;
+        let mut end = rest.len();
+        while let Some(&(i, c)) = chars.peek() {
+            if c.width().unwrap_or(0) > 0 && previous != ZERO_WIDTH_JOINER {
+                end = i;
+                break;
+            }
+            previous = c;
+            chars.next();
+        }
+        let (cluster, tail) = rest.split_at(end);
+        rest = tail;
+        Some(cluster)
+    })
+}
+
+#[test]
+fn wrap_width() {
+    let narrow = WidthOptions::default();
+    let wide = WidthOptions {
+        ambiguous_wide: true,
+    };
+    let text =
+        "混合テキスト with e\u{301}mojis 👨\u{200D}👩\u{200D}👧👍🏽 and ±·± ambiguous 漢字漢字漢字";
+
+    for &columns in &[4, 5, 8, 13] {
+        for options in &[narrow, wide] {
+            let lines = wrap(text, columns, options);
+            for line in &lines {
+                assert!(width(line, options) <= columns, "{:?}", line);
+            }
+            assert_eq!(lines.concat().replace(' ', ""), text.replace(' ', ""));
+        }
+    }
+
+    assert_eq!(
+        wrap(
+            "👨\u{200D}👩\u{200D}👧👨\u{200D}👩\u{200D}👧 e\u{301}e\u{301}e\u{301}",
+            2,
+            &narrow
+        ),
+        [
+            "👨\u{200D}👩\u{200D}👧",
+            "👨\u{200D}👩\u{200D}👧",
+            "e\u{301}e\u{301}",
+            "e\u{301}"
+        ]
+    );
+    assert_eq!(wrap("±±±", 4, &narrow), ["±±±"]);
+    assert_eq!(wrap("±±±", 4, &wide), ["±±", "±"]);
+    assert_eq!(wrap("漢", 1, &narrow), ["漢"]);
+    assert!(wrap(" \n ", 10, &narrow).is_empty());
+}
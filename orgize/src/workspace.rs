@@ -0,0 +1,569 @@
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use std::path::{Component, Path, PathBuf};
+
+use indextree::NodeId;
+
+use crate::batch::parse_files_with;
+use crate::config::ParseConfig;
+use crate::diagnostics::Diagnostic;
+use crate::elements::{Element, Link};
+use crate::node::HeadlineNode;
+use crate::org::Org;
+
+/// A set of documents which can link to each other
+///
+/// ```rust
+/// use orgize::{LinkIssueKind, Org, Workspace};
+///
+/// let mut workspace = Workspace::new();
+/// workspace.add("notes/index.org", Org::parse("See [[file:todo.org::*Groceries]]."));
+/// workspace.add("notes/todo.org", Org::parse("* Grocery\n"));
+///
+/// // orgize never reads the filesystem by itself
+/// let issues = workspace.check_links(|_| false);
+///
+/// assert_eq!(issues.len(), 1);
+/// assert_eq!(issues[0].kind, LinkIssueKind::MissingTarget);
+/// assert_eq!(issues[0].text, "[[file:todo.org::*Groceries]]");
+/// assert_eq!(issues[0].suggestion.as_ref().unwrap(), "*Grocery");
+/// ```
+#[derive(Default)]
+pub struct Workspace<'a> {
+    documents: Vec<(PathBuf, Org<'a>)>,
+    /// Index of each document, in the same order
+    indices: Vec<Index>,
+}
+
+/// Headlines of a document by id and by title
+#[derive(Default)]
+struct Index {
+    ids: HashMap<String, HeadlineNode>,
+    titles: HashMap<String, Vec<HeadlineNode>>,
+}
+
+impl Index {
+    fn new(org: &Org<'_>) -> Index {
+        let mut index = Index::default();
+
+        for headline in org.headlines() {
+            if let Some(id) = headline_id(headline, org) {
+                index.ids.entry(id.to_string()).or_insert(headline);
+            }
+            index
+                .titles
+                .entry(headline.title(org).raw.trim().to_string())
+                .or_default()
+                .push(headline);
+        }
+
+        index
+    }
+}
+
+/// A broken link or an ambiguous link target, returned by
+/// [`Workspace::check_links`]
+///
+/// [`Workspace::check_links`]: struct.Workspace.html#method.check_links
+#[derive(Clone, Debug, PartialEq)]
+pub struct LinkIssue {
+    /// Path of the document containing the link or the target
+    pub document: PathBuf,
+    /// Byte range of the link or the target in its document, `None` if it
+    /// has been modified since parsing
+    pub span: Option<Range<usize>>,
+    /// The link or the target, as written in the document
+    pub text: String,
+    pub kind: LinkIssueKind,
+    /// The existing target, file or id closest to the one of the link
+    pub suggestion: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LinkIssueKind {
+    /// An internal link, e.g. `[[#custom-id]]`, `[[*Title]]`, `[[name]]` or
+    /// `[[file:other.org::*Title]]`, whose target doesn't exist
+    MissingTarget,
+    /// A `file:` link to a file which is neither in the workspace nor on the
+    /// filesystem
+    MissingFile,
+    /// An `id:` link to an id no headline of the workspace has
+    UnknownId,
+    /// A custom id, a `#+NAME:`, a target or an id defined more than once,
+    /// reported at each definition but the first
+    DuplicateTarget,
+}
+
+/// Targets of the internal links of a document
+#[derive(Default)]
+struct Targets {
+    custom_ids: HashMap<String, NodeId>,
+    titles: HashMap<String, NodeId>,
+    /// `#+NAME:` keywords and `<<targets>>`
+    names: HashMap<String, NodeId>,
+}
+
+impl Targets {
+    fn new(org: &Org<'_>, issues: &mut Vec<LinkIssue>, document: &Path) -> Targets {
+        let mut targets = Targets::default();
+
+        for node in org.root.descendants(&org.arena) {
+            match org.arena[node].get() {
+                Element::Title(title) => {
+                    if let Some(value) = title.property("CUSTOM_ID") {
+                        insert(&mut targets.custom_ids, value, node, org, issues, document);
+                    }
+                    targets
+                        .titles
+                        .entry(title.raw.trim().to_string())
+                        .or_insert(node);
+                }
+                Element::Keyword(keyword) if keyword.key.eq_ignore_ascii_case("NAME") => {
+                    insert(
+                        &mut targets.names,
+                        &keyword.value,
+                        node,
+                        org,
+                        issues,
+                        document,
+                    );
+                }
+                Element::Target(target) => {
+                    insert(
+                        &mut targets.names,
+                        &target.target,
+                        node,
+                        org,
+                        issues,
+                        document,
+                    );
+                }
+                _ => (),
+            }
+        }
+
+        targets
+    }
+
+    /// Returns `true` if `search`, the path of an internal link or the search
+    /// option of a file link, has a target
+    fn contains(&self, search: &str) -> bool {
+        if let Some(id) = search.strip_prefix('#') {
+            self.custom_ids.contains_key(id)
+        } else if let Some(title) = search.strip_prefix('*') {
+            self.titles.contains_key(title.trim())
+        } else {
+            self.names.contains_key(search) || self.titles.contains_key(search)
+        }
+    }
+
+    fn suggest(&self, search: &str) -> Option<String> {
+        if let Some(id) = search.strip_prefix('#') {
+            nearest(id, self.custom_ids.keys()).map(|id| format!("#{}", id))
+        } else if let Some(title) = search.strip_prefix('*') {
+            nearest(title.trim(), self.titles.keys()).map(|title| format!("*{}", title))
+        } else {
+            nearest(search, self.names.keys().chain(self.titles.keys()))
+        }
+    }
+}
+
+fn insert(
+    map: &mut HashMap<String, NodeId>,
+    key: &str,
+    node: NodeId,
+    org: &Org<'_>,
+    issues: &mut Vec<LinkIssue>,
+    document: &Path,
+) {
+    let key = key.trim();
+    if map.contains_key(key) {
+        issues.push(LinkIssue {
+            document: document.to_path_buf(),
+            span: org.span_of(node),
+            text: source_text(org, node, key),
+            kind: LinkIssueKind::DuplicateTarget,
+            suggestion: None,
+        });
+    } else {
+        map.insert(key.to_string(), node);
+    }
+}
+
+/// Destination of a link, as far as the link checker is concerned
+enum Destination<'a> {
+    /// Path of an internal link, e.g. `#custom-id`, `*Title` or `name`
+    Internal(&'a str),
+    File {
+        path: &'a str,
+        search: Option<&'a str>,
+    },
+    Id(&'a str),
+    /// External links and coderefs, which aren't checked
+    Other,
+}
+
+impl Destination<'_> {
+    fn of<'a>(link: &'a Link<'_>) -> Destination<'a> {
+        let path = link.path.trim();
+
+        if path.starts_with('(') {
+            return Destination::Other;
+        }
+        if path.starts_with('#') || path.starts_with('*') {
+            return Destination::Internal(path);
+        }
+        if path.starts_with('/')
+            || path.starts_with("./")
+            || path.starts_with("../")
+            || path.starts_with('~')
+        {
+            return Destination::file(path);
+        }
+
+        match path.find(':') {
+            Some(i)
+                if path[0..i]
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-') =>
+            {
+                let target = &path[i + 1..];
+                match &*path[0..i].to_ascii_lowercase() {
+                    "file" => Destination::file(target),
+                    "id" => Destination::Id(target.trim()),
+                    _ => Destination::Other,
+                }
+            }
+            _ => Destination::Internal(path),
+        }
+    }
+
+    fn file(target: &str) -> Destination<'_> {
+        match target.find("::") {
+            Some(i) => Destination::File {
+                path: &target[0..i],
+                search: Some(&target[i + 2..]).filter(|search| !search.is_empty()),
+            },
+            None => Destination::File {
+                path: target,
+                search: None,
+            },
+        }
+    }
+}
+
+impl<'a> Workspace<'a> {
+    pub fn new() -> Workspace<'a> {
+        Workspace::default()
+    }
+
+    /// Parses and adds many files, see [`parse_files`]
+    ///
+    /// The ids and titles of the headlines are indexed while parsing, on the
+    /// same thread. Returns the workspace and the diagnostics of every file.
+    ///
+    /// [`parse_files`]: fn.parse_files.html
+    pub fn parse_files<I, P, C>(
+        files: I,
+        config: &ParseConfig,
+    ) -> (Workspace<'static>, Vec<Diagnostic>)
+    where
+        I: IntoIterator<Item = (P, C)>,
+        P: Into<PathBuf>,
+        C: AsRef<[u8]> + Send,
+    {
+        let mut workspace = Workspace::new();
+        let mut diagnostics = Vec::new();
+
+        for (path, org, file_diagnostics, index) in parse_files_with(files, config, Index::new) {
+            workspace.insert(normalize(&path), org, index);
+            diagnostics.extend(file_diagnostics);
+        }
+
+        (workspace, diagnostics)
+    }
+
+    /// Adds a document, `path` is used to resolve its `file:` links
+    ///
+    /// Adding a document whose path is already in the workspace replaces it.
+    pub fn add<P: Into<PathBuf>>(&mut self, path: P, org: Org<'a>) {
+        let index = Index::new(&org);
+        self.insert(normalize(&path.into()), org, index);
+    }
+
+    fn insert(&mut self, path: PathBuf, org: Org<'a>, index: Index) {
+        match self.documents.iter().position(|(p, _)| *p == path) {
+            Some(i) => {
+                self.documents[i].1 = org;
+                self.indices[i] = index;
+            }
+            None => {
+                self.documents.push((path, org));
+                self.indices.push(index);
+            }
+        }
+    }
+
+    /// Returns the documents, in the order they were added
+    pub fn documents(&self) -> impl Iterator<Item = (&Path, &Org<'a>)> {
+        self.documents
+            .iter()
+            .map(|(path, org)| (path.as_path(), org))
+    }
+
+    /// Returns the document at `path`
+    pub fn document(&self, path: &Path) -> Option<&Org<'a>> {
+        let path = normalize(path);
+        self.documents
+            .iter()
+            .find(|(p, _)| *p == path)
+            .map(|(_, org)| org)
+    }
+
+    /// Returns the document and the headline whose `ID` property is `id`
+    pub fn find_id(&self, id: &str) -> Option<(&Path, HeadlineNode)> {
+        self.documents
+            .iter()
+            .zip(&self.indices)
+            .find_map(|((path, _), index)| Some((path.as_path(), *index.ids.get(id)?)))
+    }
+
+    /// Returns the documents and the headlines whose title is `title`,
+    /// without todo keyword, priority and tags
+    pub fn find_title<'b>(
+        &'b self,
+        title: &'b str,
+    ) -> impl Iterator<Item = (&'b Path, HeadlineNode)> + 'b {
+        self.documents
+            .iter()
+            .zip(&self.indices)
+            .flat_map(move |((path, _), index)| {
+                index
+                    .titles
+                    .get(title.trim())
+                    .into_iter()
+                    .flatten()
+                    .map(move |headline| (path.as_path(), *headline))
+            })
+    }
+
+    /// Returns the broken links of the documents, and the targets defined
+    /// more than once
+    ///
+    /// `file:` links are resolved relative to the directory of their
+    /// document. The ones to files outside of the workspace are broken unless
+    /// `exists` returns `true` for their path.
+    pub fn check_links<F>(&self, exists: F) -> Vec<LinkIssue>
+    where
+        F: Fn(&Path) -> bool,
+    {
+        let mut issues = Vec::new();
+
+        let targets: Vec<_> = self
+            .documents
+            .iter()
+            .map(|(path, org)| Targets::new(org, &mut issues, path))
+            .collect();
+
+        let mut ids = HashSet::new();
+        for (path, org) in &self.documents {
+            for headline in org.headlines() {
+                if let Some(id) = headline_id(headline, org) {
+                    if !ids.insert(id) {
+                        issues.push(LinkIssue {
+                            document: path.clone(),
+                            span: org.span_of(headline.title_node),
+                            text: id.to_string(),
+                            kind: LinkIssueKind::DuplicateTarget,
+                            suggestion: None,
+                        });
+                    }
+                }
+            }
+        }
+
+        for ((path, org), document_targets) in self.documents.iter().zip(&targets) {
+            let dir = path.parent().unwrap_or_else(|| Path::new(""));
+
+            for node in org.root.descendants(&org.arena) {
+                let link = match org.arena[node].get() {
+                    Element::Link(link) => link,
+                    _ => continue,
+                };
+
+                let (kind, suggestion) = match Destination::of(link) {
+                    Destination::Internal(search) => {
+                        if document_targets.contains(search) {
+                            continue;
+                        }
+                        (
+                            LinkIssueKind::MissingTarget,
+                            document_targets.suggest(search),
+                        )
+                    }
+                    Destination::File { path: file, search } => {
+                        let file = normalize(&dir.join(file));
+                        match self.documents.iter().position(|(p, _)| *p == file) {
+                            Some(i) => match search {
+                                Some(search) if !targets[i].contains(search) => {
+                                    (LinkIssueKind::MissingTarget, targets[i].suggest(search))
+                                }
+                                _ => continue,
+                            },
+                            None if exists(&file) => continue,
+                            None => (
+                                LinkIssueKind::MissingFile,
+                                self.nearest_file(&file).map(|nearest| {
+                                    relative_to(dir, nearest).to_string_lossy().into_owned()
+                                }),
+                            ),
+                        }
+                    }
+                    Destination::Id(id) => {
+                        if ids.contains(id) {
+                            continue;
+                        }
+                        (LinkIssueKind::UnknownId, nearest(id, &ids))
+                    }
+                    Destination::Other => continue,
+                };
+
+                issues.push(LinkIssue {
+                    document: path.clone(),
+                    span: org.span_of(node),
+                    text: source_text(org, node, &format!("[[{}]]", link.path)),
+                    kind,
+                    suggestion,
+                });
+            }
+        }
+
+        issues
+    }
+
+    /// Returns the path of the document whose file name is the closest to
+    /// the one of `file`
+    fn nearest_file(&self, file: &Path) -> Option<&Path> {
+        let name = file.file_name()?.to_string_lossy();
+        let names: Vec<_> = self
+            .documents
+            .iter()
+            .filter_map(|(path, _)| Some((path.file_name()?.to_string_lossy(), path)))
+            .collect();
+        let nearest = nearest(&name, names.iter().map(|(name, _)| name))?;
+        names
+            .iter()
+            .find(|(name, _)| *name == nearest)
+            .map(|(_, path)| path.as_path())
+    }
+}
+
+fn headline_id<'a>(headline: HeadlineNode, org: &'a Org<'_>) -> Option<&'a str> {
+    headline.property("ID", org).map(str::trim)
+}
+
+fn source_text(org: &Org<'_>, node: NodeId, fallback: &str) -> String {
+    org.source_of(node)
+        .map(|source| source.trim_end().to_string())
+        .unwrap_or_else(|| fallback.to_string())
+}
+
+/// Removes the `.` components and the `..` components following a normal
+/// component, without touching the filesystem
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+
+    for component in path.components() {
+        match component {
+            Component::CurDir => (),
+            Component::ParentDir => match normalized.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    normalized.pop();
+                }
+                _ => normalized.push(component),
+            },
+            _ => normalized.push(component),
+        }
+    }
+
+    normalized
+}
+
+/// Returns `path` relative to the directory `dir`, both being normalized
+fn relative_to(dir: &Path, path: &Path) -> PathBuf {
+    let dir: Vec<_> = dir.components().collect();
+    let components: Vec<_> = path.components().collect();
+    let common = dir
+        .iter()
+        .zip(&components)
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let mut relative = PathBuf::new();
+    for _ in common..dir.len() {
+        relative.push("..");
+    }
+    for component in &components[common..] {
+        relative.push(component);
+    }
+    relative
+}
+
+/// Returns the candidate closest to `target`, if it's close enough to be a
+/// likely typo
+fn nearest<I, S>(target: &str, candidates: I) -> Option<String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let target = target.to_lowercase();
+    let max_distance = (target.chars().count() / 3).max(2);
+
+    candidates
+        .into_iter()
+        .map(|candidate| {
+            let distance = edit_distance(&target, &candidate.as_ref().to_lowercase());
+            (distance, candidate)
+        })
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by(|(a, x), (b, y)| a.cmp(b).then_with(|| x.as_ref().cmp(y.as_ref())))
+        .map(|(_, candidate)| candidate.as_ref().to_string())
+}
+
+/// Levenshtein distance, counted in characters
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, a) in a.chars().enumerate() {
+        let mut previous = row[0];
+        row[0] = i + 1;
+        for j in 0..b.len() {
+            let current = row[j + 1];
+            row[j + 1] = if a == b[j] {
+                previous
+            } else {
+                1 + previous.min(row[j]).min(current)
+            };
+            previous = current;
+        }
+    }
+
+    row[b.len()]
+}
+
+#[test]
+fn paths() {
+    assert_eq!(normalize(Path::new("a/./b/../c.org")), Path::new("a/c.org"));
+    assert_eq!(normalize(Path::new("../a/../../b")), Path::new("../../b"));
+    assert_eq!(
+        relative_to(Path::new("a/b"), Path::new("a/c/d.org")),
+        Path::new("../c/d.org")
+    );
+    assert_eq!(edit_distance("kitten", "sitting"), 3);
+    assert_eq!(
+        nearest("Intro", vec!["Introduction", "intro.", "Outro"]),
+        Some("intro.".to_string())
+    );
+    assert_eq!(nearest("abc", vec!["xyz"]), None);
+}
@@ -0,0 +1,273 @@
+//! Conformance of the parser with org-element, the parser of org-mode.
+//!
+//! Each file of `corpus/` covers a syntax feature, named by its `#+TITLE:`.
+//! Its tree, normalized by `normalize`, is compared with `snapshots/`, the
+//! tree org-element gives it converted by `org_element`. The known
+//! differences are listed in `differences.json`, with the status of the
+//! feature they make partial or unsupported, and the reason. Other
+//! differences, and known ones which are gone, fail the test.
+//!
+//! The snapshots are converted again from the dumps of
+//! `org-element-dump.el` when `ORG_ELEMENT_DUMPS` is set to their
+//! directory, and the conformance table of the README is written again when
+//! `UPDATE_CONFORMANCE` is set.
+//!
+//! Cases without a snapshot are skipped. Snapshots are only ever converted
+//! from dumps of org-element, never written by hand, since the suite would
+//! then compare the parser with itself.
+
+mod normalize;
+mod org_element;
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use orgize::Org;
+use serde_json::Value;
+
+use normalize::normalize;
+
+const TABLE_START: &str = "<!-- conformance:start -->\n";
+const TABLE_END: &str = "<!-- conformance:end -->\n";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Status {
+    Supported,
+    Partial,
+    Unsupported,
+}
+
+/// An intentional difference, at `path` of the tree of `case`
+struct Tolerance {
+    case: String,
+    path: String,
+    /// Property which differs, any if `None`
+    property: Option<String>,
+    status: Status,
+    reason: String,
+    used: bool,
+}
+
+/// A difference between a snapshot and the tree of orgize
+#[derive(Debug)]
+struct Difference {
+    /// Indices of the node and its ancestors among their siblings, like
+    /// `/0/1`
+    path: String,
+    property: String,
+    expected: Value,
+    actual: Value,
+}
+
+fn dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/conformance")
+}
+
+fn read_json(path: &Path) -> Value {
+    let text = fs::read_to_string(path).unwrap_or_else(|err| panic!("{:?}: {}", path, err));
+    serde_json::from_str(&text).unwrap_or_else(|err| panic!("{:?}: {}", path, err))
+}
+
+fn tolerances() -> Vec<Tolerance> {
+    let differences = read_json(&dir().join("differences.json"));
+    let string = |value: &Value, key: &str| {
+        value[key]
+            .as_str()
+            .unwrap_or_else(|| panic!("difference without {}: {}", key, value))
+            .to_string()
+    };
+
+    differences
+        .as_array()
+        .expect("differences.json is an array")
+        .iter()
+        .map(|difference| Tolerance {
+            case: string(difference, "case"),
+            path: string(difference, "path"),
+            property: difference["property"].as_str().map(Into::into),
+            status: match difference["status"].as_str() {
+                Some("partial") => Status::Partial,
+                Some("unsupported") => Status::Unsupported,
+                _ => panic!("status is partial or unsupported: {}", difference),
+            },
+            reason: string(difference, "reason"),
+            used: false,
+        })
+        .collect()
+}
+
+/// Pushes the differences between `expected` and `actual`, at `path`
+fn compare(expected: &Value, actual: &Value, path: &str, differences: &mut Vec<Difference>) {
+    let mut difference = |property: &str, expected: &Value, actual: &Value| {
+        differences.push(Difference {
+            path: path.to_string(),
+            property: property.to_string(),
+            expected: expected.clone(),
+            actual: actual.clone(),
+        })
+    };
+
+    if expected["type"] != actual["type"] {
+        difference("type", expected, actual);
+        return;
+    }
+
+    let mut keys: Vec<&String> = expected
+        .as_object()
+        .into_iter()
+        .chain(actual.as_object())
+        .flat_map(|node| node.keys())
+        .filter(|&key| key != "children")
+        .collect();
+    keys.sort();
+    keys.dedup();
+    for key in keys {
+        if expected[key] != actual[key] {
+            difference(key, &expected[key], &actual[key]);
+        }
+    }
+
+    let empty = Vec::new();
+    let expected_children = expected["children"].as_array().unwrap_or(&empty);
+    let actual_children = actual["children"].as_array().unwrap_or(&empty);
+    if expected_children.len() != actual_children.len() {
+        difference("children", &expected["children"], &actual["children"]);
+        return;
+    }
+    for (i, (expected, actual)) in expected_children.iter().zip(actual_children).enumerate() {
+        compare(expected, actual, &format!("{}/{}", path, i), differences);
+    }
+}
+
+/// Returns the conformance table, with the status of each feature and the
+/// reasons of its differences
+fn table(rows: &[(String, Status, Vec<String>)]) -> String {
+    if rows.is_empty() {
+        return String::from("No feature has been compared with org-element yet.\n");
+    }
+
+    let mut table = String::from("| Feature | Status | Notes |\n| --- | --- | --- |\n");
+    for (feature, status, reasons) in rows {
+        let status = match status {
+            Status::Supported => "supported",
+            Status::Partial => "partial",
+            Status::Unsupported => "unsupported",
+        };
+        table += &format!("| {} | {} | {} |\n", feature, status, reasons.join(" "));
+    }
+    table
+}
+
+#[test]
+fn conformance() {
+    let mut tolerances = tolerances();
+    let dumps = env::var_os("ORG_ELEMENT_DUMPS").map(PathBuf::from);
+
+    let mut cases: Vec<PathBuf> = fs::read_dir(dir().join("corpus"))
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension() == Some("org".as_ref()))
+        .collect();
+    cases.sort();
+
+    let mut rows = Vec::new();
+    let mut failures = Vec::new();
+    for path in cases {
+        let case = path.file_stem().unwrap().to_string_lossy().into_owned();
+        let snapshot = dir().join("snapshots").join(format!("{}.json", case));
+
+        if let Some(dump) = dumps
+            .as_ref()
+            .map(|dumps| dumps.join(format!("{}.json", case)))
+        {
+            let converted = org_element::convert(&read_json(&dump));
+            let json = serde_json::to_string_pretty(&converted).unwrap();
+            fs::create_dir_all(snapshot.parent().unwrap()).unwrap();
+            fs::write(&snapshot, json + "\n").unwrap();
+        }
+        if !snapshot.exists() {
+            continue;
+        }
+
+        let text = fs::read_to_string(&path).unwrap();
+        let feature = text
+            .lines()
+            .find_map(|line| line.strip_prefix("#+TITLE: "))
+            .unwrap_or_else(|| panic!("{:?} has no title", path))
+            .to_string();
+
+        let mut differences = Vec::new();
+        compare(
+            &read_json(&snapshot),
+            &normalize(&Org::parse(&text)),
+            "",
+            &mut differences,
+        );
+
+        let mut status = Status::Supported;
+        let mut reasons: Vec<String> = Vec::new();
+        for difference in differences {
+            let tolerance = tolerances.iter_mut().find(|tolerance| {
+                tolerance.case == case
+                    && tolerance.path == difference.path
+                    && tolerance
+                        .property
+                        .iter()
+                        .all(|property| *property == difference.property)
+            });
+            match tolerance {
+                Some(tolerance) => {
+                    tolerance.used = true;
+                    status = status.max(tolerance.status);
+                    if !reasons.contains(&tolerance.reason) {
+                        reasons.push(tolerance.reason.clone());
+                    }
+                }
+                None => failures.push(format!(
+                    "{}: {} of the node at {:?} differs\nexpected: {}\nactual: {}",
+                    case,
+                    difference.property,
+                    difference.path,
+                    difference.expected,
+                    difference.actual
+                )),
+            }
+        }
+        rows.push((feature, status, reasons));
+    }
+
+    for tolerance in tolerances.iter().filter(|tolerance| !tolerance.used) {
+        failures.push(format!(
+            "{}: no difference at {:?} anymore, remove it from differences.json",
+            tolerance.case, tolerance.path
+        ));
+    }
+    assert!(failures.is_empty(), "\n{}", failures.join("\n\n"));
+    rows.sort();
+
+    let readme_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("README.md");
+    let readme = fs::read_to_string(&readme_path).unwrap();
+    let start = readme
+        .find(TABLE_START)
+        .expect("README has a conformance table")
+        + TABLE_START.len();
+    let end = readme
+        .find(TABLE_END)
+        .expect("README has a conformance table");
+    let table = table(&rows);
+    if env::var_os("UPDATE_CONFORMANCE").is_some() {
+        fs::write(
+            &readme_path,
+            format!("{}{}{}", &readme[..start], table, &readme[end..]),
+        )
+        .unwrap();
+    } else {
+        assert!(
+            readme[start..end] == table,
+            "the conformance table of the README is outdated, \
+             run `UPDATE_CONFORMANCE=1 cargo test --test conformance`\n{}",
+            table
+        );
+    }
+}
@@ -0,0 +1,141 @@
+//! Normalized trees of orgize, the schema of the snapshots
+
+use orgize::elements::{Checkbox, Timestamp};
+use orgize::{Element, Event, Org};
+use serde_json::{json, Map, Value};
+
+/// Returns the tree of `org`, made of the nodes returned by `node`
+pub fn normalize(org: &Org<'_>) -> Value {
+    let mut stack = vec![Map::new()];
+
+    for event in org.iter() {
+        match event {
+            Event::Start(element) => stack.push(node(element)),
+            Event::End(_) => {
+                let node = stack.pop().unwrap();
+                push_child(stack.last_mut().unwrap(), Value::Object(node));
+            }
+        }
+    }
+
+    let mut root = stack.pop().unwrap();
+    match root.remove("children") {
+        Some(Value::Array(mut children)) => children.remove(0),
+        _ => unreachable!("documents always have a root"),
+    }
+}
+
+/// Appends `child` to the `children` of `node`
+pub fn push_child(node: &mut Map<String, Value>, child: Value) {
+    if let Value::Array(children) = node
+        .entry("children")
+        .or_insert_with(|| Value::Array(Vec::new()))
+    {
+        children.push(child);
+    }
+}
+
+/// Returns the node of `element`: its `type`, the kebab-case name of its
+/// kind, and the properties which org-element also has, without its
+/// `children`
+fn node(element: &Element<'_>) -> Map<String, Value> {
+    let mut node = Map::new();
+    node.insert("type".into(), kind(element).into());
+    for (key, value) in properties(element) {
+        if !value.is_null() {
+            node.insert(key.into(), value);
+        }
+    }
+    node
+}
+
+fn kind(element: &Element<'_>) -> String {
+    let mut kind = String::new();
+    for c in format!("{:?}", element.kind()).chars() {
+        if c.is_ascii_uppercase() && !kind.is_empty() {
+            kind.push('-');
+        }
+        kind.push(c.to_ascii_lowercase());
+    }
+    kind
+}
+
+fn properties(element: &Element<'_>) -> Vec<(&'static str, Value)> {
+    match element {
+        Element::Headline { level } => vec![("level", json!(level))],
+        Element::Title(title) => vec![
+            ("keyword", json!(title.keyword)),
+            ("priority", json!(title.priority.map(|p| p.to_string()))),
+            (
+                "tags",
+                if title.tags.is_empty() {
+                    Value::Null
+                } else {
+                    json!(title.tags)
+                },
+            ),
+        ],
+        Element::Text { value }
+        | Element::Verbatim { value }
+        | Element::Code { value }
+        | Element::Comment { value }
+        | Element::FixedWidth { value } => vec![("value", json!(value))],
+        Element::Link(link) => vec![("path", json!(link.path))],
+        Element::List(list) => vec![("ordered", json!(list.ordered))],
+        Element::ListItem(item) => vec![
+            ("bullet", json!(item.bullet.trim_end())),
+            (
+                "checkbox",
+                json!(item.checkbox.map(|checkbox| match checkbox {
+                    Checkbox::Checked => "checked",
+                    Checkbox::Unchecked => "unchecked",
+                    Checkbox::Partial => "partial",
+                })),
+            ),
+            ("counter", json!(item.counter)),
+        ],
+        Element::TableRow(row) if row.is_rule() => vec![("rule", json!(true))],
+        Element::Keyword(keyword) => {
+            vec![("key", json!(keyword.key)), ("value", json!(keyword.value))]
+        }
+        Element::SourceBlock(block) => vec![
+            ("language", json!(block.language)),
+            ("contents", json!(block.contents)),
+        ],
+        Element::ExampleBlock(block) => vec![("contents", json!(block.contents))],
+        Element::Drawer(drawer) => vec![("name", json!(drawer.name))],
+        Element::FnDef(fn_def) => vec![("label", json!(fn_def.label))],
+        Element::FnRef(fn_ref) => vec![("label", json!(fn_ref.label))],
+        Element::Target(target) => vec![("target", json!(target.target))],
+        Element::RadioTarget(radio_target) => vec![("target", json!(radio_target.target))],
+        Element::Entity(entity) => vec![("name", json!(entity.name))],
+        Element::LatexFragment(fragment) => vec![("value", json!(fragment.value))],
+        Element::Timestamp(timestamp) => vec![(
+            "timestamp-type",
+            json!(match timestamp {
+                Timestamp::Active { .. } => "active",
+                Timestamp::Inactive { .. } => "inactive",
+                Timestamp::ActiveRange { .. } => "active-range",
+                Timestamp::InactiveRange { .. } => "inactive-range",
+                Timestamp::Diary { .. } => "diary",
+            }),
+        )],
+        Element::Macros(macros) => vec![
+            ("name", json!(macros.name)),
+            ("arguments", json!(macros.arguments)),
+        ],
+        Element::Snippet(snippet) => {
+            vec![
+                ("name", json!(snippet.name)),
+                ("value", json!(snippet.value)),
+            ]
+        }
+        Element::InlineSrc(inline_src) => vec![
+            ("lang", json!(inline_src.lang)),
+            ("body", json!(inline_src.body)),
+        ],
+        Element::InlineCall(inline_call) => vec![("name", json!(inline_call.name))],
+        Element::Cookie(cookie) => vec![("value", json!(cookie.value))],
+        _ => Vec::new(),
+    }
+}
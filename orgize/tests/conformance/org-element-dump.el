@@ -0,0 +1,67 @@
+;;; org-element-dump.el --- Dump org-element trees for the conformance suite  -*- lexical-binding: t -*-
+
+;; Parses each org file given on the command line with `org-element' and
+;; writes its tree as JSON to DIR/NAME.json, keeping the properties which
+;; `org_element.rs' converts to the schema of orgize:
+;;
+;;   emacs --batch -l org-element-dump.el -f orgize-dump DIR corpus/*.org
+;;
+;; Then the snapshots are converted from the dumps with:
+;;
+;;   ORG_ELEMENT_DUMPS=DIR cargo test --test conformance
+
+(require 'json)
+(require 'org)
+(require 'org-element)
+
+(defconst orgize-dump-properties
+  '(:args :back-end :bullet :call :checkbox :counter :drawer-name :key
+    :label :language :level :name :post-blank :priority :raw-link
+    :raw-value :todo-keyword :type :value)
+  "Properties of the nodes written to the dumps.")
+
+(defun orgize-dump-value (value)
+  "Returns VALUE, a property, as a JSON value."
+  (cond ((stringp value) (substring-no-properties value))
+        ((or (numberp value) (null value)) value)
+        ((symbolp value) (symbol-name value))
+        ((consp value) (vconcat (mapcar #'orgize-dump-value value)))))
+
+(defun orgize-dump-node (node)
+  "Returns NODE, an element, an object or a string, as a JSON value."
+  (if (stringp node)
+      (substring-no-properties node)
+    (let ((properties nil))
+      (dolist (property orgize-dump-properties)
+        (let ((value (org-element-property property node)))
+          (when value
+            (push (cons (substring (symbol-name property) 1)
+                        (orgize-dump-value value))
+                  properties))))
+      ;; secondary strings, parsed objects stored in properties
+      (dolist (property '(:title :tag))
+        (let ((value (org-element-property property node)))
+          (when value
+            (push (cons (substring (symbol-name property) 1)
+                        (vconcat (mapcar #'orgize-dump-node value)))
+                  properties))))
+      (list (cons "type" (symbol-name (org-element-type node)))
+            (cons "properties" (or (nreverse properties) (make-hash-table)))
+            (cons "contents"
+                  (vconcat (mapcar #'orgize-dump-node
+                                   (org-element-contents node))))))))
+
+(defun orgize-dump ()
+  "Dumps the files of `command-line-args-left' to its first element."
+  (let ((directory (pop command-line-args-left)))
+    (dolist (file command-line-args-left)
+      (let ((tree (with-temp-buffer
+                    (insert-file-contents file)
+                    (org-mode)
+                    (org-element-parse-buffer))))
+        (with-temp-file (expand-file-name
+                         (concat (file-name-base file) ".json") directory)
+          (insert (json-encode (orgize-dump-node tree))))))
+    (setq command-line-args-left nil)))
+
+;;; org-element-dump.el ends here
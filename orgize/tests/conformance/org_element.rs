@@ -0,0 +1,332 @@
+//! Conversion of the trees dumped by `org-element-dump.el` to the schema of
+//! `normalize`
+//!
+//! Besides renaming types and properties, it moves whitespace where orgize
+//! keeps it:
+//!
+//! + org-element counts the spaces following an object as its
+//!   `:post-blank`, orgize keeps them in the text after it
+//! + org-element ends the line break object with the newline following it,
+//!   orgize keeps it in the text after it
+//! + org-element keeps the newline ending a paragraph in its last text,
+//!   orgize doesn't
+//!
+//! The planning and the property drawer of a headline, which orgize stores
+//! in its title, aren't compared.
+
+use serde_json::{json, Map, Value};
+
+use crate::normalize::push_child;
+
+/// Converts `dump`, the tree of a document
+pub fn convert(dump: &Value) -> Value {
+    node(dump).expect("documents are converted")
+}
+
+/// Converts an element or an object, or returns `None` for the ones orgize
+/// doesn't store in its tree
+fn node(dump: &Value) -> Option<Value> {
+    let dump_type = dump["type"].as_str().unwrap_or_default();
+    let properties = &dump["properties"];
+    let property = |key: &str| properties.get(key).cloned().unwrap_or(Value::Null);
+    let string = |key: &str| property(key).as_str().unwrap_or_default().to_string();
+
+    let (kind, converted): (&str, Vec<(&str, Value)>) = match dump_type {
+        "org-data" => ("document", vec![]),
+        "planning" | "property-drawer" => return None,
+        "headline" => ("headline", vec![("level", property("level"))]),
+        "plain-list" => (
+            "list",
+            vec![("ordered", json!(string("type") == "ordered"))],
+        ),
+        "item" => (
+            "list-item",
+            vec![
+                ("bullet", json!(string("bullet").trim_end())),
+                (
+                    "checkbox",
+                    match string("checkbox").as_str() {
+                        "on" => json!("checked"),
+                        "off" => json!("unchecked"),
+                        "trans" => json!("partial"),
+                        _ => Value::Null,
+                    },
+                ),
+                ("counter", property("counter")),
+            ],
+        ),
+        "table-row" if string("type") == "rule" => ("table-row", vec![("rule", json!(true))]),
+        "keyword" => (
+            "keyword",
+            vec![("key", property("key")), ("value", property("value"))],
+        ),
+        "src-block" => (
+            "source-block",
+            vec![
+                ("language", json!(string("language"))),
+                ("contents", property("value")),
+            ],
+        ),
+        "example-block" => ("example-block", vec![("contents", property("value"))]),
+        "drawer" => ("drawer", vec![("name", property("drawer-name"))]),
+        "footnote-definition" => ("fn-def", vec![("label", property("label"))]),
+        "footnote-reference" => ("fn-ref", vec![("label", property("label"))]),
+        "target" => ("target", vec![("target", property("value"))]),
+        "radio-target" => ("radio-target", vec![("target", property("raw-value"))]),
+        "entity" => ("entity", vec![("name", property("name"))]),
+        "latex-fragment" => (
+            "latex-fragment",
+            vec![("value", json!(strip_delimiters(&string("value"))))],
+        ),
+        "latex-environment" => (
+            "latex-fragment",
+            vec![("value", json!(string("value").trim_end()))],
+        ),
+        "timestamp" => ("timestamp", vec![("timestamp-type", property("type"))]),
+        "macro" => (
+            "macros",
+            vec![
+                ("name", property("key")),
+                (
+                    "arguments",
+                    match property("args") {
+                        Value::Array(args) if !args.is_empty() => json!(args
+                            .iter()
+                            .filter_map(Value::as_str)
+                            .collect::<Vec<_>>()
+                            .join(",")),
+                        _ => Value::Null,
+                    },
+                ),
+            ],
+        ),
+        "export-snippet" => (
+            "snippet",
+            vec![("name", property("back-end")), ("value", property("value"))],
+        ),
+        "inline-src-block" => (
+            "inline-src",
+            vec![("lang", property("language")), ("body", property("value"))],
+        ),
+        "inline-babel-call" => ("inline-call", vec![("name", property("call"))]),
+        "statistics-cookie" => ("cookie", vec![("value", property("value"))]),
+        "verbatim" | "code" | "comment" | "fixed-width" => {
+            (dump_type, vec![("value", property("value"))])
+        }
+        "link" => ("link", vec![("path", property("raw-link"))]),
+        "strike-through" => ("strike", vec![]),
+        "horizontal-rule" => ("rule", vec![]),
+        "dynamic-block" => ("dyn-block", vec![]),
+        kind => (kind, vec![]),
+    };
+
+    let mut node = Map::new();
+    node.insert("type".into(), kind.into());
+    for (key, value) in converted {
+        if !value.is_null() {
+            node.insert(key.into(), value);
+        }
+    }
+
+    match dump_type {
+        "headline" => {
+            let mut title = Map::new();
+            title.insert("type".into(), "title".into());
+            for (key, dump_key) in &[("keyword", "todo-keyword"), ("tags", "tags")] {
+                if let Some(value) = properties.get(dump_key) {
+                    title.insert(key.to_string(), value.clone());
+                }
+            }
+            // priorities are characters
+            if let Some(priority) = properties["priority"].as_u64() {
+                let priority = std::char::from_u32(priority as u32).unwrap_or_default();
+                title.insert("priority".into(), priority.to_string().into());
+            }
+            for child in objects(&properties["title"]) {
+                push_child(&mut title, child);
+            }
+            push_child(&mut node, Value::Object(title));
+        }
+        "item" if properties.get("tag").is_some() => {
+            let mut tag = Map::new();
+            tag.insert("type".into(), "list-item-tag".into());
+            for child in objects(&properties["tag"]) {
+                push_child(&mut tag, child);
+            }
+            push_child(&mut node, Value::Object(tag));
+        }
+        _ => (),
+    }
+
+    let mut children = objects(&dump["contents"]);
+    if dump_type == "paragraph" {
+        if let Some(Value::Object(last)) = children.last_mut() {
+            if last["type"] == "text" {
+                if let Some(Value::String(value)) = last.get_mut("value") {
+                    let len = value.trim_end().len();
+                    value.truncate(len);
+                }
+            }
+        }
+        if children.last() == Some(&json!({ "type": "text", "value": "" })) {
+            children.pop();
+        }
+    }
+    for child in children {
+        push_child(&mut node, child);
+    }
+
+    Some(Value::Object(node))
+}
+
+/// Converts the contents of a node, merging the adjacent texts
+fn objects(contents: &Value) -> Vec<Value> {
+    let mut children: Vec<Value> = Vec::new();
+    let empty = Vec::new();
+
+    for content in contents.as_array().unwrap_or(&empty) {
+        if let Some(text) = content.as_str() {
+            push_text(&mut children, text);
+            continue;
+        }
+        if let Some(child) = node(content) {
+            children.push(child);
+        }
+        if is_object(content["type"].as_str().unwrap_or_default()) {
+            let post_blank = content["properties"]["post-blank"].as_u64().unwrap_or(0);
+            push_text(&mut children, &" ".repeat(post_blank as usize));
+        }
+        if content["type"] == "line-break" {
+            push_text(&mut children, "\n");
+        }
+    }
+
+    children
+}
+
+fn push_text(children: &mut Vec<Value>, text: &str) {
+    if text.is_empty() {
+        return;
+    }
+    if let Some(Value::Object(last)) = children.last_mut() {
+        if last["type"] == "text" {
+            if let Some(Value::String(value)) = last.get_mut("value") {
+                value.push_str(text);
+                return;
+            }
+        }
+    }
+    children.push(json!({ "type": "text", "value": text }));
+}
+
+/// Returns `true` for the types of org-element objects, whose `:post-blank`
+/// is a number of spaces rather than of blank lines
+fn is_object(dump_type: &str) -> bool {
+    match dump_type {
+        "bold" | "citation" | "citation-reference" | "code" | "entity" | "export-snippet"
+        | "footnote-reference" | "inline-babel-call" | "inline-src-block" | "italic"
+        | "latex-fragment" | "line-break" | "link" | "macro" | "radio-target"
+        | "statistics-cookie" | "strike-through" | "subscript" | "superscript" | "table-cell"
+        | "target" | "timestamp" | "underline" | "verbatim" => true,
+        _ => false,
+    }
+}
+
+/// Returns the value of a LaTeX fragment without its `$`, `$$`, `\(` and
+/// `\)`, or `\[` and `\]` delimiters, like orgize stores it
+fn strip_delimiters(value: &str) -> &str {
+    for (open, close) in &[("$$", "$$"), ("\\(", "\\)"), ("\\[", "\\]"), ("$", "$")] {
+        if value.len() >= open.len() + close.len()
+            && value.starts_with(open)
+            && value.ends_with(close)
+        {
+            return &value[open.len()..value.len() - close.len()];
+        }
+    }
+    value
+}
+
+#[test]
+fn convert_dump() {
+    let dump = json!({
+        "type": "org-data",
+        "properties": {},
+        "contents": [{
+            "type": "headline",
+            "properties": {
+                "level": 1,
+                "priority": 65,
+                "raw-value": "Title",
+                "tags": ["a"],
+                "title": ["Title"],
+                "todo-keyword": "TODO"
+            },
+            "contents": [{
+                "type": "section",
+                "properties": {},
+                "contents": [
+                    { "type": "planning", "properties": {}, "contents": [] },
+                    {
+                        "type": "paragraph",
+                        "properties": {},
+                        "contents": [
+                            {
+                                "type": "bold",
+                                "properties": { "post-blank": 1 },
+                                "contents": ["bold"]
+                            },
+                            "text",
+                            {
+                                "type": "line-break",
+                                "properties": { "post-blank": 0 },
+                                "contents": []
+                            },
+                            {
+                                "type": "latex-fragment",
+                                "properties": { "post-blank": 0, "value": "$x$" },
+                                "contents": []
+                            },
+                            "\n"
+                        ]
+                    }
+                ]
+            }]
+        }]
+    });
+
+    assert_eq!(
+        convert(&dump),
+        json!({
+            "type": "document",
+            "children": [{
+                "type": "headline",
+                "level": 1,
+                "children": [
+                    {
+                        "type": "title",
+                        "keyword": "TODO",
+                        "priority": "A",
+                        "tags": ["a"],
+                        "children": [{ "type": "text", "value": "Title" }]
+                    },
+                    {
+                        "type": "section",
+                        "children": [{
+                            "type": "paragraph",
+                            "children": [
+                                {
+                                    "type": "bold",
+                                    "children": [{ "type": "text", "value": "bold" }]
+                                },
+                                { "type": "text", "value": " text" },
+                                { "type": "line-break" },
+                                { "type": "text", "value": "\n" },
+                                { "type": "latex-fragment", "value": "x" }
+                            ]
+                        }]
+                    }
+                ]
+            }]
+        })
+    );
+}
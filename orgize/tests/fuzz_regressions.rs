@@ -0,0 +1,162 @@
+//! Minimized inputs found by the fuzz targets in `fuzz/`.
+//!
+//! Each test runs the input through the parser and every exporter, which
+//! also runs `Org::validate` in debug builds.
+
+use orgize::Org;
+use pretty_assertions::assert_eq;
+
+fn write_org(text: &str) -> String {
+    let mut writer = Vec::new();
+    Org::parse(text).org(&mut writer).unwrap();
+    String::from_utf8(writer).unwrap()
+}
+
+#[cfg(feature = "export-html")]
+fn write_html(text: &str) -> String {
+    let mut writer = Vec::new();
+    Org::parse(text).html(&mut writer).unwrap();
+    String::from_utf8(writer).unwrap()
+}
+
+macro_rules! no_panic {
+    ($name:ident, $content:expr) => {
+        #[test]
+        fn $name() {
+            #[cfg(feature = "export-html")]
+            write_html($content);
+            write_org($content);
+        }
+    };
+}
+
+macro_rules! round_trip {
+    ($name:ident, $content:expr) => {
+        #[test]
+        fn $name() {
+            let first = write_org($content);
+            assert_eq!(first, write_org(&first));
+        }
+    };
+}
+
+// empty emphasis markers
+no_panic!(empty_italic, "//::+ ");
+no_panic!(empty_underline, "__ }}}#+END:-----a");
+
+// bullet directly followed by a newline
+no_panic!(bullet_newline, "9 #@@html:: \r\n  *\n>>>");
+
+// empty containers
+no_panic!(empty_list_item, "- ");
+no_panic!(empty_ordered_list_item, "1) ");
+no_panic!(empty_nested_list_item, "\r\n  * + 1) ");
+no_panic!(empty_quote_block, "#+BEGIN_QUOTE");
+no_panic!(empty_special_block, "#+BEGIN_SRCb");
+no_panic!(empty_dyn_block, "#+BEGIN:\tb::");
+no_panic!(empty_table_row, "|");
+no_panic!(empty_table_row_in_list, "- |");
+no_panic!(table_el_rule, "+-");
+no_panic!(table_el_in_list, "- +-\n |\n+-");
+
+// footnote definitions are containers
+no_panic!(fn_def_content, "[fn:1]9");
+no_panic!(fn_def_empty, "[fn:--][");
+
+// non-ASCII lines checked for ASCII prefixes
+no_panic!(non_ascii_after_headline, "* a\n日本語\n");
+no_panic!(non_ascii_drawer_end, ":abcdé");
+no_panic!(non_ascii_block_end, "#+ENDé");
+no_panic!(non_ascii_planning, "* a\nCLOSEDé");
+
+round_trip!(round_trip_bullet_at_eof, "9)");
+round_trip!(round_trip_cookie, "[%]");
+round_trip!(round_trip_quote_block, "#+BEGIN_QUOTE\nfoo\n#+END_QUOTE\n");
+round_trip!(round_trip_table, "| a | b |\n|---+---|\n| c | d |\n");
+round_trip!(round_trip_tblfm, "| a |\n#+TBLFM: $1=1\n#+tblfm: @1=2\n");
+round_trip!(round_trip_table_el, "  +-+\n  |a|\n  +-+\n");
+round_trip!(round_trip_fn_def, "[fn:1] note\n");
+
+// writing the output again used to move blank lines or change elements
+round_trip!(idempotent_empty_headline_crlf, "*\r\n\\end{eq}#+END_SRC");
+round_trip!(idempotent_empty_list_item, "2) \n\n1. ü:tag:");
+round_trip!(idempotent_empty_checkbox_item, "- [ ] \n- x");
+round_trip!(idempotent_item_on_next_line, "- \r\n :a:b:/i/*** ");
+round_trip!(idempotent_bullet_crlf, "+\r\n[[file:a.png]]  ");
+round_trip!(
+    idempotent_comment_in_item_crlf,
+    "+ # comment\r\n:abcdé# comment\n#+NAME: n"
+);
+round_trip!(
+    idempotent_keyword_in_item,
+    "a\n- {{{title}}}\n\n  #+STARTUP: odd"
+);
+round_trip!(idempotent_table_in_tight_list, "- c\n  | x |\n- d");
+round_trip!(
+    idempotent_indented_tag,
+    "+   :: #+PRINT_BIBLIOGRAPHY: :: \té/+"
+);
+round_trip!(idempotent_empty_fn_def, "[fn:1]\n\n :a:b:<b");
+round_trip!(idempotent_trailing_tab, ":\t");
+round_trip!(idempotent_trailing_tab_bullet, "a\n-\t");
+round_trip!(idempotent_empty_item_ending_list, "+\n\n\n\n a");
+round_trip!(
+    idempotent_planning_like_paragraph,
+    "* DONE\n\nCLOSED: [2020-01-01 Wed] x"
+);
+round_trip!(idempotent_spaced_checkbox, "+  [X] ");
+round_trip!(idempotent_item_with_only_a_tag, "+ > :: \r\n- x");
+round_trip!(idempotent_angle_link_in_bracket, "[<https://x.y>");
+round_trip!(idempotent_plain_link_in_parens, "(http://x.y)#+SELECT_TAGS");
+
+// seeds of the `org_roundtrip` target
+round_trip!(
+    round_trip_seed_blocks,
+    include_str!("../fuzz/seeds/blocks.org")
+);
+round_trip!(
+    round_trip_seed_drawers,
+    include_str!("../fuzz/seeds/drawers.org")
+);
+round_trip!(
+    round_trip_seed_emphasis,
+    include_str!("../fuzz/seeds/emphasis.org")
+);
+round_trip!(
+    round_trip_seed_headlines,
+    include_str!("../fuzz/seeds/headlines.org")
+);
+round_trip!(
+    round_trip_seed_keywords,
+    include_str!("../fuzz/seeds/keywords.org")
+);
+round_trip!(round_trip_seed_link, include_str!("../fuzz/seeds/link.org"));
+round_trip!(round_trip_seed_list, include_str!("../fuzz/seeds/list.org"));
+round_trip!(
+    round_trip_seed_org_element_elements,
+    include_str!("../fuzz/seeds/org-element-elements.org")
+);
+round_trip!(
+    round_trip_seed_org_element_greater,
+    include_str!("../fuzz/seeds/org-element-greater.org")
+);
+round_trip!(
+    round_trip_seed_org_element_headlines,
+    include_str!("../fuzz/seeds/org-element-headlines.org")
+);
+round_trip!(
+    round_trip_seed_org_element_objects,
+    include_str!("../fuzz/seeds/org-element-objects.org")
+);
+round_trip!(
+    round_trip_seed_objects,
+    include_str!("../fuzz/seeds/objects.org")
+);
+round_trip!(
+    round_trip_seed_remnants,
+    include_str!("../fuzz/seeds/remnants.org")
+);
+round_trip!(
+    round_trip_seed_table,
+    include_str!("../fuzz/seeds/table.org")
+);
@@ -0,0 +1,199 @@
+#![cfg(feature = "md")]
+
+use orgize::{
+    elements::{Checkbox, ElementKind},
+    from_markdown, from_markdown_with_diagnostics, Element, Event, Org,
+};
+
+#[test]
+fn headlines() {
+    let org = from_markdown("intro\n\n# One\n\ntext\n\n### Three\n\n## Two\n\n# *Four*\n");
+
+    let headlines: Vec<_> = org
+        .headlines()
+        .map(|headline| (headline.level(), headline.title(&org).raw.to_string()))
+        .collect();
+    assert_eq!(
+        headlines,
+        [
+            (1, "One".into()),
+            (3, "Three".into()),
+            (2, "Two".into()),
+            (1, "/Four/".into())
+        ]
+    );
+
+    let one = org.headlines().next().unwrap();
+    let children: Vec<_> = one.children(&org).map(|child| child.level()).collect();
+    assert_eq!(children, [3, 2]);
+    assert_eq!(
+        org.document().children(&org).count(),
+        2,
+        "headlines are nested by level"
+    );
+
+    let mut writer = Vec::new();
+    org.org(&mut writer).unwrap();
+    assert_eq!(
+        String::from_utf8(writer).unwrap(),
+        "intro\n\n* One\ntext\n\n*** Three\n** Two\n* /Four/\n"
+    );
+}
+
+#[test]
+fn blocks() {
+    let org = from_markdown(
+        r#"- [x] done
+- [ ] todo
+  1. one
+  2. two
+
+```sql
+select 1;
+* from
+```
+
+    indented
+
+> quoted
+
+| a | b |
+|---|---|
+| 1 | 2 |
+
+Some **bold**, ~~struck~~, `code` and <https://x.org>.[^n]
+
+[^n]: A note.
+"#,
+    );
+
+    let mut bullets = Vec::new();
+    let mut checkboxes = Vec::new();
+    let mut texts = Vec::new();
+    let mut elements = Vec::new();
+    for event in org.iter() {
+        match event {
+            Event::Start(Element::ListItem(item)) => {
+                bullets.push(item.bullet.to_string());
+                checkboxes.push(item.checkbox);
+            }
+            Event::Start(Element::Text { value }) => texts.push(value.to_string()),
+            Event::Start(Element::SourceBlock(block)) => {
+                elements.push(format!("src {}: {}", block.language, block.contents))
+            }
+            Event::Start(Element::ExampleBlock(block)) => {
+                elements.push(format!("example: {}", block.contents))
+            }
+            Event::Start(Element::QuoteBlock(_)) => elements.push("quote".into()),
+            Event::Start(Element::Table(_)) => elements.push("table".into()),
+            Event::Start(Element::Bold) => elements.push("bold".into()),
+            Event::Start(Element::Strike) => elements.push("strike".into()),
+            Event::Start(Element::Code { value }) => elements.push(format!("code {}", value)),
+            Event::Start(Element::Link(link)) => elements.push(format!("link {}", link.path)),
+            Event::Start(Element::FnRef(fn_ref)) => {
+                elements.push(format!("fn-ref {}", fn_ref.label))
+            }
+            Event::Start(Element::FnDef(fn_def)) => {
+                elements.push(format!("fn-def {}", fn_def.label))
+            }
+            _ => (),
+        }
+    }
+
+    assert_eq!(bullets, ["- ", "- ", "1. ", "2. "]);
+    assert_eq!(
+        checkboxes,
+        [
+            Some(Checkbox::Checked),
+            Some(Checkbox::Unchecked),
+            None,
+            None
+        ]
+    );
+    assert!(texts.iter().any(|text| text.starts_with("done")));
+    assert!(texts.iter().any(|text| text.starts_with("todo")));
+    assert_eq!(
+        elements,
+        [
+            "src sql: select 1;\n* from\n",
+            "example: indented\n",
+            "quote",
+            "table",
+            "bold",
+            "strike",
+            "code code",
+            "link https://x.org",
+            "fn-ref n",
+            "fn-def n",
+        ]
+    );
+}
+
+#[test]
+fn html() {
+    let markdown = "<div>\nraw\n</div>\n\n<!-- a\nnote -->\n\nSome <b>html</b>.\n";
+    let (org, diagnostics) = from_markdown_with_diagnostics(markdown);
+
+    let mut writer = Vec::new();
+    org.org(&mut writer).unwrap();
+    assert_eq!(
+        String::from_utf8(writer).unwrap(),
+        "#+BEGIN_EXPORT html\n<div>\nraw\n</div>\n#+END_EXPORT\n\
+         # a\n# note\n\
+         Some @@html:<b>@@html@@html:</b>@@.\n\n"
+    );
+
+    let sources: Vec<_> = diagnostics
+        .iter()
+        .map(|diagnostic| &markdown[diagnostic.span.clone()])
+        .collect();
+    assert_eq!(
+        sources,
+        ["<div>\nraw\n</div>\n", "<!-- a\nnote -->\n", "<b>", "</b>"]
+    );
+}
+
+#[test]
+fn graft() {
+    // converted headlines can be added to another document
+    let markdown = from_markdown("# Imported\n\nSee *this*.\n\n## Tasks\n\n- [x] done\n");
+    let text = String::from("* Inbox\n");
+    let mut org = Org::parse(&text);
+
+    let mut imported = org.graft(&markdown, markdown.headlines().next().unwrap());
+    imported.set_level(2, &mut org).unwrap();
+    let inbox = org.headlines().next().unwrap();
+    inbox.append(imported, &mut org).unwrap();
+
+    let headlines: Vec<_> = org
+        .headlines()
+        .map(|headline| (headline.level(), headline.title(&org).raw.to_string()))
+        .collect();
+    assert_eq!(
+        headlines,
+        [
+            (1, "Inbox".into()),
+            (2, "Imported".into()),
+            (3, "Tasks".into())
+        ]
+    );
+    let kinds: Vec<_> = org
+        .iter()
+        .filter_map(|event| match event {
+            Event::Start(element) => Some(element.kind()),
+            Event::End(_) => None,
+        })
+        .collect();
+    assert!(kinds.contains(&ElementKind::Italic));
+    assert!(org.iter().any(|event| match event {
+        Event::Start(Element::ListItem(item)) => item.checkbox == Some(Checkbox::Checked),
+        _ => false,
+    }));
+
+    let mut writer = Vec::new();
+    org.org(&mut writer).unwrap();
+    assert_eq!(
+        String::from_utf8(writer).unwrap(),
+        "* Inbox\n** Imported\nSee /this/.\n\n*** Tasks\n- [X] done\n\n\n"
+    );
+}
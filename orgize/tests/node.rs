@@ -1,8 +1,10 @@
-use orgize::elements::Title;
+use orgize::elements::{Timestamp, Title};
 use orgize::Org;
 use pretty_assertions::assert_eq;
+#[cfg(feature = "ser")]
 use serde_json::to_string;
 
+#[cfg(feature = "export-html")]
 #[test]
 fn set_content() {
     let mut org = Org::parse(
@@ -25,6 +27,25 @@ section 1
     );
 }
 
+#[cfg(feature = "export-html")]
+#[test]
+fn set_text() {
+    let mut org = Org::parse("* title 1\nsection 1\n");
+    let headline = org.headlines().next().unwrap();
+    headline.set_title_text("TODO a *bold* title", &mut org);
+    headline.set_section_text("#+TITLE: and [[a link]]", &mut org);
+
+    let mut writer = Vec::new();
+    org.html(&mut writer).unwrap();
+    assert_eq!(
+        String::from_utf8(writer)
+            .unwrap()
+            .replace(orgize::escape::ZERO_WIDTH_SPACE, ""),
+        "<main><h1>TODO a *bold* title</h1><section><p>#+TITLE: and [[a link]]</p></section></main>"
+    );
+}
+
+#[cfg(all(feature = "export-html", feature = "ser"))]
 #[test]
 fn insert() {
     let mut org = Org::new();
@@ -66,3 +87,929 @@ fn insert() {
          <h1>title</h1><section><p>section</p></section></main>"
     );
 }
+
+#[test]
+fn source() {
+    let text = "* title 1\nsection 1\n** title 2\n* title 3\n";
+    let mut org = Org::parse(text);
+    let headlines: Vec<_> = org.headlines().collect();
+
+    assert_eq!(
+        headlines[0].source(&org),
+        Some("* title 1\nsection 1\n** title 2\n")
+    );
+    assert_eq!(headlines[1].source(&org), Some("** title 2\n"));
+
+    headlines[1].set_title_content("changed", &mut org);
+    assert_eq!(headlines[0].source(&org), None);
+    assert_eq!(headlines[1].source(&org), None);
+    assert_eq!(headlines[2].source(&org), Some("* title 3\n"));
+
+    headlines[2].detach(&mut org);
+    assert_eq!(headlines[2].source(&org), Some("* title 3\n"));
+
+    let headline = org.new_headline(Title {
+        level: 1,
+        raw: "new".into(),
+        ..Default::default()
+    });
+    assert_eq!(headline.source(&org), None);
+}
+
+#[cfg(feature = "export-html")]
+#[test]
+fn shrink_to_fit() {
+    let mut org = Org::parse("* title 1\nsection 1\n** title 2\n* title 3\n* title 4\n");
+    let headlines: Vec<_> = org.headlines().collect();
+    headlines[1].detach(&mut org);
+    headlines[2].set_section_content(String::from("section 3"), &mut org);
+
+    let mut before = Vec::new();
+    org.html(&mut before).unwrap();
+    let count = org.arena().iter().count();
+
+    org.shrink_to_fit();
+
+    let mut after = Vec::new();
+    org.html(&mut after).unwrap();
+    assert_eq!(before, after);
+    assert!(org.arena().iter().count() < count);
+    assert_eq!(
+        org.headlines().last().unwrap().source(&org),
+        Some("* title 4\n")
+    );
+}
+
+#[test]
+fn entries() {
+    let org = Org::parse(
+        r#"* TODO task :work:
+CLOSED: [2019-10-10 Thu 10:00]
+:PROPERTIES:
+:CREATED:  [2019-10-01 Tue 09:00]
+:LAST_REPEAT: [2019-10-05 Sat]
+:END:
+some text   
+
+more text
+** child
+"#,
+    );
+    let reformatted = Org::parse(
+        r#"* TODO task :work:
+CLOSED: [2019-10-10 Thu 10:00]
+:PROPERTIES:
+:LAST_REPEAT: [2019-10-05 Sat]
+:CREATED:  [2019-10-01 Tue 09:00]
+:END:
+
+some text
+
+
+more text
+
+** child
+"#,
+    );
+    let edited = Org::parse("* TODO task :work:\nsome other text\n");
+
+    let entries: Vec<_> = org.entries().collect();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].title, "task");
+    assert_eq!(entries[0].state, Some("TODO"));
+    assert_eq!(entries[0].tags, ["work"]);
+    assert!(entries[0].closed.is_some());
+    let day = |timestamp: &Option<Timestamp>| match timestamp {
+        Some(Timestamp::Inactive { start, .. }) => Some(start.day),
+        _ => None,
+    };
+    assert_eq!(day(&entries[0].created), Some(1));
+    assert_eq!(day(&entries[0].last_repeat), Some(5));
+    assert_eq!(
+        entries[0].headline.canonical_body(&org),
+        "some text\n\nmore text\n"
+    );
+    assert_eq!(entries[1].headline.canonical_body(&org), "");
+
+    let reformatted: Vec<_> = reformatted.entries().collect();
+    assert_eq!(entries[0].body_hash, reformatted[0].body_hash);
+    assert_eq!(entries[1].body_hash, reformatted[1].body_hash);
+    assert_ne!(
+        entries[0].body_hash,
+        edited.entries().next().unwrap().body_hash
+    );
+}
+
+#[test]
+fn detached() {
+    let mut org = Org::parse("* title 1\n** title 2\n");
+    let headlines: Vec<_> = org.headlines().collect();
+    assert!(!headlines[0].is_detached(&org));
+    assert!(!headlines[1].is_detached(&org));
+
+    headlines[0].detach(&mut org);
+    assert!(headlines[0].is_detached(&org));
+    assert!(!headlines[1].is_detached(&org));
+}
+
+#[test]
+fn ordering() {
+    use orgize::OrderBy;
+
+    let mut org = Org::parse(
+        r#"* parent
+** a
+:PROPERTIES:
+:ORDER: 20
+:END:
+** b
+** c
+:PROPERTIES:
+:order: 10
+:END:
+** d
+:PROPERTIES:
+:ORDER: 20
+:END:
+"#,
+    );
+    let parent = org.headlines().next().unwrap();
+
+    let raw = |org: &Org, order| -> Vec<String> {
+        parent
+            .children_ordered(order, org)
+            .iter()
+            .map(|headline| headline.title(org).raw.to_string())
+            .collect()
+    };
+
+    assert_eq!(raw(&org, OrderBy::Document), ["a", "b", "c", "d"]);
+    assert_eq!(raw(&org, OrderBy::Property("ORDER")), ["c", "a", "d", "b"]);
+
+    parent.assign_order_properties("ORDER", 100, &mut org);
+    assert_eq!(raw(&org, OrderBy::Property("ORDER")), ["c", "a", "d", "b"]);
+
+    let values: Vec<_> = parent
+        .children(&org)
+        .map(|headline| {
+            let properties = &headline.title(&org).properties;
+            properties
+                .get("ORDER")
+                .or_else(|| properties.get("order"))
+                .unwrap()
+                .to_string()
+        })
+        .collect();
+    assert_eq!(values, ["200", "400", "100", "300"]);
+
+    // the text isn't reordered
+    let mut writer = Vec::new();
+    org.org(&mut writer).unwrap();
+    let text = String::from_utf8(writer).unwrap();
+    assert!(text.find("** a").unwrap() < text.find("** c").unwrap());
+    assert!(text.contains("** b\n:PROPERTIES:\n:ORDER: 400\n:END:\n"));
+}
+
+#[test]
+fn priorities() {
+    use orgize::{elements::Priority, OrderBy, ParseConfig, SettingsResolver};
+
+    let text = "#+PRIORITIES: 1 64 32\n* parent\n** [#20] a\n** b\n** TODO [#3] c\n** [#64] d\n** [#007] e\n";
+    let settings_org = Org::parse(text);
+    let config = SettingsResolver::new(&settings_org, &ParseConfig::default()).parse_config();
+    assert_eq!(config.highest_priority, Priority::Num(1));
+    assert_eq!(config.lowest_priority, Priority::Num(64));
+
+    let org = Org::parse_with_config(text, &config);
+    let parent = org.headlines().next().unwrap();
+    let raw: Vec<_> = parent
+        .children_ordered(OrderBy::Priority, &org)
+        .iter()
+        .map(|headline| headline.title(&org).raw.to_string())
+        .collect();
+    assert_eq!(raw, ["c", "a", "d", "b", "[#007] e"]);
+
+    let mut writer = Vec::new();
+    org.org(&mut writer).unwrap();
+    assert_eq!(String::from_utf8(writer).unwrap(), text);
+
+    #[cfg(feature = "ser")]
+    assert_eq!(
+        to_string(&parent.children(&org).next().unwrap().title(&org)).unwrap(),
+        r#"{"level":2,"priority":20,"raw":"a"}"#
+    );
+}
+
+#[test]
+fn timestamps() {
+    use orgize::{AgendaKind, AgendaOptions};
+
+    let mut org = Org::parse(
+        "* a <2020-01-01 Wed>\nSCHEDULED: <2020-01-02 Thu>\n- <2020-01-03 Fri> [2020-01-04 Sat]\n** b\n<2020-01-05 Sun>\n",
+    );
+    let headlines: Vec<_> = org.headlines().collect();
+    let (a, b) = (headlines[0], headlines[1]);
+
+    let agenda = org.agenda(&AgendaOptions::default());
+    let kinds: Vec<_> = agenda.iter().map(|entry| entry.kind).collect();
+    assert_eq!(
+        kinds,
+        [
+            AgendaKind::Scheduled,
+            AgendaKind::Timestamp,
+            AgendaKind::Timestamp,
+            AgendaKind::Timestamp
+        ]
+    );
+    assert_eq!(a.timestamps(&org).count(), 3);
+
+    let agenda = org.agenda(&AgendaOptions {
+        body_timestamps: false,
+    });
+    assert_eq!(agenda.len(), 1);
+
+    a.set_section_content("[2020-01-06 Mon]\n", &mut org);
+    b.set_title_content("b <2020-01-07 Tue> <2020-01-08 Wed>", &mut org);
+    assert_eq!(a.timestamps(&org).count(), 2);
+    assert_eq!(b.timestamps(&org).count(), 3);
+}
+
+#[test]
+fn headline_dates() {
+    use orgize::{AgendaKind, AgendaOptions, ParseConfig};
+
+    let config = ParseConfig {
+        headline_date_formats: vec!["%A, %Y-%m-%d".into(), "%Y-%m-%d %A".into()],
+        ..Default::default()
+    };
+    let org = Org::parse_with_config(
+        "* Monday, 2024-03-04\n** 10:00 standup\n* 2024-03-05 Tuesday\n* March 2024\n",
+        &config,
+    );
+
+    let dates: Vec<_> = org
+        .headlines()
+        .map(|headline| match headline.title(&org).headline_date() {
+            Some(Timestamp::Active { start, .. }) => Some((start.day, start.dayname.to_string())),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        dates,
+        [
+            Some((4, "Mon".to_string())),
+            None,
+            Some((5, "Tue".to_string())),
+            None
+        ]
+    );
+
+    let agenda = org.agenda(&AgendaOptions::default());
+    assert_eq!(agenda.len(), 2);
+    assert_eq!(agenda[0].kind, AgendaKind::HeadlineDate);
+
+    // January of the year 0 falls in the year -1 for the day of the week
+    let config = ParseConfig {
+        headline_date_formats: vec!["%Y-%m-%d".into()],
+        ..Default::default()
+    };
+    let org = Org::parse_with_config(
+        "* 0000-01-01
+",
+        &config,
+    );
+    let headline = org.headlines().next().unwrap();
+    match headline.title(&org).headline_date() {
+        Some(Timestamp::Active { start, .. }) => assert_eq!(start.dayname, "Sat"),
+        date => panic!("unexpected date {:?}", date),
+    }
+}
+
+#[cfg(feature = "query")]
+#[test]
+fn match_entries() {
+    use orgize::{query::Matcher, ParseConfig, SettingsResolver};
+
+    let text = r#"#+TODO: TODO NEXT WAITING | DONE
+#+FILETAGS: :home:
+#+CATEGORY: chores
+* TODO [#A] Garden :garden:
+  SCHEDULED: <2020-03-01 Sun>
+** NEXT Buy paint :errand:urgent:
+** WAITING Sand the planks :errand:
+   :PROPERTIES:
+   :EFFORT:   2
+   :END:
+** DONE Water the plants
+* Work
+  :PROPERTIES:
+  :CATEGORY: office
+  :END:
+** NEXT Reply to emails :work:
+   DEADLINE: <2020-02-01 Sat>
+"#;
+    let settings_org = Org::parse(text);
+    let config = SettingsResolver::new(&settings_org, &ParseConfig::default()).parse_config();
+    let org = Org::parse_with_config(text, &config);
+
+    let titles = |query: &str| -> Vec<String> {
+        org.match_entries(&Matcher::parse(query).unwrap())
+            .iter()
+            .map(|headline| headline.title(&org).raw.to_string())
+            .collect()
+    };
+
+    assert_eq!(titles(r#"+errand-urgent"#), ["Sand the planks"]);
+    assert_eq!(
+        titles(r#"+garden+TODO="NEXT"|work"#),
+        ["Buy paint", "Reply to emails"]
+    );
+    assert_eq!(titles(r#"CATEGORY="office"&LEVEL=2"#), ["Reply to emails"]);
+    assert_eq!(titles(r#"CATEGORY="chores"&PRIORITY="A""#), ["Garden"]);
+    assert_eq!(titles("EFFORT>1"), ["Sand the planks"]);
+    assert_eq!(titles(r#"SCHEDULED>="<2020-03-01>""#), ["Garden"]);
+    assert_eq!(titles(r#"DEADLINE<"<2020-03-01>""#), ["Reply to emails"]);
+    assert_eq!(titles("home-garden"), ["Work", "Reply to emails"]);
+    assert_eq!(
+        titles("garden/NEXT|WAITING"),
+        ["Buy paint", "Sand the planks"]
+    );
+    assert_eq!(titles("garden/!-TODO"), ["Buy paint", "Sand the planks"]);
+
+    let error = Matcher::parse(r#"TODO="NEXT"#).unwrap_err();
+    assert_eq!(error.offset, 5);
+}
+
+#[test]
+fn write_org_minimal() {
+    let mut text = String::from("#+TITLE: large\n\n");
+    for i in 0..50 {
+        text.push_str(&format!(
+            "* Project {}     :work:\n:PROPERTIES:\n:ID:       project-{}\n:END:\n\nSummary of *project* {}.\n\n",
+            i, i, i
+        ));
+        for j in 0..5 {
+            text.push_str(&format!(
+                "** TODO Task {}.{}\nSCHEDULED: <2020-03-0{} Mon>\n:PROPERTIES:\n:EFFORT:   {}:00\n:END:\n- step one\n- step two\n\n*** Note\n#+BEGIN_SRC sh\necho {}\n#+END_SRC\n\n",
+                i, j, j + 1, j, j
+            ));
+        }
+    }
+
+    let mut org = Org::parse(&text);
+
+    let mut writer = Vec::new();
+    org.write_org_minimal(&text, &mut writer).unwrap();
+    assert_eq!(String::from_utf8(writer).unwrap(), text);
+
+    let headline = org
+        .headlines()
+        .find(|headline| headline.title(&org).raw == "Task 31.3")
+        .unwrap();
+    headline
+        .title_mut(&mut org)
+        .properties
+        .insert("EFFORT".into(), "5:00".into());
+
+    let mut writer = Vec::new();
+    org.write_org_minimal(&text, &mut writer).unwrap();
+    let written = String::from_utf8(writer).unwrap();
+
+    let original: Vec<_> = text.lines().collect();
+    let lines: Vec<_> = written.lines().collect();
+    assert_eq!(lines.len(), original.len());
+
+    let changed: Vec<_> = (0..lines.len())
+        .filter(|&i| lines[i] != original[i])
+        .collect();
+    let start = original
+        .iter()
+        .position(|line| *line == "** TODO Task 31.3")
+        .unwrap();
+    assert_eq!(changed, [start + 3]);
+    assert_eq!(lines[start + 3], ":EFFORT: 5:00");
+}
+
+#[test]
+fn instantiate_template() {
+    use orgize::elements::Datetime;
+    use orgize::TemplateOptions;
+    use std::collections::HashMap;
+
+    let text = r#"* Templates
+** TODO Review %{date:%Y-%m-%d} :template:review:
+:PROPERTIES:
+:OWNER: %{owner}
+:END:
+Ask %{owner} about [[https://a.b/%{owner}][%{owner}'s notes]], %{unknown} ~%{owner}~
+<2020-01-01 Wed>
+#+BEGIN_SRC sh
+echo %{owner}
+#+END_SRC
+*** Inbox of %{owner}
+"#;
+    let mut org = Org::parse(text);
+    let template = org.headlines().nth(1).unwrap();
+    let mut writer = Vec::new();
+    org.org(&mut writer).unwrap();
+    let before = String::from_utf8(writer).unwrap();
+
+    let mut vars = HashMap::new();
+    vars.insert("owner", "Bob");
+    let options = TemplateOptions {
+        date: Some(Datetime {
+            year: 2020,
+            month: 3,
+            day: 4,
+            dayname: "Wed".into(),
+            hour: None,
+            minute: None,
+        }),
+        include_code: false,
+    };
+    let copy = template
+        .instantiate_template_with_options(None, &vars, &options, &mut org)
+        .unwrap();
+
+    assert_eq!(copy.level(), 1);
+    assert_eq!(copy.title(&org).tags, ["review"]);
+    assert_eq!(copy.timestamps(&org).count(), 1);
+    let children: Vec<_> = copy.children(&org).collect();
+    assert_eq!(children.len(), 1);
+    assert_eq!(children[0].level(), 2);
+    assert_eq!(children[0].title(&org).raw, "Inbox of Bob");
+    // the template is unchanged
+    assert_eq!(template.title(&org).tags, ["template", "review"]);
+
+    let mut writer = Vec::new();
+    org.org(&mut writer).unwrap();
+    let written = String::from_utf8(writer).unwrap();
+    let title = if cfg!(feature = "chrono") {
+        "Review 2020-03-04"
+    } else {
+        "Review %{date:%Y-%m-%d}"
+    };
+    assert_eq!(
+        &written[before.len()..],
+        format!(
+            r#"* TODO {} :review:
+:PROPERTIES:
+:OWNER: Bob
+:END:
+Ask Bob about [[https://a.b/%{{owner}}][Bob's notes]], %{{unknown}} ~%{{owner}}~
+<2020-01-01 Wed>
+
+#+BEGIN_SRC sh
+echo %{{owner}}
+#+END_SRC
+** Inbox of Bob
+"#,
+            title
+        )
+    );
+
+    let options = TemplateOptions {
+        include_code: true,
+        ..Default::default()
+    };
+    let parent = org.headlines().next().unwrap();
+    let copy = template
+        .instantiate_template_with_options(Some(parent), &vars, &options, &mut org)
+        .unwrap();
+    assert_eq!(copy.level(), 2);
+    let mut writer = Vec::new();
+    org.org(&mut writer).unwrap();
+    let written = String::from_utf8(writer).unwrap();
+    assert!(written.contains("echo Bob\n"));
+    assert!(written.contains("~Bob~"));
+}
+
+#[test]
+fn properties() {
+    let org = Org::parse(
+        r#":PROPERTIES:
+:ID: document
+:END:
+* a
+:PROPERTIES:
+:custom_id: a
+:HEADER-ARGS: :var x=1
+:header-args+: :var y=2
+:END:
+* b
+:PROPERTIES:
+:ID: b
+text
+* c
+"#,
+    );
+    let headlines: Vec<_> = org.headlines().collect();
+
+    assert_eq!(
+        org.document().property("ID", &org).as_deref(),
+        Some("document")
+    );
+    assert_eq!(headlines[0].property("CUSTOM_ID", &org), Some("a"));
+    assert_eq!(
+        headlines[0].property("header-args", &org),
+        Some(":var x=1 :var y=2")
+    );
+    assert_eq!(headlines[0].title(&org).properties.len(), 2);
+
+    // a drawer without `:END:` is left in the section
+    assert_eq!(headlines[1].property("ID", &org), None);
+    assert_eq!(
+        headlines[1].canonical_body(&org),
+        ":PROPERTIES:\n:ID: b\ntext\n"
+    );
+    assert_eq!(headlines[2].title(&org).raw, "c");
+}
+
+#[test]
+fn typed_properties() {
+    let mut org = Org::parse(
+        r#"* Book
+:PROPERTIES:
+:CREATED: [2024-01-01 Mon]
+:PAGES: 37
+:AUTHORS: alice bob
+:END:
+* Article
+:PROPERTIES:
+:CREATED: yesterday
+:PAGES: a few
+:END:
+"#,
+    );
+    let headlines: Vec<_> = org.headlines().collect();
+    let (book, article) = (headlines[0], headlines[1]);
+
+    assert_eq!(book.property_number("pages", &org), Some(37.0));
+    assert_eq!(
+        book.property_list("AUTHORS", " ", &org),
+        Some(vec!["alice", "bob"])
+    );
+    assert!(book.property_timestamp("CREATED", &org).is_some());
+    assert_eq!(article.property_number("PAGES", &org), None);
+    assert_eq!(article.property_timestamp("CREATED", &org), None);
+    assert_eq!(article.property_list("AUTHORS", " ", &org), None);
+
+    #[cfg(feature = "query")]
+    {
+        assert_eq!(match_titles("PAGES>30", &org), ["Book"]);
+        assert_eq!(match_titles(r#"CREATED>="[2023-12-31]""#, &org), ["Book"]);
+    }
+
+    let created = book
+        .property_timestamp("CREATED", &org)
+        .unwrap()
+        .into_owned();
+    article.set_property_timestamp("CREATED", &created, &mut org);
+    article.set_property_number("PAGES", 12.5, &mut org);
+    article.set_property_list("AUTHORS", &["carol", "dave"], ", ", &mut org);
+    assert_eq!(article.property("CREATED", &org), Some("[2024-01-01 Mon]"));
+    assert_eq!(article.property("PAGES", &org), Some("12.5"));
+    assert_eq!(
+        article.property_list("AUTHORS", ",", &org),
+        Some(vec!["carol", "dave"])
+    );
+    #[cfg(feature = "query")]
+    assert_eq!(
+        match_titles(r#"CREATED="<2024-01-01>""#, &org),
+        ["Book", "Article"]
+    );
+}
+
+#[cfg(feature = "query")]
+fn match_titles(query: &str, org: &Org<'_>) -> Vec<String> {
+    use orgize::query::Matcher;
+
+    org.match_entries(&Matcher::parse(query).unwrap())
+        .iter()
+        .map(|headline| headline.title(org).raw.to_string())
+        .collect()
+}
+
+#[cfg(feature = "export-html")]
+#[test]
+fn refile() {
+    use orgize::OrgizeError;
+
+    let mut org =
+        Org::parse("* Inbox\n** Call Bob <2020-01-02 Thu>\n*** Notes\n* Projects\n** Garden\n");
+    let headlines: Vec<_> = org.headlines().collect();
+    let (mut call, garden) = (headlines[1], headlines[4]);
+
+    match call.set_level(3, &mut org) {
+        Err(OrgizeError::Detached { .. }) => (),
+        _ => panic!("headline isn't detached"),
+    }
+
+    call.detach(&mut org);
+    match call.set_level(0, &mut org) {
+        Err(OrgizeError::HeadlineLevel { .. }) => (),
+        _ => panic!("level 0 is invalid"),
+    }
+    match garden.append(call, &mut org) {
+        Err(OrgizeError::HeadlineLevel { .. }) => (),
+        _ => panic!("level 2 doesn't fit under a level 2 headline"),
+    }
+
+    call.set_level(3, &mut org).unwrap();
+    garden.append(call, &mut org).unwrap();
+    assert!(org.validate().is_ok());
+
+    let levels: Vec<_> = org
+        .headlines()
+        .map(|headline| (headline.title(&org).raw.to_string(), headline.level()))
+        .collect();
+    assert_eq!(
+        levels,
+        [
+            ("Inbox".to_string(), 1),
+            ("Projects".to_string(), 1),
+            ("Garden".to_string(), 2),
+            ("Call Bob <2020-01-02 Thu>".to_string(), 3),
+            ("Notes".to_string(), 4)
+        ]
+    );
+    assert_eq!(call.timestamps(&org).count(), 1);
+
+    let mut writer = Vec::new();
+    org.html(&mut writer).unwrap();
+    assert_eq!(
+        String::from_utf8(writer).unwrap(),
+        "<main><h1>Inbox</h1><h1>Projects</h1><h2>Garden</h2><h3>Call Bob \
+         <span class=\"timestamp-wrapper\"><span class=\"timestamp\">&lt;2020-01-02 Thu&gt;</span></span>\
+         </h3><h4>Notes</h4></main>"
+    );
+}
+
+#[test]
+fn promote_demote() {
+    use orgize::OrgizeError;
+
+    fn outline(org: &Org) -> Vec<(String, usize, Option<String>)> {
+        org.headlines()
+            .map(|headline| {
+                let title = headline.title(org);
+                assert_eq!(title.level, headline.level());
+                (
+                    title.raw.to_string(),
+                    title.level,
+                    headline
+                        .parent(org)
+                        .map(|parent| parent.title(org).raw.to_string()),
+                )
+            })
+            .collect()
+    }
+
+    let text = "* A\n** B\n*** C\n** D\n**** E\n*** F\n* G\n";
+    for i in 0..7 {
+        for &demote in &[true, false] {
+            let mut org = Org::parse(text);
+            let mut headline = org.headlines().nth(i).unwrap();
+            let level = headline.level();
+            let result = if demote {
+                headline.demote(&mut org)
+            } else {
+                headline.promote(&mut org)
+            };
+            if !demote && level == 1 {
+                assert!(result.is_err());
+                continue;
+            }
+            result.unwrap();
+            assert!(org.validate().is_ok());
+            assert_eq!(headline.level(), if demote { level + 1 } else { level - 1 });
+
+            // the exported document is parsed back into the same outline
+            let mut writer = Vec::new();
+            org.org(&mut writer).unwrap();
+            let exported = String::from_utf8(writer).unwrap();
+            assert_eq!(
+                outline(&org),
+                outline(&Org::parse(&exported)),
+                "{}",
+                exported
+            );
+        }
+    }
+
+    let mut org = Org::parse(text);
+    let mut d = org.headlines().nth(3).unwrap();
+    match d.demote_within(4, &mut org) {
+        Err(OrgizeError::HeadlineLevel { max: Some(4), .. }) => (),
+        _ => panic!("E would be deeper than 4"),
+    }
+    assert_eq!(d.level(), 2);
+    d.demote_within(5, &mut org).unwrap();
+    assert_eq!(
+        outline(&org)[3..5].to_vec(),
+        [
+            ("D".to_string(), 3, Some("B".to_string())),
+            ("E".to_string(), 5, Some("D".to_string()))
+        ]
+    );
+}
+
+#[test]
+fn promote_demote_odd_levels() {
+    use orgize::OrgizeError;
+
+    let text = "#+STARTUP: odd\n* A\n*** B\n***** C\n*** D\n";
+    let export = |org: &Org| {
+        let mut writer = Vec::new();
+        org.org(&mut writer).unwrap();
+        String::from_utf8(writer).unwrap()
+    };
+
+    let mut org = Org::parse(text);
+    let mut b = org.headlines().nth(1).unwrap();
+    b.promote(&mut org).unwrap();
+    assert_eq!(b.level(), 1);
+    assert_eq!(export(&org), "#+STARTUP: odd\n* A\n* B\n*** C\n*** D\n");
+    assert_eq!(
+        org.headlines()
+            .nth(3)
+            .unwrap()
+            .parent(&org)
+            .unwrap()
+            .level(),
+        1
+    );
+    b.demote(&mut org).unwrap();
+    assert_eq!(b.level(), 3);
+    assert_eq!(
+        export(&org),
+        "#+STARTUP: odd\n* A\n*** B\n***** C\n***** D\n"
+    );
+
+    let mut org = Org::parse(text);
+    let mut d = org.headlines().nth(3).unwrap();
+    match d.demote_within(4, &mut org) {
+        Err(OrgizeError::HeadlineLevel { max: Some(4), .. }) => (),
+        _ => panic!("D would be deeper than 4"),
+    }
+    d.demote_within(5, &mut org).unwrap();
+    assert_eq!(d.level(), 5);
+    assert_eq!(d.parent(&org).unwrap().title(&org).raw, "B");
+}
+
+#[test]
+fn journal_entries() {
+    use orgize::elements::Datetime;
+    use orgize::journal::{append_entry, append_entry_with_diagnostics, JournalLayout};
+
+    let date = |year, month, day| Datetime {
+        year,
+        month,
+        day,
+        dayname: "".into(),
+        hour: None,
+        minute: None,
+    };
+    let org_string = |org: &Org<'_>| {
+        let mut writer = Vec::new();
+        org.org(&mut writer).unwrap();
+        String::from_utf8(writer).unwrap()
+    };
+
+    let layout = JournalLayout::default();
+    let mut org = Org::parse("");
+    let entry = append_entry(&mut org, &date(2024, 3, 4), "Standup", "", &layout).unwrap();
+    append_entry(&mut org, &date(2024, 3, 4), "*Review*", "Done.\n", &layout).unwrap();
+    append_entry(&mut org, &date(2023, 12, 31), "Party", "", &layout).unwrap();
+    append_entry(&mut org, &date(2024, 2, 1), "Plan", "", &layout).unwrap();
+    assert_eq!(entry.level(), 4);
+    assert_eq!(
+        org_string(&org),
+        "* 2023\n** 2023-12 December\n*** 2023-12-31 Sunday\n**** Party\n\
+         * 2024\n** 2024-02 February\n*** 2024-02-01 Thursday\n**** Plan\n\
+         ** 2024-03 March\n*** 2024-03-04 Monday\n**** Standup\n**** *Review*\nDone.\n\n"
+    );
+
+    let layout = JournalLayout {
+        levels: vec!["%G".into(), "Week %V".into()],
+    };
+    let mut org = Org::parse("* 2025\n** Week 02\n* Inbox\n* 2024\n** Week 52\n");
+    append_entry(&mut org, &date(2024, 12, 30), "Holidays", "", &layout).unwrap();
+    let (_, diagnostics) =
+        append_entry_with_diagnostics(&mut org, &date(2024, 1, 10), "Start", "", &layout).unwrap();
+    assert_eq!(
+        org_string(&org),
+        "* 2025\n** Week 01\n*** Holidays\n** Week 02\n* Inbox\n\
+         * 2024\n** Week 02\n*** Start\n** Week 52\n"
+    );
+    assert_eq!(diagnostics, vec![]);
+
+    let (_, diagnostics) =
+        append_entry_with_diagnostics(&mut org, &date(2026, 1, 10), "Later", "", &layout).unwrap();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].span, Some(18..26));
+    assert_eq!(
+        diagnostics[0].message,
+        "headline doesn't match the journal format \"%G\": Inbox"
+    );
+    assert!(org_string(&org).ends_with("** Week 52\n* 2026\n** Week 02\n*** Later\n"));
+}
+
+/// Asserts that `headlines`, `iter` and the org serializer agree on the
+/// order of the headlines, and that events are nested
+fn assert_document_order(org: &Org<'_>) {
+    use orgize::{elements::ElementKind, Element, Event};
+
+    let headlines: Vec<_> = org
+        .headlines()
+        .map(|headline| (headline.level(), headline.title(org).raw.to_string()))
+        .collect();
+
+    let mut titles = Vec::new();
+    let mut stack = Vec::new();
+    for event in org.iter() {
+        match event {
+            Event::Start(element) => {
+                if let Element::Title(title) = element {
+                    titles.push((title.level, title.raw.to_string()));
+                }
+                stack.push(element.kind());
+            }
+            Event::End(element) => assert_eq!(stack.pop(), Some(element.kind())),
+        }
+    }
+    assert_eq!(stack, Vec::<ElementKind>::new());
+    assert_eq!(titles, headlines);
+
+    let keys: Vec<_> = org
+        .headlines()
+        .map(|headline| headline.order_key(org).unwrap())
+        .collect();
+    assert!(keys.windows(2).all(|keys| keys[0] < keys[1]));
+
+    let mut writer = Vec::new();
+    org.org(&mut writer).unwrap();
+    let text = String::from_utf8(writer).unwrap();
+    let reparsed = Org::parse(&text);
+    let serialized: Vec<_> = reparsed
+        .headlines()
+        .map(|headline| (headline.level(), headline.title(&reparsed).raw.to_string()))
+        .collect();
+    assert_eq!(serialized, headlines);
+}
+
+proptest::proptest! {
+    #[test]
+    fn iteration_follows_document_order(
+        operations in proptest::collection::vec((0u8..5, 0usize..32, 0usize..32), 0..24)
+    ) {
+        let mut org = Org::parse(
+            "* a\nA\n** b\n*** c\n** d\nD\n* e\n** f\n- F\n* g\n** h\n*** i\n*** j\n* k\n",
+        );
+        assert_document_order(&org);
+
+        for (i, (operation, source, target)) in operations.into_iter().enumerate() {
+            let headlines: Vec<_> = org.headlines().collect();
+            if headlines.is_empty() {
+                break;
+            }
+            let mut source = headlines[source % headlines.len()];
+            match operation {
+                // remove
+                0 => source.detach(&mut org),
+                // move after, before or under another headline
+                1..=3 => {
+                    source.detach(&mut org);
+                    let headlines: Vec<_> = org.headlines().collect();
+                    if headlines.is_empty() {
+                        break;
+                    }
+                    let target = headlines[target % headlines.len()];
+                    let level = if operation == 3 {
+                        target.level() + 1
+                    } else {
+                        target.level()
+                    };
+                    source.set_level(level, &mut org).unwrap();
+                    // the level may not fit the neighbours, which removes it
+                    let _ = match operation {
+                        1 => target.insert_after(source, &mut org),
+                        2 => target.insert_before(source, &mut org),
+                        _ => target.append(source, &mut org),
+                    };
+                }
+                // insert a new headline
+                _ => {
+                    let headline = org.new_headline(Title {
+                        level: source.level(),
+                        raw: format!("new {}", i).into(),
+                        ..Title::default()
+                    });
+                    headline.set_section_content(format!("Section {}\n", i), &mut org);
+                    let _ = source.insert_after(headline, &mut org);
+                }
+            }
+            assert_document_order(&org);
+        }
+    }
+}
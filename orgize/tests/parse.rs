@@ -1,8 +1,9 @@
-use orgize::Org;
+use orgize::{Org, ParseConfig};
 use pretty_assertions::assert_eq;
 
 macro_rules! test_suite {
     ($name:ident, $content:expr, $expected:expr) => {
+        #[cfg(feature = "export-html")]
         #[test]
         fn $name() {
             let mut writer = Vec::new();
@@ -27,6 +28,12 @@ test_suite!(
     r#"<main><section><p>Visit<a href="http://example.com">link1</a>or<a href="http://example.com">link1</a>.</p></section></main>"#
 );
 
+test_suite!(
+    link_description,
+    r"[[https://example.com][*bold* \[1\] & ~code~]] [[https://example.com]]",
+    r#"<main><section><p><a href="https://example.com"><b>bold</b> [1] &amp; <code>code</code></a> <a href="https://example.com">https://example.com</a></p></section></main>"#
+);
+
 test_suite!(
     section_and_headline,
     r#"* title 1
@@ -67,6 +74,21 @@ test_suite!(
     "<main><section><p><del>delete this</del></p></section></main>"
 );
 
+test_suite!(
+    raw_export,
+    r#"#+HTML: <hr class="fancy"/>
+#+LATEX: \newpage
+#+ATTR_LATEX: :width 5cm
+#+BEGIN_EXPORT html
+<div></div>
+#+END_EXPORT
+#+BEGIN_EXPORT latex
+\clearpage
+#+END_EXPORT
+@@latex:\\@@"#,
+    "<main><section><hr class=\"fancy\"/><div></div>\n<p></p></section></main>"
+);
+
 test_suite!(
     paragraphs,
     r#"* title
@@ -83,3 +105,3607 @@ paragraph 4"#,
      <p>paragraph 3</p><p>paragraph 4</p>\
      </section></main>"
 );
+
+#[cfg(feature = "export-html")]
+#[test]
+fn syntax_compat() {
+    use orgize::SyntaxCompat;
+
+    let content = "#+BEGIN_HTML\n<hr>\n#+END_HTML\n#+RESULT: 42\n";
+
+    let html = |config: &ParseConfig| {
+        let mut writer = Vec::new();
+        Org::parse_with_config(content, config)
+            .html(&mut writer)
+            .unwrap();
+        String::from_utf8(writer).unwrap()
+    };
+
+    assert_eq!(
+        html(&ParseConfig::default()),
+        "<main><section><div class=\"HTML\"><p>&lt;hr&gt;</p></div></section></main>"
+    );
+
+    let config = ParseConfig {
+        syntax_compat: SyntaxCompat::Legacy,
+        ..Default::default()
+    };
+    assert_eq!(html(&config), "<main><section><hr>\n</section></main>");
+
+    let org = Org::parse_with_config(content, &config);
+    let keyword = org.iter().find_map(|event| match event {
+        orgize::Event::Start(orgize::Element::Keyword(keyword)) => Some(keyword),
+        _ => None,
+    });
+    assert_eq!(keyword.map(|k| &*k.key), Some("RESULTS"));
+}
+
+#[cfg(feature = "export-html")]
+#[test]
+fn star_bullets() {
+    let content = "* Head\n- a\n  * b\n  * c\n- d\n\n  * e\n* Head 2\n  * f\n";
+
+    let org = Org::parse(content);
+    assert_eq!(org.headlines().count(), 2);
+
+    let mut writer = Vec::new();
+    org.org(&mut writer).unwrap();
+    let written = String::from_utf8(writer).unwrap();
+    assert_eq!(Org::parse(&written).headlines().count(), 2);
+
+    let config = ParseConfig {
+        allow_star_bullets: false,
+        ..Default::default()
+    };
+    let mut writer = Vec::new();
+    Org::parse_with_config("- a\n  * b\n", &config)
+        .html(&mut writer)
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(writer).unwrap(),
+        "<main><section><ul><li><p>a\n  * b</p></li></ul></section></main>"
+    );
+}
+
+#[cfg(feature = "export-html")]
+#[test]
+fn raw() {
+    let content = include_str!("../fuzz/seeds/remnants.org");
+    let org = Org::parse(content);
+
+    assert_eq!(
+        org.debug_tree(),
+        r##"document (1 child) 0..228
+  headline l1 "title" (3 children) 0..228
+    title (1 child) 0..8
+      text "title" 2..7
+    section (7 children) 8..156
+      paragraph (1 child) 8..17
+        text "some text" 8..17
+      raw ":END:" 18..24
+      paragraph (3 children) 24..88
+        text "more text\nCLOCK: " 24..41
+        timestamp inactive 2019-10-10 10:00 41..63
+        text "--\n:PROPERTIES:\n:ID: 1234" 63..88
+      paragraph (2 children) 90..117
+        text "SCHEDULED: " 90..101
+        timestamp active 2019-10-10 101..117
+      raw "#+end_src" 118..128
+      paragraph (1 child) 128..148
+        text "#+BEGIN_QUOTE\nquoted" 128..148
+      raw "#+END:" 149..156
+    headline l2 "child" [DONE] (2 children) 156..228
+      title (1 child) 156..201
+        text "child" 164..169
+      section (1 child) 201..228
+        raw "DEADLINE: [2019-10-11 Fri]" 201..228
+"##
+    );
+
+    // every line survives writing the document back
+    let mut writer = Vec::new();
+    org.org(&mut writer).unwrap();
+    let written = String::from_utf8(writer).unwrap();
+    for line in content.lines().filter(|line| !line.is_empty()) {
+        assert!(written.contains(line), "{:?} is missing", line);
+    }
+
+    let mut writer = Vec::new();
+    Org::parse("text\n:END:\n<b>").html(&mut writer).unwrap();
+    assert_eq!(
+        String::from_utf8(writer).unwrap(),
+        "<main><section><p>text</p>:END:<p>&lt;b&gt;</p></section></main>"
+    );
+}
+
+#[cfg(feature = "export-html")]
+#[test]
+fn safe_mode() {
+    use orgize::export::DefaultHtmlHandler;
+
+    let mut writer = Vec::new();
+    let mut handler = DefaultHtmlHandler {
+        safe_mode: true,
+        ..Default::default()
+    };
+    Org::parse("#+HTML: <script>\n\n@@html:<b>@@")
+        .html_with_handler(&mut writer, &mut handler)
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(writer).unwrap(),
+        "<main><section>&lt;script&gt;<p>&lt;b&gt;</p></section></main>"
+    );
+}
+
+#[cfg(feature = "export-html")]
+#[test]
+fn progress_cookies() {
+    use orgize::export::DefaultHtmlHandler;
+
+    let text = "* Tasks [3/7]\n- [0/0] empty\n- [40%] half\n\
+                - [184467440737095517/184467440737095517] large\n";
+    let html = |handler: &mut DefaultHtmlHandler| {
+        let mut writer = Vec::new();
+        Org::parse(text)
+            .html_with_handler(&mut writer, handler)
+            .unwrap();
+        String::from_utf8(writer).unwrap()
+    };
+
+    assert_eq!(
+        html(&mut DefaultHtmlHandler::default()),
+        "<main><h1>Tasks <code>[3/7]</code></h1><section><ul>\
+         <li><p><code>[0/0]</code> empty</p></li>\
+         <li><p><code>[40%]</code> half</p></li>\
+         <li><p><code>[184467440737095517/184467440737095517]</code> large</p></li>\
+         </ul></section></main>"
+    );
+    assert_eq!(
+        html(&mut DefaultHtmlHandler {
+            progress_cookies: true,
+            ..Default::default()
+        }),
+        "<main><h1>Tasks <progress max=\"7\" value=\"3\">42%</progress> <code>[3/7]</code></h1>\
+         <section><ul>\
+         <li><p><progress max=\"1\" value=\"0\">0%</progress> <code>[0/0]</code> empty</p></li>\
+         <li><p><progress max=\"100\" value=\"40\">40%</progress> <code>[40%]</code> half</p></li>\
+         <li><p><progress max=\"184467440737095517\" value=\"184467440737095517\">100%</progress> \
+         <code>[184467440737095517/184467440737095517]</code> large</p></li>\
+         </ul></section></main>"
+    );
+}
+
+#[cfg(feature = "export-html")]
+#[test]
+fn checkboxes() {
+    let text = "- [X] done\n- [-] partial\n  + [ ] todo\n- see [ ] reference\n";
+    let org = Org::parse(text);
+
+    let mut writer = Vec::new();
+    org.html(&mut writer).unwrap();
+    assert_eq!(
+        String::from_utf8(writer).unwrap(),
+        "<main><section><ul>\
+         <li><input type=\"checkbox\" checked disabled><p>done</p></li>\
+         <li><input type=\"checkbox\" aria-checked=\"mixed\" disabled><p>partial</p><ul>\
+         <li><input type=\"checkbox\" disabled><p>todo</p></li></ul></li>\
+         <li><p>see [ ] reference</p></li></ul></section></main>"
+    );
+
+    let mut writer = Vec::new();
+    org.org(&mut writer).unwrap();
+    assert_eq!(
+        String::from_utf8(writer).unwrap().trim_end(),
+        text.trim_end()
+    );
+}
+
+#[cfg(feature = "export-html")]
+#[test]
+fn description_lists() {
+    let text = "- *term* :: definition\n- [[file:a.org::x]] :: link\n\n\n\
+                4. [@10] ten\n5. eleven\n\n\n\
+                1. one\n2. [@5] five\n";
+    let org = Org::parse(text);
+
+    let mut writer = Vec::new();
+    org.html(&mut writer).unwrap();
+    assert_eq!(
+        String::from_utf8(writer).unwrap(),
+        "<main><section><dl>\
+         <dt><b>term</b></dt><dd><p>definition</p></dd>\
+         <dt><a href=\"file:a.org::x\">file:a.org::x</a></dt><dd><p>link</p></dd></dl>\
+         <ol start=\"10\"><li value=\"10\"><p>ten</p></li><li><p>eleven</p></li></ol>\
+         <ol><li><p>one</p></li><li value=\"5\"><p>five</p></li></ol></section></main>"
+    );
+
+    let mut writer = Vec::new();
+    org.org(&mut writer).unwrap();
+    let lines = |text: &str| {
+        text.lines()
+            .filter(|line| !line.is_empty())
+            .map(String::from)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(lines(&String::from_utf8(writer).unwrap()), lines(text));
+}
+
+#[test]
+fn list_metadata() {
+    use orgize::elements::{ListBullet, ListKind};
+    use orgize::{Element, Event};
+
+    let org = Org::parse("3) [@3] a\n4) b\n\n\n- t :: d\n\n- u :: e\n\n\n  + x\n");
+    let lists: Vec<_> = org
+        .iter()
+        .filter_map(|event| match event {
+            Event::Start(Element::List(list)) => Some(list),
+            _ => None,
+        })
+        .collect();
+
+    let metadata: Vec<_> = lists
+        .iter()
+        .map(|list| {
+            (
+                list.kind(),
+                list.bullet,
+                list.start,
+                list.indent,
+                list.tight,
+            )
+        })
+        .collect();
+    assert_eq!(
+        metadata,
+        vec![
+            (ListKind::Ordered, ListBullet::Parenthesis, Some(3), 0, true),
+            (ListKind::Descriptive, ListBullet::Hyphen, None, 0, false),
+            (ListKind::Unordered, ListBullet::Plus, None, 2, true),
+        ]
+    );
+    #[cfg(feature = "ser")]
+    assert_eq!(
+        serde_json::to_string(lists[0]).unwrap(),
+        r#"{"indent":0,"ordered":true,"bullet":"parenthesis","tight":true,"description":false,"start":3}"#
+    );
+}
+
+#[cfg(feature = "export-html")]
+#[test]
+fn margin_notes() {
+    use orgize::export::{DefaultHtmlHandler, HtmlExportOptions};
+
+    let org = Org::parse(
+        "- item\n  # TODO(review): reword\n- <b>\n\n\
+         #+CAPTION: Results\n# TODO(review): check the numbers\n#   again\n| 1 |\n\n\
+         #+BEGIN_COMMENT\nWhy?\n#+END_COMMENT\n",
+    );
+    assert_eq!(org.tables().next().unwrap().number, Some(1));
+
+    let analysis = org.analyze_for_html(&HtmlExportOptions::default());
+    let html = |margin_notes| {
+        let mut handler = DefaultHtmlHandler {
+            margin_notes,
+            ..Default::default()
+        };
+        let mut writer = Vec::new();
+        org.html_with_analysis(&mut writer, &mut handler, &analysis)
+            .unwrap();
+        String::from_utf8(writer).unwrap()
+    };
+
+    assert_eq!(
+        html(true),
+        "<main><section><ul>\
+         <li><p>item</p><aside class=\"margin-note\">TODO(review): reword</aside></li>\
+         <li><p>&lt;b&gt;</p></li></ul>\
+         <aside class=\"margin-note\">TODO(review): check the numbers\n  again</aside>\
+         <table><caption>Table 1: Results</caption><tbody><tr><td>1</td></tr></tbody></table>\
+         <aside class=\"margin-note\">Why?</aside></section></main>"
+    );
+    assert_eq!(
+        html(false),
+        "<main><section><ul><li><p>item</p></li><li><p>&lt;b&gt;</p></li></ul>\
+         <table><caption>Table 1: Results</caption>\
+         <tbody><tr><td>1</td></tr></tbody></table></section></main>"
+    );
+
+    let mut writer = Vec::new();
+    org.org(&mut writer).unwrap();
+    assert!(String::from_utf8(writer)
+        .unwrap()
+        .contains("# TODO(review): check the numbers\n#   again\n"));
+}
+
+#[cfg(feature = "export-html")]
+#[test]
+fn latex_fragments() {
+    use orgize::elements::LatexKind;
+    use orgize::{Element, Event};
+
+    let input = "Let $x^2$ and \\(a < b\\), costs $5 or $10,\n\
+                 \\[ E = mc^2 \\] and $$a_1$$ in [[#a][\\[1\\]]].\n\n\
+                 \\begin{align*}\na &= b\n\\end{align*}\n";
+    let org = Org::parse(input);
+
+    let fragments: Vec<_> = org
+        .iter()
+        .filter_map(|event| match event {
+            Event::Start(Element::LatexFragment(fragment)) => {
+                Some((fragment.kind, &*fragment.value))
+            }
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        fragments,
+        vec![
+            (LatexKind::Dollar, "x^2"),
+            (LatexKind::Parenthesis, "a < b"),
+            (LatexKind::Bracket, " E = mc^2 "),
+            (LatexKind::DoubleDollar, "a_1"),
+            (
+                LatexKind::Environment,
+                "\\begin{align*}\na &= b\n\\end{align*}"
+            ),
+        ]
+    );
+
+    let mut writer = Vec::new();
+    org.html(&mut writer).unwrap();
+    assert_eq!(
+        String::from_utf8(writer).unwrap(),
+        "<main><section><p>Let <span class=\"math\">\\(x^2\\)</span> and \
+         <span class=\"math\">\\(a &lt; b\\)</span>, costs $5 or $10,\n\
+         <span class=\"math\">\\[ E = mc^2 \\]</span> and \
+         <span class=\"math\">\\[a_1\\]</span> in <a href=\"#a\">[1]</a>.</p>\
+         <div class=\"math\">\\begin{align*}\na &amp;= b\n\\end{align*}</div>\
+         </section></main>"
+    );
+
+    let mut writer = Vec::new();
+    org.org(&mut writer).unwrap();
+    assert_eq!(String::from_utf8(writer).unwrap(), input);
+}
+
+#[cfg(all(feature = "export-html", feature = "export-markdown"))]
+#[test]
+fn entities() {
+    let input = "\\alpha\\to\\beta, 5\\nbsp{}km \\frac12 \\alphabet \\foo \\dots{}x\n";
+    let org = Org::parse(input);
+
+    let mut writer = Vec::new();
+    org.html(&mut writer).unwrap();
+    assert_eq!(
+        String::from_utf8(writer).unwrap(),
+        "<main><section><p>&alpha;&rarr;&beta;, 5&nbsp;km &frac12; \\alphabet \\foo &hellip;x\
+         </p></section></main>"
+    );
+
+    let mut writer = Vec::new();
+    org.markdown(&mut writer).unwrap();
+    assert_eq!(
+        String::from_utf8(writer).unwrap(),
+        "α→β, 5\u{a0}km ½ \\\\alphabet \\\\foo …x\n"
+    );
+
+    let mut writer = Vec::new();
+    org.org(&mut writer).unwrap();
+    assert_eq!(String::from_utf8(writer).unwrap(), format!("{}\n", input));
+}
+
+#[cfg(all(feature = "export-html", feature = "export-markdown"))]
+#[test]
+fn line_breaks() {
+    let input = "Roses are red,\\\\\nviolets are blue \\\\  \nand \\\\ this isn't one.\n\n\
+                 | a\\\\ | b |\n\n#+BEGIN_VERSE\nGreat clouds overhead\\\\\nTiny black birds rise\\\\\n\
+                 #+END_VERSE\n";
+    let org = Org::parse(input);
+
+    let mut writer = Vec::new();
+    org.html(&mut writer).unwrap();
+    assert_eq!(
+        String::from_utf8(writer).unwrap(),
+        "<main><section><p>Roses are red,<br>\nviolets are blue <br>\nand \\\\ this isn&#39;t one.</p>\
+         <table><tbody><tr><td>a<br></td><td>b</td></tr></tbody></table><p class=\"verse\">\
+         <p>Great clouds overhead<br>\nTiny black birds rise<br></p></p></section></main>"
+    );
+
+    let mut writer = Vec::new();
+    org.markdown(&mut writer).unwrap();
+    assert!(String::from_utf8(writer)
+        .unwrap()
+        .starts_with("Roses are red,\\\nviolets are blue \\\nand"));
+}
+
+#[cfg(feature = "export-html")]
+#[test]
+fn macro_expansion() {
+    let input = "#+TITLE: Notes\n#+AUTHOR: Jane\n#+DATE: <2024-03-04 Mon>\n#+VERSION: 1.2\n\
+                 #+MACRO: pair $2, $1 ($3)\n#+MACRO: loop {{{loop}}}\n\n\
+                 {{{title}}} by {{{author}}}{{{email}}}, v{{{keyword(VERSION)}}}: \
+                 {{{pair(a\\, b,*c*)}}} {{{pair({{{author}}})}}}{{{undefined}}}{{{loop}}}.\n";
+
+    let mut org = Org::parse(input);
+    let undefined = org.try_expand_macros().unwrap_err();
+    assert_eq!(
+        format!("{:?}", undefined.element(&org)),
+        "Macros(Macros { name: \"undefined\", arguments: None })"
+    );
+
+    org.expand_macros();
+    let mut writer = Vec::new();
+    org.html(&mut writer).unwrap();
+    let html = String::from_utf8(writer).unwrap();
+    assert!(
+        html.ends_with("<p>Notes by Jane, v1.2: <b>c</b>, a, b () , Jane ().</p></section></main>")
+    );
+    assert!(org.try_expand_macros().is_ok());
+}
+
+#[test]
+fn empty_macro_expansion() {
+    use orgize::elements::ElementKind;
+    use orgize::{Element, Event};
+
+    // macros expanding to nothing leave no empty containers behind
+    let input =
+        "* {{{title}}}\n{{{undefined}}}\n* b\ntext\n\n*{{{undefined}}}*\n\n- {{{title}}} :: item\n";
+    let mut org = Org::parse(input);
+    org.expand_macros();
+    org.validate().unwrap();
+
+    let mut writer = Vec::new();
+    org.org(&mut writer).unwrap();
+    assert_eq!(
+        String::from_utf8(writer).unwrap(),
+        "* \n* b\ntext\n\n- item\n\n\n"
+    );
+
+    // expansions are parsed with the config of the document
+    let config = ParseConfig {
+        disabled_elements: vec![ElementKind::Bold],
+        ..Default::default()
+    };
+    let mut org = Org::parse_with_config("#+MACRO: b *$1*\n\n{{{b(x)}}}\n", &config);
+    org.expand_macros();
+    assert!(!org
+        .iter()
+        .any(|event| matches!(event, Event::Start(Element::Bold))));
+}
+
+#[cfg(all(feature = "export-html", feature = "export-markdown"))]
+#[test]
+fn convert() {
+    use orgize::export::HtmlExportOptions;
+    use orgize::Severity;
+
+    let src = "* Title\n/italic/\n";
+    let mut writer = Vec::new();
+    Org::parse(src).html(&mut writer).unwrap();
+    assert_eq!(
+        orgize::org_to_html(src, None),
+        Ok(String::from_utf8(writer).unwrap())
+    );
+    assert_eq!(
+        orgize::org_to_markdown(src, None, false),
+        Ok("# Title\n\n*italic*\n".to_string())
+    );
+
+    let src = "<2021-02-30 Tue>\n";
+    let mut options = HtmlExportOptions::default();
+    options.parse_config.strict_severity = Severity::Warning;
+    assert!(orgize::org_to_html(src, Some(&options)).is_ok());
+    assert!(orgize::org_to_markdown(src, Some(&options.parse_config), false).is_ok());
+    options.strict = true;
+    let diagnostics = orgize::org_to_html(src, Some(&options)).unwrap_err();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].severity, Severity::Warning);
+    assert!(orgize::org_to_markdown(src, Some(&options.parse_config), true).is_err());
+
+    // errors only fail strict conversions
+    let src = "#+BEGIN_SRC rust\nfoo\n[fn:1] a\n\n[fn:1] b\n";
+    assert!(orgize::org_to_html(src, None).is_ok());
+    assert!(orgize::org_to_markdown(src, None, false).is_ok());
+    assert!(orgize::org_to_markdown(src, None, true).is_err());
+}
+
+#[test]
+fn org_writer_keeps_every_element() {
+    let src =
+        "#+CALL: double(n=4)\n{{{title}}} {{{poem(red,blue)}}} <<target>> <0000-01-01 Sat>\n\n";
+    let mut writer = Vec::new();
+    Org::parse(src).org(&mut writer).unwrap();
+    assert_eq!(String::from_utf8(writer).unwrap(), src);
+}
+
+#[test]
+fn org_writer_keeps_link_formats() {
+    let src = "See https://en.wikipedia.org/wiki/Org_(software), <https://orgmode.org/a b> \
+               and [[https://orgmode.org]].\n\n";
+    let mut writer = Vec::new();
+    Org::parse(src).org(&mut writer).unwrap();
+    assert_eq!(String::from_utf8(writer).unwrap(), src);
+}
+
+#[cfg(feature = "export-html")]
+#[test]
+fn content_modes() {
+    use orgize::elements::{ContentMode, ElementKind, Timestamp};
+    use orgize::{Element, Event};
+
+    let config = ParseConfig {
+        drawer_modes: vec![("review_data".to_string(), ContentMode::Properties)],
+        block_modes: vec![("AGENDA_NOTE".to_string(), ContentMode::Raw)],
+        ..Default::default()
+    };
+    let src = "* Paper\n:REVIEW_DATA:\n:SCORE: 4.5\n:REVIEWED: [2024-03-01 Fri]\n:REVIEWER: alice\n:END:\n\
+               #+BEGIN_AGENDA_NOTE\nsee *not bold*\n,#+END_AGENDA_NOTE\n#+END_AGENDA_NOTE\n";
+    let org = Org::parse_with_config(src, &config);
+
+    let drawer = org
+        .iter()
+        .find_map(|event| match event {
+            Event::Start(Element::Drawer(drawer)) => Some(drawer),
+            _ => None,
+        })
+        .unwrap();
+    assert_eq!(drawer.mode, ContentMode::Properties);
+    assert_eq!(drawer.property_number("score"), Some(4.5));
+    assert_eq!(drawer.property("Reviewer"), Some("alice"));
+    match drawer.property_timestamp("REVIEWED") {
+        Some(Timestamp::Inactive { start, .. }) => assert_eq!((start.month, start.day), (3, 1)),
+        timestamp => panic!("unexpected timestamp: {:?}", timestamp),
+    }
+    assert_eq!(drawer.property_number("REVIEWER"), None);
+
+    let block = org
+        .iter()
+        .find_map(|event| match event {
+            Event::Start(Element::SpecialBlock(block)) => Some(block),
+            _ => None,
+        })
+        .unwrap();
+    assert_eq!(block.mode, ContentMode::Raw);
+    assert_eq!(
+        block.contents.as_deref(),
+        Some("see *not bold*\n#+END_AGENDA_NOTE\n")
+    );
+
+    // neither element has children, their contents are written back as is
+    let kinds: Vec<_> = org
+        .iter()
+        .filter_map(|event| match event {
+            Event::Start(element) => Some(element.kind()),
+            _ => None,
+        })
+        .collect();
+    assert!(!kinds.contains(&ElementKind::Bold));
+    assert!(!kinds.contains(&ElementKind::Keyword));
+    let mut writer = Vec::new();
+    org.org(&mut writer).unwrap();
+    assert_eq!(String::from_utf8(writer).unwrap(), src);
+
+    let mut writer = Vec::new();
+    org.html(&mut writer).unwrap();
+    assert!(String::from_utf8(writer).unwrap().contains(
+        "<div class=\"AGENDA_NOTE\"><pre>see *not bold*\n#+END_AGENDA_NOTE\n</pre></div>"
+    ));
+}
+
+#[cfg(feature = "export-html")]
+#[test]
+fn sub_superscripts() {
+    use orgize::SubSuperscripts;
+
+    let input = "H_2O, E=mc^2, x^{2n+1}, a_{/i/}, file_name and _underline_.\n";
+    let html = |use_sub_superscripts| {
+        let config = ParseConfig {
+            use_sub_superscripts,
+            ..Default::default()
+        };
+        let org = Org::parse_with_config(input, &config);
+        let mut writer = Vec::new();
+        org.html(&mut writer).unwrap();
+        let mut org_writer = Vec::new();
+        org.org(&mut org_writer).unwrap();
+        assert_eq!(
+            String::from_utf8(org_writer).unwrap(),
+            format!("{}\n", input)
+        );
+        String::from_utf8(writer).unwrap()
+    };
+
+    assert_eq!(
+        html(SubSuperscripts::Braces),
+        "<main><section><p>H_2O, E=mc^2, x<sup>2n+1</sup>, a<sub><i>i</i></sub>, \
+         file_name and <u>underline</u>.</p></section></main>"
+    );
+    assert_eq!(
+        html(SubSuperscripts::Always),
+        "<main><section><p>H<sub>2O</sub>, E=mc<sup>2</sup>, x<sup>2n+1</sup>, \
+         a<sub><i>i</i></sub>, file<sub>name</sub> and <u>underline</u>.</p></section></main>"
+    );
+    assert_eq!(
+        html(SubSuperscripts::Never),
+        "<main><section><p>H_2O, E=mc^2, x^{2n+1}, a_{<i>i</i>}, file_name and \
+         <u>underline</u>.</p></section></main>"
+    );
+}
+
+#[cfg(feature = "export-html")]
+#[test]
+fn disabled_elements() {
+    use orgize::elements::ElementKind;
+
+    let input = "* TODO Task\nSCHEDULED: <2024-03-04 Mon>\n\
+                 *bold* and [[https://example.com][a link]] on <2024-03-05 Tue>\n#+BEGIN_SRC rust\nfn main() {}\n#+END_SRC\n| a | b |\n";
+    let html = |disabled_elements| {
+        let config = ParseConfig {
+            disabled_elements,
+            ..Default::default()
+        };
+        let org = Org::parse_with_config(input, &config);
+        let mut writer = Vec::new();
+        org.html(&mut writer).unwrap();
+        String::from_utf8(writer).unwrap()
+    };
+
+    assert_eq!(
+        html(vec![ElementKind::Bold, ElementKind::Link]),
+        "<main><h1>Task</h1><section><p>*bold* and [[https://example.com][a link]] on \
+         <span class=\"timestamp-wrapper\"><span class=\"timestamp\">&lt;2024-03-05 Tue&gt;\
+         </span></span></p><div class=\"org-src-container\"><pre class=\"src src-rust\">\
+         fn main() {}\n</pre></div><table><tbody><tr><td>a</td><td>b</td></tr></tbody></table></section></main>"
+    );
+    assert_eq!(
+        html(vec![
+            ElementKind::Timestamp,
+            ElementKind::SourceBlock,
+            ElementKind::Table
+        ]),
+        "<main><h1>Task</h1><section>SCHEDULED: &lt;2024-03-04 Mon&gt;<p><b>bold</b> and \
+         <a href=\"https://example.com\">a link</a> on &lt;2024-03-05 Tue&gt;\n\
+         #+BEGIN_SRC rust\nfn main() {}</p>#+END_SRC<p>| a | b |</p></section></main>"
+    );
+}
+
+#[cfg(feature = "export-html")]
+#[test]
+fn inline_images() {
+    use orgize::export::{DefaultHtmlHandler, HtmlExportOptions};
+    use std::path::Path;
+    use std::sync::Arc;
+
+    let org = Org::parse(
+        "#+TITLE: Images\n[[file:a.png]] [[./images/b.png]] [[big.png]] \
+         [[https://example.com/c.png]] [[file:a.png][described]]\n",
+    );
+    let options = HtmlExportOptions {
+        image_resolver: Some(Arc::new(|path: &Path| match path.to_str()? {
+            "a.png" => Some(("image/png".to_string(), b"M".to_vec())),
+            "./images/b.png" => Some(("image/png".to_string(), b"Ma".to_vec())),
+            "big.png" => Some(("image/png".to_string(), vec![0; 10])),
+            _ => None,
+        })),
+        max_inline_image_size: Some(4),
+        #[cfg(feature = "css")]
+        embed_css: true,
+        ..Default::default()
+    };
+    let analysis = org.analyze_for_html(&options);
+
+    let mut writer = Vec::new();
+    org.html_page_with_analysis(&mut writer, &mut DefaultHtmlHandler::default(), &analysis)
+        .unwrap();
+    let html = String::from_utf8(writer).unwrap();
+
+    assert!(html.contains(
+        "<p><img src=\"data:image/png;base64,TQ==\" alt=\"a.png\"> \
+         <img src=\"data:image/png;base64,TWE=\" alt=\"./images/b.png\"> \
+         <img src=\"big.png\" alt=\"big.png\"> \
+         <img src=\"https://example.com/c.png\" alt=\"https://example.com/c.png\"> \
+         <a href=\"file:a.png\">described</a></p>"
+    ));
+    assert_eq!(
+        analysis
+            .diagnostics()
+            .iter()
+            .map(|diagnostic| diagnostic.message.as_str())
+            .collect::<Vec<_>>(),
+        ["image big.png isn't inlined: it's larger than 4 bytes"]
+    );
+    #[cfg(feature = "css")]
+    assert!(html.contains("<style>\n.title {"));
+}
+
+#[cfg(feature = "export-html")]
+#[test]
+fn image_links() {
+    use orgize::export::DefaultHtmlHandler;
+
+    let org = Org::parse(
+        "#+CAPTION: A cat\n#+ATTR_HTML: :alt A \"cat\" :width 300\n#+ATTR_HTML: :width 200 :on<x> 1\n\
+         [[file:images/my cat.png]]\n\n\
+         [[./cat.avif]] [[./cat.PNG][a cat]] [[./notes.txt]]\n",
+    );
+
+    let mut writer = Vec::new();
+    org.html(&mut writer).unwrap();
+    assert_eq!(
+        String::from_utf8(writer).unwrap(),
+        "<main><section><figure><figcaption>Figure 1: A cat</figcaption>\
+         <p><img src=\"images/my%20cat.png\" alt=\"A &quot;cat&quot;\" width=\"200\"></p></figure>\
+         <p><a href=\"./cat.avif\">./cat.avif</a> <a href=\"./cat.PNG\">a cat</a> \
+         <a href=\"./notes.txt\">./notes.txt</a></p></section></main>"
+    );
+
+    let mut handler = DefaultHtmlHandler {
+        image_extensions: Some(vec!["AVIF".into()]),
+        ..Default::default()
+    };
+    let mut writer = Vec::new();
+    Org::parse("[[./cat.avif]] [[./cat.png]]")
+        .html_with_handler(&mut writer, &mut handler)
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(writer).unwrap(),
+        "<main><section><p><img src=\"./cat.avif\" alt=\"./cat.avif\"> \
+         <a href=\"./cat.png\">./cat.png</a></p></section></main>"
+    );
+}
+
+#[cfg(feature = "export-html")]
+#[test]
+fn link_resolution() {
+    use orgize::elements::Link;
+    use orgize::export::HtmlHandler;
+    use std::borrow::Cow;
+    use std::io::Error;
+
+    struct WikiHandler;
+
+    impl HtmlHandler<Error> for WikiHandler {
+        fn resolve_link<'a>(&self, link: &'a Link) -> Option<Cow<'a, str>> {
+            if link.path.starts_with("roam:") {
+                None
+            } else if let Some(path) = link.path.strip_prefix("file:") {
+                Some(format!("/static/{}", path).into())
+            } else if link.path.contains(':') || link.path.starts_with(&['#', '.'][..]) {
+                Some(Cow::Borrowed(&link.path))
+            } else {
+                Some(format!("/wiki/{}", link.path.replace(' ', "-")).into())
+            }
+        }
+    }
+
+    let org = Org::parse(
+        "[[My Page]] [[roam:Draft][draft *notes*]] [[roam:Bare]] [[file:cat.png]] \
+         [[https://x.org][x]] [[fig]]\n\n#+NAME: fig\n#+CAPTION: Cat\n[[./cat.png]]\n",
+    );
+    let mut writer = Vec::new();
+    org.html_with_handler(&mut writer, &mut WikiHandler)
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(writer).unwrap(),
+        "<main><section><p><a href=\"/wiki/My-Page\">My Page</a> draft <b>notes</b> roam:Bare \
+         <img src=\"/static/cat.png\" alt=\"cat.png\"> <a href=\"https://x.org\">x</a> \
+         <a href=\"#fig\">Figure 1</a></p>\
+         <figure><a id=\"fig\"></a><figcaption>Figure 1: Cat</figcaption>\
+         <p><img src=\"./cat.png\" alt=\"./cat.png\"></p></figure></section></main>"
+    );
+}
+
+#[cfg(feature = "export-html")]
+#[test]
+fn plain_links() {
+    let org = Org::parse(
+        "See https://orgmode.org. Or (mailto:me@x.org), <ftp://x.org/a b> and \
+         https://en.wikipedia.org/wiki/Org_(software)!\n\n\
+         Not xhttps://x.org, <gemini://x.org>, =https://x.org= or [[https://x.org][https://y.org]].\n",
+    );
+    let mut writer = Vec::new();
+    org.html(&mut writer).unwrap();
+    assert_eq!(
+        String::from_utf8(writer).unwrap(),
+        "<main><section><p>See <a href=\"https://orgmode.org\">https://orgmode.org</a>. \
+         Or (<a href=\"mailto:me@x.org\">mailto:me@x.org</a>), \
+         <a href=\"ftp://x.org/a%20b\">ftp://x.org/a b</a> and \
+         <a href=\"https://en.wikipedia.org/wiki/Org_(software)\">\
+         https://en.wikipedia.org/wiki/Org_(software)</a>!</p>\
+         <p>Not xhttps://x.org, &lt;gemini://x.org&gt;, <code>https://x.org</code> or \
+         <a href=\"https://x.org\">https://y.org</a>.</p></section></main>"
+    );
+
+    let config = ParseConfig {
+        link_protocols: Vec::new(),
+        ..Default::default()
+    };
+    let org = Org::parse_with_config("See https://orgmode.org.", &config);
+    assert!(!org.iter().any(|event| match event {
+        orgize::Event::Start(orgize::Element::Link(_)) => true,
+        _ => false,
+    }));
+}
+
+#[cfg(feature = "export-html")]
+#[test]
+fn radio_targets() {
+    let mut org = Org::parse(
+        "* Using Org mode\nSee [[https://orgmode.org][Org mode]] and =org mode=.\n\
+         * <<<Org mode>>> and <<<org>>>\nOrg, org-mode and org modes.\n",
+    );
+
+    let mut writer = Vec::new();
+    org.org(&mut writer).unwrap();
+    assert!(String::from_utf8(writer)
+        .unwrap()
+        .contains("* <<<Org mode>>> and <<<org>>>\n"));
+
+    assert_eq!(org.resolve_radio_targets(), 4);
+    let mut writer = Vec::new();
+    org.html(&mut writer).unwrap();
+    assert_eq!(
+        String::from_utf8(writer).unwrap(),
+        "<main><h1>Using <a href=\"#radio-org-mode\">Org mode</a></h1>\
+         <section><p>See <a href=\"https://orgmode.org\">Org mode</a> and <code>org mode</code>.</p></section>\
+         <h1><a id=\"radio-org-mode\">Org mode</a> and <a id=\"radio-org\">org</a></h1>\
+         <section><p><a href=\"#radio-org\">Org</a>, <a href=\"#radio-org\">org</a>-mode \
+         and <a href=\"#radio-org\">org</a> modes.</p></section></main>"
+    );
+}
+
+#[cfg(feature = "export-html")]
+#[test]
+fn html_escaping() {
+    let html = |text: &str| {
+        let mut writer = Vec::new();
+        Org::parse(text).html(&mut writer).unwrap();
+        String::from_utf8(writer).unwrap()
+    };
+
+    assert_eq!(
+        html("<script>alert(1)</script> & [[https://a.b/?q=\"x\"][<i>]]"),
+        "<main><section><p>&lt;script&gt;alert(1)&lt;/script&gt; &amp; \
+         <a href=\"https://a.b/?q=&quot;x&quot;\">&lt;i&gt;</a></p></section></main>"
+    );
+    assert_eq!(
+        html("~a<b~ =&amp;= src_c\"><{x<y} [1/2] <2020-01-01 <a&b>"),
+        "<main><section><p><code>a&lt;b</code> <code>&amp;amp;</code> \
+         <code class=\"src src-c&quot;&gt;&lt;\">x&lt;y</code> <code>[1/2]</code> \
+         <span class=\"timestamp-wrapper\"><span class=\"timestamp\">\
+         &lt;2020-01-01 &lt;a&amp;b&gt;</span></span></p></section></main>"
+    );
+    assert_eq!(
+        html("#+BEGIN_SRC c\"\nif (a < b && c) {}\n#+END_SRC\n"),
+        "<main><section><div class=\"org-src-container\">\
+         <pre class=\"src src-c&quot;\">if (a &lt; b &amp;&amp; c) {}\n</pre></div></section></main>"
+    );
+}
+
+#[cfg(feature = "export-html")]
+#[test]
+fn special_blocks() {
+    use orgize::export::{DefaultHtmlHandler, ExportOptions, HtmlBackend, SpecialBlockPolicy};
+
+    let org = Org::parse(
+        "#+BEGIN_sidebar\n*Careful*\n#+END_sidebar\n#+BEGIN_draft\nhidden\n#+END_draft\n\
+         #+BEGIN_chart\n<svg>\n\n</svg>\n#+END_chart\n",
+    );
+    let mut handler = DefaultHtmlHandler {
+        special_blocks: vec![
+            ("DRAFT".into(), SpecialBlockPolicy::Drop),
+            ("chart".into(), SpecialBlockPolicy::Raw),
+        ],
+        ..Default::default()
+    };
+    let expected = "<main><section><div class=\"sidebar\"><p><b>Careful</b></p></div>\
+                    <svg>\n\n</svg></section></main>";
+
+    let mut writer = Vec::new();
+    org.html_with_handler(&mut writer, &mut handler).unwrap();
+    assert_eq!(String::from_utf8(writer).unwrap(), expected);
+
+    let mut writer = Vec::new();
+    org.export_with_handler(
+        &mut writer,
+        &mut HtmlBackend(handler.clone()),
+        &ExportOptions::default(),
+    )
+    .unwrap();
+    assert_eq!(String::from_utf8(writer).unwrap(), expected);
+
+    handler.unknown_blocks = SpecialBlockPolicy::Drop;
+    handler.safe_mode = true;
+    let mut writer = Vec::new();
+    org.html_with_handler(&mut writer, &mut handler).unwrap();
+    assert_eq!(
+        String::from_utf8(writer).unwrap(),
+        "<main><section>&lt;svg&gt;\n\n&lt;/svg&gt;</section></main>"
+    );
+}
+
+#[test]
+fn parse_lossy() {
+    let org = Org::parse_lossy(b"\xEF\xBB\xBF* caf\xE9\n");
+    let title = org.headlines().next().unwrap().title(&org);
+    assert_eq!(title.raw, "caf\u{FFFD}");
+    assert_eq!(org.headlines().next().unwrap().source(&org), None);
+
+    let org = Org::parse_lossy(b"* title\n");
+    assert_eq!(
+        org.headlines().next().unwrap().source(&org),
+        Some("* title\n")
+    );
+}
+
+#[cfg(feature = "export-html")]
+#[test]
+fn headline_data() {
+    use orgize::export::DefaultHtmlHandler;
+
+    let org = Org::parse(
+        r#"* TODO [#A] title :a:b:
+SCHEDULED: <2019-10-10 Thu 10:00> DEADLINE: <2019-10-11 Fri>
+:PROPERTIES:
+:ID: "quoted" & <tagged>
+:HTML_CLASS: fancy
+:END:
+** child
+"#,
+    );
+
+    let mut writer = Vec::new();
+    org.html(&mut writer).unwrap();
+    assert!(String::from_utf8(writer)
+        .unwrap()
+        .starts_with("<main><h1>title</h1>"));
+
+    let mut writer = Vec::new();
+    let mut handler = DefaultHtmlHandler {
+        headline_data: [
+            "id",
+            "TODO",
+            "priority",
+            "tags",
+            "scheduled",
+            "deadline",
+            "closed",
+        ]
+        .iter()
+        .map(|key| key.to_string())
+        .collect(),
+        headline_class: true,
+        ..Default::default()
+    };
+    org.html_with_handler(&mut writer, &mut handler).unwrap();
+    assert_eq!(
+        String::from_utf8(writer).unwrap(),
+        "<main><h1 class=\"fancy\" data-id=\"&quot;quoted&quot; &amp; &lt;tagged&gt;\" \
+         data-todo=\"TODO\" data-priority=\"A\" data-tags=\"a b\" \
+         data-scheduled=\"2019-10-10T10:00\" data-deadline=\"2019-10-11\">title</h1>\
+         <h2>child</h2></main>"
+    );
+}
+
+#[cfg(feature = "export-html")]
+#[test]
+fn captions() {
+    use orgize::Element;
+
+    let org = Org::parse(
+        r#"#+CAPTION[Short]: The *important* table
+#+NAME: tab:first
+#+ATTR_HTML: :border 1
+| a | b |
+
+| no caption |
+
+#+NAME: fig:cat
+#+CAPTION: A cat
+[[./cat.png]]
+
+#+CAPTION: Not a figure
+[[./cat.png][desc]]
+"#,
+    );
+
+    let tables: Vec<_> = org.tables().collect();
+    assert_eq!(tables.len(), 2);
+    assert_eq!(tables[0].name, Some("tab:first"));
+    assert_eq!(tables[0].short_caption, Some("Short"));
+    assert_eq!(tables[0].number, Some(1));
+    assert_eq!(tables[1].caption, None);
+    assert_eq!(tables[1].number, None);
+
+    let caption = tables[0].caption.unwrap();
+    let children: Vec<_> = caption
+        .children(org.arena())
+        .map(|child| org.arena()[child].get())
+        .collect();
+    assert_eq!(children.len(), 3);
+    if let Element::Bold = children[1] {
+    } else {
+        panic!("{:?} is not bold", children[1]);
+    }
+
+    let figures: Vec<_> = org.figures().collect();
+    assert_eq!(figures.len(), 1);
+    assert_eq!(figures[0].name, Some("fig:cat"));
+    assert_eq!(figures[0].number, Some(1));
+
+    let mut writer = Vec::new();
+    Org::parse("#+CAPTION: The *important* table\n| a |\n")
+        .html(&mut writer)
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(writer).unwrap(),
+        "<main><section><table><caption>Table 1: The <b>important</b> table</caption><tbody><tr><td>a</td></tr></tbody></table></section></main>"
+    );
+
+    let mut writer = Vec::new();
+    org.org(&mut writer).unwrap();
+    let written = String::from_utf8(writer).unwrap();
+    assert!(written.starts_with("#+CAPTION[Short]: The *important* table\n#+NAME: tab:first\n"));
+    assert_eq!(Org::parse(&written).tables().count(), 2);
+}
+
+#[cfg(feature = "export-html")]
+#[test]
+fn toc_lists() {
+    let org = Org::parse(
+        r#"#+TOC: tables
+#+TOC: listings
+
+#+CAPTION: First
+| a |
+
+#+NAME: tab:named
+#+CAPTION: Second
+| b |
+
+#+CAPTION: Hello
+#+BEGIN_SRC rust
+fn main() {}
+#+END_SRC
+"#,
+    );
+
+    let mut writer = Vec::new();
+    org.html(&mut writer).unwrap();
+    assert_eq!(
+        String::from_utf8(writer).unwrap(),
+        "<main><section>\
+         <ul><li><a href=\"#table-1\">Table 1: First</a></li>\
+         <li><a href=\"#tab:named\">Table 2: Second</a></li></ul>\
+         <ul><li><a href=\"#listing-1\">Listing 1: Hello</a></li></ul>\
+         <a id=\"table-1\"></a><table><caption>Table 1: First</caption>\
+         <tbody><tr><td>a</td></tr></tbody></table>\
+         <a id=\"tab:named\"></a><table><caption>Table 2: Second</caption>\
+         <tbody><tr><td>b</td></tr></tbody></table>\
+         <figure><a id=\"listing-1\"></a><figcaption>Listing 1: Hello</figcaption>\
+         <div class=\"org-src-container\"><pre class=\"src src-rust\">fn main() {}\n</pre></div>\
+         </figure></section></main>"
+    );
+
+    let org = Org::parse(
+        r#"* One
+#+TOC: headlines 1 local
+** Two
+:PROPERTIES:
+:CUSTOM_ID: two
+:END:
+*** Three
+** Four
+* Five
+"#,
+    );
+
+    let mut writer = Vec::new();
+    org.html(&mut writer).unwrap();
+    let html = String::from_utf8(writer).unwrap();
+    assert!(html.contains(
+        "<section><ul><li><a href=\"#two\">Two</a></li>\
+         <li><a href=\"#headline-4\">Four</a></li></ul></section>"
+    ));
+    assert!(html.contains("<a id=\"two\"></a><h2>Two</h2>"));
+}
+
+#[cfg(feature = "export-html")]
+#[test]
+fn cross_references() {
+    use orgize::export::{DefaultHtmlHandler, ExportStrings, HtmlExportOptions};
+
+    let org = Org::parse(
+        r#"* One
+** Two
+:PROPERTIES:
+:CUSTOM_ID: two
+:END:
+See [[tab:data]], [[#two]], [[*One]] and [[tab:data][the data]].
+
+#+NAME: tab:data
+#+CAPTION: Data
+| a |
+"#,
+    );
+
+    let mut writer = Vec::new();
+    org.html(&mut writer).unwrap();
+    assert_eq!(
+        String::from_utf8(writer).unwrap(),
+        "<main><a id=\"headline-1\"></a><h1>One</h1><a id=\"two\"></a><h2>Two</h2><section>\
+         <p>See <a href=\"#tab:data\">Table 1</a>, <a href=\"#two\">Section 1.1</a>, \
+         <a href=\"#headline-1\">Section 1</a> and <a href=\"tab:data\">the data</a>.</p>\
+         <a id=\"tab:data\"></a><table><caption>Table 1: Data</caption>\
+         <tbody><tr><td>a</td></tr></tbody></table></section></main>"
+    );
+
+    let org = Org::parse("#+CAPTION: Données\n| a |\n[[tab]]");
+    let analysis = org.analyze_for_html(&HtmlExportOptions {
+        strings: Some(ExportStrings {
+            table: "Tableau %d".into(),
+            ..ExportStrings::default()
+        }),
+        ..HtmlExportOptions::default()
+    });
+    let mut writer = Vec::new();
+    org.html_with_analysis(&mut writer, &mut DefaultHtmlHandler::default(), &analysis)
+        .unwrap();
+    assert!(String::from_utf8(writer)
+        .unwrap()
+        .contains("<caption>Tableau 1: Données</caption>"));
+}
+
+#[cfg(feature = "export-html")]
+#[test]
+fn export_strings() {
+    use orgize::export::{ExportStrings, HtmlExportOptions};
+
+    let text = r#"#+TITLE: Sink
+* One
+See [[tab:data]], [[fig]], [[src]] and [[*One]][fn:1].
+
+#+NAME: tab:data
+#+CAPTION: Data
+| a |
+
+#+NAME: fig
+#+CAPTION: Picture
+[[./a.png]]
+
+#+NAME: src
+#+CAPTION: Code
+#+BEGIN_SRC rust
+fn main() {}
+#+END_SRC
+
+#+BEGIN_NOTE
+a
+#+END_NOTE
+#+BEGIN_TIP
+b
+#+END_TIP
+#+BEGIN_IMPORTANT
+c
+#+END_IMPORTANT
+#+BEGIN_WARNING
+d
+#+END_WARNING
+#+BEGIN_CAUTION
+e
+#+END_CAUTION
+
+[fn:1] f
+"#;
+    let html = |org: &Org, strings| {
+        let mut writer = Vec::new();
+        org.html_page(
+            &mut writer,
+            &HtmlExportOptions {
+                strings,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        String::from_utf8(writer).unwrap()
+    };
+    const ENGLISH: [&str; 10] = [
+        "Table",
+        "Figure",
+        "Listing",
+        "Section",
+        "Note",
+        "Tip",
+        "Important",
+        "Warning",
+        "Caution",
+        "Footnotes",
+    ];
+
+    let org = Org::parse(text);
+    let english = html(&org, None);
+    for literal in ENGLISH.iter() {
+        assert!(english.contains(literal), "{}", literal);
+    }
+
+    let sentinels = ExportStrings {
+        table: "<t%d>".into(),
+        figure: "<f%d>".into(),
+        listing: "<l%d>".into(),
+        section: "<s%d>".into(),
+        note: "<n>".into(),
+        tip: "<ti>".into(),
+        important: "<i>".into(),
+        warning: "<w>".into(),
+        caution: "<c>".into(),
+        footnotes: "<fn>".into(),
+    };
+    let sentinel = html(&org, Some(sentinels));
+    for literal in ENGLISH.iter() {
+        assert!(!sentinel.contains(literal), "{}", literal);
+    }
+    for expected in &[
+        "&lt;t1&gt;: Data",
+        "&lt;f1&gt;: Picture",
+        "&lt;l1&gt;: Code",
+        ">&lt;s1&gt;</a>",
+        "&lt;n&gt;",
+        "&lt;ti&gt;",
+        "&lt;i&gt;",
+        "&lt;w&gt;",
+        "&lt;c&gt;",
+        "&lt;fn&gt;",
+    ] {
+        assert!(sentinel.contains(expected), "{}", expected);
+    }
+
+    let text = format!("#+LANGUAGE: ru\n{}", text);
+    let org = Org::parse(&text);
+    let russian = html(&org, None);
+    assert!(russian.contains("<html lang=\"ru\">"));
+    assert!(russian.contains("Таблица 1: Data"));
+    assert!(russian.contains("Осторожно"));
+    assert!(russian.contains("Сноски"));
+    for literal in ENGLISH.iter() {
+        assert!(!russian.contains(literal), "{}", literal);
+    }
+}
+
+#[test]
+fn whitespace() {
+    use orgize::{Element, Event};
+
+    // the string values of the elements parsed from a text, in order
+    fn values(text: &str) -> Vec<String> {
+        let org = Org::parse(text);
+        org.iter()
+            .filter_map(|event| match event {
+                Event::Start(Element::Keyword(keyword)) => {
+                    Some(format!("keyword {:?}", keyword.value))
+                }
+                Event::Start(Element::BabelCall(call)) => Some(format!("call {:?}", call.value)),
+                Event::Start(Element::Title(title)) => Some(format!(
+                    "title {:?} {:?} {:?}",
+                    title.raw, title.tags, title.properties
+                )),
+                Event::Start(Element::ListItem(item)) => Some(format!("item {:?}", item.tag)),
+                Event::Start(Element::QuoteBlock(block)) => {
+                    Some(format!("quote {:?}", block.parameters))
+                }
+                Event::Start(Element::SourceBlock(block)) => Some(format!(
+                    "src {:?} {:?} {:?}",
+                    block.language, block.arguments, block.contents
+                )),
+                Event::Start(Element::Comment { value }) => Some(format!("comment {:?}", value)),
+                Event::Start(Element::FixedWidth { value }) => Some(format!("fixed {:?}", value)),
+                Event::Start(Element::Text { value }) => Some(format!("text {:?}", value)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    let cases: &[(&str, &[&str])] = &[
+        ("#+KEY: \tvalue \t\n\n\n", &[r#"keyword "value""#]),
+        ("#+CALL: f() \t\n", &[r#"call "f()""#]),
+        (
+            "* Head \t:a:b: \t\n",
+            &[r#"title "Head" ["a", "b"] {}"#, r#"text "Head""#],
+        ),
+        (
+            "* Head\t:a:\n:PROPERTIES:\n:KEY: \t value \t\n:END:\n",
+            &[r#"title "Head" ["a"] {"KEY": "value"}"#, r#"text "Head""#],
+        ),
+        ("line \t\nnext \t\n\n\n", &[r#"text "line \t\nnext""#]),
+        (
+            "a\n*b* \t\nc \t\r\n\n",
+            &[r#"text "a\n""#, r#"text "b""#, r#"text " \t\nc""#],
+        ),
+        ("a\n\n\n\nb \t", &[r#"text "a""#, r#"text "b""#]),
+        (
+            "- [X] \t item \t\n- term \t:: \t desc\n",
+            &[
+                "item None",
+                r#"text "item""#,
+                "item None",
+                r#"text "term \t:: \t desc""#,
+            ],
+        ),
+        (
+            "- term :: \t desc \t\n\n\n  more\n",
+            &[
+                r#"item Some("term")"#,
+                r#"text "term""#,
+                r#"text "desc""#,
+                r#"text "  more""#,
+            ],
+        ),
+        ("[fn:1] \t note \t\n", &[r#"text "note""#]),
+        (
+            "#+BEGIN_QUOTE \t p \t\nq \t\n\n\n#+END_QUOTE\n",
+            &[r#"quote Some("p")"#, r#"text "q""#],
+        ),
+        (
+            "#+BEGIN_SRC rust \t :a 1 \t\ncode \t\n\n\n#+END_SRC\n",
+            &[r#"src "rust" ":a 1" "code \t\n\n\n""#],
+        ),
+        ("# comment \t\n\n\n", &[r##"comment "# comment \t\n""##]),
+        (": fixed \t\n: two", &[r#"fixed ": fixed \t\n: two\n""#]),
+    ];
+    for (text, expected) in cases {
+        assert_eq!(values(text), *expected, "{:?}", text);
+    }
+}
+
+#[cfg(feature = "export-html")]
+#[test]
+fn html_footnotes() {
+    let org = Org::parse(
+        "[fn:b] Defined *first*.\n\n\
+         See[fn:a], again[fn:a], [fn::inline] and [fn:b].\nMissing[fn:none].\n\n\
+         [fn:a] The /a/ note.\n\n[fn:unused] Dropped.\n",
+    );
+
+    let mut writer = Vec::new();
+    org.html(&mut writer).unwrap();
+    assert_eq!(
+        String::from_utf8(writer).unwrap(),
+        "<main><section><p>See<sup><a href=\"#fn.1\" id=\"fnr.1\">1</a></sup>, \
+         again<sup><a href=\"#fn.1\" id=\"fnr.1.2\">1</a></sup>, \
+         <sup><a href=\"#fn.2\" id=\"fnr.2\">2</a></sup> and \
+         <sup><a href=\"#fn.3\" id=\"fnr.3\">3</a></sup>.\n\
+         Missing<sup><a href=\"#fn.4\" id=\"fnr.4\">4</a></sup>.</p></section>\
+         <div id=\"footnotes\"><h2 class=\"footnotes\">Footnotes</h2><div id=\"text-footnotes\">\
+         <div class=\"footdef\"><sup><a id=\"fn.1\" class=\"footnum\" href=\"#fnr.1\">1</a></sup> \
+         <div class=\"footpara\"><p>The <i>a</i> note.</p></div></div>\
+         <div class=\"footdef\"><sup><a id=\"fn.2\" class=\"footnum\" href=\"#fnr.2\">2</a></sup> \
+         <div class=\"footpara\"><p>inline</p></div></div>\
+         <div class=\"footdef\"><sup><a id=\"fn.3\" class=\"footnum\" href=\"#fnr.3\">3</a></sup> \
+         <div class=\"footpara\"><p>Defined <b>first</b>.</p></div></div></div></div></main>"
+    );
+
+    // without definitions, there are no footnotes
+    let mut writer = Vec::new();
+    Org::parse("Missing[fn:1].").html(&mut writer).unwrap();
+    assert_eq!(
+        String::from_utf8(writer).unwrap(),
+        "<main><section><p>Missing<sup><a href=\"#fn.1\" id=\"fnr.1\">1</a></sup>.</p></section></main>"
+    );
+}
+
+#[cfg(feature = "export-html")]
+#[test]
+fn html_analysis() {
+    use orgize::export::{DefaultHtmlHandler, HtmlExportOptions};
+
+    let org = Org::parse(
+        r#"* One
+Note[fn:a], again[fn:a] and [fn::anonymous][fn:b].
+* Draft :noexport:
+** Hidden
+* Two
+** Three :selected:
+"#,
+    );
+
+    let analysis = org.analyze_for_html(&HtmlExportOptions::default());
+    let headlines: Vec<_> = org.headlines().collect();
+    let numbers: Vec<_> = headlines
+        .iter()
+        .map(|headline| analysis.section_number(headline.headline_node()))
+        .collect();
+    assert_eq!(numbers, [Some("1"), None, None, Some("2"), Some("2.1")]);
+    assert!(analysis.is_excluded(&org, headlines[2].headline_node()));
+
+    let footnotes: Vec<_> = org
+        .arena()
+        .iter()
+        .filter_map(|node| match node.get() {
+            orgize::Element::FnRef(_) => analysis.footnote_number(org.arena().get_node_id(node)?),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(footnotes, [1, 1, 2, 3]);
+
+    let mut writer = Vec::new();
+    org.html(&mut writer).unwrap();
+    let html = String::from_utf8(writer).unwrap();
+    assert!(!html.contains("Draft") && !html.contains("Hidden"));
+
+    let analysis = org.analyze_for_html(&HtmlExportOptions {
+        select_tags: vec!["selected".into()],
+        ..HtmlExportOptions::default()
+    });
+    let mut writer = Vec::new();
+    org.html_with_analysis(&mut writer, &mut DefaultHtmlHandler::default(), &analysis)
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(writer).unwrap(),
+        "<main><h1>Two</h1><h2>Three</h2></main>"
+    );
+
+    let mut writer = Vec::new();
+    org.html_fragment_with_analysis(
+        headlines[4].headline_node(),
+        &mut writer,
+        &mut DefaultHtmlHandler::default(),
+        &analysis,
+    )
+    .unwrap();
+    assert_eq!(String::from_utf8(writer).unwrap(), "<h2>Three</h2>");
+}
+
+#[cfg(feature = "export-html")]
+#[test]
+fn html_page_dates() {
+    use orgize::elements::Datetime;
+    use orgize::export::HtmlExportOptions;
+
+    let page = |text: &str, options: &HtmlExportOptions| {
+        let mut writer = Vec::new();
+        Org::parse(text).html_page(&mut writer, options).unwrap();
+        String::from_utf8(writer).unwrap()
+    };
+
+    let options = HtmlExportOptions {
+        date_format: Some("%d/%m/%Y".into()),
+        date_subtitle: true,
+        modification_time: Some(Datetime {
+            year: 2020,
+            month: 3,
+            day: 4,
+            dayname: "Wed".into(),
+            hour: Some(9),
+            minute: Some(30),
+        }),
+        ..HtmlExportOptions::default()
+    };
+
+    // timestamped date
+    let html = page(
+        "#+TITLE: Notes\n#+DATE: <2020-01-02 Thu>\n\
+         {{{date}}}, {{{date(%B %e\\, %Y)}}}, {{{modification-time(%H:%M)}}}\n",
+        &options,
+    );
+    if cfg!(feature = "chrono") {
+        assert_eq!(
+            html,
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+             <title>Notes</title>\n<meta name=\"date\" content=\"02/01/2020\">\n\
+             </head>\n<body>\n<h1 class=\"title\">Notes</h1>\n\
+             <p class=\"subtitle\">02/01/2020</p>\n\
+             <main><section><p>&lt;2020-01-02 Thu&gt;, January  2, 2020, 09:30</p></section></main>\n\
+             </body>\n</html>\n"
+        );
+    } else {
+        assert!(html.contains("<meta name=\"date\" content=\"&lt;2020-01-02 Thu&gt;\">"));
+        assert!(html.contains(
+            "<p>&lt;2020-01-02 Thu&gt;, &lt;2020-01-02 Thu&gt;, 2020-03-04 Wed 09:30</p>"
+        ));
+    }
+
+    // plain text date, written as is
+    let html = page(
+        "#+DATE: Spring 2020\n{{{date(%Y)}}}\n",
+        &HtmlExportOptions {
+            date_meta: false,
+            ..options.clone()
+        },
+    );
+    assert!(!html.contains("<meta name=\"date\""));
+    assert!(html.contains("<p class=\"subtitle\">Spring 2020</p>"));
+    assert!(html.contains("<p>Spring 2020</p>"));
+
+    // missing date
+    let html = page(
+        "{{{date}}}{{{modification-time}}}\n",
+        &HtmlExportOptions::default(),
+    );
+    assert!(!html.contains("<title>") && !html.contains("date"));
+    assert!(html.contains("<main><section><p></p></section></main>"));
+}
+
+#[cfg(all(feature = "export-html", feature = "rayon"))]
+#[test]
+fn html_parallel() {
+    use orgize::export::HtmlExportOptions;
+
+    let mut text = String::from("#+OPTIONS: toc:1\n#+TOC: tables\n");
+    for i in 0..2_000 {
+        text += &format!(
+            "* Chapter {}\nSee [[tab:{}]][fn:{}].\n#+NAME: tab:{}\n#+CAPTION: Data\n| a | b |\n",
+            i,
+            i,
+            i % 7,
+            i
+        );
+    }
+    text += "\n[fn:3] Three.\n";
+    let org = Org::parse(&text);
+
+    let mut sequential = Vec::new();
+    org.html(&mut sequential).unwrap();
+    let mut parallel = Vec::new();
+    org.html_parallel(&mut parallel, &HtmlExportOptions::default())
+        .unwrap();
+
+    assert!(sequential == parallel);
+}
+
+#[cfg(all(feature = "export-html", feature = "export-markdown"))]
+#[test]
+fn export_backends() {
+    use indextree::NodeId;
+    use orgize::export::{
+        DefaultHtmlHandler, DefaultMarkdownHandler, DefaultOrgHandler, ExportHandler,
+        ExportOptions, HtmlBackend, MarkdownBackend, OrgBackend,
+    };
+    use std::io::Error;
+
+    struct Progress<H> {
+        inner: H,
+        calls: Vec<(usize, usize)>,
+    }
+
+    impl<H: ExportHandler<Error>> ExportHandler<Error> for Progress<H> {
+        fn backend(&self) -> &str {
+            self.inner.backend()
+        }
+
+        fn start<W: std::io::Write>(
+            &mut self,
+            w: W,
+            element: &orgize::Element,
+        ) -> Result<(), Error> {
+            self.inner.start(w, element)
+        }
+
+        fn end<W: std::io::Write>(&mut self, w: W, element: &orgize::Element) -> Result<(), Error> {
+            self.inner.end(w, element)
+        }
+
+        fn set_node(&mut self, node: NodeId) {
+            self.inner.set_node(node)
+        }
+
+        fn progress(&mut self, done: usize, total: usize) {
+            self.calls.push((done, total));
+        }
+    }
+
+    fn export<H: ExportHandler<Error>>(org: &Org, handler: H, options: &ExportOptions) -> String {
+        let mut handler = Progress {
+            inner: handler,
+            calls: Vec::new(),
+        };
+        let mut writer = Vec::new();
+        org.export_with_handler(&mut writer, &mut handler, options)
+            .unwrap();
+        let total = handler.calls.last().map_or(0, |(_, total)| *total);
+        assert_eq!(
+            handler.calls,
+            (1..=total).map(|done| (done, total)).collect::<Vec<_>>()
+        );
+        String::from_utf8(writer).unwrap()
+    }
+
+    let org = Org::parse(
+        r#"* Public
+@@html:<br>@@ @@org:kept@@
+* Draft :noexport:
+draft text
+** Child
+* Other :secret:
+other text
+"#,
+    );
+    let subtree = org.headlines().next().unwrap().headline_node();
+
+    // the same driver filters the output of every backend
+    let outputs = vec![
+        export(
+            &org,
+            HtmlBackend(DefaultHtmlHandler::default()),
+            &ExportOptions::default(),
+        ),
+        export(
+            &org,
+            OrgBackend(DefaultOrgHandler),
+            &ExportOptions::default(),
+        ),
+        export(
+            &org,
+            MarkdownBackend::new(&org, DefaultMarkdownHandler::default()),
+            &ExportOptions::default(),
+        ),
+    ];
+    for output in &outputs {
+        assert!(output.contains("Public") && output.contains("Other"));
+        assert!(!output.contains("Draft") && !output.contains("draft text"));
+        assert!(!output.contains("Child"));
+    }
+
+    let options = ExportOptions {
+        exclude_tags: vec!["secret".into()],
+        select_tags: vec!["noexport".into()],
+        ..ExportOptions::default()
+    };
+    for output in &[
+        export(&org, HtmlBackend(DefaultHtmlHandler::default()), &options),
+        export(&org, OrgBackend(DefaultOrgHandler), &options),
+        export(
+            &org,
+            MarkdownBackend::new(&org, DefaultMarkdownHandler::default()),
+            &options,
+        ),
+    ] {
+        assert!(output.contains("Draft") && output.contains("Child"));
+        assert!(!output.contains("Public") && !output.contains("Other"));
+    }
+
+    let options = ExportOptions {
+        subtree: Some(subtree),
+        safe_mode: true,
+        ..ExportOptions::default()
+    };
+    assert_eq!(
+        export(&org, HtmlBackend(DefaultHtmlHandler::default()), &options),
+        "<h1>Public</h1><section><p>&lt;br&gt; </p></section>"
+    );
+    assert_eq!(
+        export(&org, OrgBackend(DefaultOrgHandler), &options),
+        "* Public\n@@html:<br>@@ kept\n\n"
+    );
+    assert_eq!(
+        export(
+            &org,
+            MarkdownBackend::new(&org, DefaultMarkdownHandler::default()),
+            &options
+        ),
+        "# Public\n"
+    );
+}
+
+#[cfg(feature = "export-html")]
+#[test]
+fn commented_subtrees() {
+    use orgize::export::{DefaultOrgHandler, ExportOptions, OrgBackend};
+
+    let org = Org::parse(
+        r#"#+EXCLUDE_TAGS: private
+* Public
+* COMMENT Notes
+** Child
+* TODO [#A] COMMENT Later
+* Diary :private:
+* Draft :noexport:
+"#,
+    );
+    let commented: Vec<_> = org
+        .headlines()
+        .map(|headline| headline.title(&org).is_commented())
+        .collect();
+    assert_eq!(commented, [false, true, false, true, false, false]);
+
+    let mut writer = Vec::new();
+    org.html(&mut writer).unwrap();
+    assert_eq!(
+        String::from_utf8(writer).unwrap(),
+        "<main><section></section><h1>Public</h1></main>"
+    );
+
+    let mut writer = Vec::new();
+    org.export_with_handler(
+        &mut writer,
+        &mut OrgBackend(DefaultOrgHandler),
+        &ExportOptions::default(),
+    )
+    .unwrap();
+    assert_eq!(
+        String::from_utf8(writer).unwrap(),
+        "#+EXCLUDE_TAGS: private\n* Public\n"
+    );
+
+    // commented subtrees stay excluded when selected
+    let org = Org::parse("#+SELECT_TAGS: web\n* Home :web:\n* COMMENT Old :web:\n* Other\n");
+    let mut writer = Vec::new();
+    org.html(&mut writer).unwrap();
+    assert_eq!(
+        String::from_utf8(writer).unwrap(),
+        "<main><section></section><h1>Home</h1></main>"
+    );
+}
+
+#[cfg(feature = "export-html")]
+#[test]
+fn archived_subtrees() {
+    use orgize::export::{DefaultOrgHandler, ExportOptions, HtmlExportOptions, OrgBackend};
+
+    let text =
+        "#+ARCHIVE: %s_archive::\n* Done :ARCHIVE:\nnotes\n** Old\n*** Older\n* Current\n** Child\n";
+    let config = ParseConfig {
+        skip_archived: true,
+        ..ParseConfig::default()
+    };
+    let org = Org::parse_with_config(text, &config);
+    let titles: Vec<_> = org
+        .headlines()
+        .map(|headline| headline.title(&org).raw.to_string())
+        .collect();
+    assert_eq!(titles, ["Done", "Current", "Child"]);
+    assert!(org.parse_diagnostics().is_empty());
+    assert_eq!(org.document().archive_location(&org), Some("%s_archive::"));
+
+    let mut writer = Vec::new();
+    org.org(&mut writer).unwrap();
+    assert_eq!(String::from_utf8(writer).unwrap(), text);
+
+    let org = Org::parse(text);
+    let archived: Vec<_> = org
+        .headlines()
+        .map(|headline| headline.is_archived(&org))
+        .collect();
+    assert_eq!(archived, [true, true, true, false, false]);
+
+    let mut writer = Vec::new();
+    org.export_with_handler(
+        &mut writer,
+        &mut OrgBackend(DefaultOrgHandler),
+        &ExportOptions {
+            skip_archived: true,
+            ..ExportOptions::default()
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        String::from_utf8(writer).unwrap(),
+        "#+ARCHIVE: %s_archive::\n* Done :ARCHIVE:\n* Current\n** Child\n"
+    );
+
+    let mut writer = Vec::new();
+    org.html_page(
+        &mut writer,
+        &HtmlExportOptions {
+            skip_archived: true,
+            ..HtmlExportOptions::default()
+        },
+    )
+    .unwrap();
+    let html = String::from_utf8(writer).unwrap();
+    assert!(html.contains("Done") && html.contains("Current"));
+    assert!(!html.contains("notes") && !html.contains("Old"));
+}
+
+#[cfg(all(feature = "export-html", feature = "export-markdown"))]
+#[test]
+fn raw_html() {
+    use orgize::elements::ExportBlock;
+    use orgize::export::{DefaultHtmlHandler, ExportOptions, HtmlBackend};
+    use orgize::Element;
+
+    let mut org = Org::parse("* Diagram\n@@html:<b>@@ flow\n");
+    let paragraph = org
+        .arena()
+        .iter()
+        .find(|node| *node.get() == Element::Paragraph)
+        .and_then(|node| org.arena().get_node_id(node))
+        .unwrap();
+    org.insert_raw_html_after(paragraph, "<svg><rect/></svg>");
+    assert!(org.validate().is_ok());
+
+    let mut writer = Vec::new();
+    let mut handler = DefaultHtmlHandler {
+        safe_mode: true,
+        ..DefaultHtmlHandler::default()
+    };
+    org.html_with_handler(&mut writer, &mut handler).unwrap();
+    assert_eq!(
+        String::from_utf8(writer).unwrap(),
+        "<main><h1>Diagram</h1><section><p>&lt;b&gt; flow</p><svg><rect/></svg></section></main>"
+    );
+
+    let mut writer = Vec::new();
+    org.export_with_handler(
+        &mut writer,
+        &mut HtmlBackend(DefaultHtmlHandler::default()),
+        &ExportOptions {
+            safe_mode: true,
+            ..ExportOptions::default()
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        String::from_utf8(writer).unwrap(),
+        "<main><h1>Diagram</h1><section><p>&lt;b&gt; flow</p><svg><rect/></svg></section></main>"
+    );
+
+    let mut writer = Vec::new();
+    org.markdown(&mut writer).unwrap();
+    assert_eq!(String::from_utf8(writer).unwrap(), "# Diagram\n\nflow\n");
+    assert_eq!(org.export_diagnostics("markdown").len(), 1);
+
+    // the org export writes an export block, parsed back as such
+    let mut writer = Vec::new();
+    org.org(&mut writer).unwrap();
+    let text = String::from_utf8(writer).unwrap();
+    let org = Org::parse(&text);
+    let blocks: Vec<_> = org
+        .iter()
+        .filter_map(|event| match event {
+            orgize::Event::Start(Element::ExportBlock(ExportBlock { data, contents })) => {
+                Some((data.to_string(), contents.to_string()))
+            }
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        blocks,
+        [("html".to_string(), "<svg><rect/></svg>\n".to_string())]
+    );
+}
+
+#[cfg(feature = "export-html")]
+#[test]
+fn link_descriptions() {
+    use orgize::{elements::Link, Element};
+
+    let text = "* See [[#a][/the/ \\[a\\]]]\n#+TOC: headlines\n";
+    let org = Org::parse(text);
+
+    let mut children = Vec::new();
+    for event in org.iter() {
+        if let orgize::Event::Start(element) = event {
+            match element {
+                Element::Link(Link { desc, .. }) => {
+                    assert_eq!(desc.as_deref(), Some(r"/the/ \[a\]"))
+                }
+                Element::Italic => children.push("italic".to_string()),
+                Element::Text { value } => children.push(value.to_string()),
+                _ => (),
+            }
+        }
+    }
+    assert_eq!(children, ["See ", "italic", "the", r" \[a\]"]);
+
+    let mut writer = Vec::new();
+    org.org(&mut writer).unwrap();
+    assert_eq!(String::from_utf8(writer).unwrap(), text);
+
+    // the table of contents uses the plain text of titles
+    let mut writer = Vec::new();
+    org.html(&mut writer).unwrap();
+    let html = String::from_utf8(writer).unwrap();
+    assert!(
+        html.contains(r##"<li><a href="#headline-1">See the [a]</a></li>"##),
+        "{}",
+        html
+    );
+}
+
+#[cfg(feature = "export-html")]
+#[test]
+fn link_paths() {
+    use orgize::{
+        elements::{Link, LinkFormat},
+        Element,
+    };
+
+    let org = Org::parse(
+        "[[https://example.com/a%20b?q=1&r=2]] [[file:my notes.org]] \
+         [[https://example.com/page#section-2]] [[file:日本語.org][日本]] [[file:a\\[1\\].org]]",
+    );
+
+    let links: Vec<_> = org
+        .iter()
+        .filter_map(|event| match event {
+            orgize::Event::Start(Element::Link(link)) => Some(link),
+            _ => None,
+        })
+        .collect();
+    let paths: Vec<_> = links.iter().map(|link| &*link.path).collect();
+    let decoded: Vec<_> = links.iter().map(|link| link.path_decoded()).collect();
+
+    assert_eq!(
+        paths,
+        [
+            "https://example.com/a%20b?q=1&r=2",
+            "file:my notes.org",
+            "https://example.com/page#section-2",
+            "file:日本語.org",
+            "file:a[1].org",
+        ]
+    );
+    assert_eq!(
+        decoded,
+        [
+            "https://example.com/a b?q=1&r=2",
+            "file:my notes.org",
+            "https://example.com/page#section-2",
+            "file:日本語.org",
+            "file:a[1].org",
+        ]
+    );
+
+    let mut writer = Vec::new();
+    org.html(&mut writer).unwrap();
+    let html = String::from_utf8(writer).unwrap();
+    let hrefs: Vec<_> = html
+        .split("href=\"")
+        .skip(1)
+        .map(|s| &s[0..s.find('"').unwrap()])
+        .collect();
+    assert_eq!(
+        hrefs,
+        [
+            "https://example.com/a%20b?q=1&amp;r=2",
+            "file:my%20notes.org",
+            "https://example.com/page#section-2",
+            "file:%E6%97%A5%E6%9C%AC%E8%AA%9E.org",
+            "file:a[1].org",
+        ]
+    );
+
+    let mut writer = Vec::new();
+    org.org(&mut writer).unwrap();
+    assert!(String::from_utf8(writer)
+        .unwrap()
+        .contains("[[file:a\\[1\\].org]]"));
+
+    let link = Link {
+        path: "file:%ZZ%e6%97%a5".into(),
+        desc: None,
+        format: LinkFormat::Bracket,
+    };
+    assert_eq!(link.path_decoded(), "file:%ZZ日");
+}
+
+#[test]
+fn parse_report() {
+    use orgize::{elements::ElementKind, SyntaxFeature};
+
+    let org = Org::parse(
+        r#"#+TITLE: Report
+#+BIND: x 1
+#+attr_html: :width 100
+* TODO [#A] Tasks [1/2] :work:
+SCHEDULED: <2020-01-01 Wed>
+:PROPERTIES:
+:ID: 1
+:END:
+- [X] *done*
+- [ ] see [[https://example.com]][fn:1]
+| a | b |
+#+BEGIN_note
+Note
+#+END_note
+#+END_QUOTE
+"#,
+    );
+    let report = org.parse_report();
+
+    assert_eq!(report.count(ElementKind::Headline), 1);
+    assert_eq!(report.count(ElementKind::ListItem), 2);
+    assert_eq!(report.count(ElementKind::TableCell), 2);
+    assert_eq!(report.count(ElementKind::Clock), 0);
+    assert_eq!(report.unknown_blocks.iter().collect::<Vec<_>>(), ["note"]);
+    assert_eq!(report.unknown_keywords.iter().collect::<Vec<_>>(), ["BIND"]);
+    assert_eq!(report.raw_lines, ["#+END_QUOTE"]);
+    assert_eq!(
+        report.features.iter().cloned().collect::<Vec<_>>(),
+        [
+            SyntaxFeature::Headlines,
+            SyntaxFeature::TodoKeywords,
+            SyntaxFeature::Priorities,
+            SyntaxFeature::Tags,
+            SyntaxFeature::Planning,
+            SyntaxFeature::Properties,
+            SyntaxFeature::Lists,
+            SyntaxFeature::Tables,
+            SyntaxFeature::Footnotes,
+            SyntaxFeature::Links,
+            SyntaxFeature::Emphasis,
+            SyntaxFeature::Cookies,
+            SyntaxFeature::SpecialBlocks,
+        ]
+    );
+}
+
+#[cfg(all(feature = "export-html", feature = "ser"))]
+#[test]
+fn outline() {
+    use orgize::export::HtmlExportOptions;
+
+    let org = Org::parse(
+        r#"* One
+:PROPERTIES:
+:CUSTOM_ID: one
+:END:
+** DONE Two =code= [[https://example.com][link]]
+* Draft :noexport:
+* Three :nav:
+"#,
+    );
+
+    let outline = org.outline(10);
+    assert_eq!(
+        serde_json::to_string(&outline).unwrap(),
+        r#"[{"title":"One","level":1,"id":"one","number":"1","children":[{"title":"Two code link","level":2,"id":"headline-2","number":"1.1","todo":"DONE"}]},{"title":"Three","level":1,"id":"headline-4","number":"2"}]"#
+    );
+    assert!(org.outline(1)[0].children.is_empty());
+
+    let analysis = org.analyze_for_html(&HtmlExportOptions {
+        select_tags: vec!["nav".into()],
+        ..Default::default()
+    });
+    let titles: Vec<_> = org
+        .outline_with_analysis(&analysis, 3)
+        .into_iter()
+        .map(|entry| entry.title)
+        .collect();
+    assert_eq!(titles, ["Three"]);
+}
+
+#[cfg(feature = "export-html")]
+#[test]
+fn unnumbered_headlines() {
+    use orgize::export::HtmlExportOptions;
+
+    let text = r#"#+TOC: headlines
+* One
+* Appendix
+:PROPERTIES:
+:UNNUMBERED: t
+:END:
+** Details
+* Two
+** Hidden
+:PROPERTIES:
+:UNNUMBERED: notoc
+:END:
+*** Deeper
+** Three
+See [[*Appendix]] and [[*Three]].
+"#;
+    let org = Org::parse(text);
+    let analysis = org.analyze_for_html(&HtmlExportOptions::default());
+
+    let numbers: Vec<_> = org
+        .headlines()
+        .map(|headline| analysis.section_number(headline.headline_node()))
+        .collect();
+    assert_eq!(
+        numbers,
+        [Some("1"), None, None, Some("2"), None, None, Some("2.1")]
+    );
+    let in_toc: Vec<_> = org
+        .headlines()
+        .map(|headline| analysis.in_toc(&org, headline.headline_node()))
+        .collect();
+    assert_eq!(in_toc, [true, true, true, true, false, false, true]);
+
+    let outline: Vec<_> = org
+        .outline_with_analysis(&analysis, 3)
+        .into_iter()
+        .map(|entry| (entry.title, entry.number, entry.children.len()))
+        .collect();
+    assert_eq!(
+        outline,
+        [
+            ("One".into(), Some("1".into()), 0),
+            ("Appendix".into(), None, 1),
+            ("Two".into(), Some("2".into()), 1),
+        ]
+    );
+
+    let mut writer = Vec::new();
+    org.html(&mut writer).unwrap();
+    let html = String::from_utf8(writer).unwrap();
+    assert!(!html.contains("<a href=\"#headline-5\">"));
+    assert!(html.contains("<a href=\"#headline-2\">Appendix</a>"));
+    assert!(html.contains("<a href=\"#headline-7\">Section 2.1</a>"));
+
+    let numbers = |text: &str| {
+        let org = Org::parse(text);
+        let analysis = org.analyze_for_html(&HtmlExportOptions::default());
+        org.headlines()
+            .map(|headline| {
+                analysis
+                    .section_number(headline.headline_node())
+                    .map(String::from)
+            })
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        numbers("#+OPTIONS: num:nil\n* One\n* Two\n:PROPERTIES:\n:NUMBERED: t\n:END:\n"),
+        [None, Some("1".into())]
+    );
+    assert_eq!(
+        numbers("#+OPTIONS: toc:nil num:1\n* One\n** Two\n* Three\n"),
+        [Some("1".into()), None, Some("2".into())]
+    );
+}
+
+#[cfg(feature = "export-html")]
+#[test]
+fn options_keyword() {
+    use orgize::{ExportSettings, Levels};
+
+    let src = "#+OPTIONS: num:t H:1 toc:1 -:t \\n:t unknown:x\n* TODO Intro\n\
+               Wait... 1--2 or 1---2\\\\\nsecond *line*\nthird\n** Deep\ntext\n* Next\n";
+    let org = Org::parse(src);
+    assert_eq!(
+        org.export_settings(),
+        ExportSettings {
+            num: Some(Levels::All),
+            toc: Some(Levels::UpTo(1)),
+            headline_levels: Some(1),
+            special_strings: Some(true),
+            preserve_breaks: Some(true),
+            sub_superscripts: None,
+        }
+    );
+
+    let mut writer = Vec::new();
+    org.html(&mut writer).unwrap();
+    assert_eq!(
+        String::from_utf8(writer).unwrap(),
+        "<main><ul><li><a href=\"#headline-1\">Intro</a></li><li><a href=\"#headline-3\">Next</a></li></ul>\
+         <section></section><a id=\"headline-1\"></a><h1><span class=\"section-number\">1</span> Intro</h1>\
+         <section><p>Wait\u{2026} 1\u{2013}2 or 1\u{2014}2<br>\nsecond <b>line</b><br>\nthird</p></section>\
+         <a id=\"headline-2\"></a><p><b>Deep</b></p><section><p>text</p></section>\
+         <a id=\"headline-3\"></a><h1><span class=\"section-number\">2</span> Next</h1></main>"
+    );
+
+    // without options, nothing changes
+    let mut writer = Vec::new();
+    Org::parse("* Intro\nWait...\nsecond\n")
+        .html(&mut writer)
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(writer).unwrap(),
+        "<main><h1>Intro</h1><section><p>Wait...\nsecond</p></section></main>"
+    );
+}
+
+#[cfg(feature = "export-html")]
+#[test]
+fn toc_slug_ids() {
+    use orgize::export::{slugify, DefaultHtmlHandler, HtmlExportOptions};
+
+    let org = Org::parse(
+        "* Getting Started\n** Install\n*** Deep\n* Getting started!\n\
+         * Hidden :noexport:\n* Custom\n:PROPERTIES:\n:CUSTOM_ID: getting-started-2\n:END:\n\
+         * Getting Started\n",
+    );
+    let options = HtmlExportOptions {
+        toc: Some(2),
+        slug_ids: true,
+        ..Default::default()
+    };
+    let analysis = org.analyze_for_html(&options);
+    let mut writer = Vec::new();
+    org.html_with_analysis(&mut writer, &mut DefaultHtmlHandler::default(), &analysis)
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(writer).unwrap(),
+        "<main><ul><li><a href=\"#getting-started\">Getting Started</a>\
+         <ul><li><a href=\"#install\">Install</a></li></ul></li>\
+         <li><a href=\"#getting-started-1\">Getting started!</a></li>\
+         <li><a href=\"#getting-started-2\">Custom</a></li>\
+         <li><a href=\"#getting-started-3\">Getting Started</a></li></ul>\
+         <h1 id=\"getting-started\">Getting Started</h1>\
+         <h2 id=\"install\">Install</h2><h3 id=\"deep\">Deep</h3>\
+         <h1 id=\"getting-started-1\">Getting started!</h1>\
+         <h1 id=\"getting-started-2\">Custom</h1>\
+         <h1 id=\"getting-started-3\">Getting Started</h1></main>"
+    );
+
+    assert_eq!(slugify("Étape 2: Déployer"), "étape-2-déployer");
+}
+
+#[test]
+fn normalize_logbooks() {
+    use orgize::{NormalizeOptions, NormalizeReport};
+
+    let text = "* Work\n:LOGBOOK:\n\
+                CLOCK: [2023-05-01 Mon 09:00]--[2023-05-01 Mon 10:00] =>  1:00\n\
+                - Note taken on [2023-05-01 Mon 10:00] \\\\\n  done\n\
+                CLOCK: [2023-05-01 Mon 09:30]--[2023-05-01 Mon 11:15] =>  1:45\n\
+                CLOCK: [2023-05-04 Thu 08:00]\n\
+                CLOCK: [2023-02-30 Thu 08:00]--[2023-02-30 Thu 09:00] =>  9:99\n\
+                :END:\n\
+                * Sorted\n:LOGBOOK:\n\
+                CLOCK: [2023-05-02 Tue 23:00]--[2023-05-03 Wed 00:30] =>  1:30\n\
+                :END:\n";
+    let mut org = Org::parse(text);
+    let report = org.normalize_logbooks(&NormalizeOptions {
+        merge_overlapping: true,
+        ..Default::default()
+    });
+    assert_eq!(
+        report,
+        NormalizeReport {
+            duplicates: 0,
+            merged: 1,
+            zero_length: 0,
+            durations: 0,
+            sorted: 1,
+        }
+    );
+
+    let mut writer = Vec::new();
+    org.write_org_minimal(text, &mut writer).unwrap();
+    assert_eq!(
+        String::from_utf8(writer).unwrap(),
+        "* Work\n:LOGBOOK:\n\
+         CLOCK: [2023-05-04 Thu 08:00]\n\
+         - Note taken on [2023-05-01 Mon 10:00] \\\\\n  done\n\
+         CLOCK: [2023-05-01 Mon 09:00]--[2023-05-01 Mon 11:15] => 2:15\n\
+         CLOCK: [2023-02-30 Thu 08:00]--[2023-02-30 Thu 09:00] => 9:99\n\
+         :END:\n\
+         * Sorted\n:LOGBOOK:\n\
+         CLOCK: [2023-05-02 Tue 23:00]--[2023-05-03 Wed 00:30] =>  1:30\n\
+         :END:\n"
+    );
+
+    // nothing left to change
+    assert_eq!(
+        org.normalize_logbooks(&NormalizeOptions::default()),
+        NormalizeReport::default()
+    );
+}
+
+#[test]
+fn change_log() {
+    use orgize::{elements::Title, Change, ChangeKind, OrgizeError};
+
+    let text =
+        "Intro\n* Inbox\n** Call Bob\n*** Number\n* Projects\n:PROPERTIES:\n:ID: p\n:END:\nNotes\n";
+    let mut org = Org::parse(text);
+
+    // changes made before enabling the log aren't recorded
+    org.document().set_section_content("Preface\n", &mut org);
+    assert!(org.take_changes().is_empty());
+
+    let mut copy = Org::parse(text);
+    copy.document().set_section_content("Preface\n", &mut copy);
+
+    org.enable_change_log();
+    let headlines: Vec<_> = org.headlines().collect();
+    let (inbox, mut call, projects) = (headlines[0], headlines[1], headlines[3]);
+    projects.set_property("ID", "projects", &mut org);
+    projects.set_section_content("More /notes/\n", &mut org);
+    call.promote(&mut org).unwrap();
+    call.set_title_content("Call Alice", &mut org);
+
+    // changes to detached headlines are part of the inserted subtree
+    let mut new = org.new_headline(Title {
+        raw: "Review".into(),
+        ..Default::default()
+    });
+    new.set_level(2, &mut org).unwrap();
+    new.set_section_content("Weekly\n", &mut org);
+    inbox.append(new, &mut org).unwrap();
+
+    let changes = org.take_changes();
+    assert_eq!(changes.len(), 6);
+    assert_eq!(
+        changes[0],
+        Change {
+            path: vec![1],
+            kind: ChangeKind::Property {
+                key: "ID".into(),
+                before: Some("p".into()),
+                after: "projects".into(),
+            },
+        }
+    );
+    assert_eq!(
+        changes[1].kind,
+        ChangeKind::Section {
+            before: "Notes\n\n".into(),
+            after: "More /notes/\n\n".into(),
+        }
+    );
+    assert_eq!(
+        changes[2],
+        Change {
+            path: vec![0, 0],
+            kind: ChangeKind::Remove {
+                subtree: "** Call Bob\n*** Number\n".into(),
+            },
+        }
+    );
+    assert_eq!(
+        changes[3],
+        Change {
+            path: vec![1],
+            kind: ChangeKind::Insert {
+                subtree: "* Call Bob\n** Number\n".into(),
+            },
+        }
+    );
+    assert_eq!(
+        changes[5],
+        Change {
+            path: vec![0, 0],
+            kind: ChangeKind::Insert {
+                subtree: "** Review\nWeekly\n\n".into(),
+            },
+        }
+    );
+    assert!(org.take_changes().is_empty());
+
+    copy.apply_changes(&changes).unwrap();
+    let expected = "Preface\n\n* Inbox\n** Review\nWeekly\n\n* Call Alice\n** Number\n\
+                    * Projects\n:PROPERTIES:\n:ID: projects\n:END:\nMore /notes/\n\n";
+    for org in &[&org, &copy] {
+        let mut writer = Vec::new();
+        org.org(&mut writer).unwrap();
+        assert_eq!(String::from_utf8(writer).unwrap(), expected);
+    }
+
+    // the copy diverged: the second headline is "Call Alice" now
+    let mut diverged = Org::parse(text);
+    let projects = diverged.headlines().nth(3).unwrap();
+    projects.set_section_content("Changed\n", &mut diverged);
+    match diverged.apply_changes(&changes) {
+        Err(OrgizeError::ChangeConflict { index: 1, at }) => {
+            assert_eq!(at, projects.headline_node())
+        }
+        result => panic!("unexpected result: {:?}", result),
+    }
+}
+
+#[test]
+fn headline_levels() {
+    let text = "* a\n** b\n*** c\ntext\n**** d\n* e\n";
+
+    let config = ParseConfig {
+        max_headline_level: Some(2),
+        ..Default::default()
+    };
+    let org = Org::parse_with_config(text, &config);
+    let titles: Vec<_> = org
+        .headlines()
+        .map(|headline| (headline.level(), headline.title(&org).raw.to_string()))
+        .collect();
+    assert_eq!(titles, [(1, "a".into()), (2, "b".into()), (1, "e".into())]);
+
+    assert_eq!(
+        org.headlines().nth(1).unwrap().source(&org),
+        Some("** b\n*** c\ntext\n**** d\n")
+    );
+    let mut writer = Vec::new();
+    org.org(&mut writer).unwrap();
+    assert!(
+        Org::parse_with_config(&String::from_utf8(writer).unwrap(), &config).structural_eq(&org)
+    );
+
+    let config = ParseConfig {
+        flatten_levels: true,
+        ..Default::default()
+    };
+    let org = Org::parse_with_config(text, &config);
+    let titles: Vec<_> = org
+        .headlines()
+        .map(|headline| {
+            let title = headline.title(&org);
+            (headline.level(), title.stars, title.raw.to_string())
+        })
+        .collect();
+    assert_eq!(
+        titles,
+        [
+            (1, Some(1), "a".into()),
+            (1, Some(2), "b".into()),
+            (1, Some(3), "c".into()),
+            (1, Some(4), "d".into()),
+            (1, Some(1), "e".into()),
+        ]
+    );
+    assert!(org.validate().is_ok());
+
+    let mut writer = Vec::new();
+    org.org(&mut writer).unwrap();
+    assert_eq!(
+        String::from_utf8(writer).unwrap(),
+        "* a\n* b\n* c\ntext\n\n* d\n* e\n"
+    );
+}
+
+#[cfg(feature = "tools")]
+#[test]
+fn code_blocks() {
+    use orgize::tools::extract_code;
+
+    let text = r#"#+BEGIN_SRC python
+print(1)
+#+END_SRC
+* Scripts
+** Setup
+#+NAME: install
+#+HEADER: :dir /tmp :results silent
+#+BEGIN_SRC sh :results output
+  ,* not a headline
+    echo done
+#+END_SRC
+
+#+BEGIN_SRC sh -i
+  indented
+#+END_SRC
+"#;
+    let org = Org::parse(text);
+
+    let blocks: Vec<_> = org.code_blocks().collect();
+    assert_eq!(blocks.len(), 3);
+    assert!(blocks[0].headline.is_none());
+    assert_eq!(blocks[0].contents_line, Some(1));
+
+    let install = &blocks[1];
+    assert_eq!(install.language, "sh");
+    assert_eq!(install.name, Some("install"));
+    assert_eq!(install.contents, "* not a headline\n  echo done\n");
+    assert_eq!(
+        install.header_args,
+        [
+            (":dir", "/tmp"),
+            (":results", "silent"),
+            (":results", "output")
+        ]
+    );
+    assert_eq!(install.header_arg(":results"), Some("output"));
+    assert_eq!(install.header_arg(":exports"), None);
+    let headline = install.headline.unwrap();
+    assert_eq!(headline.title(&org).raw, "Setup");
+    let span = install.span.clone().unwrap();
+    assert!(text[span].starts_with("#+BEGIN_SRC sh :results output\n"));
+    assert_eq!(install.document_line(1), Some(9));
+    assert_eq!(text.lines().nth(9), Some("    echo done"));
+
+    let blocks = extract_code(&org, Some("sh"));
+    assert_eq!(blocks.len(), 2);
+    assert_eq!(blocks[1].contents, "  indented\n");
+    assert_eq!(extract_code(&org, None).len(), 3);
+    assert!(extract_code(&org, Some("rust")).is_empty());
+}
+
+#[test]
+fn parse_files() {
+    use orgize::{parse_files, ParseConfig, Position};
+    use std::path::PathBuf;
+
+    let files: Vec<(String, Vec<u8>)> = (0..64)
+        .map(|i| (format!("{}.org", i), format!("* {}\n", i).into_bytes()))
+        .chain(vec![
+            ("bad.org".to_string(), b"* caf\xE9\n:END:\n".to_vec()),
+            ("last.org".to_string(), b"* last\n".to_vec()),
+        ])
+        .collect();
+
+    let parsed = parse_files(files, &ParseConfig::default());
+    assert_eq!(parsed.len(), 66);
+
+    for (i, (path, org, diagnostics)) in parsed.iter().take(64).enumerate() {
+        assert_eq!(*path, PathBuf::from(format!("{}.org", i)));
+        assert_eq!(
+            org.headlines().next().unwrap().title(org).raw,
+            i.to_string()
+        );
+        assert!(diagnostics.is_empty());
+    }
+
+    let (path, org, diagnostics) = &parsed[64];
+    assert_eq!(
+        org.headlines().next().unwrap().title(org).raw,
+        "caf\u{FFFD}"
+    );
+    let messages: Vec<_> = diagnostics
+        .iter()
+        .map(|diagnostic| {
+            (
+                diagnostic.path.as_ref(),
+                diagnostic.span.clone(),
+                diagnostic.position,
+            )
+        })
+        .collect();
+    assert_eq!(
+        messages,
+        [
+            (Some(path), None, None),
+            (
+                Some(path),
+                Some(9..15),
+                Some(Position { line: 1, column: 0 })
+            )
+        ]
+    );
+
+    assert_eq!(parsed[65].1.headlines().count(), 1);
+    assert!(parsed[65].2.is_empty());
+}
+
+#[cfg(feature = "export-html")]
+#[test]
+fn admonitions() {
+    use orgize::export::DefaultHtmlHandler;
+
+    let org = Org::parse(
+        "- item\n  #+BEGIN_WARNING\n  Hot\n  #+END_WARNING\n\
+         #+BEGIN_tip\n#+BEGIN_SRC sh\nls\n#+END_SRC\n#+END_tip\n",
+    );
+    let html = |handler: &mut DefaultHtmlHandler| {
+        let mut writer = Vec::new();
+        org.html_with_handler(&mut writer, handler).unwrap();
+        String::from_utf8(writer).unwrap()
+    };
+
+    let mut handler = DefaultHtmlHandler {
+        admonition_titles: vec![("tip".into(), "Astuce".into())],
+        ..Default::default()
+    };
+    assert_eq!(
+        html(&mut handler),
+        "<main><section><ul><li><p>item</p>\
+         <aside class=\"admonition warning\"><p class=\"admonition-title\">Warning</p>\
+         <p>  Hot</p></aside></li></ul>\
+         <aside class=\"admonition tip\"><p class=\"admonition-title\">Astuce</p>\
+         <div class=\"org-src-container\"><pre class=\"src src-sh\">ls\n</pre></div>\
+         </aside></section></main>"
+    );
+
+    handler.plain_admonitions = true;
+    assert_eq!(
+        html(&mut handler),
+        "<main><section><ul><li><p>item</p>\
+         <div class=\"WARNING\"><p>  Hot</p></div></li></ul>\
+         <div class=\"tip\"><div class=\"org-src-container\"><pre class=\"src src-sh\">ls\n</pre></div>\
+         </div></section></main>"
+    );
+}
+
+#[cfg(feature = "export-html")]
+#[test]
+fn tight_lists() {
+    use orgize::export::{DefaultHtmlHandler, HtmlExportOptions};
+    use orgize::Element;
+
+    let org = Org::parse("- a\n- b\n\nloose:\n\n- c\n\n- d\n  #+BEGIN_QUOTE\n  e\n  #+END_QUOTE\n");
+    let tight: Vec<_> = org
+        .arena()
+        .iter()
+        .filter_map(|node| match node.get() {
+            Element::List(list) => Some(list.tight),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(tight, [true, false]);
+
+    let html = |tight_lists| {
+        let analysis = org.analyze_for_html(&HtmlExportOptions {
+            tight_lists,
+            ..Default::default()
+        });
+        let mut writer = Vec::new();
+        org.html_with_analysis(&mut writer, &mut DefaultHtmlHandler::default(), &analysis)
+            .unwrap();
+        String::from_utf8(writer).unwrap()
+    };
+
+    let loose = "<ul><li><p>c</p></li><li><p>d</p><blockquote><p>  e</p></blockquote></li></ul>";
+    assert_eq!(
+        html(false),
+        format!(
+            "<main><section><ul><li><p>a</p></li><li><p>b</p></li></ul><p>loose:</p>{}</section></main>",
+            loose
+        )
+    );
+    assert_eq!(
+        html(true),
+        format!(
+            "<main><section><ul><li>a</li><li>b</li></ul><p>loose:</p>{}</section></main>",
+            loose
+        )
+    );
+}
+
+#[cfg(feature = "export-html")]
+#[test]
+fn block_whitespace() {
+    use orgize::export::{block_text, DefaultHtmlHandler, HtmlExportOptions};
+
+    let cases = [
+        ("#+BEGIN_SRC rust\n#+END_SRC\n", ""),
+        ("#+BEGIN_SRC rust\n\n  \n\n#+END_SRC\n", ""),
+        (
+            "#+BEGIN_SRC rust\r\n  a\r\n\r\n    b\r\n#+END_SRC\r\n",
+            "a\n\n  b\n",
+        ),
+        ("#+BEGIN_SRC rust -i\n  a\n\n\n#+END_SRC\n", "  a\n"),
+        ("#+BEGIN_EXAMPLE\n\ta\n\t\tb\n#+END_EXAMPLE\n", "a\n\tb\n"),
+        ("#+BEGIN_EXAMPLE\n  ,* a\n#+END_EXAMPLE\n", "* a\n"),
+        (": a\n:\n:   b\n", "a\n\n  b\n"),
+        (":\n:\n", ""),
+    ];
+
+    for (text, expected) in cases.iter() {
+        let org = Org::parse(text);
+        let contents: Vec<_> = org
+            .arena()
+            .iter()
+            .filter_map(|node| block_text(node.get()))
+            .collect();
+        assert_eq!(contents, [*expected], "{:?}", text);
+
+        for block in org.code_blocks() {
+            assert_eq!(block.contents, *expected, "{:?}", text);
+        }
+
+        let mut writer = Vec::new();
+        org.html(&mut writer).unwrap();
+        let html = String::from_utf8(writer).unwrap();
+
+        let analysis = org.analyze_for_html(&HtmlExportOptions::default());
+        let mut writer = Vec::new();
+        org.html_with_analysis(&mut writer, &mut DefaultHtmlHandler::default(), &analysis)
+            .unwrap();
+        assert_eq!(String::from_utf8(writer).unwrap(), html, "{:?}", text);
+        assert!(
+            html.contains(&format!("\">{}</pre>", expected)),
+            "{:?} {}",
+            text,
+            html
+        );
+
+        // the org writer keeps the contents, so they survive a round trip
+        let mut writer = Vec::new();
+        org.org(&mut writer).unwrap();
+        let written = String::from_utf8(writer).unwrap();
+        let reparsed = Org::parse(&written);
+        let contents: Vec<_> = reparsed
+            .arena()
+            .iter()
+            .filter_map(|node| block_text(node.get()))
+            .collect();
+        assert_eq!(contents, [*expected], "{:?}", written);
+    }
+}
+
+#[cfg(feature = "export-html")]
+#[test]
+fn todo_keywords() {
+    use orgize::export::{DefaultHtmlHandler, HtmlExportOptions};
+    use std::sync::Arc;
+
+    use orgize::SettingsResolver;
+
+    let text = "#+TODO: TODO(t) DOING | DONE(d!) CANCELED\n* TODO One\n* DOING Two\n* DONE Three\n* DONE Four\n* Five\n* DONE Hidden :noexport:\n";
+    let config = SettingsResolver::new(&Org::parse(text), &ParseConfig::default()).parse_config();
+    let org = Org::parse_with_config(text, &config);
+
+    let analysis = org.analyze_for_html(&HtmlExportOptions::default());
+    assert_eq!(
+        analysis.todo_counts(),
+        [("TODO", 1), ("DOING", 1), ("DONE", 2), ("CANCELED", 0)]
+    );
+    assert!(analysis.is_done_keyword("CANCELED"));
+    assert!(!analysis.is_done_keyword("DOING"));
+
+    let mut handler = DefaultHtmlHandler {
+        todo_renderer: Some(Arc::new(|keyword, done, w| {
+            write!(w, "<span class=\"{}\">{}</span> ", done, keyword)
+        })),
+        ..Default::default()
+    };
+    let mut writer = Vec::new();
+    org.html_with_analysis(&mut writer, &mut handler, &analysis)
+        .unwrap();
+    let html = String::from_utf8(writer).unwrap();
+    assert!(html.contains("<h1><span class=\"false\">DOING</span> Two</h1>"));
+    assert!(html.contains("<h1><span class=\"true\">DONE</span> Four</h1>"));
+    assert!(html.contains("<h1>Five</h1>"));
+
+    // without `#+TODO:`, keywords come from the parse config
+    let config = ParseConfig {
+        todo_keywords: vec!["NEXT".into()],
+        done_keywords: vec!["DONE".into()],
+        ..Default::default()
+    };
+    let org = Org::parse_with_config("* NEXT One\n* DONE Two\n", &config);
+    let analysis = org.analyze_for_html(&HtmlExportOptions {
+        parse_config: config,
+        ..Default::default()
+    });
+    assert_eq!(analysis.todo_counts(), [("NEXT", 1), ("DONE", 1)]);
+}
+
+/// Classes written by the default handler, renaming one breaks the
+/// stylesheets of users
+const HTML_CLASSES: [&str; 19] = [
+    "admonition",
+    "admonition-title",
+    "center",
+    "example",
+    "footdef",
+    "footnotes",
+    "footnum",
+    "footpara",
+    "note",
+    "org-src-container",
+    "sidebar",
+    "src",
+    "src-python",
+    "src-rust",
+    "subtitle",
+    "timestamp",
+    "timestamp-wrapper",
+    "title",
+    "verse",
+];
+
+#[cfg(feature = "export-html")]
+#[test]
+fn html_classes() {
+    use orgize::export::HtmlExportOptions;
+    use std::collections::BTreeSet;
+
+    let org = Org::parse(
+        r#"#+TITLE: Kitchen sink
+#+DATE: 2020-01-02
+* TODO Headline :tag:
+SCHEDULED: <2020-01-02 Thu>
+Text with *bold*, =verbatim=, src_python{print(1)}, [fn:1] and <2020-01-02 Thu>--<2020-01-03 Fri>.
+
+#+BEGIN_NOTE
+Note
+#+END_NOTE
+
+#+BEGIN_sidebar
+Sidebar
+#+END_sidebar
+
+#+BEGIN_CENTER
+Center
+#+END_CENTER
+
+#+BEGIN_VERSE
+Verse
+#+END_VERSE
+
+#+BEGIN_QUOTE
+Quote
+#+END_QUOTE
+
+#+BEGIN_EXAMPLE
+example
+#+END_EXAMPLE
+
+: fixed
+
+#+BEGIN_SRC rust
+fn main() {}
+#+END_SRC
+
+#+BEGIN_SRC
+plain
+#+END_SRC
+
+| a | b |
+|---+---|
+| 1 | 2 |
+
+- [X] item
+- term :: description
+
+[fn:1] Footnote
+"#,
+    );
+
+    let mut writer = Vec::new();
+    org.html_page(
+        &mut writer,
+        &HtmlExportOptions {
+            date_subtitle: true,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    let html = String::from_utf8(writer).unwrap();
+
+    let classes: BTreeSet<_> = html
+        .split("class=\"")
+        .skip(1)
+        .flat_map(|rest| rest[0..rest.find('"').unwrap()].split_whitespace())
+        .collect();
+    assert_eq!(classes.into_iter().collect::<Vec<_>>(), HTML_CLASSES);
+
+    #[cfg(feature = "css")]
+    for class in HTML_CLASSES.iter() {
+        assert!(
+            ["note", "sidebar", "src-python", "src-rust"].contains(class)
+                || orgize::export::DEFAULT_CSS.contains(&format!(".{} ", class))
+                || orgize::export::DEFAULT_CSS.contains(&format!(".{},", class)),
+            "{}",
+            class
+        );
+    }
+}
+
+#[cfg(feature = "ser")]
+#[test]
+fn serialize_events() {
+    use orgize::{nest_events, EventTree, NestError, StreamEventKind};
+    use serde_json::{Map, Value};
+
+    let org = Org::parse(
+        "#+TITLE: Events\n* TODO Headline :tag:\nText *with /nested/ markup* and [[https://example.com][a link]].\n\n- item\n  | a | b |\n** Empty\n",
+    );
+
+    // newline-delimited json, one event per line
+    let mut ndjson = Vec::new();
+    for event in org.stream_events() {
+        serde_json::to_writer(&mut ndjson, &event).unwrap();
+        ndjson.push(b'\n');
+    }
+    let events: Vec<Value> = String::from_utf8(ndjson)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+
+    let mut array = Vec::new();
+    org.serialize_events(&mut serde_json::Serializer::new(&mut array))
+        .unwrap();
+    assert_eq!(
+        serde_json::from_slice::<Value>(&array).unwrap(),
+        Value::Array(events.clone())
+    );
+
+    // the nested events are the tree serialization of the document
+    fn to_tree(tree: EventTree<Value>) -> Value {
+        let mut object: Map<String, Value> = match tree.event {
+            Value::Object(object) => object,
+            _ => unreachable!(),
+        };
+        object.remove("event");
+        object.remove("span");
+        if !tree.children.is_empty() {
+            object.insert(
+                "children".into(),
+                tree.children.into_iter().map(to_tree).collect(),
+            );
+        }
+        Value::Object(object)
+    }
+    let kind = |event: &Value| event["event"].as_str().and_then(StreamEventKind::from_name);
+    let mut trees = nest_events(events.clone(), kind).unwrap();
+    assert_eq!(trees.len(), 1);
+    assert_eq!(
+        to_tree(trees.remove(0)),
+        serde_json::to_value(&org).unwrap()
+    );
+
+    assert_eq!(
+        nest_events(events[1..].to_vec(), kind),
+        Err(NestError::UnmatchedEnd {
+            index: events.len() - 2
+        })
+    );
+    assert_eq!(
+        nest_events(events[0..events.len() - 1].to_vec(), kind),
+        Err(NestError::Unclosed { index: 0 })
+    );
+}
+
+#[test]
+fn line_endings() {
+    let cases = [
+        "* Headline :tag1:tag2:",
+        "#+TITLE: value",
+        "#+BEGIN_SRC rust\n,* foo\n#+END_SRC",
+        "#+BEGIN_QUOTE\nfoo\nbar\n#+END_QUOTE",
+        "* TODO title :t:\nSCHEDULED: <2020-01-01 Wed>\n:PROPERTIES:\n:ID: x\n:END:\nbody",
+        "- a\n- b\n  more\n\n| a | b |\n|---+---|",
+        ": fixed\n: more",
+        "# comment\n# more",
+        "text *bold\nacross* =verb\natim= [[link][desc\nmore]]\n\npara",
+        "[fn:1] note\nmore",
+        "#+BEGIN_QUOTE\nunclosed",
+    ];
+
+    for case in cases.iter() {
+        let org = Org::parse(case);
+        let crlf = case.replace('\n', "\r\n");
+        for text in [format!("{}\n", case), crlf.clone(), format!("{}\r\n", crlf)].iter() {
+            assert!(org.structural_eq(&Org::parse(text)), "{:?}", text);
+        }
+    }
+
+    assert!(!Org::parse("* a :b:").structural_eq(&Org::parse("* a :c:")));
+    assert!(!Org::parse("* a\n** b").structural_eq(&Org::parse("* a\n* b")));
+}
+
+#[test]
+fn debug_tree() {
+    use orgize::elements::{Priority, Title};
+    use orgize::DebugTree;
+
+    let mut org = Org::parse(
+        "#+TITLE: Tree\n\n- [[https://example.com][=link=]]\n- <2019-10-10 Thu 10:00>\n\n\
+         | a | b |\n|---+---|\n| 1 | 2 |\n\n#+BEGIN_SRC rust\nfn main() {}\n#+END_SRC\n",
+    );
+    assert_eq!(
+        org.debug_tree(),
+        r#"document (1 child) 0..146
+  section (4 children) 0..146
+    keyword TITLE "Tree" 0..14
+    list (2 children) 15..75
+      list-item "- " (1 child) 15..49
+        paragraph (1 child) 17..48
+          link "https://example.com" (1 child) 17..48
+            verbatim "link" 40..46
+      list-item "- " (1 child) 49..74
+        paragraph (1 child) 51..73
+          timestamp active 2019-10-10 10:00 51..73
+    table org with-header (3 children) 75..105
+      table-row header (2 children) 75..84
+        table-cell header (1 child) 76..79
+          text "a" 77..78
+        table-cell header (1 child) 80..83
+          text "b" 81..82
+      table-row header-rule (0 children) 85..94
+      table-row (2 children) 95..104
+        table-cell (1 child) 96..99
+          text "1" 97..98
+        table-cell (1 child) 100..103
+          text "2" 101..102
+    source-block "rust" "fn main() {}\n" 106..146
+"#
+    );
+
+    // headlines created after parsing have no span
+    let headline = org.new_headline(Title {
+        level: 1,
+        keyword: Some("DONE".into()),
+        priority: Some(Priority::Char('B')),
+        tags: vec!["a".into(), "b".into()],
+        raw: "new".into(),
+        ..Default::default()
+    });
+    org.document().append(headline, &mut org).unwrap();
+    let node = headline.headline_node();
+    assert_eq!(
+        DebugTree::of(&org, node).to_string(),
+        "headline l1 \"new\" [DONE] [#B] :a:b: (1 child)\n  title (1 child)\n    text \"new\"\n"
+    );
+}
+
+#[cfg(feature = "export-html")]
+#[test]
+fn unknown_keywords() {
+    use orgize::export::{DefaultOrgHandler, EventBackend, ExportOptions, OrgBackend};
+    use orgize::{Element, Event};
+
+    let text = "#+TITLE: Known\n#+BIND: x 1\n#+HUGO_BASE_DIR: .\n* Headline\ntext\n\n\
+                #+HUGO_DRAFT:\n#+HTML: <hr>\n";
+    let org = Org::parse(text);
+
+    let keywords: Vec<_> = org
+        .unknown_keywords()
+        .iter()
+        .map(|keyword| (keyword.key, keyword.value, keyword.span.clone().unwrap()))
+        .collect();
+    assert_eq!(
+        keywords,
+        [
+            ("BIND", "x 1", 15..27),
+            ("HUGO_BASE_DIR", ".", 27..46),
+            ("HUGO_DRAFT", "", 63..77),
+        ]
+    );
+
+    let mut writer = Vec::new();
+    org.org(&mut writer).unwrap();
+    assert_eq!(String::from_utf8(writer).unwrap(), text);
+
+    let mut writer = Vec::new();
+    org.export_with_handler(
+        &mut writer,
+        &mut OrgBackend(DefaultOrgHandler),
+        &ExportOptions::default(),
+    )
+    .unwrap();
+    assert_eq!(String::from_utf8(writer).unwrap(), text);
+
+    let mut writer = Vec::new();
+    org.html(&mut writer).unwrap();
+    assert_eq!(
+        String::from_utf8(writer).unwrap(),
+        "<main><section></section><h1>Headline</h1><section><p>text</p><hr></section></main>"
+    );
+
+    let mut keys = Vec::new();
+    let mut backend = EventBackend(|event: Event| {
+        if let Event::Start(Element::Keyword(keyword)) = event {
+            keys.push(keyword.key.to_string());
+        }
+        Ok::<(), std::io::Error>(())
+    });
+    org.export_with_handler(std::io::sink(), &mut backend, &ExportOptions::default())
+        .unwrap();
+    assert_eq!(keys, ["TITLE", "HTML"]);
+}
+
+#[cfg(feature = "export-html")]
+#[test]
+fn citations() {
+    use orgize::{ParseConfig, SettingsResolver, SyntaxFeature};
+
+    let text = "#+BIBLIOGRAPHY: refs.bib\n#+BIBLIOGRAPHY: more.json\n#+CITE_EXPORT: csl ieee.csl\n\
+                As shown [cite/t:See;see @doe2020, p. 3; @smith; for details], \
+                [cite:@doe2020] and [cite:no key].\n";
+    let org = Org::parse(text);
+
+    assert_eq!(org.cited_keys(), ["doe2020", "smith"]);
+    assert!(org.unknown_keywords().is_empty());
+    assert!(org.parse_report().uses(SyntaxFeature::Citations));
+
+    let config = ParseConfig::default();
+    let settings = SettingsResolver::new(&org, &config);
+    assert_eq!(settings.bibliography(), ["refs.bib", "more.json"]);
+    assert_eq!(settings.cite_export(), Some("csl ieee.csl"));
+
+    let mut writer = Vec::new();
+    org.org(&mut writer).unwrap();
+    assert_eq!(String::from_utf8(writer).unwrap(), format!("{}\n", text));
+
+    let mut writer = Vec::new();
+    org.html(&mut writer).unwrap();
+    assert_eq!(
+        String::from_utf8(writer).unwrap(),
+        "<main><section><p>As shown <cite>(See; see doe2020, p. 3; smith; for details)</cite>, \
+         <cite>(doe2020)</cite> and [cite:no key].</p></section></main>"
+    );
+}
+
+#[cfg(feature = "export-html")]
+#[test]
+fn print_bibliography() {
+    use orgize::export::{BibliographyRenderer, DefaultHtmlHandler, HtmlExportOptions};
+    use std::sync::Arc;
+
+    struct KeyList;
+
+    impl BibliographyRenderer for KeyList {
+        fn render(&self, keys: &[&str], options: &str) -> String {
+            let items: String = keys.iter().map(|key| format!("<li>{}</li>", key)).collect();
+            format!("<ul class=\"{}\">{}</ul>", options, items)
+        }
+    }
+
+    let org = Org::parse(
+        "[cite:@doe2020; @smith]\n\n#+PRINT_BIBLIOGRAPHY: refs\n\n\
+         [cite:@adams; @doe2020]\n* Skipped :noexport:\n[cite:@hidden]\n",
+    );
+
+    let analysis = org.analyze_for_html(&HtmlExportOptions {
+        bibliography_renderer: Some(Arc::new(KeyList)),
+        ..Default::default()
+    });
+    assert!(analysis.diagnostics().is_empty());
+
+    let mut writer = Vec::new();
+    org.html_with_analysis(&mut writer, &mut DefaultHtmlHandler::default(), &analysis)
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(writer).unwrap(),
+        "<main><section><p><cite>(doe2020; smith)</cite></p>\
+         <ul class=\"refs\"><li>doe2020</li><li>smith</li><li>adams</li></ul>\
+         <p><cite>(adams; doe2020)</cite></p></section></main>"
+    );
+
+    let analysis = org.analyze_for_html(&HtmlExportOptions::default());
+    assert_eq!(analysis.diagnostics().len(), 1);
+    assert_eq!(analysis.diagnostics()[0].span, Some(25..52));
+
+    let mut writer = Vec::new();
+    org.html_with_analysis(&mut writer, &mut DefaultHtmlHandler::default(), &analysis)
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(writer).unwrap(),
+        "<main><section><p><cite>(doe2020; smith)</cite></p>\
+         <p><cite>(adams; doe2020)</cite></p></section></main>"
+    );
+}
+
+#[cfg(feature = "export-html")]
+#[test]
+fn tables() {
+    use orgize::elements::{Table, TableCell, TableRow};
+    use orgize::{Element, Event};
+
+    let org = Org::parse(
+        "| Name | [[https://x.org][Score]] |\n|------+-------|\n| a | *1* |\n|-\n| b | 2 |\n\
+         #+TBLFM: $2=1\n\n|---|\n| c |\n\n  +---+\n  | d |\n  +---+\n",
+    );
+
+    let elements: Vec<_> = org
+        .iter()
+        .filter_map(|event| match event {
+            Event::Start(Element::Table(Table::Org { has_header, tblfm })) => {
+                Some(format!("org {} {:?}", has_header, tblfm))
+            }
+            Event::Start(Element::Table(Table::TableEl { value })) => {
+                Some(format!("table.el {:?}", value))
+            }
+            Event::Start(Element::TableRow(TableRow::Header)) => Some("header".into()),
+            Event::Start(Element::TableRow(TableRow::Body)) => Some("body".into()),
+            Event::Start(Element::TableRow(TableRow::HeaderRule)) => Some("header-rule".into()),
+            Event::Start(Element::TableRow(TableRow::BodyRule)) => Some("body-rule".into()),
+            Event::Start(Element::TableCell(TableCell::Header)) => Some("th".into()),
+            Event::Start(Element::TableCell(TableCell::Body)) => Some("td".into()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        elements,
+        [
+            "org true Some(\"$2=1\")",
+            "header",
+            "th",
+            "th",
+            "header-rule",
+            "body",
+            "td",
+            "td",
+            "body-rule",
+            "body",
+            "td",
+            "td",
+            "org false None",
+            "body-rule",
+            "body",
+            "td",
+            "table.el \"+---+\\n| d |\\n+---+\\n\"",
+        ]
+    );
+
+    let mut writer = Vec::new();
+    org.html(&mut writer).unwrap();
+    assert_eq!(
+        String::from_utf8(writer).unwrap(),
+        "<main><section><table><thead><tr><th>Name</th>\
+         <th><a href=\"https://x.org\">Score</a></th></tr></thead>\
+         <tbody><tr><td>a</td><td><b>1</b></td></tr><tr><td>b</td><td>2</td></tr></tbody></table>\
+         <table><tbody><tr><td>c</td></tr></tbody></table>\
+         <pre class=\"table-el\">+---+\n| d |\n+---+\n</pre></section></main>"
+    );
+
+    let mut writer = Vec::new();
+    org.org(&mut writer).unwrap();
+    assert_eq!(
+        String::from_utf8(writer).unwrap(),
+        "| Name | [[https://x.org][Score]] |\n|-\n| a | *1* |\n|-\n| b | 2 |\n#+TBLFM: $2=1\n\n\
+         |-\n| c |\n\n+---+\n| d |\n+---+\n\n"
+    );
+}
+
+#[test]
+fn strict_parsing() {
+    use orgize::{ParseConfig, Position, Severity};
+
+    let text = "* a\nSCHEDULED: <2021-02-29 Mon>\n\n[fn:1] one\n\n[fn:1] two\n\n\
+                text\n:END:\n#+END_SRC\nCLOCK: soon\nDEADLINE: <2021-01-01 Fri>\n";
+
+    let org = Org::parse(text);
+    assert_eq!(org.headlines().count(), 1);
+
+    let diagnostics: Vec<_> = Org::try_parse(text)
+        .err()
+        .unwrap()
+        .into_iter()
+        .map(|diagnostic| {
+            (
+                diagnostic.severity,
+                diagnostic.message,
+                diagnostic.span.unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        diagnostics,
+        [
+            (
+                Severity::Error,
+                "planning timestamp with an invalid date".to_string(),
+                0..32
+            ),
+            (
+                Severity::Error,
+                "footnote label defined more than once: 1".to_string(),
+                45..56
+            ),
+            (Severity::Warning, "orphaned :END:".to_string(), 62..68),
+            (
+                Severity::Warning,
+                "block end line without block: #+END_SRC".to_string(),
+                68..78
+            ),
+        ]
+    );
+
+    let positions: Vec<_> = org
+        .parse_diagnostics()
+        .into_iter()
+        .map(|diagnostic| diagnostic.position.unwrap())
+        .collect();
+    assert_eq!(
+        positions,
+        [
+            Position { line: 0, column: 0 },
+            Position { line: 5, column: 0 },
+            Position { line: 8, column: 0 },
+            Position { line: 9, column: 0 },
+        ]
+    );
+
+    assert!(Org::try_parse("* a\n<2021-02-30 Tue>\n").is_ok());
+    assert!(Org::try_parse("text\n:END:\n").is_ok());
+    let strict = ParseConfig {
+        strict_severity: Severity::Warning,
+        ..Default::default()
+    };
+    let errors = Org::try_parse_with_config("* a\n<2021-02-30 Tue>\n", &strict)
+        .err()
+        .unwrap();
+    assert_eq!(errors.len(), 1);
+    assert!(Org::try_parse_with_config("* a\n<2021-03-01 Mon>\n", &strict).is_ok());
+
+    // blocks and drawers which are never closed are parsed as text
+    for text in &[
+        "#+BEGIN_SRC rust\nfoo\n",
+        "#+BEGIN_QUOTE\nfoo\n",
+        "#+BEGIN: clocktable\nfoo\n",
+        ":LOGBOOK:\nfoo\n",
+        "* a\n:PROPERTIES:\n:ID: x\n",
+    ] {
+        assert!(Org::try_parse(text).is_ok());
+        assert_eq!(
+            Org::try_parse_with_config(text, &strict)
+                .err()
+                .unwrap()
+                .len(),
+            1,
+            "{:?}",
+            text
+        );
+    }
+    let errors = Org::try_parse_with_config("text\n  #+begin_src rust\nfoo\n", &strict)
+        .err()
+        .unwrap();
+    assert_eq!(errors[0].message, "unclosed block: #+begin_src rust");
+    assert_eq!(errors[0].span, Some(5..24));
+    let errors = Org::try_parse_with_config(":LOGBOOK:\nfoo\n", &strict)
+        .err()
+        .unwrap();
+    assert_eq!(errors[0].message, "unclosed drawer: :LOGBOOK:");
+    assert!(Org::try_parse_with_config("#+BEGIN_SRC rust\nfoo\n#+END_SRC\n", &strict).is_ok());
+}
+
+#[test]
+fn round_trip_fixture() {
+    use orgize::{Element, Event};
+
+    fn events<'a, 'b>(org: &'b Org<'a>) -> Vec<(bool, &'b Element<'a>)> {
+        org.iter()
+            .map(|event| match event {
+                Event::Start(element) => (true, element),
+                Event::End(element) => (false, element),
+            })
+            .collect()
+    }
+
+    let org = Org::parse(include_str!("fixtures/agenda.org"));
+    assert_eq!(org.parse_diagnostics(), []);
+
+    let mut writer = Vec::new();
+    org.org(&mut writer).unwrap();
+    let written = String::from_utf8(writer).unwrap();
+    let parsed = Org::parse(&written);
+
+    assert_eq!(events(&parsed), events(&org));
+
+    let mut writer = Vec::new();
+    parsed.org(&mut writer).unwrap();
+    assert_eq!(String::from_utf8(writer).unwrap(), written);
+}
+
+#[cfg(feature = "export-markdown")]
+#[test]
+fn markdown() {
+    let org = Org::parse(
+        r#"Intro with *bold*, /italic/, +strike+, =code `x`= and [[file:a b.org][a file]].
+See [[https://orgmode.org]][fn:1] and [fn::an inline note].
+
+* Lists
+1. one
+2) two
+   - nested
+
+     second paragraph
+3. three
+
+between
+
+- loose
+
+- list
+** Blocks
+#+BEGIN_SRC rust
+fn main() {}
+#+END_SRC
+
+#+BEGIN_QUOTE
+quoted
+
+more
+#+END_QUOTE
+
+#+BEGIN_WARNING
+Careful.
+#+END_WARNING
+
+| a | b |
+|---+---|
+| c |
+
+[fn:1] The manual.
+"#,
+    );
+
+    let mut writer = Vec::new();
+    org.markdown(&mut writer).unwrap();
+    assert_eq!(
+        String::from_utf8(writer).unwrap(),
+        r#"Intro with **bold**, *italic*, ~~strike~~, `` code `x` `` and [a file](<file:a b.org>).
+See <https://orgmode.org>[^1] and  (an inline note).
+
+# Lists
+
+1. one
+2. two
+   - nested
+
+     second paragraph
+3. three
+
+between
+
+- loose
+
+- list
+
+## Blocks
+
+```rust
+fn main() {}
+```
+
+> quoted
+>
+> more
+
+> [!WARNING]
+> Careful.
+
+| a | b |
+| --- | --- |
+| c |
+
+[^1]: The manual.
+"#
+    );
+
+    // excluded subtrees are left out like in the other exports
+    let org = Org::parse(
+        "#+EXCLUDE_TAGS: private\n* Kept\n** Draft :noexport:\ndraft\n\
+         * Notes :private:\n* COMMENT Todo\n* Old :ARCHIVE:\nold\n",
+    );
+    let mut writer = Vec::new();
+    org.markdown(&mut writer).unwrap();
+    assert_eq!(
+        String::from_utf8(writer).unwrap(),
+        "# Kept\n\n# Old\n\nold\n"
+    );
+}
+
+#[cfg(feature = "export-markdown")]
+#[test]
+fn markdown_tasks() {
+    let org = Org::parse(include_str!("fixtures/tasks.org"));
+
+    let mut writer = Vec::new();
+    org.markdown(&mut writer).unwrap();
+    assert_eq!(
+        String::from_utf8(writer).unwrap(),
+        include_str!("fixtures/tasks.md")
+    );
+
+    // a list starting with another number than 1 can't interrupt a paragraph
+    let org = Org::parse("1. one\n   1. [@5] [-] five\n   2. six\n2. [@7] seven\n");
+    let mut writer = Vec::new();
+    org.markdown(&mut writer).unwrap();
+    assert_eq!(
+        String::from_utf8(writer).unwrap(),
+        "1. one\n\n   5. [ ] five\n   6. six\n7) seven\n"
+    );
+}
+
+#[test]
+fn timestamp_repeaters() {
+    use orgize::elements::{DelayType, RepeaterType, TimeUnit, Timestamp};
+    use orgize::{Element, Event};
+
+    let text = "* TODO Review\nSCHEDULED: <2023-01-01 Sun .+1d>\n<2023-01-01 Sun 10:00 +1w -2d> [2023-01-01 Sun ++2m]--[2023-01-03 Tue ++2m]\n\n** Next\n";
+    let org = Org::parse(text);
+    let timestamps: Vec<&Timestamp> = org
+        .iter()
+        .filter_map(|event| match event {
+            Event::Start(Element::Timestamp(timestamp)) => Some(timestamp),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(timestamps.len(), 2);
+
+    let repeater = timestamps[0].repeater().unwrap();
+    assert_eq!(
+        (repeater.ty, repeater.value, repeater.unit),
+        (RepeaterType::Cumulate, 1, TimeUnit::Week)
+    );
+    let delay = timestamps[0].delay().unwrap();
+    assert_eq!(
+        (delay.ty, delay.value, delay.unit),
+        (DelayType::All, 2, TimeUnit::Day)
+    );
+    #[cfg(feature = "ser")]
+    assert_eq!(
+        serde_json::to_value(timestamps[1]).unwrap()["repeater"],
+        serde_json::json!({ "ty": "catch-up", "value": 2, "unit": "month" })
+    );
+
+    let headline = org.headlines().next().unwrap();
+    let scheduled = headline.title(&org).scheduled().unwrap();
+    assert_eq!(scheduled.repeater().unwrap().to_string(), ".+1d");
+
+    let mut writer = Vec::new();
+    org.org(&mut writer).unwrap();
+    assert_eq!(String::from_utf8(writer).unwrap(), text);
+
+    // each end keeps its own modifiers, in the order they're written
+    for text in &[
+        "<2023-01-01 Sun +1w>--<2023-01-02 Mon +2w>\n",
+        "<2023-01-01 Sun>--<2023-01-02 Mon -1d>\n",
+        "<2023-01-01 Sun -2d +1w>\n",
+        "[2023-01-01 Sun +1w -2d]--[2023-01-02 Mon --1d .+2w]\n",
+    ] {
+        let mut writer = Vec::new();
+        Org::parse(text).org(&mut writer).unwrap();
+        assert_eq!(String::from_utf8(writer).unwrap(), format!("{}\n", text));
+    }
+}
+
+#[test]
+fn property_drawers() {
+    let text = "* a\n:PROPERTIES:\n:ALIAS: a\n:KEY value\n:alias: b\n  wrapped value\n:ALIAS+: c\n:ID:\n:END:\n";
+    let org = Org::parse(text);
+    let title = org.headlines().next().unwrap().title(&org);
+
+    assert_eq!(title.property("alias"), Some("b c"));
+    assert_eq!(title.properties_all("ALIAS"), vec!["a", "b", "c"]);
+    assert_eq!(title.property("KEY"), None);
+    assert_eq!(title.properties.len(), 2);
+
+    let diagnostics = org.parse_diagnostics();
+    assert_eq!(diagnostics.len(), 2);
+    assert_eq!(diagnostics[0].severity, orgize::Severity::Warning);
+    assert_eq!(
+        diagnostics[0].message,
+        "malformed property line: :KEY value"
+    );
+    assert_eq!(
+        diagnostics[1].message,
+        "malformed property line: wrapped value"
+    );
+
+    let mut writer = Vec::new();
+    org.org(&mut writer).unwrap();
+    assert_eq!(String::from_utf8(writer).unwrap(), text);
+
+    // changed properties are written sorted
+    let mut org = Org::parse(text);
+    let headline = org.headlines().next().unwrap();
+    headline.set_property("ID", "1", &mut org);
+    let mut writer = Vec::new();
+    org.org(&mut writer).unwrap();
+    assert_eq!(
+        String::from_utf8(writer).unwrap(),
+        "* a\n:PROPERTIES:\n:ID: 1\n:alias: b c\n:END:\n"
+    );
+}
+
+#[test]
+fn huge_property_drawer() {
+    use std::time::{Duration, Instant};
+
+    let drawer = |entries: usize| {
+        let mut text = String::from("* roam\n:PROPERTIES:\n");
+        for i in 0..entries {
+            match i % 4 {
+                0 => text += &format!(":KEY_{}: {}\n", i, i),
+                1 => text += &format!(":key_{}: duplicate\n", i - 1),
+                2 => text += &format!(":KEY_{}+: more\n", i - 2),
+                _ => text += &format!(":KEY_{} malformed\n", i),
+            }
+        }
+        text + ":END:\n"
+    };
+    let parse = |text: &str| {
+        (0..3)
+            .map(|_| {
+                let start = Instant::now();
+                Org::parse(text);
+                start.elapsed()
+            })
+            .min()
+            .unwrap()
+            .max(Duration::from_micros(1))
+    };
+
+    let text = drawer(10_000);
+    let org = Org::parse(&text);
+    let title = org.headlines().next().unwrap().title(&org);
+    assert_eq!(title.properties.len(), 2_500);
+    assert_eq!(title.property_lines.len(), 10_000);
+    assert_eq!(title.property("key_4"), Some("duplicate more"));
+    assert_eq!(
+        title.properties_all("KEY_4"),
+        vec!["4", "duplicate", "more"]
+    );
+    assert_eq!(org.parse_diagnostics().len(), 2_500);
+
+    let mut writer = Vec::new();
+    org.org(&mut writer).unwrap();
+    assert_eq!(String::from_utf8(writer).unwrap(), text);
+
+    // four times the entries take about four times as long, not sixteen
+    let small = parse(&drawer(2_500));
+    let large = parse(&text);
+    assert!(
+        large < small * 10,
+        "parsing 10k entries took {:?}, 2.5k took {:?}",
+        large,
+        small
+    );
+}
@@ -0,0 +1,150 @@
+use orgize::{Levels, Org, ParseConfig, SettingsResolver};
+use pretty_assertions::assert_eq;
+
+#[test]
+fn precedence() {
+    let org = Org::parse(
+        r#"#+SETUPFILE: "setup.org"
+#+OPTIONS: toc:nil
+#+TAGS: { @work(w) @home(h) } laptop(l)
+#+PROPERTY: header-args :results silent
+* headline
+:PROPERTIES:
+:EXPORT_OPTIONS: num:nil
+:END:
+** child
+:PROPERTIES:
+:header-args: :results output
+:END:
+#+STARTUP: indent
+"#,
+    );
+    let config = ParseConfig::default();
+    let mut requested = Vec::new();
+    let settings = SettingsResolver::with_setup_files(&org, &config, |file| {
+        requested.push(file.to_string());
+        match file {
+            "setup.org" => Some(
+                "#+SETUPFILE: nested.org\n#+OPTIONS: toc:2 num:3\n#+STARTUP: overview\n".into(),
+            ),
+            // includes itself again
+            "nested.org" => Some(
+                "#+SETUPFILE: setup.org\n#+SEQ_TODO: TODO(t) WAIT | DONE(d) CANCELED\n\
+                 #+LINK: gh https://github.com/%s\n"
+                    .into(),
+            ),
+            _ => None,
+        }
+    });
+
+    assert_eq!(requested, ["setup.org", "nested.org"]);
+
+    assert_eq!(
+        settings.todo_keywords(),
+        (
+            vec!["TODO".to_string(), "WAIT".to_string()],
+            vec!["DONE".to_string(), "CANCELED".to_string()]
+        )
+    );
+    assert_eq!(settings.parse_config().todo_keywords, ["TODO", "WAIT"]);
+    assert_eq!(settings.tags(), ["@work", "@home", "laptop"]);
+    assert_eq!(settings.startup(), ["overview", "indent"]);
+    assert_eq!(
+        settings.link_abbreviation("gh"),
+        Some("https://github.com/%s")
+    );
+    assert_eq!(settings.link_abbreviation("gl"), None);
+
+    let headlines: Vec<_> = org.headlines().collect();
+
+    // in-buffer keywords override setup files
+    assert_eq!(settings.export_option("toc", None), Some("nil"));
+    assert_eq!(settings.export_option("num", None), Some("3"));
+    // subtree properties override in-buffer keywords
+    assert_eq!(
+        settings.export_option("num", Some(headlines[1])),
+        Some("nil")
+    );
+    assert_eq!(
+        settings.export_option("toc", Some(headlines[1])),
+        Some("nil")
+    );
+    assert_eq!(settings.export_settings(None).num, Some(Levels::UpTo(3)));
+    let subtree = settings.export_settings(Some(headlines[1]));
+    assert_eq!(
+        (subtree.num, subtree.toc),
+        (Some(Levels::None), Some(Levels::None))
+    );
+
+    assert_eq!(
+        settings.property("header-args", headlines[0]),
+        Some(":results silent")
+    );
+    assert_eq!(
+        settings.property("header-args", headlines[1]),
+        Some(":results output")
+    );
+}
+
+#[test]
+fn defaults() {
+    let org = Org::parse("#+SETUPFILE: missing.org\n* TODO headline");
+    let config = ParseConfig::default();
+    let settings = SettingsResolver::new(&org, &config);
+
+    assert_eq!(
+        settings.todo_keywords(),
+        (config.todo_keywords.clone(), config.done_keywords.clone())
+    );
+    assert_eq!(settings.keyword("SETUPFILE"), None);
+    assert!(settings.startup().is_empty());
+    assert_eq!(settings.export_option("toc", None), None);
+
+    // without `|`, the last keyword is the done keyword
+    let org = Org::parse("#+TODO: TODO FEEDBACK VERIFY DONE\n#+todo: BUG | FIXED");
+    let settings = SettingsResolver::new(&org, &config);
+    assert_eq!(
+        settings.todo_keywords(),
+        (
+            vec![
+                "TODO".to_string(),
+                "FEEDBACK".to_string(),
+                "VERIFY".to_string(),
+                "BUG".to_string()
+            ],
+            vec!["DONE".to_string(), "FIXED".to_string()]
+        )
+    );
+}
+
+#[test]
+fn startup_options() {
+    use orgize::StartupOptions;
+
+    let org =
+        Org::parse("#+STARTUP: odd showstars\n#+STARTUP: hidestars\n* one\n*** two\n***** three\n");
+    let config = ParseConfig::default();
+    let startup = SettingsResolver::new(&org, &config).startup_options();
+
+    assert_eq!(
+        startup,
+        StartupOptions {
+            odd: true,
+            hide_stars: true
+        }
+    );
+
+    let levels: Vec<_> = org
+        .headlines()
+        .map(|headline| headline.title(&org).display_level(&startup))
+        .collect();
+    assert_eq!(levels, [1, 2, 3]);
+
+    assert_eq!(startup.change_level(3, 1), 5);
+    assert_eq!(startup.change_level(3, -1), 1);
+    assert_eq!(StartupOptions::default().change_level(3, -1), 2);
+    assert_eq!(
+        StartupOptions::from_flags(&["odd", "oddeven"]).display_level(3),
+        3
+    );
+}
@@ -0,0 +1,131 @@
+#![cfg(feature = "workspace")]
+
+use orgize::{LinkIssueKind, Org, Workspace};
+use pretty_assertions::assert_eq;
+use std::path::Path;
+
+#[test]
+fn check_links() {
+    let index = r#"* Index
+:PROPERTIES:
+:ID: 6f1c
+:CUSTOM_ID: index
+:END:
+[[#index]] [[#indx]] [[*Index]] [[Introduction]] [[table]] [[target]]
+[[file:notes/a.org]] [[./notes/b.org::#custom]] [[file:notes/missing.org]]
+[[file:image.png]] [[id:6f1c]] [[id:6f1d]] [[https://example.com]] [[(ref)]]
+#+NAME: table
+| a |
+<<target>>
+"#;
+    let a = r#"* A
+:PROPERTIES:
+:ID: 6f1c
+:END:
+[[../index.org::*Index]] [[file:../index.org::target]] [[file:../index.org::nope]]
+"#;
+    let b = r#"* B
+:PROPERTIES:
+:CUSTOM_ID: custom
+:END:
+<<x>> <<x>>
+"#;
+
+    let mut workspace = Workspace::new();
+    workspace.add("index.org", Org::parse(index));
+    workspace.add("notes/a.org", Org::parse(a));
+    workspace.add("./notes/../notes/b.org", Org::parse(b));
+
+    assert!(workspace.document(Path::new("notes/b.org")).is_some());
+    let (path, headline) = workspace.find_id("6f1c").unwrap();
+    assert_eq!(path, Path::new("index.org"));
+    assert_eq!(
+        headline.title(workspace.document(path).unwrap()).raw,
+        "Index"
+    );
+
+    let issues = workspace.check_links(|path| path == Path::new("image.png"));
+    let summary: Vec<_> = issues
+        .iter()
+        .map(|issue| {
+            (
+                issue.document.to_str().unwrap(),
+                issue.text.as_str(),
+                issue.kind,
+                issue.suggestion.as_deref(),
+            )
+        })
+        .collect();
+
+    assert_eq!(
+        summary,
+        [
+            ("notes/b.org", "<<x>>", LinkIssueKind::DuplicateTarget, None),
+            ("notes/a.org", "6f1c", LinkIssueKind::DuplicateTarget, None),
+            (
+                "index.org",
+                "[[#indx]]",
+                LinkIssueKind::MissingTarget,
+                Some("#index")
+            ),
+            (
+                "index.org",
+                "[[Introduction]]",
+                LinkIssueKind::MissingTarget,
+                None
+            ),
+            (
+                "index.org",
+                "[[file:notes/missing.org]]",
+                LinkIssueKind::MissingFile,
+                None
+            ),
+            (
+                "index.org",
+                "[[id:6f1d]]",
+                LinkIssueKind::UnknownId,
+                Some("6f1c")
+            ),
+            (
+                "notes/a.org",
+                "[[file:../index.org::nope]]",
+                LinkIssueKind::MissingTarget,
+                None
+            ),
+        ]
+    );
+
+    let span = issues[2].span.clone().unwrap();
+    assert_eq!(&index[span], "[[#indx]]");
+}
+
+#[test]
+fn parse_files() {
+    use orgize::ParseConfig;
+
+    let files = vec![
+        (
+            "notes/a.org",
+            "* A\n:PROPERTIES:\n:ID: 6f1c\n:END:\n* Shared\n",
+        ),
+        ("./notes/../b.org", "* TODO Shared :tag:\n:END:\n"),
+    ];
+
+    let (workspace, diagnostics) = Workspace::parse_files(files, &ParseConfig::default());
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(
+        diagnostics[0].path.as_deref(),
+        Some(Path::new("./notes/../b.org"))
+    );
+
+    let (path, headline) = workspace.find_id("6f1c").unwrap();
+    assert_eq!(path, Path::new("notes/a.org"));
+    let org = workspace.document(path).unwrap();
+    assert_eq!(headline.title(org).raw, "A");
+
+    let paths: Vec<_> = workspace
+        .find_title("Shared")
+        .map(|(path, _)| path)
+        .collect();
+    assert_eq!(paths, [Path::new("notes/a.org"), Path::new("b.org")]);
+}
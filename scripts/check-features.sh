@@ -0,0 +1,41 @@
+#!/usr/bin/env bash
+# Checks that orgize compiles with every combination of a curated subset of
+# its features, like `--no-default-features --features export-html`, and
+# that its tests pass with each feature on its own
+set -euo pipefail
+
+cd "$(dirname "$0")/../orgize"
+
+# their power set is checked
+features=(ser export-html export-markdown tools chrono)
+# these are checked on their own
+others=(css md query workspace encoding rayon)
+
+check() {
+    echo "checking features: ${1:-(none)}"
+    cargo check --quiet --lib --no-default-features --features "$1"
+}
+
+test_features() {
+    echo "testing features: ${1:-(none)}"
+    cargo test --quiet --no-default-features --features "$1"
+}
+
+for ((mask = 0; mask < 1 << ${#features[@]}; mask++)); do
+    selected=()
+    for i in "${!features[@]}"; do
+        if ((mask >> i & 1)); then
+            selected+=("${features[i]}")
+        fi
+    done
+    check "$(IFS=,; echo "${selected[*]:-}")"
+done
+
+for feature in "${others[@]}"; do
+    check "$feature"
+done
+
+test_features ""
+for feature in "${features[@]}" "${others[@]}"; do
+    test_features "$feature"
+done